@@ -0,0 +1,109 @@
+//! Hosts scanned per second against [`MockNet`], so throughput regressions
+//! in the scanner's dispatch show up as numbers.
+//!
+//! `instant` uses `MockNet` as is: every probe answers at once, so it
+//! measures what the worker pool, progress and result flushing cost per
+//! host. `rtt_20ms` holds every echo for 20 ms, like a real subnet of silent
+//! hosts, so it measures how many hosts the scanner keeps in flight.
+//!
+//! `cargo bench --features test-support --bench scan_throughput`
+
+use async_trait::async_trait;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use ragescanner::net::{EchoReply, MockNet, NetworkProvider};
+use ragescanner::scanner::Scanner;
+use ragescanner::types::{GError, ScanConfig, UdpPortState};
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use tokio_util::sync::CancellationToken;
+
+/// A /20: large enough that dispatch dominates, small enough to sample.
+const HOSTS: u32 = 4096;
+
+/// [`MockNet`] with every echo answered (or not) after a fixed delay.
+struct LaggedNet(Duration);
+
+#[async_trait]
+impl NetworkProvider for LaggedNet {
+    fn echo(&self, ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+        MockNet.echo(ip)
+    }
+    async fn echo_async(
+        &self,
+        ip: Ipv4Addr,
+        timeout: Duration,
+    ) -> Result<Option<EchoReply>, GError> {
+        tokio::time::sleep(self.0).await;
+        MockNet.echo_async(ip, timeout).await
+    }
+    fn resolve_mac(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+        MockNet.resolve_mac(ip)
+    }
+    fn resolve_hostname(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+        MockNet.resolve_hostname(ip)
+    }
+    fn resolve_vendor(&self, mac: &str) -> Option<String> {
+        MockNet.resolve_vendor(mac)
+    }
+    async fn scan_port(&self, ip: Ipv4Addr, port: u16) -> bool {
+        MockNet.scan_port(ip, port).await
+    }
+    async fn scan_udp_port(&self, ip: Ipv4Addr, port: u16) -> UdpPortState {
+        MockNet.scan_udp_port(ip, port).await
+    }
+}
+
+/// Scans [`HOSTS`] addresses from 10.0.0.0 and drops every message.
+async fn scan(net: Arc<dyn NetworkProvider>, concurrency: usize) {
+    let (tx, mut rx) = tokio::sync::mpsc::channel(1024);
+    let drain = tokio::spawn(async move { while rx.recv().await.is_some() {} });
+    let config = ScanConfig {
+        concurrency,
+        resolve_hostnames: false,
+        ..ScanConfig::default()
+    };
+    let scanner = Scanner::new(net, tx).with_config(config);
+    let start = Ipv4Addr::new(10, 0, 0, 0);
+    let end = Ipv4Addr::from(u32::from(start) + HOSTS - 1);
+    scanner
+        .scan_range(start, end, CancellationToken::new())
+        .await;
+    drop(scanner);
+    drain.await.expect("drain task failed");
+}
+
+fn scan_throughput(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("tokio runtime");
+
+    let mut instant = c.benchmark_group("instant");
+    instant.throughput(Throughput::Elements(u64::from(HOSTS)));
+    instant.sample_size(10);
+    for concurrency in [100, 1000] {
+        instant.bench_with_input(
+            BenchmarkId::from_parameter(concurrency),
+            &concurrency,
+            |b, &concurrency| b.iter(|| runtime.block_on(scan(Arc::new(MockNet), concurrency))),
+        );
+    }
+    instant.finish();
+
+    let mut lagged = c.benchmark_group("rtt_20ms");
+    lagged.throughput(Throughput::Elements(u64::from(HOSTS)));
+    lagged.sample_size(10);
+    for concurrency in [100, 1000, 4096] {
+        lagged.bench_with_input(
+            BenchmarkId::from_parameter(concurrency),
+            &concurrency,
+            |b, &concurrency| {
+                let net = Arc::new(LaggedNet(Duration::from_millis(20)));
+                b.iter(|| runtime.block_on(scan(net.clone(), concurrency)))
+            },
+        );
+    }
+    lagged.finish();
+}
+
+criterion_group!(benches, scan_throughput);
+criterion_main!(benches);
@@ -0,0 +1,157 @@
+//! Scanning from library code: a custom [`NetworkProvider`], a tuned
+//! [`ScanConfig`] and the [`ScanSession`] stream API, end to end.
+//!
+//! `SimNet` simulates a small office network, so the example runs anywhere
+//! and checks what it finds: `cargo run --example library_usage` fails if the
+//! library surface stops behaving as documented.
+//!
+//! [`ScanSession`]: ragescanner::scan_session::ScanSession
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use ragescanner::net::NetworkProvider;
+use ragescanner::scan_session::ScanOutcome;
+use ragescanner::scanner::Scanner;
+use ragescanner::targets::ScanTargets;
+use ragescanner::types::{GError, ScanConfig, ScanResult, ScanStatus, UdpPortState};
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A simulated host: last octet, MAC, name and open TCP ports.
+struct SimHost {
+    last: u8,
+    mac: &'static str,
+    name: &'static str,
+    ports: &'static [u16],
+}
+
+/// Simulated 10.0.0.0/28: a router, a file server and a workstation.
+const HOSTS: &[SimHost] = &[
+    SimHost {
+        last: 1,
+        mac: "00:0C:42:00:00:01",
+        name: "router.lan",
+        ports: &[22, 80, 443],
+    },
+    SimHost {
+        last: 5,
+        mac: "00:11:32:00:00:05",
+        name: "nas.lan",
+        ports: &[22, 445, 5000],
+    },
+    SimHost {
+        last: 9,
+        mac: "3C:22:FB:00:00:09",
+        name: "desk-09.lan",
+        ports: &[3389],
+    },
+];
+
+/// A [`NetworkProvider`] answering from [`HOSTS`] instead of the wire.
+///
+/// Only the required methods are implemented; optional probes (mDNS, SNMP,
+/// banners, ...) keep their defaults and report nothing.
+struct SimNet;
+
+impl SimNet {
+    fn host(ip: Ipv4Addr) -> Option<&'static SimHost> {
+        let [a, b, c, last] = ip.octets();
+        if [a, b, c] != [10, 0, 0] {
+            return None;
+        }
+        HOSTS.iter().find(|h| h.last == last)
+    }
+}
+
+#[async_trait]
+impl NetworkProvider for SimNet {
+    fn echo(&self, ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+        Ok(Self::host(ip).map(|h| u32::from(h.last)))
+    }
+
+    fn resolve_mac(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+        Ok(Self::host(ip).map(|h| h.mac.to_string()))
+    }
+
+    fn resolve_hostname(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+        Ok(Self::host(ip).map(|h| h.name.to_string()))
+    }
+
+    fn resolve_vendor(&self, mac: &str) -> Option<String> {
+        match &mac[..8] {
+            "00:0C:42" => Some("Routerboard.com".to_string()),
+            "00:11:32" => Some("Synology Incorporated".to_string()),
+            _ => None,
+        }
+    }
+
+    async fn scan_port(&self, ip: Ipv4Addr, port: u16) -> bool {
+        Self::host(ip).is_some_and(|h| h.ports.contains(&port))
+    }
+
+    async fn scan_udp_port(&self, _ip: Ipv4Addr, _port: u16) -> UdpPortState {
+        UdpPortState::Closed
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let config = ScanConfig {
+        concurrency: 8,
+        ping_timeout: Duration::from_millis(100),
+        retries: 0,
+        ports: vec![22, 80, 443, 445, 3389, 5000],
+        ..ScanConfig::default()
+    };
+    let targets = ScanTargets::parse("10.0.0.0/28").unwrap();
+
+    // Results stream in completion order while progress is tracked separately.
+    let mut session = Scanner::start(Arc::new(SimNet), targets.clone(), config.clone());
+    let progress = session.progress();
+    let mut online: Vec<ScanResult> = Vec::new();
+    let mut scanned = 0;
+    while let Some(res) = session.results().next().await {
+        scanned += 1;
+        if res.status == ScanStatus::Online {
+            online.push(res);
+        }
+    }
+    assert_eq!(session.await, Ok(ScanOutcome::Complete));
+    assert_eq!(*progress.borrow(), 100);
+
+    // Every address gets a result; only .0/.255 of a full /24 are skipped.
+    assert_eq!(scanned, 16);
+    online.sort_by_key(|r| r.ip);
+    for (res, host) in online.iter().zip(HOSTS) {
+        let ports: Vec<u16> = res.open_ports.iter().map(|p| p.port).collect();
+        println!(
+            "{:<12} {:<12} {:<22} ports {:?}  os {}",
+            res.ip,
+            res.hostname.as_deref().unwrap_or("-"),
+            res.vendor.as_deref().unwrap_or("-"),
+            ports,
+            res.os_guess
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_else(|| "-".to_string()),
+        );
+        assert_eq!(res.ip, Ipv4Addr::new(10, 0, 0, host.last));
+        assert_eq!(res.mac.as_deref(), Some(host.mac));
+        assert_eq!(res.hostname.as_deref(), Some(host.name));
+        assert_eq!(ports, host.ports);
+    }
+    assert_eq!(online.len(), HOSTS.len());
+
+    // A cancelled session still completes, marking targets it never reached.
+    let mut session = Scanner::start(Arc::new(SimNet), targets, config);
+    session.cancel();
+    let skipped = session
+        .results()
+        .filter(|r| std::future::ready(r.status == ScanStatus::NotScanned))
+        .count()
+        .await;
+    assert_eq!(session.await, Ok(ScanOutcome::Cancelled));
+    assert_eq!(skipped, 16);
+    println!("cancelled scan: {} targets not scanned", skipped);
+}
@@ -0,0 +1,179 @@
+//! What the About dialog and popup show.
+//!
+//! Besides the version and build, [`about_fields`] summarizes the scan
+//! settings in effect and where vendor and service names come from, so users
+//! can tell how stale the vendor database is and whether it is time to run
+//! `ragescanner-cli --update-oui PATH`.
+
+use crate::net::{self, OuiSource};
+use crate::report::format_utc;
+use crate::settings::Settings;
+use crate::types::{COMMON_PORTS, COMMON_UDP_PORTS, port_labels};
+use std::path::Path;
+
+/// Labelled lines describing this build, `settings` (saved at
+/// `settings_path`, if anywhere) and the data sources in use, in display
+/// order.
+pub fn about_fields(
+    settings: &Settings,
+    settings_path: Option<&Path>,
+) -> Vec<(&'static str, String)> {
+    let config = settings.scan_config();
+    let mut scan = vec![
+        format!("concurrency {}", config.concurrency),
+        format!("ping timeout {} ms", config.ping_timeout.as_millis()),
+        match config.port_timeout {
+            Some(timeout) => format!("port timeout {} ms", timeout.as_millis()),
+            None => "adaptive port timeout".to_string(),
+        },
+        format!("{} ports", config.ports.len()),
+    ];
+    if let Some(max) = config.max_host_connections {
+        scan.push(format!("{} connections per host", max));
+    }
+    let lookups: Vec<&str> = [
+        (config.resolve_hostnames, "hostnames"),
+        (config.resolve_vendors, "vendors"),
+    ]
+    .into_iter()
+    .filter_map(|(on, name)| on.then_some(name))
+    .collect();
+
+    let custom = port_labels().len();
+    let mut services = format!(
+        "built-in table of {} TCP and {} UDP ports",
+        COMMON_PORTS.len(),
+        COMMON_UDP_PORTS.len()
+    );
+    if custom > 0 {
+        services.push_str(&format!(", {} custom labels", custom));
+    }
+
+    vec![
+        (
+            "Version",
+            format!("RageScanner {}", env!("CARGO_PKG_VERSION")),
+        ),
+        ("Build", build_info()),
+        (
+            "Settings",
+            match settings_path {
+                Some(path) => path.display().to_string(),
+                None => "not saved".to_string(),
+            },
+        ),
+        ("Profile", settings.profile.to_string()),
+        ("Scan", scan.join(", ")),
+        (
+            "Lookups",
+            match lookups.as_slice() {
+                [] => "none".to_string(),
+                names => names.join(", "),
+            },
+        ),
+        ("Vendors", describe_oui(&net::oui_source())),
+        ("Services", services),
+    ]
+}
+
+/// Profile, target and optional features of this build.
+fn build_info() -> String {
+    let features: Vec<&str> = [
+        (cfg!(feature = "history"), "history"),
+        (cfg!(feature = "api"), "api"),
+        (cfg!(feature = "serde"), "serde"),
+    ]
+    .into_iter()
+    .filter_map(|(on, name)| on.then_some(name))
+    .collect();
+    format!(
+        "{}, {}/{}, features: {}",
+        if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        },
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        if features.is_empty() {
+            "none".to_string()
+        } else {
+            features.join(", ")
+        }
+    )
+}
+
+fn describe_oui(source: &OuiSource) -> String {
+    let Some(records) = source.records else {
+        return "unavailable".to_string();
+    };
+    match &source.path {
+        Some(path) => {
+            let updated = source
+                .modified
+                .map(format_utc)
+                .unwrap_or_else(|| "unknown".to_string());
+            format!(
+                "{}, {} records, updated {}",
+                path.display(),
+                records,
+                updated
+            )
+        }
+        None => format!("bundled with mac_oui, {} records", records),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_about_fields_summarize_the_settings() {
+        let settings = Settings {
+            concurrency: 7,
+            port_timeout_ms: None,
+            max_host_connections: Some(2),
+            resolve_hostnames: false,
+            resolve_vendors: true,
+            ..Settings::default()
+        };
+        let fields = about_fields(&settings, None);
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find(|(label, _)| *label == name)
+                .map(|(_, value)| value.clone())
+                .unwrap()
+        };
+        assert!(field("Version").ends_with(env!("CARGO_PKG_VERSION")));
+        assert_eq!(field("Settings"), "not saved");
+        assert_eq!(field("Profile"), "standard");
+        let scan = field("Scan");
+        assert!(scan.starts_with("concurrency 7, "), "{}", scan);
+        assert!(scan.contains("adaptive port timeout"), "{}", scan);
+        assert!(scan.ends_with("2 connections per host"), "{}", scan);
+        assert_eq!(field("Lookups"), "vendors");
+    }
+
+    #[test]
+    fn test_describe_oui_dates_custom_databases() {
+        let custom = OuiSource {
+            path: Some(PathBuf::from("oui.csv")),
+            records: Some(40),
+            modified: Some(UNIX_EPOCH + Duration::from_secs(86_400)),
+        };
+        assert_eq!(
+            describe_oui(&custom),
+            "oui.csv, 40 records, updated 1970-01-02 00:00:00 UTC"
+        );
+        let bundled = OuiSource {
+            records: Some(5),
+            ..OuiSource::default()
+        };
+        assert_eq!(describe_oui(&bundled), "bundled with mac_oui, 5 records");
+        assert_eq!(describe_oui(&OuiSource::default()), "unavailable");
+    }
+}
@@ -0,0 +1,1119 @@
+//! Remote scanning agents.
+//!
+//! An agent is a RageScanner instance in another network segment that sends
+//! probes on behalf of a scanner elsewhere. [`AgentServer`] runs the agent
+//! (`ragescanner-cli --agent-listen`); [`RemoteAgent`] is a
+//! [`NetworkProvider`] that forwards every probe to one, so a scan, a
+//! frontend's [`Bridge`](crate::bridge::Bridge) or the CLI (`--agent`) can
+//! reach subnets the local machine cannot.
+//!
+//! The protocol is newline-delimited JSON over TLS, or over a named pipe
+//! such as `\\server\pipe\ragescanner-agent` on Windows. The client opens
+//! with `{"id": 0, "op": "hello", "token": "…"}`; the agent answers with its
+//! version and [`Capabilities`], or refuses a wrong token and hangs up.
+//! Every later request carries an `id` that its response repeats:
+//! `{"id": 7, "ok": <result>}` or `{"id": 7, "error": <GError>}`. Requests
+//! are answered concurrently and in completion order, so a whole scan
+//! shares one connection.
+//!
+//! mDNS browsing, interface and power events stay local: they describe the
+//! machine running the scan, not the agent's network.
+
+use crate::net::{Capabilities, EchoReply, NetUtils, NetworkProvider};
+use crate::types::{
+    DEFAULT_PING_TIMEOUT, DnsConfig, GError, NameSource, NetworkRole, SmbInfo, SnmpInfo,
+    TcpProbeOptions, TlsInfo, TraceHop, UdpPortState, UpnpInfo, WebInfo,
+};
+use async_trait::async_trait;
+use futures::channel::oneshot;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::io::{
+    AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, Lines, ReadHalf, WriteHalf,
+};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use tokio_native_tls::{TlsAcceptor, TlsConnector, native_tls};
+
+/// TCP port agents listen on unless told otherwise.
+pub const DEFAULT_AGENT_PORT: u16 = 8788;
+/// Environment variable holding the shared agent token, kept off the
+/// command line where other users could read it.
+pub const AGENT_TOKEN_ENV: &str = "RAGESCANNER_AGENT_TOKEN";
+/// Environment variable holding the password of the agent's PKCS#12 file.
+pub const AGENT_IDENTITY_PASSWORD_ENV: &str = "RAGESCANNER_AGENT_IDENTITY_PASSWORD";
+/// Version of the protocol, reported in the agent's hello.
+pub const AGENT_PROTOCOL_VERSION: u32 = 1;
+
+/// Time allowed to connect and exchange hellos.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest wait for the answer to one request; probes time out well before.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Where an agent listens, or is reached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AgentAddress {
+    /// TLS over TCP. `host` is also the name the agent's certificate must carry.
+    Tls { host: String, port: u16 },
+    /// A Windows named pipe such as `\\server\pipe\ragescanner-agent`,
+    /// secured by Windows authentication.
+    Pipe(String),
+}
+
+impl FromStr for AgentAddress {
+    type Err = String;
+
+    /// Parses `host[:port]` (port [`DEFAULT_AGENT_PORT`] if omitted) or a
+    /// pipe name starting with `\\`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.starts_with(r"\\") {
+            return Ok(AgentAddress::Pipe(s.to_string()));
+        }
+        let (host, port) = match s.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse()
+                    .map_err(|_| format!("Invalid agent port '{}'", port))?,
+            ),
+            None => (s, DEFAULT_AGENT_PORT),
+        };
+        if host.is_empty() {
+            return Err(format!("Invalid agent address '{}'", s));
+        }
+        Ok(AgentAddress::Tls {
+            host: host.to_string(),
+            port,
+        })
+    }
+}
+
+impl fmt::Display for AgentAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AgentAddress::Tls { host, port } => write!(f, "{}:{}", host, port),
+            AgentAddress::Pipe(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// One request of the protocol, tagged by `op`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum AgentRequest {
+    Hello {
+        token: String,
+    },
+    Echo {
+        ip: Ipv4Addr,
+        timeout_ms: u64,
+    },
+    ResolveMac {
+        ip: Ipv4Addr,
+    },
+    ReadArpTable,
+    ResolveHostname {
+        ip: Ipv4Addr,
+    },
+    ResolveHostnames {
+        ips: Vec<Ipv4Addr>,
+        dns: DnsConfig,
+    },
+    ResolveAddresses {
+        names: Vec<String>,
+        dns: DnsConfig,
+    },
+    ResolveLocalName {
+        ip: Ipv4Addr,
+    },
+    DiscoverUpnp,
+    DiscoverRoles {
+        dhcp_probe: bool,
+    },
+    QuerySnmp {
+        ip: Ipv4Addr,
+        community: String,
+    },
+    ScanPort {
+        ip: Ipv4Addr,
+        port: u16,
+        timeout_ms: u64,
+        options: TcpProbeOptions,
+    },
+    ScanUdpPort {
+        ip: Ipv4Addr,
+        port: u16,
+    },
+    GrabBanner {
+        ip: Ipv4Addr,
+        port: u16,
+    },
+    ProbeSmb1 {
+        ip: Ipv4Addr,
+    },
+    SmbInfo {
+        ip: Ipv4Addr,
+    },
+    AuditTls {
+        ip: Ipv4Addr,
+        port: u16,
+    },
+    FetchWebInfo {
+        ip: Ipv4Addr,
+        port: u16,
+    },
+    RouteAvailable {
+        ip: Ipv4Addr,
+    },
+    TraceHop {
+        ip: Ipv4Addr,
+        ttl: u8,
+        timeout_ms: u64,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope {
+    id: u64,
+    #[serde(flatten)]
+    request: AgentRequest,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AgentResponse {
+    id: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ok: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<GError>,
+}
+
+impl AgentResponse {
+    fn new(id: u64, result: Result<Value, GError>) -> Self {
+        match result {
+            Ok(value) => Self {
+                id,
+                ok: Some(value),
+                error: None,
+            },
+            Err(e) => Self {
+                id,
+                ok: None,
+                error: Some(e),
+            },
+        }
+    }
+
+    fn into_result(self) -> Result<Value, GError> {
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(self.ok.unwrap_or(Value::Null)),
+        }
+    }
+}
+
+/// What the agent says in answer to a hello.
+#[derive(Debug, Serialize, Deserialize)]
+struct Welcome {
+    version: u32,
+    capabilities: Capabilities,
+}
+
+/// Compares tokens in time independent of where they differ.
+fn same_token(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis().min(u128::from(u64::MAX)) as u64
+}
+
+fn encode<T: Serialize>(value: T) -> Result<Value, GError> {
+    serde_json::to_value(value)
+        .map_err(|e| GError::Internal(format!("Failed to encode agent response: {}", e)))
+}
+
+fn decode<T: DeserializeOwned>(value: Value) -> Result<T, GError> {
+    serde_json::from_value(value)
+        .map_err(|e| GError::Internal(format!("Invalid agent response: {}", e)))
+}
+
+/// Writes `message` as one line.
+async fn write_line<W: AsyncWrite + Unpin>(
+    output: &mut W,
+    message: &impl Serialize,
+) -> std::io::Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    output.write_all(line.as_bytes()).await?;
+    output.flush().await
+}
+
+/// Runs `probe` on a blocking thread, for the provider's synchronous methods.
+async fn blocking<T, F>(net: &Arc<dyn NetworkProvider>, probe: F) -> Result<T, GError>
+where
+    T: Send + 'static,
+    F: FnOnce(&dyn NetworkProvider) -> T + Send + 'static,
+{
+    let net = net.clone();
+    tokio::task::spawn_blocking(move || probe(net.as_ref()))
+        .await
+        .map_err(|e| GError::Internal(format!("Agent probe failed: {}", e)))
+}
+
+/// Carries out one request with the agent's provider.
+async fn answer(net: &Arc<dyn NetworkProvider>, request: AgentRequest) -> Result<Value, GError> {
+    let ms = Duration::from_millis;
+    match request {
+        AgentRequest::Hello { .. } => Err(GError::Internal("Unexpected hello".to_string())),
+        AgentRequest::Echo { ip, timeout_ms } => encode(net.echo_async(ip, ms(timeout_ms)).await?),
+        AgentRequest::ResolveMac { ip } => encode(net.resolve_mac_async(ip).await?),
+        AgentRequest::ReadArpTable => encode(blocking(net, |net| net.read_arp_table()).await??),
+        AgentRequest::ResolveHostname { ip } => {
+            encode(blocking(net, move |net| net.resolve_hostname(ip)).await??)
+        }
+        AgentRequest::ResolveHostnames { ips, dns } => {
+            encode(net.resolve_hostnames(&ips, &dns).await)
+        }
+        AgentRequest::ResolveAddresses { names, dns } => {
+            encode(net.resolve_addresses(&names, &dns).await)
+        }
+        AgentRequest::ResolveLocalName { ip } => {
+            encode(blocking(net, move |net| net.resolve_local_name(ip)).await?)
+        }
+        AgentRequest::DiscoverUpnp => encode(blocking(net, |net| net.discover_upnp()).await?),
+        AgentRequest::DiscoverRoles { dhcp_probe } => {
+            encode(blocking(net, move |net| net.discover_roles(dhcp_probe)).await?)
+        }
+        AgentRequest::QuerySnmp { ip, community } => {
+            encode(blocking(net, move |net| net.query_snmp(ip, &community)).await?)
+        }
+        AgentRequest::ScanPort {
+            ip,
+            port,
+            timeout_ms,
+            options,
+        } => encode(
+            net.scan_port_with_options(ip, port, ms(timeout_ms), options)
+                .await,
+        ),
+        AgentRequest::ScanUdpPort { ip, port } => encode(net.scan_udp_port(ip, port).await),
+        AgentRequest::GrabBanner { ip, port } => encode(net.grab_banner(ip, port).await),
+        AgentRequest::ProbeSmb1 { ip } => encode(net.probe_smb1(ip).await),
+        AgentRequest::SmbInfo { ip } => encode(net.smb_info(ip).await),
+        AgentRequest::AuditTls { ip, port } => encode(net.audit_tls(ip, port).await),
+        AgentRequest::FetchWebInfo { ip, port } => encode(net.fetch_web_info(ip, port).await),
+        AgentRequest::RouteAvailable { ip } => encode(net.route_available(ip)),
+        AgentRequest::TraceHop {
+            ip,
+            ttl,
+            timeout_ms,
+        } => encode(blocking(net, move |net| net.trace_hop(ip, ttl, ms(timeout_ms))).await??),
+    }
+}
+
+/// Serves one client over `stream` until it disconnects: checks its hello
+/// against `token`, then answers requests concurrently with `net`.
+///
+/// # Errors
+///
+/// Returns [`GError::PermissionDenied`] for a wrong token, or
+/// [`GError::Internal`] if the hello is missing or malformed or the stream
+/// fails.
+pub async fn serve_connection<S>(
+    stream: S,
+    net: Arc<dyn NetworkProvider>,
+    token: &str,
+) -> Result<(), GError>
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let read_failed = |e: std::io::Error| GError::Internal(format!("Agent read failed: {}", e));
+    let write_failed = |e: std::io::Error| GError::Internal(format!("Agent write failed: {}", e));
+    let (input, mut output) = tokio::io::split(stream);
+    let mut lines = BufReader::new(input).lines();
+
+    let hello = tokio::time::timeout(HANDSHAKE_TIMEOUT, lines.next_line())
+        .await
+        .map_err(|_| GError::Internal("Agent client sent no hello".to_string()))?
+        .map_err(read_failed)?
+        .ok_or_else(|| GError::Internal("Agent client sent no hello".to_string()))?;
+    let hello: Envelope = serde_json::from_str(&hello)
+        .map_err(|e| GError::Internal(format!("Invalid agent hello: {}", e)))?;
+    match hello.request {
+        AgentRequest::Hello { token: given } if same_token(&given, token) => {
+            let welcome = Welcome {
+                version: AGENT_PROTOCOL_VERSION,
+                capabilities: net.capabilities(),
+            };
+            let response = AgentResponse::new(hello.id, encode(welcome));
+            write_line(&mut output, &response)
+                .await
+                .map_err(write_failed)?;
+        }
+        _ => {
+            let denied = GError::PermissionDenied("Invalid agent token".to_string());
+            let response = AgentResponse::new(hello.id, Err(denied.clone()));
+            let _ = write_line(&mut output, &response).await;
+            return Err(denied);
+        }
+    }
+
+    let (responses, mut outgoing) = unbounded_channel::<AgentResponse>();
+    let writer = tokio::spawn(async move {
+        while let Some(response) = outgoing.recv().await {
+            write_line(&mut output, &response).await?;
+        }
+        Ok::<(), std::io::Error>(())
+    });
+    while let Some(line) = lines.next_line().await.map_err(read_failed)? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: Value = match serde_json::from_str(&line) {
+            Ok(value) => value,
+            Err(e) => {
+                log::debug!("Ignoring agent request that is not JSON: {}", e);
+                continue;
+            }
+        };
+        let Some(id) = value.get("id").and_then(Value::as_u64) else {
+            log::debug!("Ignoring agent request without an id");
+            continue;
+        };
+        let envelope = match serde_json::from_value::<Envelope>(value) {
+            Ok(envelope) => envelope,
+            Err(e) => {
+                let error = GError::Internal(format!("Invalid agent request: {}", e));
+                let _ = responses.send(AgentResponse::new(id, Err(error)));
+                continue;
+            }
+        };
+        let net = net.clone();
+        let responses = responses.clone();
+        tokio::spawn(async move {
+            let result = answer(&net, envelope.request).await;
+            let _ = responses.send(AgentResponse::new(envelope.id, result));
+        });
+    }
+    drop(responses);
+    writer
+        .await
+        .map_err(|e| GError::Internal(format!("Agent writer failed: {}", e)))?
+        .map_err(write_failed)
+}
+
+/// Where an [`AgentServer`] takes connections.
+enum AgentListener {
+    Tls {
+        listener: std::net::TcpListener,
+        acceptor: TlsAcceptor,
+    },
+    #[cfg_attr(not(windows), allow(dead_code))]
+    Pipe(String),
+}
+
+/// Runs an agent: answers the probes of [`RemoteAgent`]s that present the
+/// shared token.
+pub struct AgentServer {
+    listener: AgentListener,
+    net: Arc<dyn NetworkProvider>,
+    token: Arc<str>,
+}
+
+impl AgentServer {
+    /// Binds `address`, probing with `net` for clients that present `token`.
+    ///
+    /// A TLS address needs the agent's certificate and key as `identity`,
+    /// e.g. from [`load_identity`]; a pipe ignores it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if the token is empty, a TLS address
+    /// has no identity, the address cannot be bound, or a pipe is asked for
+    /// outside Windows.
+    pub fn bind(
+        address: &AgentAddress,
+        identity: Option<native_tls::Identity>,
+        net: Arc<dyn NetworkProvider>,
+        token: &str,
+    ) -> Result<Self, GError> {
+        if token.is_empty() {
+            return Err(GError::Internal("The agent token is empty".to_string()));
+        }
+        let listener = match address {
+            AgentAddress::Tls { host, port } => {
+                let identity = identity.ok_or_else(|| {
+                    GError::Internal("A TLS agent needs a certificate (PKCS#12 file)".to_string())
+                })?;
+                let acceptor = native_tls::TlsAcceptor::new(identity)
+                    .map_err(|e| GError::Internal(format!("Invalid agent certificate: {}", e)))?;
+                let bind_error = |e: std::io::Error| {
+                    GError::Internal(format!("Failed to bind agent to {}: {}", address, e))
+                };
+                let listener =
+                    std::net::TcpListener::bind((host.as_str(), *port)).map_err(bind_error)?;
+                listener.set_nonblocking(true).map_err(bind_error)?;
+                AgentListener::Tls {
+                    listener,
+                    acceptor: TlsAcceptor::from(acceptor),
+                }
+            }
+            AgentAddress::Pipe(name) => {
+                if !cfg!(windows) {
+                    return Err(GError::Internal(
+                        "Named pipes are only available on Windows".to_string(),
+                    ));
+                }
+                AgentListener::Pipe(name.clone())
+            }
+        };
+        Ok(Self {
+            listener,
+            net,
+            token: token.into(),
+        })
+    }
+
+    /// The bound TCP address, e.g. to find the port picked for `:0`.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        match &self.listener {
+            AgentListener::Tls { listener, .. } => listener.local_addr().ok(),
+            AgentListener::Pipe(_) => None,
+        }
+    }
+
+    /// Serves clients on the current Tokio runtime until it shuts down.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if the listener cannot be registered
+    /// with the runtime or a pipe instance cannot be created.
+    pub async fn serve(self) -> Result<(), GError> {
+        match self.listener {
+            AgentListener::Tls { listener, acceptor } => {
+                let listener = TcpListener::from_std(listener)
+                    .map_err(|e| GError::Internal(format!("Failed to start agent: {}", e)))?;
+                loop {
+                    let (stream, peer) = match listener.accept().await {
+                        Ok(accepted) => accepted,
+                        Err(e) => {
+                            log::warn!("Agent accept failed: {}", e);
+                            continue;
+                        }
+                    };
+                    let acceptor = acceptor.clone();
+                    let net = self.net.clone();
+                    let token = self.token.clone();
+                    tokio::spawn(async move {
+                        let result = match acceptor.accept(stream).await {
+                            Ok(tls) => serve_connection(tls, net, &token).await,
+                            Err(e) => Err(GError::Internal(format!("TLS handshake failed: {}", e))),
+                        };
+                        match result {
+                            Ok(()) => log::info!("Agent client {} disconnected", peer),
+                            Err(e) => log::warn!("Agent client {}: {}", peer, e),
+                        }
+                    });
+                }
+            }
+            AgentListener::Pipe(name) => serve_pipe(&name, self.net, self.token).await,
+        }
+    }
+}
+
+/// Serves pipe clients, creating the next instance as each one connects.
+#[cfg(windows)]
+async fn serve_pipe(
+    name: &str,
+    net: Arc<dyn NetworkProvider>,
+    token: Arc<str>,
+) -> Result<(), GError> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+    let create_error =
+        |e: std::io::Error| GError::Internal(format!("Failed to create pipe {}: {}", name, e));
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .reject_remote_clients(false)
+        .create(name)
+        .map_err(create_error)?;
+    loop {
+        if let Err(e) = server.connect().await {
+            log::warn!("Agent pipe connect failed: {}", e);
+            continue;
+        }
+        let client = server;
+        server = ServerOptions::new()
+            .reject_remote_clients(false)
+            .create(name)
+            .map_err(create_error)?;
+        let net = net.clone();
+        let token = token.clone();
+        tokio::spawn(async move {
+            if let Err(e) = serve_connection(client, net, &token).await {
+                log::warn!("Agent pipe client: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(not(windows))]
+async fn serve_pipe(
+    _name: &str,
+    _net: Arc<dyn NetworkProvider>,
+    _token: Arc<str>,
+) -> Result<(), GError> {
+    Err(GError::Internal(
+        "Named pipes are only available on Windows".to_string(),
+    ))
+}
+
+/// Reads a PKCS#12 file (`.p12`/`.pfx`) holding the agent's certificate and key.
+///
+/// # Errors
+///
+/// Returns [`GError::Internal`] if the file cannot be read or decrypted.
+pub fn load_identity(path: &Path, password: &str) -> Result<native_tls::Identity, GError> {
+    let der = fs::read(path)
+        .map_err(|e| GError::Internal(format!("Failed to read {}: {}", path.display(), e)))?;
+    native_tls::Identity::from_pkcs12(&der, password)
+        .map_err(|e| GError::Internal(format!("{}: {}", path.display(), e)))
+}
+
+/// A request waiting to be written, and where its answer goes.
+struct PendingRequest {
+    id: u64,
+    request: AgentRequest,
+    reply: oneshot::Sender<Result<Value, GError>>,
+}
+
+/// A [`NetworkProvider`] whose probes run on a remote agent; see the
+/// [module docs](self).
+///
+/// Vendor lookups use the local OUI database. The synchronous methods
+/// block their thread until the agent answers, so call them from blocking
+/// threads as the scanner does, not from a current-thread runtime.
+pub struct RemoteAgent {
+    address: String,
+    requests: UnboundedSender<PendingRequest>,
+    next_id: AtomicU64,
+    capabilities: Capabilities,
+}
+
+impl RemoteAgent {
+    /// Connects to the agent at `address` and says hello with `token`.
+    ///
+    /// The agent's TLS certificate must be valid for the host name; a
+    /// self-signed one can be trusted by passing it (PEM) as `ca_cert`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::HostUnreachable`] if the agent cannot be reached,
+    /// [`GError::Timeout`] if it does not answer the handshake,
+    /// [`GError::PermissionDenied`] if it refuses the token, or
+    /// [`GError::Internal`] for TLS and protocol failures.
+    pub async fn connect(
+        address: &AgentAddress,
+        token: &str,
+        ca_cert: Option<&Path>,
+    ) -> Result<Self, GError> {
+        let unreachable = |e: std::io::Error| {
+            GError::HostUnreachable(format!("Cannot reach agent {}: {}", address, e))
+        };
+        match address {
+            AgentAddress::Tls { host, port } => {
+                let mut builder = native_tls::TlsConnector::builder();
+                if let Some(path) = ca_cert {
+                    let pem = fs::read(path).map_err(|e| {
+                        GError::Internal(format!("Failed to read {}: {}", path.display(), e))
+                    })?;
+                    let cert = native_tls::Certificate::from_pem(&pem)
+                        .map_err(|e| GError::Internal(format!("{}: {}", path.display(), e)))?;
+                    builder.add_root_certificate(cert);
+                }
+                let connector = TlsConnector::from(
+                    builder
+                        .build()
+                        .map_err(|e| GError::Internal(format!("TLS setup failed: {}", e)))?,
+                );
+                let stream = tokio::time::timeout(
+                    HANDSHAKE_TIMEOUT,
+                    TcpStream::connect((host.as_str(), *port)),
+                )
+                .await
+                .map_err(|_| GError::Timeout(format!("Agent {} did not answer", address)))?
+                .map_err(unreachable)?;
+                let tls = connector.connect(host, stream).await.map_err(|e| {
+                    GError::Internal(format!(
+                        "TLS handshake with agent {} failed: {}",
+                        address, e
+                    ))
+                })?;
+                Self::over_named(tls, token, address.to_string()).await
+            }
+            AgentAddress::Pipe(name) => {
+                #[cfg(windows)]
+                {
+                    use tokio::net::windows::named_pipe::ClientOptions;
+                    let pipe = ClientOptions::new().open(name).map_err(unreachable)?;
+                    Self::over_named(pipe, token, name.clone()).await
+                }
+                #[cfg(not(windows))]
+                {
+                    let _ = (name, unreachable);
+                    Err(GError::Internal(
+                        "Named pipes are only available on Windows".to_string(),
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Says hello over an already connected `stream`, e.g. an SSH tunnel
+    /// or, in tests, one end of [`tokio::io::duplex`].
+    ///
+    /// # Errors
+    ///
+    /// As for [`connect`](RemoteAgent::connect), minus connection failures.
+    pub async fn over<S>(stream: S, token: &str) -> Result<Self, GError>
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        Self::over_named(stream, token, "agent".to_string()).await
+    }
+
+    async fn over_named<S>(stream: S, token: &str, address: String) -> Result<Self, GError>
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (input, mut output) = tokio::io::split(stream);
+        let mut lines = BufReader::new(input).lines();
+        let hello = Envelope {
+            id: 0,
+            request: AgentRequest::Hello {
+                token: token.to_string(),
+            },
+        };
+        let exchange = async {
+            write_line(&mut output, &hello).await?;
+            lines.next_line().await
+        };
+        let failed = || GError::Internal(format!("Agent {} did not say hello", address));
+        let line = tokio::time::timeout(HANDSHAKE_TIMEOUT, exchange)
+            .await
+            .map_err(|_| failed())?
+            .map_err(|e| GError::Internal(format!("Agent {} hello failed: {}", address, e)))?
+            .ok_or_else(failed)?;
+        let response: AgentResponse = serde_json::from_str(&line)
+            .map_err(|e| GError::Internal(format!("Invalid agent hello: {}", e)))?;
+        let welcome: Welcome = decode(response.into_result()?)?;
+        log::info!(
+            "Connected to agent {} (protocol {}, {})",
+            address,
+            welcome.version,
+            welcome.capabilities
+        );
+
+        let (requests, pending) = unbounded_channel();
+        tokio::spawn(run_client(lines, output, pending, address.clone()));
+        Ok(Self {
+            address,
+            requests,
+            next_id: AtomicU64::new(1),
+            capabilities: welcome.capabilities,
+        })
+    }
+
+    fn send(
+        &self,
+        request: AgentRequest,
+    ) -> Result<oneshot::Receiver<Result<Value, GError>>, GError> {
+        let (reply, answer) = oneshot::channel();
+        let pending = PendingRequest {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            request,
+            reply,
+        };
+        self.requests
+            .send(pending)
+            .map_err(|_| self.disconnected())?;
+        Ok(answer)
+    }
+
+    fn disconnected(&self) -> GError {
+        GError::Internal(format!("Agent {} disconnected", self.address))
+    }
+
+    async fn ask<T: DeserializeOwned>(&self, request: AgentRequest) -> Result<T, GError> {
+        let answer = self.send(request)?;
+        match tokio::time::timeout(REQUEST_TIMEOUT, answer).await {
+            Ok(Ok(result)) => decode(result?),
+            Ok(Err(_)) => Err(self.disconnected()),
+            Err(_) => Err(GError::Timeout(format!(
+                "Agent {} did not answer in time",
+                self.address
+            ))),
+        }
+    }
+
+    fn ask_blocking<T: DeserializeOwned>(&self, request: AgentRequest) -> Result<T, GError> {
+        let answer = self.send(request)?;
+        match futures::executor::block_on(answer) {
+            Ok(result) => decode(result?),
+            Err(_) => Err(self.disconnected()),
+        }
+    }
+}
+
+/// Writes requests and routes responses back to their callers until either
+/// side goes away; callers still waiting then see the agent disconnect.
+async fn run_client<S>(
+    mut lines: Lines<BufReader<ReadHalf<S>>>,
+    mut output: WriteHalf<S>,
+    mut pending: UnboundedReceiver<PendingRequest>,
+    address: String,
+) where
+    S: AsyncRead + AsyncWrite,
+{
+    let mut waiting: HashMap<u64, oneshot::Sender<Result<Value, GError>>> = HashMap::new();
+    loop {
+        tokio::select! {
+            next = pending.recv() => {
+                let Some(PendingRequest { id, request, reply }) = next else {
+                    break;
+                };
+                if let Err(e) = write_line(&mut output, &Envelope { id, request }).await {
+                    log::warn!("Agent {} write failed: {}", address, e);
+                    break;
+                }
+                waiting.insert(id, reply);
+            }
+            line = lines.next_line() => {
+                let line = match line {
+                    Ok(Some(line)) => line,
+                    Ok(None) => break,
+                    Err(e) => {
+                        log::warn!("Agent {} read failed: {}", address, e);
+                        break;
+                    }
+                };
+                match serde_json::from_str::<AgentResponse>(&line) {
+                    Ok(response) => {
+                        if let Some(reply) = waiting.remove(&response.id) {
+                            let _ = reply.send(response.into_result());
+                        }
+                    }
+                    Err(e) => log::debug!("Ignoring invalid agent response: {}", e),
+                }
+            }
+        }
+    }
+    log::warn!("Agent {} disconnected", address);
+}
+
+#[async_trait]
+impl NetworkProvider for RemoteAgent {
+    fn echo(&self, ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+        self.echo_timeout(ip, DEFAULT_PING_TIMEOUT)
+    }
+
+    fn echo_timeout(&self, ip: Ipv4Addr, timeout: Duration) -> Result<Option<u32>, GError> {
+        let reply: Option<EchoReply> = self.ask_blocking(AgentRequest::Echo {
+            ip,
+            timeout_ms: millis(timeout),
+        })?;
+        Ok(reply.map(|reply| reply.rtt_ms))
+    }
+
+    async fn echo_async(
+        &self,
+        ip: Ipv4Addr,
+        timeout: Duration,
+    ) -> Result<Option<EchoReply>, GError> {
+        self.ask(AgentRequest::Echo {
+            ip,
+            timeout_ms: millis(timeout),
+        })
+        .await
+    }
+
+    fn trace_hop(&self, ip: Ipv4Addr, ttl: u8, timeout: Duration) -> Result<TraceHop, GError> {
+        self.ask_blocking(AgentRequest::TraceHop {
+            ip,
+            ttl,
+            timeout_ms: millis(timeout),
+        })
+    }
+
+    fn resolve_mac(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+        self.ask_blocking(AgentRequest::ResolveMac { ip })
+    }
+
+    async fn resolve_mac_async(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+        self.ask(AgentRequest::ResolveMac { ip }).await
+    }
+
+    fn read_arp_table(&self) -> Result<HashMap<Ipv4Addr, String>, GError> {
+        self.ask_blocking(AgentRequest::ReadArpTable)
+    }
+
+    fn resolve_hostname(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+        self.ask_blocking(AgentRequest::ResolveHostname { ip })
+    }
+
+    async fn resolve_hostnames(
+        &self,
+        ips: &[Ipv4Addr],
+        dns: &DnsConfig,
+    ) -> HashMap<Ipv4Addr, String> {
+        let request = AgentRequest::ResolveHostnames {
+            ips: ips.to_vec(),
+            dns: dns.clone(),
+        };
+        self.ask(request).await.unwrap_or_else(|e| {
+            log::warn!("Reverse DNS through agent failed: {}", e);
+            HashMap::new()
+        })
+    }
+
+    async fn resolve_addresses(
+        &self,
+        names: &[String],
+        dns: &DnsConfig,
+    ) -> HashMap<String, Vec<Ipv4Addr>> {
+        let request = AgentRequest::ResolveAddresses {
+            names: names.to_vec(),
+            dns: dns.clone(),
+        };
+        self.ask(request).await.unwrap_or_else(|e| {
+            log::warn!("Forward DNS through agent failed: {}", e);
+            HashMap::new()
+        })
+    }
+
+    fn resolve_local_name(&self, ip: Ipv4Addr) -> Option<(String, NameSource)> {
+        self.ask_blocking(AgentRequest::ResolveLocalName { ip })
+            .ok()
+            .flatten()
+    }
+
+    fn discover_upnp(&self) -> HashMap<Ipv4Addr, UpnpInfo> {
+        self.ask_blocking(AgentRequest::DiscoverUpnp)
+            .unwrap_or_else(|e| {
+                log::warn!("SSDP discovery through agent failed: {}", e);
+                HashMap::new()
+            })
+    }
+
+    fn discover_roles(&self, dhcp_probe: bool) -> HashMap<Ipv4Addr, Vec<NetworkRole>> {
+        self.ask_blocking(AgentRequest::DiscoverRoles { dhcp_probe })
+            .unwrap_or_else(|e| {
+                log::warn!("Role discovery through agent failed: {}", e);
+                HashMap::new()
+            })
+    }
+
+    fn query_snmp(&self, ip: Ipv4Addr, community: &str) -> Option<SnmpInfo> {
+        self.ask_blocking(AgentRequest::QuerySnmp {
+            ip,
+            community: community.to_string(),
+        })
+        .ok()
+        .flatten()
+    }
+
+    /// From the local OUI database; vendors do not depend on the network.
+    fn resolve_vendor(&self, mac: &str) -> Option<String> {
+        NetUtils::new().resolve_vendor(mac)
+    }
+
+    async fn scan_port(&self, ip: Ipv4Addr, port: u16) -> bool {
+        self.scan_port_timeout(ip, port, crate::net::DEFAULT_PORT_TIMEOUT)
+            .await
+    }
+
+    async fn scan_port_timeout(&self, ip: Ipv4Addr, port: u16, timeout: Duration) -> bool {
+        self.scan_port_with_options(ip, port, timeout, TcpProbeOptions::default())
+            .await
+    }
+
+    async fn scan_port_with_options(
+        &self,
+        ip: Ipv4Addr,
+        port: u16,
+        timeout: Duration,
+        options: TcpProbeOptions,
+    ) -> bool {
+        self.ask(AgentRequest::ScanPort {
+            ip,
+            port,
+            timeout_ms: millis(timeout),
+            options,
+        })
+        .await
+        .unwrap_or(false)
+    }
+
+    async fn scan_udp_port(&self, ip: Ipv4Addr, port: u16) -> UdpPortState {
+        self.ask(AgentRequest::ScanUdpPort { ip, port })
+            .await
+            .unwrap_or(UdpPortState::Closed)
+    }
+
+    async fn grab_banner(&self, ip: Ipv4Addr, port: u16) -> Option<String> {
+        self.ask(AgentRequest::GrabBanner { ip, port })
+            .await
+            .ok()
+            .flatten()
+    }
+
+    async fn probe_smb1(&self, ip: Ipv4Addr) -> Option<bool> {
+        self.ask(AgentRequest::ProbeSmb1 { ip })
+            .await
+            .ok()
+            .flatten()
+    }
+
+    async fn smb_info(&self, ip: Ipv4Addr) -> Option<SmbInfo> {
+        self.ask(AgentRequest::SmbInfo { ip }).await.ok().flatten()
+    }
+
+    async fn audit_tls(&self, ip: Ipv4Addr, port: u16) -> Option<TlsInfo> {
+        self.ask(AgentRequest::AuditTls { ip, port })
+            .await
+            .ok()
+            .flatten()
+    }
+
+    async fn fetch_web_info(&self, ip: Ipv4Addr, port: u16) -> Option<WebInfo> {
+        self.ask(AgentRequest::FetchWebInfo { ip, port })
+            .await
+            .ok()
+            .flatten()
+    }
+
+    fn route_available(&self, ip: Ipv4Addr) -> bool {
+        self.ask_blocking(AgentRequest::RouteAvailable { ip })
+            .unwrap_or(false)
+    }
+
+    /// What the agent reported when the connection was made.
+    fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::MockNet;
+
+    /// A client connected to an agent serving [`MockNet`] in this process.
+    async fn connected(token: &str) -> Result<RemoteAgent, GError> {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        tokio::spawn(async move { serve_connection(server, Arc::new(MockNet), "secret").await });
+        RemoteAgent::over(client, token).await
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_probes_run_on_the_agent() {
+        let agent = Arc::new(connected("secret").await.unwrap());
+        let host = Ipv4Addr::new(192, 168, 1, 1);
+
+        let reply = agent
+            .echo_async(host, Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert_eq!(
+            reply,
+            Some(EchoReply {
+                rtt_ms: 2,
+                ttl: Some(128)
+            })
+        );
+        assert_eq!(
+            agent.resolve_mac_async(host).await.unwrap().as_deref(),
+            Some("00:11:22:33:44:55")
+        );
+        assert!(agent.scan_port(host, 80).await);
+        assert!(!agent.scan_port(host, 22).await);
+        assert_eq!(
+            agent.scan_udp_port(host, 161).await,
+            UdpPortState::OpenFiltered
+        );
+        assert_eq!(
+            agent.grab_banner(host, 80).await.as_deref(),
+            Some("mock-httpd/1.0")
+        );
+        assert_eq!(agent.capabilities(), Capabilities::default());
+
+        // Errors keep their kind across the connection.
+        let failing = Ipv4Addr::new(192, 168, 1, 2);
+        assert_eq!(
+            agent.echo_async(failing, Duration::from_secs(1)).await,
+            Err(GError::Internal("Simulated Failure".to_string()))
+        );
+
+        // Synchronous lookups, from a blocking thread as the scanner makes them.
+        let blocking_agent = agent.clone();
+        let name = tokio::task::spawn_blocking(move || blocking_agent.resolve_hostname(host))
+            .await
+            .unwrap();
+        assert_eq!(name.unwrap().as_deref(), Some("mock-host"));
+
+        // Many probes share the connection.
+        let probes = (1..=100u16).map(|port| agent.scan_port(host, port));
+        let open = futures::future::join_all(probes).await;
+        assert_eq!(open.iter().filter(|&&open| open).count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_wrong_token_is_refused() {
+        let err = connected("guess").await.err().unwrap();
+        assert_eq!(
+            err,
+            GError::PermissionDenied("Invalid agent token".to_string())
+        );
+    }
+
+    #[test]
+    fn test_agent_address_parsing() {
+        assert_eq!(
+            "10.0.8.2".parse::<AgentAddress>(),
+            Ok(AgentAddress::Tls {
+                host: "10.0.8.2".to_string(),
+                port: DEFAULT_AGENT_PORT
+            })
+        );
+        assert_eq!(
+            "agent.lan:9000"
+                .parse::<AgentAddress>()
+                .unwrap()
+                .to_string(),
+            "agent.lan:9000"
+        );
+        assert_eq!(
+            r"\\branch\pipe\ragescanner-agent".parse::<AgentAddress>(),
+            Ok(AgentAddress::Pipe(
+                r"\\branch\pipe\ragescanner-agent".to_string()
+            ))
+        );
+        assert!("agent.lan:http".parse::<AgentAddress>().is_err());
+        assert!(":9000".parse::<AgentAddress>().is_err());
+    }
+}
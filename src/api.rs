@@ -0,0 +1,661 @@
+//! REST API for triggering scans from other tools.
+//!
+//! [`ApiServer`] is one more frontend of a [`Bridge`]: every `POST /scans`
+//! becomes a tagged scan ([`BridgeMessage::StartTaggedScan`]), so API scans
+//! run alongside anything else the bridge is doing and never trip its busy
+//! policy. All bodies are JSON:
+//!
+//! | Request | Response |
+//! |---|---|
+//! | `POST /scans` `{"range": "10.0.0.0/24", "profile": "safe", "config": {...}}` | `201 {"id": 1, "state": "running", ...}` |
+//! | `GET /scans/{id}` | `200` state, progress (overall and per phase) and result count |
+//! | `GET /scans/{id}/results` | `200` results so far, as [`export`] JSON |
+//! | `DELETE /scans/{id}` | `202` and the scan is cancelled; `409` if it already ended |
+//!
+//! `profile` ([`ScanProfile`], default `standard`) picks the starting
+//! config; `config` is optional and may set any subset of the
+//! [`ScanConfig`](crate::types::ScanConfig) fields on top of it.
+//! A range the bridge cannot parse shows up as a `failed` scan. There is no
+//! authentication, so bind to loopback unless the network is trusted.
+//!
+//! Enabled by the `api` feature.
+
+use crate::bridge::Bridge;
+use crate::export::{self, OutputFormat};
+use crate::types::{
+    BridgeMessage, GError, PhaseProgress, RangeProgress, ScanId, ScanProfile, ScanResult,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::Sender as TokioSender;
+
+/// Address the CLI serves on when `--serve` is given without one.
+pub const DEFAULT_API_ADDR: &str = "127.0.0.1:8787";
+
+/// Finished scans kept for `GET`; the oldest are forgotten first.
+pub const MAX_FINISHED_SCANS: usize = 32;
+
+/// Request head and body bytes accepted at most.
+const MAX_REQUEST: usize = 64 * 1024;
+
+/// Budget for a client to send its whole request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Lifecycle of a scan started over the API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiScanState {
+    Running,
+    Complete,
+    Cancelled,
+    /// The bridge reported an error, e.g. an unparsable range.
+    Failed,
+}
+
+/// What `GET /scans/{id}` returns.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ApiScanStatus {
+    pub id: u64,
+    pub state: ApiScanState,
+    pub progress: u8,
+    /// Discovery and port-scan progress, once the scanner has reported it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phase: Option<PhaseProgress>,
+    /// Results received so far.
+    pub results: usize,
+    /// Per-range progress when the scan covers several ranges.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ranges: Vec<RangeProgress>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Body of `POST /scans`.
+#[derive(Debug, Deserialize)]
+struct StartRequest {
+    range: String,
+    /// Where the config starts from.
+    #[serde(default)]
+    profile: ScanProfile,
+    /// Fields overriding the profile's config.
+    #[serde(default)]
+    config: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+struct ApiScan {
+    state: ApiScanState,
+    progress: u8,
+    phase: Option<PhaseProgress>,
+    ranges: Vec<RangeProgress>,
+    results: Vec<ScanResult>,
+    error: Option<String>,
+}
+
+/// Every scan the server knows about, fed from the bridge's messages.
+#[derive(Default)]
+struct ScanTable {
+    next_id: u64,
+    scans: BTreeMap<u64, ApiScan>,
+}
+
+impl ScanTable {
+    /// Registers a new running scan and returns its id.
+    fn create(&mut self) -> ScanId {
+        self.prune();
+        self.next_id += 1;
+        self.scans.insert(
+            self.next_id,
+            ApiScan {
+                state: ApiScanState::Running,
+                progress: 0,
+                phase: None,
+                ranges: Vec::new(),
+                results: Vec::new(),
+                error: None,
+            },
+        );
+        ScanId(self.next_id)
+    }
+
+    /// Records a bridge message; anything not tagged with a known scan is ignored.
+    fn apply(&mut self, msg: BridgeMessage) {
+        let BridgeMessage::Tagged(ScanId(id), msg) = msg else {
+            return;
+        };
+        let Some(scan) = self.scans.get_mut(&id) else {
+            return;
+        };
+        match *msg {
+            BridgeMessage::ScanUpdate(res) => scan.results.push(res),
+            BridgeMessage::Progress(progress) => scan.progress = progress,
+            BridgeMessage::Phase(phase) => scan.phase = Some(phase),
+            BridgeMessage::RangeProgress(range) => {
+                match scan.ranges.iter_mut().find(|r| r.index == range.index) {
+                    Some(known) => *known = range,
+                    None => scan.ranges.push(range),
+                }
+            }
+            BridgeMessage::ScanComplete => {
+                scan.state = ApiScanState::Complete;
+                scan.progress = 100;
+            }
+            BridgeMessage::ScanCancelled => scan.state = ApiScanState::Cancelled,
+            BridgeMessage::Error(e) => {
+                scan.state = ApiScanState::Failed;
+                scan.error = Some(e.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    fn status(&self, id: u64) -> Option<ApiScanStatus> {
+        let scan = self.scans.get(&id)?;
+        Some(ApiScanStatus {
+            id,
+            state: scan.state,
+            progress: scan.progress,
+            phase: scan.phase,
+            ranges: scan.ranges.clone(),
+            results: scan.results.len(),
+            error: scan.error.clone(),
+        })
+    }
+
+    /// Forgets the oldest finished scans beyond [`MAX_FINISHED_SCANS`].
+    fn prune(&mut self) {
+        let finished: Vec<u64> = self
+            .scans
+            .iter()
+            .filter(|(_, scan)| scan.state != ApiScanState::Running)
+            .map(|(&id, _)| id)
+            .collect();
+        for id in finished
+            .iter()
+            .take(finished.len().saturating_sub(MAX_FINISHED_SCANS))
+        {
+            self.scans.remove(id);
+        }
+    }
+}
+
+struct ApiState {
+    cmd_tx: TokioSender<BridgeMessage>,
+    scans: Arc<Mutex<ScanTable>>,
+}
+
+impl ApiState {
+    fn new(cmd_tx: TokioSender<BridgeMessage>) -> Self {
+        Self {
+            cmd_tx,
+            scans: Arc::new(Mutex::new(ScanTable::default())),
+        }
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, ScanTable>, Response> {
+        self.scans
+            .lock()
+            .map_err(|_| Response::error(500, "Scan table is unavailable"))
+    }
+}
+
+/// A JSON response.
+#[derive(Debug, PartialEq, Eq)]
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    fn json<T: Serialize>(status: u16, value: &T) -> Self {
+        match serde_json::to_string_pretty(value) {
+            Ok(body) => Self { status, body },
+            Err(e) => Self::error(500, &format!("Failed to serialize response: {}", e)),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            body: serde_json::json!({ "error": message }).to_string(),
+        }
+    }
+
+    /// Replaces a `200` with `status`; errors keep theirs.
+    fn with_status(self, status: u16) -> Self {
+        if self.status == 200 {
+            Self { status, ..self }
+        } else {
+            self
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            201 => "Created",
+            202 => "Accepted",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            409 => "Conflict",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        }
+    }
+
+    fn to_http(&self) -> String {
+        format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            self.reason(),
+            self.body.len(),
+            self.body
+        )
+    }
+}
+
+/// HTTP server exposing a [`Bridge`] as a REST API; see the [module docs](self).
+pub struct ApiServer {
+    listener: std::net::TcpListener,
+    state: Arc<ApiState>,
+    /// Shuts down with the server.
+    _bridge: Bridge,
+}
+
+impl ApiServer {
+    /// Binds `addr` and takes over `bridge`, whose `ui_rx` must have no
+    /// other reader: a collector thread drains it into the scan table.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if `addr` cannot be bound.
+    pub fn bind(addr: SocketAddr, bridge: Bridge) -> Result<Self, GError> {
+        let bind_error =
+            |e: std::io::Error| GError::Internal(format!("Failed to bind API to {}: {}", addr, e));
+        let listener = std::net::TcpListener::bind(addr).map_err(bind_error)?;
+        listener.set_nonblocking(true).map_err(bind_error)?;
+
+        let state = Arc::new(ApiState::new(bridge.cmd_tx.clone()));
+        let scans = state.scans.clone();
+        let ui_rx = bridge.ui_rx.clone();
+        thread::spawn(move || {
+            while let Ok(msg) = ui_rx.recv() {
+                if let Ok(mut table) = scans.lock() {
+                    table.apply(msg);
+                }
+            }
+        });
+        Ok(Self {
+            listener,
+            state,
+            _bridge: bridge,
+        })
+    }
+
+    /// The bound address, e.g. to find the port picked for `:0`.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.listener.local_addr().ok()
+    }
+
+    /// Serves requests on the current Tokio runtime until it shuts down.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if the listener cannot be registered
+    /// with the runtime.
+    pub async fn serve(self) -> Result<(), GError> {
+        let listener = TcpListener::from_std(self.listener)
+            .map_err(|e| GError::Internal(format!("Failed to start API server: {}", e)))?;
+        loop {
+            match listener.accept().await {
+                Ok((stream, peer)) => {
+                    let state = self.state.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = handle_connection(&state, stream).await {
+                            log::debug!("API request from {} failed: {}", peer, e);
+                        }
+                    });
+                }
+                Err(e) => log::warn!("API accept failed: {}", e),
+            }
+        }
+    }
+
+    /// Serves from a background thread with its own runtime, for frontends
+    /// such as the GUI that do not run Tokio themselves.
+    pub fn spawn(self) {
+        thread::spawn(move || {
+            let rt = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(rt) => rt,
+                Err(e) => {
+                    log::error!("Failed to create API runtime: {}", e);
+                    return;
+                }
+            };
+            if let Err(e) = rt.block_on(self.serve()) {
+                log::error!("{}", e);
+            }
+        });
+    }
+}
+
+/// Reads one request, routes it and writes the response.
+async fn handle_connection(state: &ApiState, mut stream: TcpStream) -> std::io::Result<()> {
+    let response = match tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream)).await {
+        Ok(Ok(Some((method, path, body)))) => route(state, &method, &path, &body).await,
+        Ok(Ok(None)) => Response::error(400, "Malformed HTTP request"),
+        Ok(Err(e)) => return Err(e),
+        Err(_) => return Ok(()),
+    };
+    stream.write_all(response.to_http().as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Reads the request line, headers and `Content-Length` body.
+///
+/// Returns `None` for anything that is not a well-formed request within
+/// [`MAX_REQUEST`] bytes.
+async fn read_request(
+    stream: &mut TcpStream,
+) -> std::io::Result<Option<(String, String, Vec<u8>)>> {
+    let mut raw = Vec::new();
+    let mut buf = [0u8; 4096];
+    let head_end = loop {
+        if let Some(pos) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if raw.len() > MAX_REQUEST {
+            return Ok(None);
+        }
+        match stream.read(&mut buf).await? {
+            0 => return Ok(None),
+            n => raw.extend_from_slice(&buf[..n]),
+        }
+    };
+    let Some((method, path, content_length)) = parse_head(&raw[..head_end]) else {
+        return Ok(None);
+    };
+    // Content-Length is client-supplied; compare without adding so a huge
+    // value cannot wrap past the limit.
+    if content_length > MAX_REQUEST.saturating_sub(head_end) {
+        return Ok(None);
+    }
+    let mut body = raw.split_off(head_end);
+    while body.len() < content_length {
+        match stream.read(&mut buf).await? {
+            0 => return Ok(None),
+            n => body.extend_from_slice(&buf[..n]),
+        }
+    }
+    body.truncate(content_length);
+    Ok(Some((method, path, body)))
+}
+
+/// Parses the method, path (without query) and `Content-Length` of a request head.
+fn parse_head(head: &[u8]) -> Option<(String, String, usize)> {
+    let head = std::str::from_utf8(head).ok()?;
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_ascii_uppercase();
+    let target = request_line.next()?;
+    let path = target.split('?').next().unwrap_or(target).to_string();
+    let mut content_length = 0;
+    for line in lines {
+        if let Some((name, value)) = line.split_once(':')
+            && name.trim().eq_ignore_ascii_case("content-length")
+        {
+            content_length = value.trim().parse().ok()?;
+        }
+    }
+    Some((method, path, content_length))
+}
+
+/// Dispatches a request to its endpoint.
+async fn route(state: &ApiState, method: &str, path: &str, body: &[u8]) -> Response {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let id = match segments.as_slice() {
+        ["scans"] if method == "POST" => return start_scan(state, body).await,
+        ["scans"] => return Response::error(405, "Use POST to start a scan"),
+        ["scans", id, ..] => match id.parse::<u64>() {
+            Ok(id) => id,
+            Err(_) => return Response::error(404, "No such scan"),
+        },
+        _ => return Response::error(404, "No such endpoint"),
+    };
+    match (method, &segments[2..]) {
+        ("GET", []) => scan_status(state, id),
+        ("DELETE", []) => cancel_scan(state, id).await,
+        ("GET", ["results"]) => scan_results(state, id),
+        (_, [] | ["results"]) => Response::error(405, "Method not allowed here"),
+        _ => Response::error(404, "No such endpoint"),
+    }
+}
+
+async fn start_scan(state: &ApiState, body: &[u8]) -> Response {
+    let request: StartRequest = match serde_json::from_slice(body) {
+        Ok(request) => request,
+        Err(e) => return Response::error(400, &format!("Invalid scan request: {}", e)),
+    };
+    let config = match request.profile.config_with(request.config) {
+        Ok(config) => config,
+        Err(e) => return Response::error(400, &format!("Invalid scan config: {}", e)),
+    };
+    // Registered before the request is sent, so no early message is missed.
+    let id = match state.lock() {
+        Ok(mut table) => table.create(),
+        Err(response) => return response,
+    };
+    let start = BridgeMessage::StartTaggedScan {
+        id,
+        request: Box::new(BridgeMessage::StartScanWithConfig {
+            range: request.range,
+            config: Box::new(config),
+        }),
+    };
+    if state.cmd_tx.send(start).await.is_err() {
+        if let Ok(mut table) = state.lock() {
+            table.scans.remove(&id.0);
+        }
+        return Response::error(503, "Scanner is not running");
+    }
+    scan_status(state, id.0).with_status(201)
+}
+
+fn scan_status(state: &ApiState, id: u64) -> Response {
+    let status = match state.lock() {
+        Ok(table) => table.status(id),
+        Err(response) => return response,
+    };
+    match status {
+        Some(status) => Response::json(200, &status),
+        None => Response::error(404, "No such scan"),
+    }
+}
+
+fn scan_results(state: &ApiState, id: u64) -> Response {
+    let results = match state.lock() {
+        Ok(table) => table.scans.get(&id).map(|scan| scan.results.clone()),
+        Err(response) => return response,
+    };
+    let Some(results) = results else {
+        return Response::error(404, "No such scan");
+    };
+    match export::render(&results, OutputFormat::Json) {
+        Ok(body) => Response { status: 200, body },
+        Err(e) => Response::error(500, &e.to_string()),
+    }
+}
+
+async fn cancel_scan(state: &ApiState, id: u64) -> Response {
+    let status = match state.lock() {
+        Ok(table) => table.status(id),
+        Err(response) => return response,
+    };
+    match status {
+        None => Response::error(404, "No such scan"),
+        Some(status) if status.state != ApiScanState::Running => {
+            Response::error(409, &format!("Scan {} has already ended", id))
+        }
+        Some(status) => {
+            if state
+                .cmd_tx
+                .send(BridgeMessage::StopTaggedScan(ScanId(id)))
+                .await
+                .is_err()
+            {
+                return Response::error(503, "Scanner is not running");
+            }
+            Response::json(202, &status)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ScanConfig, ScanStatus};
+    use std::net::Ipv4Addr;
+    use tokio::sync::mpsc::channel;
+
+    #[tokio::test]
+    async fn test_scan_lifecycle() {
+        let (cmd_tx, mut cmd_rx) = channel(8);
+        let state = ApiState::new(cmd_tx);
+
+        let body = br#"{"range": "10.0.0.0/30", "config": {"ports": [22], "tls_audit": true}}"#;
+        let created = route(&state, "POST", "/scans", body).await;
+        assert_eq!(created.status, 201, "{}", created.body);
+        let Some(BridgeMessage::StartTaggedScan { id, request }) = cmd_rx.recv().await else {
+            panic!("no scan started");
+        };
+        assert_eq!(id, ScanId(1));
+        let BridgeMessage::StartScanWithConfig { range, config } = *request else {
+            panic!("unexpected request");
+        };
+        assert_eq!(range, "10.0.0.0/30");
+        assert_eq!(config.ports, [22]);
+        assert!(config.tls_audit);
+        assert_eq!(config.concurrency, ScanConfig::default().concurrency);
+
+        let mut online = ScanResult::new(Ipv4Addr::new(10, 0, 0, 1));
+        online.status = ScanStatus::Online;
+        let tagged = |msg| BridgeMessage::Tagged(ScanId(1), Box::new(msg));
+        {
+            let mut table = state.scans.lock().unwrap();
+            table.apply(tagged(BridgeMessage::ScanUpdate(online)));
+            table.apply(tagged(BridgeMessage::Progress(50)));
+            table.apply(tagged(BridgeMessage::Phase(PhaseProgress {
+                hosts_discovered: 2,
+                hosts_total: 2,
+                ports_probed: 0,
+                ports_total: Some(10),
+            })));
+            // Untagged and unknown scans are not ours.
+            table.apply(BridgeMessage::ScanComplete);
+            table.apply(BridgeMessage::Tagged(
+                ScanId(9),
+                Box::new(BridgeMessage::ScanComplete),
+            ));
+        }
+        let status = route(&state, "GET", "/scans/1", b"").await;
+        assert_eq!(status.status, 200);
+        assert!(status.body.contains(r#""state": "running""#));
+        assert!(status.body.contains(r#""progress": 50"#));
+        assert!(status.body.contains(r#""ports_total": 10"#));
+        assert!(!status.body.contains("ranges"));
+        let results = route(&state, "GET", "/scans/1/results", b"").await;
+        let parsed: Vec<ScanResult> = serde_json::from_str(&results.body).unwrap();
+        assert_eq!(parsed.len(), 1);
+
+        assert_eq!(route(&state, "DELETE", "/scans/1", b"").await.status, 202);
+        assert!(matches!(
+            cmd_rx.recv().await,
+            Some(BridgeMessage::StopTaggedScan(ScanId(1)))
+        ));
+        state
+            .scans
+            .lock()
+            .unwrap()
+            .apply(tagged(BridgeMessage::ScanCancelled));
+        assert_eq!(route(&state, "DELETE", "/scans/1", b"").await.status, 409);
+    }
+
+    #[tokio::test]
+    async fn test_bad_requests() {
+        let (cmd_tx, _cmd_rx) = channel(8);
+        let state = ApiState::new(cmd_tx);
+        assert_eq!(route(&state, "POST", "/scans", b"{}").await.status, 400);
+        let bad_config = br#"{"range": "10.0.0.1", "config": {"ports": "all"}}"#;
+        assert_eq!(
+            route(&state, "POST", "/scans", bad_config).await.status,
+            400
+        );
+        let bad_profile = br#"{"range": "10.0.0.1", "profile": "turbo"}"#;
+        assert_eq!(
+            route(&state, "POST", "/scans", bad_profile).await.status,
+            400
+        );
+        assert_eq!(route(&state, "GET", "/scans", b"").await.status, 405);
+        assert_eq!(route(&state, "GET", "/scans/7", b"").await.status, 404);
+        assert_eq!(route(&state, "PUT", "/scans/7", b"").await.status, 405);
+        assert_eq!(route(&state, "GET", "/hosts", b"").await.status, 404);
+
+        let head = b"post /scans?x=1 HTTP/1.1\r\nHost: a\r\ncontent-length: 12\r\n\r\n";
+        assert_eq!(
+            parse_head(head),
+            Some(("POST".to_string(), "/scans".to_string(), 12))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_oversized_content_length_is_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (mut server, _) = listener.accept().await.unwrap();
+        client
+            .write_all(b"POST /scans HTTP/1.1\r\nContent-Length: 18446744073709551615\r\n\r\n")
+            .await
+            .unwrap();
+        let read = tokio::time::timeout(Duration::from_secs(5), read_request(&mut server)).await;
+        assert!(matches!(read, Ok(Ok(None))));
+    }
+
+    #[test]
+    fn test_scan_config_overlays_the_profile() {
+        let overrides = serde_json::from_str(r#"{"concurrency": 2}"#).unwrap();
+        let config = ScanProfile::Safe.config_with(Some(overrides)).unwrap();
+        assert_eq!(config.concurrency, 2);
+        assert!(config.icmp_only);
+        assert_eq!(
+            ScanProfile::Standard.config_with(None).unwrap(),
+            ScanConfig::default()
+        );
+    }
+
+    #[test]
+    fn test_finished_scans_are_pruned() {
+        let mut table = ScanTable::default();
+        for _ in 0..MAX_FINISHED_SCANS + 5 {
+            let id = table.create();
+            table.apply(BridgeMessage::Tagged(
+                id,
+                Box::new(BridgeMessage::ScanComplete),
+            ));
+        }
+        let running = table.create();
+        assert_eq!(table.scans.len(), MAX_FINISHED_SCANS + 1);
+        assert!(table.status(running.0).is_some());
+        assert!(table.status(1).is_none());
+    }
+}
@@ -0,0 +1,1163 @@
+//! Headless scanner for scripts and scheduled jobs.
+//!
+//! Scans one or more ranges, prints the results to stdout and exits with:
+//! - `0` if at least one host is online,
+//! - `1` if the scan finished but found no hosts,
+//! - `2` on invalid arguments or a scan error.
+//!
+//! With `--health <config>` it instead watches the hosts in the config,
+//! printing outages and recoveries until interrupted with Ctrl+C. With
+//! `--serve [addr]` it runs the REST API until interrupted, and with
+//! `--prune-history` / `--compact-history` it maintains the history database.
+//! `--update-oui <path>` downloads the current vendor registry, and `--rpc`
+//! takes JSON-RPC requests on stdin (see [`ragescanner::rpc`]).
+//! `--matrix <hosts>` prints which of the hosts accept connections on which
+//! of `--ports` (see [`ragescanner::reach`]), and `--arp-watch` reports
+//! hosts as they appear in the ARP cache, without probing, until Ctrl+C.
+//!
+//! `ragescanner-cli run <definition>` runs a scan described in a file (see
+//! [`ragescanner::definition`]), writes its exports and exits with `1` if
+//! any of its alert rules fired.
+
+use clap::{Parser, Subcommand};
+use futures::StreamExt;
+use ragescanner::agent::{
+    self, AGENT_IDENTITY_PASSWORD_ENV, AGENT_TOKEN_ENV, AgentAddress, AgentServer, RemoteAgent,
+};
+use ragescanner::bridge::Bridge;
+use ragescanner::conflict::{
+    ConflictWatch, DEFAULT_CONFLICT_WATCH_INTERVAL, MIN_CONFLICT_WATCH_INTERVAL,
+};
+use ragescanner::definition::ScanDefinition;
+use ragescanner::devices::{self, DEFAULT_DEVICES_PATH};
+use ragescanner::export::{self, OutputFormat, TableColumn};
+use ragescanner::health::{self, HealthConfig};
+use ragescanner::inventory;
+use ragescanner::monitor;
+use ragescanner::net::{self, NetUtils, NetworkProvider};
+use ragescanner::oui;
+use ragescanner::passive::{ArpWatch, MIN_ARP_WATCH_INTERVAL};
+use ragescanner::reach::{self, DEFAULT_MATRIX_CONCURRENCY};
+use ragescanner::report;
+use ragescanner::rpc;
+use ragescanner::scanner::Scanner;
+use ragescanner::session::ResultStream;
+use ragescanner::settings::Settings;
+use ragescanner::targets::ScanTargets;
+use ragescanner::types::{
+    BridgeMessage, DEFAULT_CONCURRENCY, DEFAULT_DNS_TIMEOUT, DEFAULT_PORT_CONCURRENCY,
+    DEFAULT_RETRY_DELAY, DnsConfig, GError, ScanConfig, ScanLabel, ScanOrder, ScanProfile,
+    ScanStatus, Socks5Config, TcpProbeOptions, set_port_labels,
+};
+use ragescanner::web;
+use ragescanner::wol::{self, WakeTarget};
+use std::collections::BTreeSet;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio_util::sync::CancellationToken;
+
+/// Scan an IPv4 range without the GUI.
+#[derive(Parser, Debug)]
+#[command(
+    name = "ragescanner-cli",
+    version,
+    about,
+    subcommand_negates_reqs = true
+)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Ranges to scan, comma-separated, e.g. 192.168.1.1-254 or 10.0.1.0/24,10.0.5.1-50
+    #[arg(
+        short,
+        long,
+        required_unless_present_any = ["file", "health", "serve", "prune_history", "compact_history", "update_oui", "rpc", "wake", "agent_listen", "matrix", "arp_watch", "conflict_watch"],
+        conflicts_with = "file"
+    )]
+    range: Option<String>,
+
+    /// File of targets to scan, one or more ranges per line (`#` starts a comment)
+    #[arg(short, long)]
+    file: Option<PathBuf>,
+
+    /// Health check config (JSON): ping its hosts on an interval and raise
+    /// alarms after consecutive misses, instead of scanning
+    #[arg(long, value_name = "CONFIG", conflicts_with_all = ["range", "file"])]
+    health: Option<PathBuf>,
+
+    /// Serve the REST API (`api` feature) instead of scanning: POST /scans,
+    /// GET /scans/{id}[/results], DELETE /scans/{id} [default: 127.0.0.1:8787]
+    #[arg(
+        long,
+        value_name = "ADDR",
+        num_args = 0..=1,
+        default_missing_value = "127.0.0.1:8787",
+        conflicts_with_all = ["range", "file", "health"]
+    )]
+    serve: Option<SocketAddr>,
+
+    /// Delete old scans from the history database (`history` feature) instead
+    /// of scanning; limits come from --keep-scans/--keep-days or the
+    /// RAGESCANNER_HISTORY_KEEP_SCANS/_DAYS variables
+    #[arg(long, conflicts_with_all = ["range", "file", "health", "serve"])]
+    prune_history: bool,
+
+    /// With --prune-history: keep only the newest N scans
+    #[arg(long, value_name = "N", requires = "prune_history")]
+    keep_scans: Option<u32>,
+
+    /// With --prune-history: drop scans older than DAYS days
+    #[arg(long, value_name = "DAYS", requires = "prune_history")]
+    keep_days: Option<u32>,
+
+    /// Compact the history database to release the space of deleted scans
+    #[arg(long, conflicts_with_all = ["range", "file", "health", "serve"])]
+    compact_history: bool,
+
+    /// Download the current IEEE OUI registry to PATH instead of scanning;
+    /// use it with --oui-db PATH
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["range", "file", "health", "serve"])]
+    update_oui: Option<PathBuf>,
+
+    /// Read JSON-RPC requests from stdin, one per line, and write responses
+    /// and scan events to stdout as newline-delimited JSON, until stdin closes
+    #[arg(long, conflicts_with_all = ["range", "file", "health", "serve", "update_oui"])]
+    rpc: bool,
+
+    /// Wake a `wake_groups` entry of config.toml, or comma-separated MACs,
+    /// instead of scanning; packets go out 500 ms apart and the hosts are
+    /// pinged until they answer. Exit code 0 if all came online, 1 if not
+    #[arg(
+        long,
+        value_name = "GROUP|MACS",
+        conflicts_with_all = ["range", "file", "health", "serve", "update_oui", "rpc"]
+    )]
+    wake: Option<String>,
+
+    /// With --wake: seconds to wait for the hosts to answer
+    #[arg(long, value_name = "SECS", default_value_t = 120, requires = "wake")]
+    wake_timeout: u64,
+
+    /// Test which of these hosts (names, addresses or ranges, comma-separated)
+    /// accept TCP connections on --ports, instead of scanning, and print a
+    /// host × port matrix. Exit code 0 if every host took every port, 1 if not
+    #[arg(
+        long,
+        value_name = "HOSTS",
+        conflicts_with_all = ["range", "file", "health", "serve", "update_oui", "rpc", "wake"]
+    )]
+    matrix: Option<String>,
+
+    /// Read the ARP cache every SECS seconds and print hosts as they appear
+    /// in it, sending no probes, until Ctrl+C; --range limits the addresses
+    /// reported [default: 10]
+    #[arg(
+        long,
+        value_name = "SECS",
+        num_args = 0..=1,
+        default_missing_value = "10",
+        conflicts_with_all = ["file", "health", "serve", "update_oui", "rpc", "wake", "matrix", "agent_listen"]
+    )]
+    arp_watch: Option<u64>,
+
+    /// ARP this address over and over and print a line whenever a different
+    /// MAC answers, until Ctrl+C, to catch intermittent IP conflicts. Exit
+    /// code 1 if more than one MAC answered
+    #[arg(
+        long,
+        value_name = "IP",
+        conflicts_with_all = ["range", "file", "health", "serve", "update_oui", "rpc", "wake", "matrix", "arp_watch", "agent_listen"]
+    )]
+    conflict_watch: Option<Ipv4Addr>,
+
+    /// With --conflict-watch: milliseconds between two ARP requests
+    #[arg(
+        long,
+        value_name = "MS",
+        default_value_t = DEFAULT_CONFLICT_WATCH_INTERVAL.as_millis() as u64,
+        requires = "conflict_watch"
+    )]
+    conflict_interval: u64,
+
+    /// Run as a remote agent instead of scanning: answer the probes of
+    /// scanners connecting with --agent and the RAGESCANNER_AGENT_TOKEN
+    /// token, over TLS or a `\\.\pipe\NAME` named pipe [default: 0.0.0.0:8788]
+    #[arg(
+        long,
+        value_name = "ADDR",
+        num_args = 0..=1,
+        default_missing_value = "0.0.0.0:8788",
+        conflicts_with_all = ["range", "file", "health", "serve", "update_oui", "rpc", "wake", "agent"]
+    )]
+    agent_listen: Option<AgentAddress>,
+
+    /// With --agent-listen on TCP: PKCS#12 file with the agent's certificate
+    /// and key; its password comes from RAGESCANNER_AGENT_IDENTITY_PASSWORD
+    #[arg(long, value_name = "P12", requires = "agent_listen")]
+    agent_identity: Option<PathBuf>,
+
+    /// Scan through the remote agent at ADDR (`host[:port]` or a named pipe),
+    /// authenticating with the RAGESCANNER_AGENT_TOKEN token
+    #[arg(long, value_name = "ADDR")]
+    agent: Option<AgentAddress>,
+
+    /// With --agent: PEM certificate to trust for the agent, e.g. a self-signed one
+    #[arg(long, value_name = "PEM", requires = "agent")]
+    agent_ca: Option<PathBuf>,
+
+    /// Start from a preset instead of the tuning flags: `safe` pings one
+    /// host at a time at 5 packets/s with no port, UDP or name probes, for
+    /// fragile OT/ICS networks
+    #[arg(
+        long,
+        value_name = "PROFILE",
+        conflicts_with_all = [
+            "ports", "timeout", "concurrency", "port_concurrency", "retries", "retry_delay",
+            "max_rate", "adaptive_rate", "smb1_audit", "tls_audit", "mdns", "ssdp", "dhcp",
+            "snmp",
+        ]
+    )]
+    profile: Option<ScanProfile>,
+
+    /// TCP ports to probe on online hosts, e.g. 22,80,8000-8100 [default: common ports]
+    #[arg(short, long)]
+    ports: Option<String>,
+
+    /// TCP connect timeout in milliseconds [default: adaptive, from ping latency]
+    #[arg(short, long)]
+    timeout: Option<u64>,
+
+    /// Number of hosts probed at once
+    #[arg(short, long, default_value_t = DEFAULT_CONCURRENCY)]
+    concurrency: usize,
+
+    /// Number of TCP ports probed at once on each host
+    #[arg(long, default_value_t = DEFAULT_PORT_CONCURRENCY)]
+    port_concurrency: usize,
+
+    /// Most TCP connections open at once to any one host, counting port
+    /// probes, banner grabs and service checks, for fragile devices
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    max_host_connections: Option<u16>,
+
+    /// Extra ping and ARP attempts before a host is reported offline
+    #[arg(long, default_value_t = 0)]
+    retries: u32,
+
+    /// Pause before each retry in milliseconds, jittered by ±50%
+    #[arg(long, value_name = "MS", default_value_t = DEFAULT_RETRY_DELAY.as_millis() as u64)]
+    retry_delay: u64,
+
+    /// Check hosts with port 445 open for SMBv1 and warn about those accepting it
+    #[arg(long)]
+    smb1_audit: bool,
+
+    /// Check open TLS ports for TLS 1.0/1.1 and certificates expiring within 30 days
+    #[arg(long)]
+    tls_audit: bool,
+
+    /// Maximum packets per second across the scan [default: unlimited]
+    #[arg(long, value_name = "PPS", value_parser = clap::value_parser!(u32).range(1..))]
+    max_rate: Option<u32>,
+
+    /// Halve the rate when echo timeouts spike, recovering once they settle
+    #[arg(long, requires = "max_rate")]
+    adaptive_rate: bool,
+
+    /// Most probe packets (ICMP, ARP, TCP and UDP) the scan may send; hosts
+    /// left once it is used up are reported as not scanned
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    probe_budget: Option<u64>,
+
+    /// Cap on the estimated probe bandwidth in kilobits per second
+    #[arg(long, value_name = "KBPS", value_parser = clap::value_parser!(u32).range(1..))]
+    max_bandwidth: Option<u32>,
+
+    /// Send TCP port probes from this local port
+    #[arg(long, value_name = "PORT", value_parser = clap::value_parser!(u16).range(1..))]
+    source_port: Option<u16>,
+
+    /// IP TTL of TCP port probes
+    #[arg(long, value_name = "TTL", value_parser = clap::value_parser!(u8).range(1..))]
+    probe_ttl: Option<u8>,
+
+    /// Turn TCP keepalive off on port probes
+    #[arg(long)]
+    no_keepalive: bool,
+
+    /// DNS servers for reverse lookups, comma-separated [default: the system's]
+    #[arg(long, value_name = "IP,...", value_delimiter = ',')]
+    dns_server: Vec<IpAddr>,
+
+    /// Time to wait for a DNS answer, in milliseconds
+    #[arg(long, value_name = "MS", default_value_t = DEFAULT_DNS_TIMEOUT.as_millis() as u64)]
+    dns_timeout: u64,
+
+    /// Look reverse DNS names up again and flag those that do not resolve
+    /// back to their host
+    #[arg(long)]
+    dns_verify: bool,
+
+    /// Send TCP port probes and banner grabs through this SOCKS5 proxy;
+    /// ping, ARP and UDP probes are skipped. A user without a password
+    /// reads it from RAGESCANNER_PROXY_PASSWORD
+    #[arg(long, value_name = "[socks5://][USER[:PASS]@]HOST:PORT")]
+    proxy: Option<Socks5Config>,
+
+    /// Probe order of hosts and ports: sequential or random
+    #[arg(long, default_value = "sequential")]
+    order: ScanOrder,
+
+    /// Seed of a random order, to repeat an earlier scan's order
+    #[arg(long, value_name = "N")]
+    seed: Option<u64>,
+
+    /// Wait up to this many milliseconds, at random, before each probe
+    #[arg(long, value_name = "MS")]
+    probe_jitter: Option<u64>,
+
+    /// Output format: json, csv, xml (nmap) or table
+    #[arg(short, long, default_value = "table")]
+    output: OutputFormat,
+
+    /// Columns of table output, in order, from ip, status, hostname, mac,
+    /// vendor, ports, latency, issues, asset_tag, owner, location, name and roles [default: ip,status,hostname,mac,ports,issues]
+    #[arg(long, value_name = "COLUMNS")]
+    columns: Option<String>,
+
+    /// Browse mDNS/Bonjour first; hosts that answer count as online
+    #[arg(long)]
+    mdns: bool,
+
+    /// Search for UPnP devices over SSDP first and read their descriptions
+    #[arg(long)]
+    ssdp: bool,
+
+    /// Send a DHCPINFORM first to find the DHCP servers; gateways and DNS
+    /// servers are always tagged from the adapter configuration
+    #[arg(long)]
+    dhcp: bool,
+
+    /// Query the SNMP system group of online hosts, with the given community (default: public)
+    #[arg(long, value_name = "COMMUNITY", num_args = 0..=1, default_missing_value = ragescanner::snmp::DEFAULT_COMMUNITY)]
+    snmp: Option<String>,
+
+    /// Name for this scan, printed at the top of the output
+    #[arg(long, value_name = "NAME")]
+    label: Option<String>,
+
+    /// Notes for this scan, printed below the label
+    #[arg(long, value_name = "TEXT", requires = "label")]
+    description: Option<String>,
+
+    /// Also save the scan as a standalone HTML report
+    #[arg(long, value_name = "PATH")]
+    report: Option<PathBuf>,
+
+    /// Append each result to an NDJSON file as its host finishes, so an
+    /// interrupted scan keeps what it found; ragescanner-view opens the file
+    #[arg(long, value_name = "PATH")]
+    stream: Option<PathBuf>,
+
+    /// Custom port labels shown instead of the built-in ones, e.g. 8006=Proxmox,32400=Plex
+    #[arg(long, value_name = "PORT=LABEL,...")]
+    labels: Option<String>,
+
+    /// OUI CSV used for vendor lookups instead of the bundled database
+    #[arg(long, value_name = "PATH")]
+    oui_db: Option<PathBuf>,
+
+    /// Asset inventory CSV keyed by MAC or IP; its asset tag, owner and
+    /// location columns are joined into the output
+    #[arg(long, value_name = "PATH")]
+    inventory: Option<PathBuf>,
+
+    /// Include offline hosts in the output
+    #[arg(short, long)]
+    all: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run a scan definition file (YAML, TOML or JSON): scan its targets
+    /// with its profile and probes, write its exports and check its alert
+    /// rules. Exit code 0 if no rule fired, 1 if one did
+    Run {
+        /// The definition, e.g. scans/office.yaml
+        definition: PathBuf,
+    },
+}
+
+const EXIT_NO_HOSTS: u8 = 1;
+/// `run`: at least one alert rule fired.
+const EXIT_ALERTS: u8 = 1;
+/// `--matrix`: a host refused or ignored a port, or did not resolve.
+const EXIT_UNREACHABLE: u8 = 1;
+/// `--conflict-watch`: more than one MAC answered for the address.
+const EXIT_CONFLICT: u8 = 1;
+const EXIT_ERROR: u8 = 2;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+
+    if let Some(Command::Run { definition }) = &args.command {
+        return run_definition(definition, &args).await;
+    }
+    if let Some(path) = &args.health {
+        return run_health_check(path).await;
+    }
+    if let Some(addr) = args.serve {
+        return run_api_server(addr).await;
+    }
+    if args.rpc {
+        let input = tokio::io::BufReader::new(tokio::io::stdin());
+        return match rpc::serve(Bridge::new(), input, tokio::io::stdout()).await {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                ExitCode::from(EXIT_ERROR)
+            }
+        };
+    }
+    if args.prune_history || args.compact_history {
+        return maintain_history(&args);
+    }
+    if let Some(spec) = &args.wake {
+        return run_wake(spec, Duration::from_secs(args.wake_timeout)).await;
+    }
+    if let Some(spec) = &args.matrix {
+        return run_matrix(spec, &args).await;
+    }
+    if let Some(secs) = args.arp_watch {
+        return run_arp_watch(Duration::from_secs(secs), &args).await;
+    }
+    if let Some(ip) = args.conflict_watch {
+        return run_conflict_watch(ip, Duration::from_millis(args.conflict_interval), &args).await;
+    }
+    if let Some(address) = &args.agent_listen {
+        return run_agent(address, args.agent_identity.as_deref()).await;
+    }
+    if let Some(path) = &args.update_oui {
+        return match oui::update(path).await {
+            Ok(records) => {
+                eprintln!("Saved {} OUI records to {}", records, path.display());
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("error: {}", e);
+                ExitCode::from(EXIT_ERROR)
+            }
+        };
+    }
+
+    let targets = match (&args.range, &args.file) {
+        (Some(spec), _) => ScanTargets::parse(spec),
+        (None, Some(path)) => ScanTargets::from_file(path).map_err(|e| e.to_string()),
+        (None, None) => unreachable!("clap requires --range, --file or --health"),
+    };
+    let targets = match targets {
+        Ok(targets) => targets,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+
+    if let Some(spec) = &args.labels {
+        match Bridge::parse_port_labels(spec) {
+            Ok(labels) => set_port_labels(labels),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return ExitCode::from(EXIT_ERROR);
+            }
+        }
+    }
+
+    let columns = match &args.columns {
+        Some(_) if args.output != OutputFormat::Table => {
+            eprintln!("error: --columns only applies to table output");
+            return ExitCode::from(EXIT_ERROR);
+        }
+        Some(spec) => match TableColumn::parse_list(spec) {
+            Ok(columns) => Some(columns),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return ExitCode::from(EXIT_ERROR);
+            }
+        },
+        None => None,
+    };
+
+    if let Some(path) = args.oui_db
+        && let Err(e) = net::set_oui_database(Some(path))
+    {
+        eprintln!("error: {}", e);
+        return ExitCode::from(EXIT_ERROR);
+    }
+    if let Some(path) = args.inventory
+        && let Err(e) = inventory::set_inventory(Some(path))
+    {
+        eprintln!("error: {}", e);
+        return ExitCode::from(EXIT_ERROR);
+    }
+    if let Err(e) = devices::open(Path::new(DEFAULT_DEVICES_PATH)) {
+        eprintln!("warning: {}; device names are not shown", e);
+    }
+
+    let proxy = args.proxy.map(|mut proxy| {
+        if proxy.username.is_some() && proxy.password.is_none() {
+            proxy.password = std::env::var(net::PROXY_PASSWORD_ENV).ok();
+        }
+        proxy
+    });
+    let mut config = ScanConfig {
+        concurrency: args.concurrency,
+        port_concurrency: args.port_concurrency,
+        max_host_connections: args.max_host_connections.map(usize::from),
+        port_timeout: args.timeout.map(Duration::from_millis),
+        retries: args.retries,
+        retry_delay: Duration::from_millis(args.retry_delay),
+        smb1_audit: args.smb1_audit,
+        tls_audit: args.tls_audit,
+        max_rate: args.max_rate,
+        adaptive_rate: args.adaptive_rate,
+        probe_budget: args.probe_budget,
+        max_bandwidth_kbps: args.max_bandwidth,
+        tcp_options: TcpProbeOptions {
+            source_port: args.source_port,
+            ttl: args.probe_ttl,
+            disable_keepalive: args.no_keepalive,
+        },
+        mdns_discovery: args.mdns,
+        ssdp_discovery: args.ssdp,
+        dhcp_discovery: args.dhcp,
+        snmp_community: args.snmp,
+        order: args.order,
+        order_seed: args.seed,
+        probe_jitter: args.probe_jitter.map(Duration::from_millis),
+        dns: DnsConfig {
+            servers: args.dns_server,
+            timeout: Duration::from_millis(args.dns_timeout),
+        },
+        verify_dns: args.dns_verify,
+        proxy,
+        label: args.label.map(|name| ScanLabel {
+            name,
+            description: args.description,
+        }),
+        ..ScanConfig::default()
+    };
+    if let Some(profile) = args.profile {
+        config = ScanConfig {
+            label: config.label,
+            tcp_options: config.tcp_options,
+            order: config.order,
+            order_seed: config.order_seed,
+            probe_jitter: config.probe_jitter,
+            dns: config.dns,
+            verify_dns: config.verify_dns,
+            proxy: config.proxy,
+            max_host_connections: config.max_host_connections,
+            probe_budget: config.probe_budget,
+            max_bandwidth_kbps: config.max_bandwidth_kbps,
+            ..profile.config()
+        };
+    }
+    if let Some(spec) = &args.ports {
+        match Bridge::parse_ports(spec) {
+            Ok(ports) => config.ports = ports,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return ExitCode::from(EXIT_ERROR);
+            }
+        }
+    }
+
+    let net = match scan_provider(args.agent.as_ref(), args.agent_ca.as_deref()).await {
+        Ok(net) => net,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+
+    let mut stream = match args.stream.as_deref().map(ResultStream::create) {
+        Some(Ok(stream)) => Some(stream),
+        Some(Err(e)) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+        None => None,
+    };
+
+    let label = config.label.clone();
+    let started = SystemTime::now();
+    let mut session = Scanner::start(net, targets, config);
+    let mut results = Vec::new();
+    let mut updates = session.results();
+    while let Some(res) = updates.next().await {
+        if let Some(out) = &mut stream
+            && let Err(e) = out.append(&res)
+        {
+            eprintln!("warning: {}; no longer streaming results", e);
+            stream = None;
+        }
+        results.push(res);
+    }
+    drop(updates);
+    let summary = session.summary();
+    if let Err(e) = session.await {
+        print_error(&e);
+        return ExitCode::from(EXIT_ERROR);
+    }
+
+    results.sort_by_key(|r| r.ip);
+    let online = results
+        .iter()
+        .filter(|r| r.status == ScanStatus::Online)
+        .count();
+    let mut hints = BTreeSet::new();
+    for res in &results {
+        if let ScanStatus::SystemError(e) = &res.status {
+            eprintln!("warning: {}: {}", res.ip, e);
+            hints.extend(e.hint());
+        }
+        for warning in &res.warnings {
+            eprintln!("warning: {}: {}", res.ip, warning);
+            hints.extend(warning.hint());
+        }
+        for issue in &res.issues {
+            eprintln!("warning: {}: {}", res.ip, issue);
+        }
+    }
+    // Once per kind of error, however many hosts hit it.
+    for hint in hints {
+        eprintln!("hint: {}", hint);
+    }
+    if let Some(warning) = web::detect_interception(&results) {
+        eprintln!("warning: {}", warning);
+    }
+    if let Some(summary) = &summary
+        && (args.probe_budget.is_some() || args.max_bandwidth.is_some())
+    {
+        eprintln!("Probe traffic: {}", summary.traffic);
+        if summary.budget_exhausted {
+            eprintln!("warning: probe budget used up; remaining hosts were not scanned");
+        }
+    }
+    if let Some(path) = &args.report
+        && let Err(e) = report::write_scan_report(
+            path,
+            &results,
+            summary.as_ref(),
+            label.as_ref(),
+            Some(started),
+        )
+    {
+        eprintln!("error: {}", e);
+        return ExitCode::from(EXIT_ERROR);
+    }
+    if !args.all {
+        results.retain(|r| r.status == ScanStatus::Online);
+    }
+
+    let rendered = match &columns {
+        Some(columns) => Ok(export::render_table_labeled(
+            &results,
+            columns,
+            label.as_ref(),
+        )),
+        None => export::render_labeled(&results, args.output, label.as_ref()),
+    };
+    match rendered {
+        Ok(text) => print!("{}", text),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    }
+
+    if online > 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(EXIT_NO_HOSTS)
+    }
+}
+
+/// Serves the REST API on `addr` until Ctrl+C.
+#[cfg(feature = "api")]
+async fn run_api_server(addr: SocketAddr) -> ExitCode {
+    let server = match ragescanner::api::ApiServer::bind(addr, Bridge::new()) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+    eprintln!(
+        "Serving the scan API on http://{}. Ctrl+C to stop.",
+        server.local_addr().unwrap_or(addr)
+    );
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => ExitCode::SUCCESS,
+        result = server.serve() => match result {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                ExitCode::from(EXIT_ERROR)
+            }
+        },
+    }
+}
+
+#[cfg(not(feature = "api"))]
+async fn run_api_server(_addr: SocketAddr) -> ExitCode {
+    eprintln!("error: --serve needs a build with the `api` feature");
+    ExitCode::from(EXIT_ERROR)
+}
+
+/// Prunes and/or compacts the history database.
+#[cfg(feature = "history")]
+fn maintain_history(args: &Args) -> ExitCode {
+    use ragescanner::history::{
+        DEFAULT_HISTORY_PATH, History, KEEP_DAYS_VAR, KEEP_SCANS_VAR, RetentionPolicy,
+    };
+
+    let mut history = match History::open(Path::new(DEFAULT_HISTORY_PATH)) {
+        Ok(history) => history,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+    if args.prune_history {
+        let env = RetentionPolicy::from_env();
+        let policy = RetentionPolicy {
+            keep_scans: args.keep_scans.or(env.keep_scans),
+            keep_days: args.keep_days.or(env.keep_days),
+        };
+        if policy.is_unlimited() {
+            eprintln!(
+                "error: --prune-history needs --keep-scans, --keep-days, {} or {}",
+                KEEP_SCANS_VAR, KEEP_DAYS_VAR
+            );
+            return ExitCode::from(EXIT_ERROR);
+        }
+        match history.prune(&policy) {
+            Ok(removed) => eprintln!("Pruned {} scans from {}", removed, DEFAULT_HISTORY_PATH),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return ExitCode::from(EXIT_ERROR);
+            }
+        }
+    }
+    if args.compact_history {
+        if let Err(e) = history.compact() {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+        eprintln!("Compacted {}", DEFAULT_HISTORY_PATH);
+    }
+    ExitCode::SUCCESS
+}
+
+#[cfg(not(feature = "history"))]
+fn maintain_history(_args: &Args) -> ExitCode {
+    eprintln!("error: history maintenance needs a build with the `history` feature");
+    ExitCode::from(EXIT_ERROR)
+}
+
+/// Wakes a named group from the saved settings, or a comma-separated list of
+/// MACs, and prints which hosts came online.
+/// Prints `error: <e>`, then a `hint:` line if the error has a likely fix.
+fn print_error(e: &GError) {
+    eprintln!("error: {}", e);
+    if let Some(hint) = e.hint() {
+        eprintln!("hint: {}", hint);
+    }
+}
+
+/// The remote agent given with `--agent`, else the local network stack.
+async fn scan_provider(
+    agent: Option<&AgentAddress>,
+    ca_cert: Option<&Path>,
+) -> Result<Arc<dyn NetworkProvider>, String> {
+    Ok(match agent {
+        Some(address) => Arc::new(connect_agent(address, ca_cert).await?),
+        None => Arc::new(NetUtils::new()),
+    })
+}
+
+/// Runs a scan definition: scans, prints the online hosts, writes the
+/// exports and reports the alerts that fired.
+async fn run_definition(path: &Path, args: &Args) -> ExitCode {
+    let definition = match ScanDefinition::from_file(path) {
+        Ok(definition) => definition,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+    let (targets, config) = match definition
+        .targets()
+        .and_then(|targets| definition.scan_config().map(|config| (targets, config)))
+    {
+        Ok(scan) => scan,
+        Err(e) => {
+            eprintln!("error: {}: {}", path.display(), e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+    let net = match scan_provider(args.agent.as_ref(), args.agent_ca.as_deref()).await {
+        Ok(net) => net,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+
+    let started = SystemTime::now();
+    let mut session = Scanner::start(net, targets, config);
+    let mut results: Vec<_> = session.results().collect().await;
+    let summary = session.summary();
+    if let Err(e) = session.await {
+        print_error(&e);
+        return ExitCode::from(EXIT_ERROR);
+    }
+    results.sort_by_key(|r| r.ip);
+
+    // Exports sit next to the definition, wherever it is run from.
+    let base = path.parent().unwrap_or(Path::new(""));
+    match definition.write_exports(base, &results, summary.as_ref(), Some(started)) {
+        Ok(written) => {
+            for path in written {
+                eprintln!("Wrote {}", path.display());
+            }
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    }
+    let alerts = definition.evaluate_alerts(&results);
+    for alert in &alerts {
+        eprintln!("alert: {}", alert);
+    }
+    if let Err(e) = definition.send_alerts(&alerts).await {
+        eprintln!("error: {}", e);
+        return ExitCode::from(EXIT_ERROR);
+    }
+
+    let online: Vec<_> = results
+        .into_iter()
+        .filter(|r| r.status == ScanStatus::Online)
+        .collect();
+    let table = export::render_table_labeled(
+        &online,
+        export::DEFAULT_TABLE_COLUMNS,
+        definition.label().as_ref(),
+    );
+    print!("{}", table);
+    if alerts.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(EXIT_ALERTS)
+    }
+}
+
+/// The agent token from [`AGENT_TOKEN_ENV`].
+fn agent_token() -> Result<String, String> {
+    std::env::var(AGENT_TOKEN_ENV)
+        .ok()
+        .filter(|token| !token.is_empty())
+        .ok_or_else(|| format!("set {} to the agent token", AGENT_TOKEN_ENV))
+}
+
+async fn connect_agent(
+    address: &AgentAddress,
+    ca_cert: Option<&Path>,
+) -> Result<RemoteAgent, String> {
+    let token = agent_token()?;
+    RemoteAgent::connect(address, &token, ca_cert)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn run_agent(address: &AgentAddress, identity: Option<&Path>) -> ExitCode {
+    let server = agent_token().and_then(|token| {
+        let identity = identity
+            .map(|path| {
+                let password = std::env::var(AGENT_IDENTITY_PASSWORD_ENV).unwrap_or_default();
+                agent::load_identity(path, &password)
+            })
+            .transpose()
+            .map_err(|e| e.to_string())?;
+        AgentServer::bind(address, identity, Arc::new(NetUtils::new()), &token)
+            .map_err(|e| e.to_string())
+    });
+    let server = match server {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+    match server.local_addr() {
+        Some(addr) => eprintln!("Agent listening on {}", addr),
+        None => eprintln!("Agent listening on {}", address),
+    }
+    match server.serve().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+async fn run_wake(spec: &str, timeout: Duration) -> ExitCode {
+    let settings = match Settings::default_path() {
+        Some(path) => Settings::load_or_default(&path),
+        None => Ok(Settings::default()),
+    };
+    let settings = match settings {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+    let mut targets = match settings.wake_group(spec) {
+        Some(targets) => targets,
+        None => {
+            let macs: Vec<&str> = spec.split(',').map(str::trim).collect();
+            if macs.iter().any(|mac| wol::parse_mac(mac).is_err()) {
+                eprintln!(
+                    "error: {} is neither a wake group in config.toml nor a list of MAC addresses",
+                    spec
+                );
+                return ExitCode::from(EXIT_ERROR);
+            }
+            macs.into_iter()
+                .map(|mac| WakeTarget {
+                    mac: mac.to_string(),
+                    ip: None,
+                })
+                .collect()
+        }
+    };
+
+    let net = NetUtils::new();
+    match net.read_arp_table() {
+        Ok(arp) => wol::fill_addresses(&mut targets, &arp),
+        Err(e) => eprintln!("warning: cannot read the ARP table: {}", e),
+    }
+    let report = wol::wake_batch(&net, targets, timeout, |target, sent| match sent {
+        Ok(()) => eprintln!("Sent magic packet to {}", target.mac),
+        Err(e) => eprintln!("error: {}: {}", target.mac, e),
+    })
+    .await;
+    println!("{}", report);
+    if report.online_count() == report.outcomes.len() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(EXIT_NO_HOSTS)
+    }
+}
+
+/// Tests `spec`'s hosts on `--ports` and prints the reachability matrix.
+async fn run_matrix(spec: &str, args: &Args) -> ExitCode {
+    let ports = match &args.ports {
+        Some(ports) => Bridge::parse_ports(ports),
+        None => Ok(ScanConfig::default().ports),
+    };
+    let ports = match ports {
+        Ok(ports) => ports,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+    let hosts = match reach::resolve_hosts(spec).await {
+        Ok(hosts) => hosts,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+    for host in hosts.iter().filter(|h| h.ip.is_none()) {
+        eprintln!("warning: cannot resolve {}", host.name);
+    }
+    let net = match scan_provider(args.agent.as_ref(), args.agent_ca.as_deref()).await {
+        Ok(net) => net,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+    let timeout = args
+        .timeout
+        .map_or(net::DEFAULT_PORT_TIMEOUT, Duration::from_millis);
+    let matrix = reach::probe(&*net, hosts, &ports, timeout, DEFAULT_MATRIX_CONCURRENCY).await;
+    match matrix.render(args.output) {
+        Ok(text) => print!("{}", text),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    }
+    if matrix.all_reachable() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(EXIT_UNREACHABLE)
+    }
+}
+
+/// Prints hosts appearing in the ARP cache, read every `interval`, until Ctrl+C.
+async fn run_arp_watch(interval: Duration, args: &Args) -> ExitCode {
+    let targets = match args.range.as_deref().map(ScanTargets::parse).transpose() {
+        Ok(targets) => targets,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+    let net = match scan_provider(args.agent.as_ref(), args.agent_ca.as_deref()).await {
+        Ok(net) => net,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+    let interval = interval.max(MIN_ARP_WATCH_INTERVAL);
+    eprintln!(
+        "Reading the ARP cache every {} s; hosts already in it form the baseline. Ctrl+C to stop.",
+        interval.as_secs()
+    );
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(64);
+    let cancel = CancellationToken::new();
+    let watch = tokio::spawn(ArpWatch::new(net, tx, targets).run(interval, cancel.clone()));
+
+    let mut code = ExitCode::SUCCESS;
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            msg = rx.recv() => match msg {
+                Some(BridgeMessage::Error(e)) => {
+                    print_error(&e);
+                    code = ExitCode::from(EXIT_ERROR);
+                    break;
+                }
+                Some(msg) => {
+                    if let Some(text) = monitor::describe_event(&msg) {
+                        println!("{}", text);
+                    }
+                }
+                None => break,
+            }
+        }
+    }
+    cancel.cancel();
+    let _ = watch.await;
+    code
+}
+
+/// ARPs `ip` every `interval` and prints each change of MAC until Ctrl+C.
+async fn run_conflict_watch(ip: Ipv4Addr, interval: Duration, args: &Args) -> ExitCode {
+    let net = match scan_provider(args.agent.as_ref(), args.agent_ca.as_deref()).await {
+        Ok(net) => net,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+    let interval = interval.max(MIN_CONFLICT_WATCH_INTERVAL);
+    eprintln!(
+        "ARPing {} every {} ms; Ctrl+C to stop.",
+        ip,
+        interval.as_millis()
+    );
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(64);
+    let cancel = CancellationToken::new();
+    let watch = tokio::spawn(ConflictWatch::new(net, tx, ip).run(interval, cancel.clone()));
+
+    let mut code = ExitCode::SUCCESS;
+    let mut answered = false;
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            msg = rx.recv() => match msg {
+                Some(BridgeMessage::Error(e)) => {
+                    print_error(&e);
+                    code = ExitCode::from(EXIT_ERROR);
+                    break;
+                }
+                Some(BridgeMessage::ScanUpdate(res)) if !answered => {
+                    answered = true;
+                    let mac = res.mac.unwrap_or_default();
+                    match res.vendor {
+                        Some(vendor) => println!("{} answered by {} ({})", ip, mac, vendor),
+                        None => println!("{} answered by {}", ip, mac),
+                    }
+                }
+                Some(msg) => {
+                    if let Some(text) = monitor::describe_event(&msg) {
+                        println!("{}", text);
+                        code = ExitCode::from(EXIT_CONFLICT);
+                    }
+                }
+                None => break,
+            }
+        }
+    }
+    cancel.cancel();
+    let _ = watch.await;
+    code
+}
+
+/// Watches the hosts in the health check config at `path` until Ctrl+C.
+async fn run_health_check(path: &Path) -> ExitCode {
+    let config = match HealthConfig::from_file(path) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+    eprintln!(
+        "Checking {} hosts every {} ms; alarm after {} missed echoes. Ctrl+C to stop.",
+        config.hosts.len(),
+        config.interval_ms,
+        config.misses
+    );
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(64);
+    let cancel = CancellationToken::new();
+    let checker = tokio::spawn({
+        let (config, cancel) = (config.clone(), cancel.clone());
+        async move { health::run(Arc::new(NetUtils::new()), &config, tx, cancel).await }
+    });
+
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            event = rx.recv() => {
+                let Some(event) = event else { break };
+                println!("{}", event);
+                if let Err(e) = health::fire_alarms(&config, &event).await {
+                    eprintln!("warning: alarm failed: {}", e);
+                }
+            }
+        }
+    }
+    cancel.cancel();
+    let _ = checker.await;
+    ExitCode::SUCCESS
+}
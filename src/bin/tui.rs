@@ -1,32 +1,82 @@
-use ragescanner::bridge::Bridge;
-use ragescanner::tui::app::{App, ScanState};
+use ragescanner::bridge::{Bridge, EXIT_STOP_TIMEOUT};
+use ragescanner::inventory::{self, INVENTORY_VAR};
+use ragescanner::monitor;
+use ragescanner::net::{self, NetUtils, NetworkProvider};
+use ragescanner::settings::Settings;
+use ragescanner::tui::app::{App, ScanState, osc52_copy};
 use ragescanner::tui::event::{AppEvent, EventHandler};
+use ragescanner::tui::setup::SetupWizard;
 use ragescanner::tui::ui;
-use ragescanner::types::BridgeMessage;
+use ragescanner::types::{BridgeMessage, ScanRunState};
 
 use ratatui::{
     Terminal,
     backend::CrosstermBackend,
     crossterm::{
-        event::{DisableMouseCapture, EnableMouseCapture},
+        event::{
+            DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        },
         execute,
         terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
     },
 };
-use std::io;
+use std::io::{self, Write};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 1. Terminal setup
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     // 2. Bridge & App setup
     let bridge = Bridge::new();
     let mut app = App::new(bridge.cmd_tx.clone());
+    app.capabilities = NetUtils::new().capabilities();
+    if let Some(iface) = net::active_interface() {
+        app.input = iface.range_string();
+        app.notice = Some(format!(
+            "Detected {} ({}/{})",
+            iface.name, iface.address, iface.prefix_len
+        ));
+    }
+    // Saved settings win over the detected subnet: the user picked that range.
+    // A broken file is reported and left alone rather than overwritten.
+    if let Some(path) = Settings::default_path() {
+        let first_run = !path.exists();
+        match Settings::load_or_default(&path) {
+            Ok(settings) => {
+                app.apply_settings(settings);
+                if first_run {
+                    let interfaces = net::interfaces().unwrap_or_else(|e| {
+                        log::warn!("Failed to list network interfaces: {}", e);
+                        Vec::new()
+                    });
+                    app.setup = Some(SetupWizard::new(
+                        net::candidate_interfaces(&interfaces),
+                        &app.input,
+                    ));
+                }
+                let _ = bridge
+                    .cmd_tx
+                    .try_send(BridgeMessage::WatchSettings(path.clone()));
+                app.settings_path = Some(path);
+            }
+            Err(e) => app.show_error(e.to_string()),
+        }
+    }
+    if let Some(path) = std::env::var_os(INVENTORY_VAR) {
+        let _ = bridge
+            .cmd_tx
+            .try_send(BridgeMessage::SetInventory(Some(path.into())));
+    }
     let mut events = EventHandler::new(bridge.ui_rx.clone());
 
     // 3. Main Loop
@@ -37,29 +87,119 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             match event {
                 AppEvent::Input(key) => {
                     app.on_key(key.code);
+                    if let Some(text) = app.clipboard.take() {
+                        let backend = terminal.backend_mut();
+                        backend.write_all(osc52_copy(&text).as_bytes())?;
+                        backend.flush()?;
+                    }
                 }
-                AppEvent::Tick => {}
+                AppEvent::Paste(text) => app.on_paste(&text),
+                AppEvent::Tick => app.on_tick(),
                 AppEvent::Bridge(msg) => {
-                    match msg {
-                        BridgeMessage::ScanUpdate(res) => {
-                            // Update or add result
-                            if let Some(existing) = app.results.iter_mut().find(|r| r.ip == res.ip)
-                            {
-                                *existing = res;
-                            } else {
-                                app.results.push(res);
-                            }
-                        }
+                    app.record_bridge_event(&msg);
+                    match *msg {
+                        BridgeMessage::ScanUpdate(res) => app.upsert_result(res),
+                        BridgeMessage::PortFound { ip, port } => app.on_port_found(ip, port),
                         BridgeMessage::Progress(p) => app.progress = p,
+                        BridgeMessage::ScanStats(stats) => app.stats = Some(stats),
+                        BridgeMessage::Phase(phase) => app.phase = Some(phase),
+                        BridgeMessage::ScanSummary(summary) => app.summary = Some(summary),
+                        BridgeMessage::RangeProgress(range) => app.on_range_progress(range),
                         BridgeMessage::ScanComplete => {
                             app.scan_state = ScanState::Complete;
                             app.progress = 100;
-                            app.sort_results();
+                            #[cfg(feature = "history")]
+                            app.save_history();
+                        }
+                        BridgeMessage::ScanCancelled => app.on_scan_cancelled(),
+                        BridgeMessage::ScanStarted(label) => app.active_label = label,
+                        BridgeMessage::ScanQueued(position) => {
+                            app.notice = Some(format!("Scan queued (#{})", position));
+                        }
+                        BridgeMessage::ScanPaused(reason) => {
+                            app.notice = Some(format!("Paused: {}", reason));
+                        }
+                        BridgeMessage::ScanResumed => {
+                            app.notice = Some("Network settled, scan resumed".to_string());
+                        }
+                        BridgeMessage::SystemSuspended => {
+                            app.notice = Some("Paused: the computer is going to sleep".to_string());
+                        }
+                        BridgeMessage::SystemResumed => {
+                            app.notice = Some(
+                                "Woke from sleep: Space resumes the scan, s stops it to start over"
+                                    .to_string(),
+                            );
+                        }
+                        BridgeMessage::ConfigReloaded(settings) => {
+                            app.on_config_reloaded(*settings)
+                        }
+                        BridgeMessage::InventoryLoaded(count) => {
+                            app.results.update_where(
+                                |_| true,
+                                |res| res.asset = inventory::lookup(res.ip, res.mac.as_deref()),
+                            );
+                            app.notice = Some(format!("Asset inventory: {} devices", count));
+                        }
+                        BridgeMessage::DeviceNoteSaved { mac, note } => {
+                            app.on_device_note_saved(&mac, note)
+                        }
+                        BridgeMessage::ConfigReloadFailed(e) => {
+                            app.notice = Some(format!("Settings not reloaded: {}", e));
+                        }
+                        BridgeMessage::ScanState(state) => {
+                            app.paused = state == ScanRunState::Paused;
+                            app.notice = app.paused.then(|| "Scan paused".to_string());
+                        }
+                        BridgeMessage::ScanRejected => {
+                            app.notice = Some("Scan rejected: another scan is running".to_string());
+                        }
+                        BridgeMessage::InterceptionSuspected(warning) => {
+                            app.notice = Some(warning.to_string());
+                        }
+                        BridgeMessage::DeepScanPort(..)
+                        | BridgeMessage::DeepScanProgress(..)
+                        | BridgeMessage::DeepScanComplete(..)
+                        | BridgeMessage::DeepScanCancelled(_) => app.on_deep_scan_event(*msg),
+                        BridgeMessage::TracerouteHop(..) | BridgeMessage::TracerouteComplete(_) => {
+                            app.on_traceroute_event(*msg)
+                        }
+                        BridgeMessage::ReachMatrixComplete(matrix) => app.on_reach_matrix(matrix),
+                        BridgeMessage::HostRescanned(res) => {
+                            app.notice = Some(format!("Rescanned {}", res.ip));
+                            app.upsert_result(res);
+                        }
+                        BridgeMessage::WakeOnLanSent(mac) => {
+                            app.notice = Some(format!("Wake-on-LAN packet sent to {}", mac));
+                        }
+                        BridgeMessage::WakeBatchComplete(report) => {
+                            app.notice = Some(format!("Wake-on-LAN: {}", report.summary()));
+                        }
+                        BridgeMessage::WakeOnLanFailed(mac, e) => {
+                            app.show_error(format!("Could not wake {}: {}", mac, e));
+                        }
+                        BridgeMessage::DeviceAppeared(_)
+                        | BridgeMessage::DeviceDisappeared(_)
+                        | BridgeMessage::DeviceChanged { .. } => {
+                            app.last_change = monitor::describe_event(&msg);
+                        }
+                        BridgeMessage::PortsChanged { .. }
+                        | BridgeMessage::LatencyAnomaly { .. }
+                        | BridgeMessage::IpConflict { .. } => {
+                            app.last_change = monitor::describe_event(&msg);
+                            app.notice = app.last_change.clone();
+                        }
+                        BridgeMessage::SecurityAlert { ref host, .. } => {
+                            app.security_alerts.insert(host.ip);
+                            app.last_change = monitor::describe_event(&msg);
+                            app.notice = app.last_change.clone();
                         }
-                        BridgeMessage::ScanCancelled => app.scan_state = ScanState::Cancelled,
                         BridgeMessage::Error(e) => {
                             app.scan_state = ScanState::Idle;
-                            app.error = Some(e.to_string());
+                            app.show_error(match e.hint() {
+                                Some(hint) => format!("{} - {}", e, hint),
+                                None => e.to_string(),
+                            });
                         }
                         _ => {}
                     }
@@ -72,12 +212,34 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    // 4. Restore terminal
+    // 4. Let a running scan wind down instead of dropping it mid-probe
+    if app.stop_for_exit() {
+        terminal.draw(|f| ui::render(f, &mut app))?;
+        let deadline = tokio::time::Instant::now() + EXIT_STOP_TIMEOUT;
+        while let Ok(Some(event)) = tokio::time::timeout_at(deadline, events.rx.recv()).await {
+            let AppEvent::Bridge(msg) = event else {
+                continue;
+            };
+            match *msg {
+                BridgeMessage::ScanUpdate(res) => app.upsert_result(res),
+                BridgeMessage::ScanComplete => {
+                    #[cfg(feature = "history")]
+                    app.save_history();
+                    break;
+                }
+                BridgeMessage::ScanCancelled => break,
+                _ => {}
+            }
+        }
+    }
+
+    // 5. Restore terminal
     disable_raw_mode()?;
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
@@ -0,0 +1,100 @@
+//! Read-only terminal viewer for saved scans.
+//!
+//! Opens an autosaved session, a JSON export or a `ragescanner-cli --stream`
+//! file and offers the `rageping` table, online filter, details and vendor
+//! lookups. It has no scanning capability, so it is safe to hand to auditors.
+
+use clap::Parser;
+use ragescanner::inventory;
+use ragescanner::session;
+use ragescanner::settings::Settings;
+use ragescanner::tui::app::{App, osc52_copy};
+use ragescanner::tui::event::{AppEvent, EventHandler};
+use ragescanner::tui::{theme, ui};
+use ratatui::{
+    Terminal,
+    backend::CrosstermBackend,
+    crossterm::{
+        execute,
+        terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+    },
+};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Browse saved scan results without scanning.
+#[derive(Parser, Debug)]
+#[command(name = "ragescanner-view", version, about)]
+struct Args {
+    /// Session file, JSON export or result stream, e.g. results.json
+    file: PathBuf,
+
+    /// Asset inventory CSV keyed by MAC or IP, joined into the results
+    #[arg(long, value_name = "PATH")]
+    inventory: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+    // Load before touching the terminal so errors print normally.
+    let mut saved = match session::load_results(&args.file) {
+        Ok(saved) => saved,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Some(path) = args.inventory {
+        if let Err(e) = inventory::set_inventory(Some(path)) {
+            eprintln!("error: {}", e);
+            return ExitCode::FAILURE;
+        }
+        inventory::annotate(&mut saved.results);
+    }
+    // Same colors as the TUI; an unreadable settings file just keeps the default.
+    if let Some(settings) = Settings::default_path().and_then(|path| Settings::load(&path).ok()) {
+        theme::set(settings.theme);
+    }
+    let app = App::viewer(&args.file.display().to_string(), saved.results, saved.label);
+    match run(app).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(mut app: App) -> Result<(), Box<dyn std::error::Error>> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    // No bridge: the sender is dropped at once, so no bridge events ever arrive.
+    let (_, bridge_rx) = crossbeam_channel::unbounded();
+    let mut events = EventHandler::new(bridge_rx);
+
+    while !app.should_quit {
+        terminal.draw(|f| ui::render(f, &mut app))?;
+        match events.rx.recv().await {
+            Some(AppEvent::Input(key)) => {
+                app.on_key(key.code);
+                if let Some(text) = app.clipboard.take() {
+                    let backend = terminal.backend_mut();
+                    backend.write_all(osc52_copy(&text).as_bytes())?;
+                    backend.flush()?;
+                }
+            }
+            Some(_) => {}
+            None => break,
+        }
+    }
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    Ok(())
+}
@@ -3,26 +3,773 @@
 //! [`Bridge`] spawns a background thread with a Tokio runtime and provides
 //! channel-based communication for any frontend (GUI, TUI, CLI).
 
-use crate::net::NetUtils;
-use crate::scanner::Scanner;
-use crate::types::{BridgeMessage, GError};
-use crossbeam_channel::{Receiver, unbounded};
+use crate::cache::PortCache;
+use crate::conflict::{ConflictWatch, MIN_CONFLICT_WATCH_INTERVAL};
+use crate::deep::{DeepScanner, deep_scan_ports};
+use crate::devices::{self, DEFAULT_DEVICES_PATH};
+use crate::inventory;
+use crate::monitor::{MIN_MONITOR_INTERVAL, Monitor};
+use crate::net::{self, DEFAULT_PORT_TIMEOUT, NetUtils, NetworkProvider, PowerState, PowerWatch};
+use crate::notify::{Notifier, NotifyConfig};
+use crate::passive::{ArpWatch, MIN_ARP_WATCH_INTERVAL};
+use crate::reach::{self, DEFAULT_MATRIX_CONCURRENCY, ReachHost};
+use crate::scanner::{HostStates, Scanner};
+use crate::settings::{RELOAD_POLL_INTERVAL, Settings, SettingsWatcher};
+use crate::targets::ScanTargets;
+use crate::types::{
+    BridgeMessage, BusyPolicy, GError, PortInfo, ScanConfig, ScanId, ScanLabel, ScanResult,
+    ScanStatus,
+};
+use crate::wol;
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, unbounded};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
-use tokio::runtime::Runtime;
+use std::time::{Duration, Instant};
+use tokio::runtime::{Builder as RuntimeBuilder, Handle, Runtime};
 use tokio::sync::mpsc::{Sender as TokioSender, channel as tokio_channel};
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+/// Capacity of the command channel of a [`Bridge`] unless
+/// [`BridgeBuilder::channel_capacity`] sets another.
+pub const DEFAULT_CHANNEL_CAPACITY: usize = 32;
+
+/// How long [`Bridge::shutdown`] waits for the bridge thread to exit.
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a frontend that is closing waits for a running scan to answer
+/// [`BridgeMessage::StopScan`] with [`BridgeMessage::ScanCancelled`].
+pub const EXIT_STOP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Highest TTL a [`BridgeMessage::Traceroute`] probes before giving up.
+pub const MAX_TRACE_HOPS: u8 = 30;
+
+/// Time to wait for the answer at each hop of a traceroute.
+pub const TRACE_HOP_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Most [`BridgeMessage::PortFound`] forwarded per [`PORT_FOUND_WINDOW`];
+/// the rest are dropped, as the host's final result lists them anyway.
+pub const PORT_FOUND_BURST: usize = 50;
+
+/// Window over which [`PORT_FOUND_BURST`] is counted.
+pub const PORT_FOUND_WINDOW: Duration = Duration::from_millis(250);
+
+/// What a scan request targets.
+enum ScanTarget {
+    Range(Ipv4Addr, Ipv4Addr),
+    List(Vec<Ipv4Addr>),
+    /// Several ranges, merged by the scanner.
+    Multi(ScanTargets),
+    /// Repeated sweeps of a range, `interval` apart.
+    Monitor(Ipv4Addr, Ipv4Addr, Duration),
+    /// Reads of the ARP cache, `interval` apart, limited to the targets if any.
+    ArpWatch(Option<ScanTargets>, Duration),
+    /// ARP requests for one address, `interval` apart.
+    ConflictWatch(Ipv4Addr, Duration),
+}
+
+/// Per-scan settings accumulated from `Set*` commands.
+struct ScanOptions {
+    udp_scan: bool,
+    include_subnet_edges: bool,
+    ping_attempts: u32,
+    port_cache: Arc<PortCache>,
+    notify: Option<Arc<NotifyConfig>>,
+    config: ScanConfig,
+}
+
+impl ScanOptions {
+    /// Creates a scanner reporting to `tx` with these settings applied.
+    fn build_scanner(
+        self,
+        net_utils: Arc<dyn NetworkProvider>,
+        tx: TokioSender<BridgeMessage>,
+        pause: watch::Receiver<bool>,
+    ) -> Scanner {
+        let mut scanner = Scanner::new(net_utils, tx).with_pause(pause);
+        // Cached results only cover the default port list.
+        if self.config.ports == ScanConfig::default().ports {
+            scanner = scanner.with_port_cache(self.port_cache);
+        }
+        let scanner = scanner.with_config(self.config);
+        scanner.set_udp_scan(self.udp_scan);
+        scanner.set_include_subnet_edges(self.include_subnet_edges);
+        scanner.set_ping_attempts(self.ping_attempts);
+        scanner
+    }
+}
+
+/// Bookkeeping for a scan owned by the bridge thread.
+///
+/// Each scan gets its own [`Scanner`] and forwarding task so that a scan
+/// replaced by a newer one can be silenced: its trailing updates and
+/// [`BridgeMessage::ScanCancelled`] must not be mistaken for the new scan's.
+/// Once the scan has finished and its messages are drained, the forwarder
+/// reports `id` on the completion channel. Scans started with a caller
+/// [`ScanId`] have every message wrapped in [`BridgeMessage::Tagged`].
+struct ActiveScan {
+    id: u64,
+    tag: Option<ScanId>,
+    token: CancellationToken,
+    superseded: Arc<AtomicBool>,
+    /// Cancelled by a stop, but still forwarding its last updates and
+    /// [`BridgeMessage::ScanCancelled`].
+    stopped: bool,
+    paused: watch::Sender<bool>,
+    states: Arc<HostStates>,
+}
+
+impl ActiveScan {
+    /// Starts a scan on the current runtime and forwards its messages to `ui_tx`.
+    fn spawn(
+        id: u64,
+        tag: Option<ScanId>,
+        net_utils: Arc<dyn NetworkProvider>,
+        ui_tx: Sender<BridgeMessage>,
+        done_tx: TokioSender<u64>,
+        options: ScanOptions,
+        target: ScanTarget,
+    ) -> Self {
+        let token = CancellationToken::new();
+        let superseded = Arc::new(AtomicBool::new(false));
+        let (paused, pause) = watch::channel(false);
+        let states = Arc::new(HostStates::default());
+        // Sent before the forwarder exists, so it precedes every scan message.
+        let _ = ui_tx.send(tagged(
+            tag,
+            BridgeMessage::ScanStarted(options.config.label.clone()),
+        ));
+
+        let (scanner_tx, mut scanner_rx) = tokio_channel::<BridgeMessage>(100);
+        let silenced = superseded.clone();
+        let mut notifier = options.notify.clone().map(Notifier::new);
+        tokio::spawn(async move {
+            let mut port_throttle = PortFoundThrottle::default();
+            while let Some(msg) = scanner_rx.recv().await {
+                // Keep draining so the scanner never blocks, but stay quiet.
+                if !silenced.load(Ordering::SeqCst) && port_throttle.admit(&msg, Instant::now()) {
+                    if let Some(notifier) = &mut notifier {
+                        notifier.handle(&msg);
+                    }
+                    let _ = ui_tx.send(tagged(tag, msg));
+                }
+            }
+            // Every sender is gone, so the scan task has ended.
+            let _ = done_tx.send(id).await;
+        });
+
+        let scan_token = token.clone();
+        match target {
+            ScanTarget::Range(start, end) => {
+                let scanner = options
+                    .build_scanner(net_utils, scanner_tx, pause)
+                    .with_host_states(states.clone());
+                tokio::spawn(async move { scanner.scan_range(start, end, scan_token).await });
+            }
+            ScanTarget::List(targets) => {
+                let scanner = options
+                    .build_scanner(net_utils, scanner_tx, pause)
+                    .with_host_states(states.clone());
+                tokio::spawn(async move { scanner.scan_targets(targets, scan_token).await });
+            }
+            ScanTarget::Multi(targets) => {
+                let scanner = options
+                    .build_scanner(net_utils, scanner_tx, pause)
+                    .with_host_states(states.clone());
+                tokio::spawn(async move { scanner.scan_multi(targets, scan_token).await });
+            }
+            ScanTarget::Monitor(start, end, interval) => {
+                // The monitor sits between the scanner and the forwarder to diff sweeps.
+                let (sweep_tx, sweep_rx) = tokio_channel::<BridgeMessage>(100);
+                let scanner = options
+                    .build_scanner(net_utils, sweep_tx, pause)
+                    .with_host_states(states.clone());
+                let monitor = Monitor::new(scanner, sweep_rx, scanner_tx);
+                tokio::spawn(monitor.run(start, end, interval, scan_token));
+            }
+            ScanTarget::ArpWatch(targets, interval) => {
+                let watch = ArpWatch::new(net_utils, scanner_tx, targets);
+                tokio::spawn(watch.run(interval, scan_token));
+            }
+            ScanTarget::ConflictWatch(ip, interval) => {
+                let watch = ConflictWatch::new(net_utils, scanner_tx, ip);
+                tokio::spawn(watch.run(interval, scan_token));
+            }
+        }
+
+        Self {
+            id,
+            tag,
+            token,
+            superseded,
+            stopped: false,
+            paused,
+            states,
+        }
+    }
+
+    /// Cancels the scan without reporting `ScanCancelled` to the frontend.
+    fn supersede(self) {
+        self.superseded.store(true, Ordering::SeqCst);
+        self.token.cancel();
+    }
+}
+
+/// Thins out [`BridgeMessage::PortFound`] so a host answering on thousands
+/// of ports does not flood the frontend: at most [`PORT_FOUND_BURST`] pass
+/// per [`PORT_FOUND_WINDOW`]. Other messages always pass.
+#[derive(Default)]
+struct PortFoundThrottle {
+    window_start: Option<Instant>,
+    admitted: usize,
+    dropped: usize,
+}
+
+impl PortFoundThrottle {
+    /// Whether `msg`, arriving at `now`, should be forwarded.
+    fn admit(&mut self, msg: &BridgeMessage, now: Instant) -> bool {
+        if !matches!(msg, BridgeMessage::PortFound { .. }) {
+            return true;
+        }
+        if self
+            .window_start
+            .is_none_or(|start| now.duration_since(start) >= PORT_FOUND_WINDOW)
+        {
+            if self.dropped > 0 {
+                log::debug!("Dropped {} PortFound messages", self.dropped);
+            }
+            self.window_start = Some(now);
+            self.admitted = 0;
+            self.dropped = 0;
+        }
+        if self.admitted < PORT_FOUND_BURST {
+            self.admitted += 1;
+            true
+        } else {
+            self.dropped += 1;
+            false
+        }
+    }
+}
+
+/// Wraps `msg` for the scan tagged `tag`; untagged messages pass through.
+fn tagged(tag: Option<ScanId>, msg: BridgeMessage) -> BridgeMessage {
+    match tag {
+        Some(id) => BridgeMessage::Tagged(id, Box::new(msg)),
+        None => msg,
+    }
+}
+
+/// Starts, queues and stops scans according to the current [`BusyPolicy`].
+///
+/// Tagged scans run alongside everything else: the policy only weighs
+/// untagged requests against untagged running scans.
+struct Dispatcher {
+    net_utils: Arc<dyn NetworkProvider>,
+    ui_tx: Sender<BridgeMessage>,
+    done_tx: TokioSender<u64>,
+    policy: BusyPolicy,
+    next_id: u64,
+    active: Vec<ActiveScan>,
+    queue: VecDeque<(ScanTarget, ScanOptions)>,
+    /// Starts that arrived while a stopped scan with the same tag was still
+    /// winding down. They are requested again once it finishes, so its
+    /// trailing messages cannot be taken for theirs.
+    deferred: Vec<(Option<ScanId>, ScanTarget, ScanOptions)>,
+    /// Last [`PowerState::sleeps`] seen.
+    sleeps: u64,
+    /// Whether an untagged scan was paused for the current sleep.
+    slept_mid_scan: bool,
+}
+
+impl Dispatcher {
+    /// Creates an idle dispatcher. Finished scans report their id on `done_tx`,
+    /// which the owner must feed back into [`finished`](Dispatcher::finished).
+    fn new(
+        net_utils: Arc<dyn NetworkProvider>,
+        ui_tx: Sender<BridgeMessage>,
+        done_tx: TokioSender<u64>,
+    ) -> Self {
+        Self {
+            net_utils,
+            ui_tx,
+            done_tx,
+            policy: BusyPolicy::default(),
+            next_id: 0,
+            active: Vec::new(),
+            queue: VecDeque::new(),
+            deferred: Vec::new(),
+            sleeps: 0,
+            slept_mid_scan: false,
+        }
+    }
+
+    /// Starts `target` now, or applies the busy policy if a scan is running.
+    fn request(&mut self, target: ScanTarget, options: ScanOptions) {
+        if self.stopping(None) {
+            self.deferred.push((None, target, options));
+            return;
+        }
+        if !self.busy() {
+            self.start(target, options, None);
+            return;
+        }
+        match self.policy {
+            BusyPolicy::Replace => {
+                self.queue.clear();
+                let (replaced, tagged) = std::mem::take(&mut self.active)
+                    .into_iter()
+                    .partition(|scan| scan.tag.is_none());
+                self.active = tagged;
+                for scan in replaced {
+                    scan.supersede();
+                }
+                self.start(target, options, None);
+            }
+            BusyPolicy::Queue => {
+                self.queue.push_back((target, options));
+                let _ = self.ui_tx.send(BridgeMessage::ScanQueued(self.queue.len()));
+            }
+            BusyPolicy::Reject => {
+                let _ = self.ui_tx.send(BridgeMessage::ScanRejected);
+            }
+            BusyPolicy::Parallel => self.start(target, options, None),
+        }
+    }
+
+    /// Starts `target` as scan `tag` unless a scan with that id is still running.
+    fn request_tagged(&mut self, tag: ScanId, target: ScanTarget, options: ScanOptions) {
+        if self.stopping(Some(tag)) {
+            self.deferred.push((Some(tag), target, options));
+            return;
+        }
+        if self.active.iter().any(|scan| scan.tag == Some(tag)) {
+            let error = GError::Internal(format!("Scan {} is already running", tag));
+            let _ = self
+                .ui_tx
+                .send(tagged(Some(tag), BridgeMessage::Error(error)));
+            return;
+        }
+        self.start(target, options, Some(tag));
+    }
+
+    /// Whether an untagged scan is running and has not been stopped.
+    fn busy(&self) -> bool {
+        self.active
+            .iter()
+            .any(|scan| scan.tag.is_none() && !scan.stopped)
+    }
+
+    /// Whether a stopped scan tagged `tag` has yet to finish.
+    fn stopping(&self, tag: Option<ScanId>) -> bool {
+        self.active
+            .iter()
+            .any(|scan| scan.stopped && scan.tag == tag)
+    }
+
+    fn start(&mut self, target: ScanTarget, options: ScanOptions, tag: Option<ScanId>) {
+        self.next_id += 1;
+        self.active.push(ActiveScan::spawn(
+            self.next_id,
+            tag,
+            self.net_utils.clone(),
+            self.ui_tx.clone(),
+            self.done_tx.clone(),
+            options,
+            target,
+        ));
+    }
+
+    /// Forgets scan `id`, starts the next queued scan once no untagged scan
+    /// is running, and retries starts that waited on a stopped scan.
+    fn finished(&mut self, id: u64) {
+        self.active.retain(|scan| scan.id != id);
+        if !self.busy()
+            && !self.stopping(None)
+            && let Some((target, options)) = self.queue.pop_front()
+        {
+            self.start(target, options, None);
+        }
+        for (tag, target, options) in std::mem::take(&mut self.deferred) {
+            match tag {
+                Some(tag) => self.request_tagged(tag, target, options),
+                None => self.request(target, options),
+            }
+        }
+    }
+
+    /// Sends a [`BridgeMessage::StateSnapshot`] of every running scan.
+    fn send_snapshots(&self) {
+        for scan in self.active.iter().filter(|scan| !scan.stopped) {
+            let _ = self.ui_tx.send(tagged(
+                scan.tag,
+                BridgeMessage::StateSnapshot(scan.states.snapshot()),
+            ));
+        }
+    }
+
+    /// Pauses or resumes every running scan; queued scans start unpaused.
+    fn set_paused(&self, paused: bool) {
+        for scan in self.active.iter().filter(|scan| !scan.stopped) {
+            scan.paused.send_replace(paused);
+        }
+    }
+
+    /// Pauses every running scan when the machine suspends, so the sleep
+    /// window is not probed as a wall of timeouts. After wake, tagged scans
+    /// continue on their own; untagged ones stay paused and the frontend gets
+    /// [`BridgeMessage::SystemResumed`] to offer resume or restart.
+    fn power_changed(&mut self, state: PowerState) {
+        if state.sleeps != self.sleeps {
+            self.sleeps = state.sleeps;
+            self.set_paused(true);
+            if self.busy() && !self.slept_mid_scan {
+                self.slept_mid_scan = true;
+                let _ = self.ui_tx.send(BridgeMessage::SystemSuspended);
+            }
+        }
+        if !state.suspended {
+            for scan in self.active.iter().filter(|scan| scan.tag.is_some()) {
+                scan.paused.send_replace(false);
+            }
+            if std::mem::take(&mut self.slept_mid_scan) {
+                let _ = self.ui_tx.send(BridgeMessage::SystemResumed);
+            }
+        }
+    }
+
+    /// Cancels every running scan, tagged or not, and drops the queue.
+    ///
+    /// Stopped scans stay in `active` until they finish, so a start that
+    /// arrives meanwhile waits instead of receiving their trailing messages.
+    fn stop(&mut self) {
+        self.queue.clear();
+        self.deferred.clear();
+        for scan in &mut self.active {
+            scan.stopped = true;
+            scan.token.cancel();
+        }
+    }
+
+    /// Cancels the scan tagged `tag`; it reports `ScanCancelled` under that tag.
+    fn stop_tagged(&mut self, tag: ScanId) {
+        self.deferred
+            .retain(|(deferred, ..)| *deferred != Some(tag));
+        for scan in self.active.iter_mut().filter(|scan| scan.tag == Some(tag)) {
+            scan.stopped = true;
+            scan.token.cancel();
+        }
+    }
+}
+
+/// Loads an OUI database off the runtime and reports the outcome to the frontend.
+fn spawn_oui_load<F>(ui_tx: Sender<BridgeMessage>, load: F)
+where
+    F: FnOnce() -> Result<usize, GError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        let msg = match load() {
+            Ok(records) => BridgeMessage::OuiDatabaseLoaded(records),
+            Err(e) => BridgeMessage::Error(e),
+        };
+        let _ = ui_tx.send(msg);
+    });
+}
+
+/// Starts a deep scan of `ip` and forwards its tagged messages to `ui_tx`.
+///
+/// Returns the token that cancels it.
+fn spawn_deep_scan(
+    deep_scanner: &DeepScanner,
+    ui_tx: Sender<BridgeMessage>,
+    ip: Ipv4Addr,
+) -> CancellationToken {
+    let token = CancellationToken::new();
+    let (deep_tx, mut deep_rx) = tokio_channel::<BridgeMessage>(100);
+    tokio::spawn(async move {
+        while let Some(msg) = deep_rx.recv().await {
+            let _ = ui_tx.send(msg);
+        }
+    });
+
+    let deep_scanner = deep_scanner.clone();
+    let scan_token = token.clone();
+    tokio::spawn(async move {
+        deep_scanner
+            .scan_host(ip, deep_scan_ports(), deep_tx, scan_token)
+            .await
+    });
+    token
+}
+
+/// Scans `ip` again, outside the dispatcher, and forwards its result as
+/// [`BridgeMessage::HostRescanned`].
+///
+/// The scan's progress and completion messages are dropped so they cannot be
+/// mistaken for those of a range scan running at the same time.
+fn spawn_rescan(
+    net_utils: Arc<dyn NetworkProvider>,
+    ui_tx: Sender<BridgeMessage>,
+    options: ScanOptions,
+    ip: Ipv4Addr,
+) {
+    let (rescan_tx, mut rescan_rx) = tokio_channel::<BridgeMessage>(100);
+    tokio::spawn(async move {
+        while let Some(msg) = rescan_rx.recv().await {
+            if let BridgeMessage::ScanUpdate(result) = msg {
+                let _ = ui_tx.send(BridgeMessage::HostRescanned(result));
+            }
+        }
+    });
+
+    let scanner = options.build_scanner(net_utils, rescan_tx, watch::channel(false).1);
+    tokio::spawn(async move {
+        scanner
+            .scan_targets(vec![ip], CancellationToken::new())
+            .await
+    });
+}
+
+/// Scans `ip` again, then probes `ports` on it if it is online, reporting as
+/// described on [`BridgeMessage::ScanHost`].
+///
+/// Like [`spawn_rescan`] it bypasses the dispatcher and drops the range-scan
+/// messages of its single-host scan. Returns the token that cancels it.
+fn spawn_host_scan(
+    net_utils: Arc<dyn NetworkProvider>,
+    deep_scanner: &DeepScanner,
+    ui_tx: Sender<BridgeMessage>,
+    options: ScanOptions,
+    ip: Ipv4Addr,
+    ports: Vec<u16>,
+) -> CancellationToken {
+    let token = CancellationToken::new();
+    let deep_scanner = deep_scanner.clone();
+    let scan_token = token.clone();
+    tokio::spawn(async move {
+        let (host_tx, mut host_rx) = tokio_channel::<BridgeMessage>(100);
+        let scanner = options.build_scanner(net_utils, host_tx, watch::channel(false).1);
+        let sweep_token = scan_token.clone();
+        tokio::spawn(async move { scanner.scan_targets(vec![ip], sweep_token).await });
+        let mut result = None;
+        while let Some(msg) = host_rx.recv().await {
+            if let BridgeMessage::ScanUpdate(res) = msg {
+                result = Some(res);
+            }
+        }
+
+        if let Some(mut result) = result {
+            let _ = ui_tx.send(BridgeMessage::ScanUpdate(result.clone()));
+            if result.status == ScanStatus::Online && !scan_token.is_cancelled() {
+                let (deep_tx, mut deep_rx) = tokio_channel::<BridgeMessage>(100);
+                tokio::spawn(async move {
+                    deep_scanner.scan_host(ip, ports, deep_tx, scan_token).await
+                });
+                while let Some(msg) = deep_rx.recv().await {
+                    match msg {
+                        BridgeMessage::DeepScanPort(_, info) => {
+                            merge_open_port(&mut result, info);
+                            let _ = ui_tx.send(BridgeMessage::ScanUpdate(result.clone()));
+                        }
+                        BridgeMessage::DeepScanProgress(_, p) => {
+                            let _ = ui_tx.send(BridgeMessage::HostScanProgress(ip, p));
+                        }
+                        BridgeMessage::DeepScanComplete(_, infos) => {
+                            for info in infos {
+                                merge_open_port(&mut result, info);
+                            }
+                            let _ = ui_tx.send(BridgeMessage::ScanUpdate(result.clone()));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        let _ = ui_tx.send(BridgeMessage::HostScanFinished(ip));
+    });
+    token
+}
+
+/// Adds an open port to `result`, keeping the list sorted. A port already
+/// listed keeps its details and only gains a banner it lacked.
+fn merge_open_port(result: &mut ScanResult, info: PortInfo) {
+    match result
+        .open_ports
+        .binary_search_by_key(&info.port, |p| p.port)
+    {
+        Ok(i) => {
+            let known = &mut result.open_ports[i];
+            if known.banner.is_none() {
+                known.banner = info.banner;
+            }
+        }
+        Err(i) => result.open_ports.insert(i, info),
+    }
+}
+
+/// Traces the route to `ip` one TTL at a time, reporting as described on
+/// [`BridgeMessage::Traceroute`]. Each probe blocks, so it runs off the
+/// runtime. Returns the token that cancels it; a cancelled trace sends nothing
+/// more.
+fn spawn_traceroute(
+    net_utils: Arc<dyn NetworkProvider>,
+    ui_tx: Sender<BridgeMessage>,
+    ip: Ipv4Addr,
+) -> CancellationToken {
+    let token = CancellationToken::new();
+    let trace_token = token.clone();
+    tokio::spawn(async move {
+        for ttl in 1..=MAX_TRACE_HOPS {
+            let net = net_utils.clone();
+            let probe =
+                tokio::task::spawn_blocking(move || net.trace_hop(ip, ttl, TRACE_HOP_TIMEOUT));
+            let hop = tokio::select! {
+                _ = trace_token.cancelled() => return,
+                hop = probe => hop,
+            };
+            match hop {
+                Ok(Ok(hop)) => {
+                    let _ = ui_tx.send(BridgeMessage::TracerouteHop(ip, hop));
+                    if hop.addr == Some(ip) {
+                        break;
+                    }
+                }
+                Ok(Err(e)) => {
+                    log::warn!("Traceroute to {} stopped: {}", ip, e);
+                    break;
+                }
+                Err(_) => break,
+            }
+        }
+        let _ = ui_tx.send(BridgeMessage::TracerouteComplete(ip));
+    });
+    token
+}
+
+/// Probes the matrix of [`BridgeMessage::ReachMatrix`] and sends it back.
+fn spawn_reach_matrix(
+    net_utils: Arc<dyn NetworkProvider>,
+    ui_tx: Sender<BridgeMessage>,
+    hosts: Vec<ReachHost>,
+    ports: Vec<u16>,
+    timeout: Duration,
+) {
+    tokio::spawn(async move {
+        let matrix = reach::probe(
+            &*net_utils,
+            hosts,
+            &ports,
+            timeout,
+            DEFAULT_MATRIX_CONCURRENCY,
+        )
+        .await;
+        let _ = ui_tx.send(BridgeMessage::ReachMatrixComplete(matrix));
+    });
+}
+
+/// Polls the settings file at `path` and forwards every edit to `tx` until
+/// the task is aborted or the bridge goes away.
+fn spawn_settings_watch(
+    path: PathBuf,
+    tx: TokioSender<Result<Settings, GError>>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut watcher = SettingsWatcher::new(path);
+        loop {
+            tokio::time::sleep(RELOAD_POLL_INTERVAL).await;
+            if let Some(reloaded) = watcher.poll() {
+                log::info!("Settings file {} changed", watcher.path().display());
+                if tx.send(reloaded).await.is_err() {
+                    return;
+                }
+            }
+        }
+    })
+}
+
+/// Applies an edit of the watched settings file to `config` and returns the
+/// message for the frontend. Edits that leave the scan tuning alone, such as
+/// a frontend saving the last range, report nothing.
+fn apply_reloaded_settings(
+    config: &mut ScanConfig,
+    reloaded: Result<Settings, GError>,
+) -> Option<BridgeMessage> {
+    match reloaded {
+        Ok(settings) => {
+            let tuned = settings.scan_config();
+            if tuned == *config {
+                return None;
+            }
+            *config = tuned;
+            Some(BridgeMessage::ConfigReloaded(Box::new(settings)))
+        }
+        Err(e) => {
+            log::warn!("Settings not reloaded: {}", e);
+            Some(BridgeMessage::ConfigReloadFailed(e))
+        }
+    }
+}
+
+/// Broadcasts a Wake-on-LAN packet and reports the outcome to the frontend.
+fn spawn_wake_on_lan(ui_tx: Sender<BridgeMessage>, mac: String) {
+    tokio::spawn(async move {
+        let msg = match wol::wake_on_lan(&mac).await {
+            Ok(()) => BridgeMessage::WakeOnLanSent(mac),
+            Err(e) => BridgeMessage::WakeOnLanFailed(mac, e),
+        };
+        let _ = ui_tx.send(msg);
+    });
+}
+
+/// Wakes `targets` as described on [`BridgeMessage::WakeBatch`], filling in
+/// missing addresses from the ARP table first.
+fn spawn_wake_batch(
+    net_utils: Arc<dyn NetworkProvider>,
+    ui_tx: Sender<BridgeMessage>,
+    mut targets: Vec<wol::WakeTarget>,
+    timeout: Duration,
+) {
+    tokio::spawn(async move {
+        if targets.iter().any(|t| t.ip.is_none()) {
+            let net = net_utils.clone();
+            let arp = tokio::task::spawn_blocking(move || net.read_arp_table())
+                .await
+                .ok()
+                .and_then(Result::ok)
+                .unwrap_or_default();
+            wol::fill_addresses(&mut targets, &arp);
+        }
+        let report = wol::wake_batch(net_utils.as_ref(), targets, timeout, |target, sent| {
+            let _ = ui_tx.send(match sent {
+                Ok(()) => BridgeMessage::WakeOnLanSent(target.mac.clone()),
+                Err(e) => BridgeMessage::WakeOnLanFailed(target.mac.clone(), e),
+            });
+        })
+        .await;
+        log::info!("Wake-on-LAN batch: {}", report.summary());
+        let _ = ui_tx.send(BridgeMessage::WakeBatchComplete(report));
+    });
+}
 
 /// Orchestrator that bridges a frontend to the async scanner.
 ///
 /// Spawns a background thread with a Tokio runtime. Commands are sent via
 /// [`cmd_tx`](Bridge::cmd_tx) and results received via [`ui_rx`](Bridge::ui_rx).
+/// Dropping the bridge shuts it down; see [`Bridge::shutdown`].
 pub struct Bridge {
     /// Receiver for messages directed to the UI.
     pub ui_rx: Receiver<BridgeMessage>,
     /// Sender for commands directed to the scanner.
     pub cmd_tx: TokioSender<BridgeMessage>,
+    shutdown: CancellationToken,
+    thread: Option<thread::JoinHandle<()>>,
+    /// Disconnects when the bridge thread exits.
+    exited: Receiver<()>,
 }
 
 impl Default for Bridge {
@@ -31,90 +778,520 @@ impl Default for Bridge {
     }
 }
 
+impl Drop for Bridge {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Options for spawning a [`Bridge`], from [`Bridge::builder`].
+///
+/// ```no_run
+/// use ragescanner::bridge::Bridge;
+///
+/// let bridge = Bridge::builder().channel_capacity(128).runtime_threads(2).build();
+/// ```
+#[derive(Default)]
+pub struct BridgeBuilder {
+    net: Option<Arc<dyn NetworkProvider>>,
+    channel_capacity: Option<usize>,
+    runtime_threads: Option<usize>,
+    runtime: Option<Handle>,
+}
+
+impl BridgeBuilder {
+    /// Network backend of every scan, deep scan, traceroute and wake-up.
+    /// Default: [`NetUtils`].
+    pub fn network_provider(mut self, net: Arc<dyn NetworkProvider>) -> Self {
+        self.net = Some(net);
+        self
+    }
+
+    /// Commands [`Bridge::cmd_tx`] buffers before `send` waits (at least 1).
+    /// Default: [`DEFAULT_CHANNEL_CAPACITY`].
+    pub fn channel_capacity(mut self, capacity: usize) -> Self {
+        self.channel_capacity = Some(capacity.max(1));
+        self
+    }
+
+    /// Worker threads of the bridge's own runtime (at least 1). Default: one
+    /// per CPU core. Ignored when [`runtime`](Self::runtime) is set.
+    pub fn runtime_threads(mut self, threads: usize) -> Self {
+        self.runtime_threads = Some(threads.max(1));
+        self
+    }
+
+    /// Runs the bridge's tasks on an existing multi-threaded runtime instead
+    /// of starting one. The bridge thread still blocks on its command loop.
+    pub fn runtime(mut self, handle: Handle) -> Self {
+        self.runtime = Some(handle);
+        self
+    }
+
+    /// Spawns the bridge thread.
+    pub fn build(self) -> Bridge {
+        Bridge::spawn(self)
+    }
+}
+
 impl Bridge {
-    /// Creates a new bridge, spawning the background scanner thread.
+    /// Creates a new bridge with [`NetUtils`], spawning the background
+    /// scanner thread.
     ///
     /// The bridge starts a Tokio runtime in a dedicated OS thread to handle
     /// asynchronous networking tasks while the caller remains responsive.
+    /// Use [`Bridge::builder`] to inject a provider or share a runtime.
     pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    /// Starts configuring a bridge.
+    pub fn builder() -> BridgeBuilder {
+        BridgeBuilder::default()
+    }
+
+    fn spawn(options: BridgeBuilder) -> Self {
         let (ui_tx, ui_rx) = unbounded::<BridgeMessage>();
-        let (cmd_tx, mut cmd_rx) = tokio_channel::<BridgeMessage>(32);
+        let capacity = options.channel_capacity.unwrap_or(DEFAULT_CHANNEL_CAPACITY);
+        let (cmd_tx, mut cmd_rx) = tokio_channel::<BridgeMessage>(capacity);
+        let shutdown = CancellationToken::new();
+        let stop = shutdown.clone();
+        let (exited_tx, exited) = crossbeam_channel::bounded::<()>(0);
 
-        thread::spawn(move || {
-            let rt = match Runtime::new() {
-                Ok(r) => r,
-                Err(e) => {
-                    let _ = ui_tx.send(BridgeMessage::Error(GError::Internal(format!(
-                        "Failed to create tokio runtime: {}",
-                        e
-                    ))));
-                    return;
+        let thread = thread::spawn(move || {
+            let _exited_tx = exited_tx;
+            let mut owned_runtime = None;
+            let rt = match options.runtime {
+                Some(handle) => handle,
+                None => {
+                    let built = match options.runtime_threads {
+                        Some(threads) => RuntimeBuilder::new_multi_thread()
+                            .worker_threads(threads)
+                            .enable_all()
+                            .build(),
+                        None => Runtime::new(),
+                    };
+                    match built {
+                        Ok(r) => owned_runtime.insert(r).handle().clone(),
+                        Err(e) => {
+                            let _ = ui_tx.send(BridgeMessage::Error(GError::Internal(format!(
+                                "Failed to create tokio runtime: {}",
+                                e
+                            ))));
+                            return;
+                        }
+                    }
                 }
             };
 
             rt.block_on(async move {
-                let (scanner_tx, mut scanner_rx) = tokio_channel::<BridgeMessage>(100);
-
-                let ui_tx_clone = ui_tx.clone();
-                tokio::spawn(async move {
-                    while let Some(msg) = scanner_rx.recv().await {
-                        let _ = ui_tx_clone.send(msg);
+                let net_utils: Arc<dyn NetworkProvider> = match options.net {
+                    Some(net) => net,
+                    None => Arc::new(NetUtils::new()),
+                };
+                let mut udp_scan = false;
+                let mut include_subnet_edges = false;
+                let mut ping_attempts = 1;
+                let mut scan_label: Option<ScanLabel> = None;
+                let mut default_config = ScanConfig::default();
+                let mut notify = match NotifyConfig::load_default() {
+                    Ok(config) => config.map(Arc::new),
+                    Err(e) => {
+                        let _ = ui_tx.send(BridgeMessage::Error(e));
+                        None
                     }
+                };
+                if let Err(e) = devices::open(Path::new(DEFAULT_DEVICES_PATH)) {
+                    let _ = ui_tx.send(BridgeMessage::Error(e));
+                }
+                let port_cache = Arc::new(PortCache::default());
+                let (done_tx, mut done_rx) = tokio_channel::<u64>(16);
+                let mut dispatcher = Dispatcher::new(net_utils.clone(), ui_tx.clone(), done_tx);
+                // Deep scans bypass the dispatcher: they neither supersede nor
+                // queue behind range scans.
+                let deep_scanner = DeepScanner::new(net_utils.clone());
+                let mut deep_scans: HashMap<Ipv4Addr, CancellationToken> = HashMap::new();
+                let mut traceroutes: HashMap<Ipv4Addr, CancellationToken> = HashMap::new();
+                let (reload_tx, mut reload_rx) = tokio_channel::<Result<Settings, GError>>(4);
+                let mut settings_watch: Option<tokio::task::JoinHandle<()>> = None;
+                let mut power = net_utils.watch_power().unwrap_or_else(|e| {
+                    log::warn!("Sleep detection unavailable: {}", e);
+                    PowerWatch::inert()
                 });
 
-                // Instantiate real NetUtils and inject as NetworkProvider trait object
-                let net_utils = Arc::new(NetUtils::new());
-                let scanner = Arc::new(Scanner::new(net_utils, scanner_tx));
-
-                let mut current_cancel_token: Option<tokio_util::sync::CancellationToken> = None;
-
-                while let Some(msg) = cmd_rx.recv().await {
-                    match msg {
-                        BridgeMessage::StartScan(range) => {
-                            if let Some(token) = current_cancel_token.take() {
-                                token.cancel();
+                loop {
+                    let msg = tokio::select! {
+                        _ = stop.cancelled() => break,
+                        Some(id) = done_rx.recv() => {
+                            dispatcher.finished(id);
+                            continue;
+                        }
+                        state = power.changed() => {
+                            dispatcher.power_changed(state);
+                            continue;
+                        }
+                        Some(reloaded) = reload_rx.recv() => {
+                            if let Some(msg) = apply_reloaded_settings(&mut default_config, reloaded) {
+                                let _ = ui_tx.send(msg);
                             }
-
-                            let token = tokio_util::sync::CancellationToken::new();
-                            current_cancel_token = Some(token.clone());
-
+                            continue;
+                        }
+                        msg = cmd_rx.recv() => match msg {
+                            Some(msg) => msg,
+                            None => break,
+                        },
+                    };
+                    let (tag, msg) = match msg {
+                        BridgeMessage::StartTaggedScan { id, request } => (Some(id), *request),
+                        msg => (None, msg),
+                    };
+                    if tag.is_some() && !Self::is_scan_request(&msg) {
+                        let error = GError::Internal(
+                            "Only scan requests can be started as a tagged scan".to_string(),
+                        );
+                        let _ = ui_tx.send(tagged(tag, BridgeMessage::Error(error)));
+                        continue;
+                    }
+                    let mut config = default_config.clone();
+                    let target = match msg {
+                        BridgeMessage::StartScanWithConfig {
+                            range,
+                            config: scan_config,
+                        } => {
+                            config = *scan_config;
+                            match Self::parse_target(&range) {
+                                Ok(target) => target,
+                                Err(e) => {
+                                    let _ = ui_tx.send(tagged(
+                                        tag,
+                                        BridgeMessage::Error(GError::Internal(e)),
+                                    ));
+                                    continue;
+                                }
+                            }
+                        }
+                        BridgeMessage::StartScan(range) => match Self::parse_target(&range) {
+                            Ok(target) => target,
+                            Err(e) => {
+                                let _ = ui_tx
+                                    .send(tagged(tag, BridgeMessage::Error(GError::Internal(e))));
+                                continue;
+                            }
+                        },
+                        BridgeMessage::StartScanRange(start, end) => ScanTarget::Range(start, end),
+                        BridgeMessage::StartScanTargets(targets) => ScanTarget::List(targets),
+                        BridgeMessage::StartScanMulti(targets) => ScanTarget::Multi(targets),
+                        BridgeMessage::StartMonitor { interval, range } => {
                             match Self::parse_range(&range) {
-                                Ok((start, end)) => {
-                                    let scanner_clone = scanner.clone();
-                                    tokio::spawn(async move {
-                                        scanner_clone.scan_range(start, end, token).await;
-                                    });
+                                Ok((start, end)) => ScanTarget::Monitor(
+                                    start,
+                                    end,
+                                    interval.max(MIN_MONITOR_INTERVAL),
+                                ),
+                                Err(e) => {
+                                    let _ = ui_tx.send(tagged(
+                                        tag,
+                                        BridgeMessage::Error(GError::Internal(e)),
+                                    ));
+                                    continue;
                                 }
+                            }
+                        }
+                        BridgeMessage::StartArpWatch { interval, range } => {
+                            let targets = match range.trim() {
+                                "" => Ok(None),
+                                range => ScanTargets::parse(range).map(Some),
+                            };
+                            match targets {
+                                Ok(targets) => ScanTarget::ArpWatch(
+                                    targets,
+                                    interval.max(MIN_ARP_WATCH_INTERVAL),
+                                ),
                                 Err(e) => {
-                                    let _ = ui_tx.send(BridgeMessage::Error(GError::Internal(e)));
+                                    let _ = ui_tx.send(tagged(
+                                        tag,
+                                        BridgeMessage::Error(GError::Internal(e)),
+                                    ));
+                                    continue;
                                 }
                             }
                         }
-                        BridgeMessage::StartScanRange(start, end) => {
-                            if let Some(token) = current_cancel_token.take() {
+                        BridgeMessage::StartConflictWatch { ip, interval } => {
+                            ScanTarget::ConflictWatch(ip, interval.max(MIN_CONFLICT_WATCH_INTERVAL))
+                        }
+                        BridgeMessage::StopScan => {
+                            dispatcher.stop();
+                            continue;
+                        }
+                        BridgeMessage::StopTaggedScan(id) => {
+                            dispatcher.stop_tagged(id);
+                            continue;
+                        }
+                        BridgeMessage::PauseScan => {
+                            dispatcher.set_paused(true);
+                            continue;
+                        }
+                        BridgeMessage::ResumeScan => {
+                            dispatcher.set_paused(false);
+                            continue;
+                        }
+                        BridgeMessage::RequestStateSnapshot => {
+                            dispatcher.send_snapshots();
+                            continue;
+                        }
+                        BridgeMessage::StartDeepScan(ip) => {
+                            if let Some(previous) = deep_scans.remove(&ip) {
+                                previous.cancel();
+                            }
+                            let deep_scanner = deep_scanner
+                                .with_host_limit(default_config.max_host_connections);
+                            let token = spawn_deep_scan(&deep_scanner, ui_tx.clone(), ip);
+                            deep_scans.insert(ip, token);
+                            continue;
+                        }
+                        BridgeMessage::StopDeepScan(ip) => {
+                            if let Some(token) = deep_scans.remove(&ip) {
                                 token.cancel();
                             }
-
-                            let token = tokio_util::sync::CancellationToken::new();
-                            current_cancel_token = Some(token.clone());
-
-                            let scanner_clone = scanner.clone();
-                            tokio::spawn(async move {
-                                scanner_clone.scan_range(start, end, token).await;
+                            continue;
+                        }
+                        BridgeMessage::RescanHost(ip) => {
+                            let options = ScanOptions {
+                                udp_scan,
+                                include_subnet_edges,
+                                ping_attempts,
+                                port_cache: port_cache.clone(),
+                                notify: None,
+                                config: default_config.clone(),
+                            };
+                            spawn_rescan(net_utils.clone(), ui_tx.clone(), options, ip);
+                            continue;
+                        }
+                        BridgeMessage::ScanHost { ip, ports } => {
+                            if let Some(previous) = deep_scans.remove(&ip) {
+                                previous.cancel();
+                            }
+                            let options = ScanOptions {
+                                udp_scan,
+                                include_subnet_edges,
+                                ping_attempts,
+                                port_cache: port_cache.clone(),
+                                notify: None,
+                                config: default_config.clone(),
+                            };
+                            let deep_scanner = deep_scanner
+                                .with_host_limit(default_config.max_host_connections);
+                            let token = spawn_host_scan(
+                                net_utils.clone(),
+                                &deep_scanner,
+                                ui_tx.clone(),
+                                options,
+                                ip,
+                                ports,
+                            );
+                            deep_scans.insert(ip, token);
+                            continue;
+                        }
+                        BridgeMessage::WakeOnLan(mac) => {
+                            spawn_wake_on_lan(ui_tx.clone(), mac);
+                            continue;
+                        }
+                        BridgeMessage::WakeBatch { targets, timeout } => {
+                            spawn_wake_batch(net_utils.clone(), ui_tx.clone(), targets, timeout);
+                            continue;
+                        }
+                        BridgeMessage::Traceroute(ip) => {
+                            let token = spawn_traceroute(net_utils.clone(), ui_tx.clone(), ip);
+                            if let Some(previous) = traceroutes.insert(ip, token) {
+                                previous.cancel();
+                            }
+                            continue;
+                        }
+                        BridgeMessage::ReachMatrix { hosts, ports } => {
+                            let ports = if ports.is_empty() {
+                                default_config.ports.clone()
+                            } else {
+                                ports
+                            };
+                            let timeout = default_config.port_timeout.unwrap_or(DEFAULT_PORT_TIMEOUT);
+                            spawn_reach_matrix(net_utils.clone(), ui_tx.clone(), hosts, ports, timeout);
+                            continue;
+                        }
+                        BridgeMessage::SetUdpScan(enabled) => {
+                            udp_scan = enabled;
+                            continue;
+                        }
+                        BridgeMessage::SetIncludeSubnetEdges(enabled) => {
+                            include_subnet_edges = enabled;
+                            continue;
+                        }
+                        BridgeMessage::SetPingAttempts(attempts) => {
+                            ping_attempts = attempts;
+                            continue;
+                        }
+                        BridgeMessage::SetPortCacheTtl(ttl) => {
+                            port_cache.set_ttl(ttl);
+                            continue;
+                        }
+                        BridgeMessage::SetBusyPolicy(policy) => {
+                            dispatcher.policy = policy;
+                            continue;
+                        }
+                        BridgeMessage::SetPortLabels(labels) => {
+                            crate::types::set_port_labels(labels);
+                            continue;
+                        }
+                        BridgeMessage::SetOuiDatabase(path) => {
+                            spawn_oui_load(ui_tx.clone(), move || net::set_oui_database(path));
+                            continue;
+                        }
+                        BridgeMessage::ReloadOuiDatabase => {
+                            spawn_oui_load(ui_tx.clone(), net::reload_oui_database);
+                            continue;
+                        }
+                        BridgeMessage::SetInventory(path) => {
+                            let ui_tx = ui_tx.clone();
+                            tokio::task::spawn_blocking(move || {
+                                let _ = ui_tx.send(match inventory::set_inventory(path) {
+                                    Ok(count) => BridgeMessage::InventoryLoaded(count),
+                                    Err(e) => BridgeMessage::Error(e),
+                                });
                             });
+                            continue;
                         }
-                        BridgeMessage::StopScan => {
-                            if let Some(token) = current_cancel_token.take() {
-                                token.cancel();
+                        BridgeMessage::SetDeviceNote { mac, note } => {
+                            let ui_tx = ui_tx.clone();
+                            tokio::task::spawn_blocking(move || {
+                                let _ = ui_tx.send(match devices::set_note(&mac, note.clone()) {
+                                    Ok(()) => BridgeMessage::DeviceNoteSaved { mac, note },
+                                    Err(e) => BridgeMessage::Error(e),
+                                });
+                            });
+                            continue;
+                        }
+                        BridgeMessage::SetScanConfig(config) => {
+                            default_config = *config;
+                            continue;
+                        }
+                        BridgeMessage::WatchSettings(path) => {
+                            if let Some(previous) = settings_watch.take() {
+                                previous.abort();
                             }
+                            settings_watch = Some(spawn_settings_watch(path, reload_tx.clone()));
+                            continue;
                         }
-                        _ => {}
+                        BridgeMessage::SetScanLabel(label) => {
+                            scan_label = label;
+                            continue;
+                        }
+                        BridgeMessage::SetNotifications(config) => {
+                            notify = config.map(Arc::new);
+                            continue;
+                        }
+                        _ => continue,
+                    };
+
+                    if config.label.is_none() {
+                        config.label = scan_label.clone();
+                    }
+                    let options = ScanOptions {
+                        udp_scan,
+                        include_subnet_edges,
+                        ping_attempts,
+                        port_cache: port_cache.clone(),
+                        notify: notify.clone(),
+                        config,
+                    };
+                    match tag {
+                        Some(id) => dispatcher.request_tagged(id, target, options),
+                        None => dispatcher.request(target, options),
                     }
                 }
+
+                dispatcher.stop();
+                for token in deep_scans.values().chain(traceroutes.values()) {
+                    token.cancel();
+                }
+                if let Some(watch) = settings_watch {
+                    watch.abort();
+                }
             });
+            // Blocking probes still in flight finish on their own; nothing
+            // reads their results any more.
+            if let Some(rt) = owned_runtime {
+                rt.shutdown_background();
+            }
         });
 
-        Self { ui_rx, cmd_tx }
+        Self {
+            ui_rx,
+            cmd_tx,
+            shutdown,
+            thread: Some(thread),
+            exited,
+        }
+    }
+
+    /// Stops the bridge: cancels every scan, deep scan and traceroute, stops
+    /// taking commands and waits up to [`SHUTDOWN_TIMEOUT`] for the bridge
+    /// thread to exit. [`ui_rx`](Bridge::ui_rx) disconnects once the last
+    /// in-flight probe is done. Calling it again does nothing.
+    ///
+    /// Returns `false` if the thread was still running after the timeout; it
+    /// is then left to finish in the background.
+    pub fn shutdown(&mut self) -> bool {
+        let Some(thread) = self.thread.take() else {
+            return true;
+        };
+        self.shutdown.cancel();
+        match self.exited.recv_timeout(SHUTDOWN_TIMEOUT) {
+            Err(RecvTimeoutError::Timeout) => {
+                log::warn!("Bridge thread did not stop within {:?}", SHUTDOWN_TIMEOUT);
+                false
+            }
+            _ => {
+                let _ = thread.join();
+                true
+            }
+        }
+    }
+
+    /// Whether `msg` starts a scan, i.e. may be wrapped in `StartTaggedScan`.
+    fn is_scan_request(msg: &BridgeMessage) -> bool {
+        matches!(
+            msg,
+            BridgeMessage::StartScan(_)
+                | BridgeMessage::StartScanRange(..)
+                | BridgeMessage::StartScanTargets(_)
+                | BridgeMessage::StartScanMulti(_)
+                | BridgeMessage::StartScanWithConfig { .. }
+                | BridgeMessage::StartMonitor { .. }
+                | BridgeMessage::StartArpWatch { .. }
+                | BridgeMessage::StartConflictWatch { .. }
+        )
+    }
+
+    /// Parses the text of a `StartScan*` command: a range, a list of ranges,
+    /// or failing that, any addresses pasted into the text.
+    fn parse_target(text: &str) -> Result<ScanTarget, String> {
+        let text = &Self::normalize_input(text);
+        match Self::parse_range(text) {
+            Ok((start, end)) => Ok(ScanTarget::Range(start, end)),
+            Err(e) => {
+                if let Ok(targets) = ScanTargets::parse(text) {
+                    return Ok(match targets.ranges() {
+                        &[(start, end)] => ScanTarget::Range(start, end),
+                        _ => ScanTarget::Multi(targets),
+                    });
+                }
+                let targets = Self::extract_targets(text);
+                if targets.is_empty() {
+                    Err(e)
+                } else {
+                    Ok(ScanTarget::List(targets))
+                }
+            }
+        }
     }
 
     /// Parses an IP range string.
@@ -158,11 +1335,147 @@ impl Bridge {
             Err(format!("Invalid End IP or Octet: '{}'", end_part))
         }
     }
+
+    /// Parses a port list such as "22,80,8000-8010".
+    ///
+    /// Entries are single ports or inclusive ranges separated by commas.
+    /// The result is sorted and deduplicated; port 0 is rejected.
+    pub fn parse_ports(spec: &str) -> Result<Vec<u16>, String> {
+        let parse = |s: &str| match s.trim().parse::<u16>() {
+            Ok(0) | Err(_) => Err(format!("Invalid port: '{}'", s.trim())),
+            Ok(port) => Ok(port),
+        };
+
+        let mut ports = Vec::new();
+        for entry in spec.split(',').filter(|e| !e.trim().is_empty()) {
+            match entry.split_once('-') {
+                Some((lo, hi)) => {
+                    let (lo, hi) = (parse(lo)?, parse(hi)?);
+                    if hi < lo {
+                        return Err(format!("Invalid port range: '{}'", entry.trim()));
+                    }
+                    ports.extend(lo..=hi);
+                }
+                None => ports.push(parse(entry)?),
+            }
+        }
+        if ports.is_empty() {
+            return Err("Empty port list".to_string());
+        }
+        ports.sort_unstable();
+        ports.dedup();
+        Ok(ports)
+    }
+
+    /// Parses port labels such as "8006=Proxmox,32400=Plex".
+    ///
+    /// Entries are `port=label` pairs separated by commas; labels are trimmed
+    /// and must not be empty. A later entry for the same port wins.
+    pub fn parse_port_labels(spec: &str) -> Result<BTreeMap<u16, String>, String> {
+        let mut labels = BTreeMap::new();
+        for entry in spec.split(',').filter(|e| !e.trim().is_empty()) {
+            let (port, label) = entry
+                .split_once('=')
+                .ok_or_else(|| format!("Expected port=label: '{}'", entry.trim()))?;
+            let port = match port.trim().parse::<u16>() {
+                Ok(0) | Err(_) => return Err(format!("Invalid port: '{}'", port.trim())),
+                Ok(port) => port,
+            };
+            let label = label.trim();
+            if label.is_empty() {
+                return Err(format!("Empty label for port {}", port));
+            }
+            labels.insert(port, label.to_string());
+        }
+        Ok(labels)
+    }
+
+    /// Extracts every IPv4 address from free-form text such as a pasted host list.
+    ///
+    /// Anything that is not a dotted quad (labels, commas, newlines, invalid
+    /// octets) is ignored. The result is sorted and deduplicated.
+    pub fn extract_targets(text: &str) -> Vec<Ipv4Addr> {
+        let mut ips: Vec<Ipv4Addr> = text
+            .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .map(|token| token.trim_matches('.'))
+            .filter_map(|token| Ipv4Addr::from_str(token).ok())
+            .collect();
+        ips.sort();
+        ips.dedup();
+        ips
+    }
+
+    /// Folds the full-width characters an East Asian IME types
+    /// (`１９２．１６８．１．１`, `－`, `／`, `，`) and the ideographic full stop
+    /// `。` into their ASCII forms.
+    ///
+    /// Every other character is kept as is.
+    pub fn normalize_input(text: &str) -> String {
+        text.chars()
+            .map(|c| match c {
+                '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+                '\u{3002}' | '\u{FF61}' => '.',
+                '\u{3000}' => ' ',
+                _ => c,
+            })
+            .collect()
+    }
+
+    /// Checks the Start / End fields of a range form the way a scan reads them.
+    ///
+    /// A lone Start address needs an End address or last octet; otherwise the
+    /// Start field may hold ranges or CIDR blocks (End is ignored) or a pasted
+    /// host list. Both fields go through [`Bridge::normalize_input`] first.
+    /// The error is short enough to show next to the fields.
+    pub fn check_range_input(start: &str, end: &str) -> Result<(), String> {
+        let start = Self::normalize_input(start);
+        let end = Self::normalize_input(end);
+        let (start, end) = (start.trim(), end.trim());
+        if start.is_empty() {
+            return Err("Enter a Start IP, range or CIDR block".to_string());
+        }
+        if start.parse::<Ipv4Addr>().is_ok() {
+            if end.is_empty() {
+                return Err("Enter an End IP or last octet".to_string());
+            }
+            return Self::parse_range(&format!("{}-{}", start, end)).map(|_| ());
+        }
+        match ScanTargets::parse(start) {
+            Ok(_) => Ok(()),
+            Err(_) if !Self::extract_targets(start).is_empty() => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Renders a short preview of a target list, e.g. for a confirmation prompt.
+    ///
+    /// Lists at most five addresses followed by the number of remaining ones.
+    pub fn describe_targets(targets: &[Ipv4Addr]) -> String {
+        const SHOWN: usize = 5;
+        let listed = targets
+            .iter()
+            .take(SHOWN)
+            .map(|ip| ip.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        if targets.len() > SHOWN {
+            format!(
+                "{} targets: {} (+{} more)",
+                targets.len(),
+                listed,
+                targets.len() - SHOWN
+            )
+        } else {
+            format!("{} targets: {}", targets.len(), listed)
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::net::MockNet;
+    use crate::types::{ScanStatus, TraceHop};
     use rand::Rng;
 
     #[test]
@@ -195,6 +1508,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_port_found_throttle() {
+        let found = BridgeMessage::PortFound {
+            ip: Ipv4Addr::new(10, 0, 0, 1),
+            port: 80,
+        };
+        let start = Instant::now();
+        let mut throttle = PortFoundThrottle::default();
+        let admitted = (0..PORT_FOUND_BURST * 2)
+            .filter(|_| throttle.admit(&found, start))
+            .count();
+        assert_eq!(admitted, PORT_FOUND_BURST);
+        // Only port reports are held back.
+        assert!(throttle.admit(&BridgeMessage::ScanComplete, start));
+        assert!(throttle.admit(&found, start + PORT_FOUND_WINDOW));
+    }
+
     #[test]
     fn test_parse_invalid() {
         assert!(Bridge::parse_range("not-an-ip").is_err());
@@ -203,6 +1533,667 @@ mod tests {
         assert!(Bridge::parse_range("192.168.1.10-5").is_err()); // End < Start
     }
 
+    impl Default for ScanOptions {
+        fn default() -> Self {
+            Self {
+                udp_scan: false,
+                include_subnet_edges: false,
+                ping_attempts: 1,
+                port_cache: Arc::new(PortCache::default()),
+                notify: None,
+                config: ScanConfig::default(),
+            }
+        }
+    }
+
+    /// Spawns a scan whose completion notice is discarded.
+    fn spawn_scan(
+        net_utils: Arc<dyn NetworkProvider>,
+        ui_tx: Sender<BridgeMessage>,
+        options: ScanOptions,
+        target: ScanTarget,
+    ) -> ActiveScan {
+        let (done_tx, _) = tokio_channel(1);
+        ActiveScan::spawn(0, None, net_utils, ui_tx, done_tx, options, target)
+    }
+
+    fn large_range() -> ScanTarget {
+        ScanTarget::Range(Ipv4Addr::new(10, 0, 0, 0), Ipv4Addr::new(10, 0, 255, 255))
+    }
+
+    #[test]
+    fn test_builder_runs_scans_on_injected_provider() {
+        let bridge = Bridge::builder()
+            .network_provider(Arc::new(MockNet))
+            .channel_capacity(4)
+            .runtime_threads(1)
+            .build();
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        bridge
+            .cmd_tx
+            .blocking_send(BridgeMessage::StartScanRange(ip, ip))
+            .unwrap();
+
+        let mut found = None;
+        loop {
+            match bridge.ui_rx.recv_timeout(Duration::from_secs(5)).unwrap() {
+                BridgeMessage::ScanUpdate(res) => found = Some(res),
+                BridgeMessage::ScanComplete => break,
+                _ => {}
+            }
+        }
+        let res = found.expect("no result for the mock host");
+        assert_eq!(res.status, ScanStatus::Online);
+        assert_eq!(res.mac.as_deref(), Some("00:11:22:33:44:55"));
+    }
+
+    #[test]
+    fn test_shutdown_stops_scans_and_closes_channels() {
+        let mut bridge = Bridge::builder()
+            .network_provider(Arc::new(MockNet))
+            .build();
+        let cmd_tx = bridge.cmd_tx.clone();
+        let ui_rx = bridge.ui_rx.clone();
+        cmd_tx
+            .blocking_send(BridgeMessage::StartScanRange(
+                Ipv4Addr::new(10, 0, 0, 0),
+                Ipv4Addr::new(10, 0, 255, 255),
+            ))
+            .unwrap();
+        assert!(ui_rx.recv_timeout(Duration::from_secs(5)).is_ok());
+
+        assert!(bridge.shutdown());
+        assert!(bridge.shutdown());
+        assert!(cmd_tx.blocking_send(BridgeMessage::StopScan).is_err());
+        drop(bridge);
+        loop {
+            match ui_rx.recv_timeout(Duration::from_secs(5)) {
+                Ok(msg) => assert!(!matches!(msg, BridgeMessage::ScanComplete)),
+                Err(e) => {
+                    assert_eq!(e, RecvTimeoutError::Disconnected);
+                    break;
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deep_scan_runs_during_range_scan() {
+        let (ui_tx, ui_rx) = unbounded();
+        let net: Arc<dyn NetworkProvider> = Arc::new(MockNet);
+        let range = spawn_scan(
+            net.clone(),
+            ui_tx.clone(),
+            ScanOptions::default(),
+            large_range(),
+        );
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        let deep = spawn_deep_scan(&DeepScanner::new(net), ui_tx, ip);
+
+        let range_finished_first = tokio::task::spawn_blocking(move || {
+            for msg in ui_rx.iter() {
+                match msg {
+                    BridgeMessage::DeepScanComplete(host, ports) => {
+                        assert_eq!(host, ip);
+                        assert_eq!(ports.len(), 1);
+                        return false;
+                    }
+                    BridgeMessage::ScanComplete | BridgeMessage::ScanCancelled => return true,
+                    _ => {}
+                }
+            }
+            true
+        })
+        .await
+        .unwrap();
+        assert!(!range_finished_first);
+        range.token.cancel();
+        deep.cancel();
+    }
+
+    #[tokio::test]
+    async fn test_rescan_forwards_only_the_host_result() {
+        let (ui_tx, ui_rx) = unbounded();
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        spawn_rescan(Arc::new(MockNet), ui_tx, ScanOptions::default(), ip);
+
+        // The forwarder drops the sender once the scan has ended.
+        let messages = tokio::task::spawn_blocking(move || ui_rx.iter().collect::<Vec<_>>())
+            .await
+            .unwrap();
+        assert_eq!(messages.len(), 1);
+        match &messages[0] {
+            BridgeMessage::HostRescanned(result) => {
+                assert_eq!(result.ip, ip);
+                assert_eq!(result.status, ScanStatus::Online);
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_traceroute_stops_at_the_target_or_the_hop_limit() {
+        let (ui_tx, ui_rx) = unbounded();
+        let online = Ipv4Addr::new(192, 168, 1, 1);
+        let offline = Ipv4Addr::new(192, 168, 1, 3);
+        let failing = Ipv4Addr::new(192, 168, 1, 2);
+        for ip in [online, offline, failing] {
+            spawn_traceroute(Arc::new(MockNet), ui_tx.clone(), ip);
+        }
+        drop(ui_tx);
+
+        let messages = tokio::task::spawn_blocking(move || ui_rx.iter().collect::<Vec<_>>())
+            .await
+            .unwrap();
+        let hops = |target: Ipv4Addr| -> Vec<TraceHop> {
+            messages
+                .iter()
+                .filter_map(|m| match m {
+                    BridgeMessage::TracerouteHop(ip, hop) if *ip == target => Some(*hop),
+                    _ => None,
+                })
+                .collect()
+        };
+        // MockNet answers directly: one hop to an online host.
+        assert_eq!(hops(online).len(), 1);
+        assert_eq!(hops(online)[0].addr, Some(online));
+        let silent = hops(offline);
+        assert_eq!(silent.len(), usize::from(MAX_TRACE_HOPS));
+        assert!(silent.iter().all(|hop| hop.addr.is_none()));
+        assert_eq!(silent.last().map(|hop| hop.ttl), Some(MAX_TRACE_HOPS));
+        // A local error ends the trace early.
+        assert!(hops(failing).is_empty());
+        for ip in [online, offline, failing] {
+            assert_eq!(
+                messages
+                    .iter()
+                    .filter(|m| matches!(m, BridgeMessage::TracerouteComplete(done) if *done == ip))
+                    .count(),
+                1
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_host_scan_updates_the_host_in_place() {
+        let (ui_tx, ui_rx) = unbounded();
+        let net: Arc<dyn NetworkProvider> = Arc::new(MockNet);
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        let mut options = ScanOptions::default();
+        options.config.ports = Vec::new();
+        spawn_host_scan(
+            net.clone(),
+            &DeepScanner::new(net),
+            ui_tx,
+            options,
+            ip,
+            (1..=1024).collect(),
+        );
+
+        let messages = tokio::task::spawn_blocking(move || ui_rx.iter().collect::<Vec<_>>())
+            .await
+            .unwrap();
+        let updates: Vec<&ScanResult> = messages
+            .iter()
+            .filter_map(|msg| match msg {
+                BridgeMessage::ScanUpdate(res) => Some(res),
+                _ => None,
+            })
+            .collect();
+        assert!(updates.iter().all(|res| res.ip == ip));
+        assert!(updates[0].open_ports.is_empty());
+        let last = updates.last().unwrap();
+        assert_eq!(last.open_ports.len(), 1);
+        assert_eq!(last.open_ports[0].port, 80);
+        assert_eq!(last.open_ports[0].banner.as_deref(), Some("mock-httpd/1.0"));
+        assert!(messages.iter().any(|msg| matches!(
+            msg,
+            BridgeMessage::HostScanProgress(host, 100) if *host == ip
+        )));
+        assert!(matches!(
+            messages.last(),
+            Some(BridgeMessage::HostScanFinished(host)) if *host == ip
+        ));
+        assert!(!messages.iter().any(|msg| matches!(
+            msg,
+            BridgeMessage::ScanComplete | BridgeMessage::Progress(_)
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_queue_policy_runs_scans_in_order() {
+        let (ui_tx, ui_rx) = unbounded();
+        let (done_tx, mut done_rx) = tokio_channel(4);
+        let mut dispatcher = Dispatcher::new(Arc::new(MockNet), ui_tx, done_tx);
+        dispatcher.policy = BusyPolicy::Queue;
+
+        let first = Ipv4Addr::new(192, 168, 1, 1);
+        let second = Ipv4Addr::new(192, 168, 1, 9);
+        let labelled = |name: &str| {
+            let mut options = ScanOptions::default();
+            options.config.label = Some(ScanLabel::new(name));
+            options
+        };
+        dispatcher.request(ScanTarget::Range(first, first), labelled("first"));
+        dispatcher.request(ScanTarget::Range(second, second), labelled("second"));
+        assert!(
+            matches!(ui_rx.recv(), Ok(BridgeMessage::ScanStarted(Some(l))) if l.name == "first")
+        );
+        assert!(matches!(ui_rx.recv(), Ok(BridgeMessage::ScanQueued(1))));
+
+        // Nothing from the queued scan may arrive before the first one finishes.
+        for _ in 0..2 {
+            let id = done_rx.recv().await.unwrap();
+            dispatcher.finished(id);
+        }
+        let events: Vec<String> = ui_rx
+            .try_iter()
+            .filter_map(|msg| match msg {
+                BridgeMessage::ScanUpdate(res) => Some(res.ip.to_string()),
+                BridgeMessage::ScanStarted(label) => label.map(|l| l.name),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            events,
+            [first.to_string(), "second".into(), second.to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reject_policy_refuses_while_busy() {
+        let (ui_tx, ui_rx) = unbounded();
+        let (done_tx, _done_rx) = tokio_channel(4);
+        let mut dispatcher = Dispatcher::new(Arc::new(MockNet), ui_tx, done_tx);
+        dispatcher.policy = BusyPolicy::Reject;
+
+        dispatcher.request(large_range(), ScanOptions::default());
+        dispatcher.request(large_range(), ScanOptions::default());
+        let rejected = tokio::task::spawn_blocking(move || {
+            ui_rx
+                .iter()
+                .any(|msg| matches!(msg, BridgeMessage::ScanRejected))
+        });
+        dispatcher.stop();
+        assert!(rejected.await.unwrap());
+        assert!(dispatcher.active.iter().all(|scan| scan.stopped));
+    }
+
+    #[tokio::test]
+    async fn test_start_after_stop_waits_for_the_stopped_scan() {
+        let (ui_tx, ui_rx) = unbounded();
+        let (done_tx, mut done_rx) = tokio_channel(4);
+        let mut dispatcher = Dispatcher::new(Arc::new(MockNet), ui_tx, done_tx);
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+
+        dispatcher.request(large_range(), ScanOptions::default());
+        dispatcher.request_tagged(ScanId(1), large_range(), ScanOptions::default());
+        dispatcher.stop();
+        dispatcher.request(ScanTarget::Range(ip, ip), ScanOptions::default());
+        dispatcher.request_tagged(ScanId(1), ScanTarget::Range(ip, ip), ScanOptions::default());
+        assert_eq!(dispatcher.active.len(), 2);
+
+        for _ in 0..4 {
+            let id = done_rx.recv().await.unwrap();
+            dispatcher.finished(id);
+        }
+        assert!(dispatcher.active.is_empty());
+
+        let mut events = [Vec::new(), Vec::new()];
+        for msg in ui_rx.try_iter() {
+            let (slot, msg) = match msg {
+                BridgeMessage::Tagged(_, msg) => (1, *msg),
+                msg => (0, msg),
+            };
+            let event = match msg {
+                BridgeMessage::ScanStarted(_) => "started",
+                BridgeMessage::ScanCancelled => "cancelled",
+                BridgeMessage::ScanComplete => "complete",
+                BridgeMessage::ScanUpdate(res) if res.ip == ip => "new",
+                BridgeMessage::ScanUpdate(_) => "old",
+                _ => continue,
+            };
+            if events[slot].last() != Some(&event) {
+                events[slot].push(event);
+            }
+        }
+        // The old scan's messages all arrive before the new one starts.
+        for events in events {
+            let events: Vec<_> = events.into_iter().filter(|e| *e != "old").collect();
+            assert_eq!(
+                events,
+                ["started", "cancelled", "started", "new", "complete"]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stop_reports_cancelled() {
+        let (ui_tx, ui_rx) = unbounded();
+        let scan = spawn_scan(
+            Arc::new(MockNet),
+            ui_tx,
+            ScanOptions::default(),
+            large_range(),
+        );
+        scan.token.cancel();
+
+        let cancelled = tokio::task::spawn_blocking(move || {
+            ui_rx
+                .iter()
+                .any(|msg| matches!(msg, BridgeMessage::ScanCancelled))
+        })
+        .await
+        .unwrap();
+        assert!(cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_stopped_monitor_reports_cancelled_once() {
+        let (ui_tx, ui_rx) = unbounded();
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        let scan = spawn_scan(
+            Arc::new(MockNet),
+            ui_tx,
+            ScanOptions::default(),
+            ScanTarget::Monitor(ip, ip, MIN_MONITOR_INTERVAL),
+        );
+
+        let first = tokio::task::spawn_blocking(move || {
+            let started = ui_rx.recv().unwrap();
+            let first = ui_rx
+                .iter()
+                .find(|m| !matches!(m, BridgeMessage::Phase(_) | BridgeMessage::Progress(_)))
+                .unwrap();
+            (started, first, ui_rx)
+        });
+        let (started, first, ui_rx) = first.await.unwrap();
+        assert!(matches!(started, BridgeMessage::ScanStarted(None)));
+        assert!(matches!(first, BridgeMessage::ScanUpdate(_)));
+        scan.token.cancel();
+
+        let rest = tokio::task::spawn_blocking(move || ui_rx.iter().collect::<Vec<_>>())
+            .await
+            .unwrap();
+        let cancelled = rest
+            .iter()
+            .filter(|m| matches!(m, BridgeMessage::ScanCancelled))
+            .count();
+        assert_eq!(cancelled, 1);
+        assert!(
+            !rest
+                .iter()
+                .any(|m| matches!(m, BridgeMessage::ScanComplete))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_superseded_scan_is_silent() {
+        let (ui_tx, ui_rx) = unbounded();
+        let first = spawn_scan(
+            Arc::new(MockNet),
+            ui_tx.clone(),
+            ScanOptions::default(),
+            large_range(),
+        );
+        first.supersede();
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        let _second = spawn_scan(
+            Arc::new(MockNet),
+            ui_tx,
+            ScanOptions::default(),
+            ScanTarget::Range(ip, ip),
+        );
+
+        let messages = tokio::task::spawn_blocking(move || {
+            ui_rx
+                .iter()
+                .take_while(|msg| !matches!(msg, BridgeMessage::ScanComplete))
+                .collect::<Vec<_>>()
+        })
+        .await
+        .unwrap();
+        assert!(
+            !messages
+                .iter()
+                .any(|msg| matches!(msg, BridgeMessage::ScanCancelled))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tagged_scans_run_side_by_side_and_stop_individually() {
+        let (ui_tx, ui_rx) = unbounded();
+        let (done_tx, _done_rx) = tokio_channel(4);
+        let mut dispatcher = Dispatcher::new(Arc::new(MockNet), ui_tx, done_tx);
+        let (big, small) = (ScanId(1), ScanId(2));
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+
+        dispatcher.request_tagged(big, large_range(), ScanOptions::default());
+        dispatcher.request_tagged(big, large_range(), ScanOptions::default());
+        // Replace only supersedes untagged scans.
+        dispatcher.request(large_range(), ScanOptions::default());
+        dispatcher.request(large_range(), ScanOptions::default());
+        dispatcher.request_tagged(small, ScanTarget::Range(ip, ip), ScanOptions::default());
+        dispatcher.stop_tagged(big);
+
+        let events = tokio::task::spawn_blocking(move || {
+            let mut events = Vec::new();
+            for msg in ui_rx.iter() {
+                let BridgeMessage::Tagged(id, msg) = msg else {
+                    continue;
+                };
+                match *msg {
+                    BridgeMessage::ScanUpdate(res) if id == small => assert_eq!(res.ip, ip),
+                    BridgeMessage::ScanComplete => events.push((id, "complete")),
+                    BridgeMessage::ScanCancelled => events.push((id, "cancelled")),
+                    BridgeMessage::Error(_) => events.push((id, "error")),
+                    _ => {}
+                }
+                if events.len() == 3 {
+                    break;
+                }
+            }
+            events.sort();
+            events
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            events,
+            [(big, "cancelled"), (big, "error"), (small, "complete")]
+        );
+        dispatcher.stop();
+    }
+
+    #[test]
+    fn test_reloaded_settings_retune_only_when_they_differ() {
+        let mut config = Settings::default().scan_config();
+        let saved_range = Settings {
+            last_range: Some("10.0.0.1-50".to_string()),
+            ..Settings::default()
+        };
+        assert!(apply_reloaded_settings(&mut config, Ok(saved_range)).is_none());
+
+        let edited = Settings {
+            ping_timeout_ms: 250,
+            ..Settings::default()
+        };
+        let msg = apply_reloaded_settings(&mut config, Ok(edited));
+        assert!(matches!(msg, Some(BridgeMessage::ConfigReloaded(s)) if s.ping_timeout_ms == 250));
+        assert_eq!(config.ping_timeout, Duration::from_millis(250));
+
+        let broken = Err(GError::Internal(
+            "concurrency must be at least 1".to_string(),
+        ));
+        let msg = apply_reloaded_settings(&mut config, broken);
+        assert!(matches!(msg, Some(BridgeMessage::ConfigReloadFailed(_))));
+        assert_eq!(config.ping_timeout, Duration::from_millis(250));
+    }
+
+    #[tokio::test]
+    async fn test_state_snapshots_answer_for_each_running_scan() {
+        let (ui_tx, ui_rx) = unbounded();
+        let (done_tx, _done_rx) = tokio_channel(4);
+        let mut dispatcher = Dispatcher::new(Arc::new(MockNet), ui_tx, done_tx);
+        dispatcher.request(large_range(), ScanOptions::default());
+        dispatcher.request_tagged(ScanId(7), large_range(), ScanOptions::default());
+        dispatcher.send_snapshots();
+        dispatcher.stop();
+
+        let tags: Vec<Option<ScanId>> = ui_rx
+            .try_iter()
+            .filter_map(|msg| match msg {
+                BridgeMessage::StateSnapshot(_) => Some(None),
+                BridgeMessage::Tagged(id, msg) => {
+                    matches!(*msg, BridgeMessage::StateSnapshot(_)).then_some(Some(id))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(tags, [None, Some(ScanId(7))]);
+    }
+
+    #[tokio::test]
+    async fn test_sleep_pauses_scans_until_the_frontend_decides() {
+        let (ui_tx, ui_rx) = unbounded();
+        let (done_tx, _done_rx) = tokio_channel(4);
+        let mut dispatcher = Dispatcher::new(Arc::new(MockNet), ui_tx, done_tx);
+        dispatcher.request(large_range(), ScanOptions::default());
+        dispatcher.request_tagged(ScanId(1), large_range(), ScanOptions::default());
+        let paused = |dispatcher: &Dispatcher| {
+            dispatcher
+                .active
+                .iter()
+                .map(|scan| *scan.paused.borrow())
+                .collect::<Vec<_>>()
+        };
+
+        dispatcher.power_changed(PowerState {
+            suspended: true,
+            sleeps: 1,
+        });
+        assert_eq!(paused(&dispatcher), [true, true]);
+        dispatcher.power_changed(PowerState {
+            suspended: false,
+            sleeps: 1,
+        });
+        // The tagged scan has no one to ask and carries on.
+        assert_eq!(paused(&dispatcher), [true, false]);
+        dispatcher.stop();
+
+        let events: Vec<_> = ui_rx
+            .try_iter()
+            .filter(|msg| {
+                matches!(
+                    msg,
+                    BridgeMessage::SystemSuspended | BridgeMessage::SystemResumed
+                )
+            })
+            .collect();
+        assert!(matches!(
+            events[..],
+            [BridgeMessage::SystemSuspended, BridgeMessage::SystemResumed]
+        ));
+    }
+
+    #[test]
+    fn test_parse_target_multi_ranges() {
+        assert!(matches!(
+            Bridge::parse_target("10.0.1.0/24,10.0.5.1-50"),
+            Ok(ScanTarget::Multi(t)) if t.ranges().len() == 2
+        ));
+        // A lone CIDR block is an ordinary range.
+        assert!(matches!(
+            Bridge::parse_target("10.0.1.0/24"),
+            Ok(ScanTarget::Range(start, end))
+                if start == Ipv4Addr::new(10, 0, 1, 0) && end == Ipv4Addr::new(10, 0, 1, 255)
+        ));
+        // Messy text still falls back to address extraction.
+        assert!(matches!(
+            Bridge::parse_target("router 10.0.0.1, nas 10.0.0.7"),
+            Ok(ScanTarget::List(t)) if t.len() == 2
+        ));
+    }
+
+    #[test]
+    fn test_parse_port_labels() {
+        let labels = Bridge::parse_port_labels("8006=Proxmox, 32400 = Plex ,8006=PVE").unwrap();
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels[&8006], "PVE");
+        assert_eq!(labels[&32400], "Plex");
+        assert!(Bridge::parse_port_labels("").unwrap().is_empty());
+        assert!(Bridge::parse_port_labels("8006").is_err());
+        assert!(Bridge::parse_port_labels("0=Zero").is_err());
+        assert!(Bridge::parse_port_labels("8006=").is_err());
+    }
+
+    #[test]
+    fn test_parse_ports() {
+        assert_eq!(
+            Bridge::parse_ports("443, 22,80-82,80"),
+            Ok(vec![22, 80, 81, 82, 443])
+        );
+        assert!(Bridge::parse_ports("0").is_err());
+        assert!(Bridge::parse_ports("90-80").is_err());
+        assert!(Bridge::parse_ports("ssh").is_err());
+        assert!(Bridge::parse_ports(" , ").is_err());
+    }
+
+    #[test]
+    fn test_check_range_input_explains_the_problem() {
+        assert_eq!(
+            Bridge::normalize_input("１９２．１６８．１．１－２５４"),
+            "192.168.1.1-254"
+        );
+        assert_eq!(Bridge::normalize_input("10。0。0。0／８"), "10.0.0.0/8");
+
+        assert!(Bridge::check_range_input("192.168.1.1", "255").is_ok());
+        assert!(Bridge::check_range_input("１９２．１６８．１．１", "１０").is_ok());
+        assert!(Bridge::check_range_input("10.0.0.0/24, 10.0.2.1-9", "").is_ok());
+        assert!(Bridge::check_range_input("web 10.0.0.5\ndb 10.0.0.6", "").is_ok());
+
+        assert_eq!(
+            Bridge::check_range_input(" ", "255"),
+            Err("Enter a Start IP, range or CIDR block".to_string())
+        );
+        assert_eq!(
+            Bridge::check_range_input("192.168.1.1", ""),
+            Err("Enter an End IP or last octet".to_string())
+        );
+        assert_eq!(
+            Bridge::check_range_input("192.168.1.10", "5"),
+            Err("End IP (192.168.1.5) cannot be less than Start IP (192.168.1.10)".to_string())
+        );
+        assert_eq!(
+            Bridge::check_range_input("192.168.1.1", "300"),
+            Err("Invalid End IP or Octet: '300'".to_string())
+        );
+        assert!(Bridge::check_range_input("192.168.1", "").is_err());
+    }
+
+    #[test]
+    fn test_extract_targets_from_messy_text() {
+        let text = "Host 192.168.1.4 \n 192.168.1.9, 192.168.1.20\n(gw: 192.168.1.4.) 300.1.1.1";
+        assert_eq!(
+            Bridge::extract_targets(text),
+            vec![
+                Ipv4Addr::new(192, 168, 1, 4),
+                Ipv4Addr::new(192, 168, 1, 9),
+                Ipv4Addr::new(192, 168, 1, 20),
+            ]
+        );
+        assert!(Bridge::extract_targets("no addresses here").is_empty());
+    }
+
+    #[test]
+    fn test_describe_targets_truncates() {
+        let targets: Vec<Ipv4Addr> = (1..=7).map(|i| Ipv4Addr::new(10, 0, 0, i)).collect();
+        assert_eq!(
+            Bridge::describe_targets(&targets),
+            "7 targets: 10.0.0.1, 10.0.0.2, 10.0.0.3, 10.0.0.4, 10.0.0.5 (+2 more)"
+        );
+    }
+
     #[test]
     fn test_parse_ui_generated_range_randomized() {
         let mut rng = rand::thread_rng();
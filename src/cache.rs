@@ -0,0 +1,159 @@
+//! Time-limited cache of per-host port scan results.
+//!
+//! Successive scans of the same network (e.g. an auto-refresh loop) mostly
+//! rediscover the same hosts with the same services. [`PortCache`] lets the
+//! scanner skip the port phase for a host that is still online with the same
+//! MAC address as when its ports were last scanned.
+
+use crate::types::{PortInfo, ScanResult};
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Port findings remembered for one host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedPorts {
+    pub open_ports: Vec<PortInfo>,
+    pub open_udp_ports: Vec<u16>,
+    pub filtered_udp_ports: Vec<u16>,
+    /// Whether the UDP phase ran when this entry was stored.
+    pub udp_scanned: bool,
+}
+
+struct Entry {
+    mac: Option<String>,
+    ports: CachedPorts,
+    stored_at: Instant,
+}
+
+/// Shared, thread-safe port result cache with a configurable TTL.
+///
+/// A TTL of zero disables the cache: lookups always miss and nothing is stored.
+pub struct PortCache {
+    ttl: Mutex<Duration>,
+    entries: Mutex<HashMap<Ipv4Addr, Entry>>,
+}
+
+impl PortCache {
+    /// Creates an empty cache whose entries expire after `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl: Mutex::new(ttl),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Changes the TTL. Setting it to zero also drops all entries.
+    pub fn set_ttl(&self, ttl: Duration) {
+        if let Ok(mut current) = self.ttl.lock() {
+            *current = ttl;
+        }
+        if ttl.is_zero() {
+            self.clear();
+        }
+    }
+
+    /// Returns the current TTL.
+    pub fn ttl(&self) -> Duration {
+        self.ttl.lock().map(|t| *t).unwrap_or_default()
+    }
+
+    /// Returns the cached ports for `ip` if the entry is fresh, was stored for
+    /// the same MAC address, and covers UDP when `need_udp` is set.
+    pub fn get(&self, ip: Ipv4Addr, mac: Option<&str>, need_udp: bool) -> Option<CachedPorts> {
+        let ttl = self.ttl();
+        if ttl.is_zero() {
+            return None;
+        }
+        let entries = self.entries.lock().ok()?;
+        let entry = entries.get(&ip)?;
+        let fresh = entry.stored_at.elapsed() < ttl;
+        let same_device = entry.mac.as_deref() == mac;
+        let covers_udp = entry.ports.udp_scanned || !need_udp;
+        (fresh && same_device && covers_udp).then(|| entry.ports.clone())
+    }
+
+    /// Stores the port findings of a freshly scanned, online host.
+    pub fn insert(&self, result: &ScanResult, udp_scanned: bool) {
+        if self.ttl().is_zero() {
+            return;
+        }
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(
+                result.ip,
+                Entry {
+                    mac: result.mac.clone(),
+                    ports: CachedPorts {
+                        open_ports: result.open_ports.clone(),
+                        open_udp_ports: result.open_udp_ports.clone(),
+                        filtered_udp_ports: result.filtered_udp_ports.clone(),
+                        udp_scanned,
+                    },
+                    stored_at: Instant::now(),
+                },
+            );
+        }
+    }
+
+    /// Drops every cached entry.
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+}
+
+impl Default for PortCache {
+    /// A disabled cache (TTL of zero).
+    fn default() -> Self {
+        Self::new(Duration::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(mac: &str, ports: &[u16]) -> ScanResult {
+        let mut res = ScanResult::new(Ipv4Addr::new(10, 0, 0, 5));
+        res.mac = Some(mac.to_string());
+        res.open_ports = ports.iter().map(|&p| PortInfo::new(p)).collect();
+        res
+    }
+
+    #[test]
+    fn test_hit_requires_same_mac() {
+        let cache = PortCache::new(Duration::from_secs(60));
+        let ip = Ipv4Addr::new(10, 0, 0, 5);
+        cache.insert(&result("AA:AA:AA:AA:AA:AA", &[22]), false);
+
+        let hit = cache.get(ip, Some("AA:AA:AA:AA:AA:AA"), false).unwrap();
+        assert_eq!(hit.open_ports, vec![PortInfo::new(22)]);
+        assert!(cache.get(ip, Some("BB:BB:BB:BB:BB:BB"), false).is_none());
+        assert!(cache.get(ip, None, false).is_none());
+    }
+
+    #[test]
+    fn test_udp_requirement_and_expiry() {
+        let cache = PortCache::new(Duration::from_millis(20));
+        let ip = Ipv4Addr::new(10, 0, 0, 5);
+        cache.insert(&result("AA:AA:AA:AA:AA:AA", &[22]), false);
+        assert!(cache.get(ip, Some("AA:AA:AA:AA:AA:AA"), true).is_none());
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(cache.get(ip, Some("AA:AA:AA:AA:AA:AA"), false).is_none());
+    }
+
+    #[test]
+    fn test_zero_ttl_disables() {
+        let cache = PortCache::default();
+        cache.insert(&result("AA:AA:AA:AA:AA:AA", &[22]), false);
+        cache.set_ttl(Duration::from_secs(60));
+        assert!(
+            cache
+                .get(Ipv4Addr::new(10, 0, 0, 5), Some("AA:AA:AA:AA:AA:AA"), false)
+                .is_none()
+        );
+    }
+}
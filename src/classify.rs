@@ -0,0 +1,308 @@
+//! Device-type classification from what the scan already found.
+//!
+//! Like [`fingerprint`](crate::fingerprint), nothing extra is sent on the
+//! wire: [`classify`] weighs the MAC vendor, open ports, host name, mDNS
+//! services, UPnP and SNMP descriptions and the OS guess. Each hint adds
+//! points to a [`DeviceType`] and the best-scoring type wins if it has
+//! collected at least [`MIN_POINTS`].
+
+use crate::types::{DeviceType, OsFamily, ScanResult};
+
+/// Below this a guess is left out: a lone weak hint says little.
+pub const MIN_POINTS: u32 = 30;
+
+/// Vendor name fragments (lowercase) hinting at a type, with their weight.
+const VENDOR_HINTS: &[(&str, DeviceType, u32)] = &[
+    ("hikvision", DeviceType::Camera, 60),
+    ("dahua", DeviceType::Camera, 60),
+    ("axis communications", DeviceType::Camera, 60),
+    ("reolink", DeviceType::Camera, 60),
+    ("amcrest", DeviceType::Camera, 60),
+    ("brother", DeviceType::Printer, 50),
+    ("canon", DeviceType::Printer, 40),
+    ("seiko epson", DeviceType::Printer, 50),
+    ("lexmark", DeviceType::Printer, 50),
+    ("xerox", DeviceType::Printer, 50),
+    ("kyocera", DeviceType::Printer, 50),
+    ("ricoh", DeviceType::Printer, 50),
+    ("synology", DeviceType::Nas, 60),
+    ("qnap", DeviceType::Nas, 60),
+    ("western digital", DeviceType::Nas, 30),
+    ("cisco", DeviceType::Router, 40),
+    ("juniper", DeviceType::Router, 40),
+    ("mikrotik", DeviceType::Router, 50),
+    ("routerboard", DeviceType::Router, 50),
+    ("ubiquiti", DeviceType::Router, 40),
+    ("aruba", DeviceType::Router, 40),
+    ("netgear", DeviceType::Router, 30),
+    ("tp-link", DeviceType::Router, 30),
+    ("zyxel", DeviceType::Router, 30),
+    ("arris", DeviceType::Router, 40),
+    ("avm", DeviceType::Router, 40),
+    ("espressif", DeviceType::Iot, 50),
+    ("tuya", DeviceType::Iot, 50),
+    ("shelly", DeviceType::Iot, 50),
+    ("signify", DeviceType::Iot, 40),
+    ("nest labs", DeviceType::Iot, 40),
+    ("ring llc", DeviceType::Camera, 40),
+    ("roku", DeviceType::MediaPlayer, 50),
+    ("sonos", DeviceType::MediaPlayer, 50),
+    ("intel", DeviceType::Pc, 30),
+    ("dell", DeviceType::Pc, 30),
+    ("lenovo", DeviceType::Pc, 30),
+    ("micro-star", DeviceType::Pc, 30),
+    ("giga-byte", DeviceType::Pc, 30),
+    ("asrock", DeviceType::Pc, 30),
+    ("samsung", DeviceType::Phone, 20),
+    ("xiaomi", DeviceType::Phone, 20),
+    ("oneplus", DeviceType::Phone, 30),
+    // Most hosts hiding behind a per-network MAC
+    // (`RANDOMIZED_MAC_VENDOR`) are phones.
+    ("randomized mac", DeviceType::Phone, 30),
+];
+
+/// Open TCP ports hinting at a type, with their weight.
+const PORT_HINTS: &[(u16, DeviceType, u32)] = &[
+    (9100, DeviceType::Printer, 50),
+    (515, DeviceType::Printer, 40),
+    (631, DeviceType::Printer, 30),
+    (554, DeviceType::Camera, 40),
+    (5000, DeviceType::Nas, 25),
+    (5001, DeviceType::Nas, 25),
+    (548, DeviceType::Nas, 15),
+    (53, DeviceType::Router, 30),
+    (3389, DeviceType::Pc, 40),
+    (135, DeviceType::Pc, 15),
+    (62078, DeviceType::Phone, 60),
+    (8008, DeviceType::MediaPlayer, 30),
+    (8009, DeviceType::MediaPlayer, 30),
+    (1883, DeviceType::Iot, 30),
+    (8883, DeviceType::Iot, 30),
+];
+
+/// DNS-SD service types hinting at a type, with their weight.
+const SERVICE_HINTS: &[(&str, DeviceType, u32)] = &[
+    ("_ipp._tcp", DeviceType::Printer, 50),
+    ("_ipps._tcp", DeviceType::Printer, 50),
+    ("_printer._tcp", DeviceType::Printer, 50),
+    ("_pdl-datastream._tcp", DeviceType::Printer, 50),
+    ("_rtsp._tcp", DeviceType::Camera, 30),
+    ("_googlecast._tcp", DeviceType::MediaPlayer, 50),
+    ("_airplay._tcp", DeviceType::MediaPlayer, 30),
+    ("_raop._tcp", DeviceType::MediaPlayer, 20),
+    ("_hap._tcp", DeviceType::Iot, 40),
+    ("_apple-mobdev2._tcp", DeviceType::Phone, 50),
+    ("_companion-link._tcp", DeviceType::Phone, 20),
+    ("_adisk._tcp", DeviceType::Nas, 30),
+    ("_rdp._tcp", DeviceType::Pc, 30),
+];
+
+/// Word prefixes (lowercase) in host names and UPnP/SNMP descriptions, with
+/// their weight. Words are split on anything not alphanumeric, so
+/// `DESKTOP-4F2K9` yields `desktop` and `BRN3C2AF4` starts with `brn`.
+const NAME_HINTS: &[(&str, DeviceType, u32)] = &[
+    ("router", DeviceType::Router, 40),
+    ("gateway", DeviceType::Router, 30),
+    ("fritz", DeviceType::Router, 40),
+    ("switch", DeviceType::Router, 30),
+    ("printer", DeviceType::Printer, 40),
+    ("laserjet", DeviceType::Printer, 50),
+    ("officejet", DeviceType::Printer, 50),
+    ("deskjet", DeviceType::Printer, 50),
+    ("brn", DeviceType::Printer, 30),
+    ("npi", DeviceType::Printer, 30),
+    ("diskstation", DeviceType::Nas, 50),
+    ("nas", DeviceType::Nas, 40),
+    ("camera", DeviceType::Camera, 40),
+    ("ipcam", DeviceType::Camera, 40),
+    ("iphone", DeviceType::Phone, 50),
+    ("ipad", DeviceType::Phone, 40),
+    ("android", DeviceType::Phone, 40),
+    ("galaxy", DeviceType::Phone, 40),
+    ("pixel", DeviceType::Phone, 30),
+    ("desktop", DeviceType::Pc, 40),
+    ("laptop", DeviceType::Pc, 40),
+    ("macbook", DeviceType::Pc, 50),
+    ("imac", DeviceType::Pc, 50),
+    ("chromecast", DeviceType::MediaPlayer, 50),
+    ("appletv", DeviceType::MediaPlayer, 50),
+    ("roku", DeviceType::MediaPlayer, 50),
+    ("tv", DeviceType::MediaPlayer, 30),
+    ("esp", DeviceType::Iot, 30),
+    ("shelly", DeviceType::Iot, 40),
+    ("tasmota", DeviceType::Iot, 40),
+];
+
+/// Types in tie-break order: the more common answer comes first.
+const TYPES: [DeviceType; 8] = [
+    DeviceType::Pc,
+    DeviceType::Phone,
+    DeviceType::Router,
+    DeviceType::Printer,
+    DeviceType::Nas,
+    DeviceType::MediaPlayer,
+    DeviceType::Camera,
+    DeviceType::Iot,
+];
+
+#[derive(Default)]
+struct Scores {
+    points: [u32; TYPES.len()],
+}
+
+impl Scores {
+    fn add(&mut self, kind: DeviceType, points: u32) {
+        let i = TYPES.iter().position(|t| *t == kind).unwrap_or(0);
+        self.points[i] += points;
+    }
+
+    /// Adds the best [`NAME_HINTS`] match among the words of `text`.
+    fn add_name_hints(&mut self, text: &str) {
+        let lower = text.to_lowercase();
+        let best = lower
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .filter_map(|word| {
+                NAME_HINTS
+                    .iter()
+                    .find(|(prefix, ..)| word.starts_with(prefix))
+            })
+            .max_by_key(|(_, _, points)| *points);
+        if let Some(&(_, kind, points)) = best {
+            self.add(kind, points);
+        }
+    }
+}
+
+/// Guesses what kind of device `res` is.
+///
+/// - **Vendor**: OUI vendors such as Hikvision (camera), Brother (printer)
+///   or Synology (NAS); a randomized MAC hints at a phone.
+/// - **Ports**: 9100/515/631 (printing), 554 (RTSP), 5000/5001 (DSM),
+///   53 (DNS), 3389 (RDP), 62078 (iOS sync), 8008/8009 (Cast), 1883 (MQTT).
+/// - **Names**: host name words such as `iphone`, `desktop` or `laserjet`,
+///   and the same words in the UPnP friendly name, model and manufacturer
+///   and the SNMP description.
+/// - **Services**: mDNS types such as `_ipp._tcp` or `_googlecast._tcp`.
+/// - **OS guess**: a network OS suggests a router, a NAS OS a NAS and
+///   Windows or macOS a PC.
+///
+/// Returns `None` when no type reaches [`MIN_POINTS`].
+pub fn classify(res: &ScanResult) -> Option<DeviceType> {
+    let mut scores = Scores::default();
+
+    if let Some(vendor) = res.vendor.as_deref() {
+        let lower = vendor.to_lowercase();
+        if let Some(&(_, kind, points)) = VENDOR_HINTS
+            .iter()
+            .find(|(fragment, ..)| lower.contains(fragment))
+        {
+            scores.add(kind, points);
+        }
+    }
+
+    for &(port, kind, points) in PORT_HINTS {
+        if res.open_ports.iter().any(|p| p.port == port) {
+            scores.add(kind, points);
+        }
+    }
+
+    for &(service, kind, points) in SERVICE_HINTS {
+        if res.services.iter().any(|s| s.eq_ignore_ascii_case(service)) {
+            scores.add(kind, points);
+        }
+    }
+
+    if let Some(hostname) = res.hostname.as_deref() {
+        scores.add_name_hints(hostname);
+    }
+    if let Some(upnp) = &res.upnp {
+        for text in [&upnp.friendly_name, &upnp.model, &upnp.manufacturer]
+            .into_iter()
+            .flatten()
+        {
+            scores.add_name_hints(text);
+        }
+    }
+    if let Some(description) = res.snmp.as_ref().and_then(|s| s.description.as_deref()) {
+        scores.add_name_hints(description);
+    }
+
+    if let Some(guess) = &res.os_guess {
+        match guess.family {
+            OsFamily::NetworkDevice => scores.add(DeviceType::Router, 20),
+            OsFamily::Nas => scores.add(DeviceType::Nas, 20),
+            OsFamily::Windows | OsFamily::MacOs => scores.add(DeviceType::Pc, 15),
+            OsFamily::Linux => {}
+        }
+    }
+
+    // `max_by_key` keeps the last maximum, so scan in reverse to prefer earlier types.
+    let (best, &points) = scores
+        .points
+        .iter()
+        .enumerate()
+        .rev()
+        .max_by_key(|(_, points)| **points)?;
+    (points >= MIN_POINTS).then_some(TYPES[best])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{OsGuess, PortInfo, UpnpInfo};
+    use std::net::Ipv4Addr;
+
+    fn host(ports: &[u16]) -> ScanResult {
+        let mut res = ScanResult::new(Ipv4Addr::new(192, 168, 1, 10));
+        res.open_ports = ports.iter().map(|&p| PortInfo::new(p)).collect();
+        res
+    }
+
+    #[test]
+    fn test_printer_from_ports_and_services() {
+        let mut res = host(&[80, 631, 9100]);
+        assert_eq!(classify(&res), Some(DeviceType::Printer));
+        res.open_ports.clear();
+        res.services = vec!["_ipp._tcp".to_string()];
+        assert_eq!(classify(&res), Some(DeviceType::Printer));
+    }
+
+    #[test]
+    fn test_host_name_words_and_upnp_descriptions() {
+        let mut res = host(&[]);
+        res.hostname = Some("iPhone-de-Ana.local".to_string());
+        assert_eq!(classify(&res), Some(DeviceType::Phone));
+        res.hostname = Some("DESKTOP-4F2K9".to_string());
+        assert_eq!(classify(&res), Some(DeviceType::Pc));
+        // Only word prefixes count: "canvas" is not a NAS.
+        res.hostname = Some("canvas".to_string());
+        assert_eq!(classify(&res), None);
+
+        res.upnp = Some(UpnpInfo {
+            friendly_name: Some("Living Room".to_string()),
+            manufacturer: Some("Google Inc.".to_string()),
+            model: Some("Chromecast".to_string()),
+        });
+        assert_eq!(classify(&res), Some(DeviceType::MediaPlayer));
+    }
+
+    #[test]
+    fn test_vendor_and_os_guess_combine() {
+        let mut res = host(&[53, 80]);
+        res.vendor = Some("MikroTik".to_string());
+        res.os_guess = Some(OsGuess {
+            family: OsFamily::NetworkDevice,
+            confidence: 80,
+            evidence: Vec::new(),
+        });
+        assert_eq!(classify(&res), Some(DeviceType::Router));
+
+        let mut camera = host(&[80, 554]);
+        camera.vendor = Some("Hangzhou Hikvision Digital Technology".to_string());
+        assert_eq!(classify(&camera), Some(DeviceType::Camera));
+
+        // A web server alone is not enough to go on.
+        assert_eq!(classify(&host(&[80, 443])), None);
+    }
+}
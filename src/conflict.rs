@@ -0,0 +1,221 @@
+//! Hunting intermittent IP conflicts.
+//!
+//! Two devices configured with the same address both answer ARP requests for
+//! it and whichever answers first wins, so a conflict shows up as the
+//! address's MAC flipping every now and then. [`ConflictWatch`] ARPs a single
+//! address over and over and reports every flip as a
+//! [`BridgeMessage::IpConflict`].
+
+use crate::net::NetworkProvider;
+use crate::types::{BridgeMessage, GError, ScanResult, ScanStatus};
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc::Sender;
+use tokio_util::sync::CancellationToken;
+
+/// Time between two ARP requests if none is given.
+pub const DEFAULT_CONFLICT_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Shortest interval the bridge accepts between two ARP requests.
+pub const MIN_CONFLICT_WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// ARPs one address repeatedly and reports when another MAC answers.
+pub struct ConflictWatch {
+    net: Arc<dyn NetworkProvider>,
+    tx: Sender<BridgeMessage>,
+    ip: Ipv4Addr,
+    /// Every MAC that answered, in order of first answer.
+    macs: Vec<String>,
+    /// MAC of the latest answer.
+    current: Option<String>,
+}
+
+impl ConflictWatch {
+    /// Creates a watch of `ip` reporting to `tx`.
+    pub fn new(net: Arc<dyn NetworkProvider>, tx: Sender<BridgeMessage>, ip: Ipv4Addr) -> Self {
+        Self {
+            net,
+            tx,
+            ip,
+            macs: Vec::new(),
+            current: None,
+        }
+    }
+
+    /// Resolves the address's MAC every `interval` until `cancel_token`
+    /// fires, then sends [`BridgeMessage::ScanCancelled`].
+    ///
+    /// The first answer and every change of MAC are sent as an online
+    /// [`BridgeMessage::ScanUpdate`]; a change is followed by a
+    /// [`BridgeMessage::IpConflict`]. Requests nobody answers are ignored,
+    /// as conflicting devices come and go. A failed request stops the watch
+    /// after forwarding the error while nothing has answered yet, and is
+    /// only logged afterwards.
+    pub async fn run(mut self, interval: Duration, cancel_token: CancellationToken) {
+        log::info!("Watching {} for IP conflicts", self.ip);
+        loop {
+            let (net, ip) = (self.net.clone(), self.ip);
+            let answer = match tokio::task::spawn_blocking(move || net.resolve_mac(ip)).await {
+                Ok(answer) => answer,
+                Err(e) => Err(GError::Internal(format!("ARP request panicked: {}", e))),
+            };
+            match answer {
+                Ok(Some(mac)) => {
+                    for event in self.update(mac, SystemTime::now()) {
+                        let _ = self.tx.send(event).await;
+                    }
+                }
+                Ok(None) => log::debug!("No ARP answer from {}", self.ip),
+                Err(e) if self.macs.is_empty() => {
+                    let _ = self.tx.send(BridgeMessage::Error(e)).await;
+                    return;
+                }
+                Err(e) => log::warn!("ARP request for {} failed: {}", self.ip, e),
+            }
+
+            tokio::select! {
+                _ = cancel_token.cancelled() => break,
+                _ = tokio::time::sleep(interval) => {}
+            }
+        }
+
+        log::info!(
+            "Conflict watch of {} stopped; {} MACs answered.",
+            self.ip,
+            self.macs.len()
+        );
+        let _ = self.tx.send(BridgeMessage::ScanCancelled).await;
+    }
+
+    /// Records an answer from `mac` and returns the messages it produces.
+    fn update(&mut self, mac: String, now: SystemTime) -> Vec<BridgeMessage> {
+        if self.current.as_deref() == Some(mac.as_str()) {
+            return Vec::new();
+        }
+        if !self.macs.contains(&mac) {
+            self.macs.push(mac.clone());
+        }
+        let mut res = ScanResult::new(self.ip);
+        res.status = ScanStatus::Online;
+        res.vendor = self.net.resolve_vendor(&mac);
+        res.mac = Some(mac.clone());
+        res.stamp(Duration::ZERO, now);
+
+        let mut messages = vec![BridgeMessage::ScanUpdate(res)];
+        if let Some(previous) = self.current.replace(mac.clone()) {
+            log::warn!("{} now answered by {}, was {}", self.ip, mac, previous);
+            messages.push(BridgeMessage::IpConflict {
+                ip: self.ip,
+                previous,
+                current: mac,
+                macs: self.macs.clone(),
+            });
+        }
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::MockNet;
+    use crate::types::UdpPortState;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+    use tokio::sync::mpsc::channel;
+
+    /// Serves a scripted sequence of ARP answers, repeating the last one.
+    struct FlappingNet {
+        answers: Mutex<Vec<Result<Option<&'static str>, GError>>>,
+    }
+
+    #[async_trait]
+    impl NetworkProvider for FlappingNet {
+        fn echo(&self, _ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+            Ok(None)
+        }
+        fn resolve_mac(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            let mut answers = self.answers.lock().unwrap();
+            let answer = if answers.len() > 1 {
+                answers.remove(0)
+            } else {
+                answers[0].clone()
+            };
+            answer.map(|mac| mac.map(str::to_string))
+        }
+        fn resolve_hostname(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            Ok(None)
+        }
+        fn resolve_vendor(&self, mac: &str) -> Option<String> {
+            MockNet.resolve_vendor(mac)
+        }
+        async fn scan_port(&self, _ip: Ipv4Addr, _port: u16) -> bool {
+            false
+        }
+        async fn scan_udp_port(&self, _ip: Ipv4Addr, _port: u16) -> UdpPortState {
+            UdpPortState::Closed
+        }
+    }
+
+    async fn watch(answers: Vec<Result<Option<&'static str>, GError>>) -> Vec<BridgeMessage> {
+        let net = FlappingNet {
+            answers: Mutex::new(answers),
+        };
+        let (tx, mut rx) = channel(100);
+        let ip = Ipv4Addr::new(10, 0, 0, 5);
+        let token = CancellationToken::new();
+        let watch = ConflictWatch::new(Arc::new(net), tx, ip);
+        let task = tokio::spawn(watch.run(Duration::from_millis(5), token.clone()));
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        token.cancel();
+        task.await.unwrap();
+        let mut messages = Vec::new();
+        while let Some(msg) = rx.recv().await {
+            messages.push(msg);
+        }
+        messages
+    }
+
+    #[tokio::test]
+    async fn test_reports_each_change_of_mac() {
+        let (a, b) = ("AA:AA:AA:AA:AA:AA", "BB:BB:BB:BB:BB:BB");
+        let messages = watch(vec![
+            Ok(Some(a)),
+            Ok(None),
+            Ok(Some(a)),
+            Err(GError::Internal("timeout".to_string())),
+            Ok(Some(b)),
+            Ok(Some(a)),
+        ])
+        .await;
+
+        let conflicts: Vec<(&str, &str, usize)> = messages
+            .iter()
+            .filter_map(|msg| match msg {
+                BridgeMessage::IpConflict {
+                    previous,
+                    current,
+                    macs,
+                    ..
+                } => Some((previous.as_str(), current.as_str(), macs.len())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(conflicts, [(a, b, 2), (b, a, 2)]);
+        assert!(matches!(&messages[0], BridgeMessage::ScanUpdate(r)
+            if r.mac.as_deref() == Some(a) && r.status == ScanStatus::Online
+                && r.vendor.as_deref() == Some("Mock Vendor")));
+        assert_eq!(messages.len(), 6);
+        assert!(matches!(
+            messages.last(),
+            Some(BridgeMessage::ScanCancelled)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stops_when_the_first_request_fails() {
+        let messages = watch(vec![Err(GError::Internal("not on link".to_string()))]).await;
+        assert!(matches!(messages[..], [BridgeMessage::Error(_)]));
+    }
+}
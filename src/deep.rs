@@ -0,0 +1,188 @@
+//! Per-host deep port scans that run alongside range scans.
+//!
+//! A range scan only probes [`COMMON_PORTS`]. [`DeepScanner`] probes a much
+//! larger port list on a single host, typically requested from a detail view
+//! while a range scan is still in progress. It shares the
+//! [`NetworkProvider`] with the range scan but limits its own concurrency
+//! with a separate semaphore, and every message it emits carries the host
+//! address so frontends can route it independently of the range scan.
+
+use crate::net::{HostLimitProvider, NetworkProvider};
+use crate::scanner::adaptive_port_timeout;
+use crate::types::{BridgeMessage, COMMON_PORTS, LatencyStats, PortInfo};
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::sync::mpsc::Sender;
+use tokio_util::sync::CancellationToken;
+
+/// Maximum simultaneous port probes across all deep scans.
+const MAX_DEEP_SCAN_PROBES: usize = 64;
+
+/// Ports probed by a deep scan: the well-known range plus every common port above it.
+pub fn deep_scan_ports() -> Vec<u16> {
+    let mut ports: Vec<u16> = (1..=1024).collect();
+    ports.extend(COMMON_PORTS.iter().map(|(p, _)| *p).filter(|p| *p > 1024));
+    ports
+}
+
+/// Runs deep port scans on individual hosts.
+///
+/// Clones share the probe semaphore, so concurrent deep scans of several
+/// hosts together stay within [`MAX_DEEP_SCAN_PROBES`].
+#[derive(Clone)]
+pub struct DeepScanner {
+    net_utils: Arc<dyn NetworkProvider>,
+    semaphore: Arc<Semaphore>,
+}
+
+impl DeepScanner {
+    /// Creates a deep scanner using the given provider.
+    pub fn new(net_utils: Arc<dyn NetworkProvider>) -> Self {
+        Self {
+            net_utils,
+            semaphore: Arc::new(Semaphore::new(MAX_DEEP_SCAN_PROBES)),
+        }
+    }
+
+    /// A clone whose connections count against `limit` per host, shared
+    /// with scans using the same cap; see [`HostLimitProvider`]. `None`
+    /// returns a plain clone.
+    pub fn with_host_limit(&self, limit: Option<usize>) -> Self {
+        let mut scanner = self.clone();
+        if let Some(limit) = limit {
+            scanner.net_utils = Arc::new(HostLimitProvider::new(scanner.net_utils, limit));
+        }
+        scanner
+    }
+
+    /// Probes `ports` on `ip`, then grabs banners for the open ones.
+    ///
+    /// Sends [`BridgeMessage::DeepScanPort`] for each open port as it is found,
+    /// [`BridgeMessage::DeepScanProgress`] as probes finish, and ends with
+    /// [`BridgeMessage::DeepScanComplete`] (open ports sorted, with banners) or
+    /// [`BridgeMessage::DeepScanCancelled`].
+    pub async fn scan_host(
+        &self,
+        ip: Ipv4Addr,
+        ports: Vec<u16>,
+        tx: Sender<BridgeMessage>,
+        cancel_token: CancellationToken,
+    ) {
+        log::info!("Starting deep scan of {} ({} ports)", ip, ports.len());
+
+        // One echo to size the connect timeout; unreachable-by-ICMP hosts get the default.
+        let net_utils = self.net_utils.clone();
+        let latency = tokio::task::spawn_blocking(move || net_utils.echo(ip))
+            .await
+            .ok()
+            .and_then(|r| r.ok().flatten())
+            .and_then(|rtt| LatencyStats::from_samples(&[rtt], 1));
+        let timeout = adaptive_port_timeout(latency.as_ref());
+
+        let total = ports.len().max(1);
+        let mut tasks = tokio::task::JoinSet::new();
+        for port in ports {
+            if cancel_token.is_cancelled() {
+                break;
+            }
+            let Ok(permit) = self.semaphore.clone().acquire_owned().await else {
+                break;
+            };
+            let net_utils = self.net_utils.clone();
+            tasks.spawn(async move {
+                let _permit = permit;
+                net_utils
+                    .scan_port_timeout(ip, port, timeout)
+                    .await
+                    .then_some(port)
+            });
+        }
+
+        let mut open = Vec::new();
+        let mut completed = 0;
+        let mut last_progress = 0;
+        while let Some(joined) = tasks.join_next().await {
+            completed += 1;
+            if let Ok(Some(port)) = joined {
+                open.push(port);
+                let _ = tx
+                    .send(BridgeMessage::DeepScanPort(ip, PortInfo::new(port)))
+                    .await;
+            }
+            let progress = (completed * 100 / total) as u8;
+            if progress != last_progress {
+                last_progress = progress;
+                let _ = tx.send(BridgeMessage::DeepScanProgress(ip, progress)).await;
+            }
+        }
+
+        if cancel_token.is_cancelled() {
+            log::info!("Deep scan of {} cancelled.", ip);
+            let _ = tx.send(BridgeMessage::DeepScanCancelled(ip)).await;
+            return;
+        }
+
+        open.sort_unstable();
+        let mut infos: Vec<PortInfo> = open.into_iter().map(PortInfo::new).collect();
+        for info in &mut infos {
+            if cancel_token.is_cancelled() {
+                break;
+            }
+            info.banner = self.net_utils.grab_banner(ip, info.port).await;
+        }
+
+        log::info!("Deep scan of {} found {} open ports", ip, infos.len());
+        let _ = tx.send(BridgeMessage::DeepScanComplete(ip, infos)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::MockNet;
+    use tokio::sync::mpsc::channel;
+
+    #[test]
+    fn test_deep_scan_ports() {
+        let ports = deep_scan_ports();
+        assert_eq!(ports[0], 1);
+        assert!(ports.contains(&1024));
+        assert!(ports.contains(&3389));
+        assert!(!ports.contains(&1025));
+    }
+
+    #[tokio::test]
+    async fn test_deep_scan_reports_tagged_events() {
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        let (tx, mut rx) = channel(512);
+        let scanner = DeepScanner::new(Arc::new(MockNet));
+        scanner
+            .scan_host(ip, deep_scan_ports(), tx, CancellationToken::new())
+            .await;
+
+        let mut found = Vec::new();
+        let mut progress = 0;
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::DeepScanPort(host, info) => {
+                    assert_eq!(host, ip);
+                    found.push(info.port);
+                }
+                BridgeMessage::DeepScanProgress(host, p) => {
+                    assert_eq!(host, ip);
+                    progress = p;
+                }
+                BridgeMessage::DeepScanComplete(host, ports) => {
+                    assert_eq!(host, ip);
+                    assert_eq!(ports.len(), 1);
+                    assert_eq!(ports[0].banner.as_deref(), Some("mock-httpd/1.0"));
+                    break;
+                }
+                other => panic!("unexpected message: {:?}", other),
+            }
+        }
+        assert_eq!(found, vec![80]);
+        assert_eq!(progress, 100);
+    }
+}
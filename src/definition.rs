@@ -0,0 +1,656 @@
+//! Scan-as-code: declarative scan definitions.
+//!
+//! A [`ScanDefinition`] file names the targets, a profile with probe
+//! overrides, the files to export and the rules that raise alerts, so a
+//! scan can be reviewed and checked into a repository like any other
+//! config and repeated exactly with `ragescanner-cli run scan.yaml`.
+//!
+//! ```yaml
+//! name: branch-office
+//! description: Nightly sweep of the Leeds office
+//! targets: [10.20.0.0/24, 10.20.8.1-50]
+//! profile: standard
+//! probes:
+//!   ports: 22,80,443,3389
+//!   retries: 1
+//!   tls_audit: true
+//! exports:
+//!   - path: out/branch-office.json
+//!   - path: out/branch-office.html
+//! alerts:
+//!   - port_open: 3389
+//!   - issue: smb1
+//!   - host_down: 10.20.0.1
+//! webhook: http://alerts.lan:8080/ragescanner
+//! ```
+//!
+//! Files ending in `.yaml`/`.yml` are read as YAML, `.toml` as TOML and
+//! anything else as JSON.
+
+use crate::bridge::Bridge;
+use crate::export;
+use crate::health;
+use crate::report;
+use crate::targets::ScanTargets;
+use crate::types::{
+    GError, ScanConfig, ScanLabel, ScanOrder, ScanProfile, ScanResult, ScanStatus, ScanSummary,
+    SecurityIssue,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// A list written either as one comma-separated entry or as a sequence.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ListSpec {
+    Joined(ListItem),
+    Items(Vec<ListItem>),
+}
+
+/// One entry of a [`ListSpec`]; YAML and TOML read a bare port as a number.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ListItem {
+    Text(String),
+    Number(u32),
+}
+
+impl fmt::Display for ListItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListItem::Text(text) => f.write_str(text),
+            ListItem::Number(number) => write!(f, "{}", number),
+        }
+    }
+}
+
+impl ListSpec {
+    /// The entries joined with commas, as the range and port parsers take them.
+    pub fn joined(&self) -> String {
+        match self {
+            ListSpec::Joined(item) => item.to_string(),
+            ListSpec::Items(items) => items
+                .iter()
+                .map(ListItem::to_string)
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+}
+
+/// Probe settings laid over the definition's profile; unset fields keep
+/// the profile's values.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProbeSettings {
+    /// TCP ports, e.g. `22,80,8000-8100`; an empty list probes none.
+    pub ports: Option<ListSpec>,
+    /// TCP connect timeout; unset derives it from ping latency.
+    pub port_timeout_ms: Option<u64>,
+    pub ping_timeout_ms: Option<u64>,
+    pub concurrency: Option<usize>,
+    pub port_concurrency: Option<usize>,
+    pub max_host_connections: Option<usize>,
+    pub retries: Option<u32>,
+    pub resolve_hostnames: Option<bool>,
+    pub resolve_vendors: Option<bool>,
+    pub mdns: Option<bool>,
+    pub ssdp: Option<bool>,
+    /// Probe for DHCP servers with a `DHCPINFORM`.
+    pub dhcp: Option<bool>,
+    /// SNMP community to query online hosts with.
+    pub snmp: Option<String>,
+    pub smb1_audit: Option<bool>,
+    pub tls_audit: Option<bool>,
+    /// Packets per second across the scan.
+    pub max_rate: Option<u32>,
+    /// Most probe packets the scan may send.
+    pub probe_budget: Option<u64>,
+    /// Cap on the estimated probe bandwidth in kilobits per second.
+    pub max_bandwidth_kbps: Option<u32>,
+    pub order: Option<ScanOrder>,
+    pub seed: Option<u64>,
+    /// DNS servers for reverse lookups.
+    pub dns_servers: Option<Vec<IpAddr>>,
+    /// Flag reverse DNS names that do not resolve back to their host.
+    pub verify_dns: Option<bool>,
+}
+
+/// A file to write the results to, in the format its extension names:
+/// `.json`, `.csv`, `.xml` (nmap), `.html` (a [report](crate::report)),
+/// anything else a table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExportSpec {
+    /// Relative paths are taken from the definition file's folder.
+    pub path: PathBuf,
+    /// Also export offline hosts (reports always list every host).
+    #[serde(default)]
+    pub include_offline: bool,
+}
+
+/// A condition on the results that raises an [`Alert`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum AlertRule {
+    /// Any online host has this TCP port open.
+    PortOpen(u16),
+    /// Any host has an issue of this kind; see [`IssueKind`].
+    Issue(IssueKind),
+    /// This host did not answer.
+    HostDown(Ipv4Addr),
+    /// Fewer hosts than this were online.
+    MinOnline(usize),
+    /// More hosts than this were online.
+    MaxOnline(usize),
+}
+
+/// Kinds of [`SecurityIssue`] an [`AlertRule::Issue`] matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// Any issue.
+    Any,
+    /// SMBv1 enabled or SMBv1 only.
+    Smb1,
+    /// A TLS protocol older than 1.2.
+    LegacyTls,
+    /// An expired or expiring certificate.
+    Certificate,
+    /// A reverse DNS name that does not resolve back to its host.
+    Dns,
+}
+
+impl IssueKind {
+    pub fn matches(self, issue: &SecurityIssue) -> bool {
+        match self {
+            IssueKind::Any => true,
+            IssueKind::Smb1 => {
+                matches!(issue, SecurityIssue::Smb1Enabled | SecurityIssue::Smb1Only)
+            }
+            IssueKind::LegacyTls => matches!(issue, SecurityIssue::LegacyTls { .. }),
+            IssueKind::Certificate => matches!(
+                issue,
+                SecurityIssue::CertificateExpired { .. }
+                    | SecurityIssue::CertificateExpiring { .. }
+            ),
+            IssueKind::Dns => matches!(issue, SecurityIssue::DnsMismatch { .. }),
+        }
+    }
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IssueKind::Any => "security",
+            IssueKind::Smb1 => "SMBv1",
+            IssueKind::LegacyTls => "legacy TLS",
+            IssueKind::Certificate => "certificate",
+            IssueKind::Dns => "DNS mismatch",
+        })
+    }
+}
+
+/// A rule that fired, with what made it fire.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Alert {
+    pub rule: AlertRule,
+    /// Scan name, if the definition has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scan: Option<String>,
+    /// One line per host or figure that broke the rule.
+    pub findings: Vec<String>,
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.rule, self.findings.join("; "))
+    }
+}
+
+impl fmt::Display for AlertRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertRule::PortOpen(port) => write!(f, "port {} open", port),
+            AlertRule::Issue(kind) => write!(f, "{} issue", kind),
+            AlertRule::HostDown(ip) => write!(f, "{} down", ip),
+            AlertRule::MinOnline(n) => write!(f, "fewer than {} hosts online", n),
+            AlertRule::MaxOnline(n) => write!(f, "more than {} hosts online", n),
+        }
+    }
+}
+
+impl AlertRule {
+    /// Checks the rule against a finished scan's `results`; `None` if it holds.
+    pub fn check(&self, results: &[ScanResult]) -> Option<Vec<String>> {
+        let online = || results.iter().filter(|r| r.status == ScanStatus::Online);
+        let findings: Vec<String> = match self {
+            AlertRule::PortOpen(port) => online()
+                .filter(|r| r.open_ports.iter().any(|p| p.port == *port))
+                .map(|r| r.ip.to_string())
+                .collect(),
+            AlertRule::Issue(kind) => results
+                .iter()
+                .flat_map(|r| {
+                    r.issues
+                        .iter()
+                        .filter(|issue| kind.matches(issue))
+                        .map(move |issue| format!("{}: {}", r.ip, issue))
+                })
+                .collect(),
+            AlertRule::HostDown(ip) => {
+                let up = online().any(|r| r.ip == *ip);
+                if up { Vec::new() } else { vec![ip.to_string()] }
+            }
+            AlertRule::MinOnline(min) => {
+                let count = online().count();
+                if count < *min {
+                    vec![format!("{} online", count)]
+                } else {
+                    Vec::new()
+                }
+            }
+            AlertRule::MaxOnline(max) => {
+                let count = online().count();
+                if count > *max {
+                    vec![format!("{} online", count)]
+                } else {
+                    Vec::new()
+                }
+            }
+        };
+        (!findings.is_empty()).then_some(findings)
+    }
+}
+
+/// A scan described in a file; see the [module docs](self).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScanDefinition {
+    /// Becomes the scan's [`ScanLabel`].
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Ranges and CIDR blocks, as `--range` takes them.
+    pub targets: ListSpec,
+    #[serde(default)]
+    pub profile: ScanProfile,
+    #[serde(default)]
+    pub probes: ProbeSettings,
+    #[serde(default)]
+    pub exports: Vec<ExportSpec>,
+    #[serde(default)]
+    pub alerts: Vec<AlertRule>,
+    /// `http://` URL that receives each [`Alert`] as a JSON `POST`.
+    #[serde(default)]
+    pub webhook: Option<String>,
+}
+
+impl ScanDefinition {
+    /// Reads and validates a definition, in the format its extension names.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if the file cannot be read or parsed, or
+    /// fails [`validate`](ScanDefinition::validate).
+    pub fn from_file(path: &Path) -> Result<Self, GError> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| GError::Internal(format!("Failed to read {}: {}", path.display(), e)))?;
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        let parsed = if ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml") {
+            Self::from_yaml(&text).map_err(|e| e.to_string())
+        } else if ext.eq_ignore_ascii_case("toml") {
+            toml::from_str(&text).map_err(|e| e.to_string())
+        } else {
+            serde_json::from_str(&text).map_err(|e| e.to_string())
+        };
+        let definition: Self =
+            parsed.map_err(|e| GError::Internal(format!("{}: {}", path.display(), e)))?;
+        definition
+            .validate()
+            .map_err(|e| GError::Internal(format!("{}: {}", path.display(), e)))?;
+        Ok(definition)
+    }
+
+    /// Parses YAML, where alert rules are one-key maps such as `port_open: 3389`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the parse error; the definition is not validated.
+    pub fn from_yaml(text: &str) -> Result<Self, serde_yaml::Error> {
+        serde_yaml::with::singleton_map_recursive::deserialize(serde_yaml::Deserializer::from_str(
+            text,
+        ))
+    }
+
+    /// Checks that the targets, ports and webhook parse and the numbers make sense.
+    pub fn validate(&self) -> Result<(), String> {
+        self.targets()?;
+        self.scan_config()?;
+        if let Some(url) = &self.webhook {
+            health::parse_http_url(url)?;
+        }
+        if self.exports.iter().any(|e| e.path.as_os_str().is_empty()) {
+            return Err("export path is empty".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn targets(&self) -> Result<ScanTargets, String> {
+        ScanTargets::parse(&self.targets.joined())
+    }
+
+    /// The profile's config with the probe overrides and label applied.
+    pub fn scan_config(&self) -> Result<ScanConfig, String> {
+        let probes = &self.probes;
+        let mut config = self.profile.config();
+        if let Some(ports) = &probes.ports {
+            let spec = ports.joined();
+            config.ports = if spec.trim().is_empty() {
+                Vec::new()
+            } else {
+                Bridge::parse_ports(&spec)?
+            };
+        }
+        if let Some(ms) = probes.port_timeout_ms {
+            config.port_timeout = Some(Duration::from_millis(ms));
+        }
+        if let Some(ms) = probes.ping_timeout_ms {
+            if ms == 0 {
+                return Err("ping_timeout_ms must be at least 1".to_string());
+            }
+            config.ping_timeout = Duration::from_millis(ms);
+        }
+        if let Some(concurrency) = probes.concurrency {
+            if concurrency == 0 {
+                return Err("concurrency must be at least 1".to_string());
+            }
+            config.concurrency = concurrency;
+        }
+        if let Some(concurrency) = probes.port_concurrency {
+            if concurrency == 0 {
+                return Err("port_concurrency must be at least 1".to_string());
+            }
+            config.port_concurrency = concurrency;
+        }
+        if let Some(limit) = probes.max_host_connections {
+            if limit == 0 {
+                return Err("max_host_connections must be at least 1".to_string());
+            }
+            config.max_host_connections = Some(limit);
+        }
+        if let Some(max_rate) = probes.max_rate {
+            if max_rate == 0 {
+                return Err("max_rate must be at least 1".to_string());
+            }
+            config.max_rate = Some(max_rate);
+        }
+        if let Some(budget) = probes.probe_budget {
+            if budget == 0 {
+                return Err("probe_budget must be at least 1".to_string());
+            }
+            config.probe_budget = Some(budget);
+        }
+        if let Some(kbps) = probes.max_bandwidth_kbps {
+            if kbps == 0 {
+                return Err("max_bandwidth_kbps must be at least 1".to_string());
+            }
+            config.max_bandwidth_kbps = Some(kbps);
+        }
+        config.retries = probes.retries.unwrap_or(config.retries);
+        config.resolve_hostnames = probes.resolve_hostnames.unwrap_or(config.resolve_hostnames);
+        config.resolve_vendors = probes.resolve_vendors.unwrap_or(config.resolve_vendors);
+        config.mdns_discovery = probes.mdns.unwrap_or(config.mdns_discovery);
+        config.ssdp_discovery = probes.ssdp.unwrap_or(config.ssdp_discovery);
+        config.dhcp_discovery = probes.dhcp.unwrap_or(config.dhcp_discovery);
+        config.smb1_audit = probes.smb1_audit.unwrap_or(config.smb1_audit);
+        config.tls_audit = probes.tls_audit.unwrap_or(config.tls_audit);
+        config.order = probes.order.unwrap_or(config.order);
+        if let Some(community) = &probes.snmp {
+            config.snmp_community = Some(community.clone());
+        }
+        if probes.seed.is_some() {
+            config.order_seed = probes.seed;
+        }
+        if let Some(servers) = &probes.dns_servers {
+            config.dns.servers = servers.clone();
+        }
+        config.verify_dns = probes.verify_dns.unwrap_or(config.verify_dns);
+        config.label = self.label();
+        Ok(config)
+    }
+
+    pub fn label(&self) -> Option<ScanLabel> {
+        self.name.as_ref().map(|name| ScanLabel {
+            name: name.clone(),
+            description: self.description.clone(),
+        })
+    }
+
+    /// Checks every alert rule against `results`, in the order given.
+    pub fn evaluate_alerts(&self, results: &[ScanResult]) -> Vec<Alert> {
+        self.alerts
+            .iter()
+            .filter_map(|rule| {
+                rule.check(results).map(|findings| Alert {
+                    rule: rule.clone(),
+                    scan: self.name.clone(),
+                    findings,
+                })
+            })
+            .collect()
+    }
+
+    /// Writes every export, resolving relative paths against `base`, and
+    /// returns the paths written.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`GError`] from creating a folder or writing a file;
+    /// exports before it are kept.
+    pub fn write_exports(
+        &self,
+        base: &Path,
+        results: &[ScanResult],
+        summary: Option<&ScanSummary>,
+        scanned_at: Option<SystemTime>,
+    ) -> Result<Vec<PathBuf>, GError> {
+        let label = self.label();
+        let mut written = Vec::new();
+        for spec in &self.exports {
+            let path = base.join(&spec.path);
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                fs::create_dir_all(dir).map_err(|e| {
+                    GError::Internal(format!("Failed to create {}: {}", dir.display(), e))
+                })?;
+            }
+            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+            if ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm") {
+                report::write_scan_report(&path, results, summary, label.as_ref(), scanned_at)?;
+            } else if spec.include_offline {
+                export::write_file(&path, results, label.as_ref())?;
+            } else {
+                let online: Vec<ScanResult> = results
+                    .iter()
+                    .filter(|r| r.status == ScanStatus::Online)
+                    .cloned()
+                    .collect();
+                export::write_file(&path, &online, label.as_ref())?;
+            }
+            written.push(path);
+        }
+        Ok(written)
+    }
+
+    /// Posts each alert to the webhook, if one is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first failure; see [`health::post_webhook`].
+    pub async fn send_alerts(&self, alerts: &[Alert]) -> Result<(), GError> {
+        let Some(url) = &self.webhook else {
+            return Ok(());
+        };
+        for alert in alerts {
+            health::post_webhook(url, alert).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PortInfo;
+
+    const YAML: &str = "
+name: branch-office
+description: Nightly sweep
+targets: [10.20.0.0/30, 10.20.8.1-2]
+profile: standard
+probes:
+  ports: 22,80,3389
+  retries: 2
+  tls_audit: true
+  verify_dns: true
+exports:
+  - path: out/branch.json
+  - path: out/branch.csv
+    include_offline: true
+alerts:
+  - port_open: 3389
+  - issue: smb1
+  - host_down: 10.20.0.1
+  - min_online: 3
+webhook: http://alerts.lan:8080/ragescanner
+";
+
+    fn host(ip: [u8; 4], online: bool, ports: &[u16], issues: Vec<SecurityIssue>) -> ScanResult {
+        let mut result = ScanResult::new(Ipv4Addr::from(ip));
+        result.status = if online {
+            ScanStatus::Online
+        } else {
+            ScanStatus::Offline
+        };
+        result.open_ports = ports.iter().map(|&port| PortInfo::new(port)).collect();
+        result.issues = issues;
+        result
+    }
+
+    #[test]
+    fn test_yaml_definition_builds_the_scan() {
+        let definition = ScanDefinition::from_yaml(YAML).unwrap();
+        definition.validate().unwrap();
+        assert_eq!(definition.targets().unwrap().ranges().len(), 2);
+
+        let config = definition.scan_config().unwrap();
+        assert_eq!(config.ports, vec![22, 80, 3389]);
+        assert_eq!(config.retries, 2);
+        assert!(config.tls_audit);
+        assert!(config.verify_dns);
+        assert!(!config.smb1_audit);
+        assert_eq!(config.label.unwrap().name, "branch-office");
+        assert_eq!(definition.exports.len(), 2);
+        assert!(definition.exports[1].include_offline);
+
+        // The same definition as JSON, and a profile underneath overrides.
+        let json = serde_json::to_string(&definition).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ScanDefinition>(&json).unwrap(),
+            definition
+        );
+        let safe: ScanDefinition = serde_json::from_str(
+            r#"{"targets": "10.0.0.1", "profile": "safe", "probes": {"retries": 3}}"#,
+        )
+        .unwrap();
+        let config = safe.scan_config().unwrap();
+        assert!(config.icmp_only);
+        assert_eq!(config.retries, 3);
+
+        // Bare numbers are ports too.
+        let numbers = "targets: 10.0.0.1\nprobes:\n  ports: [22, 8000-8002]\n";
+        let config = ScanDefinition::from_yaml(numbers)
+            .unwrap()
+            .scan_config()
+            .unwrap();
+        assert_eq!(config.ports, vec![22, 8000, 8001, 8002]);
+    }
+
+    #[test]
+    fn test_invalid_definitions_are_rejected() {
+        let parse = |json: &str| serde_json::from_str::<ScanDefinition>(json);
+        assert!(parse(r#"{"targets": "10.0.0.1", "colour": "red"}"#).is_err());
+        assert!(parse(r#"{"targets": "10.0.0.1", "alerts": [{"port_closed": 22}]}"#).is_err());
+        let invalid = |json: &str| parse(json).unwrap().validate().is_err();
+        assert!(invalid(r#"{"targets": "10.0.0.300"}"#));
+        assert!(invalid(
+            r#"{"targets": "10.0.0.1", "probes": {"ports": "http"}}"#
+        ));
+        assert!(invalid(
+            r#"{"targets": "10.0.0.1", "probes": {"concurrency": 0}}"#
+        ));
+        assert!(invalid(
+            r#"{"targets": "10.0.0.1", "webhook": "https://x"}"#
+        ));
+    }
+
+    #[test]
+    fn test_alert_rules() {
+        let definition = ScanDefinition::from_yaml(YAML).unwrap();
+        let results = vec![
+            host([10, 20, 0, 1], false, &[], Vec::new()),
+            host([10, 20, 0, 2], true, &[22, 3389], Vec::new()),
+            host(
+                [10, 20, 0, 3],
+                true,
+                &[445],
+                vec![SecurityIssue::Smb1Enabled],
+            ),
+        ];
+        let alerts = definition.evaluate_alerts(&results);
+        let fired: Vec<_> = alerts.iter().map(|a| a.to_string()).collect();
+        assert_eq!(
+            fired,
+            vec![
+                "port 3389 open: 10.20.0.2",
+                "SMBv1 issue: 10.20.0.3: SMBv1 enabled",
+                "10.20.0.1 down: 10.20.0.1",
+                "fewer than 3 hosts online: 2 online",
+            ]
+        );
+        assert_eq!(alerts[0].scan.as_deref(), Some("branch-office"));
+        assert!(AlertRule::MaxOnline(2).check(&results).is_none());
+        assert!(
+            AlertRule::Issue(IssueKind::LegacyTls)
+                .check(&results)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_exports_are_written_next_to_the_definition() {
+        let dir = std::env::temp_dir().join(format!("ragescanner-def-{}", std::process::id()));
+        let definition = ScanDefinition::from_yaml(YAML).unwrap();
+        let results = vec![
+            host([10, 20, 0, 1], false, &[], Vec::new()),
+            host([10, 20, 0, 2], true, &[22], Vec::new()),
+        ];
+        let written = definition
+            .write_exports(&dir, &results, None, None)
+            .unwrap();
+        assert_eq!(
+            written,
+            vec![dir.join("out/branch.json"), dir.join("out/branch.csv")]
+        );
+        let json = fs::read_to_string(&written[0]).unwrap();
+        assert!(json.contains("10.20.0.2") && !json.contains("10.20.0.1"));
+        let csv = fs::read_to_string(&written[1]).unwrap();
+        assert!(csv.contains("10.20.0.1"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
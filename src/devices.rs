@@ -0,0 +1,200 @@
+//! Friendly device names and notes kept across scans.
+//!
+//! The device book is a small JSON file mapping MAC addresses to the
+//! [`DeviceNote`] the user gave the device:
+//!
+//! ```text
+//! {"00:11:22:33:44:55": {"name": "Living-room AP", "notes": "ceiling, PoE port 4"}}
+//! ```
+//!
+//! The scanner copies a host's entry into [`ScanResult::device_note`], so the
+//! name follows the device when DHCP hands it another address.
+
+use crate::inventory::normalize_mac;
+use crate::types::{DeviceNote, GError, ScanResult};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// Device book the bridge and CLI open at startup.
+pub const DEFAULT_DEVICES_PATH: &str = "ragescanner.devices.json";
+
+static DEVICES: RwLock<Option<DeviceBook>> = RwLock::new(None);
+
+/// Device notes by MAC, and the file they are saved to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceBook {
+    path: PathBuf,
+    notes: BTreeMap<String, DeviceNote>,
+}
+
+impl DeviceBook {
+    /// Reads the book at `path`; a missing file is an empty book.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if the file cannot be read or parsed.
+    pub fn load(path: &Path) -> Result<Self, GError> {
+        let notes: BTreeMap<String, DeviceNote> = match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| {
+                GError::Internal(format!("Invalid device book '{}': {}", path.display(), e))
+            })?,
+            Err(e) if e.kind() == ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => {
+                return Err(GError::Internal(format!(
+                    "Failed to read device book '{}': {}",
+                    path.display(),
+                    e
+                )));
+            }
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            notes: notes
+                .into_iter()
+                .filter_map(|(mac, note)| Some((normalize_mac(&mac)?, note)))
+                .collect(),
+        })
+    }
+
+    /// Devices with a note.
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+
+    /// The note for `mac`, in any separator style.
+    pub fn get(&self, mac: &str) -> Option<&DeviceNote> {
+        self.notes.get(&normalize_mac(mac)?)
+    }
+
+    /// Sets the note for `mac`, or removes it for `None`, and saves the book.
+    ///
+    /// The file is written to a temporary sibling and renamed into place so a
+    /// crash never leaves it half-written.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if `mac` is not a MAC address or the file
+    /// cannot be written; the book is unchanged then.
+    pub fn set(&mut self, mac: &str, note: Option<DeviceNote>) -> Result<(), GError> {
+        let key = normalize_mac(mac)
+            .ok_or_else(|| GError::Internal(format!("'{}' is not a MAC address", mac)))?;
+        let mut notes = self.notes.clone();
+        match note {
+            Some(note) => notes.insert(key, note),
+            None => notes.remove(&key),
+        };
+
+        let json = serde_json::to_string_pretty(&notes)
+            .map_err(|e| GError::Internal(format!("Failed to serialize device book: {}", e)))?;
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, json).map_err(|e| {
+            GError::Internal(format!(
+                "Failed to write device book '{}': {}",
+                tmp.display(),
+                e
+            ))
+        })?;
+        fs::rename(&tmp, &self.path).map_err(|e| {
+            GError::Internal(format!(
+                "Failed to move device book to '{}': {}",
+                self.path.display(),
+                e
+            ))
+        })?;
+        self.notes = notes;
+        Ok(())
+    }
+}
+
+/// Opens the device book at `path` for later scans and edits.
+///
+/// Returns the number of devices it names. On error the previous book stays
+/// in use.
+///
+/// # Errors
+///
+/// See [`DeviceBook::load`].
+pub fn open(path: &Path) -> Result<usize, GError> {
+    let book = DeviceBook::load(path)?;
+    let count = book.len();
+    if let Ok(mut current) = DEVICES.write() {
+        *current = Some(book);
+    }
+    log::info!("Loaded device book with {} devices", count);
+    Ok(count)
+}
+
+/// The open book's note for `mac`.
+pub fn lookup(mac: Option<&str>) -> Option<DeviceNote> {
+    let current = DEVICES.read().ok()?;
+    current.as_ref()?.get(mac?).cloned()
+}
+
+/// Sets or, for `None`, removes the note for `mac` in the open book and
+/// saves it.
+///
+/// # Errors
+///
+/// Returns [`GError::Internal`] if no book is open; see also [`DeviceBook::set`].
+pub fn set_note(mac: &str, note: Option<DeviceNote>) -> Result<(), GError> {
+    let mut current = DEVICES
+        .write()
+        .map_err(|_| GError::Internal("The device book is poisoned".to_string()))?;
+    current
+        .as_mut()
+        .ok_or_else(|| GError::Internal("No device book is open".to_string()))?
+        .set(mac, note)
+}
+
+/// Refreshes [`ScanResult::device_note`] from the open book, for results
+/// found before a note was edited.
+pub fn annotate(results: &mut [ScanResult]) {
+    for res in results {
+        res.device_note = lookup(res.mac.as_deref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notes_survive_a_reload() {
+        let path =
+            std::env::temp_dir().join(format!("ragescanner-devices-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut book = DeviceBook::load(&path).unwrap();
+        assert!(book.is_empty());
+
+        let note = DeviceNote::parse("Living-room AP | ceiling").unwrap();
+        book.set("00-11-22-33-44-55", Some(note.clone())).unwrap();
+        book.set("66:77:88:99:aa:bb", DeviceNote::parse("Printer"))
+            .unwrap();
+        book.set("66:77:88:99:AA:BB", None).unwrap();
+        assert!(book.set("not-a-mac", None).is_err());
+
+        let book = DeviceBook::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(book.len(), 1);
+        assert_eq!(book.get("00:11:22:33:44:55"), Some(&note));
+    }
+
+    #[test]
+    fn test_load_rejects_a_corrupt_book() {
+        let path = std::env::temp_dir().join(format!(
+            "ragescanner-devices-corrupt-{}.json",
+            std::process::id()
+        ));
+        fs::write(&path, "not json").unwrap();
+        let loaded = DeviceBook::load(&path);
+        let _ = fs::remove_file(&path);
+        assert!(loaded.is_err());
+    }
+}
@@ -0,0 +1,214 @@
+//! DHCP server discovery.
+//!
+//! [`discover_servers`] sends a `DHCPINFORM` from each local address: the
+//! message asks for configuration without requesting a lease, so every DHCP
+//! server on the link answers with a `DHCPACK` naming itself and nothing is
+//! assigned. Servers reply to the client port 68, which the OS's own DHCP
+//! client usually holds; where the port cannot be shared, the probe finds
+//! nothing.
+
+use crate::net::InterfaceInfo;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// How long [`discover_servers`] listens for replies.
+pub const DHCP_PROBE_TIMEOUT: Duration = Duration::from_millis(2000);
+
+const SERVER_PORT: u16 = 67;
+const CLIENT_PORT: u16 = 68;
+
+const BOOT_REQUEST: u8 = 1;
+const BOOT_REPLY: u8 = 2;
+const HTYPE_ETHERNET: u8 = 1;
+const MAGIC_COOKIE: [u8; 4] = [99, 130, 83, 99];
+/// Length of the fixed BOOTP header, magic cookie included.
+const HEADER_LEN: usize = 240;
+
+/// DHCP options used in the exchange.
+const OPTION_PAD: u8 = 0;
+const OPTION_MESSAGE_TYPE: u8 = 53;
+const OPTION_SERVER_ID: u8 = 54;
+const OPTION_PARAMETER_LIST: u8 = 55;
+const OPTION_END: u8 = 255;
+
+const DHCPINFORM: u8 = 8;
+const DHCPACK: u8 = 5;
+
+/// Sends a `DHCPINFORM` from every interface that is up and has a routable
+/// address and returns the DHCP servers that answered, sorted.
+pub fn discover_servers(interfaces: &[InterfaceInfo], timeout: Duration) -> Vec<Ipv4Addr> {
+    let mut addresses: Vec<Ipv4Addr> = interfaces
+        .iter()
+        .filter(|i| i.is_up && !i.address.is_link_local())
+        .map(|i| i.address)
+        .collect();
+    addresses.sort();
+    addresses.dedup();
+    let mut servers: Vec<Ipv4Addr> = std::thread::scope(|scope| {
+        let probes: Vec<_> = addresses
+            .into_iter()
+            .map(|address| scope.spawn(move || probe(address, timeout)))
+            .collect();
+        probes
+            .into_iter()
+            .flat_map(|probe| probe.join().unwrap_or_default())
+            .collect()
+    });
+    servers.sort();
+    servers.dedup();
+    servers
+}
+
+/// Broadcasts one `DHCPINFORM` from `address` and collects the servers that
+/// acknowledge it within `timeout`.
+fn probe(address: Ipv4Addr, timeout: Duration) -> Vec<Ipv4Addr> {
+    let mut servers = Vec::new();
+    let socket = match client_socket(address) {
+        Ok(socket) => socket,
+        Err(e) => {
+            log::info!("DHCP probe from {} skipped: {}", address, e);
+            return servers;
+        }
+    };
+    let xid = transaction_id(address);
+    if let Err(e) = socket.send_to(&inform(xid, address), (Ipv4Addr::BROADCAST, SERVER_PORT)) {
+        log::info!("DHCP probe from {} failed: {}", address, e);
+        return servers;
+    }
+
+    let deadline = Instant::now() + timeout;
+    let mut buf = [0u8; 1500];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() || socket.set_read_timeout(Some(remaining)).is_err() {
+            break;
+        }
+        let (len, from) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(_) => break,
+        };
+        let SocketAddr::V4(from) = from else {
+            continue;
+        };
+        if let Some(server) = parse_ack(&buf[..len], xid, *from.ip()) {
+            servers.push(server);
+        }
+    }
+    servers
+}
+
+/// A broadcast-capable UDP socket on the client port of `address`, shared
+/// with the OS's DHCP client where the platform allows it.
+fn client_socket(address: Ipv4Addr) -> std::io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    socket.set_broadcast(true)?;
+    socket.bind(&SocketAddr::V4(SocketAddrV4::new(address, CLIENT_PORT)).into())?;
+    Ok(socket.into())
+}
+
+/// Transaction ID that differs per address and per run.
+fn transaction_id(address: Ipv4Addr) -> u32 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos())
+        .unwrap_or_default();
+    u32::from(address) ^ nanos ^ std::process::id()
+}
+
+/// Builds a `DHCPINFORM` for the client at `ciaddr`.
+fn inform(xid: u32, ciaddr: Ipv4Addr) -> Vec<u8> {
+    let mut packet = vec![0u8; HEADER_LEN];
+    packet[0] = BOOT_REQUEST;
+    packet[1] = HTYPE_ETHERNET;
+    packet[2] = 6;
+    packet[4..8].copy_from_slice(&xid.to_be_bytes());
+    packet[12..16].copy_from_slice(&ciaddr.octets());
+    packet[236..240].copy_from_slice(&MAGIC_COOKIE);
+    packet.extend_from_slice(&[OPTION_MESSAGE_TYPE, 1, DHCPINFORM]);
+    // Subnet mask, router and DNS servers: the usual request of a client.
+    packet.extend_from_slice(&[OPTION_PARAMETER_LIST, 3, 1, 3, 6]);
+    packet.push(OPTION_END);
+    packet
+}
+
+/// Returns the server that sent `reply` if it is a `DHCPACK` to transaction
+/// `xid`: the server identifier option, else the sender `from`.
+fn parse_ack(reply: &[u8], xid: u32, from: Ipv4Addr) -> Option<Ipv4Addr> {
+    if reply.len() < HEADER_LEN
+        || reply[0] != BOOT_REPLY
+        || reply[4..8] != xid.to_be_bytes()
+        || reply[236..240] != MAGIC_COOKIE
+    {
+        return None;
+    }
+    let mut message_type = None;
+    let mut server_id = None;
+    let mut options = &reply[HEADER_LEN..];
+    while let [code, rest @ ..] = options {
+        match *code {
+            OPTION_PAD => {
+                options = rest;
+                continue;
+            }
+            OPTION_END => break,
+            _ => {}
+        }
+        let [len, rest @ ..] = rest else {
+            break;
+        };
+        let value = rest.get(..usize::from(*len))?;
+        match (*code, value) {
+            (OPTION_MESSAGE_TYPE, [kind]) => message_type = Some(*kind),
+            (OPTION_SERVER_ID, [a, b, c, d]) => server_id = Some(Ipv4Addr::new(*a, *b, *c, *d)),
+            _ => {}
+        }
+        options = &rest[value.len()..];
+    }
+    (message_type == Some(DHCPACK)).then(|| server_id.unwrap_or(from))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ack(xid: u32, options: &[u8]) -> Vec<u8> {
+        let mut reply = inform(xid, Ipv4Addr::new(192, 168, 1, 20));
+        reply.truncate(HEADER_LEN);
+        reply[0] = BOOT_REPLY;
+        reply.extend_from_slice(options);
+        reply
+    }
+
+    #[test]
+    fn test_inform_layout() {
+        let packet = inform(0x0102_0304, Ipv4Addr::new(192, 168, 1, 20));
+        assert_eq!(&packet[..4], &[BOOT_REQUEST, HTYPE_ETHERNET, 6, 0]);
+        assert_eq!(&packet[4..8], &[1, 2, 3, 4]);
+        assert_eq!(&packet[12..16], &[192, 168, 1, 20]);
+        assert_eq!(&packet[236..240], &MAGIC_COOKIE);
+        assert_eq!(&packet[HEADER_LEN..HEADER_LEN + 3], &[53, 1, DHCPINFORM]);
+        assert_eq!(packet.last(), Some(&OPTION_END));
+    }
+
+    #[test]
+    fn test_parse_ack_prefers_the_server_identifier() {
+        let relay = Ipv4Addr::new(192, 168, 1, 254);
+        let with_id = ack(7, &[53, 1, DHCPACK, 0, 54, 4, 192, 168, 1, 1, 255]);
+        assert_eq!(
+            parse_ack(&with_id, 7, relay),
+            Some(Ipv4Addr::new(192, 168, 1, 1))
+        );
+        assert_eq!(parse_ack(&with_id, 8, relay), None);
+
+        let without_id = ack(7, &[53, 1, DHCPACK, 255]);
+        assert_eq!(parse_ack(&without_id, 7, relay), Some(relay));
+
+        let nak = ack(7, &[53, 1, 6, 54, 4, 192, 168, 1, 1, 255]);
+        assert_eq!(parse_ack(&nak, 7, relay), None);
+        let truncated = ack(7, &[53, 1, DHCPACK, 54, 4, 192]);
+        assert_eq!(parse_ack(&truncated, 7, relay), None);
+        assert_eq!(parse_ack(&inform(7, relay), 7, relay), None);
+    }
+}
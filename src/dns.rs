@@ -0,0 +1,159 @@
+//! Reverse DNS over an async resolver.
+//!
+//! The system resolver behind `getnameinfo` answers one address per call
+//! and holds a thread until it does, so on a large sweep the PTR lookups
+//! of silent hosts dominated the scan. [`reverse_lookup`] instead sends a
+//! whole batch of PTR queries from one resolver, to the system's DNS
+//! servers or those of a [`DnsConfig`], and waits for them together.
+//! [`forward_lookup`] does the same for A queries, to check that the names
+//! found lead back to their hosts.
+
+use crate::names::escape_name;
+use crate::types::{DnsConfig, GError};
+use futures::StreamExt;
+use hickory_resolver::TokioAsyncResolver;
+use hickory_resolver::config::{NameServerConfigGroup, ResolverConfig, ResolverOpts};
+use hickory_resolver::error::ResolveErrorKind;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+
+/// DNS port custom servers are asked on.
+pub const DNS_PORT: u16 = 53;
+/// Queries of one [`reverse_lookup`] or [`forward_lookup`] in flight at once.
+pub const MAX_CONCURRENT_QUERIES: usize = 64;
+
+/// Builds a resolver for `dns`: its servers if it names any, else the
+/// system's, waiting `dns.timeout` for each answer.
+///
+/// # Errors
+///
+/// Returns [`GError::Internal`] if the system's DNS configuration cannot
+/// be read.
+pub fn resolver(dns: &DnsConfig) -> Result<TokioAsyncResolver, GError> {
+    let (config, mut options) = if dns.servers.is_empty() {
+        hickory_resolver::system_conf::read_system_conf()
+            .map_err(|e| GError::Internal(format!("Failed to read DNS configuration: {}", e)))?
+    } else {
+        let servers = NameServerConfigGroup::from_ips_clear(&dns.servers, DNS_PORT, true);
+        (
+            ResolverConfig::from_parts(None, Vec::new(), servers),
+            ResolverOpts::default(),
+        )
+    };
+    options.timeout = dns.timeout;
+    // One try per server; a silent host should not cost several timeouts.
+    options.attempts = 1;
+    options.num_concurrent_reqs = options.num_concurrent_reqs.max(1);
+    Ok(TokioAsyncResolver::tokio(config, options))
+}
+
+/// Looks up the PTR names of `ips` together.
+///
+/// Addresses without a name, or whose lookup fails or times out, are left
+/// out of the map. Names lose their trailing dot and are passed through
+/// [`escape_name`].
+///
+/// # Errors
+///
+/// Returns [`GError::Internal`] if no resolver can be built; see
+/// [`resolver`].
+pub async fn reverse_lookup(
+    ips: &[Ipv4Addr],
+    dns: &DnsConfig,
+) -> Result<HashMap<Ipv4Addr, String>, GError> {
+    let resolver = resolver(dns)?;
+    let lookups = ips.iter().copied().map(move |ip| {
+        let resolver = resolver.clone();
+        async move {
+            let lookup = resolver.reverse_lookup(IpAddr::V4(ip)).await;
+            let name = match lookup {
+                Ok(lookup) => lookup.iter().find_map(|ptr| ptr_name(ip, &ptr.to_utf8())),
+                Err(e) => {
+                    log::debug!("No PTR record for {}: {}", ip, e);
+                    None
+                }
+            };
+            name.map(|name| (ip, name))
+        }
+    });
+    Ok(futures::stream::iter(lookups)
+        .buffer_unordered(MAX_CONCURRENT_QUERIES)
+        .filter_map(|found| async move { found })
+        .collect()
+        .await)
+}
+
+/// Looks up the IPv4 addresses of `names` together.
+///
+/// Names are queried as fully qualified, without search domains. A name
+/// the servers have no A records for maps to an empty list; names whose
+/// lookup fails or times out are left out of the map.
+///
+/// # Errors
+///
+/// Returns [`GError::Internal`] if no resolver can be built; see
+/// [`resolver`].
+pub async fn forward_lookup(
+    names: &[String],
+    dns: &DnsConfig,
+) -> Result<HashMap<String, Vec<Ipv4Addr>>, GError> {
+    let resolver = resolver(dns)?;
+    let lookups = names.iter().cloned().map(move |name| {
+        let resolver = resolver.clone();
+        async move {
+            match resolver.ipv4_lookup(format!("{}.", name)).await {
+                Ok(lookup) => Some((name, lookup.iter().map(|a| a.0).collect())),
+                Err(e) if matches!(e.kind(), ResolveErrorKind::NoRecordsFound { .. }) => {
+                    Some((name, Vec::new()))
+                }
+                Err(e) => {
+                    log::debug!("Lookup of {} failed: {}", name, e);
+                    None
+                }
+            }
+        }
+    });
+    Ok(futures::stream::iter(lookups)
+        .buffer_unordered(MAX_CONCURRENT_QUERIES)
+        .filter_map(|found| async move { found })
+        .collect()
+        .await)
+}
+
+/// The name to show for a PTR record of `ip`, if it names anything.
+fn ptr_name(ip: Ipv4Addr, name: &str) -> Option<String> {
+    let name = name.trim_end_matches('.');
+    (!name.is_empty() && name != ip.to_string()).then(|| escape_name(name.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_ptr_names_are_cleaned_up() {
+        let ip = Ipv4Addr::new(10, 0, 0, 5);
+        assert_eq!(
+            ptr_name(ip, "nas.example.lan.").as_deref(),
+            Some("nas.example.lan")
+        );
+        assert_eq!(ptr_name(ip, "10.0.0.5."), None);
+        assert_eq!(ptr_name(ip, "."), None);
+    }
+
+    #[tokio::test]
+    async fn test_silent_server_times_out() {
+        // TEST-NET-1 is never routed, so the query is never answered.
+        let dns = DnsConfig {
+            servers: vec![IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))],
+            timeout: Duration::from_millis(200),
+        };
+        let started = std::time::Instant::now();
+        let ips: Vec<_> = (1..=20).map(|i| Ipv4Addr::new(10, 0, 0, i)).collect();
+        let names = reverse_lookup(&ips, &dns).await.unwrap();
+        assert!(names.is_empty());
+        // Looked up together, not one timeout after another.
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+}
@@ -0,0 +1,196 @@
+//! Relaunching the GUI as administrator.
+//!
+//! Some Win32 calls fail with [`GError::PermissionDenied`] unless the process is
+//! elevated. Rather than asking the user to restart by hand, the GUI starts a
+//! new elevated instance through the UAC prompt (`ShellExecute` with the
+//! `runas` verb), handing it the scan it was about to run on the command line.
+//! Only the argument handling exists on other platforms, which have no GUI.
+
+#[cfg(windows)]
+use crate::types::GError;
+#[cfg(windows)]
+use std::mem;
+#[cfg(windows)]
+use windows::Win32::Foundation::{CloseHandle, HANDLE, HWND};
+#[cfg(windows)]
+use windows::Win32::Security::{GetTokenInformation, TOKEN_ELEVATION, TOKEN_QUERY, TokenElevation};
+#[cfg(windows)]
+use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+#[cfg(windows)]
+use windows::Win32::UI::Shell::ShellExecuteW;
+#[cfg(windows)]
+use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+#[cfg(windows)]
+use windows::core::HSTRING;
+
+/// Command-line flag carrying the Start IP field of the pending scan.
+pub const SCAN_START_ARG: &str = "--scan-start";
+/// Command-line flag carrying the End IP field of the pending scan.
+pub const SCAN_END_ARG: &str = "--scan-end";
+
+/// `ShellExecuteW` return values above this mean success.
+#[cfg(windows)]
+const SHELL_EXECUTE_OK: isize = 32;
+/// `SE_ERR_ACCESSDENIED`, also returned when the UAC prompt is declined.
+#[cfg(windows)]
+const SE_ERR_ACCESSDENIED: isize = 5;
+
+/// A scan to start as soon as the relaunched GUI is up: the text of its
+/// Start IP and End IP fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingScan {
+    pub start: String,
+    pub end: String,
+}
+
+impl PendingScan {
+    /// Command-line arguments that [`PendingScan::from_args`] reads back.
+    pub fn to_args(&self) -> Vec<String> {
+        vec![
+            SCAN_START_ARG.to_string(),
+            self.start.clone(),
+            SCAN_END_ARG.to_string(),
+            self.end.clone(),
+        ]
+    }
+
+    /// Reads the pending scan from the process arguments (without the
+    /// program name); `None` unless the Start IP field was passed.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Option<Self> {
+        let mut start = None;
+        let mut end = String::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                SCAN_START_ARG => start = args.next(),
+                SCAN_END_ARG => end = args.next().unwrap_or_default(),
+                _ => {}
+            }
+        }
+        Some(Self { start: start?, end })
+    }
+}
+
+/// Quotes one argument so `CommandLineToArgvW` reads it back unchanged.
+fn quote_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_string();
+    }
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            // Backslashes before a quote are escaped, and so is the quote.
+            '"' => {
+                quoted.extend(std::iter::repeat_n('\\', backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                quoted.extend(std::iter::repeat_n('\\', backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    // Trailing backslashes would otherwise escape the closing quote.
+    quoted.extend(std::iter::repeat_n('\\', backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+/// Joins arguments into a Windows command line.
+pub fn command_line(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| quote_arg(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether this process runs with an elevated (administrator) token.
+#[cfg(windows)]
+pub fn is_elevated() -> bool {
+    unsafe {
+        let mut token = HANDLE::default();
+        if OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token).is_err() {
+            return false;
+        }
+        let mut elevation = TOKEN_ELEVATION::default();
+        let mut len = 0;
+        let queried = GetTokenInformation(
+            token,
+            TokenElevation,
+            Some(&mut elevation as *mut TOKEN_ELEVATION as *mut _),
+            mem::size_of::<TOKEN_ELEVATION>() as u32,
+            &mut len,
+        );
+        let _ = CloseHandle(token);
+        queried.is_ok() && elevation.TokenIsElevated != 0
+    }
+}
+
+/// Starts this executable again as administrator, in the current working
+/// directory, with `args`.
+///
+/// Returns once the new process has been started; the caller should then
+/// exit. A declined UAC prompt is reported as [`GError::PermissionDenied`].
+#[cfg(windows)]
+pub fn relaunch_elevated(args: &[String]) -> Result<(), GError> {
+    let exe = std::env::current_exe()
+        .map_err(|e| GError::Internal(format!("Cannot locate the executable: {}", e)))?;
+    let dir = std::env::current_dir()
+        .map_err(|e| GError::Internal(format!("Cannot read the working directory: {}", e)))?;
+    let exe = HSTRING::from(exe.to_string_lossy().as_ref());
+    let params = HSTRING::from(command_line(args));
+    let dir = HSTRING::from(dir.to_string_lossy().as_ref());
+    let code = unsafe {
+        ShellExecuteW(
+            HWND::default(),
+            &HSTRING::from("runas"),
+            &exe,
+            &params,
+            &dir,
+            SW_SHOWNORMAL,
+        )
+    }
+    .0;
+    match code {
+        c if c > SHELL_EXECUTE_OK => Ok(()),
+        SE_ERR_ACCESSDENIED => Err(GError::PermissionDenied(
+            "Elevation was declined".to_string(),
+        )),
+        // The other SE_ERR_* values reuse the Win32 codes of the same meaning.
+        c => Err(GError::from_win32(c as u32, "ShellExecuteW")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pending_scan_round_trips_through_args() {
+        let pending = PendingScan {
+            start: "10.0.1.0/24, 10.0.5.1-50".to_string(),
+            end: String::new(),
+        };
+        assert_eq!(PendingScan::from_args(pending.to_args()), Some(pending));
+        assert_eq!(PendingScan::from_args(Vec::new()), None);
+    }
+
+    #[test]
+    fn test_command_line_quotes_like_argv() {
+        let args = [
+            "plain".to_string(),
+            "two words".to_string(),
+            String::new(),
+            r#"say "hi""#.to_string(),
+            r"C:\dir with space\".to_string(),
+        ];
+        assert_eq!(
+            command_line(&args),
+            r#"plain "two words" "" "say \"hi\"" "C:\dir with space\\""#
+        );
+    }
+}
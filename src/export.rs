@@ -0,0 +1,615 @@
+//! Renders scan results as JSON, CSV, nmap XML or a plain-text table.
+//!
+//! Used by the headless `ragescanner-cli` binary; the output is meant for
+//! scripts (`json`, `csv`), tools that ingest nmap output (`xml`, see
+//! [`nmap`](crate::nmap)) or a quick look in a terminal (`table`). The GUI
+//! and TUI export through [`write_file`], narrowed by an [`ExportScope`] to
+//! what the user is looking at.
+
+use crate::nmap;
+use crate::types::{AssetInfo, GError, ScanLabel, ScanResult};
+use serde::Serialize;
+use std::fmt::Write;
+use std::fs;
+use std::net::Ipv4Addr;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Output format for [`render`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    Json,
+    Csv,
+    /// Nmap's `-oX` schema.
+    NmapXml,
+    #[default]
+    Table,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "xml" | "nmap" => Ok(OutputFormat::NmapXml),
+            "table" => Ok(OutputFormat::Table),
+            other => Err(format!(
+                "Unknown output format '{}' (expected json, csv, xml or table)",
+                other
+            )),
+        }
+    }
+}
+
+/// A column of [`OutputFormat::Table`] output, picked with [`render_table`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableColumn {
+    Ip,
+    Status,
+    Hostname,
+    Mac,
+    Vendor,
+    Ports,
+    Latency,
+    Issues,
+    /// [`AssetInfo::asset_tag`](crate::types::AssetInfo::asset_tag) from the inventory.
+    AssetTag,
+    Owner,
+    Location,
+    /// [`DeviceNote::name`](crate::types::DeviceNote::name) from the device book.
+    Name,
+    /// Infrastructure [`roles`](crate::types::ScanResult::roles) of the host.
+    Roles,
+}
+
+/// Columns of [`render`]'s table output.
+pub const DEFAULT_TABLE_COLUMNS: &[TableColumn] = &[
+    TableColumn::Ip,
+    TableColumn::Status,
+    TableColumn::Hostname,
+    TableColumn::Mac,
+    TableColumn::Ports,
+    TableColumn::Issues,
+];
+
+impl TableColumn {
+    /// Parses a comma-separated list such as `ip,hostname,ports`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message naming the first unknown column, or if the list is empty.
+    pub fn parse_list(spec: &str) -> Result<Vec<Self>, String> {
+        let columns = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        if columns.is_empty() {
+            return Err("No columns given".to_string());
+        }
+        Ok(columns)
+    }
+
+    fn header(self) -> &'static str {
+        match self {
+            TableColumn::Ip => "IP",
+            TableColumn::Status => "STATUS",
+            TableColumn::Hostname => "HOSTNAME",
+            TableColumn::Mac => "MAC",
+            TableColumn::Vendor => "VENDOR",
+            TableColumn::Ports => "PORTS",
+            TableColumn::Latency => "LATENCY",
+            TableColumn::Issues => "ISSUES",
+            TableColumn::AssetTag => "ASSET TAG",
+            TableColumn::Owner => "OWNER",
+            TableColumn::Location => "LOCATION",
+            TableColumn::Name => "NAME",
+            TableColumn::Roles => "ROLES",
+        }
+    }
+
+    fn cell(self, res: &ScanResult) -> String {
+        let or_dash = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+        match self {
+            TableColumn::Ip => res.ip.to_string(),
+            TableColumn::Status => res.status.to_string(),
+            TableColumn::Hostname => or_dash(&res.hostname),
+            TableColumn::Mac => or_dash(&res.mac),
+            TableColumn::Vendor => or_dash(&res.vendor),
+            TableColumn::Ports => res
+                .open_ports
+                .iter()
+                .map(|p| p.describe())
+                .collect::<Vec<_>>()
+                .join(", "),
+            TableColumn::Latency => res
+                .latency_ms()
+                .map(|ms| format!("{:.1} ms", ms))
+                .unwrap_or_else(|| "-".to_string()),
+            TableColumn::Issues => issue_list(res, ", "),
+            TableColumn::AssetTag => or_dash(&asset_field(res, |a| &a.asset_tag)),
+            TableColumn::Owner => or_dash(&asset_field(res, |a| &a.owner)),
+            TableColumn::Location => or_dash(&asset_field(res, |a| &a.location)),
+            TableColumn::Name => or_dash(&res.device_note.as_ref().map(|n| n.name.clone())),
+            TableColumn::Roles if res.roles.is_empty() => "-".to_string(),
+            TableColumn::Roles => res
+                .roles
+                .iter()
+                .map(|role| role.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+}
+
+impl FromStr for TableColumn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "ip" => Ok(TableColumn::Ip),
+            "status" => Ok(TableColumn::Status),
+            "hostname" => Ok(TableColumn::Hostname),
+            "mac" => Ok(TableColumn::Mac),
+            "vendor" => Ok(TableColumn::Vendor),
+            "ports" => Ok(TableColumn::Ports),
+            "latency" => Ok(TableColumn::Latency),
+            "issues" => Ok(TableColumn::Issues),
+            "asset_tag" | "asset" => Ok(TableColumn::AssetTag),
+            "owner" => Ok(TableColumn::Owner),
+            "location" => Ok(TableColumn::Location),
+            "name" => Ok(TableColumn::Name),
+            "roles" => Ok(TableColumn::Roles),
+            other => Err(format!(
+                "Unknown column '{}' (expected ip, status, hostname, mac, vendor, ports, latency, issues, asset_tag, owner, location, name or roles)",
+                other
+            )),
+        }
+    }
+}
+
+impl OutputFormat {
+    /// Picks the format from a file extension: `.json`, `.csv`, `.xml`, else a table.
+    pub fn from_path(path: &Path) -> Self {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        if ext.eq_ignore_ascii_case("json") {
+            OutputFormat::Json
+        } else if ext.eq_ignore_ascii_case("csv") {
+            OutputFormat::Csv
+        } else if ext.eq_ignore_ascii_case("xml") {
+            OutputFormat::NmapXml
+        } else {
+            OutputFormat::Table
+        }
+    }
+}
+
+/// Which of a frontend's results an export covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportScope {
+    /// Every result, whatever the view shows.
+    #[default]
+    All,
+    /// The rows the active filter lets through.
+    Visible,
+    /// The selected rows, or the visible ones if nothing is selected.
+    Selected,
+}
+
+/// Picks the results an export in `scope` covers, keeping their order.
+///
+/// `visible` is the frontend's active filter and `selected` holds the
+/// addresses of its selected rows.
+pub fn scoped(
+    results: &[ScanResult],
+    scope: ExportScope,
+    visible: impl Fn(&ScanResult) -> bool,
+    selected: &[Ipv4Addr],
+) -> Vec<ScanResult> {
+    let shown = |r: &&ScanResult| scope == ExportScope::All || visible(r);
+    let picked = |r: &&ScanResult| shown(r) && selected.contains(&r.ip);
+    if scope == ExportScope::Selected && results.iter().any(|r| picked(&r)) {
+        results.iter().filter(picked).cloned().collect()
+    } else {
+        results.iter().filter(shown).cloned().collect()
+    }
+}
+
+/// Writes `results` to `path` in the format its extension names (see
+/// [`OutputFormat::from_path`]), headed by `label` if given.
+///
+/// # Errors
+///
+/// Returns [`GError::Internal`] if rendering or writing the file fails.
+pub fn write_file(
+    path: &Path,
+    results: &[ScanResult],
+    label: Option<&ScanLabel>,
+) -> Result<(), GError> {
+    let text = render_labeled(results, OutputFormat::from_path(path), label)?;
+    fs::write(path, text).map_err(|e| {
+        GError::Internal(format!(
+            "Failed to write export '{}': {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+const CSV_HEADER: &str = "ip,status,hostname,mac,vendor,latency_ms,open_ports,issues";
+/// Appended to [`CSV_HEADER`] when any result carries inventory data.
+const CSV_ASSET_HEADER: &str = ",asset_tag,owner,location";
+
+/// Renders `results` in the given format, one host per row.
+///
+/// # Errors
+///
+/// Returns [`GError::Internal`] if JSON serialization fails.
+pub fn render(results: &[ScanResult], format: OutputFormat) -> Result<String, GError> {
+    match format {
+        OutputFormat::Json => {
+            // Stored labels may predate the user's port label overrides.
+            let relabelled: Vec<ScanResult> = results
+                .iter()
+                .cloned()
+                .map(|mut res| {
+                    for info in &mut res.open_ports {
+                        info.service = info.label();
+                    }
+                    res
+                })
+                .collect();
+            serde_json::to_string_pretty(&relabelled)
+                .map_err(|e| GError::Internal(format!("Failed to serialize results: {}", e)))
+        }
+        OutputFormat::Csv => Ok(render_csv(results)),
+        OutputFormat::NmapXml => Ok(nmap::render_xml(results, None)),
+        OutputFormat::Table => Ok(render_table(results, DEFAULT_TABLE_COLUMNS)),
+    }
+}
+
+/// Like [`render`], but names the scan.
+///
+/// JSON becomes `{ "label": …, "results": […] }`; CSV and table output start
+/// with `# ` comment lines holding the name and description, and nmap XML
+/// holds them in comments after its doctype. Without a label
+/// the output is exactly that of [`render`].
+///
+/// # Errors
+///
+/// Returns [`GError::Internal`] if JSON serialization fails.
+pub fn render_labeled(
+    results: &[ScanResult],
+    format: OutputFormat,
+    label: Option<&ScanLabel>,
+) -> Result<String, GError> {
+    let Some(label) = label else {
+        return render(results, format);
+    };
+    if format == OutputFormat::NmapXml {
+        return Ok(nmap::render_xml(results, Some(label)));
+    }
+    if format == OutputFormat::Json {
+        #[derive(Serialize)]
+        struct Labeled<'a> {
+            label: &'a ScanLabel,
+            results: serde_json::Value,
+        }
+        let results = serde_json::from_str(&render(results, format)?)
+            .map_err(|e| GError::Internal(format!("Failed to serialize results: {}", e)))?;
+        return serde_json::to_string_pretty(&Labeled { label, results })
+            .map_err(|e| GError::Internal(format!("Failed to serialize results: {}", e)));
+    }
+
+    let mut out = comment_header(label);
+    out.push_str(&render(results, format)?);
+    Ok(out)
+}
+
+/// Like [`render_table`], headed by the `# ` label lines of [`render_labeled`].
+pub fn render_table_labeled(
+    results: &[ScanResult],
+    columns: &[TableColumn],
+    label: Option<&ScanLabel>,
+) -> String {
+    let mut out = label.map(comment_header).unwrap_or_default();
+    out.push_str(&render_table(results, columns));
+    out
+}
+
+/// The `# name` and `# description` lines that head labeled CSV and tables.
+fn comment_header(label: &ScanLabel) -> String {
+    // Keep each comment on one line whatever the user typed.
+    let flat = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut out = format!("# {}\n", flat(&label.name));
+    if let Some(description) = &label.description {
+        let _ = writeln!(out, "# {}", flat(description));
+    }
+    out
+}
+
+/// Quotes a CSV field if it contains a delimiter, quote or newline.
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn port_list(res: &ScanResult) -> String {
+    res.open_ports
+        .iter()
+        .map(|p| p.port.to_string())
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+fn issue_list(res: &ScanResult, separator: &str) -> String {
+    res.issues
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// One field of the host's inventory row, if it has one.
+fn asset_field(res: &ScanResult, field: impl Fn(&AssetInfo) -> &Option<String>) -> Option<String> {
+    res.asset.as_ref().and_then(|a| field(a).clone())
+}
+
+fn render_csv(results: &[ScanResult]) -> String {
+    let with_assets = results.iter().any(|r| r.asset.is_some());
+    let mut out = String::from(CSV_HEADER);
+    if with_assets {
+        out.push_str(CSV_ASSET_HEADER);
+    }
+    out.push('\n');
+    for res in results {
+        let mut fields = vec![
+            res.ip.to_string(),
+            res.status.to_string(),
+            res.hostname.clone().unwrap_or_default(),
+            res.mac.clone().unwrap_or_default(),
+            res.vendor.clone().unwrap_or_default(),
+            res.latency
+                .map(|l| format!("{:.1}", l.avg_ms))
+                .unwrap_or_default(),
+            port_list(res),
+            issue_list(res, ";"),
+        ];
+        if with_assets {
+            fields.extend([
+                asset_field(res, |a| &a.asset_tag).unwrap_or_default(),
+                asset_field(res, |a| &a.owner).unwrap_or_default(),
+                asset_field(res, |a| &a.location).unwrap_or_default(),
+            ]);
+        }
+        let row = fields
+            .iter()
+            .map(|f| csv_field(f))
+            .collect::<Vec<_>>()
+            .join(",");
+        out.push_str(&row);
+        out.push('\n');
+    }
+    out
+}
+
+/// Renders `results` as an aligned table of just `columns`, in that order.
+pub fn render_table(results: &[ScanResult], columns: &[TableColumn]) -> String {
+    let rows: Vec<Vec<String>> = results
+        .iter()
+        .map(|res| columns.iter().map(|c| c.cell(res)).collect())
+        .collect();
+    let header: Vec<&str> = columns.iter().map(|c| c.header()).collect();
+    render_text_table(&header, &rows)
+}
+
+/// Lays out `rows` under `header` in space-padded columns, as in
+/// [`render_table`].
+pub fn render_text_table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|h| h.len()).collect();
+    for row in rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    let mut line = |cells: &[&str]| {
+        let mut text = String::new();
+        for (i, cell) in cells.iter().enumerate() {
+            // The last column is not padded, so lines carry no trailing spaces.
+            if i + 1 == cells.len() {
+                text.push_str(cell);
+            } else {
+                let _ = write!(text, "{:<width$}  ", cell, width = widths[i]);
+            }
+        }
+        out.push_str(text.trim_end());
+        out.push('\n');
+    };
+    line(header);
+    for row in rows {
+        line(&row.iter().map(String::as_str).collect::<Vec<_>>());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{PortInfo, ScanStatus, SecurityIssue};
+    use std::net::Ipv4Addr;
+
+    fn sample() -> Vec<ScanResult> {
+        let mut router = ScanResult::new(Ipv4Addr::new(10, 0, 0, 1));
+        router.status = ScanStatus::Online;
+        router.hostname = Some("gw, main".to_string());
+        router.mac = Some("AA:BB:CC:DD:EE:FF".to_string());
+        router.open_ports = vec![PortInfo::new(22), PortInfo::new(80)];
+        router.issues = vec![
+            SecurityIssue::Smb1Enabled,
+            SecurityIssue::CertificateExpired { port: 443 },
+        ];
+        let mut idle = ScanResult::new(Ipv4Addr::new(10, 0, 0, 2));
+        idle.status = ScanStatus::Offline;
+        vec![router, idle]
+    }
+
+    #[test]
+    fn test_output_format_from_str() {
+        assert_eq!("JSON".parse::<OutputFormat>(), Ok(OutputFormat::Json));
+        assert_eq!("csv".parse::<OutputFormat>(), Ok(OutputFormat::Csv));
+        assert_eq!("xml".parse::<OutputFormat>(), Ok(OutputFormat::NmapXml));
+        assert!("yaml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_render_csv_quotes_fields() {
+        let csv = render(&sample(), OutputFormat::Csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(
+            lines[1],
+            "10.0.0.1,Online,\"gw, main\",AA:BB:CC:DD:EE:FF,,,22;80,SMBv1 enabled;expired certificate on port 443"
+        );
+        assert_eq!(lines[2], "10.0.0.2,Offline,,,,,,");
+    }
+
+    #[test]
+    fn test_inventory_columns_appear_with_inventory_data() {
+        let mut results = sample();
+        results[0].asset = Some(AssetInfo {
+            asset_tag: Some("RT-1".to_string()),
+            owner: None,
+            location: Some("Rack 2, U12".to_string()),
+        });
+        let csv = render(&results, OutputFormat::Csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], format!("{}{}", CSV_HEADER, CSV_ASSET_HEADER));
+        assert!(lines[1].ends_with(",RT-1,,\"Rack 2, U12\""));
+        assert!(lines[2].ends_with(",,,"));
+
+        let columns = TableColumn::parse_list("ip,asset,owner").unwrap();
+        let table = render_table(&results, &columns);
+        assert!(table.lines().nth(1).unwrap().contains("RT-1"));
+    }
+
+    #[test]
+    fn test_render_json_round_trips() {
+        let json = render(&sample(), OutputFormat::Json).unwrap();
+        let parsed: Vec<ScanResult> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].hostname.as_deref(), Some("gw, main"));
+        assert_eq!(parsed[0].open_ports, sample()[0].open_ports);
+    }
+
+    #[test]
+    fn test_render_labeled() {
+        let label = ScanLabel {
+            name: "Office 3F audit".to_string(),
+            description: Some("pre-\nmigration".to_string()),
+        };
+        let csv = render_labeled(&sample(), OutputFormat::Csv, Some(&label)).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[..3],
+            ["# Office 3F audit", "# pre- migration", CSV_HEADER]
+        );
+
+        let json = render_labeled(&sample(), OutputFormat::Json, Some(&label)).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["label"]["name"], "Office 3F audit");
+        assert_eq!(parsed["results"].as_array().unwrap().len(), 2);
+
+        assert_eq!(
+            render_labeled(&sample(), OutputFormat::Table, None).unwrap(),
+            render(&sample(), OutputFormat::Table).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_scoped_exports_what_the_view_shows() {
+        let results = sample();
+        let online = |r: &ScanResult| r.status == ScanStatus::Online;
+        let ips = |rs: Vec<ScanResult>| rs.iter().map(|r| r.ip.octets()[3]).collect::<Vec<_>>();
+        let router = Ipv4Addr::new(10, 0, 0, 1);
+        let idle = Ipv4Addr::new(10, 0, 0, 2);
+
+        assert_eq!(ips(scoped(&results, ExportScope::All, online, &[])), [1, 2]);
+        assert_eq!(
+            ips(scoped(&results, ExportScope::Visible, online, &[idle])),
+            [1]
+        );
+        assert_eq!(
+            ips(scoped(&results, ExportScope::Selected, |_| true, &[idle])),
+            [2]
+        );
+        // A selection the filter hides falls back to the visible rows.
+        assert_eq!(
+            ips(scoped(&results, ExportScope::Selected, online, &[idle])),
+            [1]
+        );
+        assert_eq!(
+            ips(scoped(&results, ExportScope::Selected, online, &[router])),
+            [1]
+        );
+        assert_eq!(
+            OutputFormat::from_path(Path::new("hosts.CSV")),
+            OutputFormat::Csv
+        );
+        assert_eq!(
+            OutputFormat::from_path(Path::new("hosts.txt")),
+            OutputFormat::Table
+        );
+    }
+
+    #[test]
+    fn test_render_table_with_chosen_columns() {
+        let columns = TableColumn::parse_list("ip, PORTS,latency").unwrap();
+        let mut results = sample();
+        results[0].latency = Some(crate::types::LatencyStats::from_samples(&[2, 4], 2).unwrap());
+        let table = render_table(&results, &columns);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "IP        PORTS                LATENCY");
+        assert_eq!(lines[1], "10.0.0.1  22 (SSH), 80 (HTTP)  3.0 ms");
+        assert_eq!(lines[2], "10.0.0.2                       -");
+
+        let label = ScanLabel::new("audit");
+        assert!(render_table_labeled(&results, &columns, Some(&label)).starts_with("# audit\nIP"));
+        assert!(
+            TableColumn::parse_list("ip,rtt")
+                .unwrap_err()
+                .contains("'rtt'")
+        );
+        assert!(TableColumn::parse_list(" , ").is_err());
+    }
+
+    #[test]
+    fn test_roles_column_lists_infrastructure_roles() {
+        use crate::types::NetworkRole;
+        let mut results = sample();
+        results[0].roles = vec![NetworkRole::Gateway, NetworkRole::DnsServer];
+        let table = render_table(&results, &TableColumn::parse_list("ip,roles").unwrap());
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[1], "10.0.0.1  Gateway, DNS");
+        assert_eq!(lines[2], "10.0.0.2  -");
+    }
+
+    #[test]
+    fn test_render_table_aligns_columns() {
+        let table = render(&sample(), OutputFormat::Table).unwrap();
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].starts_with("IP        STATUS   HOSTNAME"));
+        assert!(
+            lines[1]
+                .ends_with("22 (SSH), 80 (HTTP)  SMBv1 enabled, expired certificate on port 443")
+        );
+        assert_eq!(lines[2].trim_end(), lines[2]);
+    }
+}
@@ -0,0 +1,166 @@
+//! Heuristic OS fingerprinting from passive scan data.
+//!
+//! Nothing extra is sent on the wire: [`guess_os`] weighs the TTL of the
+//! ICMP echo reply, the open TCP ports and the MAC vendor. Each hint adds
+//! points to one or more [`OsFamily`] candidates and the best-scoring family
+//! wins, with the points as a rough confidence.
+
+use crate::types::{OsFamily, OsGuess, PortInfo};
+
+/// Guesses are capped below certainty: every hint can be spoofed or shared.
+const MAX_CONFIDENCE: u32 = 95;
+
+/// Vendor name fragments (lowercase) hinting at a family, with their weight.
+const VENDOR_HINTS: &[(&str, OsFamily, u32)] = &[
+    ("apple", OsFamily::MacOs, 40),
+    ("microsoft", OsFamily::Windows, 20),
+    ("synology", OsFamily::Nas, 50),
+    ("qnap", OsFamily::Nas, 50),
+    ("western digital", OsFamily::Nas, 30),
+    ("raspberry", OsFamily::Linux, 40),
+    ("cisco", OsFamily::NetworkDevice, 40),
+    ("juniper", OsFamily::NetworkDevice, 40),
+    ("mikrotik", OsFamily::NetworkDevice, 40),
+    ("routerboard", OsFamily::NetworkDevice, 40),
+    ("ubiquiti", OsFamily::NetworkDevice, 40),
+    ("aruba", OsFamily::NetworkDevice, 40),
+    ("netgear", OsFamily::NetworkDevice, 30),
+    ("tp-link", OsFamily::NetworkDevice, 30),
+];
+
+/// Families in tie-break order: the more common answer comes first.
+const FAMILIES: [OsFamily; 5] = [
+    OsFamily::Windows,
+    OsFamily::Linux,
+    OsFamily::MacOs,
+    OsFamily::Nas,
+    OsFamily::NetworkDevice,
+];
+
+#[derive(Default)]
+struct Scores {
+    points: [u32; FAMILIES.len()],
+    evidence: [Vec<String>; FAMILIES.len()],
+}
+
+impl Scores {
+    fn add(&mut self, family: OsFamily, points: u32, hint: &str) {
+        let i = FAMILIES.iter().position(|f| *f == family).unwrap_or(0);
+        self.points[i] += points;
+        self.evidence[i].push(hint.to_string());
+    }
+}
+
+/// Guesses the operating system of a host.
+///
+/// - **TTL**: stacks start at 64 (Linux, macOS, most NAS), 128 (Windows)
+///   or 255 (routers, switches); the initial value is the smallest of these
+///   at or above the received TTL.
+/// - **Ports**: 3389 (RDP) and, more weakly, 445 (SMB) suggest Windows;
+///   22 together with 548 (AFP) suggests macOS or a NAS; 5000/5001 (DSM)
+///   a NAS; 22 alone a Unix-like host.
+/// - **Vendor**: OUI vendors such as Apple, Synology or Cisco.
+///
+/// Returns `None` when there is no hint at all.
+pub fn guess_os(ttl: Option<u8>, open_ports: &[PortInfo], vendor: Option<&str>) -> Option<OsGuess> {
+    let mut scores = Scores::default();
+
+    if let Some(ttl) = ttl {
+        let hint = format!("TTL {}", ttl);
+        match ttl {
+            0..=64 => {
+                scores.add(OsFamily::Linux, 30, &hint);
+                scores.add(OsFamily::MacOs, 30, &hint);
+                scores.add(OsFamily::Nas, 30, &hint);
+            }
+            65..=128 => scores.add(OsFamily::Windows, 40, &hint),
+            _ => scores.add(OsFamily::NetworkDevice, 40, &hint),
+        }
+    }
+
+    let open = |port: u16| open_ports.iter().any(|p| p.port == port);
+    if open(3389) {
+        scores.add(OsFamily::Windows, 40, "port 3389");
+    }
+    if open(445) {
+        scores.add(OsFamily::Windows, 20, "port 445");
+    }
+    if open(22) && open(548) {
+        scores.add(OsFamily::MacOs, 30, "ports 22+548");
+        scores.add(OsFamily::Nas, 30, "ports 22+548");
+    } else if open(22) {
+        scores.add(OsFamily::Linux, 15, "port 22");
+    }
+    if open(5000) || open(5001) {
+        scores.add(OsFamily::Nas, 30, "port 5000/5001");
+    }
+
+    if let Some(vendor) = vendor {
+        let lower = vendor.to_lowercase();
+        if let Some(&(_, family, points)) = VENDOR_HINTS
+            .iter()
+            .find(|(fragment, ..)| lower.contains(fragment))
+        {
+            scores.add(family, points, &format!("vendor {}", vendor));
+        }
+    }
+
+    // `max_by_key` keeps the last maximum, so scan in reverse to prefer earlier families.
+    let (best, &points) = scores
+        .points
+        .iter()
+        .enumerate()
+        .rev()
+        .max_by_key(|(_, points)| **points)?;
+    if points == 0 {
+        return None;
+    }
+    Some(OsGuess {
+        family: FAMILIES[best],
+        confidence: points.min(MAX_CONFIDENCE) as u8,
+        evidence: std::mem::take(&mut scores.evidence[best]),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ports(list: &[u16]) -> Vec<PortInfo> {
+        list.iter().map(|&p| PortInfo::new(p)).collect()
+    }
+
+    #[test]
+    fn test_windows_from_ttl_and_rdp() {
+        let guess = guess_os(Some(127), &ports(&[135, 445, 3389]), None).unwrap();
+        assert_eq!(guess.family, OsFamily::Windows);
+        assert_eq!(guess.confidence, 95);
+        assert_eq!(guess.evidence, ["TTL 127", "port 3389", "port 445"]);
+        assert_eq!(
+            guess.to_string(),
+            "Windows (95%: TTL 127, port 3389, port 445)"
+        );
+    }
+
+    #[test]
+    fn test_ttl_64_with_afp_and_vendor_picks_mac_or_nas() {
+        let afp = ports(&[22, 548]);
+        let mac = guess_os(Some(64), &afp, Some("Apple, Inc.")).unwrap();
+        assert_eq!((mac.family, mac.confidence), (OsFamily::MacOs, 95));
+        let nas = guess_os(Some(64), &afp, Some("Synology Incorporated")).unwrap();
+        assert_eq!(nas.family, OsFamily::Nas);
+        // Without a vendor the tie goes to the earlier family.
+        let tie = guess_os(Some(63), &[], None).unwrap();
+        assert_eq!((tie.family, tie.confidence), (OsFamily::Linux, 30));
+    }
+
+    #[test]
+    fn test_network_gear_and_no_hints() {
+        let router = guess_os(Some(254), &ports(&[23]), Some("MikroTik")).unwrap();
+        assert_eq!(
+            (router.family, router.confidence),
+            (OsFamily::NetworkDevice, 80)
+        );
+        assert_eq!(guess_os(None, &ports(&[80]), Some("Unknown Co")), None);
+    }
+}
@@ -0,0 +1,537 @@
+//! Health checks: uptime monitoring of a few critical hosts.
+//!
+//! [`run`] pings every host in a [`HealthConfig`] each interval, using the
+//! same [`NetworkProvider::echo_async`] as range scans, and reports a
+//! [`HealthEvent::Down`] once a host misses `misses` echoes in a row and a
+//! [`HealthEvent::Recovered`] when it answers again. [`fire_alarms`] passes
+//! an event on to the configured webhook and the Windows Event Log.
+
+use crate::net::NetworkProvider;
+use crate::types::GError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::net::Ipv4Addr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc::Sender;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+#[cfg(windows)]
+use windows::Win32::Foundation::PSID;
+#[cfg(windows)]
+use windows::Win32::System::EventLog::{
+    DeregisterEventSource, EVENTLOG_INFORMATION_TYPE, EVENTLOG_WARNING_TYPE, REPORT_EVENT_TYPE,
+    RegisterEventSourceW, ReportEventW,
+};
+#[cfg(windows)]
+use windows::core::{HSTRING, PCWSTR};
+
+/// Shortest accepted time between two rounds of pings.
+pub const MIN_HEALTH_INTERVAL: Duration = Duration::from_millis(500);
+/// How long a webhook gets to accept the alarm.
+pub const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+/// Source name of the entries written to the Application event log.
+pub const EVENT_SOURCE: &str = "RageScanner";
+
+/// Event IDs, so log filters can tell outages from recoveries.
+#[cfg(windows)]
+const EVENT_ID_DOWN: u32 = 1001;
+#[cfg(windows)]
+const EVENT_ID_RECOVERED: u32 = 1002;
+
+/// A host to keep an eye on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CriticalHost {
+    pub ip: Ipv4Addr,
+    /// Shown in alarms instead of the bare address.
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+impl fmt::Display for CriticalHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{} ({})", name, self.ip),
+            None => write!(f, "{}", self.ip),
+        }
+    }
+}
+
+/// Health check settings, usually read with [`HealthConfig::from_file`].
+///
+/// ```json
+/// {
+///   "hosts": [{ "ip": "10.0.0.1", "name": "core-switch" }, { "ip": "10.0.0.20" }],
+///   "interval_ms": 2000,
+///   "misses": 3,
+///   "webhook": "http://alerts.lan:8080/ragescanner",
+///   "event_log": true
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthConfig {
+    pub hosts: Vec<CriticalHost>,
+    /// Time between the starts of two rounds of pings.
+    #[serde(default = "default_interval_ms")]
+    pub interval_ms: u64,
+    /// Time to wait for each echo reply; capped at the interval.
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Consecutive missed echoes before a host is reported down.
+    #[serde(default = "default_misses")]
+    pub misses: u32,
+    /// `http://` URL that receives each event as a JSON `POST`.
+    #[serde(default)]
+    pub webhook: Option<String>,
+    /// Also write events to the Windows Application log.
+    #[serde(default)]
+    pub event_log: bool,
+}
+
+fn default_interval_ms() -> u64 {
+    5000
+}
+
+fn default_timeout_ms() -> u64 {
+    1000
+}
+
+fn default_misses() -> u32 {
+    3
+}
+
+impl HealthConfig {
+    /// Reads and validates a JSON health check config.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if the file cannot be read or parsed, or
+    /// fails [`validate`](HealthConfig::validate).
+    pub fn from_file(path: &Path) -> Result<Self, GError> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| GError::Internal(format!("Failed to read {}: {}", path.display(), e)))?;
+        let config: Self = serde_json::from_str(&json)
+            .map_err(|e| GError::Internal(format!("{}: {}", path.display(), e)))?;
+        config
+            .validate()
+            .map_err(|e| GError::Internal(format!("{}: {}", path.display(), e)))?;
+        Ok(config)
+    }
+
+    /// Checks that there is something to watch and that the settings are usable.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.hosts.is_empty() {
+            return Err("no hosts to check".to_string());
+        }
+        if self.interval() < MIN_HEALTH_INTERVAL {
+            return Err(format!(
+                "interval_ms must be at least {}",
+                MIN_HEALTH_INTERVAL.as_millis()
+            ));
+        }
+        if self.misses == 0 {
+            return Err("misses must be at least 1".to_string());
+        }
+        if let Some(url) = &self.webhook {
+            parse_http_url(url)?;
+        }
+        Ok(())
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms)
+    }
+
+    /// Echo timeout, never longer than the interval.
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms.clamp(1, self.interval_ms.max(1)))
+    }
+}
+
+/// A change in a critical host's health.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum HealthEvent {
+    /// The host missed `misses` echoes in a row.
+    Down { host: CriticalHost, misses: u32 },
+    /// A host reported down answered again after `downtime`.
+    Recovered {
+        host: CriticalHost,
+        /// Sent to webhooks as whole seconds.
+        #[serde(rename = "downtime_secs", serialize_with = "serialize_secs")]
+        downtime: Duration,
+    },
+}
+
+fn serialize_secs<S: serde::Serializer>(d: &Duration, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_u64(d.as_secs())
+}
+
+impl fmt::Display for HealthEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HealthEvent::Down { host, misses } => {
+                write!(f, "DOWN {}: {} echoes missed in a row", host, misses)
+            }
+            HealthEvent::Recovered { host, downtime } => {
+                write!(
+                    f,
+                    "UP {}: answering again after {}s",
+                    host,
+                    downtime.as_secs()
+                )
+            }
+        }
+    }
+}
+
+/// Consecutive-miss counter for one host.
+#[derive(Debug, Default)]
+pub struct HostHealth {
+    misses: u32,
+    /// When the host was reported down, if it has not recovered since.
+    down_since: Option<Instant>,
+}
+
+impl HostHealth {
+    /// Counts one echo and returns the event it causes, if any.
+    ///
+    /// `Down` fires once, on the `threshold`-th consecutive miss; further
+    /// misses are silent until the host answers and `Recovered` fires.
+    pub fn record(
+        &mut self,
+        host: &CriticalHost,
+        answered: bool,
+        threshold: u32,
+    ) -> Option<HealthEvent> {
+        if answered {
+            self.misses = 0;
+            return self.down_since.take().map(|since| HealthEvent::Recovered {
+                host: host.clone(),
+                downtime: since.elapsed(),
+            });
+        }
+        self.misses = self.misses.saturating_add(1);
+        (self.misses == threshold && self.down_since.is_none()).then(|| {
+            self.down_since = Some(Instant::now());
+            HealthEvent::Down {
+                host: host.clone(),
+                misses: self.misses,
+            }
+        })
+    }
+
+    pub fn is_down(&self) -> bool {
+        self.down_since.is_some()
+    }
+}
+
+/// Pings the configured hosts every interval until `cancel` fires, sending
+/// each [`HealthEvent`] to `tx`.
+///
+/// All hosts of a round are pinged concurrently. Local failures such as a
+/// disconnected adapter count as misses, since the hosts are unreachable.
+pub async fn run(
+    net: Arc<dyn NetworkProvider>,
+    config: &HealthConfig,
+    tx: Sender<HealthEvent>,
+    cancel: CancellationToken,
+) {
+    let mut health: Vec<HostHealth> = config.hosts.iter().map(|_| HostHealth::default()).collect();
+    let mut ticker = tokio::time::interval(config.interval());
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let timeout = config.timeout();
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => return,
+            _ = ticker.tick() => {}
+        }
+        let replies = futures::future::join_all(
+            config
+                .hosts
+                .iter()
+                .map(|host| net.echo_async(host.ip, timeout)),
+        )
+        .await;
+        for ((host, state), reply) in config.hosts.iter().zip(&mut health).zip(replies) {
+            if let Err(e) = &reply {
+                log::warn!("Health check of {} failed: {}", host, e);
+            }
+            let answered = matches!(reply, Ok(Some(_)));
+            if let Some(event) = state.record(host, answered, config.misses) {
+                log::info!("{}", event);
+                if tx.send(event).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Sends `event` to the webhook and the event log, as configured.
+///
+/// Both are attempted even if one fails.
+///
+/// # Errors
+///
+/// Returns the first failure; see [`post_webhook`] and [`write_event_log`].
+pub async fn fire_alarms(config: &HealthConfig, event: &HealthEvent) -> Result<(), GError> {
+    let webhook = match &config.webhook {
+        Some(url) => post_webhook(url, event).await,
+        None => Ok(()),
+    };
+    let event_log = if config.event_log {
+        write_event_log(event)
+    } else {
+        Ok(())
+    };
+    webhook.and(event_log)
+}
+
+/// Splits an `http://host[:port][/path]` URL into host, port and path.
+pub fn parse_http_url(url: &str) -> Result<(String, u16, String), String> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("webhook '{}' must be an http:// URL", url))?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (
+            host,
+            port.parse()
+                .map_err(|_| format!("invalid port in webhook '{}'", url))?,
+        ),
+        None => (authority, 80),
+    };
+    if host.is_empty() {
+        return Err(format!("webhook '{}' has no host", url));
+    }
+    Ok((host.to_string(), port, path.to_string()))
+}
+
+/// Posts `event` as JSON to an `http://` webhook.
+///
+/// # Errors
+///
+/// Returns [`GError::Internal`] if the URL is invalid, the connection fails
+/// or times out after [`WEBHOOK_TIMEOUT`], or the status is not 2xx.
+pub async fn post_webhook<T: Serialize>(url: &str, event: &T) -> Result<(), GError> {
+    let (host, port, path) = parse_http_url(url).map_err(GError::Internal)?;
+    let body = serde_json::to_string(event)
+        .map_err(|e| GError::Internal(format!("Failed to serialize event: {}", e)))?;
+    let request = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        host,
+        body.len(),
+        body
+    );
+    let exchange = async {
+        let mut stream = TcpStream::connect((host.as_str(), port)).await?;
+        stream.write_all(request.as_bytes()).await?;
+        let mut status_line = [0u8; 12];
+        stream.read_exact(&mut status_line).await?;
+        Ok::<_, std::io::Error>(status_line)
+    };
+    let status_line = tokio::time::timeout(WEBHOOK_TIMEOUT, exchange)
+        .await
+        .map_err(|_| GError::Timeout(format!("Webhook {} did not answer", url)))?
+        .map_err(|e| GError::Internal(format!("Webhook {} failed: {}", url, e)))?;
+    // "HTTP/1.1 204"
+    match &status_line[9..10] {
+        b"2" => Ok(()),
+        _ => Err(GError::Internal(format!(
+            "Webhook {} answered {}",
+            url,
+            String::from_utf8_lossy(&status_line[9..]).trim()
+        ))),
+    }
+}
+
+/// Writes `event` to the Windows Application log under [`EVENT_SOURCE`].
+///
+/// Outages are warnings and recoveries informational. Without a registered
+/// message file, Event Viewer shows the text as the event's insertion string.
+///
+/// # Errors
+///
+/// Returns the error classified by [`GError::from_hresult`] if the source
+/// cannot be opened or the entry written.
+#[cfg(windows)]
+pub fn write_event_log(event: &HealthEvent) -> Result<(), GError> {
+    let (kind, id): (REPORT_EVENT_TYPE, u32) = match event {
+        HealthEvent::Down { .. } => (EVENTLOG_WARNING_TYPE, EVENT_ID_DOWN),
+        HealthEvent::Recovered { .. } => (EVENTLOG_INFORMATION_TYPE, EVENT_ID_RECOVERED),
+    };
+    let source = HSTRING::from(EVENT_SOURCE);
+    let message = HSTRING::from(event.to_string());
+    unsafe {
+        let log = RegisterEventSourceW(PCWSTR::null(), &source)
+            .map_err(|e| GError::from_hresult(e.code().0, "RegisterEventSourceW"))?;
+        let written = ReportEventW(
+            log,
+            kind,
+            0,
+            id,
+            PSID::default(),
+            0,
+            Some(&[PCWSTR::from_raw(message.as_ptr())]),
+            None,
+        );
+        let _ = DeregisterEventSource(log);
+        written.map_err(|e| GError::from_hresult(e.code().0, "ReportEventW"))
+    }
+}
+
+/// There is no Event Log outside Windows; the alarm still reaches the
+/// webhook and `ragescanner.log`.
+///
+/// # Errors
+///
+/// Always returns [`GError::Internal`].
+#[cfg(not(windows))]
+pub fn write_event_log(event: &HealthEvent) -> Result<(), GError> {
+    Err(GError::Internal(format!(
+        "No Windows Event Log to record '{}' in",
+        event
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::MockNet;
+
+    fn host(last: u8) -> CriticalHost {
+        CriticalHost {
+            ip: Ipv4Addr::new(192, 168, 1, last),
+            name: None,
+        }
+    }
+
+    #[test]
+    fn test_down_fires_once_after_threshold_then_recovers() {
+        let host = host(9);
+        let mut health = HostHealth::default();
+        assert_eq!(health.record(&host, false, 3), None);
+        assert_eq!(health.record(&host, false, 3), None);
+        assert_eq!(
+            health.record(&host, false, 3),
+            Some(HealthEvent::Down {
+                host: host.clone(),
+                misses: 3
+            })
+        );
+        assert!(health.is_down());
+        assert_eq!(health.record(&host, false, 3), None);
+        assert!(matches!(
+            health.record(&host, true, 3),
+            Some(HealthEvent::Recovered { .. })
+        ));
+        assert!(!health.is_down());
+        // A blip shorter than the threshold stays quiet.
+        assert_eq!(health.record(&host, false, 3), None);
+        assert_eq!(health.record(&host, true, 3), None);
+    }
+
+    #[test]
+    fn test_config_defaults_and_validation() {
+        let config: HealthConfig =
+            serde_json::from_str(r#"{"hosts": [{"ip": "10.0.0.1", "name": "core-switch"}]}"#)
+                .unwrap();
+        assert_eq!((config.interval_ms, config.misses), (5000, 3));
+        assert_eq!(config.hosts[0].to_string(), "core-switch (10.0.0.1)");
+        assert!(config.validate().is_ok());
+
+        let with = |f: fn(&mut HealthConfig)| {
+            let mut c = config.clone();
+            f(&mut c);
+            c.validate()
+        };
+        assert!(with(|c| c.hosts.clear()).is_err());
+        assert!(with(|c| c.interval_ms = 100).is_err());
+        assert!(with(|c| c.misses = 0).is_err());
+        assert!(with(|c| c.webhook = Some("https://hooks.example".into())).is_err());
+        assert!(with(|c| c.webhook = Some("http://alerts.lan:80x/".into())).is_err());
+        assert!(with(|c| c.webhook = Some("http://alerts.lan:8080/rs".into())).is_ok());
+        assert_eq!(
+            parse_http_url("http://alerts.lan"),
+            Ok(("alerts.lan".to_string(), 80, "/".to_string()))
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_run_reports_unreachable_host() {
+        // MockNet answers .1 and nothing else.
+        let config = HealthConfig {
+            hosts: vec![host(1), host(3)],
+            interval_ms: 1000,
+            timeout_ms: 1000,
+            misses: 2,
+            webhook: None,
+            event_log: false,
+        };
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let cancel = CancellationToken::new();
+        let task = tokio::spawn({
+            let (config, cancel) = (config.clone(), cancel.clone());
+            async move { run(Arc::new(MockNet), &config, tx, cancel).await }
+        });
+
+        let start = Instant::now();
+        let event = rx.recv().await.unwrap();
+        assert_eq!(
+            event,
+            HealthEvent::Down {
+                host: host(3),
+                misses: 2
+            }
+        );
+        // The first round is immediate, the second one interval later.
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+
+        cancel.cancel();
+        task.await.unwrap();
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_webhook_posts_json_event() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/alarm", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.ends_with(b"}") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let event = HealthEvent::Down {
+            host: host(3),
+            misses: 3,
+        };
+        post_webhook(&url, &event).await.unwrap();
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /alarm HTTP/1.1\r\n"));
+        assert!(
+            request.ends_with(
+                r#"{"event":"down","host":{"ip":"192.168.1.3","name":null},"misses":3}"#
+            )
+        );
+    }
+}
@@ -0,0 +1,684 @@
+//! Scan history persisted to a local SQLite database.
+//!
+//! Every completed scan is stored as one row in `scans` plus one row per host
+//! in `results`. Each result keeps its full [`ScanResult`] as JSON alongside
+//! indexed `ip`/`mac` columns, so past scans can be loaded back unchanged and
+//! a device can be followed across scans by its MAC address.
+//!
+//! [`History::search`] answers "when was this device last here?" by
+//! finding every sighting of a MAC, IP or hostname across stored scans.
+//!
+//! A [`RetentionPolicy`] keeps long-running installs bounded: old scans are
+//! pruned after each save, and [`History::compact`] returns the freed pages
+//! to the file system.
+//!
+//! Enabled by the `history` feature.
+
+use crate::types::{GError, ScanLabel, ScanResult, ScanStatus};
+use crate::wol::parse_mac;
+use rusqlite::{Connection, OptionalExtension, params};
+use std::net::Ipv4Addr;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Default database location, next to `ragescanner.log`.
+pub const DEFAULT_HISTORY_PATH: &str = "ragescanner.history.db";
+
+/// Environment variable limiting the number of stored scans.
+pub const KEEP_SCANS_VAR: &str = "RAGESCANNER_HISTORY_KEEP_SCANS";
+/// Environment variable limiting the age of stored scans, in days.
+pub const KEEP_DAYS_VAR: &str = "RAGESCANNER_HISTORY_KEEP_DAYS";
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS scans (
+        id          INTEGER PRIMARY KEY AUTOINCREMENT,
+        target      TEXT    NOT NULL,
+        started_at  INTEGER NOT NULL,
+        finished_at INTEGER NOT NULL,
+        label       TEXT,
+        description TEXT
+    );
+    CREATE TABLE IF NOT EXISTS results (
+        scan_id INTEGER NOT NULL REFERENCES scans(id) ON DELETE CASCADE,
+        ip      TEXT    NOT NULL,
+        mac     TEXT,
+        online  INTEGER NOT NULL,
+        data    TEXT    NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS results_scan ON results(scan_id);
+    CREATE INDEX IF NOT EXISTS results_mac ON results(mac);
+";
+
+/// Summary of one stored scan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanRecord {
+    pub id: i64,
+    /// The range or target list as the user entered it.
+    pub target: String,
+    pub label: Option<ScanLabel>,
+    /// Seconds since the Unix epoch.
+    pub started_at: u64,
+    /// Seconds since the Unix epoch.
+    pub finished_at: u64,
+    pub host_count: u32,
+    pub online_count: u32,
+}
+
+/// One appearance of a device in a stored scan.
+#[derive(Debug, Clone)]
+pub struct DeviceSighting {
+    pub scan_id: i64,
+    /// When the scan containing this sighting finished, in seconds since the Unix epoch.
+    pub seen_at: u64,
+    pub result: ScanResult,
+}
+
+/// What [`History::search`] looks for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HistoryQuery {
+    /// A MAC address, normalized to `XX:XX:XX:XX:XX:XX`.
+    Mac(String),
+    Ip(Ipv4Addr),
+    /// Part of a hostname, matched case-insensitively.
+    Hostname(String),
+}
+
+impl HistoryQuery {
+    /// Reads search-box text: an IPv4 address, a MAC address (with `:`,
+    /// `-` or `.` separators), or else part of a hostname. `None` if blank.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+        if let Ok(ip) = text.parse() {
+            return Some(Self::Ip(ip));
+        }
+        if text.contains([':', '-', '.'])
+            && let Ok(mac) = parse_mac(text)
+        {
+            let hex: Vec<String> = mac.iter().map(|b| format!("{:02X}", b)).collect();
+            return Some(Self::Mac(hex.join(":")));
+        }
+        Some(Self::Hostname(text.to_string()))
+    }
+}
+
+/// Which scans to keep; the default keeps everything.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Keep only the newest this many scans.
+    pub keep_scans: Option<u32>,
+    /// Drop scans that finished more than this many days ago.
+    pub keep_days: Option<u32>,
+}
+
+impl RetentionPolicy {
+    /// Reads [`KEEP_SCANS_VAR`] and [`KEEP_DAYS_VAR`]. Unset variables keep
+    /// that limit off; invalid ones are logged and ignored.
+    pub fn from_env() -> Self {
+        let limit = |var: &str| {
+            let value = std::env::var(var).ok()?;
+            match value.trim().parse() {
+                Ok(limit) => Some(limit),
+                Err(_) => {
+                    log::warn!("Ignoring {}={:?}: not a whole number", var, value);
+                    None
+                }
+            }
+        };
+        Self {
+            keep_scans: limit(KEEP_SCANS_VAR),
+            keep_days: limit(KEEP_DAYS_VAR),
+        }
+    }
+
+    /// Whether the policy never drops a scan.
+    pub fn is_unlimited(&self) -> bool {
+        self.keep_scans.is_none() && self.keep_days.is_none()
+    }
+}
+
+fn db_error(context: &str, e: rusqlite::Error) -> GError {
+    GError::Internal(format!("{}: {}", context, e))
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn decode(json: &str) -> Result<ScanResult, GError> {
+    serde_json::from_str(json)
+        .map_err(|e| GError::Internal(format!("Corrupt history entry: {}", e)))
+}
+
+/// Appends a completed scan to the database at [`DEFAULT_HISTORY_PATH`],
+/// then prunes it with [`RetentionPolicy::from_env`].
+///
+/// Convenience for frontends that only write history once per scan. A failed
+/// prune is logged; the scan is saved regardless.
+pub fn save_completed_scan(
+    target: &str,
+    label: Option<&ScanLabel>,
+    started_at: SystemTime,
+    results: &[ScanResult],
+) -> Result<i64, GError> {
+    let mut history = History::open(Path::new(DEFAULT_HISTORY_PATH))?;
+    let id = history.record_scan(target, label, started_at, results)?;
+    if let Err(e) = history.prune(&RetentionPolicy::from_env()) {
+        log::warn!("Failed to prune scan history: {}", e);
+    }
+    Ok(id)
+}
+
+/// Handle to the history database.
+pub struct History {
+    conn: Connection,
+}
+
+impl History {
+    /// Opens (creating if needed) the database at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if the file cannot be opened or the schema
+    /// cannot be created.
+    pub fn open(path: &Path) -> Result<Self, GError> {
+        let conn = Connection::open(path).map_err(|e| {
+            GError::Internal(format!(
+                "Failed to open history '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Self::init(conn)
+    }
+
+    /// Opens a private, non-persistent database. Useful for tests.
+    pub fn open_in_memory() -> Result<Self, GError> {
+        let conn = Connection::open_in_memory()
+            .map_err(|e| db_error("Failed to open in-memory history", e))?;
+        Self::init(conn)
+    }
+
+    fn init(conn: Connection) -> Result<Self, GError> {
+        conn.execute_batch(SCHEMA)
+            .map_err(|e| db_error("Failed to create history schema", e))?;
+        Self::add_label_columns(&conn)?;
+        conn.execute_batch("PRAGMA foreign_keys = ON;")
+            .map_err(|e| db_error("Failed to configure history", e))?;
+        Ok(Self { conn })
+    }
+
+    /// Adds the label columns to databases created before scans had labels.
+    fn add_label_columns(conn: &Connection) -> Result<(), GError> {
+        let has_label = conn.prepare("SELECT label FROM scans LIMIT 0").is_ok();
+        if !has_label {
+            conn.execute_batch(
+                "ALTER TABLE scans ADD COLUMN label TEXT;
+                 ALTER TABLE scans ADD COLUMN description TEXT;",
+            )
+            .map_err(|e| db_error("Failed to upgrade history schema", e))?;
+        }
+        Ok(())
+    }
+
+    /// Stores a completed scan and returns its id.
+    ///
+    /// The scan and its results are written in a single transaction.
+    pub fn record_scan(
+        &mut self,
+        target: &str,
+        label: Option<&ScanLabel>,
+        started_at: SystemTime,
+        results: &[ScanResult],
+    ) -> Result<i64, GError> {
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| db_error("Failed to start history transaction", e))?;
+        tx.execute(
+            "INSERT INTO scans (target, started_at, finished_at, label, description)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                target,
+                unix_secs(started_at) as i64,
+                unix_secs(SystemTime::now()) as i64,
+                label.map(|l| &l.name),
+                label.and_then(|l| l.description.as_ref())
+            ],
+        )
+        .map_err(|e| db_error("Failed to record scan", e))?;
+        let scan_id = tx.last_insert_rowid();
+
+        {
+            let mut insert = tx
+                .prepare(
+                    "INSERT INTO results (scan_id, ip, mac, online, data) VALUES (?1, ?2, ?3, ?4, ?5)",
+                )
+                .map_err(|e| db_error("Failed to prepare result insert", e))?;
+            for res in results {
+                let data = serde_json::to_string(res)
+                    .map_err(|e| GError::Internal(format!("Failed to serialize result: {}", e)))?;
+                insert
+                    .execute(params![
+                        scan_id,
+                        res.ip.to_string(),
+                        res.mac,
+                        res.status == ScanStatus::Online,
+                        data
+                    ])
+                    .map_err(|e| db_error("Failed to record result", e))?;
+            }
+        }
+
+        tx.commit()
+            .map_err(|e| db_error("Failed to commit scan history", e))?;
+        log::info!(
+            "Saved scan {} ({} results) to history",
+            scan_id,
+            results.len()
+        );
+        Ok(scan_id)
+    }
+
+    /// Deletes the scans (and their results) that `policy` does not keep and
+    /// returns how many were removed.
+    ///
+    /// The file does not shrink until [`compact`](History::compact).
+    pub fn prune(&mut self, policy: &RetentionPolicy) -> Result<usize, GError> {
+        if policy.is_unlimited() {
+            return Ok(0);
+        }
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| db_error("Failed to start history transaction", e))?;
+        let mut removed = 0;
+        if let Some(keep) = policy.keep_scans {
+            removed += tx
+                .execute(
+                    "DELETE FROM scans WHERE id NOT IN
+                         (SELECT id FROM scans ORDER BY id DESC LIMIT ?1)",
+                    [keep],
+                )
+                .map_err(|e| db_error("Failed to prune scans", e))?;
+        }
+        if let Some(days) = policy.keep_days {
+            let cutoff =
+                unix_secs(SystemTime::now()).saturating_sub(u64::from(days) * SECS_PER_DAY);
+            removed += tx
+                .execute("DELETE FROM scans WHERE finished_at < ?1", [cutoff as i64])
+                .map_err(|e| db_error("Failed to prune scans", e))?;
+        }
+        // Databases from before foreign keys were enforced may hold orphans.
+        tx.execute(
+            "DELETE FROM results WHERE scan_id NOT IN (SELECT id FROM scans)",
+            [],
+        )
+        .map_err(|e| db_error("Failed to prune results", e))?;
+        tx.commit()
+            .map_err(|e| db_error("Failed to commit history pruning", e))?;
+        if removed > 0 {
+            log::info!("Pruned {} scans from history", removed);
+        }
+        Ok(removed)
+    }
+
+    /// Rebuilds the database file to release the space of deleted scans.
+    pub fn compact(&self) -> Result<(), GError> {
+        self.conn
+            .execute_batch("VACUUM;")
+            .map_err(|e| db_error("Failed to compact history", e))
+    }
+
+    /// Lists stored scans, newest first.
+    pub fn list_scans(&self) -> Result<Vec<ScanRecord>, GError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT s.id, s.target, s.started_at, s.finished_at,
+                        COUNT(r.scan_id), COALESCE(SUM(r.online), 0), s.label, s.description
+                 FROM scans s LEFT JOIN results r ON r.scan_id = s.id
+                 GROUP BY s.id ORDER BY s.id DESC",
+            )
+            .map_err(|e| db_error("Failed to query scans", e))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(ScanRecord {
+                    id: row.get(0)?,
+                    target: row.get(1)?,
+                    started_at: row.get::<_, i64>(2)? as u64,
+                    finished_at: row.get::<_, i64>(3)? as u64,
+                    host_count: row.get(4)?,
+                    online_count: row.get(5)?,
+                    label: row.get::<_, Option<String>>(6)?.map(|name| ScanLabel {
+                        name,
+                        description: row.get(7).ok().flatten(),
+                    }),
+                })
+            })
+            .map_err(|e| db_error("Failed to query scans", e))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| db_error("Failed to read scans", e))
+    }
+
+    /// Loads the results of a stored scan in address order.
+    ///
+    /// Returns `None` if no scan has this id.
+    pub fn load_scan(&self, scan_id: i64) -> Result<Option<Vec<ScanResult>>, GError> {
+        let exists = self
+            .conn
+            .query_row("SELECT 1 FROM scans WHERE id = ?1", [scan_id], |_| Ok(()))
+            .optional()
+            .map_err(|e| db_error("Failed to look up scan", e))?;
+        if exists.is_none() {
+            return Ok(None);
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT data FROM results WHERE scan_id = ?1")
+            .map_err(|e| db_error("Failed to query results", e))?;
+        let rows = stmt
+            .query_map([scan_id], |row| row.get::<_, String>(0))
+            .map_err(|e| db_error("Failed to query results", e))?;
+
+        let mut results = Vec::new();
+        for json in rows {
+            let json = json.map_err(|e| db_error("Failed to read result", e))?;
+            results.push(decode(&json)?);
+        }
+        results.sort_by_key(|r| r.ip);
+        Ok(Some(results))
+    }
+
+    /// Returns every stored sighting of the device with `mac`, oldest first.
+    ///
+    /// The MAC is matched case-insensitively.
+    pub fn device_history(&self, mac: &str) -> Result<Vec<DeviceSighting>, GError> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT r.scan_id, s.finished_at, r.data
+                 FROM results r JOIN scans s ON s.id = r.scan_id
+                 WHERE r.mac = ?1 COLLATE NOCASE
+                 ORDER BY s.id",
+            )
+            .map_err(|e| db_error("Failed to query device history", e))?;
+        let rows = stmt
+            .query_map([mac], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })
+            .map_err(|e| db_error("Failed to query device history", e))?;
+
+        let mut sightings = Vec::new();
+        for row in rows {
+            let (scan_id, seen_at, json) =
+                row.map_err(|e| db_error("Failed to read device history", e))?;
+            sightings.push(DeviceSighting {
+                scan_id,
+                seen_at: seen_at as u64,
+                result: decode(&json)?,
+            });
+        }
+        Ok(sightings)
+    }
+
+    /// Returns every online sighting matching `query`, newest first.
+    pub fn search(&self, query: &HistoryQuery) -> Result<Vec<DeviceSighting>, GError> {
+        let (condition, value) = match query {
+            HistoryQuery::Mac(mac) => ("r.mac = ?1 COLLATE NOCASE", mac.clone()),
+            HistoryQuery::Ip(ip) => ("r.ip = ?1", ip.to_string()),
+            HistoryQuery::Hostname(name) => (
+                "json_extract(r.data, '$.hostname') LIKE ?1 ESCAPE '\\'",
+                format!("%{}%", escape_like(name)),
+            ),
+        };
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT r.scan_id, s.finished_at, r.data
+                 FROM results r JOIN scans s ON s.id = r.scan_id
+                 WHERE r.online = 1 AND {}
+                 ORDER BY s.id DESC",
+                condition
+            ))
+            .map_err(|e| db_error("Failed to search history", e))?;
+        let rows = stmt
+            .query_map([value], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })
+            .map_err(|e| db_error("Failed to search history", e))?;
+
+        let mut sightings = Vec::new();
+        for row in rows {
+            let (scan_id, seen_at, json) =
+                row.map_err(|e| db_error("Failed to read history search", e))?;
+            sightings.push(DeviceSighting {
+                scan_id,
+                seen_at: seen_at as u64,
+                result: decode(&json)?,
+            });
+        }
+        Ok(sightings)
+    }
+}
+
+/// Escapes the `LIKE` wildcards in `text` with backslashes.
+fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PortInfo;
+    use std::net::Ipv4Addr;
+
+    fn host(last: u8, mac: Option<&str>, online: bool) -> ScanResult {
+        let mut res = ScanResult::new(Ipv4Addr::new(10, 0, 0, last));
+        res.mac = mac.map(str::to_string);
+        res.status = if online {
+            ScanStatus::Online
+        } else {
+            ScanStatus::Offline
+        };
+        res
+    }
+
+    #[test]
+    fn test_record_list_and_load() {
+        let mut history = History::open_in_memory().unwrap();
+        let mut router = host(1, Some("AA:BB:CC:00:00:01"), true);
+        router.open_ports = vec![PortInfo::new(80)];
+        let results = vec![host(2, None, false), router];
+
+        let label = ScanLabel {
+            name: "Office 3F audit".to_string(),
+            description: Some("pre-migration".to_string()),
+        };
+        let id = history
+            .record_scan("10.0.0.1-2", Some(&label), SystemTime::now(), &results)
+            .unwrap();
+        let scans = history.list_scans().unwrap();
+        assert_eq!(scans.len(), 1);
+        assert_eq!(scans[0].id, id);
+        assert_eq!(scans[0].target, "10.0.0.1-2");
+        assert_eq!(scans[0].label.as_ref(), Some(&label));
+        assert_eq!((scans[0].host_count, scans[0].online_count), (2, 1));
+
+        let loaded = history.load_scan(id).unwrap().unwrap();
+        assert_eq!(loaded[0].ip, Ipv4Addr::new(10, 0, 0, 1));
+        assert_eq!(loaded[0].open_ports, vec![PortInfo::new(80)]);
+        assert!(history.load_scan(id + 1).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_upgrades_database_without_labels() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE scans (
+                id INTEGER PRIMARY KEY AUTOINCREMENT, target TEXT NOT NULL,
+                started_at INTEGER NOT NULL, finished_at INTEGER NOT NULL
+            );
+            INSERT INTO scans (target, started_at, finished_at) VALUES ('old', 1, 2);",
+        )
+        .unwrap();
+        let mut history = History::init(conn).unwrap();
+        history
+            .record_scan("new", Some(&ScanLabel::new("lab")), SystemTime::now(), &[])
+            .unwrap();
+
+        let labels: Vec<_> = history
+            .list_scans()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.label.map(|l| l.name))
+            .collect();
+        assert_eq!(labels, [Some("lab".to_string()), None]);
+    }
+
+    #[test]
+    fn test_prune_by_count_and_age() {
+        let mut history = History::open_in_memory().unwrap();
+        for target in ["a", "b", "c", "d"] {
+            history
+                .record_scan(target, None, SystemTime::now(), &[host(1, None, true)])
+                .unwrap();
+        }
+        history
+            .conn
+            .execute("UPDATE scans SET finished_at = 0 WHERE target = 'c'", [])
+            .unwrap();
+        assert_eq!(history.prune(&RetentionPolicy::default()).unwrap(), 0);
+
+        let policy = RetentionPolicy {
+            keep_scans: Some(3),
+            keep_days: Some(30),
+        };
+        assert_eq!(history.prune(&policy).unwrap(), 2);
+        let targets: Vec<_> = history
+            .list_scans()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.target)
+            .collect();
+        assert_eq!(targets, ["d", "b"]);
+        let results: i64 = history
+            .conn
+            .query_row("SELECT COUNT(*) FROM results", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(results, 2);
+        history.compact().unwrap();
+    }
+
+    #[test]
+    fn test_device_history_by_mac() {
+        let mut history = History::open_in_memory().unwrap();
+        let mac = "AA:BB:CC:00:00:09";
+        history
+            .record_scan("a", None, SystemTime::now(), &[host(9, Some(mac), true)])
+            .unwrap();
+        history
+            .record_scan("b", None, SystemTime::now(), &[host(1, None, true)])
+            .unwrap();
+        // Same device, new address.
+        history
+            .record_scan("c", None, SystemTime::now(), &[host(20, Some(mac), true)])
+            .unwrap();
+
+        let sightings = history.device_history(&mac.to_lowercase()).unwrap();
+        let ips: Vec<_> = sightings.iter().map(|s| s.result.ip).collect();
+        assert_eq!(
+            ips,
+            vec![Ipv4Addr::new(10, 0, 0, 9), Ipv4Addr::new(10, 0, 0, 20)]
+        );
+    }
+
+    #[test]
+    fn test_parse_search_query() {
+        assert_eq!(
+            HistoryQuery::parse(" 10.0.0.7 "),
+            Some(HistoryQuery::Ip(Ipv4Addr::new(10, 0, 0, 7)))
+        );
+        assert_eq!(
+            HistoryQuery::parse("aa-bb-cc-00-00-09"),
+            Some(HistoryQuery::Mac("AA:BB:CC:00:00:09".to_string()))
+        );
+        assert_eq!(
+            HistoryQuery::parse("nas.local"),
+            Some(HistoryQuery::Hostname("nas.local".to_string()))
+        );
+        assert_eq!(HistoryQuery::parse("  "), None);
+    }
+
+    #[test]
+    fn test_search_by_mac_ip_and_hostname() {
+        let mut history = History::open_in_memory().unwrap();
+        let mac = "AA:BB:CC:00:00:09";
+        let mut laptop = host(9, Some(mac), true);
+        laptop.hostname = Some("Alices-Laptop".to_string());
+        history
+            .record_scan(
+                "a",
+                None,
+                SystemTime::now(),
+                &[laptop, host(5, None, false)],
+            )
+            .unwrap();
+        let mut moved = host(20, Some(mac), true);
+        moved.hostname = Some("alices-laptop".to_string());
+        let second = history
+            .record_scan("b", None, SystemTime::now(), &[moved, host(9, None, true)])
+            .unwrap();
+
+        let by_mac = history
+            .search(&HistoryQuery::parse("aa:bb:cc:00:00:09").unwrap())
+            .unwrap();
+        assert_eq!(by_mac.len(), 2);
+        assert_eq!(by_mac[0].scan_id, second);
+
+        let by_ip = history
+            .search(&HistoryQuery::Ip(Ipv4Addr::new(10, 0, 0, 9)))
+            .unwrap();
+        assert_eq!(by_ip.len(), 2);
+        // Offline rows are not sightings.
+        let offline = history
+            .search(&HistoryQuery::Ip(Ipv4Addr::new(10, 0, 0, 5)))
+            .unwrap();
+        assert!(offline.is_empty());
+
+        let by_name = history
+            .search(&HistoryQuery::Hostname("LAPTOP".to_string()))
+            .unwrap();
+        let ips: Vec<_> = by_name.iter().map(|s| s.result.ip).collect();
+        assert_eq!(
+            ips,
+            vec![Ipv4Addr::new(10, 0, 0, 20), Ipv4Addr::new(10, 0, 0, 9)]
+        );
+        let wildcard = history
+            .search(&HistoryQuery::Hostname("%".to_string()))
+            .unwrap();
+        assert!(wildcard.is_empty());
+    }
+}
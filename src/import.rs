@@ -0,0 +1,410 @@
+//! Importers for scan exports produced by other IP scanners.
+//!
+//! Converts Angry IP Scanner and Advanced IP Scanner exports (CSV or XML)
+//! into a [`Session`] so results can be viewed, resumed, and re-exported
+//! with RageScanner.
+
+use crate::session::Session;
+use crate::types::{GError, PortInfo, ScanResult, ScanStatus};
+use std::fs;
+use std::net::Ipv4Addr;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Recognized third-party export formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// Angry IP Scanner CSV/TXT export (`IP,Ping,Hostname,Ports,...`).
+    AngryCsv,
+    /// Angry IP Scanner XML export (`<host address="...">`).
+    AngryXml,
+    /// Advanced IP Scanner CSV export (`Status;Name;IP;Manufacturer;MAC address`).
+    AdvancedCsv,
+    /// Advanced IP Scanner XML export (`<row><status>...</status>...`).
+    AdvancedXml,
+}
+
+impl ImportFormat {
+    /// Guesses the export format from the file contents.
+    ///
+    /// Returns `None` if the contents match none of the supported formats.
+    pub fn detect(contents: &str) -> Option<Self> {
+        let head: String = contents
+            .chars()
+            .take(4096)
+            .collect::<String>()
+            .to_lowercase();
+        if head.trim_start().starts_with('<') {
+            if head.contains("<host ") {
+                Some(ImportFormat::AngryXml)
+            } else if head.contains("<row") {
+                Some(ImportFormat::AdvancedXml)
+            } else {
+                None
+            }
+        } else if head.contains("manufacturer") || head.contains("mac address;") {
+            Some(ImportFormat::AdvancedCsv)
+        } else if head.contains("ip") && head.contains("ping") {
+            Some(ImportFormat::AngryCsv)
+        } else {
+            None
+        }
+    }
+}
+
+/// Reads an export file and converts it into a [`Session`].
+///
+/// # Errors
+///
+/// Returns [`GError::Internal`] if the file cannot be read, its format is not
+/// recognized, or it contains no IPv4 hosts.
+pub fn import_file(path: &Path) -> Result<Session, GError> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| GError::Internal(format!("Failed to read '{}': {}", path.display(), e)))?;
+    let format = ImportFormat::detect(&contents).ok_or_else(|| {
+        GError::Internal(format!(
+            "Unrecognized export format in '{}'",
+            path.display()
+        ))
+    })?;
+    import_str(&contents, format)
+}
+
+/// Converts export contents of a known format into a [`Session`].
+///
+/// The session range spans the lowest to highest imported address.
+///
+/// # Errors
+///
+/// Returns [`GError::Internal`] if no IPv4 hosts could be parsed.
+pub fn import_str(contents: &str, format: ImportFormat) -> Result<Session, GError> {
+    let mut results = match format {
+        ImportFormat::AngryCsv | ImportFormat::AdvancedCsv => parse_csv(contents),
+        ImportFormat::AngryXml => parse_angry_xml(contents),
+        ImportFormat::AdvancedXml => parse_advanced_xml(contents),
+    };
+
+    if results.is_empty() {
+        return Err(GError::Internal(format!(
+            "No IPv4 hosts found in {:?} export",
+            format
+        )));
+    }
+
+    results.sort_by_key(|r| r.ip);
+    results.dedup_by_key(|r| r.ip);
+    let mut session = Session::new(results[0].ip, results[results.len() - 1].ip);
+    session.results = results;
+    Ok(session)
+}
+
+/// Splits a delimited line, honouring double-quoted fields, and trims each field.
+pub fn split_csv_line(line: &str, delim: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            c if c == delim && !in_quotes => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields.iter().map(|f| f.trim().to_string()).collect()
+}
+
+/// Parses either scanner's CSV export by locating the header row and mapping
+/// columns by name, so reordered or extra fetcher columns are tolerated.
+fn parse_csv(contents: &str) -> Vec<ScanResult> {
+    let mut lines = contents.lines();
+    let mut header: Option<(Vec<String>, char)> = None;
+
+    // Angry IP Scanner prefixes the header with generator/range comment lines.
+    for line in lines.by_ref() {
+        let delim = if line.contains(';') {
+            ';'
+        } else if line.contains('\t') {
+            '\t'
+        } else {
+            ','
+        };
+        let cols: Vec<String> = split_csv_line(line, delim)
+            .into_iter()
+            .map(|c| c.to_lowercase())
+            .collect();
+        if cols.iter().any(|c| c == "ip" || c == "ip address") {
+            header = Some((cols, delim));
+            break;
+        }
+    }
+
+    let Some((cols, delim)) = header else {
+        return Vec::new();
+    };
+    let find = |names: &[&str]| cols.iter().position(|c| names.contains(&c.as_str()));
+    let ip_col = find(&["ip", "ip address"]);
+    let host_col = find(&["hostname", "name"]);
+    let mac_col = find(&["mac address", "mac"]);
+    let vendor_col = find(&["mac vendor", "manufacturer", "vendor"]);
+    let ports_col = find(&["ports", "open ports"]);
+    let status_col = find(&["ping", "status"]);
+
+    let mut results = Vec::new();
+    for line in lines {
+        let fields = split_csv_line(line, delim);
+        let get = |col: Option<usize>| {
+            col.and_then(|i| fields.get(i))
+                .map(|s| s.as_str())
+                .filter(|s| !is_placeholder(s))
+        };
+
+        let Some(ip) = get(ip_col).and_then(|s| Ipv4Addr::from_str(s).ok()) else {
+            continue;
+        };
+        let mut res = ScanResult::new(ip);
+        res.hostname = get(host_col).map(str::to_string);
+        res.mac = get(mac_col).map(normalize_mac);
+        res.vendor = get(vendor_col).map(str::to_string);
+        res.open_ports = get(ports_col).map(parse_ports).unwrap_or_default();
+        res.status = parse_status(get(status_col), &res);
+        results.push(res);
+    }
+    results
+}
+
+/// Parses Angry IP Scanner XML: `<host address="ip"><result name="Ping">..</result>..</host>`.
+fn parse_angry_xml(contents: &str) -> Vec<ScanResult> {
+    let mut results = Vec::new();
+    for host in contents.split("<host ").skip(1) {
+        let body = host.split("</host>").next().unwrap_or_default();
+        let Some(ip) = attr(body, "address").and_then(|s| Ipv4Addr::from_str(&s).ok()) else {
+            continue;
+        };
+
+        let mut res = ScanResult::new(ip);
+        let mut ping = None;
+        for item in body.split("<result ").skip(1) {
+            let name = attr(item, "name").unwrap_or_default().to_lowercase();
+            let value = item
+                .split_once('>')
+                .and_then(|(_, rest)| rest.split("</result>").next())
+                .map(xml_unescape)
+                .filter(|v| !is_placeholder(v));
+            match name.as_str() {
+                "hostname" => res.hostname = value,
+                "mac address" => res.mac = value.as_deref().map(normalize_mac),
+                "mac vendor" => res.vendor = value,
+                "ports" => res.open_ports = value.as_deref().map(parse_ports).unwrap_or_default(),
+                "ping" => ping = value,
+                _ => {}
+            }
+        }
+        res.status = parse_status(ping.as_deref(), &res);
+        results.push(res);
+    }
+    results
+}
+
+/// Parses Advanced IP Scanner XML: `<row><status>on</status><ip>..</ip>..</row>`.
+fn parse_advanced_xml(contents: &str) -> Vec<ScanResult> {
+    let mut results = Vec::new();
+    for row in contents.split("<row").skip(1) {
+        let body = row.split("</row>").next().unwrap_or_default();
+        let Some(ip) = element(body, "ip").and_then(|s| Ipv4Addr::from_str(&s).ok()) else {
+            continue;
+        };
+
+        let mut res = ScanResult::new(ip);
+        res.hostname = element(body, "name");
+        res.mac = element(body, "mac").as_deref().map(normalize_mac);
+        res.vendor = element(body, "manufacturer");
+        res.status = parse_status(element(body, "status").as_deref(), &res);
+        results.push(res);
+    }
+    results
+}
+
+/// Returns the unescaped text of the first `<tag>..</tag>` in `body`.
+fn element(body: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = body.find(&open)? + open.len();
+    let len = body[start..].find(&close)?;
+    Some(xml_unescape(&body[start..start + len])).filter(|v| !is_placeholder(v))
+}
+
+/// Returns the unescaped value of `name="..."` in an opening tag.
+fn attr(tag: &str, name: &str) -> Option<String> {
+    let key = format!("{}=\"", name);
+    let start = tag.find(&key)? + key.len();
+    let len = tag[start..].find('"')?;
+    Some(xml_unescape(&tag[start..start + len]))
+}
+
+fn xml_unescape(s: &str) -> String {
+    s.trim()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Angry IP Scanner writes `[n/a]`, `[n/s]` or `[n/r]` for empty fetcher values.
+fn is_placeholder(s: &str) -> bool {
+    s.is_empty() || (s.starts_with('[') && s.ends_with(']'))
+}
+
+/// Converts `aa-bb-cc-dd-ee-ff` or `aa:bb:..` into our uppercase colon format.
+fn normalize_mac(s: &str) -> String {
+    s.trim().replace('-', ":").to_uppercase()
+}
+
+/// Parses port lists such as `22,80,443` or `80, 8080-8081` (ranges expanded).
+fn parse_ports(s: &str) -> Vec<PortInfo> {
+    let mut ports = Vec::new();
+    for part in s.split([',', ' ']).filter(|p| !p.is_empty()) {
+        match part.split_once('-') {
+            Some((a, b)) => {
+                if let (Ok(a), Ok(b)) = (a.parse::<u16>(), b.parse::<u16>()) {
+                    ports.extend(a..=b);
+                }
+            }
+            None => {
+                if let Ok(p) = part.parse::<u16>() {
+                    ports.push(p);
+                }
+            }
+        }
+    }
+    ports.sort_unstable();
+    ports.dedup();
+    ports.into_iter().map(PortInfo::new).collect()
+}
+
+/// Maps a foreign status/ping value to [`ScanStatus`], falling back to
+/// "online if we know anything about the host".
+fn parse_status(value: Option<&str>, res: &ScanResult) -> ScanStatus {
+    let online = match value.map(|v| v.to_lowercase()) {
+        Some(v) if v == "on" || v == "alive" || v == "online" || v.ends_with("ms") => true,
+        Some(v) if v == "off" || v == "dead" || v == "offline" => false,
+        _ => res.mac.is_some() || !res.open_ports.is_empty(),
+    };
+    if online {
+        ScanStatus::Online
+    } else {
+        ScanStatus::Offline
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANGRY_CSV: &str = "Generated by Angry IP Scanner 3.9.1\n\
+        \n\
+        IP,Ping,Hostname,Ports,MAC Address,MAC Vendor\n\
+        192.168.1.1,2 ms,router.lan,\"80,443\",AA-BB-CC-DD-EE-01,Acme\n\
+        192.168.1.5,[n/a],[n/s],[n/s],[n/s],[n/s]\n";
+
+    const ADVANCED_CSV: &str = "Status;Name;IP;Manufacturer;MAC address;Comments\n\
+        On;nas.local;192.168.1.20;Synology;00:11:32:AA:BB:CC;\n\
+        Off;;192.168.1.21;;;\n";
+
+    const ANGRY_XML: &str = r#"<?xml version="1.0"?>
+<scanning_report>
+  <hosts>
+    <host address="10.0.0.7">
+      <result name="Ping">1 ms</result>
+      <result name="Hostname">printer &amp; scanner</result>
+      <result name="Ports">9100</result>
+    </host>
+  </hosts>
+</scanning_report>"#;
+
+    const ADVANCED_XML: &str = r#"<?xml version="1.0"?>
+<document>
+  <row><status>on</status><name>pc-01</name><ip>10.0.0.3</ip><manufacturer>Dell</manufacturer><mac>F8-BC-12-00-00-01</mac></row>
+  <row><status>off</status><name></name><ip>10.0.0.4</ip></row>
+</document>"#;
+
+    #[test]
+    fn test_detect_formats() {
+        assert_eq!(
+            ImportFormat::detect(ANGRY_CSV),
+            Some(ImportFormat::AngryCsv)
+        );
+        assert_eq!(
+            ImportFormat::detect(ADVANCED_CSV),
+            Some(ImportFormat::AdvancedCsv)
+        );
+        assert_eq!(
+            ImportFormat::detect(ANGRY_XML),
+            Some(ImportFormat::AngryXml)
+        );
+        assert_eq!(
+            ImportFormat::detect(ADVANCED_XML),
+            Some(ImportFormat::AdvancedXml)
+        );
+        assert_eq!(ImportFormat::detect("hello world"), None);
+    }
+
+    #[test]
+    fn test_import_angry_csv() {
+        let session = import_str(ANGRY_CSV, ImportFormat::AngryCsv).unwrap();
+        assert_eq!(session.start, Ipv4Addr::new(192, 168, 1, 1));
+        assert_eq!(session.end, Ipv4Addr::new(192, 168, 1, 5));
+
+        let router = &session.results[0];
+        assert_eq!(router.status, ScanStatus::Online);
+        assert_eq!(router.hostname.as_deref(), Some("router.lan"));
+        assert_eq!(router.mac.as_deref(), Some("AA:BB:CC:DD:EE:01"));
+        assert_eq!(
+            router.open_ports,
+            vec![PortInfo::new(80), PortInfo::new(443)]
+        );
+
+        let dead = &session.results[1];
+        assert_eq!(dead.status, ScanStatus::Offline);
+        assert!(dead.hostname.is_none());
+    }
+
+    #[test]
+    fn test_import_advanced_csv() {
+        let session = import_str(ADVANCED_CSV, ImportFormat::AdvancedCsv).unwrap();
+        assert_eq!(session.results.len(), 2);
+        assert_eq!(session.results[0].vendor.as_deref(), Some("Synology"));
+        assert_eq!(session.results[0].status, ScanStatus::Online);
+        assert_eq!(session.results[1].status, ScanStatus::Offline);
+    }
+
+    #[test]
+    fn test_import_xml() {
+        let angry = import_str(ANGRY_XML, ImportFormat::AngryXml).unwrap();
+        assert_eq!(
+            angry.results[0].hostname.as_deref(),
+            Some("printer & scanner")
+        );
+        assert_eq!(angry.results[0].open_ports, vec![PortInfo::new(9100)]);
+
+        let advanced = import_str(ADVANCED_XML, ImportFormat::AdvancedXml).unwrap();
+        assert_eq!(advanced.results.len(), 2);
+        assert_eq!(
+            advanced.results[0].mac.as_deref(),
+            Some("F8:BC:12:00:00:01")
+        );
+        assert_eq!(advanced.results[1].status, ScanStatus::Offline);
+    }
+
+    #[test]
+    fn test_import_empty_is_error() {
+        assert!(import_str("IP,Ping\n", ImportFormat::AngryCsv).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+//! Asset inventory annotations joined into scan results.
+//!
+//! An inventory is a CSV exported from an asset database, keyed by MAC
+//! address or IP: one header row naming the columns, then one device per row.
+//! Column names are matched case-insensitively and extra columns are ignored:
+//!
+//! ```text
+//! mac,ip,asset tag,owner,location
+//! 00:11:22:33:44:55,,LT-0042,J. Doe,HQ 3F
+//! ,10.0.0.20,SRV-7,IT,Server room
+//! ```
+//!
+//! The scanner copies the matching row into [`ScanResult::asset`]; a MAC match
+//! wins over an IP match, since DHCP moves addresses around.
+
+use crate::import::split_csv_line;
+use crate::types::{AssetInfo, GError, ScanResult};
+use crate::wol::parse_mac;
+use std::collections::HashMap;
+use std::fs;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// Environment variable naming an inventory CSV the GUI and TUI load at startup.
+pub const INVENTORY_VAR: &str = "RAGESCANNER_INVENTORY";
+
+static INVENTORY: RwLock<Option<Arc<Inventory>>> = RwLock::new(None);
+
+/// Devices of an asset inventory, by MAC and by IP.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Inventory {
+    by_mac: HashMap<String, AssetInfo>,
+    by_ip: HashMap<Ipv4Addr, AssetInfo>,
+}
+
+impl Inventory {
+    /// Reads an inventory CSV.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if the file cannot be read or lacks a
+    /// header with a `mac` or `ip` column.
+    pub fn load(path: &Path) -> Result<Self, GError> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| GError::Internal(format!("Failed to read {}: {}", path.display(), e)))?;
+        Self::parse(&text).map_err(|e| GError::Internal(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Parses inventory CSV text. Comma, semicolon and tab delimiters are
+    /// accepted; rows with neither a valid MAC nor IP are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message if there is no header row with a `mac` or `ip` column.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'));
+        let header = lines.next().ok_or("The inventory is empty")?;
+        let delim = [';', '\t']
+            .into_iter()
+            .find(|d| header.contains(*d))
+            .unwrap_or(',');
+        let cols: Vec<String> = split_csv_line(header, delim)
+            .into_iter()
+            .map(|c| c.to_lowercase())
+            .collect();
+        let find = |names: &[&str]| cols.iter().position(|c| names.contains(&c.as_str()));
+        let mac_col = find(&["mac", "mac address"]);
+        let ip_col = find(&["ip", "ip address"]);
+        if mac_col.is_none() && ip_col.is_none() {
+            return Err("The header has no 'mac' or 'ip' column".to_string());
+        }
+        let tag_col = find(&["asset tag", "asset_tag", "asset", "tag"]);
+        let owner_col = find(&["owner", "assigned to"]);
+        let location_col = find(&["location", "site"]);
+
+        let mut inventory = Self::default();
+        for line in lines {
+            let fields = split_csv_line(line, delim);
+            let get = |col: Option<usize>| {
+                col.and_then(|i| fields.get(i))
+                    .filter(|s| !s.is_empty())
+                    .cloned()
+            };
+            let asset = AssetInfo {
+                asset_tag: get(tag_col),
+                owner: get(owner_col),
+                location: get(location_col),
+            };
+            let mac = get(mac_col).and_then(|mac| normalize_mac(&mac));
+            let ip = get(ip_col).and_then(|ip| ip.parse::<Ipv4Addr>().ok());
+            if let Some(ip) = ip {
+                inventory.by_ip.insert(ip, asset.clone());
+            }
+            if let Some(mac) = mac {
+                inventory.by_mac.insert(mac, asset);
+            }
+        }
+        Ok(inventory)
+    }
+
+    /// Devices listed, counting one listed by both MAC and IP twice.
+    pub fn len(&self) -> usize {
+        self.by_mac.len() + self.by_ip.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The row for `mac`, else the row for `ip`.
+    pub fn lookup(&self, ip: Ipv4Addr, mac: Option<&str>) -> Option<&AssetInfo> {
+        mac.and_then(normalize_mac)
+            .and_then(|mac| self.by_mac.get(&mac))
+            .or_else(|| self.by_ip.get(&ip))
+    }
+}
+
+/// `AA:BB:CC:DD:EE:FF` for any separator style [`parse_mac`] accepts.
+pub fn normalize_mac(mac: &str) -> Option<String> {
+    let bytes = parse_mac(mac).ok()?;
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+    Some(hex.join(":"))
+}
+
+/// Loads the inventory CSV at `path` for later scans; `None` drops it.
+///
+/// Returns the number of devices loaded. On error the previous inventory
+/// stays in use.
+///
+/// # Errors
+///
+/// See [`Inventory::load`].
+pub fn set_inventory(path: Option<PathBuf>) -> Result<usize, GError> {
+    let inventory = match path {
+        Some(path) => Some(Arc::new(Inventory::load(&path)?)),
+        None => None,
+    };
+    let count = inventory.as_ref().map_or(0, |i| i.len());
+    if let Ok(mut current) = INVENTORY.write() {
+        *current = inventory;
+    }
+    log::info!("Loaded asset inventory with {} devices", count);
+    Ok(count)
+}
+
+/// The current inventory's row for a host, if one is loaded and lists it.
+pub fn lookup(ip: Ipv4Addr, mac: Option<&str>) -> Option<AssetInfo> {
+    let current = INVENTORY.read().ok()?;
+    current.as_ref()?.lookup(ip, mac).cloned()
+}
+
+/// Refreshes [`ScanResult::asset`] from the current inventory, for results
+/// found before it was loaded or changed.
+pub fn annotate(results: &mut [ScanResult]) {
+    for res in results {
+        res.asset = lookup(res.ip, res.mac.as_deref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_joins_by_mac_before_ip() {
+        let inventory = Inventory::parse(
+            "# exported from the CMDB\n\
+             MAC Address;IP;Asset Tag;Owner;Location;Notes\n\
+             00-11-22-33-44-55;;LT-0042;\"Doe; J.\";HQ 3F;spare\n\
+             ;10.0.0.20;SRV-7;IT;Server room;\n\
+             not-a-mac;;X;;;\n",
+        )
+        .unwrap();
+        assert_eq!(inventory.len(), 2);
+
+        let laptop = inventory
+            .lookup(Ipv4Addr::new(10, 0, 0, 20), Some("00:11:22:33:44:55"))
+            .unwrap();
+        assert_eq!(laptop.asset_tag.as_deref(), Some("LT-0042"));
+        assert_eq!(laptop.owner.as_deref(), Some("Doe; J."));
+
+        let server = inventory
+            .lookup(Ipv4Addr::new(10, 0, 0, 20), Some("66:77:88:99:AA:BB"))
+            .unwrap();
+        assert_eq!(server.location.as_deref(), Some("Server room"));
+        assert_eq!(inventory.lookup(Ipv4Addr::new(10, 0, 0, 21), None), None);
+    }
+
+    #[test]
+    fn test_parse_requires_a_key_column() {
+        assert!(Inventory::parse("owner,location\nIT,HQ\n").is_err());
+        assert!(Inventory::parse("").is_err());
+    }
+}
@@ -1,6 +1,7 @@
 //! # RageScanner
 //!
-//! A high-performance, asynchronous IP and port scanner library for Windows.
+//! A high-performance, asynchronous IP and port scanner library for Windows,
+//! Linux and macOS.
 //!
 //! Provides ICMP ping, ARP-based MAC resolution, OUI vendor lookup,
 //! reverse DNS, and TCP port scanning — all orchestrated via the
@@ -32,8 +33,48 @@
 //! }
 //! ```
 
+pub mod about;
+pub mod agent;
+#[cfg(feature = "api")]
+pub mod api;
 pub mod bridge;
+pub mod cache;
+pub mod classify;
+pub mod conflict;
+pub mod deep;
+pub mod definition;
+pub mod devices;
+pub mod dhcp;
+pub mod dns;
+pub mod elevation;
+pub mod export;
+pub mod fingerprint;
+pub mod health;
+#[cfg(feature = "history")]
+pub mod history;
+pub mod import;
+pub mod inventory;
+pub mod monitor;
+pub mod names;
 pub mod net;
+pub mod nmap;
+pub mod notify;
+pub mod oui;
+pub mod passive;
+pub mod probe;
+pub mod reach;
+pub mod report;
+pub mod rpc;
+pub mod scan_session;
 pub mod scanner;
+pub mod session;
+pub mod settings;
+pub mod smb;
+pub mod snmp;
+pub mod targets;
+pub mod tls;
 pub mod tui;
 pub mod types;
+pub mod upnp;
+pub mod web;
+pub mod wol;
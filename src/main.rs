@@ -1,14 +1,33 @@
 #![cfg_attr(not(test), windows_subsystem = "windows")]
 
+#[cfg(windows)]
 mod ui;
 
+#[cfg(windows)]
 use log::LevelFilter;
+#[cfg(windows)]
 use ragescanner::bridge::Bridge;
+#[cfg(windows)]
+use ragescanner::elevation::PendingScan;
+#[cfg(windows)]
 use simplelog::{Config, WriteLogger};
+#[cfg(windows)]
 use std::fs::File;
+#[cfg(windows)]
 use std::panic;
+#[cfg(windows)]
 use windows::Win32::UI::WindowsAndMessaging::{MB_ICONERROR, MB_OK, MessageBoxA};
 
+/// The GUI is built on Win32; other platforms get the terminal frontends.
+#[cfg(not(windows))]
+fn main() {
+    eprintln!(
+        "The RageScanner GUI needs Windows; use `rageping` (terminal UI) or `ragescanner-cli`."
+    );
+    std::process::exit(1);
+}
+
+#[cfg(windows)]
 fn main() {
     // 1. Initialize Logging
     let log_level = if cfg!(debug_assertions) {
@@ -20,8 +39,7 @@ fn main() {
     let _ = WriteLogger::init(
         log_level,
         Config::default(),
-        File::create("ragescanner.log")
-            .unwrap_or_else(|_| File::create("ragescanner.err").unwrap()),
+        File::create(ui::LOG_PATH).unwrap_or_else(|_| File::create("ragescanner.err").unwrap()),
     );
 
     log::info!("Application Started");
@@ -56,6 +74,20 @@ fn main() {
         }
     }));
 
+    // 3. Optional REST API, on its own bridge so API scans never touch the window
+    #[cfg(feature = "api")]
+    if let Ok(addr) = std::env::var("RAGESCANNER_API") {
+        let server = addr
+            .parse()
+            .map_err(|e| ragescanner::types::GError::Internal(format!("{}: {}", addr, e)))
+            .and_then(|addr| ragescanner::api::ApiServer::bind(addr, Bridge::new()));
+        match server {
+            Ok(server) => server.spawn(),
+            Err(e) => log::error!("REST API not started: {}", e),
+        }
+    }
+
+    let pending = PendingScan::from_args(std::env::args().skip(1));
     let bridge = Bridge::new();
-    ui::run_app(bridge.cmd_tx, bridge.ui_rx);
+    ui::run_app(bridge.cmd_tx.clone(), bridge.ui_rx.clone(), pending);
 }
@@ -0,0 +1,694 @@
+//! Continuous monitoring: periodic re-scans of a range with change detection.
+//!
+//! [`Monitor`] drives a [`Scanner`] in a loop. Each sweep's updates are
+//! forwarded as usual; once a sweep finishes, its online hosts are compared
+//! with the previous sweep and the differences are reported as
+//! [`BridgeMessage::DeviceAppeared`], [`BridgeMessage::DeviceDisappeared`] and
+//! [`BridgeMessage::DeviceChanged`]. The first sweep only sets the baseline.
+//!
+//! Open ports get their own alert, [`BridgeMessage::PortsChanged`]: a new
+//! listener is often the first sign of a misconfiguration or compromise, so
+//! [`PortTracker`] compares each host against its last sighting, even if it
+//! was offline for some sweeps in between.
+//!
+//! Latency gets one too, [`BridgeMessage::LatencyAnomaly`]: a host suddenly
+//! answering several times slower than usual often has a duplex mismatch or
+//! failing hardware, so [`LatencyTracker`] keeps a rolling baseline per host.
+//!
+//! Identity changes raise [`BridgeMessage::SecurityAlert`]: an address
+//! answering with a new MAC is the signature of ARP spoofing (or a swapped
+//! device), so [`IdentityTracker`] remembers which MAC and name each address
+//! had and where each MAC was last seen.
+
+use crate::inventory::normalize_mac;
+use crate::scanner::Scanner;
+use crate::types::{BridgeMessage, IdentityChange, ScanResult, ScanStatus};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::net::Ipv4Addr;
+use std::time::Duration;
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio_util::sync::CancellationToken;
+
+/// Shortest interval the bridge accepts between the starts of two sweeps.
+pub const MIN_MONITOR_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Sweeps of echo latency kept per host for its baseline.
+pub const LATENCY_WINDOW: usize = 10;
+
+/// Normal sweeps a host needs before its latency can be flagged.
+pub const LATENCY_MIN_SAMPLES: usize = 3;
+
+/// How many times its baseline a host's latency must reach to be flagged.
+pub const LATENCY_ANOMALY_FACTOR: f32 = 3.0;
+
+/// Smallest rise over the baseline that is flagged, so sub-millisecond
+/// jitter on a quiet LAN does not raise alerts.
+pub const LATENCY_ANOMALY_MIN_MS: f32 = 5.0;
+
+/// Online hosts of one sweep, keyed by address.
+type Sweep = BTreeMap<Ipv4Addr, ScanResult>;
+
+/// Returns `true` if two sightings of the same address look like a different
+/// device or a reconfigured one. Latency is ignored as it varies per sweep.
+fn device_changed(previous: &ScanResult, current: &ScanResult) -> bool {
+    let ports = |r: &ScanResult| r.open_ports.iter().map(|p| p.port).collect::<Vec<_>>();
+    previous.mac != current.mac
+        || previous.hostname != current.hostname
+        || ports(previous) != ports(current)
+}
+
+/// Compares two sweeps and returns the resulting device events, ordered by address.
+pub fn diff_sweeps(previous: &Sweep, current: &Sweep) -> Vec<BridgeMessage> {
+    let mut events = Vec::new();
+    for (ip, res) in current {
+        match previous.get(ip) {
+            None => events.push(BridgeMessage::DeviceAppeared(res.clone())),
+            Some(old) if device_changed(old, res) => {
+                events.push(BridgeMessage::DeviceChanged {
+                    previous: Box::new(old.clone()),
+                    current: Box::new(res.clone()),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    for (ip, old) in previous {
+        if !current.contains_key(ip) {
+            events.push(BridgeMessage::DeviceDisappeared(old.clone()));
+        }
+    }
+    events.sort_by_key(|e| match e {
+        BridgeMessage::DeviceAppeared(r) | BridgeMessage::DeviceDisappeared(r) => r.ip,
+        BridgeMessage::DeviceChanged { current, .. } => current.ip,
+        _ => Ipv4Addr::UNSPECIFIED,
+    });
+    events
+}
+
+/// Open TCP ports last seen on each host.
+///
+/// Hosts that go offline keep their entry, so a host coming back with a new
+/// listener still raises an alert instead of passing as a fresh baseline.
+#[derive(Debug, Default)]
+pub struct PortTracker {
+    known: BTreeMap<Ipv4Addr, BTreeSet<u16>>,
+}
+
+impl PortTracker {
+    /// Records the online hosts of a sweep and returns a
+    /// [`BridgeMessage::PortsChanged`] for each host whose open ports differ
+    /// from its last sighting, ordered by address. A host's first sighting
+    /// only sets its baseline.
+    pub fn update(&mut self, sweep: &Sweep) -> Vec<BridgeMessage> {
+        let mut alerts = Vec::new();
+        for (ip, res) in sweep {
+            let ports: BTreeSet<u16> = res.open_ports.iter().map(|p| p.port).collect();
+            if let Some(known) = self.known.get(ip) {
+                let opened: Vec<u16> = ports.difference(known).copied().collect();
+                let closed: Vec<u16> = known.difference(&ports).copied().collect();
+                if !opened.is_empty() || !closed.is_empty() {
+                    alerts.push(BridgeMessage::PortsChanged {
+                        host: Box::new(res.clone()),
+                        opened,
+                        closed,
+                    });
+                }
+            }
+            self.known.insert(*ip, ports);
+        }
+        alerts
+    }
+}
+
+/// MAC and hostname last seen at each address, and address last seen for each MAC.
+///
+/// Like [`PortTracker`], entries outlive offline sweeps, so a device swapped
+/// while the old one was unplugged is still caught.
+#[derive(Debug, Default)]
+pub struct IdentityTracker {
+    macs: BTreeMap<Ipv4Addr, String>,
+    hostnames: BTreeMap<Ipv4Addr, String>,
+    addresses: BTreeMap<String, Ipv4Addr>,
+}
+
+impl IdentityTracker {
+    /// Records the online hosts of a sweep and returns a
+    /// [`BridgeMessage::SecurityAlert`] for each identity change, ordered by
+    /// address:
+    ///
+    /// - [`IdentityChange::MacChanged`] when an address answers with another MAC;
+    /// - [`IdentityChange::MacMoved`] when a MAC new to an address was last
+    ///   seen at one that no longer shows it;
+    /// - [`IdentityChange::HostnameChanged`] when a name differs from the last
+    ///   one resolved. Failing to resolve a name is not a change.
+    ///
+    /// MACs are compared after normalisation. First sightings only set the baseline.
+    pub fn update(&mut self, sweep: &Sweep) -> Vec<BridgeMessage> {
+        let current: BTreeSet<(String, Ipv4Addr)> = sweep
+            .values()
+            .filter_map(|r| Some((r.mac.as_deref().and_then(normalize_mac)?, r.ip)))
+            .collect();
+        let mut alerts = Vec::new();
+        let mut alert = |res: &ScanResult, change| {
+            alerts.push(BridgeMessage::SecurityAlert {
+                host: Box::new(res.clone()),
+                change,
+            })
+        };
+        for (ip, res) in sweep {
+            if let Some(mac) = res.mac.as_deref().and_then(normalize_mac) {
+                match self.macs.get(ip) {
+                    Some(previous) if *previous != mac => alert(
+                        res,
+                        IdentityChange::MacChanged {
+                            previous: previous.clone(),
+                        },
+                    ),
+                    Some(_) => {}
+                    None => {
+                        if let Some(&previous_ip) = self.addresses.get(&mac)
+                            && previous_ip != *ip
+                            && !current.contains(&(mac.clone(), previous_ip))
+                        {
+                            alert(res, IdentityChange::MacMoved { previous_ip });
+                            self.macs.remove(&previous_ip);
+                        }
+                    }
+                }
+                self.macs.insert(*ip, mac.clone());
+                self.addresses.insert(mac, *ip);
+            }
+            if let Some(name) = &res.hostname {
+                if let Some(previous) = self.hostnames.get(ip)
+                    && !previous.eq_ignore_ascii_case(name)
+                {
+                    alert(
+                        res,
+                        IdentityChange::HostnameChanged {
+                            previous: previous.clone(),
+                        },
+                    );
+                }
+                self.hostnames.insert(*ip, name.clone());
+            }
+        }
+        alerts
+    }
+}
+
+/// Rolling echo latency baseline of each host.
+///
+/// The baseline is the median average RTT over a host's last
+/// [`LATENCY_WINDOW`] normal sweeps. Anomalous sweeps are left out of it, so
+/// a link that stays slow keeps its alert instead of becoming the new normal.
+#[derive(Debug, Default)]
+pub struct LatencyTracker {
+    samples: BTreeMap<Ipv4Addr, VecDeque<f32>>,
+    flagged: BTreeSet<Ipv4Addr>,
+}
+
+impl LatencyTracker {
+    /// The host's baseline in milliseconds, once it has
+    /// [`LATENCY_MIN_SAMPLES`] normal sweeps.
+    pub fn baseline(&self, ip: Ipv4Addr) -> Option<f32> {
+        let samples = self.samples.get(&ip)?;
+        if samples.len() < LATENCY_MIN_SAMPLES {
+            return None;
+        }
+        let mut sorted: Vec<f32> = samples.iter().copied().collect();
+        sorted.sort_by(f32::total_cmp);
+        Some(sorted[sorted.len() / 2])
+    }
+
+    /// Records the latency of a sweep's online hosts and returns a
+    /// [`BridgeMessage::LatencyAnomaly`] for each host that has just gone
+    /// over [`LATENCY_ANOMALY_FACTOR`] times its baseline (and at least
+    /// [`LATENCY_ANOMALY_MIN_MS`] above it), ordered by address. A flagged
+    /// host is not reported again until a normal sweep clears it.
+    pub fn update(&mut self, sweep: &Sweep) -> Vec<BridgeMessage> {
+        let mut alerts = Vec::new();
+        for (ip, res) in sweep {
+            let Some(ms) = res.latency_ms() else {
+                continue;
+            };
+            if let Some(baseline) = self.baseline(*ip)
+                && ms >= baseline * LATENCY_ANOMALY_FACTOR
+                && ms - baseline >= LATENCY_ANOMALY_MIN_MS
+            {
+                if self.flagged.insert(*ip) {
+                    alerts.push(BridgeMessage::LatencyAnomaly {
+                        host: Box::new(res.clone()),
+                        baseline_ms: baseline,
+                    });
+                }
+                continue;
+            }
+            self.flagged.remove(ip);
+            let samples = self.samples.entry(*ip).or_default();
+            if samples.len() == LATENCY_WINDOW {
+                samples.pop_front();
+            }
+            samples.push_back(ms);
+        }
+        alerts
+    }
+}
+
+/// Renders a device event as a one-line log entry, e.g. `+ 10.0.0.4 (printer)`.
+///
+/// Returns `None` for messages that are not device events.
+pub fn describe_event(msg: &BridgeMessage) -> Option<String> {
+    let name = |r: &ScanResult| match (&r.hostname, &r.mac) {
+        (Some(host), _) => format!("{} ({})", r.ip, host),
+        (None, Some(mac)) => format!("{} ({})", r.ip, mac),
+        (None, None) => r.ip.to_string(),
+    };
+    match msg {
+        BridgeMessage::DeviceAppeared(r) => Some(format!("+ {}", name(r))),
+        BridgeMessage::DeviceDisappeared(r) => Some(format!("- {}", name(r))),
+        BridgeMessage::DeviceChanged { previous, current } => {
+            let mut what = Vec::new();
+            if previous.mac != current.mac {
+                what.push("MAC");
+            }
+            if previous.hostname != current.hostname {
+                what.push("hostname");
+            }
+            if device_changed(previous, current) && what.is_empty() {
+                what.push("ports");
+            }
+            Some(format!("~ {}: {} changed", name(current), what.join(", ")))
+        }
+        BridgeMessage::PortsChanged {
+            host,
+            opened,
+            closed,
+        } => {
+            let list = |ports: &[u16]| {
+                ports
+                    .iter()
+                    .map(u16::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            let mut what = Vec::new();
+            if !opened.is_empty() {
+                what.push(format!("opened {}", list(opened)));
+            }
+            if !closed.is_empty() {
+                what.push(format!("closed {}", list(closed)));
+            }
+            Some(format!("! {}: ports {}", name(host), what.join("; ")))
+        }
+        BridgeMessage::IpConflict {
+            ip,
+            previous,
+            current,
+            macs,
+        } => Some(format!(
+            "! {}: answered by {}, was {} ({} MACs seen)",
+            ip,
+            current,
+            previous,
+            macs.len()
+        )),
+        BridgeMessage::SecurityAlert { host, change } => {
+            Some(format!("! {}: {}", name(host), change))
+        }
+        BridgeMessage::LatencyAnomaly { host, baseline_ms } => Some(format!(
+            "! {}: latency {:.1} ms, baseline {:.1} ms",
+            name(host),
+            host.latency_ms().unwrap_or_default(),
+            baseline_ms
+        )),
+        _ => None,
+    }
+}
+
+/// Re-scans a range on a fixed interval and reports device changes.
+pub struct Monitor {
+    scanner: Scanner,
+    scanner_rx: Receiver<BridgeMessage>,
+    tx: Sender<BridgeMessage>,
+}
+
+impl Monitor {
+    /// Creates a monitor around a configured scanner.
+    ///
+    /// `scanner_rx` must be the receiving end of the scanner's channel; the
+    /// monitor consumes it and forwards to `tx`.
+    pub fn new(
+        scanner: Scanner,
+        scanner_rx: Receiver<BridgeMessage>,
+        tx: Sender<BridgeMessage>,
+    ) -> Self {
+        Self {
+            scanner,
+            scanner_rx,
+            tx,
+        }
+    }
+
+    /// Sweeps `start..=end` every `interval` until `cancel_token` fires, then
+    /// sends [`BridgeMessage::ScanCancelled`]. A sweep that overruns the
+    /// interval is followed immediately by the next one. Device events of a
+    /// sweep are sent before its security alerts, then its port and latency
+    /// alerts.
+    ///
+    /// Per-sweep `ScanComplete` messages are not forwarded, so frontends stay
+    /// in their scanning state for the lifetime of the monitor. A sweep that
+    /// ends in an error stops monitoring after forwarding the error.
+    pub async fn run(
+        mut self,
+        start: Ipv4Addr,
+        end: Ipv4Addr,
+        interval: Duration,
+        cancel_token: CancellationToken,
+    ) {
+        let mut previous: Option<Sweep> = None;
+        let mut identities = IdentityTracker::default();
+        let mut ports = PortTracker::default();
+        let mut latency = LatencyTracker::default();
+
+        loop {
+            let started = tokio::time::Instant::now();
+            let Some(current) = self.sweep(start, end, cancel_token.clone()).await else {
+                return;
+            };
+            if cancel_token.is_cancelled() {
+                break;
+            }
+
+            match &previous {
+                Some(previous) => {
+                    for event in diff_sweeps(previous, &current) {
+                        let _ = self.tx.send(event).await;
+                    }
+                }
+                None => log::info!("Monitor baseline: {} online hosts", current.len()),
+            }
+            let mut alerts = identities.update(&current);
+            alerts.extend(ports.update(&current));
+            alerts.extend(latency.update(&current));
+            for alert in alerts {
+                if let Some(text) = describe_event(&alert) {
+                    log::warn!("{}", text);
+                }
+                let _ = self.tx.send(alert).await;
+            }
+            previous = Some(current);
+
+            tokio::select! {
+                _ = cancel_token.cancelled() => break,
+                _ = tokio::time::sleep_until(started + interval) => {}
+            }
+        }
+
+        log::info!("Monitoring of {} - {} stopped.", start, end);
+        let _ = self.tx.send(BridgeMessage::ScanCancelled).await;
+    }
+
+    /// Runs one sweep, forwarding its updates and collecting online hosts.
+    ///
+    /// Returns `None` if the scanner reported an error.
+    async fn sweep(
+        &mut self,
+        start: Ipv4Addr,
+        end: Ipv4Addr,
+        cancel_token: CancellationToken,
+    ) -> Option<Sweep> {
+        let scanner = &self.scanner;
+        let scanner_rx = &mut self.scanner_rx;
+        let tx = &self.tx;
+
+        let collect = async move {
+            let mut online = Sweep::new();
+            while let Some(msg) = scanner_rx.recv().await {
+                match msg {
+                    BridgeMessage::ScanComplete | BridgeMessage::ScanCancelled => break,
+                    BridgeMessage::Error(e) => {
+                        let _ = tx.send(BridgeMessage::Error(e)).await;
+                        return None;
+                    }
+                    // The table keeps the previous sweep's rows for these.
+                    BridgeMessage::ScanUpdate(res) if res.status == ScanStatus::NotScanned => {}
+                    BridgeMessage::ScanUpdate(res) => {
+                        if res.status == ScanStatus::Online {
+                            online.insert(res.ip, res.clone());
+                        }
+                        let _ = tx.send(BridgeMessage::ScanUpdate(res)).await;
+                    }
+                    other => {
+                        let _ = tx.send(other).await;
+                    }
+                }
+            }
+            Some(online)
+        };
+
+        let (_, online) = tokio::join!(scanner.scan_range(start, end, cancel_token), collect);
+        online
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::MockNet;
+    use crate::types::PortInfo;
+    use std::sync::Arc;
+    use tokio::sync::mpsc::channel;
+
+    fn online(ip: [u8; 4], mac: &str) -> ScanResult {
+        let mut res = ScanResult::new(Ipv4Addr::from(ip));
+        res.status = ScanStatus::Online;
+        res.mac = Some(mac.to_string());
+        res
+    }
+
+    fn sweep(results: &[ScanResult]) -> Sweep {
+        results.iter().map(|r| (r.ip, r.clone())).collect()
+    }
+
+    #[test]
+    fn test_diff_sweeps() {
+        let kept = online([10, 0, 0, 1], "AA:AA:AA:AA:AA:AA");
+        let gone = online([10, 0, 0, 2], "BB:BB:BB:BB:BB:BB");
+        let mut reconfigured = online([10, 0, 0, 3], "CC:CC:CC:CC:CC:CC");
+        let previous = sweep(&[kept.clone(), gone, reconfigured.clone()]);
+
+        reconfigured.open_ports = vec![PortInfo::new(22)];
+        let mut jittery = kept.clone();
+        jittery.latency = crate::types::LatencyStats::from_samples(&[9], 1);
+        let new = online([10, 0, 0, 4], "DD:DD:DD:DD:DD:DD");
+        let current = sweep(&[jittery, reconfigured, new]);
+
+        let events = diff_sweeps(&previous, &current);
+        assert_eq!(events.len(), 3);
+        assert!(
+            matches!(&events[0], BridgeMessage::DeviceDisappeared(r) if r.ip == Ipv4Addr::new(10, 0, 0, 2))
+        );
+        assert!(
+            matches!(&events[1], BridgeMessage::DeviceChanged { current, .. } if current.open_ports.len() == 1)
+        );
+        assert!(
+            matches!(&events[2], BridgeMessage::DeviceAppeared(r) if r.ip == Ipv4Addr::new(10, 0, 0, 4))
+        );
+    }
+
+    #[test]
+    fn test_describe_event() {
+        let mut res = online([10, 0, 0, 4], "DD:DD:DD:DD:DD:DD");
+        assert_eq!(
+            describe_event(&BridgeMessage::DeviceAppeared(res.clone())).as_deref(),
+            Some("+ 10.0.0.4 (DD:DD:DD:DD:DD:DD)")
+        );
+        let previous = Box::new(res.clone());
+        res.hostname = Some("printer".to_string());
+        let changed = BridgeMessage::DeviceChanged {
+            previous,
+            current: Box::new(res),
+        };
+        assert_eq!(
+            describe_event(&changed).as_deref(),
+            Some("~ 10.0.0.4 (printer): hostname changed")
+        );
+        let conflict = BridgeMessage::IpConflict {
+            ip: Ipv4Addr::new(10, 0, 0, 4),
+            previous: "DD:DD:DD:DD:DD:DD".to_string(),
+            current: "EE:EE:EE:EE:EE:EE".to_string(),
+            macs: vec![
+                "DD:DD:DD:DD:DD:DD".to_string(),
+                "EE:EE:EE:EE:EE:EE".to_string(),
+            ],
+        };
+        assert_eq!(
+            describe_event(&conflict).as_deref(),
+            Some("! 10.0.0.4: answered by EE:EE:EE:EE:EE:EE, was DD:DD:DD:DD:DD:DD (2 MACs seen)")
+        );
+        assert_eq!(describe_event(&BridgeMessage::ScanComplete), None);
+    }
+
+    #[test]
+    fn test_port_tracker_compares_with_last_sighting() {
+        let mut host = online([10, 0, 0, 5], "EE:EE:EE:EE:EE:EE");
+        host.open_ports = vec![PortInfo::new(80), PortInfo::new(443)];
+        let mut tracker = PortTracker::default();
+        assert!(tracker.update(&sweep(&[host.clone()])).is_empty());
+        assert!(tracker.update(&sweep(&[host.clone()])).is_empty());
+
+        // Offline for a sweep, then back with telnet open and HTTPS gone.
+        assert!(tracker.update(&Sweep::new()).is_empty());
+        host.open_ports = vec![PortInfo::new(23), PortInfo::new(80)];
+        let alerts = tracker.update(&sweep(&[host]));
+        let [BridgeMessage::PortsChanged { opened, closed, .. }] = alerts.as_slice() else {
+            panic!("expected one port alert, got {:?}", alerts);
+        };
+        assert_eq!(
+            (opened.as_slice(), closed.as_slice()),
+            ([23].as_slice(), [443].as_slice())
+        );
+        assert_eq!(
+            describe_event(&alerts[0]).as_deref(),
+            Some("! 10.0.0.5 (EE:EE:EE:EE:EE:EE): ports opened 23; closed 443")
+        );
+    }
+
+    #[test]
+    fn test_identity_tracker_flags_mac_and_hostname_changes() {
+        let mut gateway = online([10, 0, 0, 1], "aa-aa-aa-aa-aa-01");
+        gateway.hostname = Some("router".to_string());
+        let laptop = online([10, 0, 0, 7], "AA:AA:AA:AA:AA:07");
+        let mut tracker = IdentityTracker::default();
+        assert!(
+            tracker
+                .update(&sweep(&[gateway.clone(), laptop.clone()]))
+                .is_empty()
+        );
+
+        // Same MAC in another notation, a failed lookup and a case change are not changes.
+        gateway.mac = Some("AA:AA:AA:AA:AA:01".to_string());
+        gateway.hostname = None;
+        assert!(tracker.update(&sweep(&[gateway.clone()])).is_empty());
+        gateway.hostname = Some("ROUTER".to_string());
+        assert!(tracker.update(&sweep(&[gateway.clone()])).is_empty());
+
+        // The laptop claims the gateway's address: a spoofed gateway.
+        let mut spoofed = gateway.clone();
+        spoofed.mac = laptop.mac.clone();
+        spoofed.hostname = Some("laptop".to_string());
+        let alerts = tracker.update(&sweep(&[spoofed]));
+        let changes: Vec<&IdentityChange> = alerts
+            .iter()
+            .map(|a| match a {
+                BridgeMessage::SecurityAlert { change, .. } => change,
+                other => panic!("expected a security alert, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                &IdentityChange::MacChanged {
+                    previous: "AA:AA:AA:AA:AA:01".to_string()
+                },
+                &IdentityChange::HostnameChanged {
+                    previous: "ROUTER".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            describe_event(&alerts[0]).as_deref(),
+            Some(
+                "! 10.0.0.1 (laptop): MAC changed from AA:AA:AA:AA:AA:01 (possible ARP spoofing or device swap)"
+            )
+        );
+
+        // The real gateway turns up at a new address.
+        let mut moved = gateway.clone();
+        moved.ip = Ipv4Addr::new(10, 0, 0, 9);
+        let alerts = tracker.update(&sweep(&[moved]));
+        assert!(matches!(
+            alerts.as_slice(),
+            [BridgeMessage::SecurityAlert {
+                change: IdentityChange::MacMoved { previous_ip },
+                ..
+            }] if *previous_ip == Ipv4Addr::new(10, 0, 0, 1)
+        ));
+
+        // A MAC answering at two addresses at once has not moved.
+        let mut alias = laptop.clone();
+        alias.ip = Ipv4Addr::new(10, 0, 0, 8);
+        assert!(
+            tracker
+                .update(&sweep(&[laptop.clone(), alias.clone()]))
+                .is_empty()
+        );
+        assert!(tracker.update(&sweep(&[laptop, alias])).is_empty());
+    }
+
+    #[test]
+    fn test_latency_tracker_flags_a_host_far_over_its_baseline() {
+        let mut host = online([10, 0, 0, 6], "FF:FF:FF:FF:FF:FF");
+        let mut tracker = LatencyTracker::default();
+        let mut sweep_at = |tracker: &mut LatencyTracker, ms: u32| {
+            host.latency = crate::types::LatencyStats::from_samples(&[ms], 1);
+            tracker.update(&sweep(&[host.clone()]))
+        };
+        // No alert before a baseline exists; a one-off spike does not move
+        // the median, and tripling it is ignored while under 5 ms.
+        assert!(sweep_at(&mut tracker, 2).is_empty());
+        assert!(sweep_at(&mut tracker, 60).is_empty());
+        assert!(sweep_at(&mut tracker, 2).is_empty());
+        assert!(sweep_at(&mut tracker, 2).is_empty());
+        assert_eq!(tracker.baseline(Ipv4Addr::new(10, 0, 0, 6)), Some(2.0));
+        assert!(sweep_at(&mut tracker, 6).is_empty());
+
+        let alerts = sweep_at(&mut tracker, 40);
+        let [BridgeMessage::LatencyAnomaly { baseline_ms, .. }] = alerts.as_slice() else {
+            panic!("expected one latency alert, got {:?}", alerts);
+        };
+        assert_eq!(*baseline_ms, 2.0);
+        assert_eq!(
+            describe_event(&alerts[0]).as_deref(),
+            Some("! 10.0.0.6 (FF:FF:FF:FF:FF:FF): latency 40.0 ms, baseline 2.0 ms")
+        );
+
+        // Still slow: no repeat, and the baseline is unmoved. Recovery re-arms it.
+        assert!(sweep_at(&mut tracker, 45).is_empty());
+        assert_eq!(tracker.baseline(Ipv4Addr::new(10, 0, 0, 6)), Some(2.0));
+        assert!(sweep_at(&mut tracker, 2).is_empty());
+        assert_eq!(sweep_at(&mut tracker, 50).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_monitor_repeats_and_stops() {
+        let (scanner_tx, scanner_rx) = channel(100);
+        let (tx, mut rx) = channel(100);
+        let scanner = Scanner::new(Arc::new(MockNet), scanner_tx);
+        let monitor = Monitor::new(scanner, scanner_rx, tx);
+
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        let token = CancellationToken::new();
+        let handle = tokio::spawn(monitor.run(ip, ip, Duration::from_millis(10), token.clone()));
+
+        let mut updates = 0;
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::ScanUpdate(_) => {
+                    updates += 1;
+                    if updates == 2 {
+                        token.cancel();
+                    }
+                }
+                BridgeMessage::ScanComplete => panic!("sweep completion must not be forwarded"),
+                BridgeMessage::DeviceAppeared(_) | BridgeMessage::DeviceDisappeared(_) => {
+                    panic!("an unchanged network must not produce device events")
+                }
+                BridgeMessage::ScanCancelled => break,
+                _ => {}
+            }
+        }
+        assert_eq!(updates, 2);
+        handle.await.unwrap();
+    }
+}
@@ -0,0 +1,574 @@
+//! Local name resolution and mDNS discovery for hosts without reverse DNS.
+//!
+//! Windows machines, printers and phones on a home or office LAN usually
+//! answer to NetBIOS (UDP 137 node status), LLMNR (UDP 5355) or mDNS
+//! (UDP 5353) instead of a DNS PTR record. Each query is sent unicast to the
+//! host itself and waits for one reply, so it only makes sense for hosts
+//! already known to be online.
+//!
+//! [`browse_mdns`] instead asks the whole link which DNS-SD services exist,
+//! finding devices that only announce themselves over mDNS.
+
+use crate::types::NameSource;
+use std::collections::{BTreeSet, HashMap};
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// How long each protocol waits for the host to answer.
+pub const LOCAL_NAME_TIMEOUT: Duration = Duration::from_millis(300);
+
+const NETBIOS_PORT: u16 = 137;
+const LLMNR_PORT: u16 = 5355;
+const MDNS_PORT: u16 = 5353;
+
+/// How long [`browse_mdns`] listens for answers to each of its two queries.
+pub const MDNS_BROWSE_TIMEOUT: Duration = Duration::from_millis(1500);
+
+const MDNS_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+/// DNS-SD meta-query that every responder answers with its service types.
+const SERVICES_QUERY: &str = "_services._dns-sd._udp.local";
+
+/// DNS record types used in queries.
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_NBSTAT: u16 = 0x21;
+const CLASS_IN: u16 = 1;
+/// Class bit asking mDNS responders for a unicast reply.
+const CLASS_UNICAST_RESPONSE: u16 = 0x8000;
+
+/// Longest name chain followed while decompressing, against pointer loops.
+const MAX_LABEL_JUMPS: usize = 16;
+
+/// Tries NetBIOS, LLMNR and mDNS in that order and returns the first name
+/// found along with the protocol that supplied it.
+pub fn resolve_local_name(ip: Ipv4Addr, timeout: Duration) -> Option<(String, NameSource)> {
+    if let Some(name) = netbios_name(ip, timeout) {
+        return Some((name, NameSource::NetBios));
+    }
+    if let Some(name) = ptr_query(ip, LLMNR_PORT, timeout) {
+        return Some((name, NameSource::Llmnr));
+    }
+    ptr_query(ip, MDNS_PORT, timeout).map(|name| (name, NameSource::Mdns))
+}
+
+/// Asks the host for its NetBIOS node status and returns its workstation name.
+pub fn netbios_name(ip: Ipv4Addr, timeout: Duration) -> Option<String> {
+    let id = transaction_id(ip);
+    let reply = exchange(ip, NETBIOS_PORT, &nbstat_query(id), timeout)?;
+    parse_nbstat_response(&reply, id)
+}
+
+/// Sends a reverse (PTR) query for `ip` to the host itself on `port`.
+///
+/// LLMNR and mDNS share the DNS wire format; an mDNS query from an
+/// ephemeral port is answered unicast ("legacy unicast").
+fn ptr_query(ip: Ipv4Addr, port: u16, timeout: Duration) -> Option<String> {
+    let id = transaction_id(ip);
+    let reply = exchange(ip, port, &ptr_query_packet(id, ip), timeout)?;
+    parse_ptr_response(&reply, id)
+}
+
+/// A device found by [`browse_mdns`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MdnsHost {
+    /// The `.local` name from the device's A record.
+    pub hostname: Option<String>,
+    /// DNS-SD service types it announces, e.g. `_ipp._tcp`, sorted.
+    pub services: Vec<String>,
+}
+
+/// Browses DNS-SD over mDNS and returns every responding IPv4 host.
+///
+/// First asks for `_services._dns-sd._udp.local` to learn the service types
+/// on the link, then for the instances of those types, which makes devices
+/// send their A records. Each query listens for `timeout`. Socket errors
+/// end the browse early with whatever was found.
+pub fn browse_mdns(timeout: Duration) -> HashMap<Ipv4Addr, MdnsHost> {
+    let mut hosts = HashMap::new();
+    let Ok(socket) = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)) else {
+        return hosts;
+    };
+    let types = mdns_round(&socket, &[SERVICES_QUERY.to_string()], timeout, &mut hosts);
+    if !types.is_empty() {
+        let types: Vec<String> = types.into_iter().collect();
+        mdns_round(&socket, &types, timeout, &mut hosts);
+    }
+    for host in hosts.values_mut() {
+        host.services.sort();
+        host.services.dedup();
+    }
+    hosts
+}
+
+/// Sends one multicast PTR query for `names` and merges every answer
+/// received within `timeout` into `hosts`. Returns the service types seen.
+fn mdns_round(
+    socket: &UdpSocket,
+    names: &[String],
+    timeout: Duration,
+    hosts: &mut HashMap<Ipv4Addr, MdnsHost>,
+) -> BTreeSet<String> {
+    let mut types = BTreeSet::new();
+    let query = mdns_query(names);
+    if socket.send_to(&query, (MDNS_GROUP, MDNS_PORT)).is_err() {
+        return types;
+    }
+
+    let deadline = Instant::now() + timeout;
+    let mut buf = [0u8; 9000];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() || socket.set_read_timeout(Some(remaining)).is_err() {
+            break;
+        }
+        let (len, from) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(_) => break,
+        };
+        let SocketAddr::V4(from) = from else {
+            continue;
+        };
+        merge_mdns_response(&buf[..len], *from.ip(), hosts, &mut types);
+    }
+    types
+}
+
+/// Builds an mDNS query with one PTR question per name.
+fn mdns_query(names: &[String]) -> Vec<u8> {
+    // mDNS queries carry ID 0; the question count is patched below.
+    let mut packet = header(0);
+    packet[4..6].copy_from_slice(&(names.len() as u16).to_be_bytes());
+    for name in names {
+        for label in name.split('.') {
+            packet.push(label.len() as u8);
+            packet.extend_from_slice(label.as_bytes());
+        }
+        packet.push(0);
+        packet.extend_from_slice(&TYPE_PTR.to_be_bytes());
+        packet.extend_from_slice(&(CLASS_IN | CLASS_UNICAST_RESPONSE).to_be_bytes());
+    }
+    packet
+}
+
+/// Merges the records of one mDNS response sent by `from` into `hosts`.
+///
+/// PTR answers list service types (to the meta-query) or instances of a
+/// type; both credit that type to the sender. A records name a host.
+fn merge_mdns_response(
+    data: &[u8],
+    from: Ipv4Addr,
+    hosts: &mut HashMap<Ipv4Addr, MdnsHost>,
+    types: &mut BTreeSet<String>,
+) {
+    let Some(records) = parse_records(data) else {
+        return;
+    };
+    for record in records {
+        match record.rtype {
+            TYPE_PTR => {
+                let Some(target) = read_name(data, record.rdata) else {
+                    continue;
+                };
+                let service = if record.name.eq_ignore_ascii_case(SERVICES_QUERY) {
+                    types.insert(target.clone());
+                    target
+                } else {
+                    record.name
+                };
+                let service = service.trim_end_matches(".local").to_string();
+                hosts.entry(from).or_default().services.push(service);
+            }
+            TYPE_A if record.rdlen == 4 => {
+                let Some(octets) = data.get(record.rdata..record.rdata + 4) else {
+                    continue;
+                };
+                let ip = Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3]);
+                hosts.entry(ip).or_default().hostname = Some(record.name);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A resource record located inside a DNS message.
+struct Record {
+    name: String,
+    rtype: u16,
+    /// Offset of the record data within the message.
+    rdata: usize,
+    rdlen: usize,
+}
+
+/// Lists every answer, authority and additional record of a response.
+fn parse_records(data: &[u8]) -> Option<Vec<Record>> {
+    let is_response = data.get(2)? & 0x80 != 0;
+    if !is_response {
+        return None;
+    }
+    let questions = read_u16(data, 4)?;
+    let records = [6, 8, 10]
+        .iter()
+        .map(|&at| read_u16(data, at).map(usize::from))
+        .sum::<Option<usize>>()?;
+
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = skip_name(data, pos)? + 4;
+    }
+    let mut parsed = Vec::with_capacity(records);
+    for _ in 0..records {
+        let name = read_name(data, pos)?;
+        pos = skip_name(data, pos)?;
+        let rtype = read_u16(data, pos)?;
+        let rdlen = read_u16(data, pos + 8)? as usize;
+        pos += 10;
+        parsed.push(Record {
+            name,
+            rtype,
+            rdata: pos,
+            rdlen,
+        });
+        pos += rdlen;
+    }
+    Some(parsed)
+}
+
+/// Turns a raw name into display-safe text without losing information.
+///
+/// NetBIOS names are in the sender's OEM code page and mDNS labels are not
+/// guaranteed to be UTF-8. Valid UTF-8 is kept as is; invalid bytes and
+/// control characters become `\xNN` and a backslash becomes `\\`, so the
+/// original bytes can always be recovered and terminals never receive
+/// escape sequences.
+pub fn escape_name(raw: &[u8]) -> String {
+    let mut out = String::with_capacity(raw.len());
+    let hex = |out: &mut String, bytes: &[u8]| {
+        for b in bytes {
+            out.push_str(&format!("\\x{:02X}", b));
+        }
+    };
+    for chunk in raw.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '\\' => out.push_str("\\\\"),
+                c if c.is_control() => hex(&mut out, c.encode_utf8(&mut [0; 4]).as_bytes()),
+                c => out.push(c),
+            }
+        }
+        hex(&mut out, chunk.invalid());
+    }
+    out
+}
+
+/// Sends `query` and returns the first datagram received from `ip`.
+fn exchange(ip: Ipv4Addr, port: u16, query: &[u8], timeout: Duration) -> Option<Vec<u8>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.set_read_timeout(Some(timeout)).ok()?;
+    socket.connect((ip, port)).ok()?;
+    socket.send(query).ok()?;
+    let mut buf = [0u8; 1500];
+    let len = socket.recv(&mut buf).ok()?;
+    Some(buf[..len].to_vec())
+}
+
+/// Transaction ID derived from the address, so concurrent queries differ.
+fn transaction_id(ip: Ipv4Addr) -> u16 {
+    let o = ip.octets();
+    u16::from_be_bytes([o[2], o[3]]) ^ 0x5253
+}
+
+fn header(id: u16) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(64);
+    packet.extend_from_slice(&id.to_be_bytes());
+    // Flags 0 (standard query), one question, no other records.
+    packet.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0, 0, 0]);
+    packet
+}
+
+/// Builds a node status request for the wildcard name `*`.
+fn nbstat_query(id: u16) -> Vec<u8> {
+    let mut packet = header(id);
+    let mut name = [0u8; 16];
+    name[0] = b'*';
+    // First-level encoding: each byte becomes two letters 'A' + nibble.
+    packet.push(32);
+    for b in name {
+        packet.push(b'A' + (b >> 4));
+        packet.push(b'A' + (b & 0x0F));
+    }
+    packet.push(0);
+    packet.extend_from_slice(&TYPE_NBSTAT.to_be_bytes());
+    packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+    packet
+}
+
+/// Builds a PTR query for `d.c.b.a.in-addr.arpa`.
+fn ptr_query_packet(id: u16, ip: Ipv4Addr) -> Vec<u8> {
+    let mut packet = header(id);
+    let o = ip.octets();
+    let labels = [
+        o[3].to_string(),
+        o[2].to_string(),
+        o[1].to_string(),
+        o[0].to_string(),
+        "in-addr".to_string(),
+        "arpa".to_string(),
+    ];
+    for label in &labels {
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&TYPE_PTR.to_be_bytes());
+    packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+    packet
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*data.get(pos)?, *data.get(pos + 1)?]))
+}
+
+/// Checks the reply header and returns the answer count.
+fn answer_count(data: &[u8], id: u16) -> Option<u16> {
+    let is_response = data.get(2)? & 0x80 != 0;
+    if read_u16(data, 0)? != id || !is_response {
+        return None;
+    }
+    read_u16(data, 6)
+}
+
+/// Returns the offset just past the (possibly compressed) name at `pos`.
+fn skip_name(data: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let len = *data.get(pos)? as usize;
+        if len == 0 {
+            return Some(pos + 1);
+        }
+        if len & 0xC0 == 0xC0 {
+            return Some(pos + 2);
+        }
+        pos += 1 + len;
+    }
+}
+
+/// Decodes the name at `pos`, following compression pointers.
+fn read_name(data: &[u8], mut pos: usize) -> Option<String> {
+    let mut labels = Vec::new();
+    for _ in 0..MAX_LABEL_JUMPS {
+        loop {
+            let len = *data.get(pos)? as usize;
+            if len == 0 {
+                return (!labels.is_empty()).then(|| labels.join("."));
+            }
+            if len & 0xC0 == 0xC0 {
+                pos = (read_u16(data, pos)? & 0x3FFF) as usize;
+                break;
+            }
+            let label = data.get(pos + 1..pos + 1 + len)?;
+            labels.push(escape_name(label));
+            pos += 1 + len;
+        }
+    }
+    None
+}
+
+/// Extracts the workstation name (suffix `0x00`, unique) from a node status reply.
+fn parse_nbstat_response(data: &[u8], id: u16) -> Option<String> {
+    if answer_count(data, id)? == 0 {
+        return None;
+    }
+    // Skip the echoed question name, then type, class, TTL and data length.
+    let mut pos = skip_name(data, 12)? + 10;
+    let count = *data.get(pos)? as usize;
+    pos += 1;
+    for _ in 0..count {
+        let entry = data.get(pos..pos + 18)?;
+        let (name, suffix, flags) = (&entry[..15], entry[15], entry[16]);
+        let is_group = flags & 0x80 != 0;
+        if suffix == 0x00 && !is_group {
+            // Names are space-padded; some stacks pad with NULs instead.
+            let len = name
+                .iter()
+                .rposition(|&b| b != b' ' && b != 0)
+                .map_or(0, |i| i + 1);
+            return (len > 0).then(|| escape_name(&name[..len]));
+        }
+        pos += 18;
+    }
+    None
+}
+
+/// Extracts the first PTR target from a DNS-format reply.
+fn parse_ptr_response(data: &[u8], id: u16) -> Option<String> {
+    let answers = answer_count(data, id)?;
+    let questions = read_u16(data, 4)?;
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = skip_name(data, pos)? + 4;
+    }
+    for _ in 0..answers {
+        pos = skip_name(data, pos)?;
+        let rtype = read_u16(data, pos)?;
+        let rdlen = read_u16(data, pos + 8)? as usize;
+        pos += 10;
+        if rtype == TYPE_PTR {
+            return read_name(data, pos);
+        }
+        pos += rdlen;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nbstat_query_encodes_wildcard() {
+        let packet = nbstat_query(0x1234);
+        assert_eq!(&packet[..2], &[0x12, 0x34]);
+        assert_eq!(packet[12], 32);
+        assert_eq!(&packet[13..15], b"CK");
+        assert!(packet[15..45].iter().all(|&b| b == b'A'));
+        assert_eq!(&packet[45..], &[0, 0, 0x21, 0, 1]);
+    }
+
+    #[test]
+    fn test_parse_nbstat_response_picks_workstation_name() {
+        let id = 0x0101;
+        let mut reply = nbstat_query(id);
+        // Response flags, one answer.
+        reply[2] = 0x84;
+        reply[6..8].copy_from_slice(&[0, 1]);
+        // TTL; the type and class were echoed with the question.
+        reply.extend_from_slice(&[0, 0, 0, 0]);
+        let entry = |name: &str, suffix: u8, flags: u8| {
+            let mut e = format!("{:<15}", name).into_bytes();
+            e.push(suffix);
+            e.extend_from_slice(&[flags, 0]);
+            e
+        };
+        let mut rdata = vec![3];
+        rdata.extend(entry("WORKGROUP", 0x00, 0x80));
+        rdata.extend(entry("DESKTOP-7QX", 0x20, 0x00));
+        rdata.extend(entry("DESKTOP-7QX", 0x00, 0x00));
+        reply.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        reply.extend(rdata);
+
+        assert_eq!(
+            parse_nbstat_response(&reply, id).as_deref(),
+            Some("DESKTOP-7QX")
+        );
+        assert_eq!(parse_nbstat_response(&reply, id + 1), None);
+        assert_eq!(parse_nbstat_response(&reply[..60], id), None);
+    }
+
+    #[test]
+    fn test_parse_ptr_response_follows_pointers() {
+        let id = 0x0202;
+        let ip = Ipv4Addr::new(192, 168, 1, 20);
+        let mut reply = ptr_query_packet(id, ip);
+        reply[2] = 0x84;
+        reply[6..8].copy_from_slice(&[0, 1]);
+        // Answer: name -> question (offset 12), PTR IN, TTL, then
+        // "living-room" followed by a pointer to a later "local" label.
+        reply.extend_from_slice(&[0xC0, 12, 0, 12, 0, 1, 0, 0, 0, 120]);
+        let target_start = reply.len() + 2;
+        let mut rdata = vec![11];
+        rdata.extend_from_slice(b"living-room");
+        let local_at = (target_start + rdata.len() + 2) as u8;
+        rdata.extend_from_slice(&[0xC0, local_at]);
+        rdata.extend_from_slice(&[5, b'l', b'o', b'c', b'a', b'l', 0]);
+        reply.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        reply.extend(rdata);
+
+        assert_eq!(
+            parse_ptr_response(&reply, id).as_deref(),
+            Some("living-room.local")
+        );
+    }
+
+    #[test]
+    fn test_merge_mdns_response_credits_services_and_names() {
+        // Response to the meta-query plus an instance answer and an A record.
+        let mut reply = vec![0, 0, 0x84, 0, 0, 0, 0, 2, 0, 0, 0, 1];
+        let name = |packet: &mut Vec<u8>, name: &str| {
+            for label in name.split('.') {
+                packet.push(label.len() as u8);
+                packet.extend_from_slice(label.as_bytes());
+            }
+            packet.push(0);
+        };
+        let record = |packet: &mut Vec<u8>, owner: &str, rtype: u16, rdata: &[u8]| {
+            name(packet, owner);
+            packet.extend_from_slice(&rtype.to_be_bytes());
+            packet.extend_from_slice(&[0x80, 1, 0, 0, 0, 120]);
+            packet.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            packet.extend_from_slice(rdata);
+        };
+        let mut target = Vec::new();
+        name(&mut target, "_ipp._tcp.local");
+        record(&mut reply, SERVICES_QUERY, TYPE_PTR, &target);
+        let mut instance = Vec::new();
+        name(&mut instance, "Office._airplay._tcp.local");
+        record(&mut reply, "_airplay._tcp.local", TYPE_PTR, &instance);
+        record(&mut reply, "brother.local", TYPE_A, &[192, 168, 1, 30]);
+
+        let from = Ipv4Addr::new(192, 168, 1, 30);
+        let mut hosts = HashMap::new();
+        let mut types = BTreeSet::new();
+        merge_mdns_response(&reply, from, &mut hosts, &mut types);
+
+        assert_eq!(types.into_iter().collect::<Vec<_>>(), ["_ipp._tcp.local"]);
+        let host = &hosts[&from];
+        assert_eq!(host.hostname.as_deref(), Some("brother.local"));
+        assert_eq!(host.services, ["_ipp._tcp", "_airplay._tcp"]);
+    }
+
+    #[test]
+    fn test_mdns_query_lists_every_name() {
+        let packet = mdns_query(&["_ipp._tcp.local".to_string(), SERVICES_QUERY.to_string()]);
+        assert_eq!(read_u16(&packet, 4), Some(2));
+        assert_eq!(read_name(&packet, 12).as_deref(), Some("_ipp._tcp.local"));
+        let second = skip_name(&packet, 12).unwrap() + 4;
+        assert_eq!(read_name(&packet, second).as_deref(), Some(SERVICES_QUERY));
+        assert_eq!(read_u16(&packet, second - 2), Some(0x8001));
+    }
+
+    #[test]
+    fn test_escape_name_is_display_safe_and_lossless() {
+        assert_eq!(
+            escape_name("Wohnzimmer-PC 名前".as_bytes()),
+            "Wohnzimmer-PC 名前"
+        );
+        // CP850 "é" in a NetBIOS name is not UTF-8.
+        assert_eq!(escape_name(b"CAF\x82"), "CAF\\x82");
+        assert_eq!(escape_name(b"tv\x1b[2J"), "tv\\x1B[2J");
+        // A literal backslash cannot be confused with an escape.
+        assert_eq!(escape_name(b"a\\x82"), "a\\\\x82");
+    }
+
+    #[test]
+    fn test_parse_nbstat_response_escapes_raw_bytes() {
+        let id = 0x0303;
+        let mut reply = nbstat_query(id);
+        reply[2] = 0x84;
+        reply[6..8].copy_from_slice(&[0, 1]);
+        reply.extend_from_slice(&[0, 0, 0, 0, 0, 19, 1]);
+        let mut name = b"B\x9aRO".to_vec();
+        name.resize(15, 0);
+        reply.extend_from_slice(&name);
+        reply.extend_from_slice(&[0x00, 0x04, 0x00]);
+
+        assert_eq!(
+            parse_nbstat_response(&reply, id).as_deref(),
+            Some("B\\x9ARO")
+        );
+    }
+
+    #[test]
+    fn test_read_name_rejects_pointer_loop() {
+        let data = [0xC0, 0];
+        assert_eq!(read_name(&data, 0), None);
+    }
+}
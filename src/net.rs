@@ -1,64 +1,829 @@
-//! Network primitives for IP scanning on Windows.
+//! Network primitives for IP scanning.
 //!
-//! Provides the [`NetworkProvider`] trait and the [`NetUtils`] implementation
-//! using Win32 APIs (`IcmpSendEcho`, `SendARP`) and Tokio for port scanning.
+//! Provides the [`NetworkProvider`] trait and the [`NetUtils`] implementation,
+//! which scans TCP/UDP ports with Tokio on every platform and pings, resolves
+//! MACs and lists interfaces through a per-OS backend:
+//!
+//! - Windows: Win32 APIs (`IcmpSendEcho2`, `SendARP`, `GetIpNetTable2`).
+//! - Linux: unprivileged ICMP datagram sockets, falling back to the `ping`
+//!   helper, and the kernel's `/proc/net/arp` and `/proc/net/route` tables.
+//! - macOS: the same ICMP sockets and `ping` fallback, with the `arp` and
+//!   `route` tools for the neighbor cache and default gateway.
+//!
+//! [`CompositeProvider`] mixes backends, routing e.g. ARP to one and ICMP to
+//! another, [`Socks5Provider`] sends TCP probes through a SOCKS5 proxy, and
+//! [`HostLimitProvider`] caps the connections open to each host.
 
-use crate::types::GError;
+use crate::dhcp::{self, DHCP_PROBE_TIMEOUT};
+use crate::dns;
+use crate::names::{self, LOCAL_NAME_TIMEOUT, MDNS_BROWSE_TIMEOUT, MdnsHost};
+use crate::oui::{self, RANDOMIZED_MAC_VENDOR};
+use crate::snmp::{self, SNMP_TIMEOUT};
+use crate::types::{
+    DEFAULT_PING_TIMEOUT, DnsConfig, GError, NameSource, NetworkRole, SmbInfo, SnmpInfo,
+    TcpProbeOptions, TlsInfo, TraceHop, UdpPortState, UpnpInfo, VendorDetails, WebInfo,
+};
+use crate::upnp::{self, SSDP_SEARCH_TIMEOUT};
+use async_trait::async_trait;
 use lazy_static::lazy_static;
-use std::ffi::c_void;
+use serde::{Deserialize, Serialize};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::future::Future;
-use std::mem;
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
-use std::time::Duration;
-use tokio::net::TcpStream;
-use windows::Win32::Foundation::{HANDLE, INVALID_HANDLE_VALUE};
-use windows::Win32::NetworkManagement::IpHelper::{
-    ICMP_ECHO_REPLY, IcmpCloseHandle, IcmpCreateFile, IcmpSendEcho, SendARP,
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpSocket, TcpStream, UdpSocket};
+use tokio::sync::watch;
+
+mod composite;
+mod limit;
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "macos")]
+mod macos;
+mod socks;
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+mod unix;
+#[cfg(windows)]
+mod win32;
+
+#[cfg(target_os = "linux")]
+use linux as platform;
+#[cfg(target_os = "macos")]
+use macos as platform;
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+use unix as sys;
+#[cfg(windows)]
+use win32 as sys;
+
+pub use composite::{
+    CompositeProvider, CompositeProviderBuilder, Operation, ProviderConfig, SYSTEM_BACKEND,
 };
+pub use limit::HostLimitProvider;
+pub use socks::{PROXY_PASSWORD_ENV, Socks5Provider, socks5_connect};
+
+#[cfg(not(any(windows, target_os = "linux", target_os = "macos")))]
+compile_error!("NetUtils has backends for Windows, Linux and macOS only");
 
 pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
 
+/// TCP connect timeout used when no RTT measurement is available.
+pub const DEFAULT_PORT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Time allowed for connecting and reading a service banner.
+const BANNER_TIMEOUT: Duration = Duration::from_millis(1500);
+/// Longest banner kept, in characters.
+const MAX_BANNER_LEN: usize = 120;
+/// Data carried by every echo request.
+const PING_PAYLOAD: &[u8] = b"PingPayload";
+/// IPv4 header without options.
+const IPV4_HEADER_LEN: u64 = 20;
+
+/// Estimated size of an echo request: IPv4 and ICMP headers plus the payload.
+pub const ECHO_REQUEST_BYTES: u64 = IPV4_HEADER_LEN + 8 + PING_PAYLOAD.len() as u64;
+/// Estimated size of the SYN a TCP connect sends: IPv4 and TCP headers plus
+/// the usual options (MSS, SACK, timestamps, window scale).
+pub const TCP_SYN_BYTES: u64 = IPV4_HEADER_LEN + 40;
+/// Size of an ARP request for IPv4 over Ethernet, without the Ethernet header.
+pub const ARP_REQUEST_BYTES: u64 = 28;
+
+/// The vendor database in use and the custom CSV it came from, if any.
+struct OuiState {
+    path: Option<PathBuf>,
+    db: Option<mac_oui::Oui>,
+}
+
 lazy_static! {
-    static ref OUI_DB: Option<mac_oui::Oui> = mac_oui::Oui::default().ok();
+    static ref OUI_DB: RwLock<OuiState> = RwLock::new(OuiState {
+        path: None,
+        db: mac_oui::Oui::default().ok(),
+    });
+}
+
+/// Loads an OUI CSV, or the database bundled with `mac_oui` for `None`.
+fn load_oui(path: Option<&Path>) -> Result<mac_oui::Oui, GError> {
+    match path {
+        Some(path) => mac_oui::Oui::from_csv_file(path).map_err(|e| {
+            GError::Internal(format!(
+                "Failed to load OUI database {}: {}",
+                path.display(),
+                e
+            ))
+        }),
+        None => mac_oui::Oui::default()
+            .map_err(|e| GError::Internal(format!("Failed to load bundled OUI database: {}", e))),
+    }
+}
+
+/// Points [`NetworkProvider::resolve_vendor`] at a custom OUI CSV (IEEE
+/// `oui.csv` layout), e.g. a corporate mirror in an air-gapped network.
+/// `None` switches back to the bundled database.
+///
+/// Returns the number of records loaded. On error the previous database
+/// stays in use.
+///
+/// # Errors
+///
+/// Returns [`GError::Internal`] if the file cannot be read or parsed.
+pub fn set_oui_database(path: Option<PathBuf>) -> Result<usize, GError> {
+    let db = load_oui(path.as_deref())?;
+    let records = usize::try_from(db.get_total_records()).unwrap_or(0);
+    if let Ok(mut state) = OUI_DB.write() {
+        *state = OuiState { path, db: Some(db) };
+    }
+    log::info!("Loaded OUI database with {} records", records);
+    Ok(records)
+}
+
+/// Re-reads the current OUI database from disk, e.g. after the CSV was updated.
+///
+/// Returns the number of records loaded. On error the previous database
+/// stays in use.
+///
+/// # Errors
+///
+/// Returns [`GError::Internal`] if the file cannot be read or parsed.
+pub fn reload_oui_database() -> Result<usize, GError> {
+    let path = OUI_DB.read().ok().and_then(|state| state.path.clone());
+    set_oui_database(path)
+}
+
+/// Where the vendor database in use came from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OuiSource {
+    /// The custom CSV loaded, or `None` for the database bundled with `mac_oui`.
+    pub path: Option<PathBuf>,
+    /// Records loaded; `None` if no database could be loaded.
+    pub records: Option<usize>,
+    /// When the custom CSV was last written, e.g. by [`oui::update`].
+    pub modified: Option<SystemTime>,
+}
+
+/// Describes the vendor database lookups currently use.
+pub fn oui_source() -> OuiSource {
+    let Ok(state) = OUI_DB.read() else {
+        return OuiSource::default();
+    };
+    let modified = state
+        .path
+        .as_ref()
+        .and_then(|path| std::fs::metadata(path).ok()?.modified().ok());
+    OuiSource {
+        path: state.path.clone(),
+        records: state
+            .db
+            .as_ref()
+            .and_then(|db| usize::try_from(db.get_total_records()).ok()),
+        modified,
+    }
+}
+
+/// Returns the full OUI record for `mac` from the current vendor database.
+///
+/// Scan results only carry the company name; frontends call this when the
+/// user asks for details on a vendor, so it is not stored per host.
+pub fn vendor_details(mac: &str) -> Option<VendorDetails> {
+    let state = OUI_DB.read().ok()?;
+    let entry = state.db.as_ref()?.lookup_by_mac(mac).ok().flatten()?;
+    Some(VendorDetails {
+        oui: entry.oui.clone(),
+        company_name: entry.company_name.clone(),
+        company_address: entry.company_address.clone(),
+        country_code: entry.country_code.clone(),
+        registry: entry.assignment_block_size.clone(),
+        is_private: entry.is_private,
+        date_updated: entry.date_updated.clone(),
+    })
+}
+
+/// Notifications of changes to the local network interfaces.
+///
+/// Each clone tracks which changes it has already seen, so a clone taken
+/// before a change still reports it after the original has consumed it.
+#[derive(Clone)]
+pub struct InterfaceWatch {
+    rx: watch::Receiver<u64>,
+    /// Keeps the OS subscription alive while any clone exists.
+    _registration: Option<Arc<dyn Send + Sync>>,
 }
 
-/// RAII wrapper for Win32 handles.
-struct SafeHandle(HANDLE);
+impl InterfaceWatch {
+    /// Creates a watch fed by `rx`; every value sent counts as one change.
+    pub fn new(rx: watch::Receiver<u64>) -> Self {
+        Self {
+            rx,
+            _registration: None,
+        }
+    }
+
+    /// A watch that never reports a change.
+    pub fn inert() -> Self {
+        let (_, rx) = watch::channel(0);
+        Self::new(rx)
+    }
 
-impl SafeHandle {
-    fn new(h: HANDLE) -> Result<Self, GError> {
-        if h == INVALID_HANDLE_VALUE || h.0 == 0 {
-            Err(GError::Internal("Invalid Win32 Handle".to_string()))
+    /// Returns `true` if an interface changed since the last call, and marks it seen.
+    pub fn take_change(&mut self) -> bool {
+        if self.rx.has_changed().unwrap_or(false) {
+            self.rx.borrow_and_update();
+            true
         } else {
-            Ok(SafeHandle(h))
+            false
+        }
+    }
+
+    /// Waits for the next change. Never completes for an [`inert`](InterfaceWatch::inert) watch.
+    pub async fn changed(&mut self) {
+        if self.rx.changed().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// The machine's sleep state, as reported by a [`PowerWatch`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PowerState {
+    /// `true` from a suspend notification until the following resume.
+    pub suspended: bool,
+    /// Number of suspends since the watch was created.
+    pub sleeps: u64,
+}
+
+/// Notifications of system suspend and resume.
+///
+/// A suspend and resume that arrive before the receiver looks collapse into
+/// one change, but still advance [`PowerState::sleeps`].
+#[derive(Clone)]
+pub struct PowerWatch {
+    rx: watch::Receiver<PowerState>,
+    /// Keeps the OS subscription alive while any clone exists.
+    _registration: Option<Arc<dyn Send + Sync>>,
+}
+
+impl PowerWatch {
+    /// Creates a watch fed by `rx`.
+    pub fn new(rx: watch::Receiver<PowerState>) -> Self {
+        Self {
+            rx,
+            _registration: None,
+        }
+    }
+
+    /// A watch that never reports a sleep.
+    pub fn inert() -> Self {
+        let (_, rx) = watch::channel(PowerState::default());
+        Self::new(rx)
+    }
+
+    /// The current state.
+    pub fn state(&self) -> PowerState {
+        *self.rx.borrow()
+    }
+
+    /// Waits for the next change and returns the new state. Never completes
+    /// for an [`inert`](PowerWatch::inert) watch.
+    pub async fn changed(&mut self) -> PowerState {
+        if self.rx.changed().await.is_err() {
+            std::future::pending::<()>().await;
         }
+        *self.rx.borrow_and_update()
     }
 }
 
-impl Drop for SafeHandle {
-    fn drop(&mut self) {
-        unsafe {
-            let _ = IcmpCloseHandle(self.0);
+/// Widest subnet offered as an automatic scan range; larger ones are
+/// narrowed to the /24 around the interface address.
+const MAX_AUTO_RANGE_PREFIX: u8 = 24;
+/// An IPv4 address assigned to a local network adapter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceInfo {
+    /// Adapter name as shown by the OS, e.g. `Wi-Fi` or `eth0`.
+    pub name: String,
+    pub address: Ipv4Addr,
+    /// On-link prefix length, e.g. 24 for `255.255.255.0`.
+    pub prefix_len: u8,
+    /// First IPv4 default gateway of the adapter.
+    pub gateway: Option<Ipv4Addr>,
+    /// Whether the adapter is operationally up.
+    pub is_up: bool,
+    /// DNS servers configured for the adapter.
+    pub dns_servers: Vec<Ipv4Addr>,
+    /// DHCP server that leased the address, where the OS reports it.
+    pub dhcp_server: Option<Ipv4Addr>,
+}
+
+impl InterfaceInfo {
+    /// Returns the subnet mask, e.g. `255.255.255.0` for a /24.
+    pub fn netmask(&self) -> Ipv4Addr {
+        Ipv4Addr::from(prefix_mask(self.prefix_len))
+    }
+
+    /// Returns the usable host range of the interface's subnet.
+    ///
+    /// Subnets wider than a /24 are narrowed to the /24 containing the
+    /// interface address, so an automatic scan stays small.
+    pub fn scan_range(&self) -> (Ipv4Addr, Ipv4Addr) {
+        let prefix = self.prefix_len.clamp(MAX_AUTO_RANGE_PREFIX, 32);
+        let mask = prefix_mask(prefix);
+        let network = u32::from(self.address) & mask;
+        let broadcast = network | !mask;
+        if prefix >= 31 {
+            // Point-to-point links have no network or broadcast address.
+            (Ipv4Addr::from(network), Ipv4Addr::from(broadcast))
+        } else {
+            (Ipv4Addr::from(network + 1), Ipv4Addr::from(broadcast - 1))
         }
     }
+
+    /// Formats [`scan_range`](InterfaceInfo::scan_range) for
+    /// [`Bridge::parse_range`](crate::bridge::Bridge::parse_range), e.g. `192.168.1.1-192.168.1.254`.
+    pub fn range_string(&self) -> String {
+        let (start, end) = self.scan_range();
+        format!("{}-{}", start, end)
+    }
+}
+
+fn prefix_mask(prefix_len: u8) -> u32 {
+    match prefix_len {
+        0 => 0,
+        p => u32::MAX << (32 - p.min(32) as u32),
+    }
+}
+
+/// Picks the interface a user most likely means by "my network": the first
+/// one that is up and has a default gateway, else the first one that is up.
+pub fn pick_active_interface(interfaces: &[InterfaceInfo]) -> Option<&InterfaceInfo> {
+    interfaces
+        .iter()
+        .find(|i| i.is_up && i.gateway.is_some())
+        .or_else(|| interfaces.iter().find(|i| i.is_up))
+}
+
+/// The interfaces worth offering as a default range, most likely first:
+/// those that are up, the ones with a default gateway ahead, one per scan
+/// range. Link-local (APIPA) addresses are left out, since they only mean
+/// DHCP failed.
+pub fn candidate_interfaces(interfaces: &[InterfaceInfo]) -> Vec<InterfaceInfo> {
+    let mut candidates: Vec<InterfaceInfo> = interfaces
+        .iter()
+        .filter(|i| i.is_up && !i.address.is_link_local())
+        .cloned()
+        .collect();
+    candidates.sort_by_key(|i| i.gateway.is_none());
+    let mut seen = HashSet::new();
+    candidates.retain(|i| seen.insert(i.scan_range()));
+    candidates
+}
+
+/// Infrastructure roles the configuration of the interfaces that are up
+/// reveals: their default gateways, DNS servers and the DHCP servers that
+/// leased their addresses. Each address maps to its roles, sorted.
+pub fn roles_from_interfaces(interfaces: &[InterfaceInfo]) -> HashMap<Ipv4Addr, Vec<NetworkRole>> {
+    let mut roles = HashMap::new();
+    for i in interfaces.iter().filter(|i| i.is_up) {
+        let found = i
+            .gateway
+            .map(|ip| (ip, NetworkRole::Gateway))
+            .into_iter()
+            .chain(i.dhcp_server.map(|ip| (ip, NetworkRole::DhcpServer)))
+            .chain(i.dns_servers.iter().map(|&ip| (ip, NetworkRole::DnsServer)));
+        for (ip, role) in found {
+            add_role(&mut roles, ip, role);
+        }
+    }
+    roles
+}
+
+/// Records that `ip` has `role`, keeping its roles sorted and unique.
+pub fn add_role(roles: &mut HashMap<Ipv4Addr, Vec<NetworkRole>>, ip: Ipv4Addr, role: NetworkRole) {
+    let list = roles.entry(ip).or_default();
+    if let Err(at) = list.binary_search(&role) {
+        list.insert(at, role);
+    }
+}
+
+/// Lists the IPv4 addresses of the local network adapters, loopback excluded.
+///
+/// An adapter with several addresses yields one entry per address.
+///
+/// # Errors
+///
+/// Returns a [`GError`] if the adapters cannot be listed.
+pub fn interfaces() -> Result<Vec<InterfaceInfo>, GError> {
+    sys::interfaces()
+}
+
+/// Returns the interface chosen by [`pick_active_interface`], or `None` if
+/// none is up or the adapters cannot be listed.
+pub fn active_interface() -> Option<InterfaceInfo> {
+    match interfaces() {
+        Ok(list) => pick_active_interface(&list).cloned(),
+        Err(e) => {
+            log::warn!("Failed to list network interfaces: {}", e);
+            None
+        }
+    }
+}
+
+/// Formats the first six bytes of a hardware address as `XX:XX:XX:XX:XX:XX`.
+fn format_mac(bytes: &[u8]) -> String {
+    format!(
+        "{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
+        bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5]
+    )
+}
+
+/// What a [`NetworkProvider`] can do on this machine; see
+/// [`NetworkProvider::capabilities`].
+///
+/// The scanner skips probes that need a missing capability instead of
+/// reporting every host as failing them, and UIs gray out the features.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// MAC addresses can be resolved over ARP and read from the neighbor cache.
+    pub arp: bool,
+    /// ICMP echoes can be sent: pings, latency and traceroute.
+    pub icmp: bool,
+    /// Raw IP sockets can be opened, which takes administrator or root rights.
+    pub raw_sockets: bool,
+    /// The machine has an IPv6 stack.
+    pub ipv6: bool,
+    /// UDP datagrams can be sent: UDP ports, mDNS, SSDP, SNMP and Wake-on-LAN.
+    pub udp: bool,
+}
+
+impl Default for Capabilities {
+    /// What the trait's default methods simulate: ARP, ICMP and UDP.
+    fn default() -> Self {
+        Self {
+            arp: true,
+            icmp: true,
+            raw_sockets: false,
+            ipv6: false,
+            udp: true,
+        }
+    }
+}
+
+impl fmt::Display for Capabilities {
+    /// Lists what is available, e.g. `ARP, ICMP, UDP`, or `none`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = [
+            (self.arp, "ARP"),
+            (self.icmp, "ICMP"),
+            (self.raw_sockets, "raw sockets"),
+            (self.ipv6, "IPv6"),
+            (self.udp, "UDP"),
+        ]
+        .into_iter()
+        .filter_map(|(available, name)| available.then_some(name))
+        .collect();
+        if names.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", names.join(", "))
+        }
+    }
+}
+
+/// An answered ICMP echo request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EchoReply {
+    /// Round-trip time in milliseconds.
+    pub rtt_ms: u32,
+    /// TTL of the reply as received, a hint at the sender's OS; `None` when
+    /// the provider cannot tell.
+    pub ttl: Option<u8>,
 }
 
 /// Trait to abstract network operations, enabling mocking for tests.
+///
+/// Async methods go through [`async_trait`] so the trait stays usable as
+/// `dyn NetworkProvider`; implementations need the same attribute.
+#[async_trait]
 pub trait NetworkProvider: Send + Sync {
     /// Sends an ICMP echo request. Returns `true` if the host responds.
-    fn ping(&self, ip: Ipv4Addr) -> Result<bool, GError>;
+    fn ping(&self, ip: Ipv4Addr) -> Result<bool, GError> {
+        self.echo(ip).map(|rtt| rtt.is_some())
+    }
+    /// Like [`ping`](NetworkProvider::ping), without holding a thread while
+    /// the request is in flight; built on
+    /// [`echo_async`](NetworkProvider::echo_async).
+    async fn ping_async(&self, ip: Ipv4Addr, timeout: Duration) -> Result<bool, GError> {
+        self.echo_async(ip, timeout)
+            .await
+            .map(|reply| reply.is_some())
+    }
+    /// Sends a single ICMP echo request. Returns the round-trip time in
+    /// milliseconds if the host responds, `None` otherwise.
+    fn echo(&self, ip: Ipv4Addr) -> Result<Option<u32>, GError>;
+    /// Like [`echo`](NetworkProvider::echo), waiting at most `timeout` for the reply.
+    ///
+    /// Providers without real network I/O may ignore the timeout.
+    fn echo_timeout(&self, ip: Ipv4Addr, _timeout: Duration) -> Result<Option<u32>, GError> {
+        self.echo(ip)
+    }
+    /// Like [`echo_timeout`](NetworkProvider::echo_timeout), without holding a
+    /// thread while the request is in flight, and with the reply's TTL.
+    ///
+    /// The default runs `echo_timeout` inline and reports no TTL, which suits
+    /// providers without real network I/O.
+    async fn echo_async(
+        &self,
+        ip: Ipv4Addr,
+        timeout: Duration,
+    ) -> Result<Option<EchoReply>, GError> {
+        let rtt = self.echo_timeout(ip, timeout)?;
+        Ok(rtt.map(|rtt_ms| EchoReply { rtt_ms, ttl: None }))
+    }
     /// Resolves the MAC address via ARP. Returns `None` if unreachable.
     fn resolve_mac(&self, ip: Ipv4Addr) -> Result<Option<String>, GError>;
+    /// Like [`resolve_mac`](NetworkProvider::resolve_mac), for use from async
+    /// code.
+    ///
+    /// The default runs `resolve_mac` inline, which suits providers without
+    /// real network I/O; providers whose lookup blocks should override it.
+    async fn resolve_mac_async(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+        self.resolve_mac(ip)
+    }
+    /// Reads the system neighbor cache in one call, mapping IPv4 addresses to MACs.
+    ///
+    /// Used by the scanner as a pre-pass so that only cache misses pay for a
+    /// per-IP ARP request. Providers without a cache return an empty map.
+    fn read_arp_table(&self) -> Result<HashMap<Ipv4Addr, String>, GError> {
+        Ok(HashMap::new())
+    }
     /// Performs reverse DNS lookup. Returns `None` if no hostname found.
+    ///
+    /// Names from the network are passed through [`names::escape_name`]
+    /// here and in [`resolve_local_name`](NetworkProvider::resolve_local_name),
+    /// so frontends can display them as is.
     fn resolve_hostname(&self, ip: Ipv4Addr) -> Result<Option<String>, GError>;
+    /// Reverse-resolves `ips` together, asking `dns`'s servers, and returns
+    /// the names found.
+    ///
+    /// The scanner gathers the lookups of hosts that finish probing around
+    /// the same time into one call. The default runs
+    /// [`resolve_hostname`](NetworkProvider::resolve_hostname) inline for
+    /// each address and ignores `dns`, which suits providers without real
+    /// network I/O.
+    async fn resolve_hostnames(
+        &self,
+        ips: &[Ipv4Addr],
+        _dns: &DnsConfig,
+    ) -> HashMap<Ipv4Addr, String> {
+        ips.iter()
+            .filter_map(|&ip| Some((ip, self.resolve_hostname(ip).ok()??)))
+            .collect()
+    }
+    /// Looks up the IPv4 addresses of `names` together, asking `dns`'s
+    /// servers, to confirm reverse DNS names. Names without A records map
+    /// to an empty list; names whose lookup failed are left out.
+    ///
+    /// The default finds nothing, which suits providers without real
+    /// network I/O: no name is confirmed or contradicted.
+    async fn resolve_addresses(
+        &self,
+        _names: &[String],
+        _dns: &DnsConfig,
+    ) -> HashMap<String, Vec<Ipv4Addr>> {
+        HashMap::new()
+    }
+    /// Asks an online host for its own name via NetBIOS, LLMNR or mDNS.
+    ///
+    /// Fallback for [`resolve_hostname`](NetworkProvider::resolve_hostname),
+    /// which finds nothing for most home devices. Providers without real
+    /// network I/O return `None`.
+    fn resolve_local_name(&self, _ip: Ipv4Addr) -> Option<(String, NameSource)> {
+        None
+    }
+    /// Browses mDNS/DNS-SD on the local link and returns the hosts that answered.
+    ///
+    /// Used by the scanner as an optional discovery pre-pass. Providers
+    /// without real network I/O return an empty map.
+    fn discover_mdns(&self) -> HashMap<Ipv4Addr, MdnsHost> {
+        HashMap::new()
+    }
+    /// Searches for UPnP root devices over SSDP and returns their descriptions.
+    ///
+    /// Used by the scanner as an optional discovery pre-pass. Providers
+    /// without real network I/O return an empty map.
+    fn discover_upnp(&self) -> HashMap<Ipv4Addr, UpnpInfo> {
+        HashMap::new()
+    }
+    /// Finds the gateways, DNS and DHCP servers of the local networks, from
+    /// the adapter configuration and, if `dhcp_probe` is set, a DHCP probe.
+    ///
+    /// Used by the scanner as a discovery pre-pass. Providers without real
+    /// network I/O return an empty map.
+    fn discover_roles(&self, _dhcp_probe: bool) -> HashMap<Ipv4Addr, Vec<NetworkRole>> {
+        HashMap::new()
+    }
+    /// Asks the host's SNMP agent for its system group using `community`.
+    ///
+    /// Providers without real network I/O return `None`.
+    fn query_snmp(&self, _ip: Ipv4Addr, _community: &str) -> Option<SnmpInfo> {
+        None
+    }
     /// Looks up the OUI vendor name for a given MAC address.
     fn resolve_vendor(&self, mac: &str) -> Option<String>;
     /// Probes a TCP port. Returns `true` if the port is open.
-    fn scan_port(&self, ip: Ipv4Addr, port: u16) -> BoxFuture<'_, bool>;
+    async fn scan_port(&self, ip: Ipv4Addr, port: u16) -> bool;
+    /// Probes a TCP port with an explicit connect timeout.
+    ///
+    /// Providers without real network I/O may ignore the timeout; the default
+    /// implementation delegates to [`scan_port`](NetworkProvider::scan_port).
+    async fn scan_port_timeout(&self, ip: Ipv4Addr, port: u16, _timeout: Duration) -> bool {
+        self.scan_port(ip, port).await
+    }
+    /// Probes a TCP port from a socket configured with `options`.
+    ///
+    /// The default implementation ignores the options and delegates to
+    /// [`scan_port_timeout`](NetworkProvider::scan_port_timeout).
+    async fn scan_port_with_options(
+        &self,
+        ip: Ipv4Addr,
+        port: u16,
+        timeout: Duration,
+        _options: TcpProbeOptions,
+    ) -> bool {
+        self.scan_port_timeout(ip, port, timeout).await
+    }
+    /// Probes a UDP port with a service-specific payload.
+    async fn scan_udp_port(&self, ip: Ipv4Addr, port: u16) -> UdpPortState;
+    /// Connects to an open TCP port and returns the first line the service
+    /// identifies itself with, e.g. `SSH-2.0-OpenSSH_9.6`.
+    ///
+    /// Providers that cannot talk to real services return `None`.
+    async fn grab_banner(&self, _ip: Ipv4Addr, _port: u16) -> Option<String> {
+        None
+    }
+    /// Asks the SMB server on port 445 to negotiate SMBv1; see
+    /// [`smb::probe_smb1`](crate::smb::probe_smb1). Providers without real
+    /// network I/O report nothing.
+    async fn probe_smb1(&self, _ip: Ipv4Addr) -> Option<bool> {
+        None
+    }
+    /// Asks the SMB server on port 445 about itself; see
+    /// [`smb::host_info`](crate::smb::host_info). Providers without real
+    /// network I/O report nothing.
+    async fn smb_info(&self, _ip: Ipv4Addr) -> Option<SmbInfo> {
+        None
+    }
+    /// Audits a TLS port; see [`tls::audit`](crate::tls::audit). Providers
+    /// without real network I/O report nothing.
+    async fn audit_tls(&self, _ip: Ipv4Addr, _port: u16) -> Option<TlsInfo> {
+        None
+    }
+    /// Requests `/` from a web port; see [`web::fetch`](crate::web::fetch).
+    /// Providers without real network I/O report nothing.
+    async fn fetch_web_info(&self, _ip: Ipv4Addr, _port: u16) -> Option<WebInfo> {
+        None
+    }
+    /// Subscribes to IPv4 interface changes (adapter connected or disconnected,
+    /// address or route change). Providers without interfaces never report one.
+    fn watch_interfaces(&self) -> Result<InterfaceWatch, GError> {
+        Ok(InterfaceWatch::inert())
+    }
+    /// Returns `true` if the OS currently has an interface that can reach `ip`.
+    fn route_available(&self, _ip: Ipv4Addr) -> bool {
+        true
+    }
+    /// Reports what this provider supports. The default is
+    /// [`Capabilities::default`], which matches the other default methods.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+    /// Subscribes to system suspend and resume. Providers without a real
+    /// machine behind them never sleep.
+    fn watch_power(&self) -> Result<PowerWatch, GError> {
+        Ok(PowerWatch::inert())
+    }
+    /// Sends one echo to `ip` with the given TTL and reports who answered: a
+    /// router saying the TTL expired, or `ip` itself. The default treats every
+    /// host as directly reachable.
+    fn trace_hop(&self, ip: Ipv4Addr, ttl: u8, timeout: Duration) -> Result<TraceHop, GError> {
+        let rtt_ms = self.echo_timeout(ip, timeout)?;
+        Ok(TraceHop {
+            ttl,
+            addr: rtt_ms.map(|_| ip),
+            rtt_ms,
+        })
+    }
+}
+
+/// Creates a TCP socket for a port probe with `options` applied.
+fn probe_socket(options: &TcpProbeOptions) -> std::io::Result<TcpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::STREAM, Some(Protocol::TCP))?;
+    socket.set_nonblocking(true)?;
+    if let Some(ttl) = options.ttl {
+        socket.set_ttl(u32::from(ttl))?;
+    }
+    if options.disable_keepalive {
+        socket.set_keepalive(false)?;
+    }
+    if let Some(port) = options.source_port {
+        socket.set_reuse_address(true)?;
+        socket.bind(&SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)).into())?;
+    }
+    Ok(TcpSocket::from_std_stream(socket.into()))
+}
+
+/// Returns the request to send before reading a banner, if the service only
+/// speaks when spoken to. FTP, SMTP, SSH and POP3 greet the client unprompted.
+fn banner_probe(port: u16) -> Option<&'static [u8]> {
+    match port {
+        80 | 8000 | 8080 | 8888 => Some(b"HEAD / HTTP/1.0\r\n\r\n"),
+        _ => None,
+    }
 }
 
-/// Implementation of [`NetworkProvider`] using standard Windows APIs.
+/// Reads the banner of the service at the other end of `stream`, sending
+/// [`banner_probe`] first if it needs prompting.
+async fn read_banner(stream: &mut TcpStream, port: u16) -> Option<String> {
+    if let Some(probe) = banner_probe(port) {
+        stream.write_all(probe).await.ok()?;
+    }
+    let mut buf = [0u8; 512];
+    let n = stream.read(&mut buf).await.ok()?;
+    parse_banner(&buf[..n])
+}
+
+/// Extracts a one-line banner from the first bytes a service sent.
+///
+/// HTTP responses yield their `Server:` header (falling back to the status
+/// line); FTP/SMTP greetings have their `220` reply code stripped. Control
+/// characters are dropped and the result is truncated to [`MAX_BANNER_LEN`].
+fn parse_banner(raw: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(raw);
+    let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+    let first = lines.next()?;
+
+    let line = if first.starts_with("HTTP/") {
+        text.lines()
+            .find_map(|l| {
+                let (name, value) = l.split_once(':')?;
+                name.eq_ignore_ascii_case("server").then(|| value.trim())
+            })
+            .filter(|v| !v.is_empty())
+            .unwrap_or(first)
+    } else if let Some(rest) = first.strip_prefix("220") {
+        rest.trim_start_matches(['-', ' '])
+    } else {
+        first
+    };
+
+    let clean: String = line
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_BANNER_LEN)
+        .collect();
+    (!clean.is_empty()).then_some(clean)
+}
+
+/// Returns the probe datagram for a well-known UDP service.
+///
+/// Services that only answer well-formed requests (DNS, NTP, SNMP, NetBIOS, SSDP)
+/// get a minimal valid query; everything else gets an empty datagram, which is
+/// still enough to provoke an ICMP port-unreachable from a closed port.
+/// Estimated size of the UDP probe of `port`: IPv4 and UDP headers plus
+/// its payload.
+pub fn udp_probe_bytes(port: u16) -> u64 {
+    IPV4_HEADER_LEN + 8 + udp_probe_payload(port).len() as u64
+}
+
+fn udp_probe_payload(port: u16) -> &'static [u8] {
+    match port {
+        // DNS: standard query for the root NS records.
+        53 => &[
+            0x52, 0x53, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x02, 0x00, 0x01,
+        ],
+        // NTP: v3 client mode request.
+        123 => &[
+            0x1B, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ],
+        // NetBIOS-NS: node status request for the wildcard name "*".
+        137 => b"\x80\x94\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x20CKAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA\x00\x00\x21\x00\x01",
+        // SNMP: v1 GetRequest for sysDescr.0 with community "public".
+        161 => &[
+            0x30, 0x29, 0x02, 0x01, 0x00, 0x04, 0x06, b'p', b'u', b'b', b'l', b'i', b'c', 0xA0,
+            0x1C, 0x02, 0x04, 0x00, 0x00, 0x00, 0x01, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00, 0x30,
+            0x0E, 0x30, 0x0C, 0x06, 0x08, 0x2B, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00, 0x05,
+            0x00,
+        ],
+        // SSDP: unicast discovery request.
+        1900 => b"M-SEARCH * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\nMAN: \"ssdp:discover\"\r\nMX: 1\r\nST: ssdp:all\r\n\r\n",
+        _ => &[],
+    }
+}
+
+/// Implementation of [`NetworkProvider`] on the host's network stack.
 pub struct NetUtils;
 
 impl NetUtils {
@@ -66,6 +831,19 @@ impl NetUtils {
     pub fn new() -> Self {
         Self
     }
+
+    /// Looks vendors up in the OUI CSV at `path` (IEEE `oui.csv` layout),
+    /// e.g. one fetched with [`oui::update`](crate::oui::update).
+    ///
+    /// The database is shared by the whole process, as with [`set_oui_database`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if the file cannot be read or parsed.
+    pub fn with_oui_file(self, path: impl Into<PathBuf>) -> Result<Self, GError> {
+        set_oui_database(Some(path.into()))?;
+        Ok(self)
+    }
 }
 
 impl Default for NetUtils {
@@ -74,53 +852,59 @@ impl Default for NetUtils {
     }
 }
 
+#[async_trait]
 impl NetworkProvider for NetUtils {
     fn resolve_mac(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
-        let dest_ip_final = u32::from_le_bytes(ip.octets());
-        // Win32 SendARP requires MAXLEN_PHYSADDR (8) bytes minimum, even if MAC is 6.
-        let mut mac_buffer = [0u8; 8];
-        let mut mac_len = mac_buffer.len() as u32;
-
-        let res = unsafe {
-            SendARP(
-                dest_ip_final,
-                0,
-                mac_buffer.as_mut_ptr() as *mut c_void,
-                &mut mac_len,
-            )
-        };
+        sys::resolve_mac(ip)
+    }
 
-        if res == 0 {
-            if mac_len >= 6 {
-                let mac_str = format!(
-                    "{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
-                    mac_buffer[0],
-                    mac_buffer[1],
-                    mac_buffer[2],
-                    mac_buffer[3],
-                    mac_buffer[4],
-                    mac_buffer[5]
-                );
-                Ok(Some(mac_str))
-            } else {
-                // Should not happen for Ethernet, but handle safely
-                log::error!(
-                    "SendARP succeeded but returned invalid mac_len: {}",
-                    mac_len
-                );
-                Ok(None)
-            }
-        } else if res == 67 || res == 1168 {
-            // 67 = ERROR_BAD_NET_NAME (Host not found), 1168 = ERROR_NOT_FOUND
-            Ok(None)
-        } else {
-            log::error!("SendARP failed for {} with error code: {}", ip, res);
-            Err(GError::Win32(res, "SendARP failed".to_string()))
-        }
+    async fn resolve_mac_async(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+        tokio::task::spawn_blocking(move || sys::resolve_mac(ip))
+            .await
+            .map_err(|e| GError::Internal(format!("ARP lookup of {} failed: {}", ip, e)))?
+    }
+
+    fn read_arp_table(&self) -> Result<HashMap<Ipv4Addr, String>, GError> {
+        let entries = sys::read_arp_table()?;
+        log::info!("ARP cache pre-pass loaded {} entries", entries.len());
+        Ok(entries)
+    }
+
+    fn watch_interfaces(&self) -> Result<InterfaceWatch, GError> {
+        sys::watch_interfaces()
+    }
+
+    fn watch_power(&self) -> Result<PowerWatch, GError> {
+        sys::watch_power()
+    }
+
+    fn route_available(&self, ip: Ipv4Addr) -> bool {
+        sys::route_available(ip)
     }
 
+    /// Detected once per process and logged.
+    fn capabilities(&self) -> Capabilities {
+        static DETECTED: OnceLock<Capabilities> = OnceLock::new();
+        *DETECTED.get_or_init(|| {
+            let capabilities = Capabilities {
+                arp: sys::arp_available(),
+                icmp: sys::icmp_available(),
+                raw_sockets: Socket::new(Domain::IPV4, Type::RAW, Some(Protocol::ICMPV4)).is_ok(),
+                ipv6: std::net::UdpSocket::bind((Ipv6Addr::UNSPECIFIED, 0)).is_ok(),
+                udp: std::net::UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).is_ok(),
+            };
+            log::info!("Network capabilities: {}", capabilities);
+            capabilities
+        })
+    }
+
+    /// Locally administered MACs are reported as [`RANDOMIZED_MAC_VENDOR`].
     fn resolve_vendor(&self, mac_str: &str) -> Option<String> {
-        OUI_DB.as_ref().and_then(|db| {
+        if oui::is_randomized(mac_str) {
+            return Some(RANDOMIZED_MAC_VENDOR.to_string());
+        }
+        let state = OUI_DB.read().ok()?;
+        state.db.as_ref().and_then(|db| {
             // mac_oui version 0.4 uses lookup_by_mac
             db.lookup_by_mac(mac_str)
                 .ok()
@@ -129,56 +913,203 @@ impl NetworkProvider for NetUtils {
         })
     }
 
+    fn resolve_local_name(&self, ip: Ipv4Addr) -> Option<(String, NameSource)> {
+        names::resolve_local_name(ip, LOCAL_NAME_TIMEOUT)
+    }
+
+    fn discover_mdns(&self) -> HashMap<Ipv4Addr, MdnsHost> {
+        names::browse_mdns(MDNS_BROWSE_TIMEOUT)
+    }
+
+    fn discover_upnp(&self) -> HashMap<Ipv4Addr, UpnpInfo> {
+        upnp::discover_upnp(SSDP_SEARCH_TIMEOUT)
+    }
+
+    fn discover_roles(&self, dhcp_probe: bool) -> HashMap<Ipv4Addr, Vec<NetworkRole>> {
+        let interfaces = interfaces().unwrap_or_else(|e| {
+            log::warn!("Failed to list network interfaces: {}", e);
+            Vec::new()
+        });
+        let mut roles = roles_from_interfaces(&interfaces);
+        if dhcp_probe {
+            for server in dhcp::discover_servers(&interfaces, DHCP_PROBE_TIMEOUT) {
+                add_role(&mut roles, server, NetworkRole::DhcpServer);
+            }
+        }
+        roles
+    }
+
+    fn query_snmp(&self, ip: Ipv4Addr, community: &str) -> Option<SnmpInfo> {
+        snmp::query_system(ip, community, SNMP_TIMEOUT)
+    }
+
     fn resolve_hostname(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
         match dns_lookup::lookup_addr(&ip.into()) {
             Ok(hostname) => {
                 if hostname == ip.to_string() {
                     Ok(None)
                 } else {
-                    Ok(Some(hostname))
+                    Ok(Some(names::escape_name(hostname.as_bytes())))
                 }
             }
             Err(_e) => Ok(None),
         }
     }
 
-    fn ping(&self, ip: Ipv4Addr) -> Result<bool, GError> {
-        let raw_handle = unsafe { IcmpCreateFile() }
-            .map_err(|e| GError::Win32(0, format!("IcmpCreateFile failed: {}", e)))?;
-
-        let handle = SafeHandle::new(raw_handle)?;
-
-        let dest_ip = u32::from_le_bytes(ip.octets());
-        let request_data = b"PingPayload";
-        let request_size = request_data.len() as u16;
-
-        let reply_size = mem::size_of::<ICMP_ECHO_REPLY>() + request_size as usize + 8;
-        let mut reply_buffer = vec![0u8; reply_size];
-
-        let ret = unsafe {
-            IcmpSendEcho(
-                handle.0,
-                dest_ip,
-                request_data.as_ptr() as *const c_void,
-                request_size,
-                None,
-                reply_buffer.as_mut_ptr() as *mut c_void,
-                reply_size as u32,
-                1000,
-            )
+    /// Through [`dns::reverse_lookup`], or the system resolver on blocking
+    /// threads if no async resolver can be set up.
+    async fn resolve_hostnames(
+        &self,
+        ips: &[Ipv4Addr],
+        dns: &DnsConfig,
+    ) -> HashMap<Ipv4Addr, String> {
+        match dns::reverse_lookup(ips, dns).await {
+            Ok(names) => names,
+            Err(e) => {
+                log::warn!("{}; using the system resolver", e);
+                let lookups = ips.iter().map(|&ip| {
+                    tokio::task::spawn_blocking(move || {
+                        NetUtils::new()
+                            .resolve_hostname(ip)
+                            .ok()
+                            .flatten()
+                            .map(|name| (ip, name))
+                    })
+                });
+                futures::future::join_all(lookups)
+                    .await
+                    .into_iter()
+                    .filter_map(|found| found.ok().flatten())
+                    .collect()
+            }
+        }
+    }
+
+    /// Through [`dns::forward_lookup`].
+    async fn resolve_addresses(
+        &self,
+        names: &[String],
+        dns: &DnsConfig,
+    ) -> HashMap<String, Vec<Ipv4Addr>> {
+        dns::forward_lookup(names, dns).await.unwrap_or_else(|e| {
+            log::warn!("{}; reverse DNS names are not verified", e);
+            HashMap::new()
+        })
+    }
+
+    fn echo(&self, ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+        self.echo_timeout(ip, DEFAULT_PING_TIMEOUT)
+    }
+
+    fn echo_timeout(&self, ip: Ipv4Addr, timeout: Duration) -> Result<Option<u32>, GError> {
+        sys::echo(ip, timeout).map(|reply| reply.map(|r| r.rtt_ms))
+    }
+
+    fn trace_hop(&self, ip: Ipv4Addr, ttl: u8, timeout: Duration) -> Result<TraceHop, GError> {
+        sys::trace_hop(ip, ttl, timeout)
+    }
+
+    async fn echo_async(
+        &self,
+        ip: Ipv4Addr,
+        timeout: Duration,
+    ) -> Result<Option<EchoReply>, GError> {
+        sys::echo_async(ip, timeout).await
+    }
+
+    async fn scan_port(&self, ip: Ipv4Addr, port: u16) -> bool {
+        self.scan_port_timeout(ip, port, DEFAULT_PORT_TIMEOUT).await
+    }
+
+    async fn scan_port_timeout(&self, ip: Ipv4Addr, port: u16, timeout: Duration) -> bool {
+        let addr = format!("{}:{}", ip, port);
+        matches!(
+            tokio::time::timeout(timeout, TcpStream::connect(addr)).await,
+            Ok(Ok(_))
+        )
+    }
+
+    async fn scan_port_with_options(
+        &self,
+        ip: Ipv4Addr,
+        port: u16,
+        timeout: Duration,
+        options: TcpProbeOptions,
+    ) -> bool {
+        if options.is_default() {
+            return self.scan_port_timeout(ip, port, timeout).await;
+        }
+        let socket = match probe_socket(&options) {
+            Ok(socket) => socket,
+            Err(e) => {
+                log::warn!("Cannot set up TCP probe of {}:{}: {}", ip, port, e);
+                return false;
+            }
         };
+        let addr = SocketAddr::from((ip, port));
+        matches!(
+            tokio::time::timeout(timeout, socket.connect(addr)).await,
+            Ok(Ok(_))
+        )
+    }
 
-        Ok(ret > 0)
+    async fn grab_banner(&self, ip: Ipv4Addr, port: u16) -> Option<String> {
+        let exchange = async {
+            let mut stream = TcpStream::connect((ip, port)).await.ok()?;
+            read_banner(&mut stream, port).await
+        };
+        tokio::time::timeout(BANNER_TIMEOUT, exchange)
+            .await
+            .ok()
+            .flatten()
     }
 
-    fn scan_port(&self, ip: Ipv4Addr, port: u16) -> BoxFuture<'_, bool> {
-        Box::pin(async move {
-            let addr = format!("{}:{}", ip, port);
-            matches!(
-                tokio::time::timeout(Duration::from_millis(500), TcpStream::connect(addr)).await,
-                Ok(Ok(_))
-            )
-        })
+    async fn fetch_web_info(&self, ip: Ipv4Addr, port: u16) -> Option<WebInfo> {
+        crate::web::fetch(ip, port).await
+    }
+
+    async fn probe_smb1(&self, ip: Ipv4Addr) -> Option<bool> {
+        crate::smb::probe_smb1(ip).await
+    }
+
+    async fn smb_info(&self, ip: Ipv4Addr) -> Option<SmbInfo> {
+        crate::smb::host_info(ip).await
+    }
+
+    async fn audit_tls(&self, ip: Ipv4Addr, port: u16) -> Option<TlsInfo> {
+        crate::tls::audit(ip, port).await
+    }
+
+    async fn scan_udp_port(&self, ip: Ipv4Addr, port: u16) -> UdpPortState {
+        let socket = match UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await {
+            Ok(s) => s,
+            Err(e) => {
+                log::error!("UDP bind failed while probing {}:{}: {}", ip, port, e);
+                return UdpPortState::OpenFiltered;
+            }
+        };
+
+        // A connected UDP socket surfaces ICMP port-unreachable as
+        // WSAECONNRESET on the next receive.
+        if socket.connect((ip, port)).await.is_err()
+            || socket.send(udp_probe_payload(port)).await.is_err()
+        {
+            return UdpPortState::OpenFiltered;
+        }
+
+        let mut buf = [0u8; 1500];
+        match tokio::time::timeout(Duration::from_millis(1000), socket.recv(&mut buf)).await {
+            Ok(Ok(_)) => UdpPortState::Open,
+            Ok(Err(e))
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::ConnectionReset | std::io::ErrorKind::ConnectionRefused
+                ) =>
+            {
+                UdpPortState::Closed
+            }
+            _ => UdpPortState::OpenFiltered,
+        }
     }
 }
 
@@ -194,6 +1125,209 @@ mod tests {
         let net = NetUtils::new();
         let _ = net.resolve_mac(Ipv4Addr::new(127, 0, 0, 1));
     }
+
+    #[tokio::test]
+    async fn test_async_lookups_match_the_blocking_ones() {
+        let router = Ipv4Addr::new(192, 168, 1, 1);
+        let absent = Ipv4Addr::new(192, 168, 1, 50);
+        let timeout = Duration::from_millis(100);
+        assert!(MockNet.ping_async(router, timeout).await.unwrap());
+        assert!(!MockNet.ping_async(absent, timeout).await.unwrap());
+        assert!(
+            MockNet
+                .ping_async(Ipv4Addr::new(192, 168, 1, 2), timeout)
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            MockNet.resolve_mac_async(router).await.unwrap(),
+            MockNet.resolve_mac(router).unwrap()
+        );
+        assert_eq!(MockNet.resolve_mac_async(absent).await.unwrap(), None);
+
+        // The blocking-pool hop must not change the answer either.
+        let net = NetUtils::new();
+        let localhost = Ipv4Addr::LOCALHOST;
+        assert_eq!(
+            net.resolve_mac_async(localhost).await.is_ok(),
+            net.resolve_mac(localhost).is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_probe_options_apply_to_the_socket() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        // Borrow a free local port for the probe's source.
+        let source_port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let options = TcpProbeOptions {
+            source_port: Some(source_port),
+            ttl: Some(32),
+            disable_keepalive: true,
+        };
+        let localhost = Ipv4Addr::LOCALHOST;
+        let net = NetUtils::new();
+        let probe = net.scan_port_with_options(localhost, port, Duration::from_secs(2), options);
+        let (open, accepted) = tokio::join!(probe, listener.accept());
+        assert!(open);
+        assert_eq!(accepted.unwrap().1.port(), source_port);
+    }
+
+    #[test]
+    fn test_format_mac() {
+        assert_eq!(
+            format_mac(&[0x00, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e, 0, 0]),
+            "00:1A:2B:3C:4D:5E"
+        );
+    }
+
+    #[test]
+    fn test_udp_probe_payloads() {
+        // SNMP and DNS payloads carry their own length fields; keep them consistent.
+        let snmp = udp_probe_payload(161);
+        assert_eq!(snmp[1] as usize, snmp.len() - 2);
+        assert_eq!(udp_probe_payload(123).len(), 48);
+        assert_eq!(udp_probe_payload(137).len(), 50);
+        assert!(udp_probe_payload(9999).is_empty());
+        assert_eq!(udp_probe_bytes(123), 76);
+        assert_eq!(udp_probe_bytes(9999), 28);
+        assert_eq!(ECHO_REQUEST_BYTES, 39);
+    }
+
+    fn iface(address: [u8; 4], prefix_len: u8, gateway: bool, is_up: bool) -> InterfaceInfo {
+        InterfaceInfo {
+            name: "test".to_string(),
+            address: Ipv4Addr::from(address),
+            prefix_len,
+            gateway: gateway.then(|| Ipv4Addr::new(10, 0, 0, 1)),
+            is_up,
+            dns_servers: Vec::new(),
+            dhcp_server: None,
+        }
+    }
+
+    #[test]
+    fn test_interface_scan_range() {
+        let home = iface([192, 168, 1, 23], 24, true, true);
+        assert_eq!(home.netmask(), Ipv4Addr::new(255, 255, 255, 0));
+        assert_eq!(home.range_string(), "192.168.1.1-192.168.1.254");
+
+        // A /16 is narrowed to the /24 around the address.
+        let corp = iface([172, 16, 5, 9], 16, true, true);
+        assert_eq!(
+            corp.scan_range(),
+            (Ipv4Addr::new(172, 16, 5, 1), Ipv4Addr::new(172, 16, 5, 254))
+        );
+
+        let small = iface([10, 1, 1, 6], 30, true, true);
+        assert_eq!(
+            small.scan_range(),
+            (Ipv4Addr::new(10, 1, 1, 5), Ipv4Addr::new(10, 1, 1, 6))
+        );
+        let host = iface([10, 1, 1, 6], 32, false, true);
+        assert_eq!(host.scan_range(), (host.address, host.address));
+    }
+
+    #[test]
+    fn test_pick_active_interface() {
+        let list = vec![
+            iface([169, 254, 3, 4], 16, false, true),
+            iface([10, 0, 0, 50], 24, true, false),
+            iface([192, 168, 1, 23], 24, true, true),
+        ];
+        assert_eq!(
+            pick_active_interface(&list).map(|i| i.address),
+            Some(Ipv4Addr::new(192, 168, 1, 23))
+        );
+        assert_eq!(
+            pick_active_interface(&list[..2]).map(|i| i.address),
+            Some(Ipv4Addr::new(169, 254, 3, 4))
+        );
+        assert_eq!(pick_active_interface(&list[1..2]), None);
+    }
+
+    #[test]
+    fn test_candidate_interfaces_put_gateways_first() {
+        let list = vec![
+            iface([169, 254, 3, 4], 16, false, true),
+            iface([10, 0, 5, 2], 24, false, true),
+            iface([10, 0, 0, 50], 24, true, false),
+            iface([192, 168, 1, 23], 24, true, true),
+            iface([192, 168, 1, 24], 24, true, true),
+        ];
+        let addresses: Vec<Ipv4Addr> = candidate_interfaces(&list)
+            .iter()
+            .map(|i| i.address)
+            .collect();
+        assert_eq!(
+            addresses,
+            [Ipv4Addr::new(192, 168, 1, 23), Ipv4Addr::new(10, 0, 5, 2)]
+        );
+    }
+
+    #[test]
+    fn test_roles_from_interfaces_merge_per_address() {
+        let router = Ipv4Addr::new(10, 0, 0, 1);
+        let resolver = Ipv4Addr::new(10, 0, 0, 53);
+        let wired = InterfaceInfo {
+            dns_servers: vec![router, resolver],
+            dhcp_server: Some(router),
+            ..iface([10, 0, 0, 20], 24, true, true)
+        };
+        let down = InterfaceInfo {
+            dns_servers: vec![Ipv4Addr::new(172, 16, 0, 1)],
+            ..iface([172, 16, 0, 20], 24, false, false)
+        };
+        let roles = roles_from_interfaces(&[wired.clone(), wired, down]);
+        assert_eq!(roles.len(), 2);
+        assert_eq!(
+            roles[&router],
+            [
+                NetworkRole::Gateway,
+                NetworkRole::DhcpServer,
+                NetworkRole::DnsServer
+            ]
+        );
+        assert_eq!(roles[&resolver], [NetworkRole::DnsServer]);
+    }
+
+    #[test]
+    fn test_missing_oui_database_is_rejected() {
+        let path = std::env::temp_dir().join("ragescanner-missing-oui.csv");
+        let err = set_oui_database(Some(path)).unwrap_err();
+        assert!(
+            matches!(&err, GError::Internal(msg) if msg.contains("ragescanner-missing-oui.csv"))
+        );
+    }
+
+    #[test]
+    fn test_parse_banner() {
+        assert_eq!(
+            parse_banner(b"SSH-2.0-OpenSSH_9.6\r\n").as_deref(),
+            Some("SSH-2.0-OpenSSH_9.6")
+        );
+        assert_eq!(
+            parse_banner(b"220 (vsFTPd 3.0.5)\r\n").as_deref(),
+            Some("(vsFTPd 3.0.5)")
+        );
+        assert_eq!(
+            parse_banner(b"220-mail.example.com ESMTP Postfix\r\n").as_deref(),
+            Some("mail.example.com ESMTP Postfix")
+        );
+        assert_eq!(
+            parse_banner(b"HTTP/1.1 200 OK\r\nserver: nginx/1.24.0\r\n\r\n").as_deref(),
+            Some("nginx/1.24.0")
+        );
+        assert_eq!(
+            parse_banner(b"HTTP/1.0 404 Not Found\r\n\r\n").as_deref(),
+            Some("HTTP/1.0 404 Not Found")
+        );
+        assert_eq!(parse_banner(b"\r\n"), None);
+    }
 }
 
 /// Mock implementation of [`NetworkProvider`] for deterministic testing.
@@ -203,25 +1337,49 @@ mod tests {
 pub struct MockNet;
 
 #[cfg(any(test, feature = "test-support"))]
+#[async_trait]
 impl NetworkProvider for MockNet {
-    fn ping(&self, ip: Ipv4Addr) -> Result<bool, GError> {
+    fn echo(&self, ip: Ipv4Addr) -> Result<Option<u32>, GError> {
         if ip == Ipv4Addr::new(192, 168, 1, 1) {
-            Ok(true)
+            Ok(Some(2))
         } else if ip == Ipv4Addr::new(192, 168, 1, 2) {
             Err(GError::Internal("Simulated Failure".to_string()))
         } else {
-            Ok(false)
+            Ok(None)
         }
     }
 
+    /// Replies carry TTL 128, as from a Windows host one hop away.
+    async fn echo_async(
+        &self,
+        ip: Ipv4Addr,
+        _timeout: Duration,
+    ) -> Result<Option<EchoReply>, GError> {
+        let rtt = self.echo(ip)?;
+        Ok(rtt.map(|rtt_ms| EchoReply {
+            rtt_ms,
+            ttl: Some(128),
+        }))
+    }
+
     fn resolve_mac(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
         if ip == Ipv4Addr::new(192, 168, 1, 1) {
             Ok(Some("00:11:22:33:44:55".to_string()))
+        } else if ip == Ipv4Addr::new(192, 168, 1, 2) {
+            Err(GError::Internal("Simulated Failure".to_string()))
         } else {
             Ok(None)
         }
     }
 
+    fn read_arp_table(&self) -> Result<HashMap<Ipv4Addr, String>, GError> {
+        // .3 only answers from the cache: ping and SendARP both miss it.
+        Ok(HashMap::from([(
+            Ipv4Addr::new(192, 168, 1, 3),
+            "AA:BB:CC:DD:EE:FF".to_string(),
+        )]))
+    }
+
     fn resolve_hostname(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
         if ip == Ipv4Addr::new(192, 168, 1, 1) {
             Ok(Some("mock-host".to_string()))
@@ -230,11 +1388,44 @@ impl NetworkProvider for MockNet {
         }
     }
 
+    async fn resolve_addresses(
+        &self,
+        names: &[String],
+        _dns: &DnsConfig,
+    ) -> HashMap<String, Vec<Ipv4Addr>> {
+        names
+            .iter()
+            .filter(|name| *name == "mock-host")
+            .map(|name| (name.clone(), vec![Ipv4Addr::new(192, 168, 1, 1)]))
+            .collect()
+    }
+
     fn resolve_vendor(&self, _mac: &str) -> Option<String> {
         Some("Mock Vendor".to_string())
     }
 
-    fn scan_port(&self, _ip: Ipv4Addr, port: u16) -> BoxFuture<'_, bool> {
-        Box::pin(async move { port == 80 })
+    async fn scan_port(&self, _ip: Ipv4Addr, port: u16) -> bool {
+        port == 80
+    }
+
+    async fn scan_udp_port(&self, _ip: Ipv4Addr, port: u16) -> UdpPortState {
+        match port {
+            53 => UdpPortState::Open,
+            161 => UdpPortState::OpenFiltered,
+            _ => UdpPortState::Closed,
+        }
+    }
+
+    async fn grab_banner(&self, _ip: Ipv4Addr, port: u16) -> Option<String> {
+        (port == 80).then(|| "mock-httpd/1.0".to_string())
+    }
+
+    async fn fetch_web_info(&self, _ip: Ipv4Addr, port: u16) -> Option<WebInfo> {
+        (port == 80).then(|| WebInfo {
+            port,
+            status: 200,
+            title: Some("Mock Router".to_string()),
+            server: Some("mock-httpd/1.0".to_string()),
+        })
     }
 }
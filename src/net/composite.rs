@@ -0,0 +1,442 @@
+//! A [`NetworkProvider`] assembled from several backends.
+//!
+//! [`CompositeProvider`] sends each group of operations ([`Operation`]) to
+//! the backend configured for it and everything else to a default backend,
+//! so e.g. a pcap-based ARP resolver or an async DNS client can replace one
+//! part of [`NetUtils`] without reimplementing the rest.
+
+use super::{Capabilities, EchoReply, InterfaceWatch, NetUtils, NetworkProvider, PowerWatch};
+use crate::names::MdnsHost;
+use crate::types::{
+    DnsConfig, GError, NameSource, NetworkRole, SmbInfo, SnmpInfo, TcpProbeOptions, TlsInfo,
+    TraceHop, UdpPortState, UpnpInfo, WebInfo,
+};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Name of the built-in [`NetUtils`] backend in a [`ProviderConfig`].
+pub const SYSTEM_BACKEND: &str = "system";
+
+/// Groups of [`NetworkProvider`] methods that are routed together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
+    /// Echoes, pings and traceroute hops.
+    Icmp,
+    /// MAC lookups and the neighbor cache.
+    Arp,
+    /// Reverse DNS, local name lookups and mDNS browsing.
+    Dns,
+    /// TCP and UDP port probes.
+    Ports,
+    /// Service probes of open ports: banners, web pages, SMB, TLS, SNMP and
+    /// SSDP discovery.
+    Services,
+}
+
+impl Operation {
+    /// Every group, in declaration order.
+    pub const ALL: [Operation; 5] = [
+        Operation::Icmp,
+        Operation::Arp,
+        Operation::Dns,
+        Operation::Ports,
+        Operation::Services,
+    ];
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Operation::Icmp => "icmp",
+            Operation::Arp => "arp",
+            Operation::Dns => "dns",
+            Operation::Ports => "ports",
+            Operation::Services => "services",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Which named backend handles each [`Operation`].
+///
+/// ```toml
+/// default = "system"
+/// arp = "pcap"
+/// dns = "hickory"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProviderConfig {
+    /// Backend of operations without a route of their own, and of vendor
+    /// lookups, interface and power watching. Default: [`SYSTEM_BACKEND`].
+    pub default: String,
+    /// Backend per operation group; missing groups use `default`.
+    #[serde(flatten)]
+    pub routes: HashMap<Operation, String>,
+}
+
+impl Default for ProviderConfig {
+    fn default() -> Self {
+        Self {
+            default: SYSTEM_BACKEND.to_string(),
+            routes: HashMap::new(),
+        }
+    }
+}
+
+/// Routes each [`Operation`] to its own [`NetworkProvider`].
+///
+/// ```
+/// use ragescanner::net::{CompositeProvider, NetUtils, Operation};
+/// use std::sync::Arc;
+///
+/// let system = Arc::new(NetUtils::new());
+/// let net = CompositeProvider::builder(system.clone())
+///     .route(Operation::Arp, system)
+///     .build();
+/// # let _ = net;
+/// ```
+#[derive(Clone)]
+pub struct CompositeProvider {
+    default: Arc<dyn NetworkProvider>,
+    routes: HashMap<Operation, Arc<dyn NetworkProvider>>,
+}
+
+/// Assembles a [`CompositeProvider`]; see [`CompositeProvider::builder`].
+pub struct CompositeProviderBuilder {
+    provider: CompositeProvider,
+}
+
+impl CompositeProviderBuilder {
+    /// Sends `operation` to `backend` instead of the default backend.
+    pub fn route(mut self, operation: Operation, backend: Arc<dyn NetworkProvider>) -> Self {
+        self.provider.routes.insert(operation, backend);
+        self
+    }
+
+    /// Finishes the provider.
+    pub fn build(self) -> CompositeProvider {
+        self.provider
+    }
+}
+
+impl CompositeProvider {
+    /// Starts a provider that sends every operation to `default`.
+    pub fn builder(default: Arc<dyn NetworkProvider>) -> CompositeProviderBuilder {
+        CompositeProviderBuilder {
+            provider: Self {
+                default,
+                routes: HashMap::new(),
+            },
+        }
+    }
+
+    /// Builds the provider `config` describes from the named `backends`.
+    /// [`SYSTEM_BACKEND`] is [`NetUtils`] unless `backends` names another.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if `config` names a backend that is not
+    /// in `backends`.
+    pub fn from_config(
+        config: &ProviderConfig,
+        backends: &HashMap<String, Arc<dyn NetworkProvider>>,
+    ) -> Result<Self, GError> {
+        let lookup = |name: &str, role: &str| -> Result<Arc<dyn NetworkProvider>, GError> {
+            match backends.get(name) {
+                Some(backend) => Ok(backend.clone()),
+                None if name == SYSTEM_BACKEND => Ok(Arc::new(NetUtils::new())),
+                None => Err(GError::Internal(format!(
+                    "Unknown network backend '{}' for {}",
+                    name, role
+                ))),
+            }
+        };
+        let mut builder = Self::builder(lookup(&config.default, "default")?);
+        for operation in Operation::ALL {
+            if let Some(name) = config.routes.get(&operation) {
+                builder = builder.route(operation, lookup(name, &operation.to_string())?);
+            }
+        }
+        Ok(builder.build())
+    }
+
+    /// The backend handling `operation`.
+    pub fn backend(&self, operation: Operation) -> &dyn NetworkProvider {
+        self.routes
+            .get(&operation)
+            .unwrap_or(&self.default)
+            .as_ref()
+    }
+}
+
+#[async_trait]
+impl NetworkProvider for CompositeProvider {
+    fn ping(&self, ip: Ipv4Addr) -> Result<bool, GError> {
+        self.backend(Operation::Icmp).ping(ip)
+    }
+
+    async fn ping_async(&self, ip: Ipv4Addr, timeout: Duration) -> Result<bool, GError> {
+        self.backend(Operation::Icmp).ping_async(ip, timeout).await
+    }
+
+    fn echo(&self, ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+        self.backend(Operation::Icmp).echo(ip)
+    }
+
+    fn echo_timeout(&self, ip: Ipv4Addr, timeout: Duration) -> Result<Option<u32>, GError> {
+        self.backend(Operation::Icmp).echo_timeout(ip, timeout)
+    }
+
+    async fn echo_async(
+        &self,
+        ip: Ipv4Addr,
+        timeout: Duration,
+    ) -> Result<Option<EchoReply>, GError> {
+        self.backend(Operation::Icmp).echo_async(ip, timeout).await
+    }
+
+    fn trace_hop(&self, ip: Ipv4Addr, ttl: u8, timeout: Duration) -> Result<TraceHop, GError> {
+        self.backend(Operation::Icmp).trace_hop(ip, ttl, timeout)
+    }
+
+    fn resolve_mac(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+        self.backend(Operation::Arp).resolve_mac(ip)
+    }
+
+    async fn resolve_mac_async(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+        self.backend(Operation::Arp).resolve_mac_async(ip).await
+    }
+
+    fn read_arp_table(&self) -> Result<HashMap<Ipv4Addr, String>, GError> {
+        self.backend(Operation::Arp).read_arp_table()
+    }
+
+    fn resolve_hostname(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+        self.backend(Operation::Dns).resolve_hostname(ip)
+    }
+
+    async fn resolve_hostnames(
+        &self,
+        ips: &[Ipv4Addr],
+        dns: &DnsConfig,
+    ) -> HashMap<Ipv4Addr, String> {
+        self.backend(Operation::Dns)
+            .resolve_hostnames(ips, dns)
+            .await
+    }
+
+    async fn resolve_addresses(
+        &self,
+        names: &[String],
+        dns: &DnsConfig,
+    ) -> HashMap<String, Vec<Ipv4Addr>> {
+        self.backend(Operation::Dns)
+            .resolve_addresses(names, dns)
+            .await
+    }
+
+    fn resolve_local_name(&self, ip: Ipv4Addr) -> Option<(String, NameSource)> {
+        self.backend(Operation::Dns).resolve_local_name(ip)
+    }
+
+    fn discover_mdns(&self) -> HashMap<Ipv4Addr, MdnsHost> {
+        self.backend(Operation::Dns).discover_mdns()
+    }
+
+    async fn scan_port(&self, ip: Ipv4Addr, port: u16) -> bool {
+        self.backend(Operation::Ports).scan_port(ip, port).await
+    }
+
+    async fn scan_port_timeout(&self, ip: Ipv4Addr, port: u16, timeout: Duration) -> bool {
+        self.backend(Operation::Ports)
+            .scan_port_timeout(ip, port, timeout)
+            .await
+    }
+
+    async fn scan_port_with_options(
+        &self,
+        ip: Ipv4Addr,
+        port: u16,
+        timeout: Duration,
+        options: TcpProbeOptions,
+    ) -> bool {
+        self.backend(Operation::Ports)
+            .scan_port_with_options(ip, port, timeout, options)
+            .await
+    }
+
+    async fn scan_udp_port(&self, ip: Ipv4Addr, port: u16) -> UdpPortState {
+        self.backend(Operation::Ports).scan_udp_port(ip, port).await
+    }
+
+    fn discover_upnp(&self) -> HashMap<Ipv4Addr, UpnpInfo> {
+        self.backend(Operation::Services).discover_upnp()
+    }
+
+    fn discover_roles(&self, dhcp_probe: bool) -> HashMap<Ipv4Addr, Vec<NetworkRole>> {
+        self.backend(Operation::Services).discover_roles(dhcp_probe)
+    }
+
+    fn query_snmp(&self, ip: Ipv4Addr, community: &str) -> Option<SnmpInfo> {
+        self.backend(Operation::Services).query_snmp(ip, community)
+    }
+
+    async fn grab_banner(&self, ip: Ipv4Addr, port: u16) -> Option<String> {
+        self.backend(Operation::Services)
+            .grab_banner(ip, port)
+            .await
+    }
+
+    async fn probe_smb1(&self, ip: Ipv4Addr) -> Option<bool> {
+        self.backend(Operation::Services).probe_smb1(ip).await
+    }
+
+    async fn smb_info(&self, ip: Ipv4Addr) -> Option<SmbInfo> {
+        self.backend(Operation::Services).smb_info(ip).await
+    }
+
+    async fn audit_tls(&self, ip: Ipv4Addr, port: u16) -> Option<TlsInfo> {
+        self.backend(Operation::Services).audit_tls(ip, port).await
+    }
+
+    async fn fetch_web_info(&self, ip: Ipv4Addr, port: u16) -> Option<WebInfo> {
+        self.backend(Operation::Services)
+            .fetch_web_info(ip, port)
+            .await
+    }
+
+    fn resolve_vendor(&self, mac: &str) -> Option<String> {
+        self.default.resolve_vendor(mac)
+    }
+
+    fn watch_interfaces(&self) -> Result<InterfaceWatch, GError> {
+        self.default.watch_interfaces()
+    }
+
+    fn watch_power(&self) -> Result<PowerWatch, GError> {
+        self.default.watch_power()
+    }
+
+    fn route_available(&self, ip: Ipv4Addr) -> bool {
+        self.default.route_available(ip)
+    }
+
+    /// ARP, ICMP and UDP as reported by the backends of [`Operation::Arp`],
+    /// [`Operation::Icmp`] and [`Operation::Ports`]; the rest from the default.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            arp: self.backend(Operation::Arp).capabilities().arp,
+            icmp: self.backend(Operation::Icmp).capabilities().icmp,
+            udp: self.backend(Operation::Ports).capabilities().udp,
+            ..self.default.capabilities()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::MockNet;
+
+    /// Resolves every address to one MAC, and can do nothing else.
+    struct FixedArp;
+
+    #[async_trait]
+    impl NetworkProvider for FixedArp {
+        fn echo(&self, _ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+            Ok(None)
+        }
+
+        fn resolve_mac(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            Ok(Some("02:00:00:00:00:01".to_string()))
+        }
+
+        fn resolve_hostname(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            Ok(None)
+        }
+
+        fn resolve_vendor(&self, _mac: &str) -> Option<String> {
+            None
+        }
+
+        async fn scan_port(&self, _ip: Ipv4Addr, _port: u16) -> bool {
+            false
+        }
+
+        async fn scan_udp_port(&self, _ip: Ipv4Addr, _port: u16) -> UdpPortState {
+            UdpPortState::Closed
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities {
+                icmp: false,
+                udp: false,
+                ..Capabilities::default()
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_operations_go_to_their_backend() {
+        let net = CompositeProvider::builder(Arc::new(MockNet))
+            .route(Operation::Arp, Arc::new(FixedArp))
+            .build();
+        let ip = Ipv4Addr::new(192, 168, 1, 9);
+
+        assert_eq!(
+            net.resolve_mac_async(ip).await.unwrap().as_deref(),
+            Some("02:00:00:00:00:01")
+        );
+        assert!(
+            net.ping_async(Ipv4Addr::new(192, 168, 1, 1), Duration::from_secs(1))
+                .await
+                .unwrap()
+        );
+        assert!(net.scan_port(ip, 80).await);
+        assert_eq!(
+            net.resolve_vendor("02:00:00:00:00:01").as_deref(),
+            Some("Mock Vendor")
+        );
+        // ICMP and UDP stay with MockNet, which has both.
+        assert_eq!(net.capabilities(), Capabilities::default());
+    }
+
+    #[test]
+    fn test_config_names_backends() {
+        let config: ProviderConfig =
+            serde_json::from_str(r#"{"default": "mock", "arp": "fixed"}"#).unwrap();
+        assert_eq!(
+            config.routes,
+            HashMap::from([(Operation::Arp, "fixed".to_string())])
+        );
+
+        let mut backends: HashMap<String, Arc<dyn NetworkProvider>> = HashMap::from([(
+            "mock".to_string(),
+            Arc::new(MockNet) as Arc<dyn NetworkProvider>,
+        )]);
+        let err = CompositeProvider::from_config(&config, &backends)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Internal Error: Unknown network backend 'fixed' for arp"
+        );
+
+        backends.insert("fixed".to_string(), Arc::new(FixedArp));
+        let net = CompositeProvider::from_config(&config, &backends).unwrap();
+        let ip = Ipv4Addr::new(10, 0, 0, 1);
+        assert_eq!(
+            net.resolve_mac(ip).unwrap().as_deref(),
+            Some("02:00:00:00:00:01")
+        );
+        assert_eq!(net.resolve_vendor("x").as_deref(), Some("Mock Vendor"));
+        assert!(ProviderConfig::default().routes.is_empty());
+    }
+}
@@ -0,0 +1,299 @@
+//! A cap on TCP connections open at once to any one host.
+//!
+//! [`HostLimitProvider`] wraps another provider and makes every TCP port
+//! probe, banner grab and service probe (SMB, TLS, web) wait for one of the
+//! host's connection slots, so a fragile device (an old printer, a PLC) sees
+//! at most `limit` connections however many hosts and ports are probed in
+//! parallel. Providers with the same limit share the slots of each host, so
+//! a range scan and a deep scan of the same device count together.
+
+use super::{Capabilities, EchoReply, InterfaceWatch, NetworkProvider, PowerWatch};
+use crate::names::MdnsHost;
+use crate::types::{
+    DnsConfig, GError, NameSource, NetworkRole, SmbInfo, SnmpInfo, TcpProbeOptions, TlsInfo,
+    TraceHop, UdpPortState, UpnpInfo, WebInfo,
+};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Slots of every host with a connection in flight, by host and limit.
+static HOST_SLOTS: Mutex<HostSlots> = Mutex::new(HostSlots {
+    slots: None,
+    prune_at: 0,
+});
+
+struct HostSlots {
+    slots: Option<HashMap<(Ipv4Addr, usize), Weak<Semaphore>>>,
+    /// Entry count at which slots of idle hosts are dropped.
+    prune_at: usize,
+}
+
+/// Hosts tracked before idle ones are dropped from [`HOST_SLOTS`].
+const MIN_PRUNE_AT: usize = 1024;
+
+/// Waits for one of the `limit` connection slots of `ip`; the slot is freed
+/// when the permit drops.
+async fn acquire_slot(ip: Ipv4Addr, limit: usize) -> Option<OwnedSemaphorePermit> {
+    let semaphore = {
+        let mut hosts = HOST_SLOTS.lock().ok()?;
+        let prune_at = hosts.prune_at;
+        let slots = hosts.slots.get_or_insert_with(HashMap::new);
+        let existing = slots.get(&(ip, limit)).and_then(Weak::upgrade);
+        match existing {
+            Some(semaphore) => semaphore,
+            None => {
+                let semaphore = Arc::new(Semaphore::new(limit));
+                slots.insert((ip, limit), Arc::downgrade(&semaphore));
+                if slots.len() >= prune_at {
+                    slots.retain(|_, slot| slot.strong_count() > 0);
+                    hosts.prune_at = (slots.len() * 2).max(MIN_PRUNE_AT);
+                }
+                semaphore
+            }
+        }
+    };
+    semaphore.acquire_owned().await.ok()
+}
+
+/// Passes everything to the wrapped provider, holding a connection slot of
+/// the target host for each TCP connection.
+pub struct HostLimitProvider {
+    inner: Arc<dyn NetworkProvider>,
+    limit: usize,
+}
+
+impl HostLimitProvider {
+    /// Wraps `inner`, allowing `limit` connections per host (minimum 1).
+    pub fn new(inner: Arc<dyn NetworkProvider>, limit: usize) -> Self {
+        Self {
+            inner,
+            limit: limit.max(1),
+        }
+    }
+
+    async fn slot(&self, ip: Ipv4Addr) -> Option<OwnedSemaphorePermit> {
+        acquire_slot(ip, self.limit).await
+    }
+}
+
+#[async_trait]
+impl NetworkProvider for HostLimitProvider {
+    fn ping(&self, ip: Ipv4Addr) -> Result<bool, GError> {
+        self.inner.ping(ip)
+    }
+
+    async fn ping_async(&self, ip: Ipv4Addr, timeout: Duration) -> Result<bool, GError> {
+        self.inner.ping_async(ip, timeout).await
+    }
+
+    fn echo(&self, ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+        self.inner.echo(ip)
+    }
+
+    fn echo_timeout(&self, ip: Ipv4Addr, timeout: Duration) -> Result<Option<u32>, GError> {
+        self.inner.echo_timeout(ip, timeout)
+    }
+
+    async fn echo_async(
+        &self,
+        ip: Ipv4Addr,
+        timeout: Duration,
+    ) -> Result<Option<EchoReply>, GError> {
+        self.inner.echo_async(ip, timeout).await
+    }
+
+    fn trace_hop(&self, ip: Ipv4Addr, ttl: u8, timeout: Duration) -> Result<TraceHop, GError> {
+        self.inner.trace_hop(ip, ttl, timeout)
+    }
+
+    fn resolve_mac(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+        self.inner.resolve_mac(ip)
+    }
+
+    async fn resolve_mac_async(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+        self.inner.resolve_mac_async(ip).await
+    }
+
+    fn read_arp_table(&self) -> Result<HashMap<Ipv4Addr, String>, GError> {
+        self.inner.read_arp_table()
+    }
+
+    fn resolve_hostname(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+        self.inner.resolve_hostname(ip)
+    }
+
+    async fn resolve_hostnames(
+        &self,
+        ips: &[Ipv4Addr],
+        dns: &DnsConfig,
+    ) -> HashMap<Ipv4Addr, String> {
+        self.inner.resolve_hostnames(ips, dns).await
+    }
+
+    async fn resolve_addresses(
+        &self,
+        names: &[String],
+        dns: &DnsConfig,
+    ) -> HashMap<String, Vec<Ipv4Addr>> {
+        self.inner.resolve_addresses(names, dns).await
+    }
+
+    fn resolve_local_name(&self, ip: Ipv4Addr) -> Option<(String, NameSource)> {
+        self.inner.resolve_local_name(ip)
+    }
+
+    fn discover_mdns(&self) -> HashMap<Ipv4Addr, MdnsHost> {
+        self.inner.discover_mdns()
+    }
+
+    async fn scan_port(&self, ip: Ipv4Addr, port: u16) -> bool {
+        let _slot = self.slot(ip).await;
+        self.inner.scan_port(ip, port).await
+    }
+
+    /// `timeout` starts once a slot is free.
+    async fn scan_port_timeout(&self, ip: Ipv4Addr, port: u16, timeout: Duration) -> bool {
+        let _slot = self.slot(ip).await;
+        self.inner.scan_port_timeout(ip, port, timeout).await
+    }
+
+    /// `timeout` starts once a slot is free.
+    async fn scan_port_with_options(
+        &self,
+        ip: Ipv4Addr,
+        port: u16,
+        timeout: Duration,
+        options: TcpProbeOptions,
+    ) -> bool {
+        let _slot = self.slot(ip).await;
+        self.inner
+            .scan_port_with_options(ip, port, timeout, options)
+            .await
+    }
+
+    /// Not limited: UDP probes open no connection.
+    async fn scan_udp_port(&self, ip: Ipv4Addr, port: u16) -> UdpPortState {
+        self.inner.scan_udp_port(ip, port).await
+    }
+
+    fn discover_upnp(&self) -> HashMap<Ipv4Addr, UpnpInfo> {
+        self.inner.discover_upnp()
+    }
+
+    fn discover_roles(&self, dhcp_probe: bool) -> HashMap<Ipv4Addr, Vec<NetworkRole>> {
+        self.inner.discover_roles(dhcp_probe)
+    }
+
+    fn query_snmp(&self, ip: Ipv4Addr, community: &str) -> Option<SnmpInfo> {
+        self.inner.query_snmp(ip, community)
+    }
+
+    async fn grab_banner(&self, ip: Ipv4Addr, port: u16) -> Option<String> {
+        let _slot = self.slot(ip).await;
+        self.inner.grab_banner(ip, port).await
+    }
+
+    async fn probe_smb1(&self, ip: Ipv4Addr) -> Option<bool> {
+        let _slot = self.slot(ip).await;
+        self.inner.probe_smb1(ip).await
+    }
+
+    async fn smb_info(&self, ip: Ipv4Addr) -> Option<SmbInfo> {
+        let _slot = self.slot(ip).await;
+        self.inner.smb_info(ip).await
+    }
+
+    async fn audit_tls(&self, ip: Ipv4Addr, port: u16) -> Option<TlsInfo> {
+        let _slot = self.slot(ip).await;
+        self.inner.audit_tls(ip, port).await
+    }
+
+    async fn fetch_web_info(&self, ip: Ipv4Addr, port: u16) -> Option<WebInfo> {
+        let _slot = self.slot(ip).await;
+        self.inner.fetch_web_info(ip, port).await
+    }
+
+    fn resolve_vendor(&self, mac: &str) -> Option<String> {
+        self.inner.resolve_vendor(mac)
+    }
+
+    fn watch_interfaces(&self) -> Result<InterfaceWatch, GError> {
+        self.inner.watch_interfaces()
+    }
+
+    fn watch_power(&self) -> Result<PowerWatch, GError> {
+        self.inner.watch_power()
+    }
+
+    fn route_available(&self, ip: Ipv4Addr) -> bool {
+        self.inner.route_available(ip)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.inner.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts the connections open at once, each held for a few milliseconds.
+    #[derive(Default)]
+    struct CountingNet {
+        open: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl NetworkProvider for CountingNet {
+        fn echo(&self, _ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+            Ok(Some(1))
+        }
+        fn resolve_mac(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            Ok(None)
+        }
+        fn resolve_hostname(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            Ok(None)
+        }
+        fn resolve_vendor(&self, _mac: &str) -> Option<String> {
+            None
+        }
+        async fn scan_port(&self, _ip: Ipv4Addr, _port: u16) -> bool {
+            let open = self.open.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(open, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            self.open.fetch_sub(1, Ordering::SeqCst);
+            true
+        }
+        async fn scan_udp_port(&self, _ip: Ipv4Addr, _port: u16) -> UdpPortState {
+            UdpPortState::Closed
+        }
+    }
+
+    #[tokio::test]
+    async fn test_providers_with_the_same_limit_share_a_hosts_slots() {
+        let net = Arc::new(CountingNet::default());
+        let first = HostLimitProvider::new(net.clone(), 2);
+        let second = HostLimitProvider::new(net.clone(), 2);
+        let ip = Ipv4Addr::new(10, 99, 0, 1);
+        let probes = (1..=8u16).map(|port| {
+            let provider = if port % 2 == 0 { &first } else { &second };
+            provider.scan_port(ip, port)
+        });
+        let open = futures::future::join_all(probes).await;
+        assert!(open.into_iter().all(|open| open));
+        assert_eq!(net.peak.load(Ordering::SeqCst), 2);
+
+        // Other hosts have slots of their own.
+        net.peak.store(0, Ordering::SeqCst);
+        let probes = (1..=4u8).map(|host| first.scan_port(Ipv4Addr::new(10, 99, 1, host), 80));
+        futures::future::join_all(probes).await;
+        assert_eq!(net.peak.load(Ordering::SeqCst), 4);
+    }
+}
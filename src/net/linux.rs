@@ -0,0 +1,121 @@
+//! Linux tables and tools for the [`unix`](super::unix) backend.
+
+use super::unix::parse_mac;
+use crate::types::GError;
+use std::collections::HashMap;
+use std::fs;
+use std::net::Ipv4Addr;
+use std::process::Command;
+use std::time::Duration;
+
+/// The kernel's IPv4 neighbor cache.
+const ARP_TABLE_PATH: &str = "/proc/net/arp";
+/// The kernel's IPv4 routing table.
+const ROUTE_TABLE_PATH: &str = "/proc/net/route";
+/// `ATF_COM`: the neighbor entry is resolved.
+const ATF_COM: u32 = 0x02;
+/// `RTF_GATEWAY`: the route goes through a gateway.
+const RTF_GATEWAY: u32 = 0x02;
+
+/// Parses `/proc/net/arp`:
+///
+/// ```text
+/// IP address       HW type     Flags       HW address            Mask     Device
+/// 192.168.1.1      0x1         0x2         00:11:22:33:44:55     *        wlan0
+/// ```
+fn parse_arp_table(text: &str) -> HashMap<Ipv4Addr, String> {
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [ip, _, flags, mac, ..] = fields.as_slice() else {
+                return None;
+            };
+            let flags = u32::from_str_radix(flags.trim_start_matches("0x"), 16).ok()?;
+            if flags & ATF_COM == 0 {
+                return None;
+            }
+            Some((ip.parse().ok()?, parse_mac(mac)?))
+        })
+        .collect()
+}
+
+pub fn read_arp_table() -> Result<HashMap<Ipv4Addr, String>, GError> {
+    fs::read_to_string(ARP_TABLE_PATH)
+        .map(|text| parse_arp_table(&text))
+        .map_err(|e| GError::from_io(&e, ARP_TABLE_PATH))
+}
+
+pub fn lookup_arp(ip: Ipv4Addr) -> Result<Option<String>, GError> {
+    Ok(read_arp_table()?.remove(&ip))
+}
+
+/// Parses the default routes out of `/proc/net/route`, where addresses are
+/// little-endian hex: gateway `0101A8C0` is `192.168.1.1`.
+fn parse_default_gateways(text: &str) -> HashMap<String, Ipv4Addr> {
+    let mut gateways = HashMap::new();
+    for line in text.lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [name, destination, gateway, flags, ..] = fields.as_slice() else {
+            continue;
+        };
+        let hex = |field: &str| u32::from_str_radix(field, 16).ok();
+        let (Some(0), Some(gateway), Some(flags)) = (hex(destination), hex(gateway), hex(flags))
+        else {
+            continue;
+        };
+        if flags & RTF_GATEWAY != 0 {
+            // The first default route of an interface is the preferred one.
+            gateways
+                .entry(name.to_string())
+                .or_insert(Ipv4Addr::from(gateway.to_le_bytes()));
+        }
+    }
+    gateways
+}
+
+pub fn default_gateways() -> HashMap<String, Ipv4Addr> {
+    fs::read_to_string(ROUTE_TABLE_PATH)
+        .map(|text| parse_default_gateways(&text))
+        .unwrap_or_default()
+}
+
+/// `ping` (iputils) sending one echo: `-W` takes whole seconds, `-t` sets the TTL.
+pub fn ping_command(ip: Ipv4Addr, timeout: Duration, ttl: Option<u8>) -> Command {
+    let mut command = Command::new("ping");
+    command
+        .args(["-n", "-c", "1", "-W"])
+        .arg(timeout.as_secs_f64().ceil().max(1.0).to_string());
+    if let Some(ttl) = ttl {
+        command.arg("-t").arg(ttl.to_string());
+    }
+    command.arg(ip.to_string());
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proc_tables() {
+        let arp = "IP address       HW type     Flags       HW address            Mask     Device\n\
+                   192.168.1.1      0x1         0x2         00:11:22:33:44:55     *        wlan0\n\
+                   192.168.1.7      0x1         0x0         00:00:00:00:00:00     *        wlan0\n\
+                   192.168.1.9      0x1         0x6         aa:bb:cc:dd:ee:0f     *        wlan0\n";
+        let table = parse_arp_table(arp);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table[&Ipv4Addr::new(192, 168, 1, 1)], "00:11:22:33:44:55");
+        assert_eq!(table[&Ipv4Addr::new(192, 168, 1, 9)], "AA:BB:CC:DD:EE:0F");
+
+        let route = "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n\
+                     wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0\n\
+                     wlan0\t0001A8C0\t00000000\t0001\t0\t0\t600\t00FFFFFF\t0\t0\t0\n\
+                     eth0\t00000000\t00000000\t0001\t0\t0\t100\t00000000\t0\t0\t0\n";
+        let gateways = parse_default_gateways(route);
+        assert_eq!(
+            gateways,
+            HashMap::from([("wlan0".to_string(), Ipv4Addr::new(192, 168, 1, 1))])
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! macOS tables and tools for the [`unix`](super::unix) backend.
+//!
+//! The neighbor cache and routing table are only exposed through `sysctl`
+//! route dumps, so they are read through the `arp` and `netstat` tools.
+
+use super::unix::parse_mac;
+use crate::types::GError;
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::process::Command;
+use std::time::Duration;
+
+/// Runs `program` with `args` and returns what it printed.
+fn run(program: &str, args: &[&str]) -> Result<String, GError> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| GError::from_io(&e, program))?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parses `arp -an` output:
+/// `? (192.168.1.1) at 0:11:22:33:44:55 on en0 ifscope [ethernet]`.
+fn parse_arp_output(text: &str) -> HashMap<Ipv4Addr, String> {
+    text.lines()
+        .filter_map(|line| {
+            let ip = line.split_once('(')?.1.split_once(')')?.0.parse().ok()?;
+            let mac = line.split_once(" at ")?.1.split_whitespace().next()?;
+            Some((ip, parse_mac(mac)?))
+        })
+        .collect()
+}
+
+pub fn read_arp_table() -> Result<HashMap<Ipv4Addr, String>, GError> {
+    run("arp", &["-an"]).map(|text| parse_arp_output(&text))
+}
+
+pub fn lookup_arp(ip: Ipv4Addr) -> Result<Option<String>, GError> {
+    let text = run("arp", &["-n", &ip.to_string()])?;
+    Ok(parse_arp_output(&text).remove(&ip))
+}
+
+/// Parses the default routes out of `netstat -rn -f inet`:
+/// `default            192.168.1.1        UGScg                 en0`.
+fn parse_default_gateways(text: &str) -> HashMap<String, Ipv4Addr> {
+    let mut gateways = HashMap::new();
+    for line in text.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let ["default", gateway, _flags, name, ..] = fields.as_slice() else {
+            continue;
+        };
+        // Interface-scoped defaults name a link (`link#6`) instead of a router.
+        if let Ok(gateway) = gateway.parse() {
+            gateways.entry(name.to_string()).or_insert(gateway);
+        }
+    }
+    gateways
+}
+
+pub fn default_gateways() -> HashMap<String, Ipv4Addr> {
+    run("netstat", &["-rn", "-f", "inet"])
+        .map(|text| parse_default_gateways(&text))
+        .unwrap_or_default()
+}
+
+/// `ping` sending one echo: `-W` takes milliseconds, `-m` sets the TTL.
+pub fn ping_command(ip: Ipv4Addr, timeout: Duration, ttl: Option<u8>) -> Command {
+    let mut command = Command::new("ping");
+    command
+        .args(["-n", "-c", "1", "-W"])
+        .arg(timeout.as_millis().max(1).to_string());
+    if let Some(ttl) = ttl {
+        command.arg("-m").arg(ttl.to_string());
+    }
+    command.arg(ip.to_string());
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tool_output() {
+        let arp = "? (192.168.1.1) at 0:11:22:33:44:55 on en0 ifscope [ethernet]\n\
+                   ? (192.168.1.5) at (incomplete) on en0 ifscope [ethernet]\n\
+                   ? (224.0.0.251) at 1:0:5e:0:0:fb on en0 ifscope permanent [ethernet]\n";
+        let table = parse_arp_output(arp);
+        assert_eq!(
+            table,
+            HashMap::from([(
+                Ipv4Addr::new(192, 168, 1, 1),
+                "00:11:22:33:44:55".to_string()
+            )])
+        );
+
+        let netstat = "Routing tables\n\nInternet:\n\
+                       Destination        Gateway            Flags               Netif Expire\n\
+                       default            192.168.1.1        UGScg                 en0\n\
+                       default            link#14            UCSIg             bridge0\n\
+                       127                127.0.0.1          UCS                   lo0\n";
+        assert_eq!(
+            parse_default_gateways(netstat),
+            HashMap::from([("en0".to_string(), Ipv4Addr::new(192, 168, 1, 1))])
+        );
+    }
+}
@@ -0,0 +1,429 @@
+//! TCP probes through a SOCKS5 proxy, for segments reachable only from a
+//! bastion host.
+//!
+//! [`Socks5Provider`] wraps another provider and opens every TCP port probe
+//! and banner grab as a SOCKS5 `CONNECT` (RFC 1928), optionally with a user
+//! name and password (RFC 1929). Echoes, ARP and UDP cannot cross the
+//! proxy, so it reports none of those capabilities and the scanner skips
+//! them; service probes that would connect directly (SMB, TLS, web, SNMP)
+//! report nothing.
+
+use super::{
+    BANNER_TIMEOUT, Capabilities, DEFAULT_PORT_TIMEOUT, InterfaceWatch, NetworkProvider,
+    PowerWatch, read_banner,
+};
+use crate::types::{DnsConfig, GError, Socks5Config, TcpProbeOptions, UdpPortState};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Environment variable the CLI reads the proxy password from when the
+/// proxy URL names a user without one.
+pub const PROXY_PASSWORD_ENV: &str = "RAGESCANNER_PROXY_PASSWORD";
+
+const VERSION: u8 = 5;
+const NO_AUTH: u8 = 0;
+const USER_PASSWORD: u8 = 2;
+const NO_ACCEPTABLE_METHOD: u8 = 0xFF;
+const CONNECT: u8 = 1;
+const ATYP_IPV4: u8 = 1;
+const ATYP_DOMAIN: u8 = 3;
+const ATYP_IPV6: u8 = 4;
+
+/// Why a connection through the proxy failed.
+enum Refusal {
+    /// The proxy itself could not be used: unreachable, bad credentials or
+    /// not speaking SOCKS5.
+    Proxy(String),
+    /// The proxy could not reach the target; the reply code says why.
+    Target(u8),
+}
+
+/// Opens a TCP connection to `target` through the proxy in `proxy`.
+///
+/// # Errors
+///
+/// Returns [`GError::Internal`] if the proxy cannot be reached, rejects the
+/// credentials, or cannot connect to `target`.
+pub async fn socks5_connect(
+    proxy: &Socks5Config,
+    target: SocketAddrV4,
+) -> Result<TcpStream, GError> {
+    connect(proxy, target)
+        .await
+        .map_err(|refusal| match refusal {
+            Refusal::Proxy(e) => GError::Internal(format!("SOCKS5 proxy {}: {}", proxy.addr, e)),
+            Refusal::Target(code) => GError::Internal(format!(
+                "SOCKS5 proxy {} cannot reach {}: {}",
+                proxy.addr,
+                target,
+                reply_text(code)
+            )),
+        })
+}
+
+async fn connect(proxy: &Socks5Config, target: SocketAddrV4) -> Result<TcpStream, Refusal> {
+    let io = |e: std::io::Error| Refusal::Proxy(e.to_string());
+    let mut stream = TcpStream::connect(proxy.addr.as_str()).await.map_err(io)?;
+
+    let method = if proxy.username.is_some() {
+        USER_PASSWORD
+    } else {
+        NO_AUTH
+    };
+    stream.write_all(&[VERSION, 1, method]).await.map_err(io)?;
+    let mut choice = [0u8; 2];
+    stream.read_exact(&mut choice).await.map_err(io)?;
+    match choice {
+        [VERSION, NO_ACCEPTABLE_METHOD] => {
+            return Err(Refusal::Proxy(
+                "no acceptable authentication method".to_string(),
+            ));
+        }
+        [VERSION, chosen] if chosen == method => {}
+        _ => return Err(Refusal::Proxy("not a SOCKS5 proxy".to_string())),
+    }
+
+    if method == USER_PASSWORD {
+        let user = proxy.username.as_deref().unwrap_or_default().as_bytes();
+        let password = proxy.password.as_deref().unwrap_or_default().as_bytes();
+        let (Ok(user_len), Ok(password_len)) =
+            (u8::try_from(user.len()), u8::try_from(password.len()))
+        else {
+            return Err(Refusal::Proxy(
+                "user name or password longer than 255 bytes".to_string(),
+            ));
+        };
+        let mut request = vec![1, user_len];
+        request.extend_from_slice(user);
+        request.push(password_len);
+        request.extend_from_slice(password);
+        stream.write_all(&request).await.map_err(io)?;
+        let mut status = [0u8; 2];
+        stream.read_exact(&mut status).await.map_err(io)?;
+        if status[1] != 0 {
+            return Err(Refusal::Proxy("user name or password rejected".to_string()));
+        }
+    }
+
+    let mut request = vec![VERSION, CONNECT, 0, ATYP_IPV4];
+    request.extend_from_slice(&target.ip().octets());
+    request.extend_from_slice(&target.port().to_be_bytes());
+    stream.write_all(&request).await.map_err(io)?;
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await.map_err(io)?;
+    if reply[0] != VERSION {
+        return Err(Refusal::Proxy("not a SOCKS5 proxy".to_string()));
+    }
+    if reply[1] != 0 {
+        return Err(Refusal::Target(reply[1]));
+    }
+    // Skip the address the proxy bound for us.
+    let bound = match reply[3] {
+        ATYP_IPV4 => 4,
+        ATYP_IPV6 => 16,
+        ATYP_DOMAIN => usize::from(stream.read_u8().await.map_err(io)?),
+        _ => return Err(Refusal::Proxy("malformed reply".to_string())),
+    };
+    let mut skipped = vec![0u8; bound + 2];
+    stream.read_exact(&mut skipped).await.map_err(io)?;
+    Ok(stream)
+}
+
+/// The meaning of a SOCKS5 reply code.
+fn reply_text(code: u8) -> &'static str {
+    match code {
+        1 => "general failure",
+        2 => "not allowed by ruleset",
+        3 => "network unreachable",
+        4 => "host unreachable",
+        5 => "connection refused",
+        6 => "TTL expired",
+        7 => "command not supported",
+        8 => "address type not supported",
+        _ => "unknown error",
+    }
+}
+
+/// Sends TCP probes through a SOCKS5 proxy and everything that does not
+/// touch the targets (name and vendor lookups, interface and power
+/// watching) to the wrapped provider.
+pub struct Socks5Provider {
+    inner: Arc<dyn NetworkProvider>,
+    proxy: Socks5Config,
+    /// Whether a failure of the proxy itself was logged already.
+    warned: AtomicBool,
+}
+
+impl Socks5Provider {
+    pub fn new(inner: Arc<dyn NetworkProvider>, proxy: Socks5Config) -> Self {
+        Self {
+            inner,
+            proxy,
+            warned: AtomicBool::new(false),
+        }
+    }
+
+    /// Connects to `ip:port` through the proxy within `timeout`.
+    ///
+    /// A proxy that cannot be used is logged once, as every probe after it
+    /// fails the same way and would otherwise pass for a closed port.
+    async fn open(&self, ip: Ipv4Addr, port: u16, timeout: Duration) -> Option<TcpStream> {
+        let target = SocketAddrV4::new(ip, port);
+        match tokio::time::timeout(timeout, connect(&self.proxy, target)).await {
+            Ok(Ok(stream)) => Some(stream),
+            Ok(Err(Refusal::Proxy(e))) => {
+                if !self.warned.swap(true, Ordering::SeqCst) {
+                    log::warn!("SOCKS5 proxy {} failed: {}", self.proxy.addr, e);
+                }
+                None
+            }
+            Ok(Err(Refusal::Target(code))) => {
+                log::debug!("{} through the proxy: {}", target, reply_text(code));
+                None
+            }
+            Err(_) => None,
+        }
+    }
+}
+
+#[async_trait]
+impl NetworkProvider for Socks5Provider {
+    /// Never sent: echoes cannot cross the proxy.
+    fn echo(&self, _ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+        Ok(None)
+    }
+
+    /// Never sent: the targets are not on a local link.
+    fn resolve_mac(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+        Ok(None)
+    }
+
+    fn resolve_hostname(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+        self.inner.resolve_hostname(ip)
+    }
+
+    async fn resolve_hostnames(
+        &self,
+        ips: &[Ipv4Addr],
+        dns: &DnsConfig,
+    ) -> HashMap<Ipv4Addr, String> {
+        self.inner.resolve_hostnames(ips, dns).await
+    }
+
+    async fn resolve_addresses(
+        &self,
+        names: &[String],
+        dns: &DnsConfig,
+    ) -> HashMap<String, Vec<Ipv4Addr>> {
+        self.inner.resolve_addresses(names, dns).await
+    }
+
+    fn resolve_vendor(&self, mac: &str) -> Option<String> {
+        self.inner.resolve_vendor(mac)
+    }
+
+    async fn scan_port(&self, ip: Ipv4Addr, port: u16) -> bool {
+        self.scan_port_timeout(ip, port, DEFAULT_PORT_TIMEOUT).await
+    }
+
+    /// `timeout` covers the whole exchange with the proxy.
+    async fn scan_port_timeout(&self, ip: Ipv4Addr, port: u16, timeout: Duration) -> bool {
+        self.open(ip, port, timeout).await.is_some()
+    }
+
+    /// The options would only shape the connection to the proxy, so they
+    /// are ignored.
+    async fn scan_port_with_options(
+        &self,
+        ip: Ipv4Addr,
+        port: u16,
+        timeout: Duration,
+        _options: TcpProbeOptions,
+    ) -> bool {
+        self.scan_port_timeout(ip, port, timeout).await
+    }
+
+    /// Never sent: UDP cannot cross the proxy.
+    async fn scan_udp_port(&self, _ip: Ipv4Addr, _port: u16) -> UdpPortState {
+        UdpPortState::Closed
+    }
+
+    async fn grab_banner(&self, ip: Ipv4Addr, port: u16) -> Option<String> {
+        let exchange = async {
+            let mut stream = self.open(ip, port, BANNER_TIMEOUT).await?;
+            read_banner(&mut stream, port).await
+        };
+        tokio::time::timeout(BANNER_TIMEOUT, exchange)
+            .await
+            .ok()
+            .flatten()
+    }
+
+    fn watch_interfaces(&self) -> Result<InterfaceWatch, GError> {
+        self.inner.watch_interfaces()
+    }
+
+    /// Whether the proxy, rather than the target, can be reached.
+    fn route_available(&self, _ip: Ipv4Addr) -> bool {
+        match self.proxy.addr.parse::<SocketAddr>() {
+            Ok(SocketAddr::V4(addr)) => self.inner.route_available(*addr.ip()),
+            _ => true,
+        }
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            arp: false,
+            icmp: false,
+            raw_sockets: false,
+            udp: false,
+            ..self.inner.capabilities()
+        }
+    }
+
+    fn watch_power(&self) -> Result<PowerWatch, GError> {
+        self.inner.watch_power()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// A SOCKS5 proxy on a local port that accepts `scout`/`s3cret` and can
+    /// only reach port 22 of `10.1.2.3`, where an SSH server greets.
+    async fn start_proxy() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            while let Ok((mut client, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut greeting = [0u8; 3];
+                    client.read_exact(&mut greeting).await.ok()?;
+                    if greeting[2] != USER_PASSWORD {
+                        client
+                            .write_all(&[VERSION, NO_ACCEPTABLE_METHOD])
+                            .await
+                            .ok()?;
+                        return None;
+                    }
+                    client.write_all(&[VERSION, USER_PASSWORD]).await.ok()?;
+                    let mut auth = [0u8; 2];
+                    client.read_exact(&mut auth).await.ok()?;
+                    let mut user = vec![0u8; usize::from(auth[1])];
+                    client.read_exact(&mut user).await.ok()?;
+                    let mut password = vec![0u8; usize::from(client.read_u8().await.ok()?)];
+                    client.read_exact(&mut password).await.ok()?;
+                    let accepted = user == b"scout" && password == b"s3cret";
+                    client.write_all(&[1, u8::from(!accepted)]).await.ok()?;
+                    if !accepted {
+                        return None;
+                    }
+                    let mut request = [0u8; 10];
+                    client.read_exact(&mut request).await.ok()?;
+                    let reachable =
+                        request[4..8] == [10, 1, 2, 3] && request[8..] == 22u16.to_be_bytes();
+                    let code = if reachable { 0 } else { 5 };
+                    client
+                        .write_all(&[VERSION, code, 0, ATYP_IPV4, 127, 0, 0, 1, 0, 0])
+                        .await
+                        .ok()?;
+                    if reachable {
+                        client.write_all(b"SSH-2.0-OpenSSH_9.6\r\n").await.ok()?;
+                    }
+                    Some(())
+                });
+            }
+        });
+        addr
+    }
+
+    fn config(addr: &str, password: &str) -> Socks5Config {
+        format!("socks5://scout:{}@{}", password, addr)
+            .parse()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_connect_authenticates_and_reports_refusals() {
+        let addr = start_proxy().await;
+        let target = SocketAddrV4::new(Ipv4Addr::new(10, 1, 2, 3), 22);
+        assert!(
+            socks5_connect(&config(&addr, "s3cret"), target)
+                .await
+                .is_ok()
+        );
+
+        let refused = SocketAddrV4::new(Ipv4Addr::new(10, 1, 2, 3), 23);
+        let e = socks5_connect(&config(&addr, "s3cret"), refused)
+            .await
+            .unwrap_err();
+        assert!(e.to_string().contains("connection refused"), "{}", e);
+        let e = socks5_connect(&config(&addr, "wrong"), target)
+            .await
+            .unwrap_err();
+        assert!(e.to_string().contains("rejected"), "{}", e);
+        let anonymous = Socks5Config {
+            addr: addr.clone(),
+            username: None,
+            password: None,
+        };
+        let e = socks5_connect(&anonymous, target).await.unwrap_err();
+        assert!(e.to_string().contains("no acceptable"), "{}", e);
+        assert!(!format!("{:?}", config(&addr, "s3cret")).contains("s3cret"));
+    }
+
+    #[tokio::test]
+    async fn test_scanner_probes_ports_through_the_proxy() {
+        use crate::net::MockNet;
+        use crate::scanner::Scanner;
+        use crate::types::{BridgeMessage, ScanConfig, ScanStatus};
+
+        let addr = start_proxy().await;
+        let (tx, mut rx) = tokio::sync::mpsc::channel(100);
+        let config = ScanConfig {
+            ports: vec![22, 80],
+            proxy: Some(config(&addr, "s3cret")),
+            ..ScanConfig::default()
+        };
+        let scanner = Scanner::new(Arc::new(MockNet), tx).with_config(config);
+        scanner
+            .scan_range(
+                Ipv4Addr::new(10, 1, 2, 3),
+                Ipv4Addr::new(10, 1, 2, 4),
+                tokio_util::sync::CancellationToken::new(),
+            )
+            .await;
+
+        let mut results = HashMap::new();
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::ScanUpdate(res) => {
+                    results.insert(res.ip.octets()[3], res);
+                }
+                BridgeMessage::ScanComplete => break,
+                _ => {}
+            }
+        }
+        // MockNet would answer port 80 anywhere; only the proxy decides here.
+        let up = &results[&3];
+        assert_eq!(up.status, ScanStatus::Online);
+        assert_eq!(up.attempts.ping, 0);
+        assert_eq!(up.mac, None);
+        assert_eq!(up.open_ports.len(), 1);
+        assert_eq!(up.open_ports[0].port, 22);
+        assert_eq!(
+            up.open_ports[0].banner.as_deref(),
+            Some("SSH-2.0-OpenSSH_9.6")
+        );
+        assert_eq!(results[&4].status, ScanStatus::Offline);
+        assert!(results[&4].open_ports.is_empty());
+    }
+}
@@ -0,0 +1,527 @@
+//! Backend of [`NetUtils`](super::NetUtils) shared by Linux and macOS.
+//!
+//! Echoes go out over an unprivileged ICMP datagram socket, the
+//! equivalent of `IcmpSendEcho2`. Linux only grants those to the groups in
+//! `net.ipv4.ping_group_range`; where the socket is refused, the setuid
+//! `ping` helper sends them instead. MACs come from the kernel's neighbor
+//! cache, read by the [`platform`] module after a datagram has made the
+//! kernel resolve the address.
+
+use super::platform;
+use super::{EchoReply, InterfaceInfo, InterfaceWatch, PING_PAYLOAD, PowerWatch, format_mac};
+use crate::types::{GError, TraceHop};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// ICMP message types.
+const ICMP_ECHO_REPLY: u8 = 0;
+const ICMP_ECHO_REQUEST: u8 = 8;
+/// Discard service, the destination of the datagram that triggers ARP.
+const DISCARD_PORT: u16 = 9;
+/// How long [`resolve_mac`] waits for the kernel to resolve a neighbor.
+const ARP_WAIT: Duration = Duration::from_millis(500);
+/// How often the neighbor cache is reread while waiting.
+const ARP_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// How often the interface list is compared for [`watch_interfaces`]; there
+/// is no change notification common to Linux and macOS.
+const INTERFACE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Resolver configuration naming the DNS servers.
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+
+/// Sequence number of the next echo request.
+static SEQUENCE: AtomicU16 = AtomicU16::new(0);
+
+/// Whether ICMP datagram sockets are available, checked on the first echo.
+fn sockets_allowed() -> bool {
+    static ALLOWED: OnceLock<bool> = OnceLock::new();
+    *ALLOWED.get_or_init(|| match icmp_socket() {
+        Ok(_) => true,
+        Err(e) => {
+            log::info!("ICMP sockets unavailable ({}), using the ping helper", e);
+            false
+        }
+    })
+}
+
+fn icmp_socket() -> io::Result<Socket> {
+    Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::ICMPV4))
+}
+
+/// An ICMP socket connected to `ip`, as a UDP socket: the kernel treats both
+/// as plain datagram sockets, so the UDP wrappers can drive it.
+fn connected_socket(ip: Ipv4Addr) -> io::Result<UdpSocket> {
+    let socket = icmp_socket()?;
+    socket.connect(&SocketAddr::from((ip, 0)).into())?;
+    Ok(socket.into())
+}
+
+/// Builds an echo request. Linux fills in its own identifier and checksum on
+/// datagram sockets; macOS sends the packet as is.
+fn echo_request(sequence: u16) -> Vec<u8> {
+    let identifier = (std::process::id() as u16).to_be_bytes();
+    let mut packet = vec![ICMP_ECHO_REQUEST, 0, 0, 0, identifier[0], identifier[1]];
+    packet.extend(sequence.to_be_bytes());
+    packet.extend(PING_PAYLOAD);
+    let sum = checksum(&packet);
+    packet[2..4].copy_from_slice(&sum.to_be_bytes());
+    packet
+}
+
+/// The Internet checksum (RFC 1071) of `data`.
+fn checksum(data: &[u8]) -> u16 {
+    let mut sum: u32 = data
+        .chunks(2)
+        .map(|pair| u32::from(u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)])))
+        .sum();
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// Splits a received datagram into its ICMP message and the TTL of the IP
+/// header in front of it, which macOS passes along and Linux strips.
+fn split_ip_header(packet: &[u8]) -> (&[u8], Option<u8>) {
+    match packet.first() {
+        Some(first) if first >> 4 == 4 => {
+            let header_len = usize::from(first & 0x0F) * 4;
+            (
+                packet.get(header_len..).unwrap_or_default(),
+                packet.get(8).copied(),
+            )
+        }
+        _ => (packet, None),
+    }
+}
+
+/// Whether `message` is the echo reply to request `sequence`.
+fn is_reply_to(message: &[u8], sequence: u16) -> bool {
+    message.len() >= 8 && message[0] == ICMP_ECHO_REPLY && message[6..8] == sequence.to_be_bytes()
+}
+
+/// Maps a failed echo to a result, as on Windows: local failures are errors,
+/// the rest mean offline.
+fn echo_failure<T>(e: &io::Error, api: &str) -> Result<Option<T>, GError> {
+    match GError::from_io(e, api) {
+        e if e.is_local() => Err(e),
+        _ => Ok(None),
+    }
+}
+
+fn rtt_ms(started: Instant) -> u32 {
+    started.elapsed().as_millis().min(u128::from(u32::MAX)) as u32
+}
+
+/// Sends one echo to `ip` and waits up to `timeout` for the reply.
+pub fn echo(ip: Ipv4Addr, timeout: Duration) -> Result<Option<EchoReply>, GError> {
+    if !sockets_allowed() {
+        return echo_helper(ip, timeout);
+    }
+    let socket = match connected_socket(ip) {
+        Ok(socket) => socket,
+        Err(e) => return echo_failure(&e, "ICMP socket"),
+    };
+    let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    let started = Instant::now();
+    if let Err(e) = socket.send(&echo_request(sequence)) {
+        return echo_failure(&e, "ICMP send");
+    }
+    let mut buf = [0u8; 512];
+    loop {
+        let remaining = timeout.saturating_sub(started.elapsed());
+        if remaining.is_zero() {
+            return Ok(None);
+        }
+        if let Err(e) = socket.set_read_timeout(Some(remaining)) {
+            return Err(GError::from_io(&e, "ICMP socket"));
+        }
+        match socket.recv(&mut buf) {
+            Ok(n) => {
+                let (message, ttl) = split_ip_header(&buf[..n]);
+                if is_reply_to(message, sequence) {
+                    return Ok(Some(EchoReply {
+                        rtt_ms: rtt_ms(started),
+                        ttl,
+                    }));
+                }
+            }
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Ok(None);
+            }
+            Err(e) => return echo_failure(&e, "ICMP receive"),
+        }
+    }
+}
+
+/// Like [`echo`], waiting for the reply on the Tokio reactor.
+pub async fn echo_async(ip: Ipv4Addr, timeout: Duration) -> Result<Option<EchoReply>, GError> {
+    if !sockets_allowed() {
+        return echo_helper_async(ip, timeout).await;
+    }
+    let socket = connected_socket(ip)
+        .and_then(|socket| {
+            socket.set_nonblocking(true)?;
+            tokio::net::UdpSocket::from_std(socket)
+        })
+        .map_err(|e| GError::from_io(&e, "ICMP socket"));
+    let socket = match socket {
+        Ok(socket) => socket,
+        Err(e) if e.is_local() => return Err(e),
+        Err(_) => return Ok(None),
+    };
+    let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    let started = Instant::now();
+    if let Err(e) = socket.send(&echo_request(sequence)).await {
+        return echo_failure(&e, "ICMP send");
+    }
+    let wait = async {
+        let mut buf = [0u8; 512];
+        loop {
+            let n = socket.recv(&mut buf).await?;
+            let (message, ttl) = split_ip_header(&buf[..n]);
+            if is_reply_to(message, sequence) {
+                return Ok::<_, io::Error>(ttl);
+            }
+        }
+    };
+    match tokio::time::timeout(timeout, wait).await {
+        Ok(Ok(ttl)) => Ok(Some(EchoReply {
+            rtt_ms: rtt_ms(started),
+            ttl,
+        })),
+        Ok(Err(e)) => echo_failure(&e, "ICMP receive"),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Pings `ip` once with the system `ping`.
+fn echo_helper(ip: Ipv4Addr, timeout: Duration) -> Result<Option<EchoReply>, GError> {
+    let output = platform::ping_command(ip, timeout, None)
+        .output()
+        .map_err(|e| GError::from_io(&e, "ping"))?;
+    Ok(parse_ping_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+async fn echo_helper_async(ip: Ipv4Addr, timeout: Duration) -> Result<Option<EchoReply>, GError> {
+    let output = tokio::process::Command::from(platform::ping_command(ip, timeout, None))
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| GError::from_io(&e, "ping"))?;
+    Ok(parse_ping_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Reads the reply out of `ping` output, which looks the same on Linux and
+/// macOS: `64 bytes from 10.0.0.1: icmp_seq=1 ttl=64 time=0.045 ms`.
+fn parse_ping_output(output: &str) -> Option<EchoReply> {
+    output.lines().find_map(|line| {
+        let field = |name: &str| {
+            let start = line.find(name)? + name.len();
+            let value = &line[start..];
+            let end = value
+                .find(|c: char| !c.is_ascii_digit() && c != '.')
+                .unwrap_or(value.len());
+            Some(&value[..end])
+        };
+        let time: f64 = field("time=")?.parse().ok()?;
+        Some(EchoReply {
+            rtt_ms: time as u32,
+            ttl: field("ttl=").and_then(|ttl| ttl.parse().ok()),
+        })
+    })
+}
+
+/// Reads the router that reported the TTL exceeded out of `ping` output:
+/// `From 10.0.0.1 icmp_seq=1 Time to live exceeded` on Linux,
+/// `36 bytes from 10.0.0.1: Time to live exceeded` on macOS.
+fn parse_ttl_exceeded(output: &str) -> Option<Ipv4Addr> {
+    output
+        .lines()
+        .filter(|line| line.to_ascii_lowercase().contains("time to live exceeded"))
+        .find_map(|line| {
+            line.split_whitespace()
+                .find_map(|word| word.trim_end_matches(':').parse().ok())
+        })
+}
+
+/// Sends one echo to `ip` with the given TTL through the `ping` helper,
+/// which receives the routers' ICMP errors that datagram sockets do not.
+pub fn trace_hop(ip: Ipv4Addr, ttl: u8, timeout: Duration) -> Result<TraceHop, GError> {
+    let started = Instant::now();
+    let output = platform::ping_command(ip, timeout, Some(ttl))
+        .output()
+        .map_err(|e| GError::from_io(&e, "ping"))?;
+    let output = String::from_utf8_lossy(&output.stdout);
+    if let Some(reply) = parse_ping_output(&output) {
+        return Ok(TraceHop {
+            ttl,
+            addr: Some(ip),
+            rtt_ms: Some(reply.rtt_ms),
+        });
+    }
+    let router = parse_ttl_exceeded(&output);
+    Ok(TraceHop {
+        ttl,
+        addr: router,
+        // The helper prints no time for errors; its run time is close.
+        rtt_ms: router.map(|_| rtt_ms(started)),
+    })
+}
+
+/// Parses a MAC as printed by the neighbor cache, `a:b:c:d:e:f` with or
+/// without leading zeros. Incomplete, broadcast and multicast entries are
+/// not hosts and give `None`.
+pub fn parse_mac(text: &str) -> Option<String> {
+    let bytes: Vec<u8> = text
+        .split(':')
+        .map(|part| u8::from_str_radix(part, 16).ok())
+        .collect::<Option<_>>()?;
+    if bytes.len() != 6 || bytes.iter().all(|b| *b == 0) || bytes[0] & 0x01 != 0 {
+        return None;
+    }
+    Some(format_mac(&bytes))
+}
+
+/// Whether echoes can go out at all: over ICMP sockets, or else through a
+/// `ping` on the `PATH`.
+pub fn icmp_available() -> bool {
+    sockets_allowed()
+        || std::env::var_os("PATH")
+            .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join("ping").is_file()))
+}
+
+/// Whether the neighbor cache can be read, which MAC lookups rely on.
+pub fn arp_available() -> bool {
+    read_arp_table().is_ok()
+}
+
+/// Reads the neighbor cache, mapping IPv4 addresses to MACs.
+pub fn read_arp_table() -> Result<HashMap<Ipv4Addr, String>, GError> {
+    platform::read_arp_table()
+}
+
+/// Returns the MAC of `ip` from the neighbor cache, first sending it a
+/// datagram so the kernel resolves it if it is not cached yet.
+pub fn resolve_mac(ip: Ipv4Addr) -> Result<Option<String>, GError> {
+    if let Some(mac) = platform::lookup_arp(ip)? {
+        return Ok(Some(mac));
+    }
+    let socket =
+        UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).map_err(|e| GError::from_io(&e, "UDP bind"))?;
+    if let Err(e) = socket.send_to(&[], (ip, DISCARD_PORT)) {
+        return match GError::from_io(&e, "UDP send") {
+            GError::HostUnreachable(_) => Ok(None),
+            e => Err(e),
+        };
+    }
+    let started = Instant::now();
+    while started.elapsed() < ARP_WAIT {
+        thread::sleep(ARP_POLL_INTERVAL);
+        if let Some(mac) = platform::lookup_arp(ip)? {
+            return Ok(Some(mac));
+        }
+    }
+    Ok(None)
+}
+
+/// Lists the local IPv4 addresses with `getifaddrs`, with each interface's
+/// default gateway from the routing table and the system-wide DNS servers
+/// from `/etc/resolv.conf`.
+pub fn interfaces() -> Result<Vec<InterfaceInfo>, GError> {
+    let gateways = platform::default_gateways();
+    let dns_servers = std::fs::read_to_string(RESOLV_CONF)
+        .map(|text| parse_nameservers(&text))
+        .unwrap_or_default();
+    let mut list: *mut libc::ifaddrs = std::ptr::null_mut();
+    if unsafe { libc::getifaddrs(&mut list) } != 0 {
+        return Err(GError::from_io(&io::Error::last_os_error(), "getifaddrs"));
+    }
+    let mut result = Vec::new();
+    let mut entry = list;
+    unsafe {
+        while let Some(ifa) = entry.as_ref() {
+            entry = ifa.ifa_next;
+            let flags = ifa.ifa_flags as libc::c_int;
+            if ifa.ifa_addr.is_null()
+                || ifa.ifa_netmask.is_null()
+                || i32::from((*ifa.ifa_addr).sa_family) != libc::AF_INET
+                || flags & libc::IFF_LOOPBACK != 0
+            {
+                continue;
+            }
+            let address = &*(ifa.ifa_addr as *const libc::sockaddr_in);
+            let netmask = &*(ifa.ifa_netmask as *const libc::sockaddr_in);
+            let name = CStr::from_ptr(ifa.ifa_name).to_string_lossy().into_owned();
+            result.push(InterfaceInfo {
+                gateway: gateways.get(&name).copied(),
+                name,
+                address: Ipv4Addr::from(u32::from_be(address.sin_addr.s_addr)),
+                prefix_len: u32::from_be(netmask.sin_addr.s_addr).count_ones() as u8,
+                is_up: flags & libc::IFF_UP != 0 && flags & libc::IFF_RUNNING != 0,
+                dns_servers: dns_servers.clone(),
+                dhcp_server: None,
+            });
+        }
+        libc::freeifaddrs(list);
+    }
+    Ok(result)
+}
+
+/// Returns the IPv4 `nameserver` entries of a `resolv.conf`, in order.
+fn parse_nameservers(text: &str) -> Vec<Ipv4Addr> {
+    text.lines()
+        .filter_map(|line| {
+            let mut words = line.split_whitespace();
+            (words.next() == Some("nameserver")).then(|| words.next()?.parse().ok())?
+        })
+        .collect()
+}
+
+/// Stops the polling thread of [`watch_interfaces`] when the last watch goes.
+struct InterfacePoller {
+    stop: Arc<AtomicBool>,
+}
+
+impl Drop for InterfacePoller {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Reports interface changes by comparing the interface list every
+/// [`INTERFACE_POLL_INTERVAL`].
+pub fn watch_interfaces() -> Result<InterfaceWatch, GError> {
+    let (tx, rx) = watch::channel(0);
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = stop.clone();
+    let mut last = interfaces()?;
+    thread::Builder::new()
+        .name("interface-watch".to_string())
+        .spawn(move || {
+            while !stopped.load(Ordering::Relaxed) {
+                thread::sleep(INTERFACE_POLL_INTERVAL);
+                let Ok(current) = interfaces() else {
+                    continue;
+                };
+                if current != last {
+                    last = current;
+                    tx.send_modify(|changes| *changes += 1);
+                }
+            }
+        })
+        .map_err(|e| GError::Internal(format!("Cannot start the interface watch: {}", e)))?;
+    Ok(InterfaceWatch {
+        rx,
+        _registration: Some(Arc::new(InterfacePoller { stop })),
+    })
+}
+
+/// Suspend and resume are not reported here: after a sleep, the interface
+/// watch sees the adapters go down and come back instead.
+pub fn watch_power() -> Result<PowerWatch, GError> {
+    Ok(PowerWatch::inert())
+}
+
+/// Whether an interface that is up has a default gateway or is on `ip`'s
+/// subnet. Assumes a route if the interfaces cannot be listed.
+pub fn route_available(ip: Ipv4Addr) -> bool {
+    match interfaces() {
+        Ok(list) => list.iter().any(|i| {
+            let mask = u32::from(i.netmask());
+            i.is_up && (i.gateway.is_some() || u32::from(i.address) & mask == u32::from(ip) & mask)
+        }),
+        Err(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_echo_request_checksum_and_reply_matching() {
+        let request = echo_request(0x0102);
+        assert_eq!(request[0], ICMP_ECHO_REQUEST);
+        assert_eq!(&request[6..8], &[0x01, 0x02]);
+        // A packet carrying its own checksum sums to zero.
+        assert_eq!(checksum(&request), 0);
+
+        let mut reply = request.clone();
+        reply[0] = ICMP_ECHO_REPLY;
+        assert!(is_reply_to(&reply, 0x0102));
+        assert!(!is_reply_to(&reply, 0x0103));
+        assert!(!is_reply_to(&request, 0x0102));
+
+        // macOS passes the IP header along; its TTL is the ninth byte.
+        let mut with_header = vec![0x45, 0, 0, 0, 0, 0, 0, 0, 57, 1, 0, 0];
+        with_header.extend([0; 8]);
+        with_header.extend(&reply);
+        assert_eq!(split_ip_header(&with_header), (&reply[..], Some(57)));
+        assert_eq!(split_ip_header(&reply), (&reply[..], None));
+    }
+
+    #[test]
+    fn test_parse_ping_output() {
+        let linux = "PING 10.0.0.1 (10.0.0.1) 56(84) bytes of data.\n\
+                     64 bytes from 10.0.0.1: icmp_seq=1 ttl=64 time=2.71 ms\n\n\
+                     --- 10.0.0.1 ping statistics ---\n\
+                     1 packets transmitted, 1 received, 0% packet loss, time 0ms\n\
+                     rtt min/avg/max/mdev = 2.713/2.713/2.713/0.000 ms\n";
+        assert_eq!(
+            parse_ping_output(linux),
+            Some(EchoReply {
+                rtt_ms: 2,
+                ttl: Some(64)
+            })
+        );
+        let unreachable = "From 10.0.0.9 icmp_seq=1 Destination Host Unreachable\n\
+                           1 packets transmitted, 0 received, +1 errors, 100% packet loss, time 0ms\n";
+        assert_eq!(parse_ping_output(unreachable), None);
+
+        let linux_hop = "From 192.168.1.1 icmp_seq=1 Time to live exceeded\n";
+        let macos_hop = "36 bytes from 192.168.1.1: Time to live exceeded\n";
+        for hop in [linux_hop, macos_hop] {
+            assert_eq!(parse_ping_output(hop), None);
+            assert_eq!(parse_ttl_exceeded(hop), Some(Ipv4Addr::new(192, 168, 1, 1)));
+        }
+    }
+
+    #[test]
+    fn test_parse_mac_normalizes_and_skips_non_hosts() {
+        assert_eq!(
+            parse_mac("0:11:2:aa:bb:cc").as_deref(),
+            Some("00:11:02:AA:BB:CC")
+        );
+        assert_eq!(parse_mac("00:00:00:00:00:00"), None);
+        assert_eq!(parse_mac("ff:ff:ff:ff:ff:ff"), None);
+        assert_eq!(parse_mac("01:00:5e:00:00:fb"), None);
+        assert_eq!(parse_mac("(incomplete)"), None);
+    }
+
+    #[test]
+    fn test_parse_nameservers_keeps_ipv4_entries() {
+        let conf = "# Generated by NetworkManager\n\
+                    search lan\n\
+                    nameserver 192.168.1.1\n\
+                    nameserver fe80::1%eth0\n\
+                    ;nameserver 10.0.0.9\n\
+                    nameserver\t1.1.1.1 # fallback\n";
+        assert_eq!(
+            parse_nameservers(conf),
+            vec![Ipv4Addr::new(192, 168, 1, 1), Ipv4Addr::new(1, 1, 1, 1)]
+        );
+    }
+}
@@ -0,0 +1,585 @@
+//! Win32 backend of [`NetUtils`](super::NetUtils): `IcmpSendEcho2` pings,
+//! `SendARP` and `GetIpNetTable2` for MACs, `GetAdaptersAddresses` for the
+//! interface list and change/power notifications from the IP Helper and
+//! power APIs.
+
+use super::{
+    EchoReply, InterfaceInfo, InterfaceWatch, PING_PAYLOAD, PowerState, PowerWatch, format_mac,
+};
+use crate::types::{GError, TraceHop};
+use std::cell::UnsafeCell;
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::mem;
+use std::net::Ipv4Addr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{oneshot, watch};
+use windows::Win32::Foundation::{
+    BOOLEAN, CloseHandle, ERROR_IO_PENDING, HANDLE, INVALID_HANDLE_VALUE,
+};
+use windows::Win32::NetworkManagement::IpHelper::{
+    CancelMibChangeNotify2, FreeMibTable, GAA_FLAG_INCLUDE_GATEWAYS, GAA_FLAG_SKIP_ANYCAST,
+    GAA_FLAG_SKIP_MULTICAST, GetAdaptersAddresses, GetBestInterface, GetIpNetTable2,
+    ICMP_ECHO_REPLY, IP_ADAPTER_ADDRESSES_LH, IP_OPTION_INFORMATION, IP_TTL_EXPIRED_TRANSIT,
+    IcmpCloseHandle, IcmpCreateFile, IcmpParseReplies, IcmpSendEcho, IcmpSendEcho2,
+    MIB_IPINTERFACE_ROW, MIB_IPNET_TABLE2, MIB_NOTIFICATION_TYPE, NotifyIpInterfaceChange, SendARP,
+};
+use windows::Win32::NetworkManagement::Ndis::IfOperStatusUp;
+use windows::Win32::Networking::WinSock::{AF_INET, NlnsProbe, SOCKADDR_IN, SOCKET_ADDRESS};
+use windows::Win32::System::Power::{
+    DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS, HPOWERNOTIFY, PowerRegisterSuspendResumeNotification,
+    PowerUnregisterSuspendResumeNotification,
+};
+use windows::Win32::System::Threading::{
+    CreateEventW, INFINITE, RegisterWaitForSingleObject, UnregisterWaitEx, WT_EXECUTEONLYONCE,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    DEVICE_NOTIFY_CALLBACK, PBT_APMRESUMEAUTOMATIC, PBT_APMRESUMESUSPEND, PBT_APMSUSPEND,
+};
+
+/// RAII wrapper for Win32 handles.
+struct SafeHandle(HANDLE);
+
+impl SafeHandle {
+    fn new(h: HANDLE) -> Result<Self, GError> {
+        if h == INVALID_HANDLE_VALUE || h.0 == 0 {
+            Err(GError::Internal("Invalid Win32 Handle".to_string()))
+        } else {
+            Ok(SafeHandle(h))
+        }
+    }
+}
+
+impl Drop for SafeHandle {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = IcmpCloseHandle(self.0);
+        }
+    }
+}
+
+/// Size of the reply buffer for one echo of [`PING_PAYLOAD`].
+const ECHO_REPLY_SIZE: usize = mem::size_of::<ICMP_ECHO_REPLY>() + PING_PAYLOAD.len() + 8;
+
+/// One `IcmpSendEcho2` request in flight.
+///
+/// The thread-pool wait callback holds a reference until the event fires, so
+/// the reply buffer outlives the request even if the awaiting future is dropped.
+struct PendingEcho {
+    icmp: SafeHandle,
+    event: HANDLE,
+    wait: Mutex<HANDLE>,
+    reply: UnsafeCell<[u8; ECHO_REPLY_SIZE]>,
+    done: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+// SAFETY: the ICMP service only writes `reply` before signalling `event`, and
+// it is only read after the callback has reported that signal.
+unsafe impl Send for PendingEcho {}
+unsafe impl Sync for PendingEcho {}
+
+impl Drop for PendingEcho {
+    fn drop(&mut self) {
+        let wait = *self.wait.get_mut().unwrap_or_else(|e| e.into_inner());
+        unsafe {
+            // A null completion event does not block, so this is safe on the callback thread.
+            if wait.0 != 0 {
+                let _ = UnregisterWaitEx(wait, None);
+            }
+            let _ = CloseHandle(self.event);
+        }
+    }
+}
+
+/// Wait callback for [`PendingEcho::event`]; wakes the awaiting future.
+unsafe extern "system" fn echo_completed(context: *mut c_void, _timed_out: BOOLEAN) {
+    let pending = unsafe { Arc::from_raw(context as *const PendingEcho) };
+    if let Some(done) = pending
+        .done
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+    {
+        let _ = done.send(());
+    }
+}
+
+/// Maps a failed echo to a result: local failures are errors, the rest mean offline.
+fn echo_failure<T>(code: u32, api: &str) -> Result<Option<T>, GError> {
+    match GError::from_win32(code, api) {
+        // Local failures affect every host, so surface them.
+        e if e.is_local() => Err(e),
+        // Timeouts and unreachable replies just mean the host is offline.
+        _ => Ok(None),
+    }
+}
+
+/// Reads the round-trip time and TTL from a reply buffer filled by the ICMP service.
+fn echo_reply(reply_buffer: &[u8]) -> Option<EchoReply> {
+    // The buffer starts with an ICMP_ECHO_REPLY; Status 0 is IP_SUCCESS.
+    let reply =
+        unsafe { std::ptr::read_unaligned(reply_buffer.as_ptr() as *const ICMP_ECHO_REPLY) };
+    (reply.Status == 0).then_some(EchoReply {
+        rtt_ms: reply.RoundTripTime,
+        ttl: Some(reply.Options.Ttl),
+    })
+}
+
+fn last_error() -> u32 {
+    std::io::Error::last_os_error().raw_os_error().unwrap_or(0) as u32
+}
+
+/// A `NotifyIpInterfaceChange` registration, cancelled on drop.
+struct InterfaceRegistration {
+    handle: HANDLE,
+    context: *mut watch::Sender<u64>,
+}
+
+// The context is only dereferenced by the notification callback, which
+// `CancelMibChangeNotify2` waits for before `drop` frees it.
+unsafe impl Send for InterfaceRegistration {}
+unsafe impl Sync for InterfaceRegistration {}
+
+impl Drop for InterfaceRegistration {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CancelMibChangeNotify2(self.handle);
+            drop(Box::from_raw(self.context));
+        }
+    }
+}
+
+unsafe extern "system" fn on_interface_change(
+    context: *const c_void,
+    _row: *const MIB_IPINTERFACE_ROW,
+    _kind: MIB_NOTIFICATION_TYPE,
+) {
+    let tx = unsafe { &*(context as *const watch::Sender<u64>) };
+    tx.send_modify(|changes| *changes += 1);
+}
+
+/// A `PowerRegisterSuspendResumeNotification` registration, removed on drop.
+struct PowerRegistration {
+    handle: isize,
+    params: *mut DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS,
+}
+
+// As for `InterfaceRegistration`: the callback is the only other user of the
+// context, and unregistering waits for it.
+unsafe impl Send for PowerRegistration {}
+unsafe impl Sync for PowerRegistration {}
+
+impl Drop for PowerRegistration {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = PowerUnregisterSuspendResumeNotification(HPOWERNOTIFY(self.handle));
+            let params = Box::from_raw(self.params);
+            drop(Box::from_raw(
+                params.Context as *mut watch::Sender<PowerState>,
+            ));
+        }
+    }
+}
+
+unsafe extern "system" fn on_power_event(
+    context: *const c_void,
+    kind: u32,
+    _setting: *const c_void,
+) -> u32 {
+    let tx = unsafe { &*(context as *const watch::Sender<PowerState>) };
+    match kind {
+        PBT_APMSUSPEND => tx.send_modify(|state| {
+            state.suspended = true;
+            state.sleeps += 1;
+        }),
+        // Both resume events arrive after a user-initiated wake; report the first.
+        PBT_APMRESUMEAUTOMATIC | PBT_APMRESUMESUSPEND => {
+            tx.send_if_modified(|state| std::mem::replace(&mut state.suspended, false));
+        }
+        _ => {}
+    }
+    0
+}
+
+/// `IF_TYPE_SOFTWARE_LOOPBACK`.
+const IF_TYPE_LOOPBACK: u32 = 24;
+/// `ERROR_BUFFER_OVERFLOW`, returned by `GetAdaptersAddresses` when the buffer is too small.
+const ERROR_BUFFER_OVERFLOW: u32 = 111;
+
+/// Reads an IPv4 address out of a `SOCKET_ADDRESS`, if it holds one.
+///
+/// # Safety
+///
+/// `sa.lpSockaddr` must be null or point to a valid socket address.
+unsafe fn socket_ipv4(sa: &SOCKET_ADDRESS) -> Option<Ipv4Addr> {
+    if sa.lpSockaddr.is_null() || unsafe { (*sa.lpSockaddr).sa_family } != AF_INET {
+        return None;
+    }
+    let sin = unsafe { &*(sa.lpSockaddr as *const SOCKADDR_IN) };
+    Some(Ipv4Addr::from(
+        unsafe { sin.sin_addr.S_un.S_addr }.to_le_bytes(),
+    ))
+}
+
+/// Lists the local IPv4 addresses through `GetAdaptersAddresses`.
+pub fn interfaces() -> Result<Vec<InterfaceInfo>, GError> {
+    let flags = GAA_FLAG_INCLUDE_GATEWAYS | GAA_FLAG_SKIP_ANYCAST | GAA_FLAG_SKIP_MULTICAST;
+    // u64 elements keep the buffer aligned for IP_ADAPTER_ADDRESSES_LH.
+    let mut buffer: Vec<u64> = vec![0; 2048];
+    let mut res = ERROR_BUFFER_OVERFLOW;
+    for _ in 0..3 {
+        let mut size = (buffer.len() * mem::size_of::<u64>()) as u32;
+        res = unsafe {
+            GetAdaptersAddresses(
+                AF_INET.0 as u32,
+                flags,
+                None,
+                Some(buffer.as_mut_ptr() as *mut IP_ADAPTER_ADDRESSES_LH),
+                &mut size,
+            )
+        };
+        if res != ERROR_BUFFER_OVERFLOW {
+            break;
+        }
+        // The adapter list can grow between calls, so retry a few times.
+        buffer = vec![0; (size as usize).div_ceil(mem::size_of::<u64>())];
+    }
+    if res != 0 {
+        return Err(GError::from_win32(res, "GetAdaptersAddresses"));
+    }
+
+    let mut result = Vec::new();
+    let mut adapter = buffer.as_ptr() as *const IP_ADAPTER_ADDRESSES_LH;
+    unsafe {
+        while let Some(a) = adapter.as_ref() {
+            adapter = a.Next;
+            if a.IfType == IF_TYPE_LOOPBACK {
+                continue;
+            }
+            let name = a.FriendlyName.to_string().unwrap_or_default();
+            let is_up = a.OperStatus == IfOperStatusUp;
+
+            let mut gateway = None;
+            let mut gw = a.FirstGatewayAddress;
+            while let Some(g) = gw.as_ref() {
+                if let Some(ip) = socket_ipv4(&g.Address) {
+                    gateway = Some(ip);
+                    break;
+                }
+                gw = g.Next;
+            }
+
+            let mut dns_servers = Vec::new();
+            let mut dns = a.FirstDnsServerAddress;
+            while let Some(d) = dns.as_ref() {
+                if let Some(ip) = socket_ipv4(&d.Address) {
+                    dns_servers.push(ip);
+                }
+                dns = d.Next;
+            }
+            let dhcp_server = socket_ipv4(&a.Dhcpv4Server);
+
+            let mut unicast = a.FirstUnicastAddress;
+            while let Some(u) = unicast.as_ref() {
+                if let Some(address) = socket_ipv4(&u.Address) {
+                    result.push(InterfaceInfo {
+                        name: name.clone(),
+                        address,
+                        prefix_len: u.OnLinkPrefixLength,
+                        gateway,
+                        is_up,
+                        dns_servers: dns_servers.clone(),
+                        dhcp_server,
+                    });
+                }
+                unicast = u.Next;
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// `IcmpSendEcho2` needs no privileges, so echoes always work.
+pub fn icmp_available() -> bool {
+    true
+}
+
+/// `SendARP` and `GetIpNetTable2` need no privileges either.
+pub fn arp_available() -> bool {
+    true
+}
+
+pub fn resolve_mac(ip: Ipv4Addr) -> Result<Option<String>, GError> {
+    let dest_ip_final = u32::from_le_bytes(ip.octets());
+    // Win32 SendARP requires MAXLEN_PHYSADDR (8) bytes minimum, even if MAC is 6.
+    let mut mac_buffer = [0u8; 8];
+    let mut mac_len = mac_buffer.len() as u32;
+
+    let res = unsafe {
+        SendARP(
+            dest_ip_final,
+            0,
+            mac_buffer.as_mut_ptr() as *mut c_void,
+            &mut mac_len,
+        )
+    };
+
+    if res == 0 {
+        if mac_len >= 6 {
+            Ok(Some(format_mac(&mac_buffer)))
+        } else {
+            // Should not happen for Ethernet, but handle safely
+            log::error!(
+                "SendARP succeeded but returned invalid mac_len: {}",
+                mac_len
+            );
+            Ok(None)
+        }
+    } else if res == 67 || res == 1168 {
+        // 67 = ERROR_BAD_NET_NAME (Host not found), 1168 = ERROR_NOT_FOUND
+        Ok(None)
+    } else {
+        let err = GError::from_win32(res, "SendARP");
+        log::error!("SendARP failed for {}: {}", ip, err);
+        Err(err)
+    }
+}
+
+pub fn read_arp_table() -> Result<HashMap<Ipv4Addr, String>, GError> {
+    let mut table: *mut MIB_IPNET_TABLE2 = std::ptr::null_mut();
+    unsafe { GetIpNetTable2(AF_INET, &mut table) }
+        .map_err(|e| GError::from_hresult(e.code().0, "GetIpNetTable2"))?;
+
+    let mut entries = HashMap::new();
+    unsafe {
+        let rows =
+            std::slice::from_raw_parts((*table).Table.as_ptr(), (*table).NumEntries as usize);
+        for row in rows {
+            // Skip unresolved entries (Unreachable/Incomplete) and non-Ethernet addresses.
+            if row.State.0 < NlnsProbe.0
+                || row.PhysicalAddressLength != 6
+                || row.Address.si_family != AF_INET
+            {
+                continue;
+            }
+            let mac = &row.PhysicalAddress[..6];
+            // Broadcast and multicast entries are not hosts.
+            if mac.iter().all(|b| *b == 0) || mac[0] & 0x01 != 0 {
+                continue;
+            }
+            let ip = Ipv4Addr::from(row.Address.Ipv4.sin_addr.S_un.S_addr.to_le_bytes());
+            entries.insert(ip, format_mac(mac));
+        }
+        let _ = FreeMibTable(table as *const c_void);
+    }
+
+    Ok(entries)
+}
+
+pub fn watch_interfaces() -> Result<InterfaceWatch, GError> {
+    let (tx, rx) = watch::channel(0);
+    let context = Box::into_raw(Box::new(tx));
+    let mut handle = HANDLE::default();
+    let registered = unsafe {
+        NotifyIpInterfaceChange(
+            AF_INET,
+            Some(on_interface_change),
+            Some(context as *const c_void),
+            BOOLEAN(0),
+            &mut handle,
+        )
+    };
+    if let Err(e) = registered {
+        drop(unsafe { Box::from_raw(context) });
+        return Err(GError::from_hresult(e.code().0, "NotifyIpInterfaceChange"));
+    }
+    Ok(InterfaceWatch {
+        rx,
+        _registration: Some(Arc::new(InterfaceRegistration { handle, context })),
+    })
+}
+
+pub fn watch_power() -> Result<PowerWatch, GError> {
+    let (tx, rx) = watch::channel(PowerState::default());
+    let params = Box::into_raw(Box::new(DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS {
+        Callback: Some(on_power_event),
+        Context: Box::into_raw(Box::new(tx)) as *mut c_void,
+    }));
+    let mut handle: *mut c_void = std::ptr::null_mut();
+    let registered = unsafe {
+        PowerRegisterSuspendResumeNotification(
+            DEVICE_NOTIFY_CALLBACK,
+            HANDLE(params as isize),
+            &mut handle,
+        )
+    };
+    if let Err(e) = registered {
+        unsafe {
+            let params = Box::from_raw(params);
+            drop(Box::from_raw(
+                params.Context as *mut watch::Sender<PowerState>,
+            ));
+        }
+        return Err(GError::from_hresult(
+            e.code().0,
+            "PowerRegisterSuspendResumeNotification",
+        ));
+    }
+    Ok(PowerWatch {
+        rx,
+        _registration: Some(Arc::new(PowerRegistration {
+            handle: handle as isize,
+            params,
+        })),
+    })
+}
+
+pub fn route_available(ip: Ipv4Addr) -> bool {
+    let mut if_index = 0u32;
+    unsafe { GetBestInterface(u32::from_le_bytes(ip.octets()), &mut if_index) == 0 }
+}
+
+pub fn echo(ip: Ipv4Addr, timeout: Duration) -> Result<Option<EchoReply>, GError> {
+    let raw_handle = unsafe { IcmpCreateFile() }
+        .map_err(|e| GError::from_hresult(e.code().0, "IcmpCreateFile"))?;
+
+    let handle = SafeHandle::new(raw_handle)?;
+
+    let dest_ip = u32::from_le_bytes(ip.octets());
+    let mut reply_buffer = [0u8; ECHO_REPLY_SIZE];
+
+    let ret = unsafe {
+        IcmpSendEcho(
+            handle.0,
+            dest_ip,
+            PING_PAYLOAD.as_ptr() as *const c_void,
+            PING_PAYLOAD.len() as u16,
+            None,
+            reply_buffer.as_mut_ptr() as *mut c_void,
+            ECHO_REPLY_SIZE as u32,
+            timeout.as_millis().clamp(1, u32::MAX as u128) as u32,
+        )
+    };
+
+    if ret == 0 {
+        return echo_failure(last_error(), "IcmpSendEcho");
+    }
+    Ok(echo_reply(&reply_buffer))
+}
+
+pub fn trace_hop(ip: Ipv4Addr, ttl: u8, timeout: Duration) -> Result<TraceHop, GError> {
+    let raw_handle = unsafe { IcmpCreateFile() }
+        .map_err(|e| GError::from_hresult(e.code().0, "IcmpCreateFile"))?;
+    let handle = SafeHandle::new(raw_handle)?;
+
+    let options = IP_OPTION_INFORMATION {
+        Ttl: ttl,
+        Tos: 0,
+        Flags: 0,
+        OptionsSize: 0,
+        OptionsData: std::ptr::null_mut(),
+    };
+    let mut reply_buffer = [0u8; ECHO_REPLY_SIZE];
+    let ret = unsafe {
+        IcmpSendEcho(
+            handle.0,
+            u32::from_le_bytes(ip.octets()),
+            PING_PAYLOAD.as_ptr() as *const c_void,
+            PING_PAYLOAD.len() as u16,
+            Some(&options),
+            reply_buffer.as_mut_ptr() as *mut c_void,
+            ECHO_REPLY_SIZE as u32,
+            timeout.as_millis().clamp(1, u32::MAX as u128) as u32,
+        )
+    };
+    let silent = TraceHop {
+        ttl,
+        addr: None,
+        rtt_ms: None,
+    };
+    if ret == 0 {
+        return echo_failure::<()>(last_error(), "IcmpSendEcho").map(|_| silent);
+    }
+    let reply =
+        unsafe { std::ptr::read_unaligned(reply_buffer.as_ptr() as *const ICMP_ECHO_REPLY) };
+    if reply.Status != 0 && reply.Status != IP_TTL_EXPIRED_TRANSIT {
+        return Ok(silent);
+    }
+    Ok(TraceHop {
+        ttl,
+        addr: Some(Ipv4Addr::from(reply.Address.to_le_bytes())),
+        rtt_ms: Some(reply.RoundTripTime),
+    })
+}
+
+pub async fn echo_async(ip: Ipv4Addr, timeout: Duration) -> Result<Option<EchoReply>, GError> {
+    let raw_handle = unsafe { IcmpCreateFile() }
+        .map_err(|e| GError::from_hresult(e.code().0, "IcmpCreateFile"))?;
+    let icmp = SafeHandle::new(raw_handle)?;
+    let event = unsafe { CreateEventW(None, false, false, None) }
+        .map_err(|e| GError::from_hresult(e.code().0, "CreateEventW"))?;
+
+    let (done_tx, done_rx) = oneshot::channel();
+    let pending = Arc::new(PendingEcho {
+        icmp,
+        event,
+        wait: Mutex::new(HANDLE(0)),
+        reply: UnsafeCell::new([0u8; ECHO_REPLY_SIZE]),
+        done: Mutex::new(Some(done_tx)),
+    });
+
+    let ret = unsafe {
+        IcmpSendEcho2(
+            pending.icmp.0,
+            pending.event,
+            None,
+            None,
+            u32::from_le_bytes(ip.octets()),
+            PING_PAYLOAD.as_ptr() as *const c_void,
+            PING_PAYLOAD.len() as u16,
+            None,
+            pending.reply.get() as *mut c_void,
+            ECHO_REPLY_SIZE as u32,
+            timeout.as_millis().clamp(1, u32::MAX as u128) as u32,
+        )
+    };
+    if ret == 0 {
+        let code = last_error();
+        if code != ERROR_IO_PENDING.0 {
+            return echo_failure(code, "IcmpSendEcho2");
+        }
+    }
+
+    // The service always signals the event, at the latest once `timeout` passes.
+    let context = Arc::into_raw(pending.clone()) as *const c_void;
+    let mut wait = HANDLE(0);
+    let registered = unsafe {
+        RegisterWaitForSingleObject(
+            &mut wait,
+            pending.event,
+            Some(echo_completed),
+            Some(context),
+            INFINITE,
+            WT_EXECUTEONLYONCE,
+        )
+    };
+    if let Err(e) = registered {
+        // The callback will never run, so reclaim its reference.
+        drop(unsafe { Arc::from_raw(context as *const PendingEcho) });
+        return Err(GError::from_hresult(
+            e.code().0,
+            "RegisterWaitForSingleObject",
+        ));
+    }
+    *pending.wait.lock().unwrap_or_else(|e| e.into_inner()) = wait;
+
+    if done_rx.await.is_err() {
+        return Err(GError::Internal("ICMP wait callback dropped".to_string()));
+    }
+    let reply = unsafe { &mut *pending.reply.get() };
+    if unsafe { IcmpParseReplies(reply.as_mut_ptr() as *mut c_void, ECHO_REPLY_SIZE as u32) } == 0 {
+        return echo_failure(last_error(), "IcmpSendEcho2");
+    }
+    Ok(echo_reply(reply))
+}
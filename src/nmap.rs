@@ -0,0 +1,347 @@
+//! Nmap-compatible XML output.
+//!
+//! Maps scan results onto the `nmaprun` schema of `nmap -oX` (host state,
+//! IPv4 and MAC addresses, hostnames, TCP and UDP ports with services, OS
+//! guess and round-trip times) so tools that ingest nmap XML, such as
+//! Faraday, Metasploit's `db_import` or EyeWitness, can read RageScanner
+//! results directly.
+
+use crate::report::format_utc;
+use crate::types::{ScanLabel, ScanResult, ScanStatus, udp_port_label};
+use std::fmt::Write;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Version of nmap's XML output the documents follow.
+pub const XML_OUTPUT_VERSION: &str = "1.05";
+
+/// Nmap's name for a TCP service, as in `nmap-services`.
+fn tcp_service_name(port: u16) -> Option<&'static str> {
+    Some(match port {
+        21 => "ftp",
+        22 => "ssh",
+        23 => "telnet",
+        25 => "smtp",
+        53 => "domain",
+        80 => "http",
+        110 => "pop3",
+        135 => "msrpc",
+        139 => "netbios-ssn",
+        443 => "https",
+        445 => "microsoft-ds",
+        1433 => "ms-sql-s",
+        3306 => "mysql",
+        3389 => "ms-wbt-server",
+        5432 => "postgresql",
+        8080 => "http-proxy",
+        _ => return None,
+    })
+}
+
+/// Nmap's name for a UDP service, as in `nmap-services`.
+fn udp_service_name(port: u16) -> Option<&'static str> {
+    Some(match port {
+        53 => "domain",
+        67 => "dhcps",
+        69 => "tftp",
+        123 => "ntp",
+        137 => "netbios-ns",
+        161 => "snmp",
+        500 => "isakmp",
+        1900 => "upnp",
+        5353 => "zeroconf",
+        _ => return None,
+    })
+}
+
+/// A service label such as `HTTP-Alt` or a user's `Proxmox` in nmap's
+/// lowercase style; `unknown` for ports without one.
+fn service_name(label: &str) -> String {
+    if label.eq_ignore_ascii_case("unknown") {
+        return "unknown".to_string();
+    }
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_ascii_lowercase()
+}
+
+/// Renders `results` as an nmap XML document, finished now.
+///
+/// Every result becomes a `<host>`: online hosts `up`, offline ones
+/// `down`, failed probes `unknown` and targets a cancelled scan never
+/// reached `skipped`. A label is kept as a comment after the doctype.
+pub fn render_xml(results: &[ScanResult], label: Option<&ScanLabel>) -> String {
+    render_xml_at(results, label, SystemTime::now())
+}
+
+fn render_xml_at(
+    results: &[ScanResult],
+    label: Option<&ScanLabel>,
+    finished: SystemTime,
+) -> String {
+    let finished_secs = unix_secs(finished);
+    // Results carry when they finished and how long they took; the scan
+    // started when the earliest of them did.
+    let start = results
+        .iter()
+        .filter_map(host_times)
+        .map(|(start, _)| start)
+        .min()
+        .unwrap_or(finished_secs);
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE nmaprun>\n");
+    if let Some(label) = label {
+        let _ = writeln!(out, "<!-- {} -->", xml_comment(&label.name));
+        if let Some(description) = &label.description {
+            let _ = writeln!(out, "<!-- {} -->", xml_comment(description));
+        }
+    }
+    let _ = writeln!(
+        out,
+        "<nmaprun scanner=\"ragescanner\" args=\"ragescanner\" start=\"{}\" startstr=\"{}\" version=\"{}\" xmloutputversion=\"{}\">",
+        start,
+        format_utc(UNIX_EPOCH + Duration::from_secs(start)),
+        env!("CARGO_PKG_VERSION"),
+        XML_OUTPUT_VERSION
+    );
+    out.push_str("<verbose level=\"0\"/>\n<debugging level=\"0\"/>\n");
+
+    let mut up = 0;
+    for res in results {
+        if res.status == ScanStatus::Online {
+            up += 1;
+        }
+        write_host(&mut out, res);
+    }
+
+    let _ = writeln!(
+        out,
+        "<runstats><finished time=\"{}\" timestr=\"{}\" elapsed=\"{}\" exit=\"success\"/><hosts up=\"{}\" down=\"{}\" total=\"{}\"/></runstats>",
+        finished_secs,
+        format_utc(finished),
+        finished_secs.saturating_sub(start),
+        up,
+        results.len() - up,
+        results.len()
+    );
+    out.push_str("</nmaprun>\n");
+    out
+}
+
+/// Appends one `<host>` element.
+fn write_host(out: &mut String, res: &ScanResult) {
+    let (state, reason) = match &res.status {
+        ScanStatus::Online if res.latency.is_some() => ("up", "echo-reply"),
+        ScanStatus::Online if res.mac.is_some() => ("up", "arp-response"),
+        ScanStatus::Online if !res.open_ports.is_empty() => ("up", "syn-ack"),
+        ScanStatus::Online => ("up", "user-set"),
+        ScanStatus::Offline | ScanStatus::Scanning => ("down", "no-response"),
+        ScanStatus::SystemError(_) => ("unknown", "error"),
+        ScanStatus::NotScanned => ("skipped", "user-set"),
+    };
+    match host_times(res) {
+        Some((start, end)) => {
+            let _ = writeln!(out, "<host starttime=\"{}\" endtime=\"{}\">", start, end);
+        }
+        None => out.push_str("<host>\n"),
+    }
+    let _ = writeln!(
+        out,
+        "<status state=\"{}\" reason=\"{}\" reason_ttl=\"0\"/>",
+        state, reason
+    );
+    let _ = writeln!(out, "<address addr=\"{}\" addrtype=\"ipv4\"/>", res.ip);
+    if let Some(mac) = &res.mac {
+        let _ = write!(
+            out,
+            "<address addr=\"{}\" addrtype=\"mac\"",
+            xml_escape(mac)
+        );
+        if let Some(vendor) = &res.vendor {
+            let _ = write!(out, " vendor=\"{}\"", xml_escape(vendor));
+        }
+        out.push_str("/>\n");
+    }
+    match &res.hostname {
+        Some(name) => {
+            let _ = writeln!(
+                out,
+                "<hostnames>\n<hostname name=\"{}\" type=\"PTR\"/>\n</hostnames>",
+                xml_escape(name)
+            );
+        }
+        None => out.push_str("<hostnames>\n</hostnames>\n"),
+    }
+
+    if !res.open_ports.is_empty()
+        || !res.open_udp_ports.is_empty()
+        || !res.filtered_udp_ports.is_empty()
+    {
+        out.push_str("<ports>\n");
+        for info in &res.open_ports {
+            let name = tcp_service_name(info.port)
+                .map(str::to_string)
+                .unwrap_or_else(|| service_name(&info.label()));
+            write_port(
+                out,
+                "tcp",
+                info.port,
+                "open",
+                "syn-ack",
+                &name,
+                info.banner.as_deref(),
+            );
+        }
+        for &port in &res.open_udp_ports {
+            let name = udp_service_name(port)
+                .map(str::to_string)
+                .unwrap_or_else(|| service_name(udp_port_label(port)));
+            write_port(out, "udp", port, "open", "udp-response", &name, None);
+        }
+        for &port in &res.filtered_udp_ports {
+            let name = udp_service_name(port)
+                .map(str::to_string)
+                .unwrap_or_else(|| service_name(udp_port_label(port)));
+            write_port(
+                out,
+                "udp",
+                port,
+                "open|filtered",
+                "no-response",
+                &name,
+                None,
+            );
+        }
+        out.push_str("</ports>\n");
+    }
+
+    if let Some(guess) = &res.os_guess {
+        let _ = writeln!(
+            out,
+            "<os>\n<osmatch name=\"{}\" accuracy=\"{}\" line=\"0\"/>\n</os>",
+            xml_escape(&guess.family.to_string()),
+            guess.confidence
+        );
+    }
+    if let Some(latency) = res.latency {
+        let srtt = (latency.avg_ms * 1000.0) as u64;
+        let rttvar = ((latency.max_ms - latency.min_ms) * 500.0) as u64;
+        let _ = writeln!(
+            out,
+            "<times srtt=\"{}\" rttvar=\"{}\" to=\"{}\"/>",
+            srtt,
+            rttvar,
+            (srtt + 4 * rttvar).max(100_000)
+        );
+    }
+    out.push_str("</host>\n");
+}
+
+/// Appends one `<port>` element with its state and service.
+fn write_port(
+    out: &mut String,
+    protocol: &str,
+    port: u16,
+    state: &str,
+    reason: &str,
+    service: &str,
+    banner: Option<&str>,
+) {
+    let _ = write!(
+        out,
+        "<port protocol=\"{}\" portid=\"{}\"><state state=\"{}\" reason=\"{}\" reason_ttl=\"0\"/><service name=\"{}\"",
+        protocol,
+        port,
+        state,
+        reason,
+        xml_escape(service)
+    );
+    if let Some(banner) = banner {
+        let _ = write!(out, " extrainfo=\"{}\"", xml_escape(banner));
+    }
+    out.push_str(" method=\"table\" conf=\"3\"/></port>\n");
+}
+
+/// When probing the host started and ended, in Unix seconds.
+fn host_times(res: &ScanResult) -> Option<(u64, u64)> {
+    let end = unix_secs(res.last_seen?);
+    let took = res.scan_duration.unwrap_or_default().as_secs();
+    Some((end.saturating_sub(took), end))
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Escapes text for XML content and quoted attributes.
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Makes text safe inside `<!-- -->`, which may not contain `--`, on one line.
+fn xml_comment(text: &str) -> String {
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    flat.replace("--", "- -")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{LatencyStats, PortInfo};
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_render_xml_maps_hosts_ports_and_services() {
+        let mut nas = ScanResult::new(Ipv4Addr::new(10, 0, 0, 5));
+        nas.status = ScanStatus::Online;
+        nas.hostname = Some("nas & co".to_string());
+        nas.mac = Some("00:11:32:AA:BB:CC".to_string());
+        nas.vendor = Some("Synology".to_string());
+        nas.latency = LatencyStats::from_samples(&[2, 4], 2);
+        nas.open_ports = vec![
+            PortInfo {
+                banner: Some("SSH-2.0-OpenSSH_9.6".to_string()),
+                ..PortInfo::new(22)
+            },
+            PortInfo::new(8006),
+        ];
+        nas.filtered_udp_ports = vec![161];
+        nas.last_seen = Some(UNIX_EPOCH + Duration::from_secs(1_000));
+        nas.scan_duration = Some(Duration::from_secs(3));
+        let gone = ScanResult::new(Ipv4Addr::new(10, 0, 0, 6));
+        let label = ScanLabel {
+            name: "Office -- weekly".to_string(),
+            description: None,
+        };
+
+        let xml = render_xml_at(
+            &[nas, gone],
+            Some(&label),
+            UNIX_EPOCH + Duration::from_secs(1_010),
+        );
+        assert!(xml.starts_with(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE nmaprun>\n<!-- Office - - weekly -->\n<nmaprun scanner=\"ragescanner\" args=\"ragescanner\" start=\"997\""
+        ));
+        assert!(xml.contains("<host starttime=\"997\" endtime=\"1000\">\n<status state=\"up\" reason=\"echo-reply\" reason_ttl=\"0\"/>\n<address addr=\"10.0.0.5\" addrtype=\"ipv4\"/>\n<address addr=\"00:11:32:AA:BB:CC\" addrtype=\"mac\" vendor=\"Synology\"/>\n<hostnames>\n<hostname name=\"nas &amp; co\" type=\"PTR\"/>\n</hostnames>\n"));
+        assert!(xml.contains("<port protocol=\"tcp\" portid=\"22\"><state state=\"open\" reason=\"syn-ack\" reason_ttl=\"0\"/><service name=\"ssh\" extrainfo=\"SSH-2.0-OpenSSH_9.6\" method=\"table\" conf=\"3\"/></port>"));
+        assert!(xml.contains("portid=\"8006\"><state state=\"open\" reason=\"syn-ack\" reason_ttl=\"0\"/><service name=\"unknown\""));
+        assert!(xml.contains("<port protocol=\"udp\" portid=\"161\"><state state=\"open|filtered\" reason=\"no-response\" reason_ttl=\"0\"/><service name=\"snmp\""));
+        assert!(xml.contains("<times srtt=\"3000\" rttvar=\"1000\" to=\"100000\"/>"));
+        assert!(xml.contains("<host>\n<status state=\"down\" reason=\"no-response\" reason_ttl=\"0\"/>\n<address addr=\"10.0.0.6\" addrtype=\"ipv4\"/>\n<hostnames>\n</hostnames>\n</host>"));
+        assert!(xml.ends_with("elapsed=\"13\" exit=\"success\"/><hosts up=\"1\" down=\"1\" total=\"2\"/></runstats>\n</nmaprun>\n"));
+    }
+
+    #[test]
+    fn test_service_names_follow_nmap_style() {
+        assert_eq!(service_name("HTTP-Alt"), "http-alt");
+        assert_eq!(service_name("My App"), "my-app");
+        assert_eq!(service_name("Unknown"), "unknown");
+    }
+}
@@ -0,0 +1,462 @@
+//! Notifications on scan events.
+//!
+//! A [`Notifier`] watches one scan's bridge messages and turns those its
+//! [`NotifyConfig`] subscribes to into [`Notification`]s: a finished scan, a
+//! device appearing in monitor mode, a monitored host changing MAC or name,
+//! an IP conflict, or a scan error. [`dispatch`] sends each
+//! one to the configured sinks, a JSON webhook (the same `POST` as health
+//! alarms) and a Windows toast. The bridge reads [`DEFAULT_NOTIFY_PATH`] at
+//! startup, so every frontend gets notifications without extra wiring.
+
+use crate::health::{parse_http_url, post_webhook};
+use crate::types::{BridgeMessage, GError, ScanStatus};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::net::Ipv4Addr;
+use std::path::Path;
+use std::sync::Arc;
+#[cfg(windows)]
+use windows::Data::Xml::Dom::XmlDocument;
+#[cfg(windows)]
+use windows::UI::Notifications::{ToastNotification, ToastNotificationManager, ToastTemplateType};
+#[cfg(windows)]
+use windows::Win32::System::WinRT::{RO_INIT_MULTITHREADED, RoInitialize};
+#[cfg(windows)]
+use windows::core::HSTRING;
+
+/// Notification settings read by the bridge at startup, next to `ragescanner.log`.
+pub const DEFAULT_NOTIFY_PATH: &str = "ragescanner.notify.json";
+
+/// Application id toasts are shown under.
+///
+/// Unpackaged programs cannot show toasts under their own id without a Start
+/// menu shortcut carrying it, so they borrow PowerShell's, which every
+/// Windows install registers.
+#[cfg(windows)]
+const TOAST_APP_ID: &str =
+    "{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\\WindowsPowerShell\\v1.0\\powershell.exe";
+
+/// Scan events that can raise a notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyTrigger {
+    /// A scan finished ([`BridgeMessage::ScanComplete`]).
+    ScanComplete,
+    /// Monitoring found a new device ([`BridgeMessage::DeviceAppeared`]).
+    DeviceAppeared,
+    /// A conflict watch saw another MAC answer ([`BridgeMessage::IpConflict`]).
+    IpConflict,
+    /// Monitoring saw a host's MAC or name change ([`BridgeMessage::SecurityAlert`]).
+    SecurityAlert,
+    /// A scan failed ([`BridgeMessage::Error`]).
+    Error,
+}
+
+/// Which events notify and where, usually read with [`NotifyConfig::from_file`].
+///
+/// ```json
+/// {
+///   "on": ["device_appeared", "error"],
+///   "webhook": "http://alerts.lan:8080/ragescanner",
+///   "toast": true
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotifyConfig {
+    /// Events to notify on; every trigger if omitted.
+    #[serde(default = "default_triggers")]
+    pub on: Vec<NotifyTrigger>,
+    /// `http://` URL that receives each notification as a JSON `POST`.
+    #[serde(default)]
+    pub webhook: Option<String>,
+    /// Show a Windows toast for each notification.
+    #[serde(default)]
+    pub toast: bool,
+}
+
+fn default_triggers() -> Vec<NotifyTrigger> {
+    vec![
+        NotifyTrigger::ScanComplete,
+        NotifyTrigger::DeviceAppeared,
+        NotifyTrigger::IpConflict,
+        NotifyTrigger::SecurityAlert,
+        NotifyTrigger::Error,
+    ]
+}
+
+impl NotifyConfig {
+    /// Reads and validates a JSON notification config.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if the file cannot be read or parsed, or
+    /// fails [`validate`](NotifyConfig::validate).
+    pub fn from_file(path: &Path) -> Result<Self, GError> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| GError::Internal(format!("Failed to read {}: {}", path.display(), e)))?;
+        let config: Self = serde_json::from_str(&json)
+            .map_err(|e| GError::Internal(format!("{}: {}", path.display(), e)))?;
+        config
+            .validate()
+            .map_err(|e| GError::Internal(format!("{}: {}", path.display(), e)))?;
+        Ok(config)
+    }
+
+    /// Reads [`DEFAULT_NOTIFY_PATH`]; `None` if the file does not exist.
+    ///
+    /// # Errors
+    ///
+    /// See [`from_file`](NotifyConfig::from_file).
+    pub fn load_default() -> Result<Option<Self>, GError> {
+        let path = Path::new(DEFAULT_NOTIFY_PATH);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Self::from_file(path).map(Some)
+    }
+
+    /// Checks that the webhook, if any, is a usable `http://` URL.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(url) = &self.webhook {
+            parse_http_url(url)?;
+        }
+        Ok(())
+    }
+}
+
+/// Something worth telling the user about.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Notification {
+    ScanComplete {
+        scanned: usize,
+        online: usize,
+    },
+    DeviceAppeared {
+        ip: Ipv4Addr,
+        mac: Option<String>,
+        hostname: Option<String>,
+        vendor: Option<String>,
+    },
+    IpConflict {
+        ip: Ipv4Addr,
+        previous: String,
+        current: String,
+    },
+    SecurityAlert {
+        ip: Ipv4Addr,
+        mac: Option<String>,
+        change: String,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl Notification {
+    /// Short heading, used as the toast title.
+    pub fn title(&self) -> &'static str {
+        match self {
+            Notification::ScanComplete { .. } => "Scan complete",
+            Notification::DeviceAppeared { .. } => "New device",
+            Notification::IpConflict { .. } => "IP conflict",
+            Notification::SecurityAlert { .. } => "Security alert",
+            Notification::Error { .. } => "Scan error",
+        }
+    }
+}
+
+impl fmt::Display for Notification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Notification::ScanComplete { scanned, online } => {
+                write!(f, "{} of {} hosts online", online, scanned)
+            }
+            Notification::DeviceAppeared {
+                ip,
+                mac,
+                hostname,
+                vendor,
+            } => {
+                write!(f, "{}", ip)?;
+                let details: Vec<&str> = [hostname, mac, vendor]
+                    .into_iter()
+                    .flatten()
+                    .map(String::as_str)
+                    .collect();
+                if !details.is_empty() {
+                    write!(f, " ({})", details.join(", "))?;
+                }
+                Ok(())
+            }
+            Notification::IpConflict {
+                ip,
+                previous,
+                current,
+            } => write!(f, "{} answered by {}, was {}", ip, current, previous),
+            Notification::SecurityAlert { ip, change, .. } => write!(f, "{}: {}", ip, change),
+            Notification::Error { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+/// Turns one scan's messages into notifications.
+pub struct Notifier {
+    config: Arc<NotifyConfig>,
+    scanned: usize,
+    online: usize,
+}
+
+impl Notifier {
+    pub fn new(config: Arc<NotifyConfig>) -> Self {
+        Self {
+            config,
+            scanned: 0,
+            online: 0,
+        }
+    }
+
+    /// Records `msg` and returns the notification it triggers, if the config
+    /// subscribes to it.
+    pub fn observe(&mut self, msg: &BridgeMessage) -> Option<Notification> {
+        let (trigger, notification) = match msg {
+            BridgeMessage::ScanUpdate(res) => {
+                if res.status != ScanStatus::NotScanned {
+                    self.scanned += 1;
+                }
+                if res.status == ScanStatus::Online {
+                    self.online += 1;
+                }
+                return None;
+            }
+            BridgeMessage::ScanComplete => (
+                NotifyTrigger::ScanComplete,
+                Notification::ScanComplete {
+                    scanned: self.scanned,
+                    online: self.online,
+                },
+            ),
+            BridgeMessage::DeviceAppeared(res) => (
+                NotifyTrigger::DeviceAppeared,
+                Notification::DeviceAppeared {
+                    ip: res.ip,
+                    mac: res.mac.clone(),
+                    hostname: res.hostname.clone(),
+                    vendor: res.vendor.clone(),
+                },
+            ),
+            BridgeMessage::IpConflict {
+                ip,
+                previous,
+                current,
+                ..
+            } => (
+                NotifyTrigger::IpConflict,
+                Notification::IpConflict {
+                    ip: *ip,
+                    previous: previous.clone(),
+                    current: current.clone(),
+                },
+            ),
+            BridgeMessage::SecurityAlert { host, change } => (
+                NotifyTrigger::SecurityAlert,
+                Notification::SecurityAlert {
+                    ip: host.ip,
+                    mac: host.mac.clone(),
+                    change: change.to_string(),
+                },
+            ),
+            BridgeMessage::Error(e) => (
+                NotifyTrigger::Error,
+                Notification::Error {
+                    message: e.to_string(),
+                },
+            ),
+            _ => return None,
+        };
+        self.config.on.contains(&trigger).then_some(notification)
+    }
+
+    /// Observes `msg` and dispatches what it triggers in the background,
+    /// logging failures. Must be called on a Tokio runtime.
+    pub fn handle(&mut self, msg: &BridgeMessage) {
+        let Some(notification) = self.observe(msg) else {
+            return;
+        };
+        let config = self.config.clone();
+        tokio::spawn(async move {
+            if let Err(e) = dispatch(&config, &notification).await {
+                log::warn!("Notification '{}' failed: {}", notification, e);
+            }
+        });
+    }
+}
+
+/// Sends `notification` to the webhook and as a toast, as configured.
+///
+/// Both are attempted even if one fails.
+///
+/// # Errors
+///
+/// Returns the first failure; see [`post_webhook`] and [`show_toast`].
+pub async fn dispatch(config: &NotifyConfig, notification: &Notification) -> Result<(), GError> {
+    let webhook = match &config.webhook {
+        Some(url) => post_webhook(url, notification).await,
+        None => Ok(()),
+    };
+    let toast = if config.toast {
+        let (title, body) = (notification.title(), notification.to_string());
+        tokio::task::spawn_blocking(move || show_toast(title, &body))
+            .await
+            .map_err(|e| GError::Internal(format!("Toast task failed: {}", e)))
+            .and_then(|shown| shown)
+    } else {
+        Ok(())
+    };
+    webhook.and(toast)
+}
+
+/// Shows a two-line Windows toast.
+///
+/// # Errors
+///
+/// Returns the error classified by [`GError::from_hresult`] if the toast cannot be
+/// built or shown.
+#[cfg(windows)]
+pub fn show_toast(title: &str, body: &str) -> Result<(), GError> {
+    let win_err = |e: windows::core::Error| GError::from_hresult(e.code().0, "Toast notification");
+    unsafe {
+        // Already initialized (S_FALSE) or in another apartment is fine too.
+        let _ = RoInitialize(RO_INIT_MULTITHREADED);
+    }
+    let content: XmlDocument =
+        ToastNotificationManager::GetTemplateContent(ToastTemplateType::ToastText02)
+            .map_err(win_err)?;
+    let lines = content
+        .GetElementsByTagName(&HSTRING::from("text"))
+        .map_err(win_err)?;
+    for (i, text) in [title, body].into_iter().enumerate() {
+        lines
+            .Item(i as u32)
+            .and_then(|node| node.SetInnerText(&HSTRING::from(text)))
+            .map_err(win_err)?;
+    }
+    let toast = ToastNotification::CreateToastNotification(&content).map_err(win_err)?;
+    ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(TOAST_APP_ID))
+        .and_then(|notifier| notifier.Show(&toast))
+        .map_err(win_err)
+}
+
+/// Toasts are a Windows feature; elsewhere only the webhook is notified.
+///
+/// # Errors
+///
+/// Always returns [`GError::Internal`].
+#[cfg(not(windows))]
+pub fn show_toast(title: &str, _body: &str) -> Result<(), GError> {
+    Err(GError::Internal(format!(
+        "Cannot show the '{}' toast outside Windows",
+        title
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{IdentityChange, ScanResult};
+
+    fn config(on: Vec<NotifyTrigger>) -> Arc<NotifyConfig> {
+        Arc::new(NotifyConfig {
+            on,
+            webhook: None,
+            toast: false,
+        })
+    }
+
+    #[test]
+    fn test_config_defaults_and_validation() {
+        let config: NotifyConfig = serde_json::from_str(r#"{"toast": true}"#).unwrap();
+        assert_eq!(config.on, default_triggers());
+        assert!(config.validate().is_ok());
+        let bad: NotifyConfig =
+            serde_json::from_str(r#"{"on": ["error"], "webhook": "https://x"}"#).unwrap();
+        assert!(bad.validate().is_err());
+        assert!(serde_json::from_str::<NotifyConfig>(r#"{"on": ["reboot"]}"#).is_err());
+    }
+
+    #[test]
+    fn test_notifier_counts_results_and_filters_triggers() {
+        let mut notifier = Notifier::new(config(vec![
+            NotifyTrigger::ScanComplete,
+            NotifyTrigger::DeviceAppeared,
+        ]));
+        let mut printer = ScanResult::new(Ipv4Addr::new(10, 0, 0, 7));
+        printer.status = ScanStatus::Online;
+        printer.mac = Some("AA:BB:CC:00:00:07".to_string());
+        printer.vendor = Some("Brother".to_string());
+        let mut skipped = ScanResult::new(Ipv4Addr::new(10, 0, 0, 8));
+        skipped.status = ScanStatus::NotScanned;
+        for res in [
+            printer.clone(),
+            ScanResult::new(Ipv4Addr::new(10, 0, 0, 9)),
+            skipped,
+        ] {
+            assert_eq!(notifier.observe(&BridgeMessage::ScanUpdate(res)), None);
+        }
+
+        let done = notifier.observe(&BridgeMessage::ScanComplete).unwrap();
+        assert_eq!(
+            done,
+            Notification::ScanComplete {
+                scanned: 2,
+                online: 1
+            }
+        );
+        assert_eq!(done.to_string(), "1 of 2 hosts online");
+
+        let appeared = notifier
+            .observe(&BridgeMessage::DeviceAppeared(printer))
+            .unwrap();
+        assert_eq!(
+            appeared.to_string(),
+            "10.0.0.7 (AA:BB:CC:00:00:07, Brother)"
+        );
+        assert_eq!(
+            serde_json::to_string(&appeared).unwrap(),
+            r#"{"event":"device_appeared","ip":"10.0.0.7","mac":"AA:BB:CC:00:00:07","hostname":null,"vendor":"Brother"}"#
+        );
+        // Not subscribed.
+        let error = BridgeMessage::Error(GError::Internal("boom".to_string()));
+        assert_eq!(notifier.observe(&error), None);
+
+        let conflict = BridgeMessage::IpConflict {
+            ip: Ipv4Addr::new(10, 0, 0, 7),
+            previous: "AA:BB:CC:00:00:07".to_string(),
+            current: "DE:AD:BE:EF:00:01".to_string(),
+            macs: Vec::new(),
+        };
+        assert_eq!(notifier.observe(&conflict), None);
+        let alert = Notifier::new(config(default_triggers()))
+            .observe(&conflict)
+            .unwrap();
+        assert_eq!(alert.title(), "IP conflict");
+        assert_eq!(
+            alert.to_string(),
+            "10.0.0.7 answered by DE:AD:BE:EF:00:01, was AA:BB:CC:00:00:07"
+        );
+
+        let mut moved = ScanResult::new(Ipv4Addr::new(10, 0, 0, 9));
+        moved.mac = Some("AA:BB:CC:00:00:07".to_string());
+        let alert = Notifier::new(config(default_triggers()))
+            .observe(&BridgeMessage::SecurityAlert {
+                host: Box::new(moved),
+                change: IdentityChange::MacMoved {
+                    previous_ip: Ipv4Addr::new(10, 0, 0, 7),
+                },
+            })
+            .unwrap();
+        assert_eq!(alert.title(), "Security alert");
+        assert_eq!(alert.to_string(), "10.0.0.9: MAC moved here from 10.0.0.7");
+    }
+}
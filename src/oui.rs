@@ -0,0 +1,139 @@
+//! Keeping the vendor database current.
+//!
+//! The OUI registry bundled with `mac_oui` ages with the crate. [`update`]
+//! downloads the IEEE's current `oui.csv` and switches vendor lookups to it;
+//! load an existing copy with [`NetUtils::with_oui_file`](crate::net::NetUtils::with_oui_file).
+//!
+//! Phones and laptops increasingly use a random, locally administered MAC
+//! per network. No registry can name their maker, so [`is_randomized`]
+//! lets lookups say so instead of leaving the vendor blank.
+
+use crate::net;
+use crate::types::GError;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_native_tls::{TlsConnector, native_tls};
+
+/// Where the IEEE publishes the MA-L registry.
+pub const IEEE_OUI_URL: &str = "https://standards-oui.ieee.org/oui/oui.csv";
+
+const IEEE_OUI_HOST: &str = "standards-oui.ieee.org";
+const IEEE_OUI_PATH: &str = "/oui/oui.csv";
+
+/// Budget for the whole download; the registry is several megabytes.
+pub const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Vendor reported for locally administered MACs.
+pub const RANDOMIZED_MAC_VENDOR: &str = "Randomized MAC";
+
+/// Returns `true` if `mac` has the locally administered bit set, as the
+/// per-network random addresses of modern phones and laptops do.
+///
+/// Accepts `:` or `-` separated hex; anything unparseable is `false`.
+pub fn is_randomized(mac: &str) -> bool {
+    mac.get(..2)
+        .and_then(|octet| u8::from_str_radix(octet, 16).ok())
+        .is_some_and(|first| first & 0x02 != 0)
+}
+
+/// Downloads the current registry from [`IEEE_OUI_URL`] to `dest` and
+/// switches vendor lookups to it.
+///
+/// The file is only replaced once the download parses, so a failed update
+/// leaves both `dest` and the database in use untouched. Returns the number
+/// of records loaded.
+///
+/// # Errors
+///
+/// Returns [`GError::Internal`] if the download fails or times out after
+/// [`DOWNLOAD_TIMEOUT`], the server does not answer `200`, the data is not
+/// an OUI CSV, or `dest` cannot be written.
+pub async fn update(dest: &Path) -> Result<usize, GError> {
+    let response = tokio::time::timeout(DOWNLOAD_TIMEOUT, download())
+        .await
+        .map_err(|_| GError::Timeout(format!("Download of {} took too long", IEEE_OUI_URL)))?
+        .map_err(|e| GError::Internal(format!("Download of {} failed: {}", IEEE_OUI_URL, e)))?;
+    let csv = response_body(&response).map_err(GError::Internal)?;
+
+    let tmp = dest.with_extension("tmp");
+    fs::write(&tmp, csv).map_err(|e| {
+        GError::Internal(format!(
+            "Failed to write OUI database '{}': {}",
+            tmp.display(),
+            e
+        ))
+    })?;
+    if let Err(e) = mac_oui::Oui::from_csv_file(&tmp) {
+        let _ = fs::remove_file(&tmp);
+        return Err(GError::Internal(format!(
+            "Downloaded OUI registry is unreadable: {}",
+            e
+        )));
+    }
+    fs::rename(&tmp, dest).map_err(|e| {
+        GError::Internal(format!(
+            "Failed to move OUI database to '{}': {}",
+            dest.display(),
+            e
+        ))
+    })?;
+    net::set_oui_database(Some(dest.to_path_buf()))
+}
+
+/// Fetches the registry over HTTPS. HTTP/1.0 keeps the body unchunked.
+async fn download() -> std::io::Result<Vec<u8>> {
+    let connector = native_tls::TlsConnector::new().map_err(std::io::Error::other)?;
+    let stream = TcpStream::connect((IEEE_OUI_HOST, 443)).await?;
+    let mut tls = TlsConnector::from(connector)
+        .connect(IEEE_OUI_HOST, stream)
+        .await
+        .map_err(std::io::Error::other)?;
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: ragescanner\r\nAccept: text/csv\r\n\r\n",
+        IEEE_OUI_PATH, IEEE_OUI_HOST
+    );
+    tls.write_all(request.as_bytes()).await?;
+    let mut response = Vec::new();
+    tls.read_to_end(&mut response).await?;
+    Ok(response)
+}
+
+/// Returns the body of a `200` response.
+fn response_body(response: &[u8]) -> Result<&[u8], String> {
+    let split = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or("Truncated response from the OUI registry")?;
+    let head = String::from_utf8_lossy(&response[..split]);
+    let status = head.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(format!("OUI registry answered '{}'", status));
+    }
+    Ok(&response[split + 4..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_randomized_checks_the_locally_administered_bit() {
+        assert!(is_randomized("DA:A1:19:00:00:01"));
+        assert!(is_randomized("02-00-00-00-00-00"));
+        assert!(!is_randomized("00:11:22:33:44:55"));
+        assert!(!is_randomized("F4:5C:89:00:00:01"));
+        assert!(!is_randomized(""));
+    }
+
+    #[test]
+    fn test_response_body_requires_200() {
+        let ok = b"HTTP/1.1 200 OK\r\nContent-Type: text/csv\r\n\r\nRegistry,Assignment\r\n";
+        assert_eq!(response_body(ok), Ok(&b"Registry,Assignment\r\n"[..]));
+        let moved = b"HTTP/1.1 301 Moved Permanently\r\nLocation: /\r\n\r\n";
+        assert!(response_body(moved).unwrap_err().contains("301"));
+        assert!(response_body(b"HTTP/1.1 200 OK\r\n").is_err());
+    }
+}
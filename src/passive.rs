@@ -0,0 +1,257 @@
+//! Passive discovery: watching the OS neighbor cache instead of probing.
+//!
+//! On networks where active scanning is not allowed, hosts still land in the
+//! ARP/neighbor table as they talk to this machine or broadcast. [`ArpWatch`]
+//! reads that table on an interval, and again whenever an interface changes,
+//! and reports new entries as [`BridgeMessage::DeviceAppeared`] without
+//! sending a single packet: no echoes, no ARP requests, no DNS lookups.
+
+use crate::net::NetworkProvider;
+use crate::targets::ScanTargets;
+use crate::types::{BridgeMessage, GError, ScanResult, ScanStatus};
+use std::collections::BTreeMap;
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc::Sender;
+use tokio_util::sync::CancellationToken;
+
+/// Time between two reads of the neighbor table if none is given.
+pub const DEFAULT_ARP_WATCH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Shortest interval the bridge accepts between two reads.
+pub const MIN_ARP_WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Watches the neighbor table and reports hosts as they show up in it.
+pub struct ArpWatch {
+    net: Arc<dyn NetworkProvider>,
+    tx: Sender<BridgeMessage>,
+    targets: Option<ScanTargets>,
+    /// Every host seen so far, keyed by address.
+    known: BTreeMap<Ipv4Addr, ScanResult>,
+}
+
+impl ArpWatch {
+    /// Creates a watch reporting to `tx`. With `targets`, entries outside
+    /// them are ignored.
+    pub fn new(
+        net: Arc<dyn NetworkProvider>,
+        tx: Sender<BridgeMessage>,
+        targets: Option<ScanTargets>,
+    ) -> Self {
+        Self {
+            net,
+            tx,
+            targets,
+            known: BTreeMap::new(),
+        }
+    }
+
+    /// Reads the neighbor table every `interval`, or sooner if an interface
+    /// changes, until `cancel_token` fires, then sends
+    /// [`BridgeMessage::ScanCancelled`].
+    ///
+    /// Every host is sent once as an online [`BridgeMessage::ScanUpdate`]
+    /// with its MAC and vendor. Hosts in the first read only set the
+    /// baseline; later ones are followed by a
+    /// [`BridgeMessage::DeviceAppeared`], and a known address answering from
+    /// a new MAC by a [`BridgeMessage::DeviceChanged`]. Entries aging out of
+    /// the cache say nothing about the host, so they are not reported, and
+    /// a host that returns is not reported again.
+    ///
+    /// A failed read stops the watch after forwarding the error.
+    pub async fn run(mut self, interval: Duration, cancel_token: CancellationToken) {
+        let mut iface = match self.net.watch_interfaces() {
+            Ok(watch) => Some(watch),
+            Err(e) => {
+                log::warn!("Interface changes will not trigger ARP cache reads: {}", e);
+                None
+            }
+        };
+        let mut baseline = true;
+
+        loop {
+            let net = self.net.clone();
+            let table = match tokio::task::spawn_blocking(move || net.read_arp_table()).await {
+                Ok(result) => result,
+                Err(e) => Err(GError::Internal(format!("ARP cache read panicked: {}", e))),
+            };
+            let table = match table {
+                Ok(table) => table,
+                Err(e) => {
+                    let _ = self.tx.send(BridgeMessage::Error(e)).await;
+                    return;
+                }
+            };
+            let events = self.update(table, baseline, SystemTime::now());
+            if baseline {
+                log::info!("ARP watch baseline: {} hosts", self.known.len());
+                baseline = false;
+            }
+            for event in events {
+                let _ = self.tx.send(event).await;
+            }
+
+            tokio::select! {
+                _ = cancel_token.cancelled() => break,
+                _ = tokio::time::sleep(interval) => {}
+                _ = async {
+                    match &mut iface {
+                        Some(iface) => iface.changed().await,
+                        None => std::future::pending().await,
+                    }
+                } => log::debug!("Interface change, re-reading the ARP cache"),
+            }
+        }
+
+        log::info!("ARP watch stopped.");
+        let _ = self.tx.send(BridgeMessage::ScanCancelled).await;
+    }
+
+    /// Records a read of the table and returns the messages it produces,
+    /// ordered by address.
+    fn update(
+        &mut self,
+        table: impl IntoIterator<Item = (Ipv4Addr, String)>,
+        baseline: bool,
+        now: SystemTime,
+    ) -> Vec<BridgeMessage> {
+        let mut table: Vec<(Ipv4Addr, String)> = table
+            .into_iter()
+            .filter(|(ip, _)| self.targets.as_ref().is_none_or(|t| t.contains(*ip)))
+            .collect();
+        table.sort();
+
+        let mut messages = Vec::new();
+        for (ip, mac) in table {
+            let previous = self.known.get(&ip);
+            if previous.is_some_and(|p| p.mac.as_deref() == Some(mac.as_str())) {
+                continue;
+            }
+            let mut res = ScanResult::new(ip);
+            res.status = ScanStatus::Online;
+            res.vendor = self.net.resolve_vendor(&mac);
+            res.mac = Some(mac);
+            res.stamp(Duration::ZERO, now);
+            if let Some(previous) = previous {
+                res.merge_seen(previous);
+            }
+            messages.push(BridgeMessage::ScanUpdate(res.clone()));
+            match previous {
+                Some(previous) => {
+                    messages.push(BridgeMessage::DeviceChanged {
+                        previous: Box::new(previous.clone()),
+                        current: Box::new(res.clone()),
+                    });
+                }
+                None if !baseline => messages.push(BridgeMessage::DeviceAppeared(res.clone())),
+                None => {}
+            }
+            self.known.insert(ip, res);
+        }
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::MockNet;
+    use crate::types::UdpPortState;
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use tokio::sync::mpsc::channel;
+
+    /// Serves a scripted sequence of ARP tables and panics on any probe.
+    struct CacheOnlyNet {
+        tables: Mutex<Vec<HashMap<Ipv4Addr, String>>>,
+    }
+
+    #[async_trait]
+    impl NetworkProvider for CacheOnlyNet {
+        fn echo(&self, _ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+            panic!("the ARP watch sent an echo");
+        }
+        fn resolve_mac(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            panic!("the ARP watch sent an ARP request");
+        }
+        fn resolve_hostname(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            panic!("the ARP watch looked up a name");
+        }
+        fn resolve_vendor(&self, mac: &str) -> Option<String> {
+            MockNet.resolve_vendor(mac)
+        }
+        fn read_arp_table(&self) -> Result<HashMap<Ipv4Addr, String>, GError> {
+            let mut tables = self.tables.lock().unwrap();
+            match tables.len() {
+                0 => Err(GError::Internal("No more tables".to_string())),
+                1 => Ok(tables[0].clone()),
+                _ => Ok(tables.remove(0)),
+            }
+        }
+        async fn scan_port(&self, _ip: Ipv4Addr, _port: u16) -> bool {
+            panic!("the ARP watch probed a port");
+        }
+        async fn scan_udp_port(&self, _ip: Ipv4Addr, _port: u16) -> UdpPortState {
+            panic!("the ARP watch probed a UDP port");
+        }
+    }
+
+    fn table(entries: &[(u8, &str)]) -> HashMap<Ipv4Addr, String> {
+        entries
+            .iter()
+            .map(|&(last, mac)| (Ipv4Addr::new(10, 0, 0, last), mac.to_string()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_reports_new_cache_entries_without_probing() {
+        let net = CacheOnlyNet {
+            tables: Mutex::new(vec![
+                table(&[(1, "AA:AA:AA:AA:AA:AA"), (200, "EE:EE:EE:EE:EE:EE")]),
+                table(&[
+                    (1, "AA:AA:AA:AA:AA:AA"),
+                    (2, "BB:BB:BB:BB:BB:BB"),
+                    (200, "EE:EE:EE:EE:EE:EE"),
+                ]),
+                // .2 aged out and .1 moved to another device.
+                table(&[(1, "CC:CC:CC:CC:CC:CC")]),
+            ]),
+        };
+        let (tx, mut rx) = channel(100);
+        let targets = ScanTargets::parse("10.0.0.0/25").ok();
+        let watch = ArpWatch::new(Arc::new(net), tx, targets);
+        let token = CancellationToken::new();
+        let task = tokio::spawn(watch.run(Duration::from_millis(10), token.clone()));
+
+        let mut messages = Vec::new();
+        while messages.len() < 5 {
+            messages.push(rx.recv().await.unwrap());
+        }
+        token.cancel();
+        while let Some(msg) = rx.recv().await {
+            messages.push(msg);
+        }
+        task.await.unwrap();
+
+        let ip = |last| Ipv4Addr::new(10, 0, 0, last);
+        // Baseline: .1 only, as .200 is outside the targets.
+        assert!(matches!(&messages[0], BridgeMessage::ScanUpdate(r)
+            if r.ip == ip(1) && r.status == ScanStatus::Online
+                && r.vendor.as_deref() == Some("Mock Vendor") && r.first_seen.is_some()));
+        assert!(matches!(&messages[1], BridgeMessage::ScanUpdate(r) if r.ip == ip(2)));
+        assert!(matches!(&messages[2], BridgeMessage::DeviceAppeared(r) if r.ip == ip(2)));
+        assert!(matches!(&messages[3], BridgeMessage::ScanUpdate(r) if r.ip == ip(1)));
+        assert!(
+            matches!(&messages[4], BridgeMessage::DeviceChanged { previous, current }
+            if previous.mac.as_deref() == Some("AA:AA:AA:AA:AA:AA")
+                && current.mac.as_deref() == Some("CC:CC:CC:CC:CC:CC"))
+        );
+        assert!(matches!(
+            messages.last(),
+            Some(BridgeMessage::ScanCancelled)
+        ));
+        assert_eq!(messages.len(), 6);
+    }
+}
@@ -0,0 +1,178 @@
+//! Custom enrichment steps supplied by downstream crates.
+//!
+//! A [`Probe`] runs on every online host after the built-in probes, sees the
+//! result so far and the scan's [`NetworkProvider`], and returns values that
+//! land in [`ScanResult::extras`]. Checking a proprietary agent port, say,
+//! then needs no fork of the scan pipeline:
+//!
+//! ```
+//! use ragescanner::net::{BoxFuture, NetworkProvider};
+//! use ragescanner::probe::{Probe, ProbeOutcome, ProbeRegistry};
+//! use ragescanner::types::ScanResult;
+//!
+//! struct AgentProbe;
+//!
+//! impl Probe for AgentProbe {
+//!     fn name(&self) -> &str {
+//!         "agent"
+//!     }
+//!
+//!     fn probe<'a>(
+//!         &'a self,
+//!         target: &'a ScanResult,
+//!         net: &'a dyn NetworkProvider,
+//!     ) -> BoxFuture<'a, ProbeOutcome> {
+//!         Box::pin(async move {
+//!             if net.scan_port(target.ip, 9100).await {
+//!                 ProbeOutcome::found("agent", "listening")
+//!             } else {
+//!                 ProbeOutcome::Skipped
+//!             }
+//!         })
+//!     }
+//! }
+//!
+//! let mut probes = ProbeRegistry::default();
+//! probes.register(AgentProbe);
+//! ```
+
+use crate::net::{BoxFuture, NetworkProvider};
+use crate::types::ScanResult;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// What a [`Probe`] found on one host.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ProbeOutcome {
+    /// Nothing to record, e.g. the agent port is closed.
+    #[default]
+    Skipped,
+    /// Values merged into [`ScanResult::extras`], replacing equal keys.
+    Found(HashMap<String, String>),
+}
+
+impl ProbeOutcome {
+    /// A single value stored under `key`.
+    pub fn found(key: impl Into<String>, value: impl Into<String>) -> Self {
+        ProbeOutcome::Found(HashMap::from([(key.into(), value.into())]))
+    }
+}
+
+/// An enrichment step run on online hosts.
+///
+/// `probe` is `async fn probe(&self, target: &ScanResult, net: &dyn
+/// NetworkProvider) -> ProbeOutcome` written out as a boxed future, like the
+/// methods of [`NetworkProvider`], so probes can be stored as trait objects.
+pub trait Probe: Send + Sync {
+    /// Short name for logs.
+    fn name(&self) -> &str;
+
+    /// Probes `target`, which holds everything the scan found so far.
+    fn probe<'a>(
+        &'a self,
+        target: &'a ScanResult,
+        net: &'a dyn NetworkProvider,
+    ) -> BoxFuture<'a, ProbeOutcome>;
+}
+
+/// The custom probes of a scan, run in registration order.
+///
+/// Attach one with [`Scanner::with_probes`](crate::scanner::Scanner::with_probes)
+/// or [`Scanner::start_with_probes`](crate::scanner::Scanner::start_with_probes).
+#[derive(Clone, Default)]
+pub struct ProbeRegistry {
+    probes: Vec<Arc<dyn Probe>>,
+}
+
+impl ProbeRegistry {
+    /// Adds `probe` after the ones already registered.
+    pub fn register(&mut self, probe: impl Probe + 'static) {
+        self.probes.push(Arc::new(probe));
+    }
+
+    /// Returns `true` if no probe is registered.
+    pub fn is_empty(&self) -> bool {
+        self.probes.is_empty()
+    }
+
+    /// Runs every probe on `target` and stores what they find in its `extras`.
+    ///
+    /// Each probe sees the values stored by the probes before it.
+    pub async fn run(&self, target: &mut ScanResult, net: &dyn NetworkProvider) {
+        for probe in &self.probes {
+            if let ProbeOutcome::Found(values) = probe.probe(target, net).await {
+                log::info!(
+                    "Probe '{}' found {} values on {}",
+                    probe.name(),
+                    values.len(),
+                    target.ip
+                );
+                target.extras.extend(values);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ProbeRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.probes.iter().map(|p| p.name()))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::MockNet;
+    use std::net::Ipv4Addr;
+
+    /// Reports an agent if its port is open, and how many extras it saw.
+    struct Agent(u16);
+
+    impl Probe for Agent {
+        fn name(&self) -> &str {
+            "agent"
+        }
+
+        fn probe<'a>(
+            &'a self,
+            target: &'a ScanResult,
+            net: &'a dyn NetworkProvider,
+        ) -> BoxFuture<'a, ProbeOutcome> {
+            Box::pin(async move {
+                if !net.scan_port(target.ip, self.0).await {
+                    return ProbeOutcome::Skipped;
+                }
+                let seen = target.extras.len().to_string();
+                ProbeOutcome::Found(HashMap::from([
+                    ("agent".to_string(), self.0.to_string()),
+                    ("seen".to_string(), seen),
+                ]))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_registry_runs_probes_in_order_and_merges_extras() {
+        let mut probes = ProbeRegistry::default();
+        assert!(probes.is_empty());
+        probes.register(Agent(22));
+        probes.register(Agent(80));
+        probes.register(Agent(80));
+        assert_eq!(format!("{:?}", probes), r#"["agent", "agent", "agent"]"#);
+
+        let mut host = ScanResult::new(Ipv4Addr::new(10, 0, 0, 1));
+        probes.run(&mut host, &MockNet).await;
+        assert_eq!(host.extras["agent"], "80");
+        // The last probe saw the values of the one before it.
+        assert_eq!(host.extras["seen"], "2");
+
+        let mut closed = ProbeRegistry::default();
+        closed.register(Agent(22));
+        let mut host = ScanResult::new(Ipv4Addr::new(10, 0, 0, 1));
+        closed.run(&mut host, &MockNet).await;
+        assert!(host.extras.is_empty());
+    }
+}
@@ -0,0 +1,298 @@
+//! Reachability matrix: which hosts accept TCP connections on which ports.
+//!
+//! The classic "which servers can I reach on which ports from here"
+//! troubleshooting table. [`resolve_hosts`] turns addresses, ranges and host
+//! names into rows, [`probe`] connects to every host and port pair, and a
+//! [`ReachMatrix`] renders as JSON, CSV or a host × port table.
+
+use crate::export::{self, OutputFormat};
+use crate::net::NetworkProvider;
+use crate::targets::ScanTargets;
+use crate::types::{GError, TcpProbeOptions};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::Path;
+use std::time::Duration;
+
+/// Connections [`probe`] has in flight at once by default.
+pub const DEFAULT_MATRIX_CONCURRENCY: usize = 64;
+/// Most rows a matrix may have once ranges are expanded.
+pub const MAX_MATRIX_HOSTS: usize = 256;
+
+/// A row of the matrix: a host as it was given, and its address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReachHost {
+    /// The name or address the host was given as.
+    pub name: String,
+    /// `None` if the name did not resolve to an IPv4 address.
+    pub ip: Option<Ipv4Addr>,
+}
+
+impl ReachHost {
+    /// A host given by its address.
+    pub fn from_ip(ip: Ipv4Addr) -> Self {
+        Self {
+            name: ip.to_string(),
+            ip: Some(ip),
+        }
+    }
+}
+
+/// One host's results.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReachRow {
+    #[serde(flatten)]
+    pub host: ReachHost,
+    /// Ports that accepted a connection, in the matrix's port order.
+    pub open: Vec<u16>,
+}
+
+/// Which ports of which hosts accepted a TCP connection.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReachMatrix {
+    /// The columns, in the order they were given.
+    pub ports: Vec<u16>,
+    /// The rows, in the order the hosts were given.
+    pub rows: Vec<ReachRow>,
+}
+
+impl ReachMatrix {
+    /// Whether every resolved host accepted every port and no name failed
+    /// to resolve.
+    pub fn all_reachable(&self) -> bool {
+        self.rows
+            .iter()
+            .all(|row| row.host.ip.is_some() && row.open.len() == self.ports.len())
+    }
+
+    /// The matrix in `format`: a JSON object, a CSV with a `yes`/`no`
+    /// column per port, or a table marking reachable cells `open` and the
+    /// rest `-`. Rows of names that did not resolve show `?` in every cell
+    /// (empty in CSV).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] for [`OutputFormat::NmapXml`], which has
+    /// no room for a matrix, or if JSON serialization fails.
+    pub fn render(&self, format: OutputFormat) -> Result<String, GError> {
+        match format {
+            OutputFormat::Json => serde_json::to_string_pretty(self)
+                .map_err(|e| GError::Internal(format!("Failed to serialize matrix: {}", e))),
+            OutputFormat::Csv => Ok(self.render_csv()),
+            OutputFormat::Table => Ok(self.render_table()),
+            OutputFormat::NmapXml => Err(GError::Internal(
+                "A reachability matrix can only be written as JSON, CSV or a table".to_string(),
+            )),
+        }
+    }
+
+    /// Writes the matrix to `path` in the format its extension names (see
+    /// [`OutputFormat::from_path`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if rendering or writing the file fails.
+    pub fn write_file(&self, path: &Path) -> Result<(), GError> {
+        let text = self.render(OutputFormat::from_path(path))?;
+        fs::write(path, text).map_err(|e| {
+            GError::Internal(format!(
+                "Failed to write matrix '{}': {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    fn render_csv(&self) -> String {
+        let mut out = String::from("host,ip");
+        for port in &self.ports {
+            out.push_str(&format!(",{}", port));
+        }
+        out.push('\n');
+        for row in &self.rows {
+            out.push_str(&export::csv_field(&row.host.name));
+            out.push(',');
+            if let Some(ip) = row.host.ip {
+                out.push_str(&ip.to_string());
+            }
+            for port in &self.ports {
+                out.push(',');
+                if row.host.ip.is_some() {
+                    out.push_str(if row.open.contains(port) { "yes" } else { "no" });
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    fn render_table(&self) -> String {
+        let ports: Vec<String> = self.ports.iter().map(u16::to_string).collect();
+        let header: Vec<&str> = ["HOST"]
+            .into_iter()
+            .chain(ports.iter().map(String::as_str))
+            .collect();
+        let rows: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let mut cells = vec![row.host.name.clone()];
+                cells.extend(self.ports.iter().map(|port| {
+                    match (row.host.ip, row.open.contains(port)) {
+                        (None, _) => "?",
+                        (Some(_), true) => "open",
+                        (Some(_), false) => "-",
+                    }
+                    .to_string()
+                }));
+                cells
+            })
+            .collect();
+        export::render_text_table(&header, &rows)
+    }
+}
+
+/// Turns comma-separated hosts into matrix rows, in order.
+///
+/// Entries [`ScanTargets::parse`] accepts (addresses, ranges, CIDR blocks)
+/// become a row per address; anything else is looked up as a host name,
+/// keeping its first IPv4 address. Names that do not resolve still get a
+/// row, without an address.
+///
+/// # Errors
+///
+/// Returns [`GError::Internal`] if no hosts are given or they expand to
+/// more than [`MAX_MATRIX_HOSTS`] rows.
+pub async fn resolve_hosts(spec: &str) -> Result<Vec<ReachHost>, GError> {
+    let mut hosts = Vec::new();
+    for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        if let Ok(targets) = ScanTargets::parse(entry) {
+            for &(start, end) in targets.ranges() {
+                let count = u64::from(u32::from(end)) - u64::from(u32::from(start)) + 1;
+                if hosts.len() as u64 + count > MAX_MATRIX_HOSTS as u64 {
+                    return Err(too_many_hosts());
+                }
+                hosts.extend(
+                    (u32::from(start)..=u32::from(end)).map(|ip| ReachHost::from_ip(ip.into())),
+                );
+            }
+            continue;
+        }
+        let ip = match tokio::net::lookup_host((entry, 0)).await {
+            Ok(mut addrs) => addrs.find_map(|addr| match addr.ip() {
+                IpAddr::V4(ip) => Some(ip),
+                IpAddr::V6(_) => None,
+            }),
+            Err(e) => {
+                log::warn!("Cannot resolve {}: {}", entry, e);
+                None
+            }
+        };
+        if hosts.len() == MAX_MATRIX_HOSTS {
+            return Err(too_many_hosts());
+        }
+        hosts.push(ReachHost {
+            name: entry.to_string(),
+            ip,
+        });
+    }
+    if hosts.is_empty() {
+        return Err(GError::Internal(
+            "No hosts given for the matrix".to_string(),
+        ));
+    }
+    Ok(hosts)
+}
+
+fn too_many_hosts() -> GError {
+    GError::Internal(format!("A matrix holds at most {} hosts", MAX_MATRIX_HOSTS))
+}
+
+/// Connects to every port of every resolved host, `concurrency` at a time,
+/// waiting `timeout` for each.
+pub async fn probe(
+    net: &dyn NetworkProvider,
+    hosts: Vec<ReachHost>,
+    ports: &[u16],
+    timeout: Duration,
+    concurrency: usize,
+) -> ReachMatrix {
+    let pairs = hosts
+        .iter()
+        .enumerate()
+        .filter_map(|(row, host)| Some((row, host.ip?)))
+        .flat_map(|(row, ip)| ports.iter().map(move |&port| (row, ip, port)))
+        .collect::<Vec<_>>();
+    let open: Vec<(usize, u16)> = futures::stream::iter(pairs)
+        .map(|(row, ip, port)| async move {
+            let reachable = net
+                .scan_port_with_options(ip, port, timeout, TcpProbeOptions::default())
+                .await;
+            reachable.then_some((row, port))
+        })
+        .buffer_unordered(concurrency.max(1))
+        .filter_map(|found| async move { found })
+        .collect()
+        .await;
+
+    let rows = hosts
+        .into_iter()
+        .enumerate()
+        .map(|(i, host)| ReachRow {
+            host,
+            open: ports
+                .iter()
+                .copied()
+                .filter(|&port| open.contains(&(i, port)))
+                .collect(),
+        })
+        .collect();
+    ReachMatrix {
+        ports: ports.to_vec(),
+        rows,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::MockNet;
+
+    #[tokio::test]
+    async fn test_matrix_marks_open_ports_and_unresolved_names() {
+        let mut hosts = resolve_hosts("192.168.1.1-2, 10.0.0.0/31").await.unwrap();
+        assert_eq!(hosts.len(), 4);
+        assert_eq!(hosts[2], ReachHost::from_ip(Ipv4Addr::new(10, 0, 0, 0)));
+        hosts.truncate(1);
+        hosts.push(ReachHost {
+            name: "gone.invalid".to_string(),
+            ip: None,
+        });
+
+        // MockNet accepts connections on port 80 only.
+        let matrix = probe(&MockNet, hosts, &[443, 80], Duration::from_millis(50), 4).await;
+        assert_eq!(matrix.rows[0].open, [80]);
+        assert!(matrix.rows[1].open.is_empty());
+        assert!(!matrix.all_reachable());
+
+        let table = matrix.render(OutputFormat::Table).unwrap();
+        assert_eq!(
+            table,
+            "HOST          443  80\n192.168.1.1   -    open\ngone.invalid  ?    ?\n"
+        );
+        let csv = matrix.render(OutputFormat::Csv).unwrap();
+        assert_eq!(
+            csv,
+            "host,ip,443,80\n192.168.1.1,192.168.1.1,no,yes\ngone.invalid,,,\n"
+        );
+        let json: ReachMatrix =
+            serde_json::from_str(&matrix.render(OutputFormat::Json).unwrap()).unwrap();
+        assert_eq!(json, matrix);
+        assert!(matrix.render(OutputFormat::NmapXml).is_err());
+
+        assert!(resolve_hosts(" , ").await.is_err());
+        assert!(resolve_hosts("10.0.0.0/23").await.is_err());
+    }
+}
@@ -0,0 +1,613 @@
+//! Host and scan reports.
+//!
+//! [`host_report`] renders everything known about one host as a compact
+//! Markdown snippet, meant to be pasted into a ticket or chat: readable as
+//! plain text, and rendered as tables wherever Markdown is.
+//!
+//! [`scan_report_html`] renders a whole scan as a standalone HTML page
+//! (summary figures, a sortable device table and per-host details) for
+//! handing results to people who will not open a CSV.
+
+use crate::types::{
+    GError, ScanLabel, ScanResult, ScanStatus, ScanSummary, format_bytes, udp_port_label,
+};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Page the HTML report is filled into; `{{name}}` marks each slot.
+const HTML_TEMPLATE: &str = include_str!("report.html");
+
+/// Renders `res` as Markdown: a field table, then sections for open ports
+/// (with banners), UDP ports, services, TLS, issues, the inventory row and
+/// probe extras.
+///
+/// `scanned_at` is when the scan that produced `res` started, if known;
+/// `generated_at` stamps the report itself. Both are shown in UTC.
+pub fn host_report(
+    res: &ScanResult,
+    scanned_at: Option<SystemTime>,
+    generated_at: SystemTime,
+) -> String {
+    let mut out = match &res.hostname {
+        Some(name) => format!("## Host {} ({})\n\n", res.ip, name),
+        None => format!("## Host {}\n\n", res.ip),
+    };
+
+    out.push_str("| Field | Value |\n|---|---|\n");
+    for (field, value) in host_fields(res) {
+        let _ = writeln!(out, "| {} | {} |", field, table_cell(&value));
+    }
+
+    out.push_str("\n### Open TCP ports\n\n");
+    if res.open_ports.is_empty() {
+        out.push_str("None found.\n");
+    } else {
+        out.push_str("| Port | Service | Banner |\n|---|---|---|\n");
+        for info in &res.open_ports {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} |",
+                info.port,
+                table_cell(&info.label()),
+                table_cell(info.banner.as_deref().unwrap_or(""))
+            );
+        }
+    }
+
+    if !res.open_udp_ports.is_empty() || !res.filtered_udp_ports.is_empty() {
+        out.push_str("\n### UDP ports\n\n");
+        for port in &res.open_udp_ports {
+            let _ = writeln!(out, "- {}/udp {}", port, udp_port_label(*port));
+        }
+        for port in &res.filtered_udp_ports {
+            let _ = writeln!(
+                out,
+                "- {}/udp {} (open|filtered)",
+                port,
+                udp_port_label(*port)
+            );
+        }
+    }
+
+    if !res.services.is_empty() {
+        out.push_str("\n### mDNS services\n\n");
+        for service in &res.services {
+            let _ = writeln!(out, "- {}", service);
+        }
+    }
+
+    if !res.tls.is_empty() {
+        out.push_str("\n### TLS\n\n");
+        for info in &res.tls {
+            let _ = write!(out, "- port {}: accepts {}", info.port, info.min_version);
+            if let Some(not_after) = info.cert_not_after {
+                let expiry = UNIX_EPOCH + Duration::from_secs(not_after);
+                let _ = write!(out, ", certificate valid until {}", format_utc(expiry));
+            }
+            out.push('\n');
+        }
+    }
+
+    if !res.issues.is_empty() {
+        out.push_str("\n### Issues\n\n");
+        for issue in &res.issues {
+            let _ = writeln!(out, "- {}", issue);
+        }
+    }
+
+    if let Some(asset) = &res.asset {
+        out.push_str("\n### Asset\n\n");
+        for (name, value) in [
+            ("Asset tag", &asset.asset_tag),
+            ("Owner", &asset.owner),
+            ("Location", &asset.location),
+        ] {
+            if let Some(value) = value {
+                let _ = writeln!(out, "- {}: {}", name, value);
+            }
+        }
+    }
+
+    if !res.extras.is_empty() {
+        let mut extras: Vec<_> = res.extras.iter().collect();
+        extras.sort();
+        out.push_str("\n### Extras\n\n");
+        for (key, value) in extras {
+            let _ = writeln!(out, "- {}: {}", key, value);
+        }
+    }
+
+    out.push('\n');
+    if let Some(scanned_at) = scanned_at {
+        let _ = write!(out, "Scanned {}. ", format_utc(scanned_at));
+    }
+    let _ = writeln!(out, "Report generated {}.", format_utc(generated_at));
+    out
+}
+
+/// Renders a scan as a standalone HTML page: summary figures, a device
+/// table that sorts by any column when its header is clicked, and details
+/// (fields, ports, UDP ports and issues) of each online host.
+///
+/// `summary` adds the duration and probe count when the scan reported
+/// them; `label` titles the page. `scanned_at` and `generated_at` are
+/// shown in UTC as in [`host_report`].
+pub fn scan_report_html(
+    results: &[ScanResult],
+    summary: Option<&ScanSummary>,
+    label: Option<&ScanLabel>,
+    scanned_at: Option<SystemTime>,
+    generated_at: SystemTime,
+) -> String {
+    let mut results: Vec<&ScanResult> = results.iter().collect();
+    results.sort_by_key(|r| r.ip);
+    let online: Vec<&ScanResult> = results
+        .iter()
+        .copied()
+        .filter(|r| r.status == ScanStatus::Online)
+        .collect();
+
+    let title = match label {
+        Some(label) => format!("Scan report: {}", label.name),
+        None => "Scan report".to_string(),
+    };
+    let mut meta = String::new();
+    if let Some(description) = label.and_then(|l| l.description.as_ref()) {
+        let _ = writeln!(meta, "<p class=\"meta\">{}</p>", html_escape(description));
+    }
+    if let Some(scanned_at) = scanned_at {
+        let _ = writeln!(
+            meta,
+            "<p class=\"meta\">Scanned {}</p>",
+            format_utc(scanned_at)
+        );
+    }
+
+    let errors = results
+        .iter()
+        .filter(|r| matches!(r.status, ScanStatus::SystemError(_)))
+        .count();
+    let mut stats = vec![
+        ("Hosts scanned", results.len().to_string()),
+        ("Online", online.len().to_string()),
+        (
+            "Offline",
+            (results.len() - online.len() - errors).to_string(),
+        ),
+        (
+            "Open ports",
+            online
+                .iter()
+                .map(|r| r.open_ports.len())
+                .sum::<usize>()
+                .to_string(),
+        ),
+        (
+            "Hosts with issues",
+            results
+                .iter()
+                .filter(|r| !r.issues.is_empty())
+                .count()
+                .to_string(),
+        ),
+    ];
+    if errors > 0 {
+        stats.insert(3, ("Errors", errors.to_string()));
+    }
+    if let Some(summary) = summary {
+        stats.push((
+            "Duration",
+            format!("{:.1} s", summary.duration.as_secs_f64()),
+        ));
+        stats.push(("Probes sent", summary.probes_sent.to_string()));
+        stats.push(("Bytes sent", format_bytes(summary.traffic.total().bytes)));
+        if summary.budget_exhausted {
+            stats.push(("Probe budget", "used up".to_string()));
+        }
+    }
+    let stats = stats
+        .into_iter()
+        .map(|(name, value)| {
+            format!(
+                "<div class=\"stat\"><span class=\"value\">{}</span><span class=\"name\">{}</span></div>\n",
+                value, name
+            )
+        })
+        .collect::<String>();
+
+    let mut rows = String::new();
+    for res in &results {
+        let ip = match res.status {
+            ScanStatus::Online => format!("<a href=\"#host-{0}\">{0}</a>", res.ip),
+            _ => res.ip.to_string(),
+        };
+        let status_class = if res.status == ScanStatus::Online {
+            " class=\"online\""
+        } else {
+            ""
+        };
+        let ports: Vec<String> = res.open_ports.iter().map(|p| p.port.to_string()).collect();
+        let issues: Vec<String> = res.issues.iter().map(|i| i.to_string()).collect();
+        let _ = writeln!(
+            rows,
+            "<tr><td data-sort=\"{}\">{}</td><td{}>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td data-sort=\"{}\">{}</td><td class=\"issue\">{}</td></tr>",
+            u32::from(res.ip),
+            ip,
+            status_class,
+            html_escape(&res.status.to_string()),
+            html_escape(res.hostname.as_deref().unwrap_or("")),
+            html_escape(res.mac.as_deref().unwrap_or("")),
+            html_escape(res.vendor.as_deref().unwrap_or("")),
+            res.device_type.map(|t| t.to_string()).unwrap_or_default(),
+            res.open_ports.len(),
+            ports.join(", "),
+            html_escape(&issues.join(", ")),
+        );
+    }
+
+    let mut hosts = String::new();
+    if online.is_empty() {
+        hosts.push_str("<p>No hosts online.</p>\n");
+    }
+    for res in &online {
+        let _ = writeln!(hosts, "<section class=\"host\" id=\"host-{}\">", res.ip);
+        let _ = match &res.hostname {
+            Some(name) => writeln!(hosts, "<h3>{} ({})</h3>", res.ip, html_escape(name)),
+            None => writeln!(hosts, "<h3>{}</h3>", res.ip),
+        };
+        hosts.push_str("<table>\n");
+        for (field, value) in host_fields(res) {
+            let _ = writeln!(
+                hosts,
+                "<tr><th>{}</th><td>{}</td></tr>",
+                field,
+                html_escape(&value)
+            );
+        }
+        hosts.push_str("</table>\n");
+
+        if res.open_ports.is_empty() {
+            hosts.push_str("<p>No open TCP ports found.</p>\n");
+        } else {
+            hosts.push_str("<table>\n<tr><th>Port</th><th>Service</th><th>Banner</th></tr>\n");
+            for info in &res.open_ports {
+                let _ = writeln!(
+                    hosts,
+                    "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                    info.port,
+                    html_escape(&info.label()),
+                    html_escape(info.banner.as_deref().unwrap_or(""))
+                );
+            }
+            hosts.push_str("</table>\n");
+        }
+
+        let udp: Vec<String> = res
+            .open_udp_ports
+            .iter()
+            .map(|port| format!("{}/udp {}", port, udp_port_label(*port)))
+            .chain(
+                res.filtered_udp_ports
+                    .iter()
+                    .map(|port| format!("{}/udp {} (open|filtered)", port, udp_port_label(*port))),
+            )
+            .collect();
+        if !udp.is_empty() {
+            let _ = writeln!(hosts, "<p>UDP: {}</p>", html_escape(&udp.join(", ")));
+        }
+        if !res.issues.is_empty() {
+            hosts.push_str("<ul class=\"issue\">\n");
+            for issue in &res.issues {
+                let _ = writeln!(hosts, "<li>{}</li>", html_escape(&issue.to_string()));
+            }
+            hosts.push_str("</ul>\n");
+        }
+        hosts.push_str("</section>\n");
+    }
+
+    let footer = format!("Report generated {}.", format_utc(generated_at));
+    fill_template(
+        HTML_TEMPLATE,
+        &[
+            ("title", html_escape(&title)),
+            ("meta", meta),
+            ("stats", stats),
+            ("rows", rows),
+            ("hosts", hosts),
+            ("footer", footer),
+        ],
+    )
+}
+
+/// Writes [`scan_report_html`] to `path`, stamped with the current time.
+///
+/// # Errors
+///
+/// Returns [`GError::Internal`] if the file cannot be written.
+pub fn write_scan_report(
+    path: &Path,
+    results: &[ScanResult],
+    summary: Option<&ScanSummary>,
+    label: Option<&ScanLabel>,
+    scanned_at: Option<SystemTime>,
+) -> Result<(), GError> {
+    let html = scan_report_html(results, summary, label, scanned_at, SystemTime::now());
+    fs::write(path, html).map_err(|e| {
+        GError::Internal(format!(
+            "Failed to write report '{}': {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+/// Replaces each `{{name}}` in `template` with its value in one pass, so
+/// text in a value that looks like a slot is left alone.
+fn fill_template(template: &str, values: &[(&str, String)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find("}}").and_then(|end| {
+            let name = &after[..end];
+            values
+                .iter()
+                .find(|(slot, _)| *slot == name)
+                .map(|(_, value)| (value, end))
+        }) {
+            Some((value, end)) => {
+                out.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Escapes text for HTML element content and quoted attributes.
+fn html_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Label/value rows describing `res`, shared by the Markdown and HTML reports.
+fn host_fields(res: &ScanResult) -> Vec<(&'static str, String)> {
+    let mut fields: Vec<(&str, String)> = vec![("Status", res.status.to_string())];
+    if let ScanStatus::SystemError(e) = &res.status
+        && let Some(hint) = e.hint()
+    {
+        fields.push(("Hint", hint.to_string()));
+    }
+    if let Some(name) = &res.hostname {
+        let value = match &res.hostname_source {
+            Some(source) => format!("{} (via {})", name, source),
+            None => name.clone(),
+        };
+        fields.push(("Hostname", value));
+    }
+    if let Some(note) = &res.device_note {
+        fields.push(("Name", note.name.clone()));
+        if let Some(notes) = &note.notes {
+            fields.push(("Notes", notes.clone()));
+        }
+    }
+    if let Some(mac) = &res.mac {
+        fields.push(("MAC", mac.clone()));
+    }
+    if let Some(vendor) = &res.vendor {
+        fields.push(("Vendor", vendor.clone()));
+    }
+    if let Some(kind) = res.device_type {
+        fields.push(("Device type", kind.to_string()));
+    }
+    if !res.roles.is_empty() {
+        let roles: Vec<String> = res.roles.iter().map(|role| role.to_string()).collect();
+        fields.push(("Roles", roles.join(", ")));
+    }
+    if let Some(guess) = &res.os_guess {
+        let value = if guess.evidence.is_empty() {
+            guess.to_string()
+        } else {
+            format!("{} ({})", guess, guess.evidence.join(", "))
+        };
+        fields.push(("OS guess", value));
+    }
+    if let Some(latency) = res.latency {
+        fields.push((
+            "Latency",
+            format!(
+                "{} ({}/{} replies)",
+                latency.summary(),
+                latency.received,
+                latency.sent
+            ),
+        ));
+    }
+    if let Some(web) = &res.web_info {
+        fields.push(("Web", web.to_string()));
+    }
+    if let Some(smb) = &res.smb_info {
+        fields.push(("SMB", smb.to_string()));
+    }
+    if let Some(upnp) = &res.upnp {
+        let parts: Vec<&str> = [&upnp.friendly_name, &upnp.manufacturer, &upnp.model]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        fields.push(("UPnP", parts.join(", ")));
+    }
+    if let Some(snmp) = &res.snmp {
+        let mut parts: Vec<String> = [&snmp.name, &snmp.description]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect();
+        if let Some(uptime) = snmp.uptime {
+            parts.push(format!("up {}", format_uptime(uptime)));
+        }
+        fields.push(("SNMP", parts.join(", ")));
+    }
+    fields.push(("Probes", res.attempts.to_string()));
+    if !res.warnings.is_empty() {
+        let warnings: Vec<String> = res.warnings.iter().map(GError::to_string).collect();
+        fields.push(("Warnings", warnings.join("; ")));
+    }
+    fields
+}
+
+/// Escapes a value for a Markdown table cell: pipes would end the cell
+/// and line breaks the row.
+fn table_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// Formats an agent uptime as `3d 04:12` (days, hours, minutes).
+pub fn format_uptime(uptime: Duration) -> String {
+    let minutes = uptime.as_secs() / 60;
+    format!(
+        "{}d {:02}:{:02}",
+        minutes / (24 * 60),
+        minutes / 60 % 24,
+        minutes % 60
+    )
+}
+
+/// Formats a time as `2024-05-01 13:45:00 UTC`; times before 1970 as the epoch.
+pub fn format_utc(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let secs_of_day = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    )
+}
+
+/// Proleptic Gregorian date of the day `days` after 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{PortInfo, SecurityIssue};
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_format_utc() {
+        assert_eq!(format_utc(UNIX_EPOCH), "1970-01-01 00:00:00 UTC");
+        let leap_day = UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+        assert_eq!(format_utc(leap_day), "2024-02-29 12:34:56 UTC");
+    }
+
+    #[test]
+    fn test_host_report_lists_fields_ports_and_times() {
+        let mut res = ScanResult::new(Ipv4Addr::new(10, 0, 0, 5));
+        res.status = ScanStatus::Online;
+        res.hostname = Some("nas".to_string());
+        res.mac = Some("00:11:32:AA:BB:CC".to_string());
+        res.open_ports = vec![
+            PortInfo {
+                banner: Some("SSH-2.0-OpenSSH_9.6 | extra".to_string()),
+                ..PortInfo::new(22)
+            },
+            PortInfo::new(445),
+        ];
+        res.issues.push(SecurityIssue::Smb1Enabled);
+
+        let now = UNIX_EPOCH + Duration::from_secs(86_400);
+        let report = host_report(&res, Some(UNIX_EPOCH), now);
+        assert!(report.starts_with("## Host 10.0.0.5 (nas)\n"));
+        assert!(report.contains("| MAC | 00:11:32:AA:BB:CC |"));
+        assert!(report.contains("| 22 | SSH | SSH-2.0-OpenSSH_9.6 \\| extra |"));
+        assert!(report.contains("| 445 | SMB |  |"));
+        assert!(report.contains("### Issues\n\n- SMBv1 enabled\n"));
+        assert!(report.ends_with(
+            "Scanned 1970-01-01 00:00:00 UTC. Report generated 1970-01-02 00:00:00 UTC.\n"
+        ));
+        assert!(!report.contains("### UDP ports"));
+    }
+
+    #[test]
+    fn test_scan_report_html_has_stats_rows_and_host_details() {
+        let mut nas = ScanResult::new(Ipv4Addr::new(10, 0, 0, 5));
+        nas.status = ScanStatus::Online;
+        nas.hostname = Some("<nas>".to_string());
+        nas.open_ports = vec![PortInfo::new(22), PortInfo::new(445)];
+        nas.issues.push(SecurityIssue::Smb1Enabled);
+        let gone = ScanResult::new(Ipv4Addr::new(10, 0, 0, 2));
+        let summary = ScanSummary {
+            duration: Duration::from_millis(4200),
+            hosts_up: 1,
+            hosts_down: 1,
+            errors: 0,
+            probes_sent: 30,
+            ..ScanSummary::default()
+        };
+        let label = ScanLabel {
+            name: "Office {{rows}}".to_string(),
+            description: None,
+        };
+
+        let html = scan_report_html(&[nas, gone], Some(&summary), Some(&label), None, UNIX_EPOCH);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>Scan report: Office {{rows}}</title>"));
+        assert!(html.contains("<span class=\"value\">1</span><span class=\"name\">Online</span>"));
+        assert!(html.contains("<span class=\"value\">4.2 s</span>"));
+        assert!(
+            html.contains("<span class=\"value\">0 B</span><span class=\"name\">Bytes sent</span>")
+        );
+        // Sorted by IP, with the numeric address as the sort key.
+        let down = html
+            .find("<td data-sort=\"167772162\">10.0.0.2</td>")
+            .unwrap();
+        let up = html
+            .find("<td data-sort=\"167772165\"><a href=\"#host-10.0.0.5\">10.0.0.5</a></td>")
+            .unwrap();
+        assert!(down < up);
+        assert!(html.contains("<td data-sort=\"2\">22, 445</td>"));
+        assert!(html.contains("<h3>10.0.0.5 (&lt;nas&gt;)</h3>"));
+        assert!(html.contains("<tr><td>445</td><td>SMB</td><td></td></tr>"));
+        assert!(html.contains("<li>SMBv1 enabled</li>"));
+        assert!(!html.contains("id=\"host-10.0.0.2\""));
+        assert!(html.contains("Report generated 1970-01-01 00:00:00 UTC."));
+    }
+}
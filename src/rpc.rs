@@ -0,0 +1,369 @@
+//! JSON-RPC over stdin/stdout for embedding the scanner.
+//!
+//! `ragescanner-cli --rpc` lets editors, Electron apps and other programs run
+//! the scanner as a child process without opening a socket. Every input line
+//! is a JSON-RPC 2.0 request; every output line is a response or, for scan
+//! events, a notification (a message without an `id`):
+//!
+//! | Method | Params | Result |
+//! |---|---|---|
+//! | `scan` | `{"range": "10.0.0.0/24", "profile": "safe", "config": {...}}` | `{"scan": 1}` |
+//! | `cancel` | `{"scan": 1}` | `{"scan": 1}` |
+//!
+//! `profile` and `config` work as in the [REST API](crate::api). Scans run
+//! as tagged scans, so several can run at once; their events arrive as the
+//! notifications `host` (`{"scan", "result"}`), `progress` (`{"scan",
+//! "percent"}`), `phase` (`{"scan", "phase"}`), `summary` (`{"scan",
+//! "summary"}`), `complete`, `cancelled` and `error` (`{"scan",
+//! "message"}`). Input ends at EOF.
+
+use crate::bridge::Bridge;
+use crate::types::{BridgeMessage, GError, ScanId, ScanProfile};
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use std::collections::HashSet;
+use std::thread;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc::{Receiver as TokioReceiver, Sender as TokioSender, channel};
+
+/// The line is not JSON.
+pub const PARSE_ERROR: i64 = -32700;
+/// The JSON is not a request object.
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+/// The bridge has stopped taking commands.
+pub const SCANNER_UNAVAILABLE: i64 = -32603;
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    /// Absent for notifications, which get no response.
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScanParams {
+    range: String,
+    #[serde(default)]
+    profile: ScanProfile,
+    #[serde(default)]
+    config: Option<Map<String, Value>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CancelParams {
+    scan: u64,
+}
+
+/// Request handling and event translation for one RPC client.
+pub struct RpcSession {
+    cmd_tx: TokioSender<BridgeMessage>,
+    next_id: u64,
+    /// Scans started by this client that have not ended.
+    running: HashSet<u64>,
+}
+
+impl RpcSession {
+    pub fn new(cmd_tx: TokioSender<BridgeMessage>) -> Self {
+        Self {
+            cmd_tx,
+            next_id: 0,
+            running: HashSet::new(),
+        }
+    }
+
+    /// Handles one input line and returns the response to write, if any.
+    pub async fn handle_line(&mut self, line: &str) -> Option<Value> {
+        let value: Value = match serde_json::from_str(line) {
+            Ok(value) => value,
+            Err(e) => return Some(error(Value::Null, PARSE_ERROR, &e.to_string())),
+        };
+        let request: Request = match serde_json::from_value(value) {
+            Ok(request) => request,
+            Err(e) => return Some(error(Value::Null, INVALID_REQUEST, &e.to_string())),
+        };
+        let result = match request.method.as_str() {
+            "scan" => self.scan(request.params).await,
+            "cancel" => self.cancel(request.params).await,
+            other => Err((METHOD_NOT_FOUND, format!("Unknown method '{}'", other))),
+        };
+        let id = request.id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error(id, code, &message),
+        })
+    }
+
+    async fn scan(&mut self, params: Value) -> Result<Value, (i64, String)> {
+        let params: ScanParams = serde_json::from_value(params)
+            .map_err(|e| (INVALID_PARAMS, format!("Invalid scan params: {}", e)))?;
+        let config = params
+            .profile
+            .config_with(params.config)
+            .map_err(|e| (INVALID_PARAMS, format!("Invalid scan config: {}", e)))?;
+        self.next_id += 1;
+        let id = self.next_id;
+        // Registered before the request is sent, so no early event is dropped.
+        self.running.insert(id);
+        let start = BridgeMessage::StartTaggedScan {
+            id: ScanId(id),
+            request: Box::new(BridgeMessage::StartScanWithConfig {
+                range: params.range,
+                config: Box::new(config),
+            }),
+        };
+        if self.cmd_tx.send(start).await.is_err() {
+            self.running.remove(&id);
+            return Err((SCANNER_UNAVAILABLE, "Scanner is not running".to_string()));
+        }
+        Ok(json!({ "scan": id }))
+    }
+
+    async fn cancel(&mut self, params: Value) -> Result<Value, (i64, String)> {
+        let params: CancelParams = serde_json::from_value(params)
+            .map_err(|e| (INVALID_PARAMS, format!("Invalid cancel params: {}", e)))?;
+        if !self.running.contains(&params.scan) {
+            return Err((
+                INVALID_PARAMS,
+                format!("Scan {} is not running", params.scan),
+            ));
+        }
+        self.cmd_tx
+            .send(BridgeMessage::StopTaggedScan(ScanId(params.scan)))
+            .await
+            .map_err(|_| (SCANNER_UNAVAILABLE, "Scanner is not running".to_string()))?;
+        Ok(json!({ "scan": params.scan }))
+    }
+
+    /// Turns a bridge message into a notification; `None` for messages that
+    /// are not events of this client's scans.
+    pub fn event(&mut self, msg: BridgeMessage) -> Option<Value> {
+        let BridgeMessage::Tagged(ScanId(scan), msg) = msg else {
+            return None;
+        };
+        if !self.running.contains(&scan) {
+            return None;
+        }
+        let (method, params) = match *msg {
+            BridgeMessage::ScanUpdate(res) => ("host", json!({ "scan": scan, "result": res })),
+            BridgeMessage::Progress(percent) => {
+                ("progress", json!({ "scan": scan, "percent": percent }))
+            }
+            BridgeMessage::Phase(phase) => ("phase", json!({ "scan": scan, "phase": phase })),
+            BridgeMessage::ScanSummary(summary) => {
+                ("summary", json!({ "scan": scan, "summary": summary }))
+            }
+            BridgeMessage::ScanComplete => {
+                self.running.remove(&scan);
+                ("complete", json!({ "scan": scan }))
+            }
+            BridgeMessage::ScanCancelled => {
+                self.running.remove(&scan);
+                ("cancelled", json!({ "scan": scan }))
+            }
+            BridgeMessage::Error(e) => {
+                self.running.remove(&scan);
+                let params = json!({
+                    "scan": scan,
+                    "message": e.to_string(),
+                    "kind": e.code(),
+                    "hint": e.hint(),
+                });
+                ("error", params)
+            }
+            _ => return None,
+        };
+        Some(json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+    }
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Serves requests from `input` on `bridge`, writing responses and
+/// notifications to `output`, until `input` ends.
+///
+/// Takes over the bridge's `ui_rx`, which must have no other reader.
+///
+/// # Errors
+///
+/// Returns [`GError::Internal`] if reading `input` or writing `output` fails.
+pub async fn serve<R, W>(bridge: Bridge, input: R, output: W) -> Result<(), GError>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let (event_tx, event_rx) = channel::<BridgeMessage>(256);
+    let ui_rx = bridge.ui_rx.clone();
+    thread::spawn(move || {
+        while let Ok(msg) = ui_rx.recv() {
+            if event_tx.blocking_send(msg).is_err() {
+                break;
+            }
+        }
+    });
+    run(
+        RpcSession::new(bridge.cmd_tx.clone()),
+        event_rx,
+        input,
+        output,
+    )
+    .await
+}
+
+async fn run<R, W>(
+    mut session: RpcSession,
+    mut events: TokioReceiver<BridgeMessage>,
+    input: R,
+    mut output: W,
+) -> Result<(), GError>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut lines = input.lines();
+    loop {
+        let message = tokio::select! {
+            line = lines.next_line() => match line {
+                Ok(Some(line)) if line.trim().is_empty() => continue,
+                Ok(Some(line)) => session.handle_line(&line).await,
+                Ok(None) => return Ok(()),
+                Err(e) => return Err(GError::Internal(format!("Failed to read request: {}", e))),
+            },
+            Some(msg) = events.recv() => session.event(msg),
+        };
+        if let Some(message) = message {
+            let failed =
+                |e: std::io::Error| GError::Internal(format!("Failed to write response: {}", e));
+            let mut line = message.to_string();
+            line.push('\n');
+            output.write_all(line.as_bytes()).await.map_err(failed)?;
+            output.flush().await.map_err(failed)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{PhaseProgress, ScanResult, ScanStatus, ScanSummary};
+    use std::net::Ipv4Addr;
+
+    #[tokio::test]
+    async fn test_scan_request_and_events() {
+        let (cmd_tx, mut cmd_rx) = channel(8);
+        let mut session = RpcSession::new(cmd_tx);
+
+        let request = r#"{"jsonrpc": "2.0", "id": 7, "method": "scan",
+            "params": {"range": "10.0.0.0/30", "profile": "safe", "config": {"concurrency": 2}}}"#;
+        let response = session.handle_line(request).await.unwrap();
+        assert_eq!(
+            response,
+            json!({ "jsonrpc": "2.0", "id": 7, "result": { "scan": 1 } })
+        );
+        let Some(BridgeMessage::StartTaggedScan { id, request }) = cmd_rx.recv().await else {
+            panic!("no scan started");
+        };
+        assert_eq!(id, ScanId(1));
+        let BridgeMessage::StartScanWithConfig { range, config } = *request else {
+            panic!("unexpected request");
+        };
+        assert_eq!(range, "10.0.0.0/30");
+        assert_eq!(config.concurrency, 2);
+        assert!(config.icmp_only);
+
+        let mut online = ScanResult::new(Ipv4Addr::new(10, 0, 0, 1));
+        online.status = ScanStatus::Online;
+        let tagged = |id, msg| BridgeMessage::Tagged(ScanId(id), Box::new(msg));
+        let host = session
+            .event(tagged(1, BridgeMessage::ScanUpdate(online)))
+            .unwrap();
+        assert_eq!(host["method"], "host");
+        assert_eq!(host["params"]["result"]["ip"], "10.0.0.1");
+        assert!(host.get("id").is_none());
+        // Untagged messages and other clients' scans are not ours.
+        assert!(session.event(BridgeMessage::Progress(5)).is_none());
+        assert!(
+            session
+                .event(tagged(9, BridgeMessage::Progress(5)))
+                .is_none()
+        );
+
+        let cancel = r#"{"jsonrpc": "2.0", "id": 8, "method": "cancel", "params": {"scan": 1}}"#;
+        assert!(
+            session
+                .handle_line(cancel)
+                .await
+                .unwrap()
+                .get("result")
+                .is_some()
+        );
+        assert!(matches!(
+            cmd_rx.recv().await,
+            Some(BridgeMessage::StopTaggedScan(ScanId(1)))
+        ));
+        let summary = ScanSummary {
+            hosts_up: 1,
+            ..ScanSummary::default()
+        };
+        let totals = session
+            .event(tagged(1, BridgeMessage::ScanSummary(summary)))
+            .unwrap();
+        assert_eq!(totals["params"]["summary"]["hosts_up"], 1);
+        let phase = PhaseProgress {
+            hosts_discovered: 3,
+            hosts_total: 4,
+            ..PhaseProgress::default()
+        };
+        let phase = session
+            .event(tagged(1, BridgeMessage::Phase(phase)))
+            .unwrap();
+        assert_eq!(phase["method"], "phase");
+        assert_eq!(phase["params"]["phase"]["hosts_discovered"], 3);
+        let ended = session
+            .event(tagged(1, BridgeMessage::ScanCancelled))
+            .unwrap();
+        assert_eq!(ended["method"], "cancelled");
+        let again = session.handle_line(cancel).await.unwrap();
+        assert_eq!(again["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_errors_and_notifications() {
+        let (cmd_tx, _cmd_rx) = channel(8);
+        let mut session = RpcSession::new(cmd_tx);
+        let code = |response: Option<Value>| response.unwrap()["error"]["code"].clone();
+
+        assert_eq!(code(session.handle_line("{not json").await), PARSE_ERROR);
+        assert_eq!(code(session.handle_line("[1, 2]").await), INVALID_REQUEST);
+        let unknown = r#"{"jsonrpc": "2.0", "id": "a", "method": "reboot"}"#;
+        assert_eq!(code(session.handle_line(unknown).await), METHOD_NOT_FOUND);
+        let bad =
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "scan", "params": {"profile": "turbo"}}"#;
+        assert_eq!(code(session.handle_line(bad).await), INVALID_PARAMS);
+        // Requests without an id get no response, even when they fail.
+        let silent = r#"{"jsonrpc": "2.0", "method": "reboot"}"#;
+        assert!(session.handle_line(silent).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_run_writes_one_line_per_message() {
+        let (cmd_tx, _cmd_rx) = channel(8);
+        let (_event_tx, event_rx) = channel(8);
+        let input: &[u8] = b"\n{\"id\": 1, \"method\": \"cancel\", \"params\": {\"scan\": 3}}\n";
+        let mut output = Vec::new();
+        run(RpcSession::new(cmd_tx), event_rx, input, &mut output)
+            .await
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().count(), 1);
+        let response: Value = serde_json::from_str(output.trim_end()).unwrap();
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+    }
+}
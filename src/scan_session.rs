@@ -0,0 +1,293 @@
+//! Typed handle over a running scan, for library callers.
+//!
+//! [`Scanner::start`] spawns a scan and returns a [`ScanSession`] instead of
+//! a raw [`BridgeMessage`] channel: results arrive as a [`Stream`], progress
+//! and the closing [`ScanSummary`] through watch channels, per-host phases as
+//! a [`StateSnapshot`] on request, and awaiting the session yields how the scan
+//! ended. Internally the scanner still reports over the usual channel; the
+//! session only sorts its messages.
+
+use crate::net::NetworkProvider;
+use crate::probe::ProbeRegistry;
+use crate::scanner::{HostStates, Scanner};
+use crate::targets::ScanTargets;
+use crate::types::{
+    BridgeMessage, GError, PhaseProgress, ScanConfig, ScanResult, ScanSummary, StateSnapshot,
+};
+use futures::Stream;
+use futures::future::BoxFuture;
+use std::future::IntoFuture;
+use std::sync::Arc;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// Bridge messages buffered between the scanner and the session's sorter.
+const EVENT_BUFFER: usize = 256;
+
+/// How a scan started with [`Scanner::start`] ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanOutcome {
+    /// Every target was probed.
+    Complete,
+    /// [`ScanSession::cancel`] stopped the scan early.
+    Cancelled,
+}
+
+/// A scan running on the Tokio runtime.
+///
+/// Results are buffered without bound until read, so a caller that only
+/// awaits completion never stalls the scan. Dropping the session does not
+/// stop the scan; call [`cancel`](ScanSession::cancel) first.
+///
+/// ```no_run
+/// use futures::StreamExt;
+/// use ragescanner::net::NetUtils;
+/// use ragescanner::scanner::Scanner;
+/// use ragescanner::targets::ScanTargets;
+/// use ragescanner::types::ScanConfig;
+/// use std::sync::Arc;
+///
+/// # async fn example() -> Result<(), ragescanner::types::GError> {
+/// let targets = ScanTargets::parse("192.168.1.0/24").unwrap();
+/// let mut session = Scanner::start(Arc::new(NetUtils::new()), targets, ScanConfig::default());
+/// while let Some(res) = session.results().next().await {
+///     println!("{}: {}", res.ip, res.status);
+/// }
+/// let outcome = session.await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ScanSession {
+    results: mpsc::UnboundedReceiver<ScanResult>,
+    progress: watch::Receiver<u8>,
+    phase: watch::Receiver<PhaseProgress>,
+    summary: watch::Receiver<Option<ScanSummary>>,
+    host_states: Arc<HostStates>,
+    cancel: CancellationToken,
+    task: JoinHandle<Result<ScanOutcome, GError>>,
+}
+
+impl ScanSession {
+    /// Scans `targets` with `config` and `probes` on the current Tokio runtime.
+    ///
+    /// # Panics
+    ///
+    /// Panics when called outside a Tokio runtime.
+    fn spawn(
+        net_utils: Arc<dyn NetworkProvider>,
+        targets: ScanTargets,
+        config: ScanConfig,
+        probes: ProbeRegistry,
+    ) -> Self {
+        let (tx, mut events) = mpsc::channel(EVENT_BUFFER);
+        let (results_tx, results) = mpsc::unbounded_channel();
+        let (progress_tx, progress) = watch::channel(0);
+        let (phase_tx, phase) = watch::channel(PhaseProgress::default());
+        let (summary_tx, summary) = watch::channel(None);
+        let cancel = CancellationToken::new();
+        let host_states = Arc::new(HostStates::default());
+        let scanner = Scanner::new(net_utils, tx)
+            .with_config(config)
+            .with_probes(probes)
+            .with_host_states(host_states.clone());
+        let token = cancel.clone();
+
+        let task = tokio::spawn(async move {
+            // The scanner owns the only sender, so `events` closes once it is dropped.
+            let scan = async move { scanner.scan_multi(targets, token).await };
+            let sort = async move {
+                let mut outcome = Err(GError::Internal(
+                    "Scan ended without reporting completion".to_string(),
+                ));
+                while let Some(msg) = events.recv().await {
+                    match msg {
+                        BridgeMessage::ScanUpdate(res) => {
+                            let _ = results_tx.send(res);
+                        }
+                        BridgeMessage::Progress(p) => {
+                            progress_tx.send_replace(p);
+                        }
+                        BridgeMessage::Phase(p) => {
+                            phase_tx.send_replace(p);
+                        }
+                        BridgeMessage::ScanSummary(s) => {
+                            summary_tx.send_replace(Some(s));
+                        }
+                        BridgeMessage::ScanComplete => outcome = Ok(ScanOutcome::Complete),
+                        BridgeMessage::ScanCancelled => outcome = Ok(ScanOutcome::Cancelled),
+                        BridgeMessage::Error(e) => outcome = Err(e),
+                        _ => {}
+                    }
+                }
+                outcome
+            };
+            tokio::join!(scan, sort).1
+        });
+
+        Self {
+            results,
+            progress,
+            phase,
+            summary,
+            host_states,
+            cancel,
+            task,
+        }
+    }
+
+    /// Streams results as hosts finish, in completion order.
+    ///
+    /// Ends once the scan is over and every buffered result has been read.
+    pub fn results(&mut self) -> impl Stream<Item = ScanResult> + '_ {
+        futures::stream::poll_fn(move |cx| self.results.poll_recv(cx))
+    }
+
+    /// Returns a receiver for the scan's progress in percent.
+    ///
+    /// Await [`changed`](watch::Receiver::changed) to follow updates.
+    pub fn progress(&self) -> watch::Receiver<u8> {
+        self.progress.clone()
+    }
+
+    /// Returns a receiver for the discovery and port-scan progress that
+    /// [`progress`](ScanSession::progress) weighs together.
+    pub fn phase(&self) -> watch::Receiver<PhaseProgress> {
+        self.phase.clone()
+    }
+
+    /// Totals of the finished scan: duration, hosts up and down, probes
+    /// sent and errors. `None` until the scan has ended.
+    pub fn summary(&self) -> Option<ScanSummary> {
+        *self.summary.borrow()
+    }
+
+    /// Where every host of the scan stands right now.
+    pub fn state_snapshot(&self) -> StateSnapshot {
+        self.host_states.snapshot()
+    }
+
+    /// Stops dispatching new hosts; in-flight hosts still report.
+    ///
+    /// The session then completes with [`ScanOutcome::Cancelled`].
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+}
+
+impl IntoFuture for ScanSession {
+    type Output = Result<ScanOutcome, GError>;
+    type IntoFuture = BoxFuture<'static, Self::Output>;
+
+    /// Waits for the scan to finish. Unread results are discarded.
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(async move {
+            self.task
+                .await
+                .map_err(|e| GError::Internal(format!("Scan task failed: {}", e)))?
+        })
+    }
+}
+
+impl Scanner {
+    /// Starts scanning `targets` with `config` and returns a typed handle.
+    ///
+    /// An alternative to driving [`Scanner::scan_multi`] and matching
+    /// [`BridgeMessage`]s by hand. Range errors, such as an empty target set,
+    /// surface as the session's `Err`.
+    ///
+    /// # Panics
+    ///
+    /// Panics when called outside a Tokio runtime.
+    pub fn start(
+        net_utils: Arc<dyn NetworkProvider>,
+        targets: ScanTargets,
+        config: ScanConfig,
+    ) -> ScanSession {
+        ScanSession::spawn(net_utils, targets, config, ProbeRegistry::default())
+    }
+
+    /// Like [`Scanner::start`], also running the custom `probes` on online hosts.
+    ///
+    /// # Panics
+    ///
+    /// Panics when called outside a Tokio runtime.
+    pub fn start_with_probes(
+        net_utils: Arc<dyn NetworkProvider>,
+        targets: ScanTargets,
+        config: ScanConfig,
+        probes: ProbeRegistry,
+    ) -> ScanSession {
+        ScanSession::spawn(net_utils, targets, config, probes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::MockNet;
+    use crate::probe::{Probe, ProbeOutcome};
+    use futures::StreamExt;
+    use std::net::Ipv4Addr;
+
+    #[tokio::test]
+    async fn test_session_streams_results_and_completes() {
+        let targets = ScanTargets::parse("10.0.0.1-10.0.0.4").unwrap();
+        let mut session = Scanner::start(Arc::new(MockNet), targets, ScanConfig::default());
+        let progress = session.progress();
+
+        let mut ips: Vec<Ipv4Addr> = session.results().map(|r| r.ip).collect().await;
+        ips.sort();
+        assert_eq!(ips.len(), 4);
+        assert_eq!(ips[0], Ipv4Addr::new(10, 0, 0, 1));
+        assert_eq!(*progress.borrow(), 100);
+        assert!(session.phase().borrow().discovery_complete());
+        let summary = session.summary().expect("summary after the last result");
+        assert_eq!(summary.hosts_up + summary.hosts_down + summary.errors, 4);
+        let snapshot = session.state_snapshot();
+        assert_eq!(snapshot.pending, 0);
+        assert_eq!(snapshot.hosts.len(), 4);
+        assert_eq!(session.await, Ok(ScanOutcome::Complete));
+    }
+
+    #[tokio::test]
+    async fn test_session_runs_custom_probes_on_online_hosts() {
+        struct Tagger;
+        impl Probe for Tagger {
+            fn name(&self) -> &str {
+                "tagger"
+            }
+
+            fn probe<'a>(
+                &'a self,
+                target: &'a ScanResult,
+                _net: &'a dyn NetworkProvider,
+            ) -> BoxFuture<'a, ProbeOutcome> {
+                let ports = target.open_ports.len().to_string();
+                Box::pin(async move { ProbeOutcome::found("ports", ports) })
+            }
+        }
+
+        let mut probes = ProbeRegistry::default();
+        probes.register(Tagger);
+        let targets = ScanTargets::parse("192.168.1.1,192.168.1.4").unwrap();
+        let config = ScanConfig {
+            ports: vec![22, 80],
+            ..ScanConfig::default()
+        };
+        let mut session = Scanner::start_with_probes(Arc::new(MockNet), targets, config, probes);
+        let mut results: Vec<ScanResult> = session.results().collect().await;
+        results.sort_by_key(|r| r.ip);
+        assert_eq!(results[0].extras["ports"], "1");
+        assert!(results[1].extras.is_empty());
+        assert_eq!(session.await, Ok(ScanOutcome::Complete));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_session_reports_cancelled() {
+        let targets = ScanTargets::parse("10.0.0.0/24").unwrap();
+        let session = Scanner::start(Arc::new(MockNet), targets, ScanConfig::default());
+        session.cancel();
+        assert_eq!(session.await, Ok(ScanOutcome::Cancelled));
+    }
+}
@@ -1,22 +1,1206 @@
-//! Async scan engine with semaphore-controlled concurrency.
+//! Async scan engine running hosts through a fixed pool of workers.
 //!
 //! The [`Scanner`] struct orchestrates per-IP scanning (ping, ARP, DNS,
 //! port scan) and streams results via a Tokio channel.
 
-use crate::net::NetworkProvider;
-use crate::types::{BridgeMessage, COMMON_PORTS, GError, ScanResult, ScanStatus};
+use crate::cache::PortCache;
+use crate::classify;
+use crate::devices;
+use crate::fingerprint;
+use crate::inventory;
+use crate::names::MdnsHost;
+use crate::net::{
+    ARP_REQUEST_BYTES, Capabilities, DEFAULT_PORT_TIMEOUT, ECHO_REQUEST_BYTES, HostLimitProvider,
+    InterfaceWatch, NetworkProvider, PowerWatch, Socks5Provider, TCP_SYN_BYTES, udp_probe_bytes,
+};
+use crate::oui::RANDOMIZED_MAC_VENDOR;
+use crate::probe::ProbeRegistry;
+use crate::smb::SMB_PORT;
+use crate::snmp::SNMP_PORT;
+use crate::targets::ScanTargets;
+use crate::tls::{self, TLS_AUDIT_PORTS};
+use crate::types::{
+    BridgeMessage, COMMON_UDP_PORTS, DnsConfig, GError, HostPhase, LatencyStats,
+    MAX_SCAN_CONNECTIONS, NameSource, NetworkRole, PhaseProgress, PortInfo, ProbeKind,
+    ProbeTraffic, RangeProgress, ScanConfig, ScanOrder, ScanResult, ScanRunState, ScanStats,
+    ScanStatus, ScanSummary, SecurityIssue, SmbInfo, StateSnapshot, UdpPortState, UpnpInfo,
+};
+use crate::web::{self, InterceptDetector};
+use futures::stream::FuturesUnordered;
+use futures::{FutureExt, StreamExt};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::hash::{BuildHasher, RandomState};
 use std::net::Ipv4Addr;
-use std::sync::Arc;
-use tokio::sync::Semaphore;
-use tokio::sync::mpsc::Sender;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU16, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc::{self, Sender};
+use tokio::sync::{Semaphore, oneshot, watch};
 
 /// Async scan engine that probes IPs for reachability, MAC, hostname, and open ports.
 pub struct Scanner {
     net_utils: Arc<dyn NetworkProvider>,
     tx_bridge: Sender<BridgeMessage>,
+    udp_scan: AtomicBool,
+    include_subnet_edges: AtomicBool,
+    ping_attempts: AtomicU32,
+    port_cache: Option<Arc<PortCache>>,
+    config: ScanConfig,
+    pause: watch::Receiver<bool>,
+    probes: Arc<ProbeRegistry>,
+    host_states: Arc<HostStates>,
 }
 
-const MAX_CONCURRENT_TASKS: usize = 100;
+/// How long interfaces must stay unchanged before a paused scan resumes.
+const INTERFACE_SETTLE: Duration = Duration::from_secs(3);
+
+/// Multiplier applied to the worst measured RTT to get a port probe timeout.
+const RTT_TIMEOUT_FACTOR: u64 = 4;
+/// Lower bound so sub-millisecond LAN RTTs still leave room for a slow TCP stack.
+const MIN_PORT_TIMEOUT: Duration = Duration::from_millis(100);
+/// Upper bound so a single slow echo cannot stall the port phase.
+const MAX_PORT_TIMEOUT: Duration = Duration::from_millis(2000);
+
+/// Derives a TCP connect timeout from measured latency.
+///
+/// Uses `4 × max RTT`, clamped to 100–2000 ms. Hosts without an RTT sample
+/// (e.g. found only via ARP) fall back to [`DEFAULT_PORT_TIMEOUT`].
+pub fn adaptive_port_timeout(latency: Option<&LatencyStats>) -> Duration {
+    match latency {
+        Some(l) => Duration::from_millis(l.max_ms.ceil() as u64 * RTT_TIMEOUT_FACTOR)
+            .clamp(MIN_PORT_TIMEOUT, MAX_PORT_TIMEOUT),
+        None => DEFAULT_PORT_TIMEOUT,
+    }
+}
+
+/// Spreads `delay` uniformly over `delay/2..3*delay/2`.
+fn jittered(delay: Duration) -> Duration {
+    // Each `RandomState` is seeded differently, which is random enough here.
+    let random = RandomState::new().hash_one(());
+    delay.mul_f64(0.5 + (random % 1000) as f64 / 1000.0)
+}
+
+/// How a scan orders and spaces its probes; see [`ScanConfig::order`].
+#[derive(Debug, Clone, Copy)]
+struct ProbeOrder {
+    /// Seed of the shuffles; `None` keeps the configured order.
+    seed: Option<u64>,
+    jitter: Option<Duration>,
+}
+
+impl ProbeOrder {
+    fn new(config: &ScanConfig) -> Self {
+        let seed = match config.order {
+            ScanOrder::Sequential => None,
+            ScanOrder::Random => {
+                let seed = config
+                    .order_seed
+                    .unwrap_or_else(|| RandomState::new().hash_one(()));
+                log::info!("Probing in random order (seed {})", seed);
+                Some(seed)
+            }
+        };
+        Self {
+            seed,
+            jitter: config.probe_jitter.filter(|jitter| !jitter.is_zero()),
+        }
+    }
+
+    /// Shuffles `items` if the order is random; `salt` gives each host its
+    /// own port order, still fixed by the seed.
+    fn arrange<T>(&self, items: &mut [T], salt: u64) {
+        if let Some(seed) = self.seed {
+            items.shuffle(&mut StdRng::seed_from_u64(seed ^ salt));
+        }
+    }
+
+    /// Waits a random time up to the configured jitter, if any.
+    async fn pause(&self) {
+        if let Some(jitter) = self.jitter {
+            let random = RandomState::new().hash_one(());
+            tokio::time::sleep(jitter.mul_f64((random % 1001) as f64 / 1000.0)).await;
+        }
+    }
+}
+
+/// Most DNS lookups resolved in one call.
+const DNS_BATCH_SIZE: usize = 64;
+
+/// Hosts probed on more TCP ports than this report each open port as
+/// [`BridgeMessage::PortFound`] as it is found, since their result may take
+/// minutes. Shorter port lists only show up in the final result.
+pub const PORT_STREAM_THRESHOLD: usize = 1024;
+
+/// Gathers the DNS lookups of hosts that finish probing around the same
+/// time, without holding any of them back, and resolves each batch with one
+/// provider call, so queries go out together instead of one blocked thread
+/// per host.
+struct DnsBatcher<K, V> {
+    requests: mpsc::UnboundedSender<(K, oneshot::Sender<Option<V>>)>,
+}
+
+// Derived `Clone` would require `K: Clone` and `V: Clone`.
+impl<K, V> Clone for DnsBatcher<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            requests: self.requests.clone(),
+        }
+    }
+}
+
+/// PTR lookups through [`NetworkProvider::resolve_hostnames`].
+type ReverseDns = DnsBatcher<Ipv4Addr, String>;
+
+/// A lookups through [`NetworkProvider::resolve_addresses`], to verify
+/// PTR names.
+type ForwardDns = DnsBatcher<String, Vec<Ipv4Addr>>;
+
+impl<K, V> DnsBatcher<K, V>
+where
+    K: Clone + Eq + std::hash::Hash + Send + 'static,
+    V: Send + 'static,
+{
+    /// Starts the batching task; it ends with the last clone. `resolve`
+    /// answers a batch with what it found.
+    fn start<F, Fut>(resolve: F) -> Self
+    where
+        F: Fn(Vec<K>) -> Fut + Send + 'static,
+        Fut: Future<Output = HashMap<K, V>> + Send + 'static,
+    {
+        let (requests, mut incoming) = mpsc::unbounded_channel::<(K, oneshot::Sender<Option<V>>)>();
+        tokio::spawn(async move {
+            while let Some(first) = incoming.recv().await {
+                // Let hosts finishing at the same moment queue up behind it.
+                tokio::task::yield_now().await;
+                let mut batch = vec![first];
+                while batch.len() < DNS_BATCH_SIZE
+                    && let Ok(request) = incoming.try_recv()
+                {
+                    batch.push(request);
+                }
+                let found = resolve(batch.iter().map(|(key, _)| key.clone()).collect());
+                tokio::spawn(async move {
+                    let mut found = found.await;
+                    for (key, reply) in batch {
+                        let _ = reply.send(found.remove(&key));
+                    }
+                });
+            }
+        });
+        Self { requests }
+    }
+
+    /// What the batch of `key` found for it, if anything.
+    async fn lookup(&self, key: K) -> Option<V> {
+        let (reply, found) = oneshot::channel();
+        self.requests.send((key, reply)).ok()?;
+        found.await.ok().flatten()
+    }
+}
+
+impl ReverseDns {
+    fn reverse(net: Arc<dyn NetworkProvider>, dns: DnsConfig) -> Self {
+        Self::start(move |ips| {
+            let (net, dns) = (net.clone(), dns.clone());
+            async move { net.resolve_hostnames(&ips, &dns).await }
+        })
+    }
+}
+
+impl ForwardDns {
+    fn forward(net: Arc<dyn NetworkProvider>, dns: DnsConfig) -> Self {
+        Self::start(move |names| {
+            let (net, dns) = (net.clone(), dns.clone());
+            async move { net.resolve_addresses(&names, &dns).await }
+        })
+    }
+}
+
+/// Returns `true` if `ip` is the network (`.0`) or broadcast (`.255`) address
+/// of a /24 that lies entirely within `start..=end`.
+///
+/// Such addresses cannot be real hosts on a full-subnet sweep. Edges of a
+/// partially covered /24 (e.g. `10.0.0.200-10.0.1.10`) are left alone, since
+/// the range may span a differently sized subnet.
+pub fn is_subnet_edge(ip: Ipv4Addr, start: Ipv4Addr, end: Ipv4Addr) -> bool {
+    let last = ip.octets()[3];
+    if last != 0 && last != 255 {
+        return false;
+    }
+    let network = u32::from(ip) & 0xFFFF_FF00;
+    network >= u32::from(start) && (network | 0xFF) <= u32::from(end)
+}
+
+/// How often [`BridgeMessage::ScanStats`] is sent and the adaptive rate adjusted.
+const STATS_INTERVAL: Duration = Duration::from_secs(1);
+/// Floor for adaptive backoff, so a congested network still makes progress.
+const MIN_ADAPTIVE_RATE: f64 = 10.0;
+/// Echoes needed in an interval before its timeout ratio is trusted.
+const MIN_RATE_SAMPLES: u32 = 20;
+/// Rise in the echo timeout ratio, over its running average, that counts as a spike.
+const TIMEOUT_SPIKE: f64 = 0.25;
+
+/// Paces the packets of a scan to a target rate and counts them.
+///
+/// Every echo and TCP/UDP probe waits in [`acquire`](RateController::acquire)
+/// for its slot, which is far enough behind the previous one to keep both
+/// the packet rate and the estimated bandwidth under their caps; ARP
+/// requests are only [`count`](RateController::count)ed. Either refuses
+/// the packet once the probe budget is used up. With adaptive backoff, [`adjust`](RateController::adjust)
+/// halves the rate when the share of unanswered echoes jumps above its
+/// running average (a router dropping packets looks like hosts going
+/// offline) and then recovers by a tenth per interval up to the target.
+pub struct RateController {
+    target: Option<f64>,
+    adaptive: bool,
+    budget: Option<u64>,
+    /// Bandwidth cap in bytes per second.
+    bandwidth: Option<f64>,
+    state: std::sync::Mutex<RateState>,
+}
+
+struct RateState {
+    rate: Option<f64>,
+    next_slot: tokio::time::Instant,
+    traffic: ProbeTraffic,
+    /// Packets let through, counted when granted so that probes waiting for
+    /// their slot can't overdraw the budget.
+    granted: u64,
+    exhausted: bool,
+    answered: u32,
+    timed_out: u32,
+    /// Running average of the echo timeout ratio; `None` until the first full interval.
+    baseline: Option<f64>,
+}
+
+impl RateController {
+    /// Creates a controller sending at most `max_rate` packets per second;
+    /// `None` only counts packets.
+    pub fn new(max_rate: Option<u32>, adaptive: bool) -> Self {
+        let target = max_rate.map(|r| f64::from(r.max(1)));
+        Self {
+            target,
+            adaptive,
+            budget: None,
+            bandwidth: None,
+            state: std::sync::Mutex::new(RateState {
+                rate: target,
+                next_slot: tokio::time::Instant::now(),
+                traffic: ProbeTraffic::default(),
+                granted: 0,
+                exhausted: false,
+                answered: 0,
+                timed_out: 0,
+                baseline: None,
+            }),
+        }
+    }
+
+    /// Refuses packets beyond the first `budget`.
+    pub fn with_budget(mut self, budget: Option<u64>) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Spaces packets so their estimated size stays under `kbps` kilobits
+    /// per second.
+    pub fn with_bandwidth(mut self, kbps: Option<u32>) -> Self {
+        self.bandwidth = kbps.map(|k| f64::from(k.max(1)) * 1000.0 / 8.0);
+        self
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, RateState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Takes one packet from the budget; `false` once it is used up.
+    fn grant(&self, state: &mut RateState) -> bool {
+        if self.budget.is_some_and(|budget| state.granted >= budget) {
+            state.exhausted = true;
+            return false;
+        }
+        state.granted += 1;
+        true
+    }
+
+    /// Waits until a `kind` packet of `bytes` may be sent and counts it;
+    /// `false`, at once, if the probe budget is used up.
+    ///
+    /// Packets are counted once their slot arrives, not when it is reserved,
+    /// so probes queued ahead don't inflate the reported rate.
+    pub async fn acquire(&self, kind: ProbeKind, bytes: u64) -> bool {
+        let slot = {
+            let mut state = self.state();
+            if !self.grant(&mut state) {
+                return false;
+            }
+            let by_rate = state.rate.map(|rate| 1.0 / rate);
+            let by_bandwidth = self.bandwidth.map(|cap| bytes as f64 / cap);
+            let Some(interval) = by_rate.into_iter().chain(by_bandwidth).reduce(f64::max) else {
+                state.traffic.record(kind, bytes);
+                return true;
+            };
+            let slot = state.next_slot.max(tokio::time::Instant::now());
+            state.next_slot = slot + Duration::from_secs_f64(interval);
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+        self.state().traffic.record(kind, bytes);
+        true
+    }
+
+    /// Counts a `kind` packet of `bytes` sent without pacing (ARP requests,
+    /// which the OS sends on its own schedule); `false`, without counting
+    /// it, if the probe budget is used up.
+    pub fn count(&self, kind: ProbeKind, bytes: u64) -> bool {
+        let mut state = self.state();
+        if !self.grant(&mut state) {
+            return false;
+        }
+        state.traffic.record(kind, bytes);
+        true
+    }
+
+    /// Records whether an echo was answered, for adaptive backoff.
+    pub fn record_echo(&self, answered: bool) {
+        let mut state = self.state();
+        if answered {
+            state.answered += 1;
+        } else {
+            state.timed_out += 1;
+        }
+    }
+
+    /// Closes an interval: backs off on a timeout spike, otherwise recovers
+    /// towards the target. Does nothing unless adaptive with a target.
+    pub fn adjust(&self) {
+        let (Some(target), true) = (self.target, self.adaptive) else {
+            return;
+        };
+        let mut state = self.state();
+        let samples = state.answered + state.timed_out;
+        if samples < MIN_RATE_SAMPLES {
+            return;
+        }
+        let ratio = f64::from(state.timed_out) / f64::from(samples);
+        (state.answered, state.timed_out) = (0, 0);
+        let rate = state.rate.unwrap_or(target);
+        state.rate = Some(match state.baseline {
+            Some(baseline) if ratio > baseline + TIMEOUT_SPIKE => {
+                let backoff = (rate / 2.0).max(MIN_ADAPTIVE_RATE.min(target));
+                log::warn!(
+                    "Echo timeouts rose to {:.0}%; backing off to {:.0} pkt/s",
+                    ratio * 100.0,
+                    backoff
+                );
+                backoff
+            }
+            _ => (rate * 1.1).min(target),
+        });
+        // The average follows a lasting change, such as moving into an
+        // empty subnet, so backoff stops once it is the new normal.
+        state.baseline = Some(match state.baseline {
+            Some(baseline) => baseline * 0.8 + ratio * 0.2,
+            None => ratio,
+        });
+    }
+
+    /// Current rate limit in packets per second, `None` if unlimited.
+    pub fn rate_limit(&self) -> Option<u32> {
+        self.state().rate.map(|r| r.round() as u32)
+    }
+
+    /// Packets sent so far.
+    pub fn packets_sent(&self) -> u64 {
+        self.state().traffic.total().packets
+    }
+
+    /// Packets and bytes sent so far, by kind.
+    pub fn traffic(&self) -> ProbeTraffic {
+        self.state().traffic
+    }
+
+    /// Whether a packet has been refused for want of budget.
+    pub fn budget_exhausted(&self) -> bool {
+        self.state().exhausted
+    }
+}
+
+/// Tracks when the last [`BridgeMessage::ScanStats`] was sent.
+struct StatsClock {
+    started: tokio::time::Instant,
+    last: tokio::time::Instant,
+    last_sent: u64,
+}
+
+impl StatsClock {
+    fn new() -> Self {
+        let now = tokio::time::Instant::now();
+        Self {
+            started: now,
+            last: now,
+            last_sent: 0,
+        }
+    }
+}
+
+/// Hosts of a scan by final status, for its [`ScanSummary`].
+#[derive(Default)]
+struct HostTally {
+    up: AtomicU32,
+    down: AtomicU32,
+    errors: AtomicU32,
+}
+
+impl HostTally {
+    fn record(&self, status: &ScanStatus) {
+        let counter = match status {
+            ScanStatus::Online => &self.up,
+            ScanStatus::Offline => &self.down,
+            ScanStatus::SystemError(_) => &self.errors,
+            ScanStatus::Scanning | ScanStatus::NotScanned => return,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn summary(&self, duration: Duration, rate: &RateController) -> ScanSummary {
+        ScanSummary {
+            duration,
+            hosts_up: self.up.load(Ordering::Relaxed),
+            hosts_down: self.down.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            probes_sent: rate.packets_sent(),
+            traffic: rate.traffic(),
+            budget_exhausted: rate.budget_exhausted(),
+        }
+    }
+}
+
+/// The phase of each host of the scan in progress, read on demand as a
+/// [`StateSnapshot`]. A scanner starts over for every scan (or monitor sweep).
+#[derive(Default)]
+pub struct HostStates(Mutex<StateSnapshot>);
+
+impl HostStates {
+    /// Where every host of the current scan stands.
+    pub fn snapshot(&self) -> StateSnapshot {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Forgets the previous scan; all `total` targets are pending.
+    fn start(&self, total: u32) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = StateSnapshot {
+            hosts: Default::default(),
+            pending: total,
+        };
+    }
+
+    fn set(&self, ip: Ipv4Addr, phase: HostPhase) {
+        let mut state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if state.hosts.insert(ip, phase).is_none() {
+            state.pending = state.pending.saturating_sub(1);
+        }
+    }
+}
+
+/// Hosts through discovery and port probes sent, for [`PhaseProgress`].
+struct PhaseTracker {
+    hosts_total: u32,
+    hosts_discovered: AtomicU32,
+    ports_probed: AtomicU32,
+    /// `None` when the scan probes no ports.
+    ports_total: Option<AtomicU32>,
+    /// Percentages of the last [`BridgeMessage::Phase`] sent: discovery in
+    /// the high byte, ports in the low one.
+    last_phase: AtomicU16,
+    /// Highest [`BridgeMessage::Progress`] sent, so the bar never goes back
+    /// when hosts found online add port probes.
+    last_overall: AtomicU8,
+    /// Held from snapshot to send, so reports from concurrent hosts reach
+    /// the channel in the order they were taken.
+    reporting: tokio::sync::Mutex<()>,
+}
+
+impl PhaseTracker {
+    fn new(hosts_total: u32, probes_ports: bool) -> Self {
+        Self {
+            hosts_total,
+            hosts_discovered: AtomicU32::new(0),
+            ports_probed: AtomicU32::new(0),
+            ports_total: probes_ports.then(|| AtomicU32::new(0)),
+            last_phase: AtomicU16::new(u16::MAX),
+            last_overall: AtomicU8::new(0),
+            reporting: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    fn snapshot(&self) -> PhaseProgress {
+        PhaseProgress {
+            hosts_discovered: self.hosts_discovered.load(Ordering::Relaxed),
+            hosts_total: self.hosts_total,
+            ports_probed: self.ports_probed.load(Ordering::Relaxed),
+            ports_total: self.ports_total.as_ref().map(|t| t.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Counts a host through discovery, owing `ports` probes.
+    fn host_discovered(&self, ports: u32) {
+        if let Some(total) = &self.ports_total {
+            total.fetch_add(ports, Ordering::Relaxed);
+        }
+        self.hosts_discovered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn port_probed(&self) {
+        self.ports_probed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Sends [`BridgeMessage::Phase`] if either percentage changed and
+    /// [`BridgeMessage::Progress`] if the overall percentage rose.
+    async fn report(&self, tx: &Sender<BridgeMessage>) {
+        let _turn = self.reporting.lock().await;
+        let phase = self.snapshot();
+        let code = u16::from(phase.discovery_percent()) << 8
+            | u16::from(phase.ports_percent().unwrap_or(0));
+        if self.last_phase.swap(code, Ordering::Relaxed) != code {
+            let _ = tx.send(BridgeMessage::Phase(phase)).await;
+        }
+        let overall = phase.overall_percent();
+        if self.last_overall.fetch_max(overall, Ordering::Relaxed) < overall {
+            let _ = tx.send(BridgeMessage::Progress(overall)).await;
+        }
+    }
+}
+
+/// Per-range host counts of a multi-range scan; empty for single scans.
+#[derive(Default)]
+struct RangeTracker {
+    ranges: Vec<RangeProgress>,
+    include_edges: bool,
+}
+
+impl RangeTracker {
+    fn new(targets: &ScanTargets, include_edges: bool) -> Self {
+        let ranges = targets
+            .ranges()
+            .iter()
+            .enumerate()
+            .map(|(index, &(start, end))| RangeProgress {
+                index,
+                start,
+                end,
+                completed: 0,
+                total: (u32::from(start)..=u32::from(end))
+                    .filter(|&ip| include_edges || !is_subnet_edge(ip.into(), start, end))
+                    .count() as u32,
+            })
+            .collect();
+        Self {
+            ranges,
+            include_edges,
+        }
+    }
+
+    /// Counts `ip` towards every range holding it and returns the ranges
+    /// whose percentage changed.
+    fn finished(&mut self, ip: Ipv4Addr) -> Vec<RangeProgress> {
+        let include_edges = self.include_edges;
+        self.ranges
+            .iter_mut()
+            .filter(|r| (r.start..=r.end).contains(&ip))
+            .filter(|r| include_edges || !is_subnet_edge(ip, r.start, r.end))
+            .filter_map(|r| {
+                let before = r.percent();
+                r.completed += 1;
+                (r.percent() != before || r.is_complete()).then(|| r.clone())
+            })
+            .collect()
+    }
+}
+
+/// A host handed to a scan worker.
+struct HostJob {
+    ip: Ipv4Addr,
+    /// `false` once the host has had its retry.
+    can_retry: bool,
+    /// Cloned at dispatch, so changes from then on are seen.
+    interfaces: InterfaceWatch,
+    /// [`PowerState::sleeps`](crate::net::PowerState::sleeps) at dispatch.
+    sleeps: u64,
+}
+
+/// How a host left its worker: `Ok((ip, retry))`, or `Err(ip)` if its
+/// probe panicked.
+type HostDone = Result<(Ipv4Addr, bool), Ipv4Addr>;
+
+/// The dispatcher's count of the hosts of a scan.
+struct Dispatch {
+    total: u32,
+    completed: u32,
+    /// Hosts queued or being probed.
+    in_flight: usize,
+    /// Hosts that asked for one more try; dispatched before new ones.
+    retries: VecDeque<Ipv4Addr>,
+    ranges: RangeTracker,
+    clock: StatsClock,
+}
+
+impl Dispatch {
+    fn new(total: u32, ranges: RangeTracker) -> Self {
+        Self {
+            total,
+            completed: 0,
+            in_flight: 0,
+            retries: VecDeque::new(),
+            ranges,
+            clock: StatsClock::new(),
+        }
+    }
+}
+
+/// Most finished hosts recorded per batch; see [`Scanner::await_finished`].
+const FLUSH_BATCH: usize = 256;
+
+/// Everything the workers of one scan share: its settings, the pre-pass
+/// findings and the counters every host adds to.
+struct HostProbe {
+    net_utils: Arc<dyn NetworkProvider>,
+    tx: Sender<BridgeMessage>,
+    config: ScanConfig,
+    capabilities: Capabilities,
+    ping_attempts: u32,
+    udp_scan: bool,
+    /// TCP and UDP probes owed by each host found online.
+    ports_per_host: u32,
+    order: ProbeOrder,
+    arp_cache: HashMap<Ipv4Addr, String>,
+    mdns_hosts: HashMap<Ipv4Addr, MdnsHost>,
+    upnp_hosts: HashMap<Ipv4Addr, UpnpInfo>,
+    roles: HashMap<Ipv4Addr, Vec<NetworkRole>>,
+    reverse_dns: Option<ReverseDns>,
+    forward_dns: Option<ForwardDns>,
+    ports: Vec<u16>,
+    snmp_community: Option<Arc<str>>,
+    port_cache: Option<Arc<PortCache>>,
+    /// [`MAX_SCAN_CONNECTIONS`] slots, one held by each TCP connection.
+    connections: Semaphore,
+    power: PowerWatch,
+    rate: Arc<RateController>,
+    intercept: Mutex<InterceptDetector>,
+    probes: Arc<ProbeRegistry>,
+    tally: HostTally,
+    phases: PhaseTracker,
+    host_states: Arc<HostStates>,
+}
+
+impl HostProbe {
+    /// Scans hosts off `queue` until it closes, reporting each on `done`.
+    async fn work(
+        self: Arc<Self>,
+        queue: Arc<tokio::sync::Mutex<mpsc::Receiver<HostJob>>>,
+        done: mpsc::UnboundedSender<HostDone>,
+    ) {
+        loop {
+            let Some(job) = queue.lock().await.recv().await else {
+                break;
+            };
+            let ip = job.ip;
+            // A panicking probe costs its host, not the worker.
+            let finished = AssertUnwindSafe(self.scan(job))
+                .catch_unwind()
+                .await
+                .map_err(|_| ip);
+            if done.send(finished).is_err() {
+                break;
+            }
+        }
+    }
+
+    /// The name of a host: its PTR name or, for a host that answered, its
+    /// NetBIOS or LLMNR name. Those queries block, so they run on the
+    /// blocking pool; nothing else a host needs does.
+    async fn hostname_of(
+        &self,
+        ip: Ipv4Addr,
+        dns_name: Option<String>,
+        online: bool,
+    ) -> Option<(String, NameSource)> {
+        if !self.config.resolve_hostnames {
+            return None;
+        }
+        match dns_name {
+            Some(name) => Some((name, NameSource::Dns)),
+            None if online && self.capabilities.udp => {
+                let net_utils = self.net_utils.clone();
+                tokio::task::spawn_blocking(move || net_utils.resolve_local_name(ip))
+                    .await
+                    .unwrap_or_else(|e| {
+                        log::warn!("Name lookup for {} failed: {}", ip, e);
+                        None
+                    })
+            }
+            None => None,
+        }
+    }
+
+    /// Probes one host and reports it. Returns its address and whether it
+    /// should be tried again.
+    async fn scan(&self, job: HostJob) -> (Ipv4Addr, bool) {
+        let HostJob {
+            ip,
+            can_retry,
+            mut interfaces,
+            sleeps,
+        } = job;
+        let Self {
+            net_utils,
+            tx,
+            config,
+            capabilities,
+            order,
+            rate,
+            tally,
+            phases,
+            host_states,
+            port_cache,
+            ports,
+            ..
+        } = self;
+        let (udp_scan, ports_per_host) = (self.udp_scan, self.ports_per_host);
+        let cached_mac = self.arp_cache.get(&ip).cloned();
+        let mdns_host = self.mdns_hosts.get(&ip).cloned();
+        let upnp_info = self.upnp_hosts.get(&ip).cloned();
+        // Distinct from the host shuffle's salt of 0.
+        let port_salt = (1 << 32) | u64::from(u32::from(ip));
+        // Nothing but a port can answer through a proxy, so every target gets one.
+        let proxied = config.proxy.is_some();
+        let started = tokio::time::Instant::now();
+        let finished = |mut result: ScanResult| {
+            result.stamp(started.elapsed(), SystemTime::now());
+            tally.record(&result.status);
+            if let Some(phase) = HostPhase::from_status(&result.status) {
+                host_states.set(ip, phase);
+            }
+            BridgeMessage::ScanUpdate(result)
+        };
+        let mut result = ScanResult::new(ip);
+        result.roles = self.roles.get(&ip).cloned().unwrap_or_default();
+        log::info!("Scanning: {}", ip);
+
+        // Try Ping. A host that misses the first echo gets `config.retries`
+        // more; only hosts that answer get the remaining attempts, so
+        // offline hosts cost `config.retries + 1` timeouts. A phase that
+        // fails leaves a warning and the next phase still runs.
+        let mut warnings = Vec::new();
+        let mut rtts = Vec::new();
+        let mut ttl = None;
+        let mut first_reply = None;
+        let mut sent = 0;
+        let mut attempts = self.ping_attempts;
+        // Set when the probe budget refused one of this host's packets.
+        let starved = AtomicBool::new(false);
+        while sent < attempts {
+            if !rate.acquire(ProbeKind::Icmp, ECHO_REQUEST_BYTES).await {
+                starved.store(true, Ordering::Relaxed);
+                break;
+            }
+            sent += 1;
+            order.pause().await;
+            let reply = net_utils.echo_async(ip, config.ping_timeout).await;
+            if let Ok(answer) = &reply {
+                rate.record_echo(answer.is_some());
+            }
+            match reply {
+                Ok(Some(echo)) => {
+                    rtts.push(echo.rtt_ms);
+                    ttl = ttl.or(echo.ttl);
+                    first_reply = first_reply.or(Some(sent));
+                }
+                Ok(None) if rtts.is_empty() => {
+                    if sent > config.retries {
+                        break;
+                    }
+                    // A retry does not use up one of the attempts.
+                    attempts += 1;
+                    tokio::time::sleep(jittered(config.retry_delay)).await;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    log::warn!("Echo to {} failed: {}", ip, e);
+                    warnings.push(e);
+                    break;
+                }
+            }
+        }
+        let echo_failed = !warnings.is_empty();
+        let latency = LatencyStats::from_samples(&rtts, sent);
+        let is_online = latency.is_some();
+        result.attempts.ping = first_reply.unwrap_or(sent);
+
+        // Try ARP (cache hit first, a request only on a miss). Hosts
+        // that ignored ICMP get the ping retries over ARP too.
+        let mut arp_attempts = 0;
+        let mut mac = None;
+        let mut arp_failed = false;
+        if capabilities.arp {
+            match cached_mac {
+                Some(cached) => mac = Some(cached),
+                None => {
+                    let tries = if is_online { 1 } else { config.retries + 1 };
+                    while arp_attempts < tries && mac.is_none() {
+                        if arp_attempts > 0 {
+                            tokio::time::sleep(jittered(config.retry_delay)).await;
+                        }
+                        if !rate.count(ProbeKind::Arp, ARP_REQUEST_BYTES) {
+                            starved.store(true, Ordering::Relaxed);
+                            break;
+                        }
+                        arp_attempts += 1;
+                        match net_utils.resolve_mac_async(ip).await {
+                            Ok(found) => mac = found,
+                            Err(e) => {
+                                log::warn!("ARP for {} failed: {}", ip, e);
+                                warnings.push(e);
+                                arp_failed = true;
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let unprobed = sent == 0 && arp_attempts == 0 && mac.is_none();
+        if unprobed && starved.load(Ordering::Relaxed) {
+            // The budget ran out before this host got a single packet.
+            phases.host_discovered(0);
+            phases.report(tx).await;
+            result.status = ScanStatus::NotScanned;
+            let _ = tx.send(finished(result)).await;
+            return (ip, false);
+        }
+
+        // Reverse DNS first; hosts that answered may also name themselves.
+        let dns_name = match &self.reverse_dns {
+            Some(reverse_dns) => reverse_dns.lookup(ip).await,
+            None => None,
+        };
+        // A name leading elsewhere sends anyone who trusts it to the wrong host.
+        let dns_mismatch = match (&self.forward_dns, &dns_name) {
+            (Some(forward_dns), Some(name)) => forward_dns
+                .lookup(name.clone())
+                .await
+                .filter(|addresses| !addresses.contains(&ip))
+                .map(|addresses| SecurityIssue::DnsMismatch {
+                    name: name.clone(),
+                    addresses,
+                }),
+            _ => None,
+        };
+        // Only a host on which every phase failed, with nothing
+        // learned, is an error; anything else keeps its partial data.
+        let learned = is_online
+            || mac.is_some()
+            || dns_name.is_some()
+            || mdns_host.is_some()
+            || upnp_info.is_some();
+        let failure = (echo_failed && (arp_failed || !capabilities.arp) && !learned)
+            .then(|| warnings.remove(0));
+        // A MAC proves the host is up even if it ignored ICMP.
+        let outcome = match failure {
+            Some(err) => Err(err),
+            None => Ok(is_online || mac.is_some()),
+        };
+
+        let slept = self.power.state().sleeps != sleeps;
+        // Only a clean answer is kept when the network was disturbed.
+        let clean = warnings.is_empty();
+        match outcome {
+            Ok(online) if can_retry && !(online && clean) && slept => {
+                log::info!(
+                    "{} looked offline or failed a probe across a system sleep; retrying",
+                    ip
+                );
+                return (ip, true);
+            }
+            Ok(online) if can_retry && !(online && clean) && interfaces.take_change() => {
+                log::info!(
+                    "{} looked offline or failed a probe during an interface change; retrying",
+                    ip
+                );
+                return (ip, true);
+            }
+            Err(e) if can_retry && slept => {
+                log::info!("Error for {} across a system sleep ({}); retrying", ip, e);
+                return (ip, true);
+            }
+            Err(e) if can_retry && interfaces.take_change() => {
+                log::info!(
+                    "Error for {} during an interface change ({}); retrying",
+                    ip,
+                    e
+                );
+                return (ip, true);
+            }
+            Ok(is_online) => {
+                let mut hostname = self.hostname_of(ip, dns_name, is_online).await;
+                let mut vendor = mac
+                    .as_deref()
+                    .filter(|_| config.resolve_vendors)
+                    .and_then(|mac| net_utils.resolve_vendor(mac));
+                result.attempts.arp = arp_attempts;
+                result.warnings = warnings;
+                result.issues.extend(dns_mismatch);
+                // A host that answered mDNS or SSDP is up even if it ignored ICMP.
+                let is_online = is_online || mdns_host.is_some() || upnp_info.is_some();
+                if let Some(host) = mdns_host {
+                    if hostname.is_none() && config.resolve_hostnames {
+                        hostname = host.hostname.map(|name| (name, NameSource::Mdns));
+                    }
+                    result.services = host.services;
+                }
+                if let Some(info) = upnp_info {
+                    // Better than nothing when the OUI is unknown or randomized.
+                    let placeholder =
+                        vendor.is_none() || vendor.as_deref() == Some(RANDOMIZED_MAC_VENDOR);
+                    if placeholder && config.resolve_vendors && info.manufacturer.is_some() {
+                        vendor = info.manufacturer.clone();
+                    }
+                    result.upnp = Some(info);
+                }
+                log::info!("Scan result for {}: online={}", ip, is_online);
+                // Force reporting for debugging
+                if true {
+                    // was if is_online
+                    if is_online {
+                        result.status = ScanStatus::Online;
+                    } else {
+                        result.status = ScanStatus::Offline;
+                    }
+                    result.mac = mac;
+                    (result.hostname, result.hostname_source) = hostname.unzip();
+                    result.vendor = vendor;
+                    result.latency = latency;
+                    result.asset = inventory::lookup(ip, result.mac.as_deref());
+                    result.device_note = devices::lookup(result.mac.as_deref());
+
+                    // Port Scan (Async), skipped when a fresh cache entry
+                    // exists for the same device.
+                    let cached = port_cache
+                        .as_ref()
+                        .and_then(|c| c.get(ip, result.mac.as_deref(), udp_scan));
+                    let probe_ports = is_online || proxied;
+                    let owed = if probe_ports && cached.is_none() {
+                        ports_per_host
+                    } else {
+                        0
+                    };
+                    phases.host_discovered(owed);
+                    phases.report(tx).await;
+                    if owed > 0 {
+                        host_states.set(ip, HostPhase::Ports);
+                    }
+                    if let (true, Some(hit)) = (probe_ports, cached) {
+                        log::info!("Using cached port results for {}", ip);
+                        result.open_ports = hit.open_ports;
+                        result.open_udp_ports = hit.open_udp_ports;
+                        result.filtered_udp_ports = hit.filtered_udp_ports;
+                    } else if probe_ports {
+                        let port_timeout = config
+                            .port_timeout
+                            .unwrap_or_else(|| adaptive_port_timeout(result.latency.as_ref()));
+                        // Probe concurrently so a host that drops SYNs costs
+                        // about one timeout per `port_concurrency` ports.
+                        let limit = Semaphore::new(config.port_concurrency.max(1));
+                        let mut host_ports = ports.to_vec();
+                        order.arrange(&mut host_ports, port_salt);
+                        let mut probes: FuturesUnordered<_> = host_ports
+                            .iter()
+                            .map(|&port| {
+                                let (limit, starved) = (&limit, &starved);
+                                async move {
+                                    let _permit = limit.acquire().await;
+                                    let _connection = self.connections.acquire().await;
+                                    if !rate.acquire(ProbeKind::Tcp, TCP_SYN_BYTES).await {
+                                        starved.store(true, Ordering::Relaxed);
+                                        return (port, false);
+                                    }
+                                    order.pause().await;
+                                    let open = net_utils
+                                        .scan_port_with_options(
+                                            ip,
+                                            port,
+                                            port_timeout,
+                                            config.tcp_options,
+                                        )
+                                        .await;
+                                    (port, open)
+                                }
+                            })
+                            .collect();
+                        let stream_ports = ports.len() > PORT_STREAM_THRESHOLD;
+                        let mut open_set = HashSet::new();
+                        while let Some((port, open)) = probes.next().await {
+                            phases.port_probed();
+                            phases.report(tx).await;
+                            if open {
+                                open_set.insert(port);
+                                if stream_ports {
+                                    let _ = tx.send(BridgeMessage::PortFound { ip, port }).await;
+                                }
+                            }
+                        }
+                        // Report in the configured port order.
+                        let mut open_ports: Vec<PortInfo> = ports
+                            .iter()
+                            .filter(|port| open_set.contains(port))
+                            .map(|&port| PortInfo::new(port))
+                            .collect();
+                        for info in &mut open_ports {
+                            let _connection = self.connections.acquire().await;
+                            info.banner = net_utils.grab_banner(ip, info.port).await;
+                        }
+                        result.open_ports = open_ports;
+
+                        if udp_scan {
+                            let mut udp_ports: Vec<u16> =
+                                COMMON_UDP_PORTS.iter().map(|&(port, _)| port).collect();
+                            order.arrange(&mut udp_ports, port_salt);
+                            for port in udp_ports {
+                                let bytes = udp_probe_bytes(port);
+                                if !rate.acquire(ProbeKind::Udp, bytes).await {
+                                    starved.store(true, Ordering::Relaxed);
+                                    phases.port_probed();
+                                    continue;
+                                }
+                                order.pause().await;
+                                match net_utils.scan_udp_port(ip, port).await {
+                                    UdpPortState::Open => result.open_udp_ports.push(port),
+                                    UdpPortState::OpenFiltered => {
+                                        result.filtered_udp_ports.push(port)
+                                    }
+                                    UdpPortState::Closed => {}
+                                }
+                                phases.port_probed();
+                                phases.report(tx).await;
+                            }
+                            let rank =
+                                |port: &u16| COMMON_UDP_PORTS.iter().position(|&(p, _)| p == *port);
+                            result.open_udp_ports.sort_by_key(rank);
+                            result.filtered_udp_ports.sort_by_key(rank);
+                        }
+
+                        // Ports the budget skipped are unknown, not closed.
+                        if let Some(cache) = port_cache
+                            .as_ref()
+                            .filter(|_| !starved.load(Ordering::Relaxed))
+                        {
+                            cache.insert(&result, udp_scan);
+                        }
+                    }
+                    let is_online = is_online || !result.open_ports.is_empty();
+                    if is_online {
+                        result.status = ScanStatus::Online;
+                    }
+
+                    // SNMP, unless a UDP scan already found port 161 closed.
+                    let snmp_closed = udp_scan
+                        && !result.open_udp_ports.contains(&SNMP_PORT)
+                        && !result.filtered_udp_ports.contains(&SNMP_PORT);
+                    if let Some(community) = self.snmp_community.clone()
+                        && is_online
+                        && !snmp_closed
+                    {
+                        let net_utils = net_utils.clone();
+                        result.snmp = tokio::task::spawn_blocking(move || {
+                            net_utils.query_snmp(ip, &community)
+                        })
+                        .await
+                        .unwrap_or(None);
+                        // An answer proves a port the UDP scan could only call open|filtered.
+                        if result.snmp.is_some() && udp_scan {
+                            result.filtered_udp_ports.retain(|&p| p != SNMP_PORT);
+                            if !result.open_udp_ports.contains(&SNMP_PORT) {
+                                result.open_udp_ports.push(SNMP_PORT);
+                                result.open_udp_ports.sort_unstable();
+                            }
+                        }
+                    }
+
+                    if is_online {
+                        // Service probes connect one at a time, so one slot covers them.
+                        let _connection = self.connections.acquire().await;
+                        result.web_info = web::probe(&**net_utils, ip, &result.open_ports).await;
+                        let warning = result.web_info.as_ref().and_then(|info| {
+                            self.intercept
+                                .lock()
+                                .ok()?
+                                .record(result.vendor.as_deref(), info)
+                        });
+                        if let Some(warning) = warning {
+                            log::warn!("{}", warning);
+                            let _ = tx.send(BridgeMessage::InterceptionSuspected(warning)).await;
+                        }
+                        let smb_open = result.open_ports.iter().any(|p| p.port == SMB_PORT);
+                        if smb_open {
+                            result.smb_info = net_utils.smb_info(ip).await;
+                            if result.smb_info.as_ref().is_some_and(SmbInfo::smb1_only) {
+                                log::warn!("{} only speaks SMBv1", ip);
+                                result.issues.push(SecurityIssue::Smb1Only);
+                            }
+                        }
+                        if config.smb1_audit
+                            && smb_open
+                            && net_utils.probe_smb1(ip).await == Some(true)
+                        {
+                            log::warn!("{} accepts SMBv1", ip);
+                            result.issues.push(SecurityIssue::Smb1Enabled);
+                        }
+                        if config.tls_audit {
+                            for &port in TLS_AUDIT_PORTS {
+                                if !result.open_ports.iter().any(|p| p.port == port) {
+                                    continue;
+                                }
+                                if let Some(info) = net_utils.audit_tls(ip, port).await {
+                                    let now = std::time::SystemTime::now();
+                                    result.issues.extend(tls::issues(&info, now));
+                                    result.tls.push(info);
+                                }
+                            }
+                        }
+                        result.os_guess = fingerprint::guess_os(
+                            ttl,
+                            &result.open_ports,
+                            result.vendor.as_deref(),
+                        );
+                        result.device_type = classify::classify(&result);
+                        self.probes.run(&mut result, &**net_utils).await;
+                    }
+
+                    if starved.load(Ordering::Relaxed) {
+                        result.warnings.push(GError::Internal(
+                            "Probe budget used up before this host was fully probed".to_string(),
+                        ));
+                    }
+                    let _ = tx.send(finished(result)).await;
+                }
+            }
+            Err(e) => {
+                log::error!("System error scanning {}: {}", ip, e);
+                phases.host_discovered(0);
+                phases.report(tx).await;
+                result.status = ScanStatus::SystemError(e);
+                result.warnings = warnings;
+                let _ = tx.send(finished(result)).await;
+            }
+        }
+        (ip, false)
+    }
+}
 
 impl Scanner {
     /// Creates a new scanner with the given network provider and result channel.
@@ -24,223 +1208,2691 @@ impl Scanner {
         Self {
             net_utils,
             tx_bridge,
+            udp_scan: AtomicBool::new(false),
+            include_subnet_edges: AtomicBool::new(false),
+            ping_attempts: AtomicU32::new(1),
+            port_cache: None,
+            config: ScanConfig::default(),
+            // The sender is dropped, so the scan is never paused.
+            pause: watch::channel(false).1,
+            probes: Arc::default(),
+            host_states: Arc::default(),
+        }
+    }
+
+    /// Applies scan tuning: concurrency, timeouts, retries, ports and lookups.
+    ///
+    /// A port cache shared with scans probing a different port list would
+    /// hand back their results, so don't combine a custom
+    /// [`ScanConfig::ports`] with [`with_port_cache`](Scanner::with_port_cache).
+    ///
+    /// With [`ScanConfig::proxy`], the scanner's provider is wrapped in a
+    /// [`Socks5Provider`], and with [`ScanConfig::max_host_connections`] in a
+    /// [`HostLimitProvider`].
+    pub fn with_config(mut self, config: ScanConfig) -> Self {
+        if let Some(proxy) = &config.proxy {
+            log::info!("TCP probes go through the SOCKS5 proxy {}", proxy.addr);
+            self.net_utils = Arc::new(Socks5Provider::new(self.net_utils, proxy.clone()));
+        }
+        if let Some(limit) = config.max_host_connections {
+            log::info!("At most {} TCP connections per host", limit);
+            self.net_utils = Arc::new(HostLimitProvider::new(self.net_utils, limit));
+        }
+        self.config = config;
+        self
+    }
+
+    /// Attaches a port result cache shared across scans.
+    ///
+    /// Online hosts with a fresh entry for the same MAC skip the port phase.
+    pub fn with_port_cache(mut self, cache: Arc<PortCache>) -> Self {
+        self.port_cache = Some(cache);
+        self
+    }
+
+    /// Lets the owner of `pause` hold the scan: while it is `true`, no new
+    /// hosts are dispatched and in-flight hosts finish.
+    ///
+    /// The scan reports [`BridgeMessage::ScanState`] when it stops and
+    /// restarts dispatching. A scan waiting for a free slot notices the pause
+    /// once a host finishes.
+    pub fn with_pause(mut self, pause: watch::Receiver<bool>) -> Self {
+        self.pause = pause;
+        self
+    }
+
+    /// Runs the custom `probes` on every online host after the built-in
+    /// probes; what they find lands in [`ScanResult::extras`].
+    pub fn with_probes(mut self, probes: ProbeRegistry) -> Self {
+        self.probes = Arc::new(probes);
+        self
+    }
+
+    /// Records the phase of each host in `states`, so a
+    /// [`StateSnapshot`] of the running scan can be taken at any time.
+    pub fn with_host_states(mut self, states: Arc<HostStates>) -> Self {
+        self.host_states = states;
+        self
+    }
+
+    /// Sets how many ICMP echoes are sent to each host that answers the first one.
+    ///
+    /// More attempts give more meaningful min/avg/max latency at the cost of
+    /// scan time. Values below 1 are clamped to 1.
+    pub fn set_ping_attempts(&self, attempts: u32) {
+        self.ping_attempts.store(attempts.max(1), Ordering::SeqCst);
+    }
+
+    /// Enables or disables UDP probing of [`COMMON_UDP_PORTS`] on online hosts.
+    ///
+    /// Takes effect for scans started after the call.
+    pub fn set_udp_scan(&self, enabled: bool) {
+        self.udp_scan.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Includes network and broadcast addresses of fully covered /24s in range scans.
+    ///
+    /// Off by default: [`scan_range`](Scanner::scan_range) skips addresses
+    /// matching [`is_subnet_edge`]. Explicit target lists are never filtered.
+    pub fn set_include_subnet_edges(&self, enabled: bool) {
+        self.include_subnet_edges.store(enabled, Ordering::SeqCst);
+    }
+
+    /// Returns whether UDP probing is enabled.
+    pub fn udp_scan_enabled(&self) -> bool {
+        self.udp_scan.load(Ordering::SeqCst)
+    }
+
+    /// Scans a contiguous range of IPv4 addresses.
+    ///
+    /// Sends [`BridgeMessage::ScanUpdate`], [`BridgeMessage::Progress`], and
+    /// [`BridgeMessage::ScanComplete`] (or [`BridgeMessage::ScanCancelled`]) through the channel.
+    ///
+    /// # Errors
+    ///
+    /// System-level errors (e.g. Win32 API failures) are not returned directly. Instead:
+    /// - Range-level errors are sent via [`BridgeMessage::Error`].
+    /// - Individual IP failures are sent via [`BridgeMessage::ScanUpdate`] with [`ScanStatus::SystemError`](crate::types::ScanStatus::SystemError),
+    ///   but only when every probe phase failed; a host with partial data keeps
+    ///   its status and lists the failures in [`ScanResult::warnings`].
+    pub async fn scan_range(
+        &self,
+        start_ip: Ipv4Addr,
+        end_ip: Ipv4Addr,
+        cancel_token: tokio_util::sync::CancellationToken,
+    ) {
+        let start_u32: u32 = u32::from(start_ip);
+        let end_u32: u32 = u32::from(end_ip);
+
+        // Simple validation
+        if start_u32 > end_u32 {
+            let _ = self
+                .tx_bridge
+                .send(BridgeMessage::Error(GError::Internal(
+                    "Invalid IP Range".to_string(),
+                )))
+                .await;
+            return;
+        }
+
+        let include_edges = self.include_subnet_edges.load(Ordering::SeqCst);
+        let ips = (start_u32..=end_u32)
+            .map(Ipv4Addr::from)
+            .filter(move |ip| include_edges || !is_subnet_edge(*ip, start_ip, end_ip));
+        let total_ips = ips.clone().count() as u32;
+
+        log::info!(
+            "Starting scan for range: {} - {} (Total: {}, skipped subnet edges: {})",
+            start_ip,
+            end_ip,
+            total_ips,
+            end_u32 - start_u32 + 1 - total_ips
+        );
+        self.run_scan(ips, total_ips, RangeTracker::default(), cancel_token)
+            .await;
+    }
+
+    /// Scans an explicit list of IPv4 addresses, e.g. one extracted from pasted text.
+    ///
+    /// Emits the same message sequence as [`scan_range`](Scanner::scan_range).
+    /// An empty list is reported via [`BridgeMessage::Error`].
+    pub async fn scan_targets(
+        &self,
+        targets: Vec<Ipv4Addr>,
+        cancel_token: tokio_util::sync::CancellationToken,
+    ) {
+        if targets.is_empty() {
+            let _ = self
+                .tx_bridge
+                .send(BridgeMessage::Error(GError::Internal(
+                    "Target list is empty".to_string(),
+                )))
+                .await;
+            return;
+        }
+
+        log::info!("Starting scan for {} listed targets", targets.len());
+        let total_ips = targets.len() as u32;
+        self.run_scan(
+            targets.into_iter(),
+            total_ips,
+            RangeTracker::default(),
+            cancel_token,
+        )
+        .await;
+    }
+
+    /// Scans several ranges in one pass, e.g. disjoint VLANs.
+    ///
+    /// Addresses are merged and de-duplicated before dispatch, so overlapping
+    /// ranges probe each host once, in ascending order. Subnet edges are
+    /// skipped per range as in [`scan_range`](Scanner::scan_range). Emits the
+    /// same message sequence; an empty target set is reported via
+    /// [`BridgeMessage::Error`].
+    pub async fn scan_multi(
+        &self,
+        targets: ScanTargets,
+        cancel_token: tokio_util::sync::CancellationToken,
+    ) {
+        let include_edges = self.include_subnet_edges.load(Ordering::SeqCst);
+        let mut ips = BTreeSet::new();
+        for &(start, end) in targets.ranges() {
+            ips.extend(
+                (u32::from(start)..=u32::from(end))
+                    .map(Ipv4Addr::from)
+                    .filter(|ip| include_edges || !is_subnet_edge(*ip, start, end)),
+            );
+        }
+
+        if ips.is_empty() {
+            let _ = self
+                .tx_bridge
+                .send(BridgeMessage::Error(GError::Internal(
+                    "Target list is empty".to_string(),
+                )))
+                .await;
+            return;
+        }
+
+        log::info!(
+            "Starting scan for {} ranges (Total: {} unique hosts)",
+            targets.ranges().len(),
+            ips.len()
+        );
+        let total_ips = ips.len() as u32;
+        // A single range is already covered by the overall progress.
+        let ranges = if targets.ranges().len() > 1 {
+            RangeTracker::new(&targets, include_edges)
+        } else {
+            RangeTracker::default()
+        };
+        for range in &ranges.ranges {
+            let _ = self
+                .tx_bridge
+                .send(BridgeMessage::RangeProgress(range.clone()))
+                .await;
+        }
+        self.run_scan(ips.into_iter(), total_ips, ranges, cancel_token)
+            .await;
+    }
+
+    /// Waits for a worker to finish a host, then records it along with up
+    /// to [`FLUSH_BATCH`] others finished by then. Returns how many it
+    /// recorded; 0 once every worker has stopped.
+    async fn await_finished(
+        &self,
+        done: &mut mpsc::UnboundedReceiver<HostDone>,
+        dispatch: &mut Dispatch,
+        probe: &HostProbe,
+    ) -> usize {
+        let mut batch = Vec::new();
+        let received = done.recv_many(&mut batch, FLUSH_BATCH).await;
+        dispatch.in_flight -= received;
+        self.hosts_finished(batch, dispatch, probe).await;
+        received
+    }
+
+    /// Records finished hosts: queues those that asked for a retry and
+    /// counts the others towards progress, sending each changed range once
+    /// and stats at most once per batch.
+    async fn hosts_finished(
+        &self,
+        batch: Vec<HostDone>,
+        dispatch: &mut Dispatch,
+        probe: &HostProbe,
+    ) {
+        let before = dispatch.completed;
+        let mut changed = BTreeMap::new();
+        for finished in batch {
+            let ip = match finished {
+                Ok((ip, true)) => {
+                    dispatch.retries.push_back(ip);
+                    continue;
+                }
+                Ok((ip, false)) => ip,
+                // The probe panicked before counting its host.
+                Err(ip) => {
+                    log::error!("Probe of {} panicked", ip);
+                    probe.phases.host_discovered(0);
+                    probe.phases.report(&self.tx_bridge).await;
+                    ip
+                }
+            };
+            for range in dispatch.ranges.finished(ip) {
+                changed.insert(range.index, range);
+            }
+            dispatch.completed += 1;
+        }
+        for range in changed.into_values() {
+            let _ = self
+                .tx_bridge
+                .send(BridgeMessage::RangeProgress(range))
+                .await;
+        }
+        if dispatch.completed == before {
+            return;
+        }
+
+        let (completed, total_ips, rate) = (dispatch.completed, dispatch.total, &probe.rate);
+        let clock = &mut dispatch.clock;
+        let now = tokio::time::Instant::now();
+        let mut interval = now - clock.last;
+        let mut last_sent = clock.last_sent;
+        if interval < STATS_INTERVAL {
+            if completed < total_ips {
+                return;
+            }
+            // The last host always reports; too soon for a fresh sample, it
+            // gives the average rate of the whole scan.
+            (interval, last_sent) = (now - clock.started, 0);
+        } else {
+            rate.adjust();
+        }
+        let sent = rate.packets_sent();
+        let remaining = total_ips.saturating_sub(completed);
+        let stats = ScanStats {
+            packets_per_second: (sent - last_sent) as f64
+                / interval.as_secs_f64().max(f64::EPSILON),
+            rate_limit: rate.rate_limit(),
+            completed,
+            total: total_ips,
+            eta: Some((now - clock.started).mul_f64(f64::from(remaining) / f64::from(completed))),
+        };
+        (clock.last, clock.last_sent) = (now, sent);
+        let _ = self.tx_bridge.send(BridgeMessage::ScanStats(stats)).await;
+    }
+
+    /// Reports targets a cancelled scan never finished as
+    /// [`ScanStatus::NotScanned`], so a partial scan is not read as
+    /// "everything else was offline".
+    async fn report_not_scanned(&self, ips: impl Iterator<Item = Ipv4Addr>) {
+        let mut count = 0;
+        for ip in ips {
+            let mut result = ScanResult::new(ip);
+            result.status = ScanStatus::NotScanned;
+            let _ = self.tx_bridge.send(BridgeMessage::ScanUpdate(result)).await;
+            count += 1;
+        }
+        if count > 0 {
+            log::info!("{} targets not scanned", count);
+        }
+    }
+
+    /// Holds dispatch while `pause` is set, reporting the state around the wait.
+    ///
+    /// Returns `false` if the scan was cancelled while paused.
+    async fn wait_while_paused(
+        &self,
+        pause: &mut watch::Receiver<bool>,
+        cancel_token: &tokio_util::sync::CancellationToken,
+    ) -> bool {
+        if !*pause.borrow_and_update() {
+            return true;
+        }
+        log::info!("Scan paused");
+        let _ = self
+            .tx_bridge
+            .send(BridgeMessage::ScanState(ScanRunState::Paused))
+            .await;
+        loop {
+            tokio::select! {
+                _ = cancel_token.cancelled() => return false,
+                changed = pause.changed() => {
+                    // A dropped sender can never resume the scan, so carry on.
+                    if changed.is_err() || !*pause.borrow_and_update() {
+                        break;
+                    }
+                }
+            }
+        }
+        log::info!("Scan resumed");
+        let _ = self
+            .tx_bridge
+            .send(BridgeMessage::ScanState(ScanRunState::Running))
+            .await;
+        true
+    }
+
+    /// Holds dispatch after an interface change until the network has been
+    /// stable for [`INTERFACE_SETTLE`] and a route to `probe` exists.
+    ///
+    /// Sends [`BridgeMessage::ScanPaused`] and then [`BridgeMessage::ScanResumed`].
+    /// Returns `false` if the scan was cancelled while paused.
+    async fn wait_for_network(
+        &self,
+        interfaces: &mut InterfaceWatch,
+        probe: Ipv4Addr,
+        cancel_token: &tokio_util::sync::CancellationToken,
+    ) -> bool {
+        log::warn!("Network interface changed; pausing scan");
+        let _ = self
+            .tx_bridge
+            .send(BridgeMessage::ScanPaused(
+                "Network interface changed, waiting for it to settle".to_string(),
+            ))
+            .await;
+
+        loop {
+            tokio::select! {
+                _ = cancel_token.cancelled() => return false,
+                _ = interfaces.changed() => {}
+                _ = tokio::time::sleep(INTERFACE_SETTLE) => {
+                    if self.net_utils.route_available(probe) {
+                        break;
+                    }
+                }
+            }
+        }
+
+        log::info!("Network settled; resuming scan");
+        let _ = self.tx_bridge.send(BridgeMessage::ScanResumed).await;
+        true
+    }
+
+    /// Probes every address yielded by `ips` and reports progress against `total_ips`.
+    async fn run_scan(
+        &self,
+        mut ips: impl Iterator<Item = Ipv4Addr>,
+        total_ips: u32,
+        ranges: RangeTracker,
+        cancel_token: tokio_util::sync::CancellationToken,
+    ) {
+        self.host_states.start(total_ips);
+        // Probes the provider cannot send are skipped, not failed host by host.
+        let capabilities = self.net_utils.capabilities();
+        if !capabilities.icmp {
+            log::warn!("ICMP unavailable; finding hosts without pings");
+        }
+        if !capabilities.arp {
+            log::warn!("ARP unavailable; MAC addresses will not be resolved");
+        }
+        if !capabilities.udp {
+            log::warn!("UDP unavailable; skipping UDP ports, mDNS, SSDP, SNMP and NetBIOS");
+        }
+        let icmp_only = self.config.icmp_only;
+        let udp_scan = self.udp_scan_enabled() && !icmp_only && capabilities.udp;
+        let ping_attempts = if capabilities.icmp {
+            self.ping_attempts.load(Ordering::SeqCst)
+        } else {
+            0
+        };
+
+        // ARP cache pre-pass: one table read instead of a SendARP per cached host.
+        let net_utils_arp = self.net_utils.clone();
+        let arp_cache = if !capabilities.arp {
+            HashMap::new()
+        } else {
+            match tokio::task::spawn_blocking(move || net_utils_arp.read_arp_table()).await {
+                Ok(Ok(table)) => table,
+                Ok(Err(e)) => {
+                    log::warn!("ARP cache pre-pass unavailable, using SendARP only: {}", e);
+                    HashMap::new()
+                }
+                Err(e) => {
+                    log::warn!("ARP cache pre-pass task failed: {}", e);
+                    HashMap::new()
+                }
+            }
+        };
+
+        let config = &self.config;
+        // Discovery pre-passes (run side by side): find devices that ignore
+        // ICMP but announce themselves over mDNS or SSDP, and the gateways,
+        // DNS and DHCP servers of the local networks.
+        let net_utils_mdns = self.net_utils.clone();
+        let mdns_discovery = config.mdns_discovery && !icmp_only && capabilities.udp;
+        let net_utils_upnp = self.net_utils.clone();
+        let ssdp_discovery = config.ssdp_discovery && !icmp_only && capabilities.udp;
+        let net_utils_roles = self.net_utils.clone();
+        let dhcp_discovery = config.dhcp_discovery && !icmp_only && capabilities.udp;
+        let (mdns_hosts, upnp_hosts, roles) = tokio::join!(
+            tokio::task::spawn_blocking(move || {
+                if mdns_discovery {
+                    net_utils_mdns.discover_mdns()
+                } else {
+                    HashMap::new()
+                }
+            }),
+            tokio::task::spawn_blocking(move || {
+                if ssdp_discovery {
+                    net_utils_upnp.discover_upnp()
+                } else {
+                    HashMap::new()
+                }
+            }),
+            tokio::task::spawn_blocking(move || net_utils_roles.discover_roles(dhcp_discovery)),
+        );
+        let mdns_hosts: HashMap<Ipv4Addr, MdnsHost> = mdns_hosts.unwrap_or_default();
+        let upnp_hosts: HashMap<Ipv4Addr, UpnpInfo> = upnp_hosts.unwrap_or_default();
+        let roles: HashMap<Ipv4Addr, Vec<NetworkRole>> = roles.unwrap_or_default();
+        if mdns_discovery || ssdp_discovery {
+            log::info!(
+                "Discovery found {} mDNS and {} UPnP hosts",
+                mdns_hosts.len(),
+                upnp_hosts.len()
+            );
+        }
+        let order = ProbeOrder::new(config);
+        let reverse_dns = config
+            .resolve_hostnames
+            .then(|| ReverseDns::reverse(self.net_utils.clone(), config.dns.clone()));
+        let forward_dns = (config.resolve_hostnames && config.verify_dns)
+            .then(|| ForwardDns::forward(self.net_utils.clone(), config.dns.clone()));
+        // A random order needs every address up front; sequential scans stay lazy.
+        let mut shuffled: Vec<Ipv4Addr> = Vec::new();
+        if order.seed.is_some() {
+            shuffled = ips.by_ref().collect();
+            order.arrange(&mut shuffled, 0);
+        }
+        let mut ips = shuffled.into_iter().chain(ips);
+        let ports: Vec<u16> = if icmp_only {
+            Vec::new()
+        } else {
+            config.ports.clone()
+        };
+        let snmp_community: Option<Arc<str>> = config
+            .snmp_community
+            .as_deref()
+            .filter(|_| !icmp_only && capabilities.udp)
+            .map(Arc::from);
+
+        let mut interfaces = self.net_utils.watch_interfaces().unwrap_or_else(|e| {
+            log::warn!("Interface change detection unavailable: {}", e);
+            InterfaceWatch::inert()
+        });
+        let power = self.net_utils.watch_power().unwrap_or_else(|e| {
+            log::warn!("Sleep detection unavailable: {}", e);
+            PowerWatch::inert()
+        });
+        // Hosts whose probe failed while an interface changed, or across a
+        // system sleep, get one more try.
+        let mut retried: HashSet<Ipv4Addr> = HashSet::new();
+        let mut dispatch = Dispatch::new(total_ips, ranges);
+        let mut last_ip = None;
+        let mut pause = self.pause.clone();
+        let rate_control = Arc::new(
+            RateController::new(config.max_rate, config.adaptive_rate)
+                .with_budget(config.probe_budget)
+                .with_bandwidth(config.max_bandwidth_kbps),
+        );
+        let ports_per_host =
+            (ports.len() + if udp_scan { COMMON_UDP_PORTS.len() } else { 0 }) as u32;
+        let shared = Arc::new(HostProbe {
+            net_utils: self.net_utils.clone(),
+            tx: self.tx_bridge.clone(),
+            config: config.clone(),
+            capabilities,
+            ping_attempts,
+            udp_scan,
+            ports_per_host,
+            order,
+            arp_cache,
+            mdns_hosts,
+            upnp_hosts,
+            roles,
+            reverse_dns,
+            forward_dns,
+            ports,
+            snmp_community,
+            port_cache: self.port_cache.clone(),
+            connections: Semaphore::new(MAX_SCAN_CONNECTIONS),
+            power: power.clone(),
+            rate: rate_control.clone(),
+            intercept: Mutex::default(),
+            probes: self.probes.clone(),
+            tally: HostTally::default(),
+            phases: PhaseTracker::new(total_ips, ports_per_host > 0),
+            host_states: self.host_states.clone(),
+        });
+
+        // A fixed pool of workers takes hosts off one queue, so memory stays
+        // flat however large the range. The queue never holds more hosts
+        // than there are idle workers: a pause, cancel or spent budget still
+        // holds back every host not yet started.
+        let workers = config.concurrency.clamp(1, (total_ips as usize).max(1));
+        let (jobs, queue) = mpsc::channel(workers);
+        let queue = Arc::new(tokio::sync::Mutex::new(queue));
+        let (done_tx, mut done) = mpsc::unbounded_channel();
+        let mut pool = tokio::task::JoinSet::new();
+        for _ in 0..workers {
+            pool.spawn(shared.clone().work(queue.clone(), done_tx.clone()));
+        }
+        drop((queue, done_tx));
+
+        loop {
+            // Check for cancellation before dispatching each IP
+            if cancel_token.is_cancelled() {
+                log::info!("Scan cancelled by user.");
+                break;
+            }
+            if rate_control.budget_exhausted() {
+                log::warn!("Probe budget used up; no further hosts are scanned.");
+                break;
+            }
+
+            if interfaces.take_change() {
+                let probe = last_ip.unwrap_or(Ipv4Addr::UNSPECIFIED);
+                if !self
+                    .wait_for_network(&mut interfaces, probe, &cancel_token)
+                    .await
+                {
+                    continue;
+                }
+            }
+
+            let next = match dispatch.retries.pop_front() {
+                Some(ip) => {
+                    retried.insert(ip);
+                    Some(ip)
+                }
+                None => ips.next(),
+            };
+            let Some(ip) = next else {
+                // Nothing left to dispatch; in-flight hosts may still ask for a retry.
+                if dispatch.in_flight == 0
+                    || self.await_finished(&mut done, &mut dispatch, &shared).await == 0
+                {
+                    break;
+                }
+                continue;
+            };
+            last_ip = Some(ip);
+
+            if !self.wait_while_paused(&mut pause, &cancel_token).await {
+                dispatch.retries.push_front(ip);
+                break;
+            }
+            while dispatch.in_flight == workers {
+                if self.await_finished(&mut done, &mut dispatch, &shared).await == 0 {
+                    break;
+                }
+            }
+
+            self.host_states.set(ip, HostPhase::Discovery);
+            let job = HostJob {
+                ip,
+                can_retry: !retried.contains(&ip),
+                interfaces: interfaces.clone(),
+                sleeps: power.state().sleeps,
+            };
+            if jobs.send(job).await.is_err() {
+                let _ = self
+                    .tx_bridge
+                    .send(BridgeMessage::Error(GError::Internal(
+                        "Scan workers stopped".to_string(),
+                    )))
+                    .await;
+                break;
+            }
+            dispatch.in_flight += 1;
+
+            if !done.is_empty() {
+                self.await_finished(&mut done, &mut dispatch, &shared).await;
+            }
+        }
+
+        // Cancelled or out of budget: let in-flight hosts finish, without retries.
+        while dispatch.in_flight > 0 {
+            if self.await_finished(&mut done, &mut dispatch, &shared).await == 0 {
+                break;
+            }
+        }
+        // Closing the queue stops the workers.
+        drop(jobs);
+        while pool.join_next().await.is_some() {}
+
+        let summary = shared
+            .tally
+            .summary(dispatch.clock.started.elapsed(), &rate_control);
+        log::info!("Scan summary: {}", summary);
+        let _ = self
+            .tx_bridge
+            .send(BridgeMessage::ScanSummary(summary))
+            .await;
+
+        if cancel_token.is_cancelled() || summary.budget_exhausted {
+            log::info!("Scan completed (Cancelled).");
+            let mut unfinished: Vec<Ipv4Addr> = dispatch.retries.into();
+            unfinished.sort_unstable();
+            self.report_not_scanned(unfinished.into_iter().chain(ips))
+                .await;
+            let _ = self.tx_bridge.send(BridgeMessage::ScanCancelled).await;
+        } else {
+            log::info!("Scan completed (Finished).");
+            let _ = self.tx_bridge.send(BridgeMessage::ScanComplete).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::{Capabilities, MockNet, PowerState};
+    use crate::types::{PacketCount, ScanProfile, SnmpInfo, TlsInfo, TlsVersion};
+    use async_trait::async_trait;
+    use tokio::sync::mpsc::{Receiver, channel};
+
+    /// The next host result, skipping progress reports sent while it was probed.
+    async fn next_update(rx: &mut Receiver<BridgeMessage>) -> Option<ScanResult> {
+        while let Some(msg) = rx.recv().await {
+            if let BridgeMessage::ScanUpdate(res) = msg {
+                return Some(res);
+            }
+        }
+        None
+    }
+
+    #[tokio::test]
+    async fn test_scanner_with_ports_and_progress() {
+        let (tx, mut rx) = channel(100);
+        let scanner = Scanner::new(Arc::new(MockNet), tx);
+
+        let start = Ipv4Addr::new(192, 168, 1, 1);
+        let end = Ipv4Addr::new(192, 168, 1, 1); // Single IP
+        let token = tokio_util::sync::CancellationToken::new();
+
+        scanner.scan_range(start, end, token).await;
+
+        let mut found_online = false;
+        let mut found_progress = false;
+        let mut complete = false;
+
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::ScanUpdate(res) if res.ip == Ipv4Addr::new(192, 168, 1, 1) => {
+                    assert_eq!(res.status, ScanStatus::Online);
+                    assert_eq!(res.hostname_source, Some(NameSource::Dns));
+                    let http = res.open_ports.iter().find(|p| p.port == 80).unwrap();
+                    assert_eq!(http.banner.as_deref(), Some("mock-httpd/1.0"));
+                    let web = res
+                        .web_info
+                        .as_ref()
+                        .expect("port 80 open without web info");
+                    assert_eq!(web.title.as_deref(), Some("Mock Router"));
+                    // MockNet replies with TTL 128.
+                    let guess = res.os_guess.expect("online host without an OS guess");
+                    assert_eq!(guess.family, crate::types::OsFamily::Windows);
+                    assert_eq!(guess.evidence, ["TTL 128"]);
+                    found_online = true;
+                }
+                BridgeMessage::Progress(p) => {
+                    assert!(p <= 100);
+                    found_progress = true;
+                }
+                BridgeMessage::ScanComplete => {
+                    complete = true;
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        assert!(found_online);
+        assert!(found_progress);
+        assert!(complete);
+    }
+
+    #[tokio::test]
+    async fn test_summary_counts_hosts_and_results_carry_timestamps() {
+        let (tx, mut rx) = channel(100);
+        let scanner = Scanner::new(Arc::new(MockNet), tx);
+        let before = SystemTime::now();
+        scanner
+            .scan_range(
+                Ipv4Addr::new(192, 168, 1, 1),
+                Ipv4Addr::new(192, 168, 1, 4),
+                tokio_util::sync::CancellationToken::new(),
+            )
+            .await;
+
+        let mut messages = Vec::new();
+        while let Ok(msg) = rx.try_recv() {
+            messages.push(msg);
+        }
+        for msg in &messages {
+            if let BridgeMessage::ScanUpdate(res) = msg {
+                assert!(res.scan_duration.is_some());
+                let online = res.status == ScanStatus::Online;
+                assert_eq!(res.first_seen.is_some(), online);
+                assert!(res.last_seen.is_none_or(|seen| seen >= before));
+            }
+        }
+        let [
+            ..,
+            BridgeMessage::ScanSummary(summary),
+            BridgeMessage::ScanComplete,
+        ] = messages.as_slice()
+        else {
+            panic!("expected a summary right before completion");
+        };
+        assert_eq!(
+            (summary.hosts_up, summary.hosts_down, summary.errors),
+            (2, 1, 1)
+        );
+        assert!(summary.probes_sent >= 4);
+    }
+
+    #[tokio::test]
+    async fn test_phase_progress_covers_discovery_and_ports() {
+        let (tx, mut rx) = channel(1000);
+        let scanner = Scanner::new(Arc::new(MockNet), tx);
+        scanner
+            .scan_range(
+                Ipv4Addr::new(192, 168, 1, 1),
+                Ipv4Addr::new(192, 168, 1, 4),
+                tokio_util::sync::CancellationToken::new(),
+            )
+            .await;
+
+        let mut phases = Vec::new();
+        let mut overall = Vec::new();
+        while let Ok(msg) = rx.try_recv() {
+            match msg {
+                BridgeMessage::Phase(phase) => phases.push(phase),
+                BridgeMessage::Progress(p) => overall.push(p),
+                _ => {}
+            }
+        }
+        assert!(overall.windows(2).all(|w| w[0] < w[1]), "{:?}", overall);
+        assert_eq!(overall.last(), Some(&100));
+
+        let last = phases.last().expect("no phase progress");
+        assert_eq!((last.hosts_discovered, last.hosts_total), (4, 4));
+        // Both online hosts owe every configured port.
+        let ports = ScanConfig::default().ports.len() as u32;
+        assert_eq!(last.ports_total, Some(2 * ports));
+        assert_eq!(last.ports_probed, 2 * ports);
+        assert!(phases.iter().any(|p| !p.discovery_complete()));
+
+        // Without ports, only discovery is reported.
+        let (tx, mut rx) = channel(100);
+        let mut config = ScanConfig::default();
+        config.ports.clear();
+        let scanner = Scanner::new(Arc::new(MockNet), tx).with_config(config);
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        scanner
+            .scan_range(ip, ip, tokio_util::sync::CancellationToken::new())
+            .await;
+        let mut last = None;
+        while let Ok(msg) = rx.try_recv() {
+            if let BridgeMessage::Phase(phase) = msg {
+                last = Some(phase);
+            }
+        }
+        assert_eq!(
+            last.map(|p| p.to_string()).as_deref(),
+            Some("Discovery 100%")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_paused_scan_waits_for_resume() {
+        let (tx, mut rx) = channel(100);
+        let (pause_tx, pause_rx) = watch::channel(true);
+        let scanner = Scanner::new(Arc::new(MockNet), tx).with_pause(pause_rx);
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        let token = tokio_util::sync::CancellationToken::new();
+        let scan = tokio::spawn(async move { scanner.scan_range(ip, ip, token).await });
+
+        assert!(matches!(
+            rx.recv().await,
+            Some(BridgeMessage::ScanState(ScanRunState::Paused))
+        ));
+        let idle = tokio::time::timeout(std::time::Duration::from_millis(50), rx.recv()).await;
+        assert!(idle.is_err(), "no host is probed while paused");
+
+        pause_tx.send_replace(false);
+        scan.await.unwrap();
+        let mut messages = Vec::new();
+        while let Ok(msg) = rx.try_recv() {
+            messages.push(msg);
+        }
+        assert!(matches!(
+            messages.first(),
+            Some(BridgeMessage::ScanState(ScanRunState::Running))
+        ));
+        assert!(
+            messages
+                .iter()
+                .any(|m| matches!(m, BridgeMessage::ScanUpdate(r) if r.ip == ip))
+        );
+        assert!(matches!(messages.last(), Some(BridgeMessage::ScanComplete)));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_scan_marks_unscanned_targets() {
+        let (tx, mut rx) = channel(100);
+        let (_pause_tx, pause_rx) = watch::channel(true);
+        let scanner = Scanner::new(Arc::new(MockNet), tx).with_pause(pause_rx);
+        let token = tokio_util::sync::CancellationToken::new();
+        let cancel = token.clone();
+        let scan = tokio::spawn(async move {
+            let (start, end) = (Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 3));
+            scanner.scan_range(start, end, token).await
+        });
+
+        assert!(matches!(
+            rx.recv().await,
+            Some(BridgeMessage::ScanState(ScanRunState::Paused))
+        ));
+        cancel.cancel();
+        scan.await.unwrap();
+        let mut not_scanned = Vec::new();
+        let mut last = None;
+        while let Ok(msg) = rx.try_recv() {
+            if let BridgeMessage::ScanUpdate(res) = &msg {
+                assert_eq!(res.status, ScanStatus::NotScanned);
+                not_scanned.push(res.ip.octets()[3]);
+            }
+            last = Some(msg);
+        }
+        // The target held back by the pause is reported too.
+        assert_eq!(not_scanned, [1, 2, 3]);
+        assert!(matches!(last, Some(BridgeMessage::ScanCancelled)));
+    }
+
+    #[tokio::test]
+    async fn test_scanner_latency_stats() {
+        let (tx, mut rx) = channel(100);
+        let scanner = Scanner::new(Arc::new(MockNet), tx);
+        scanner.set_ping_attempts(3);
+
+        let token = tokio_util::sync::CancellationToken::new();
+        let targets = vec![Ipv4Addr::new(192, 168, 1, 1), Ipv4Addr::new(192, 168, 1, 9)];
+        scanner.scan_targets(targets, token).await;
+
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::ScanUpdate(res) if res.ip == Ipv4Addr::new(192, 168, 1, 1) => {
+                    let latency = res.latency.expect("online host has latency");
+                    assert_eq!((latency.sent, latency.received), (3, 3));
+                    assert_eq!(res.latency_ms(), Some(2.0));
+                }
+                BridgeMessage::ScanUpdate(res) => assert!(res.latency.is_none()),
+                BridgeMessage::ScanComplete => break,
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_subnet_edge() {
+        let start = Ipv4Addr::new(10, 0, 0, 0);
+        let end = Ipv4Addr::new(10, 0, 1, 255);
+        assert!(is_subnet_edge(Ipv4Addr::new(10, 0, 0, 0), start, end));
+        assert!(is_subnet_edge(Ipv4Addr::new(10, 0, 1, 255), start, end));
+        assert!(!is_subnet_edge(Ipv4Addr::new(10, 0, 0, 1), start, end));
+
+        // Partially covered /24s keep their edges.
+        let start = Ipv4Addr::new(10, 0, 0, 200);
+        let end = Ipv4Addr::new(10, 0, 1, 10);
+        assert!(!is_subnet_edge(Ipv4Addr::new(10, 0, 0, 255), start, end));
+        assert!(!is_subnet_edge(Ipv4Addr::new(10, 0, 1, 0), start, end));
+    }
+
+    #[tokio::test]
+    async fn test_full_subnet_skips_edges_unless_included() {
+        let start = Ipv4Addr::new(192, 168, 1, 0);
+        let end = Ipv4Addr::new(192, 168, 1, 255);
+        for (include, expected) in [(false, 254), (true, 256)] {
+            // Room for every update and progress message; nothing is read until the scan ends.
+            let (tx, mut rx) = channel(1024);
+            let scanner = Scanner::new(Arc::new(MockNet), tx);
+            scanner.set_include_subnet_edges(include);
+            let token = tokio_util::sync::CancellationToken::new();
+            scanner.scan_range(start, end, token).await;
+
+            let mut seen = 0;
+            while let Some(msg) = rx.recv().await {
+                match msg {
+                    BridgeMessage::ScanUpdate(res) => {
+                        assert!(include || !is_subnet_edge(res.ip, start, end));
+                        seen += 1;
+                    }
+                    BridgeMessage::ScanComplete => break,
+                    _ => {}
+                }
+            }
+            assert_eq!(seen, expected);
+        }
+    }
+
+    #[test]
+    fn test_adaptive_port_timeout() {
+        let lan = LatencyStats::from_samples(&[0, 1], 2).unwrap();
+        assert_eq!(adaptive_port_timeout(Some(&lan)), MIN_PORT_TIMEOUT);
+
+        let vpn = LatencyStats::from_samples(&[120, 150], 2).unwrap();
+        assert_eq!(
+            adaptive_port_timeout(Some(&vpn)),
+            Duration::from_millis(600)
+        );
+
+        let satellite = LatencyStats::from_samples(&[900], 1).unwrap();
+        assert_eq!(adaptive_port_timeout(Some(&satellite)), MAX_PORT_TIMEOUT);
+
+        assert_eq!(adaptive_port_timeout(None), DEFAULT_PORT_TIMEOUT);
+    }
+
+    #[tokio::test]
+    async fn test_scanner_uses_port_cache() {
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        let cache = Arc::new(PortCache::new(std::time::Duration::from_secs(60)));
+        let mut cached = ScanResult::new(ip);
+        cached.mac = Some("00:11:22:33:44:55".to_string());
+        cached.open_ports = vec![PortInfo::new(443)];
+        cache.insert(&cached, false);
+
+        let (tx, mut rx) = channel(100);
+        let scanner = Scanner::new(Arc::new(MockNet), tx).with_port_cache(cache);
+        let token = tokio_util::sync::CancellationToken::new();
+        scanner.scan_range(ip, ip, token).await;
+
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                // MockNet would report port 80; the cached result wins.
+                BridgeMessage::ScanUpdate(res) => {
+                    assert_eq!(res.open_ports, vec![PortInfo::new(443)])
+                }
+                BridgeMessage::ScanComplete => break,
+                _ => {}
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scanner_applies_config() {
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        let (tx, mut rx) = channel(100);
+        let scanner = Scanner::new(Arc::new(MockNet), tx).with_config(ScanConfig {
+            concurrency: 1,
+            port_timeout: Some(Duration::from_millis(50)),
+            ports: vec![22, 8080],
+            resolve_hostnames: false,
+            resolve_vendors: false,
+            ..ScanConfig::default()
+        });
+        let token = tokio_util::sync::CancellationToken::new();
+        scanner.scan_range(ip, ip, token).await;
+
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                // MockNet's port 80 is not in the list.
+                BridgeMessage::ScanUpdate(res) => {
+                    assert!(res.open_ports.is_empty());
+                    assert_eq!((res.hostname, res.vendor), (None, None));
+                    assert!(res.mac.is_some());
+                }
+                BridgeMessage::ScanComplete => break,
+                _ => {}
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_random_order_is_a_seeded_permutation() {
+        async fn scan_order() -> Vec<Ipv4Addr> {
+            let (tx, mut rx) = channel(100);
+            let scanner = Scanner::new(Arc::new(MockNet), tx).with_config(ScanConfig {
+                concurrency: 1,
+                resolve_hostnames: false,
+                resolve_vendors: false,
+                order: ScanOrder::Random,
+                order_seed: Some(7),
+                probe_jitter: Some(Duration::from_millis(1)),
+                ..ScanConfig::default()
+            });
+            let token = tokio_util::sync::CancellationToken::new();
+            let (start, end) = (Ipv4Addr::new(10, 0, 1, 10), Ipv4Addr::new(10, 0, 1, 29));
+            scanner.scan_range(start, end, token).await;
+
+            let mut seen = Vec::new();
+            while let Some(msg) = rx.recv().await {
+                match msg {
+                    BridgeMessage::ScanUpdate(res) => seen.push(res.ip),
+                    BridgeMessage::ScanComplete => break,
+                    _ => {}
+                }
+            }
+            seen
+        }
+
+        let first = scan_order().await;
+        assert_eq!(first, scan_order().await);
+        let mut sorted = first.clone();
+        sorted.sort();
+        assert_ne!(first, sorted);
+        let expected: Vec<Ipv4Addr> = (10..=29).map(|i| Ipv4Addr::new(10, 0, 1, i)).collect();
+        assert_eq!(sorted, expected);
+    }
+
+    #[tokio::test]
+    async fn test_scanner_targets_list() {
+        let (tx, mut rx) = channel(100);
+        let scanner = Scanner::new(Arc::new(MockNet), tx);
+
+        let targets = vec![Ipv4Addr::new(192, 168, 1, 1), Ipv4Addr::new(192, 168, 1, 9)];
+        let token = tokio_util::sync::CancellationToken::new();
+        scanner.scan_targets(targets.clone(), token).await;
+
+        let mut seen = Vec::new();
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::ScanUpdate(res) => seen.push(res.ip),
+                BridgeMessage::ScanComplete => break,
+                _ => {}
+            }
+        }
+        seen.sort();
+        assert_eq!(seen, targets);
+    }
+
+    #[tokio::test]
+    async fn test_scanner_multi_merges_overlaps() {
+        let (tx, mut rx) = channel(100);
+        let scanner = Scanner::new(Arc::new(MockNet), tx);
+
+        let targets = ScanTargets::parse("10.0.1.1-5,10.0.1.3-8,10.0.5.0/30").unwrap();
+        let token = tokio_util::sync::CancellationToken::new();
+        scanner.scan_multi(targets, token).await;
+
+        let mut seen = Vec::new();
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::ScanUpdate(res) => seen.push(res.ip),
+                BridgeMessage::ScanComplete => break,
+                _ => {}
+            }
+        }
+        seen.sort();
+        let mut expected: Vec<Ipv4Addr> = (1..=8).map(|i| Ipv4Addr::new(10, 0, 1, i)).collect();
+        expected.extend((0..=3).map(|i| Ipv4Addr::new(10, 0, 5, i)));
+        assert_eq!(seen, expected);
+    }
+
+    #[tokio::test]
+    async fn test_scanner_multi_reports_range_progress() {
+        let (tx, mut rx) = channel(100);
+        let scanner = Scanner::new(Arc::new(MockNet), tx);
+
+        let targets = ScanTargets::parse("10.0.1.1-4,10.0.1.3-5").unwrap();
+        let token = tokio_util::sync::CancellationToken::new();
+        scanner.scan_multi(targets, token).await;
+
+        let mut reports = Vec::new();
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::RangeProgress(range) => reports.push(range),
+                BridgeMessage::ScanComplete => break,
+                _ => {}
+            }
+        }
+        // Both ranges are announced before any host finishes.
+        assert_eq!(reports[0].index, 0);
+        assert_eq!((reports[1].index, reports[1].completed), (1, 0));
+        assert_eq!(reports[1].to_string(), "10.0.1.3-10.0.1.5: 0/3 (0%)");
+        // Shared hosts count towards both ranges, and both finish.
+        let last = |index| reports.iter().rev().find(|r| r.index == index).unwrap();
+        assert_eq!((last(0).completed, last(0).total), (4, 4));
+        assert_eq!((last(1).completed, last(1).total), (3, 3));
+        assert_eq!(last(1).to_string(), "10.0.1.3-10.0.1.5: done");
+
+        // A single range reports overall progress only.
+        let (tx, mut rx) = channel(100);
+        let scanner = Scanner::new(Arc::new(MockNet), tx);
+        let token = tokio_util::sync::CancellationToken::new();
+        scanner
+            .scan_multi(ScanTargets::parse("10.0.1.1-4").unwrap(), token)
+            .await;
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::RangeProgress(_) => panic!("range progress for a single range"),
+                BridgeMessage::ScanComplete => break,
+                _ => {}
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scanner_arp_cache_prepass() {
+        let (tx, mut rx) = channel(100);
+        let scanner = Scanner::new(Arc::new(MockNet), tx);
+
+        let ip = Ipv4Addr::new(192, 168, 1, 3);
+        let token = tokio_util::sync::CancellationToken::new();
+        scanner.scan_range(ip, ip, token).await;
+
+        let mut found = false;
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::ScanUpdate(res) => {
+                    assert_eq!(res.status, ScanStatus::Online);
+                    assert_eq!(res.mac.as_deref(), Some("AA:BB:CC:DD:EE:FF"));
+                    found = true;
+                }
+                BridgeMessage::ScanComplete => break,
+                _ => {}
+            }
+        }
+        assert!(found);
+    }
+
+    #[tokio::test]
+    async fn test_scanner_udp_ports() {
+        let (tx, mut rx) = channel(100);
+        let scanner = Scanner::new(Arc::new(MockNet), tx);
+        scanner.set_udp_scan(true);
+
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        let token = tokio_util::sync::CancellationToken::new();
+        scanner.scan_range(ip, ip, token).await;
+
+        let mut found = false;
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::ScanUpdate(res) => {
+                    assert_eq!(res.open_udp_ports, vec![53]);
+                    assert_eq!(res.filtered_udp_ports, vec![161]);
+                    found = true;
+                }
+                BridgeMessage::ScanComplete => break,
+                _ => {}
+            }
+        }
+        assert!(found);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_safe_mode_sends_only_echoes() {
+        let (tx, mut rx) = channel(100);
+        let config = ScanConfig {
+            snmp_community: Some("public".to_string()),
+            ..ScanProfile::Safe.config()
+        };
+        let scanner = Scanner::new(Arc::new(MockNet), tx).with_config(config);
+        scanner.set_udp_scan(true);
+
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        let token = tokio_util::sync::CancellationToken::new();
+        scanner.scan_range(ip, ip, token).await;
+
+        let mut found = false;
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::ScanUpdate(res) => {
+                    assert_eq!(res.status, ScanStatus::Online);
+                    assert!(res.open_ports.is_empty());
+                    assert!(res.open_udp_ports.is_empty());
+                    assert_eq!(res.snmp, None);
+                    assert_eq!(res.hostname, None);
+                    found = true;
+                }
+                BridgeMessage::ScanComplete => break,
+                _ => {}
+            }
+        }
+        assert!(found);
+    }
+
+    /// Answers every echo and names hosts only through batched lookups,
+    /// recording each batch and the DNS settings it came with.
+    #[derive(Default)]
+    struct BatchDnsNet {
+        batches: Mutex<Vec<(Vec<Ipv4Addr>, DnsConfig)>>,
+    }
+
+    #[async_trait]
+    impl NetworkProvider for BatchDnsNet {
+        fn echo(&self, _ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+            Ok(Some(1))
+        }
+        fn resolve_mac(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            Ok(None)
+        }
+        fn resolve_hostname(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            panic!("reverse lookups must go through resolve_hostnames")
+        }
+        async fn resolve_hostnames(
+            &self,
+            ips: &[Ipv4Addr],
+            dns: &DnsConfig,
+        ) -> HashMap<Ipv4Addr, String> {
+            self.batches
+                .lock()
+                .unwrap()
+                .push((ips.to_vec(), dns.clone()));
+            ips.iter()
+                .map(|ip| (*ip, format!("host-{}", ip.octets()[3])))
+                .collect()
+        }
+        /// `host-1` leads back, `host-2` elsewhere, `host-3` nowhere;
+        /// other lookups fail.
+        async fn resolve_addresses(
+            &self,
+            names: &[String],
+            _dns: &DnsConfig,
+        ) -> HashMap<String, Vec<Ipv4Addr>> {
+            names
+                .iter()
+                .filter_map(|name| {
+                    let addresses = match name.as_str() {
+                        "host-1" => vec![Ipv4Addr::new(10, 0, 0, 1)],
+                        "host-2" => vec![Ipv4Addr::new(10, 9, 9, 9)],
+                        "host-3" => Vec::new(),
+                        _ => return None,
+                    };
+                    Some((name.clone(), addresses))
+                })
+                .collect()
+        }
+        fn resolve_vendor(&self, _mac: &str) -> Option<String> {
+            None
+        }
+        async fn scan_port(&self, _ip: Ipv4Addr, _port: u16) -> bool {
+            false
+        }
+        async fn scan_udp_port(&self, _ip: Ipv4Addr, _port: u16) -> UdpPortState {
+            UdpPortState::Closed
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reverse_lookups_are_batched_with_the_dns_config() {
+        let (tx, mut rx) = channel(100);
+        let dns = DnsConfig {
+            servers: vec!["10.0.0.53".parse().unwrap()],
+            timeout: Duration::from_millis(300),
+        };
+        let config = ScanConfig {
+            ports: Vec::new(),
+            dns: dns.clone(),
+            ..ScanConfig::default()
+        };
+        let net = Arc::new(BatchDnsNet::default());
+        let scanner = Scanner::new(net.clone(), tx).with_config(config);
+        let token = tokio_util::sync::CancellationToken::new();
+        scanner
+            .scan_range(
+                Ipv4Addr::new(10, 0, 0, 1),
+                Ipv4Addr::new(10, 0, 0, 20),
+                token,
+            )
+            .await;
+
+        let mut named = 0;
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::ScanUpdate(res) => {
+                    let expected = format!("host-{}", res.ip.octets()[3]);
+                    assert_eq!(res.hostname.as_deref(), Some(expected.as_str()));
+                    assert_eq!(res.hostname_source, Some(NameSource::Dns));
+                    assert!(res.issues.is_empty());
+                    named += 1;
+                }
+                BridgeMessage::ScanComplete => break,
+                _ => {}
+            }
+        }
+        assert_eq!(named, 20);
+        let batches = net.batches.lock().unwrap();
+        assert!(batches.iter().all(|(_, config)| *config == dns));
+        let looked_up: usize = batches.iter().map(|(ips, _)| ips.len()).sum();
+        assert_eq!(looked_up, 20);
+    }
+
+    #[tokio::test]
+    async fn test_verify_dns_flags_names_leading_elsewhere() {
+        let (tx, mut rx) = channel(100);
+        let config = ScanConfig {
+            ports: Vec::new(),
+            verify_dns: true,
+            ..ScanConfig::default()
+        };
+        let scanner = Scanner::new(Arc::new(BatchDnsNet::default()), tx).with_config(config);
+        let token = tokio_util::sync::CancellationToken::new();
+        scanner
+            .scan_range(
+                Ipv4Addr::new(10, 0, 0, 1),
+                Ipv4Addr::new(10, 0, 0, 4),
+                token,
+            )
+            .await;
+
+        let mut issues = HashMap::new();
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::ScanUpdate(res) => {
+                    issues.insert(res.ip.octets()[3], res.issues);
+                }
+                BridgeMessage::ScanComplete => break,
+                _ => {}
+            }
+        }
+        assert!(issues[&1].is_empty());
+        assert_eq!(
+            issues[&2],
+            [SecurityIssue::DnsMismatch {
+                name: "host-2".to_string(),
+                addresses: vec![Ipv4Addr::new(10, 9, 9, 9)],
+            }]
+        );
+        assert_eq!(
+            issues[&3][0].to_string(),
+            "reverse DNS name host-3 does not resolve"
+        );
+        // A failed lookup neither confirms nor contradicts the name.
+        assert!(issues[&4].is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_long_port_lists_stream_open_ports() {
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        for (ports, streamed) in [(vec![22, 80], false), ((1..=1100).collect(), true)] {
+            let (tx, mut rx) = channel(100);
+            let config = ScanConfig {
+                ports,
+                ..ScanConfig::default()
+            };
+            let scanner = Scanner::new(Arc::new(MockNet), tx).with_config(config);
+            let token = tokio_util::sync::CancellationToken::new();
+            tokio::spawn(async move { scanner.scan_range(ip, ip, token).await });
+
+            let mut found = Vec::new();
+            while let Some(msg) = rx.recv().await {
+                match msg {
+                    BridgeMessage::PortFound { ip: host, port } => found.push((host, port)),
+                    BridgeMessage::ScanUpdate(res) => {
+                        // Found ports precede the result listing them.
+                        assert_eq!(res.open_ports.len(), 1);
+                        assert_eq!(found.len(), usize::from(streamed));
+                    }
+                    BridgeMessage::ScanComplete => break,
+                    _ => {}
+                }
+            }
+            if streamed {
+                assert_eq!(found, [(ip, 80)]);
+            }
+        }
+    }
+
+    /// Loses the adapter on the first echo, as if the laptop switched Wi-Fi mid-scan.
+    struct FlakyNet {
+        changes: tokio::sync::watch::Sender<u64>,
+        dropped: AtomicBool,
+    }
+
+    #[async_trait]
+    impl NetworkProvider for FlakyNet {
+        fn echo(&self, ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+            if !self.dropped.swap(true, Ordering::SeqCst) {
+                self.changes.send_modify(|c| *c += 1);
+                return Err(GError::from_win32(11008, "IcmpSendEcho"));
+            }
+            MockNet.echo(ip)
+        }
+        fn resolve_mac(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            MockNet.resolve_mac(ip)
+        }
+        fn resolve_hostname(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            MockNet.resolve_hostname(ip)
+        }
+        fn resolve_vendor(&self, mac: &str) -> Option<String> {
+            MockNet.resolve_vendor(mac)
+        }
+        async fn scan_port(&self, ip: Ipv4Addr, port: u16) -> bool {
+            MockNet.scan_port(ip, port).await
+        }
+        async fn scan_udp_port(&self, ip: Ipv4Addr, port: u16) -> UdpPortState {
+            MockNet.scan_udp_port(ip, port).await
+        }
+        fn watch_interfaces(&self) -> Result<InterfaceWatch, GError> {
+            Ok(InterfaceWatch::new(self.changes.subscribe()))
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_interface_change_pauses_and_retries() {
+        let (tx, mut rx) = channel(100);
+        let net = FlakyNet {
+            changes: tokio::sync::watch::channel(0).0,
+            dropped: AtomicBool::new(false),
+        };
+        let scanner = Scanner::new(Arc::new(net), tx);
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        let token = tokio_util::sync::CancellationToken::new();
+        scanner.scan_range(ip, ip, token).await;
+
+        let mut events = Vec::new();
+        let mut last_progress = 0;
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::ScanPaused(_) => events.push("paused"),
+                BridgeMessage::ScanResumed => events.push("resumed"),
+                BridgeMessage::ScanUpdate(res) => {
+                    // The failed probe is retried instead of reported as an error.
+                    assert_eq!(res.status, ScanStatus::Online);
+                    events.push("update");
+                }
+                BridgeMessage::Progress(p) => last_progress = p,
+                BridgeMessage::Phase(_) => {}
+                // The pause outlasts the stats interval.
+                BridgeMessage::ScanStats(stats) => assert_eq!(stats.completed, 1),
+                BridgeMessage::ScanSummary(summary) => {
+                    assert_eq!((summary.hosts_up, summary.errors), (1, 0));
+                    events.push("summary");
+                }
+                BridgeMessage::ScanComplete => break,
+                other => panic!("unexpected message: {:?}", other),
+            }
+        }
+        assert_eq!(events, vec!["paused", "resumed", "update", "summary"]);
+        assert_eq!(last_progress, 100);
+    }
+
+    /// Sleeps through the first probe, whose echo and ARP request then time
+    /// out on wake.
+    struct SleepingNet {
+        power: tokio::sync::watch::Sender<PowerState>,
+        echoes: AtomicU32,
+    }
+
+    #[async_trait]
+    impl NetworkProvider for SleepingNet {
+        fn echo(&self, ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+            if self.echoes.fetch_add(1, Ordering::SeqCst) == 0 {
+                self.power.send_modify(|state| state.sleeps += 1);
+                return Ok(None);
+            }
+            MockNet.echo(ip)
+        }
+        fn resolve_mac(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            if self.echoes.load(Ordering::SeqCst) == 1 {
+                return Ok(None);
+            }
+            MockNet.resolve_mac(ip)
+        }
+        fn resolve_hostname(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            MockNet.resolve_hostname(ip)
+        }
+        fn resolve_vendor(&self, mac: &str) -> Option<String> {
+            MockNet.resolve_vendor(mac)
+        }
+        async fn scan_port(&self, ip: Ipv4Addr, port: u16) -> bool {
+            MockNet.scan_port(ip, port).await
+        }
+        async fn scan_udp_port(&self, ip: Ipv4Addr, port: u16) -> UdpPortState {
+            MockNet.scan_udp_port(ip, port).await
+        }
+        fn watch_power(&self) -> Result<PowerWatch, GError> {
+            Ok(PowerWatch::new(self.power.subscribe()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_probe_across_sleep_is_retried() {
+        let (tx, mut rx) = channel(100);
+        let net = SleepingNet {
+            power: tokio::sync::watch::channel(PowerState::default()).0,
+            echoes: AtomicU32::new(0),
+        };
+        let scanner = Scanner::new(Arc::new(net), tx);
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        scanner
+            .scan_range(ip, ip, tokio_util::sync::CancellationToken::new())
+            .await;
+
+        let mut updates = Vec::new();
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::ScanUpdate(res) => updates.push(res.status),
+                BridgeMessage::ScanComplete => break,
+                _ => {}
+            }
+        }
+        assert_eq!(updates, [ScanStatus::Online]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limit_paces_packets_and_reports_stats() {
+        let (tx, mut rx) = channel(100);
+        let config = ScanConfig {
+            ports: (1..=19).collect(),
+            max_rate: Some(10),
+            ..ScanConfig::default()
+        };
+        let scanner = Scanner::new(Arc::new(MockNet), tx).with_config(config);
+        let start = tokio::time::Instant::now();
+        // .1 is online: one echo and 19 port probes.
+        scanner
+            .scan_range(
+                Ipv4Addr::new(192, 168, 1, 1),
+                Ipv4Addr::new(192, 168, 1, 3),
+                tokio_util::sync::CancellationToken::new(),
+            )
+            .await;
+        // 22 packets at 10 per second, the first one immediately.
+        assert!(start.elapsed() >= Duration::from_millis(2100));
+
+        let mut stats = Vec::new();
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::ScanStats(s) => stats.push(s),
+                BridgeMessage::ScanComplete => break,
+                _ => {}
+            }
+        }
+        let last = stats.last().expect("no stats for a multi-second scan");
+        assert_eq!(last.rate_limit, Some(10));
+        assert_eq!((last.completed, last.total), (3, 3));
+        assert_eq!(last.eta, Some(Duration::ZERO));
+        assert!(last.packets_per_second <= 11.0);
+    }
+
+    #[test]
+    fn test_adaptive_rate_backs_off_on_timeout_spike() {
+        let rate = RateController::new(Some(1000), true);
+        let interval = |answered, timed_out| {
+            for i in 0..answered + timed_out {
+                rate.record_echo(i < answered);
+            }
+            rate.adjust();
+            rate.rate_limit()
+        };
+        // A busy subnet sets the baseline at 20% unanswered.
+        assert_eq!(interval(80, 20), Some(1000));
+        assert_eq!(interval(80, 20), Some(1000));
+        // Answers dry up: the router is dropping packets.
+        assert_eq!(interval(10, 90), Some(500));
+        assert_eq!(interval(10, 90), Some(250));
+        // Too few samples to judge; then recovery by a tenth per interval.
+        assert_eq!(interval(1, 5), Some(250));
+        assert_eq!(interval(80, 20), Some(275));
+        // A sparse network times out most echoes from the start; that is not a spike.
+        let sparse = RateController::new(Some(1000), true);
+        for _ in 0..3 {
+            (0..100).for_each(|i| sparse.record_echo(i < 5));
+            sparse.adjust();
+        }
+        assert_eq!(sparse.rate_limit(), Some(1000));
+        // Without adaptive backoff the limit never moves.
+        let fixed = RateController::new(Some(1000), false);
+        (0..100).for_each(|_| fixed.record_echo(false));
+        fixed.adjust();
+        assert_eq!(fixed.rate_limit(), Some(1000));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_controller_counts_traffic_within_budget_and_bandwidth() {
+        // 8 kbps is 1000 bytes per second: a 500-byte probe holds the next for half a second.
+        let rate = RateController::new(None, false)
+            .with_budget(Some(3))
+            .with_bandwidth(Some(8));
+        let start = tokio::time::Instant::now();
+        assert!(rate.acquire(ProbeKind::Udp, 500).await);
+        assert!(rate.acquire(ProbeKind::Tcp, TCP_SYN_BYTES).await);
+        assert!(start.elapsed() >= Duration::from_millis(500));
+        assert!(rate.count(ProbeKind::Arp, ARP_REQUEST_BYTES));
+        assert!(!rate.budget_exhausted());
+
+        assert!(!rate.acquire(ProbeKind::Icmp, ECHO_REQUEST_BYTES).await);
+        assert!(!rate.count(ProbeKind::Arp, ARP_REQUEST_BYTES));
+        assert!(rate.budget_exhausted());
+        assert_eq!(rate.packets_sent(), 3);
+        let traffic = rate.traffic();
+        assert_eq!(traffic.icmp, PacketCount::default());
+        assert_eq!(
+            traffic.udp,
+            PacketCount {
+                packets: 1,
+                bytes: 500
+            }
+        );
+        assert_eq!(
+            traffic.total().bytes,
+            500 + TCP_SYN_BYTES + ARP_REQUEST_BYTES
+        );
+    }
+
+    #[tokio::test]
+    async fn test_probe_budget_stops_the_scan() {
+        let (tx, mut rx) = channel(100);
+        let config = ScanConfig {
+            concurrency: 1,
+            probe_budget: Some(1),
+            ..ScanConfig::default()
+        };
+        let scanner = Scanner::new(Arc::new(MockNet), tx).with_config(config);
+        scanner
+            .scan_range(
+                Ipv4Addr::new(192, 168, 1, 1),
+                Ipv4Addr::new(192, 168, 1, 4),
+                tokio_util::sync::CancellationToken::new(),
+            )
+            .await;
+
+        let mut statuses = HashMap::new();
+        let mut summary = None;
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::ScanUpdate(res) => {
+                    if res.ip == Ipv4Addr::new(192, 168, 1, 1) {
+                        // The echo got through; the port probes did not.
+                        assert!(res.open_ports.is_empty());
+                        assert!(
+                            res.warnings
+                                .iter()
+                                .any(|w| w.to_string().contains("Probe budget used up")),
+                            "{:?}",
+                            res.warnings
+                        );
+                    }
+                    statuses.insert(res.ip, res.status);
+                }
+                BridgeMessage::ScanSummary(s) => summary = Some(s),
+                BridgeMessage::ScanCancelled => break,
+                BridgeMessage::ScanComplete => panic!("a scan out of budget completed"),
+                _ => {}
+            }
+        }
+        assert_eq!(statuses[&Ipv4Addr::new(192, 168, 1, 1)], ScanStatus::Online);
+        for last in 2..=4 {
+            assert_eq!(
+                statuses[&Ipv4Addr::new(192, 168, 1, last)],
+                ScanStatus::NotScanned
+            );
+        }
+        let summary = summary.expect("no summary");
+        assert!(summary.budget_exhausted);
+        assert_eq!(summary.probes_sent, 1);
+        assert_eq!(
+            summary.traffic.icmp,
+            PacketCount {
+                packets: 1,
+                bytes: ECHO_REQUEST_BYTES
+            }
+        );
+        assert_eq!(summary.hosts_up, 1);
+    }
+
+    /// Stalls every TCP probe and records how many were in flight at once.
+    #[derive(Default)]
+    struct StallingNet {
+        in_flight: std::sync::atomic::AtomicUsize,
+        peak: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait]
+    impl NetworkProvider for StallingNet {
+        fn echo(&self, ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+            MockNet.echo(ip)
+        }
+        fn resolve_mac(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            Ok(None)
+        }
+        fn resolve_hostname(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            Ok(None)
+        }
+        fn resolve_vendor(&self, _mac: &str) -> Option<String> {
+            None
+        }
+        async fn scan_port(&self, _ip: Ipv4Addr, port: u16) -> bool {
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            port.is_multiple_of(2)
+        }
+        async fn scan_udp_port(&self, _ip: Ipv4Addr, _port: u16) -> UdpPortState {
+            UdpPortState::Closed
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ports_probed_concurrently_up_to_limit() {
+        let net = Arc::new(StallingNet::default());
+        let (tx, mut rx) = channel(100);
+        let config = ScanConfig {
+            ports: (1..=12).rev().collect(),
+            port_concurrency: 4,
+            ..ScanConfig::default()
+        };
+        let scanner = Scanner::new(net.clone(), tx).with_config(config);
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        scanner
+            .scan_range(ip, ip, tokio_util::sync::CancellationToken::new())
+            .await;
+
+        let mut result = None;
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::ScanUpdate(res) => result = Some(res),
+                BridgeMessage::ScanComplete => break,
+                _ => {}
+            }
+        }
+        assert_eq!(net.peak.load(Ordering::SeqCst), 4);
+        let open: Vec<u16> = result.unwrap().open_ports.iter().map(|p| p.port).collect();
+        assert_eq!(open, [12, 10, 8, 6, 4, 2]);
+    }
+
+    /// Holds every echo until `hosts` of them are waiting at once, and never
+    /// answers the blocking echo, so a scan only finishes if pings are awaited.
+    struct InFlightNet {
+        barrier: tokio::sync::Barrier,
+    }
+
+    #[async_trait]
+    impl NetworkProvider for InFlightNet {
+        fn echo(&self, _ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+            panic!("echo should be awaited, not run on a blocking thread");
+        }
+        async fn echo_async(
+            &self,
+            _ip: Ipv4Addr,
+            _timeout: Duration,
+        ) -> Result<Option<crate::net::EchoReply>, GError> {
+            self.barrier.wait().await;
+            Ok(None)
+        }
+        fn resolve_mac(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            Ok(None)
+        }
+        fn resolve_hostname(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            Ok(None)
+        }
+        fn resolve_vendor(&self, _mac: &str) -> Option<String> {
+            None
+        }
+        async fn scan_port(&self, _ip: Ipv4Addr, _port: u16) -> bool {
+            false
+        }
+        async fn scan_udp_port(&self, _ip: Ipv4Addr, _port: u16) -> UdpPortState {
+            UdpPortState::Closed
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pings_in_flight_together_on_runtime() {
+        let hosts = 200;
+        let net = Arc::new(InFlightNet {
+            barrier: tokio::sync::Barrier::new(hosts),
+        });
+        let (tx, mut rx) = channel(100);
+        let config = ScanConfig {
+            concurrency: hosts,
+            retries: 0,
+            ..ScanConfig::default()
+        };
+        let scanner = Scanner::new(net, tx).with_config(config);
+        let scan = scanner.scan_range(
+            Ipv4Addr::new(10, 0, 0, 1),
+            Ipv4Addr::new(10, 0, 0, hosts as u8),
+            tokio_util::sync::CancellationToken::new(),
+        );
+        let collect = async {
+            let mut offline = 0;
+            while let Some(msg) = rx.recv().await {
+                match msg {
+                    BridgeMessage::ScanUpdate(res) => {
+                        assert_eq!(res.status, ScanStatus::Offline);
+                        offline += 1;
+                    }
+                    BridgeMessage::ScanComplete => break,
+                    _ => {}
+                }
+            }
+            offline
+        };
+        let (_, offline) = tokio::time::timeout(Duration::from_secs(10), async {
+            tokio::join!(scan, collect)
+        })
+        .await
+        .expect("pings did not overlap");
+        assert_eq!(offline, hosts);
+    }
+
+    /// Counts the echoes awaited at once, and panics on the echo to `.13`.
+    #[derive(Default)]
+    struct PoolNet {
+        waiting: AtomicU32,
+        most: AtomicU32,
+    }
+
+    #[async_trait]
+    impl NetworkProvider for PoolNet {
+        fn echo(&self, _ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+            Ok(None)
+        }
+        async fn echo_async(
+            &self,
+            ip: Ipv4Addr,
+            _timeout: Duration,
+        ) -> Result<Option<crate::net::EchoReply>, GError> {
+            assert_ne!(ip, Ipv4Addr::new(10, 0, 0, 13), "probe blew up");
+            let now = self.waiting.fetch_add(1, Ordering::SeqCst) + 1;
+            self.most.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            self.waiting.fetch_sub(1, Ordering::SeqCst);
+            Ok(None)
+        }
+        fn resolve_mac(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            Ok(None)
+        }
+        fn resolve_hostname(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            Ok(None)
+        }
+        fn resolve_vendor(&self, _mac: &str) -> Option<String> {
+            None
+        }
+        async fn scan_port(&self, _ip: Ipv4Addr, _port: u16) -> bool {
+            false
+        }
+        async fn scan_udp_port(&self, _ip: Ipv4Addr, _port: u16) -> UdpPortState {
+            UdpPortState::Closed
+        }
+    }
+
+    #[tokio::test]
+    async fn test_worker_pool_caps_hosts_in_flight() {
+        let net = Arc::new(PoolNet::default());
+        let (tx, mut rx) = channel(100);
+        let config = ScanConfig {
+            concurrency: 4,
+            ..ScanConfig::default()
+        };
+        let scanner = Scanner::new(net.clone(), tx).with_config(config);
+        let token = tokio_util::sync::CancellationToken::new();
+        let scan = scanner.scan_range(
+            Ipv4Addr::new(10, 0, 0, 20),
+            Ipv4Addr::new(10, 0, 0, 51),
+            token,
+        );
+        let collect = async {
+            let mut hosts = 0;
+            while let Some(msg) = rx.recv().await {
+                match msg {
+                    BridgeMessage::ScanUpdate(_) => hosts += 1,
+                    BridgeMessage::ScanComplete => break,
+                    _ => {}
+                }
+            }
+            hosts
+        };
+        let (_, hosts) = tokio::join!(scan, collect);
+        assert_eq!(hosts, 32);
+        assert_eq!(net.most.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_panicking_probe_costs_only_its_host() {
+        let (tx, mut rx) = channel(100);
+        let config = ScanConfig {
+            concurrency: 2,
+            ..ScanConfig::default()
+        };
+        let scanner = Scanner::new(Arc::new(PoolNet::default()), tx).with_config(config);
+        let token = tokio_util::sync::CancellationToken::new();
+        scanner
+            .scan_range(
+                Ipv4Addr::new(10, 0, 0, 11),
+                Ipv4Addr::new(10, 0, 0, 16),
+                token,
+            )
+            .await;
+
+        let (mut seen, mut stats) = (Vec::new(), None);
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::ScanUpdate(res) => seen.push(res.ip.octets()[3]),
+                BridgeMessage::ScanStats(s) => stats = Some(s),
+                BridgeMessage::ScanComplete => break,
+                _ => {}
+            }
         }
+        seen.sort_unstable();
+        assert_eq!(seen, [11, 12, 14, 15, 16]);
+        // The lost host still counts, so the scan reaches 100%.
+        let stats = stats.expect("no stats");
+        assert_eq!((stats.completed, stats.total), (6, 6));
     }
 
-    /// Scans a contiguous range of IPv4 addresses.
-    ///
-    /// Sends [`BridgeMessage::ScanUpdate`], [`BridgeMessage::Progress`], and
-    /// [`BridgeMessage::ScanComplete`] (or [`BridgeMessage::ScanCancelled`]) through the channel.
-    ///
-    /// # Errors
-    ///
-    /// System-level errors (e.g. Win32 API failures) are not returned directly. Instead:
-    /// - Range-level errors are sent via [`BridgeMessage::Error`].
-    /// - Individual IP failures are sent via [`BridgeMessage::ScanUpdate`] with [`ScanStatus::SystemError`](crate::types::ScanStatus::SystemError).
-    pub async fn scan_range(
-        &self,
-        start_ip: Ipv4Addr,
-        end_ip: Ipv4Addr,
-        cancel_token: tokio_util::sync::CancellationToken,
-    ) {
-        let start_u32: u32 = u32::from(start_ip);
-        let end_u32: u32 = u32::from(end_ip);
+    /// Answers every echo and counts the port probes in flight at once.
+    #[derive(Default)]
+    struct BusyNet {
+        open: AtomicU32,
+        most: AtomicU32,
+    }
 
-        // Simple validation
-        if start_u32 > end_u32 {
-            let _ = self
-                .tx_bridge
-                .send(BridgeMessage::Error(GError::Internal(
-                    "Invalid IP Range".to_string(),
-                )))
-                .await;
-            return;
+    #[async_trait]
+    impl NetworkProvider for BusyNet {
+        fn echo(&self, _ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+            Ok(Some(1))
+        }
+        fn resolve_mac(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            Ok(None)
+        }
+        fn resolve_hostname(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            Ok(None)
         }
+        fn resolve_vendor(&self, _mac: &str) -> Option<String> {
+            None
+        }
+        async fn scan_port(&self, _ip: Ipv4Addr, _port: u16) -> bool {
+            let now = self.open.fetch_add(1, Ordering::SeqCst) + 1;
+            self.most.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.open.fetch_sub(1, Ordering::SeqCst);
+            false
+        }
+        async fn scan_udp_port(&self, _ip: Ipv4Addr, _port: u16) -> UdpPortState {
+            UdpPortState::Closed
+        }
+    }
 
-        log::info!(
-            "Starting scan for range: {} - {} (Total: {})",
-            start_ip,
-            end_ip,
-            end_u32 - start_u32 + 1
+    #[tokio::test]
+    async fn test_connections_capped_across_hosts() {
+        let net = Arc::new(BusyNet::default());
+        let (tx, mut rx) = channel(100);
+        // 200 hosts of 8 probes each would open 1600 connections.
+        let config = ScanConfig {
+            concurrency: 200,
+            port_concurrency: 8,
+            ports: (1..=16).collect(),
+            resolve_hostnames: false,
+            ..ScanConfig::default()
+        };
+        let scanner = Scanner::new(net.clone(), tx).with_config(config);
+        let scan = scanner.scan_range(
+            Ipv4Addr::new(10, 0, 0, 1),
+            Ipv4Addr::new(10, 0, 0, 200),
+            tokio_util::sync::CancellationToken::new(),
         );
-        let total_ips = end_u32 - start_u32 + 1;
-        let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_TASKS));
-        let mut tasks = tokio::task::JoinSet::new();
-
-        for i in start_u32..=end_u32 {
-            // Check for cancellation before spawning each IP task
-            if cancel_token.is_cancelled() {
-                log::info!("Scan cancelled by user.");
-                break;
+        let collect = async {
+            let mut online = 0;
+            while let Some(msg) = rx.recv().await {
+                match msg {
+                    BridgeMessage::ScanUpdate(res) if res.status == ScanStatus::Online => {
+                        online += 1
+                    }
+                    BridgeMessage::ScanComplete => break,
+                    _ => {}
+                }
             }
+            online
+        };
+        let (_, online) = tokio::join!(scan, collect);
+        assert_eq!(online, 200);
+        let most = net.most.load(Ordering::SeqCst) as usize;
+        assert!(most <= MAX_SCAN_CONNECTIONS, "{} connections at once", most);
+        assert!(
+            most > MAX_SCAN_CONNECTIONS / 2,
+            "only {} connections at once",
+            most
+        );
+    }
 
-            let ip = Ipv4Addr::from(i);
-            let semaphore_clone = semaphore.clone();
-            let permit_res = semaphore_clone.acquire_owned().await;
+    /// Drops the first echo request, like a host that is slow to wake up.
+    struct SleepyNet(AtomicU32);
 
-            let permit = match permit_res {
-                Ok(p) => p,
-                Err(e) => {
-                    let _ = self
-                        .tx_bridge
-                        .send(BridgeMessage::Error(GError::Internal(format!(
-                            "Semaphore closed: {}",
-                            e
-                        ))))
-                        .await;
-                    break;
-                }
-            };
+    #[async_trait]
+    impl NetworkProvider for SleepyNet {
+        fn echo(&self, ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+            if self.0.fetch_add(1, Ordering::SeqCst) == 0 {
+                return Ok(None);
+            }
+            MockNet.echo(ip)
+        }
+        fn resolve_mac(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            Ok(None)
+        }
+        fn resolve_hostname(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            Ok(None)
+        }
+        fn resolve_local_name(&self, _ip: Ipv4Addr) -> Option<(String, NameSource)> {
+            Some(("SLEEPY-PC".to_string(), NameSource::NetBios))
+        }
+        fn resolve_vendor(&self, _mac: &str) -> Option<String> {
+            None
+        }
+        async fn scan_port(&self, _ip: Ipv4Addr, _port: u16) -> bool {
+            false
+        }
+        async fn scan_udp_port(&self, _ip: Ipv4Addr, _port: u16) -> UdpPortState {
+            UdpPortState::Closed
+        }
+    }
 
-            let net_utils = self.net_utils.clone();
-            let tx = self.tx_bridge.clone();
+    async fn scan_sleepy_host(retries: u32) -> ScanResult {
+        let (tx, mut rx) = channel(100);
+        let config = ScanConfig {
+            retries,
+            ..ScanConfig::default()
+        };
+        let scanner = Scanner::new(Arc::new(SleepyNet(AtomicU32::new(0))), tx).with_config(config);
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        scanner
+            .scan_range(ip, ip, tokio_util::sync::CancellationToken::new())
+            .await;
+        while let Some(msg) = rx.recv().await {
+            if let BridgeMessage::ScanUpdate(res) = msg {
+                return res;
+            }
+        }
+        panic!("no result");
+    }
 
-            tasks.spawn(async move {
-                let _permit = permit;
-                let mut result = ScanResult::new(ip);
-                log::info!("Scanning: {}", ip);
+    #[tokio::test]
+    async fn test_retries_rescue_missed_echo() {
+        assert_eq!(scan_sleepy_host(0).await.status, ScanStatus::Offline);
 
-                let net_utils_blocking = net_utils.clone();
-                let blocking_task = tokio::task::spawn_blocking(move || {
-                    let mut is_online = false;
-                    let mut system_error = None;
+        let res = scan_sleepy_host(1).await;
+        assert_eq!(res.status, ScanStatus::Online);
+        let latency = res.latency.unwrap();
+        assert_eq!((latency.received, latency.sent), (1, 2));
+        assert_eq!(res.attempts.ping, 2);
+    }
 
-                    // Try Ping
-                    match net_utils_blocking.ping(ip) {
-                        Ok(true) => is_online = true,
-                        Ok(false) => {}
-                        Err(e) => system_error = Some(e),
-                    }
+    /// [`MockNet`] with port 445 open everywhere and SMBv1 accepted on `.1`,
+    /// which speaks nothing newer.
+    struct LegacySmbNet;
 
-                    // Try ARP
-                    if system_error.is_none() {
-                        match net_utils_blocking.resolve_mac(ip) {
-                            Ok(Some(mac)) => {
-                                let hostname =
-                                    net_utils_blocking.resolve_hostname(ip).unwrap_or(None);
-                                let vendor = net_utils_blocking.resolve_vendor(&mac);
-                                return Ok((true, Some(mac), hostname, vendor));
-                            }
-                            Ok(None) => {}
-                            Err(e) => system_error = Some(e),
-                        }
-                    }
+    #[async_trait]
+    impl NetworkProvider for LegacySmbNet {
+        fn echo(&self, ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+            MockNet.echo(ip)
+        }
+        fn resolve_mac(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            MockNet.resolve_mac(ip)
+        }
+        fn resolve_hostname(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            MockNet.resolve_hostname(ip)
+        }
+        fn resolve_vendor(&self, mac: &str) -> Option<String> {
+            MockNet.resolve_vendor(mac)
+        }
+        async fn scan_port(&self, _ip: Ipv4Addr, port: u16) -> bool {
+            port == SMB_PORT
+        }
+        async fn scan_udp_port(&self, _ip: Ipv4Addr, _port: u16) -> UdpPortState {
+            UdpPortState::Closed
+        }
+        async fn probe_smb1(&self, ip: Ipv4Addr) -> Option<bool> {
+            Some(ip.octets()[3] == 1)
+        }
+        async fn smb_info(&self, ip: Ipv4Addr) -> Option<SmbInfo> {
+            let legacy = ip.octets()[3] == 1;
+            Some(SmbInfo {
+                dialect: if legacy { "1" } else { "3.1.1" }.to_string(),
+                hostname: Some("OLDNAS".to_string()),
+                domain: Some("WORKGROUP".to_string()),
+                os_version: None,
+            })
+        }
+    }
 
-                    if let Some(err) = system_error {
-                        Err(err)
-                    } else {
-                        let hostname = net_utils_blocking.resolve_hostname(ip).unwrap_or(None);
-                        Ok((is_online, None, hostname, None))
-                    }
-                })
+    #[tokio::test]
+    async fn test_smb1_audit_flags_legacy_servers() {
+        for audit in [false, true] {
+            let (tx, mut rx) = channel(100);
+            let config = ScanConfig {
+                smb1_audit: audit,
+                ..ScanConfig::default()
+            };
+            let scanner = Scanner::new(Arc::new(LegacySmbNet), tx).with_config(config);
+            let ip = Ipv4Addr::new(192, 168, 1, 1);
+            scanner
+                .scan_range(ip, ip, tokio_util::sync::CancellationToken::new())
                 .await;
+            let Some(res) = next_update(&mut rx).await else {
+                panic!("no result");
+            };
+            let expected = if audit {
+                vec![SecurityIssue::Smb1Only, SecurityIssue::Smb1Enabled]
+            } else {
+                vec![SecurityIssue::Smb1Only]
+            };
+            assert_eq!(res.issues, expected);
+            let info = res.smb_info.expect("SMB info collected without the audit");
+            assert_eq!(info.hostname.as_deref(), Some("OLDNAS"));
+        }
+    }
 
-                match blocking_task {
-                    Ok(Ok((is_online, mac, hostname, vendor))) => {
-                        log::info!("Scan result for {}: online={}", ip, is_online);
-                        // Force reporting for debugging
-                        if true {
-                            // was if is_online
-                            if is_online {
-                                result.status = ScanStatus::Online;
-                            } else {
-                                result.status = ScanStatus::Offline;
-                            }
-                            result.mac = mac;
-                            result.hostname = hostname;
-                            result.vendor = vendor;
-
-                            // Port Scan (Async)
-                            if is_online {
-                                let mut open_ports = Vec::new();
-                                for &(port, _) in COMMON_PORTS {
-                                    if net_utils.scan_port(ip, port).await {
-                                        open_ports.push(port);
-                                    }
-                                }
-                                result.open_ports = open_ports;
-                            }
+    /// Serves HTTPS with TLS 1.0 still enabled and an expired certificate.
+    struct LegacyTlsNet;
 
-                            let _ = tx.send(BridgeMessage::ScanUpdate(result)).await;
-                        }
-                    }
-                    Ok(Err(e)) => {
-                        log::error!("System error scanning {}: {}", ip, e);
-                        result.status = ScanStatus::SystemError(e);
-                        let _ = tx.send(BridgeMessage::ScanUpdate(result)).await;
-                    }
-                    Err(e) => {
-                        result.status = ScanStatus::SystemError(GError::Internal(format!(
-                            "Task failed: {}",
-                            e
-                        )));
-                        let _ = tx.send(BridgeMessage::ScanUpdate(result)).await;
-                    }
-                }
-            });
+    #[async_trait]
+    impl NetworkProvider for LegacyTlsNet {
+        fn echo(&self, ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+            MockNet.echo(ip)
+        }
+        fn resolve_mac(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            MockNet.resolve_mac(ip)
+        }
+        fn resolve_hostname(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            MockNet.resolve_hostname(ip)
+        }
+        fn resolve_vendor(&self, mac: &str) -> Option<String> {
+            MockNet.resolve_vendor(mac)
         }
+        async fn scan_port(&self, _ip: Ipv4Addr, port: u16) -> bool {
+            port == 443
+        }
+        async fn scan_udp_port(&self, _ip: Ipv4Addr, _port: u16) -> UdpPortState {
+            UdpPortState::Closed
+        }
+        async fn audit_tls(&self, _ip: Ipv4Addr, port: u16) -> Option<TlsInfo> {
+            Some(TlsInfo {
+                port,
+                min_version: TlsVersion::Tls10,
+                cert_not_after: Some(1_000_000_000),
+            })
+        }
+    }
 
-        let mut completed: u32 = 0;
-        while tasks.join_next().await.is_some() {
-            completed += 1;
-            let progress = (completed as f32 / total_ips as f32 * 100.0) as u8;
-            let _ = self.tx_bridge.send(BridgeMessage::Progress(progress)).await;
+    #[tokio::test]
+    async fn test_tls_audit_reports_legacy_protocols_and_expired_certificates() {
+        for audit in [false, true] {
+            let (tx, mut rx) = channel(100);
+            let config = ScanConfig {
+                tls_audit: audit,
+                ..ScanConfig::default()
+            };
+            let scanner = Scanner::new(Arc::new(LegacyTlsNet), tx).with_config(config);
+            let ip = Ipv4Addr::new(192, 168, 1, 1);
+            scanner
+                .scan_range(ip, ip, tokio_util::sync::CancellationToken::new())
+                .await;
+            let Some(res) = next_update(&mut rx).await else {
+                panic!("no result");
+            };
+            if !audit {
+                assert!(res.tls.is_empty() && res.issues.is_empty());
+                continue;
+            }
+            assert_eq!(res.tls.len(), 1);
+            assert_eq!(
+                res.issues,
+                [
+                    SecurityIssue::LegacyTls {
+                        port: 443,
+                        version: TlsVersion::Tls10
+                    },
+                    SecurityIssue::CertificateExpired { port: 443 },
+                ]
+            );
         }
+    }
 
-        if cancel_token.is_cancelled() {
-            log::info!("Scan completed (Cancelled).");
-            let _ = self.tx_bridge.send(BridgeMessage::ScanCancelled).await;
-        } else {
-            log::info!("Scan completed (Finished).");
-            let _ = self.tx_bridge.send(BridgeMessage::ScanComplete).await;
+    /// Ignores ICMP; only the second ARP request gets an answer.
+    struct LateArpNet(AtomicU32);
+
+    #[async_trait]
+    impl NetworkProvider for LateArpNet {
+        fn echo(&self, _ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+            Ok(None)
+        }
+        fn resolve_mac(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            Ok(
+                (self.0.fetch_add(1, Ordering::SeqCst) > 0)
+                    .then(|| "AA:BB:CC:DD:EE:FF".to_string()),
+            )
+        }
+        fn resolve_hostname(&self, _ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            Ok(None)
+        }
+        fn resolve_vendor(&self, _mac: &str) -> Option<String> {
+            None
+        }
+        async fn scan_port(&self, _ip: Ipv4Addr, _port: u16) -> bool {
+            false
+        }
+        async fn scan_udp_port(&self, _ip: Ipv4Addr, _port: u16) -> UdpPortState {
+            UdpPortState::Closed
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::net::MockNet;
-    use tokio::sync::mpsc::channel;
+    /// [`MockNet`] on a machine that can send neither ICMP nor UDP.
+    struct NoIcmpNet;
+
+    #[async_trait]
+    impl NetworkProvider for NoIcmpNet {
+        fn echo(&self, _ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+            panic!("echo sent without ICMP");
+        }
+        fn resolve_mac(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            MockNet.resolve_mac(ip)
+        }
+        fn resolve_hostname(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            MockNet.resolve_hostname(ip)
+        }
+        fn resolve_vendor(&self, mac: &str) -> Option<String> {
+            MockNet.resolve_vendor(mac)
+        }
+        fn discover_mdns(&self) -> HashMap<Ipv4Addr, MdnsHost> {
+            panic!("mDNS sent without UDP");
+        }
+        async fn scan_port(&self, ip: Ipv4Addr, port: u16) -> bool {
+            MockNet.scan_port(ip, port).await
+        }
+        async fn scan_udp_port(&self, _ip: Ipv4Addr, _port: u16) -> UdpPortState {
+            panic!("UDP probe sent without UDP");
+        }
+        fn capabilities(&self) -> Capabilities {
+            Capabilities {
+                icmp: false,
+                udp: false,
+                ..Capabilities::default()
+            }
+        }
+    }
 
     #[tokio::test]
-    async fn test_scanner_with_ports_and_progress() {
+    async fn test_unsupported_probes_are_skipped() {
         let (tx, mut rx) = channel(100);
-        let scanner = Scanner::new(Arc::new(MockNet), tx);
+        let config = ScanConfig {
+            mdns_discovery: true,
+            ..ScanConfig::default()
+        };
+        let scanner = Scanner::new(Arc::new(NoIcmpNet), tx).with_config(config);
+        scanner.set_udp_scan(true);
+        let ip = Ipv4Addr::new(192, 168, 1, 1);
+        scanner
+            .scan_range(ip, ip, tokio_util::sync::CancellationToken::new())
+            .await;
+        let Some(res) = next_update(&mut rx).await else {
+            panic!("no result");
+        };
+        // Found over ARP alone, with its TCP ports still scanned.
+        assert_eq!(res.status, ScanStatus::Online);
+        assert_eq!(res.attempts.ping, 0);
+        assert_eq!(res.latency, None);
+        assert!(res.open_ports.iter().any(|p| p.port == 80));
+        assert!(res.open_udp_ports.is_empty());
+    }
 
-        let start = Ipv4Addr::new(192, 168, 1, 1);
-        let end = Ipv4Addr::new(192, 168, 1, 1); // Single IP
-        let token = tokio_util::sync::CancellationToken::new();
+    #[tokio::test]
+    async fn test_retries_cover_arp_with_jittered_delay() {
+        let (tx, mut rx) = channel(100);
+        let config = ScanConfig {
+            retries: 2,
+            retry_delay: Duration::from_millis(40),
+            ..ScanConfig::default()
+        };
+        let scanner = Scanner::new(Arc::new(LateArpNet(AtomicU32::new(0))), tx).with_config(config);
+        let ip = Ipv4Addr::new(10, 0, 0, 7);
+        let started = std::time::Instant::now();
+        scanner
+            .scan_range(ip, ip, tokio_util::sync::CancellationToken::new())
+            .await;
+        // Two ping retries and one ARP retry, each waiting at least 20 ms.
+        assert!(started.elapsed() >= Duration::from_millis(60));
+        let Some(res) = next_update(&mut rx).await else {
+            panic!("no result");
+        };
+        assert_eq!(res.status, ScanStatus::Online);
+        assert_eq!(res.mac.as_deref(), Some("AA:BB:CC:DD:EE:FF"));
+        assert_eq!(
+            res.attempts,
+            crate::types::ProbeAttempts { ping: 3, arp: 2 }
+        );
+    }
 
-        scanner.scan_range(start, end, token).await;
+    /// [`MockNet`] with the echo failing for .1 and ARP failing for .3,
+    /// which answers pings.
+    struct PartialNet;
 
-        let mut found_online = false;
-        let mut found_progress = false;
-        let mut complete = false;
+    #[async_trait]
+    impl NetworkProvider for PartialNet {
+        fn echo(&self, ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+            match ip.octets()[3] {
+                1 => Err(GError::PermissionDenied("IcmpSendEcho".to_string())),
+                3 => Ok(Some(4)),
+                _ => MockNet.echo(ip),
+            }
+        }
+        fn resolve_mac(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            match ip.octets()[3] {
+                3 => Err(GError::AdapterDown("SendARP".to_string())),
+                _ => MockNet.resolve_mac(ip),
+            }
+        }
+        fn resolve_hostname(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            MockNet.resolve_hostname(ip)
+        }
+        fn resolve_vendor(&self, mac: &str) -> Option<String> {
+            MockNet.resolve_vendor(mac)
+        }
+        async fn scan_port(&self, ip: Ipv4Addr, port: u16) -> bool {
+            MockNet.scan_port(ip, port).await
+        }
+        async fn scan_udp_port(&self, ip: Ipv4Addr, port: u16) -> UdpPortState {
+            MockNet.scan_udp_port(ip, port).await
+        }
+    }
 
+    #[tokio::test]
+    async fn test_failed_phases_keep_partial_results() {
+        let (tx, mut rx) = channel(100);
+        let scanner = Scanner::new(Arc::new(PartialNet), tx);
+        scanner
+            .scan_range(
+                Ipv4Addr::new(192, 168, 1, 1),
+                Ipv4Addr::new(192, 168, 1, 3),
+                tokio_util::sync::CancellationToken::new(),
+            )
+            .await;
+        let mut results = HashMap::new();
         while let Some(msg) = rx.recv().await {
             match msg {
                 BridgeMessage::ScanUpdate(res) => {
-                    if res.ip == Ipv4Addr::new(192, 168, 1, 1) {
-                        assert_eq!(res.status, ScanStatus::Online);
-                        assert!(res.open_ports.contains(&80));
-                        found_online = true;
-                    }
+                    results.insert(res.ip.octets()[3], res);
                 }
-                BridgeMessage::Progress(p) => {
-                    assert!(p <= 100);
-                    found_progress = true;
+                BridgeMessage::ScanComplete => break,
+                _ => {}
+            }
+        }
+
+        // The echo failed but ARP still found the host.
+        let arp_only = &results[&1];
+        assert_eq!(arp_only.status, ScanStatus::Online);
+        assert_eq!(arp_only.mac.as_deref(), Some("00:11:22:33:44:55"));
+        assert!(arp_only.open_ports.iter().any(|p| p.port == 80));
+        assert_eq!(
+            arp_only.warnings,
+            [GError::PermissionDenied("IcmpSendEcho".to_string())]
+        );
+        // ARP failed but the ping data is kept.
+        let echo_only = &results[&3];
+        assert_eq!(echo_only.status, ScanStatus::Online);
+        assert_eq!(echo_only.latency.as_ref().map(|l| l.min_ms), Some(4.0));
+        assert_eq!(
+            echo_only.warnings,
+            [GError::AdapterDown("SendARP".to_string())]
+        );
+        // Every phase failed: the first failure is the error, the rest warnings.
+        let failed = &results[&2];
+        let simulated = GError::Internal("Simulated Failure".to_string());
+        assert_eq!(failed.status, ScanStatus::SystemError(simulated.clone()));
+        assert_eq!(failed.warnings, [simulated]);
+    }
+
+    #[test]
+    fn test_jitter_stays_within_half_the_delay() {
+        let delay = Duration::from_millis(100);
+        for _ in 0..100 {
+            let d = jittered(delay);
+            assert!(d >= delay / 2 && d < delay * 3 / 2, "{:?}", d);
+        }
+    }
+
+    /// [`MockNet`] plus a printer at `.9` that ignores ICMP but answers mDNS,
+    /// a TV at `.12` that only answers SSDP and a router at `.1` that is the
+    /// gateway and DNS server, and answers DHCP probes.
+    struct DiscoveryNet;
+
+    #[async_trait]
+    impl NetworkProvider for DiscoveryNet {
+        fn echo(&self, ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+            MockNet.echo(ip)
+        }
+        fn resolve_mac(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            MockNet.resolve_mac(ip)
+        }
+        fn resolve_hostname(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+            MockNet.resolve_hostname(ip)
+        }
+        fn discover_mdns(&self) -> HashMap<Ipv4Addr, MdnsHost> {
+            let printer = MdnsHost {
+                hostname: Some("printer.local".to_string()),
+                services: vec!["_ipp._tcp".to_string(), "_printer._tcp".to_string()],
+            };
+            HashMap::from([(Ipv4Addr::new(192, 168, 1, 9), printer)])
+        }
+        fn discover_upnp(&self) -> HashMap<Ipv4Addr, UpnpInfo> {
+            let tv = UpnpInfo {
+                friendly_name: Some("Living Room TV".to_string()),
+                manufacturer: Some("Samsung".to_string()),
+                model: Some("QE55".to_string()),
+            };
+            HashMap::from([(Ipv4Addr::new(192, 168, 1, 12), tv)])
+        }
+        fn discover_roles(&self, dhcp_probe: bool) -> HashMap<Ipv4Addr, Vec<NetworkRole>> {
+            let mut roles = vec![NetworkRole::Gateway, NetworkRole::DnsServer];
+            if dhcp_probe {
+                roles.insert(1, NetworkRole::DhcpServer);
+            }
+            HashMap::from([(Ipv4Addr::new(192, 168, 1, 1), roles)])
+        }
+        fn query_snmp(&self, ip: Ipv4Addr, community: &str) -> Option<SnmpInfo> {
+            (ip == Ipv4Addr::new(192, 168, 1, 1) && community == "private").then(|| SnmpInfo {
+                name: Some("core-sw1".to_string()),
+                description: Some("Managed Switch".to_string()),
+                uptime: Some(Duration::from_secs(3600)),
+            })
+        }
+        fn resolve_vendor(&self, mac: &str) -> Option<String> {
+            MockNet.resolve_vendor(mac)
+        }
+        async fn scan_port(&self, ip: Ipv4Addr, port: u16) -> bool {
+            MockNet.scan_port(ip, port).await
+        }
+        async fn scan_udp_port(&self, ip: Ipv4Addr, port: u16) -> UdpPortState {
+            MockNet.scan_udp_port(ip, port).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mdns_discovery_marks_silent_hosts_online() {
+        for discovery in [false, true] {
+            let (tx, mut rx) = channel(100);
+            let config = ScanConfig {
+                mdns_discovery: discovery,
+                ..ScanConfig::default()
+            };
+            let scanner = Scanner::new(Arc::new(DiscoveryNet), tx).with_config(config);
+            let ip = Ipv4Addr::new(192, 168, 1, 9);
+            scanner
+                .scan_range(ip, ip, tokio_util::sync::CancellationToken::new())
+                .await;
+
+            let mut result = None;
+            while let Some(msg) = rx.recv().await {
+                match msg {
+                    BridgeMessage::ScanUpdate(res) => result = Some(res),
+                    BridgeMessage::ScanComplete => break,
+                    _ => {}
                 }
-                BridgeMessage::ScanComplete => {
-                    complete = true;
+            }
+            let res = result.unwrap();
+            if discovery {
+                assert_eq!(res.status, ScanStatus::Online);
+                assert_eq!(res.hostname.as_deref(), Some("printer.local"));
+                assert_eq!(res.hostname_source, Some(NameSource::Mdns));
+                assert_eq!(res.services, ["_ipp._tcp", "_printer._tcp"]);
+            } else {
+                assert_eq!(res.status, ScanStatus::Offline);
+                assert!(res.services.is_empty());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ssdp_discovery_adds_device_description() {
+        let (tx, mut rx) = channel(100);
+        let config = ScanConfig {
+            ssdp_discovery: true,
+            ..ScanConfig::default()
+        };
+        let scanner = Scanner::new(Arc::new(DiscoveryNet), tx).with_config(config);
+        let ip = Ipv4Addr::new(192, 168, 1, 12);
+        scanner
+            .scan_range(ip, ip, tokio_util::sync::CancellationToken::new())
+            .await;
+
+        let mut result = None;
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                BridgeMessage::ScanUpdate(res) => result = Some(res),
+                BridgeMessage::ScanComplete => break,
+                _ => {}
+            }
+        }
+        let res = result.unwrap();
+        assert_eq!(res.status, ScanStatus::Online);
+        assert_eq!(res.vendor.as_deref(), Some("Samsung"));
+        let upnp = res.upnp.unwrap();
+        assert_eq!(upnp.friendly_name.as_deref(), Some("Living Room TV"));
+        assert_eq!(upnp.model.as_deref(), Some("QE55"));
+    }
+
+    #[tokio::test]
+    async fn test_role_discovery_tags_infrastructure_hosts() {
+        for dhcp_discovery in [false, true] {
+            let (tx, mut rx) = channel(100);
+            let config = ScanConfig {
+                dhcp_discovery,
+                ..ScanConfig::default()
+            };
+            let scanner = Scanner::new(Arc::new(DiscoveryNet), tx).with_config(config);
+            scanner
+                .scan_range(
+                    Ipv4Addr::new(192, 168, 1, 1),
+                    Ipv4Addr::new(192, 168, 1, 2),
+                    tokio_util::sync::CancellationToken::new(),
+                )
+                .await;
+
+            let mut results = HashMap::new();
+            while let Some(msg) = rx.recv().await {
+                match msg {
+                    BridgeMessage::ScanUpdate(res) => {
+                        results.insert(res.ip, res.roles);
+                    }
+                    BridgeMessage::ScanComplete => break,
+                    _ => {}
+                }
+            }
+            let expected: &[NetworkRole] = if dhcp_discovery {
+                &[
+                    NetworkRole::Gateway,
+                    NetworkRole::DhcpServer,
+                    NetworkRole::DnsServer,
+                ]
+            } else {
+                &[NetworkRole::Gateway, NetworkRole::DnsServer]
+            };
+            assert_eq!(results[&Ipv4Addr::new(192, 168, 1, 1)], expected);
+            assert!(results[&Ipv4Addr::new(192, 168, 1, 2)].is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_host_states_follow_each_host_to_its_outcome() {
+        let (tx, mut rx) = channel(100);
+        let states = Arc::new(HostStates::default());
+        let scanner = Scanner::new(Arc::new(MockNet), tx).with_host_states(states.clone());
+        assert_eq!(states.snapshot(), StateSnapshot::default());
+        let scan = scanner.scan_range(
+            Ipv4Addr::new(192, 168, 1, 1),
+            Ipv4Addr::new(192, 168, 1, 4),
+            tokio_util::sync::CancellationToken::new(),
+        );
+        let drain = async {
+            while let Some(msg) = rx.recv().await {
+                if matches!(msg, BridgeMessage::ScanComplete) {
                     break;
                 }
-                _ => {}
+            }
+        };
+        tokio::join!(scan, drain);
+
+        let snapshot = states.snapshot();
+        assert_eq!(snapshot.pending, 0);
+        assert_eq!(
+            snapshot.hosts.into_iter().collect::<Vec<_>>(),
+            [
+                (Ipv4Addr::new(192, 168, 1, 1), HostPhase::Online),
+                (Ipv4Addr::new(192, 168, 1, 2), HostPhase::Error),
+                (Ipv4Addr::new(192, 168, 1, 3), HostPhase::Online),
+                (Ipv4Addr::new(192, 168, 1, 4), HostPhase::Offline),
+            ]
+        );
+
+        states.start(5);
+        states.set(Ipv4Addr::new(10, 0, 0, 1), HostPhase::Discovery);
+        states.set(Ipv4Addr::new(10, 0, 0, 1), HostPhase::Ports);
+        let snapshot = states.snapshot();
+        assert_eq!(snapshot.pending, 4);
+        assert_eq!(
+            snapshot.hosts[&Ipv4Addr::new(10, 0, 0, 1)],
+            HostPhase::Ports
+        );
+    }
+
+    #[tokio::test]
+    async fn test_snmp_probe_uses_configured_community() {
+        for community in ["public", "private"] {
+            let (tx, mut rx) = channel(100);
+            let config = ScanConfig {
+                snmp_community: Some(community.to_string()),
+                ..ScanConfig::default()
+            };
+            let scanner = Scanner::new(Arc::new(DiscoveryNet), tx).with_config(config);
+            scanner.set_udp_scan(true);
+            let ip = Ipv4Addr::new(192, 168, 1, 1);
+            scanner
+                .scan_range(ip, ip, tokio_util::sync::CancellationToken::new())
+                .await;
+
+            let mut result = None;
+            while let Some(msg) = rx.recv().await {
+                match msg {
+                    BridgeMessage::ScanUpdate(res) => result = Some(res),
+                    BridgeMessage::ScanComplete => break,
+                    _ => {}
+                }
+            }
+            let res = result.unwrap();
+            if community == "private" {
+                assert_eq!(res.snmp.unwrap().name.as_deref(), Some("core-sw1"));
+                // The answer settles the open|filtered UDP probe.
+                assert_eq!(res.open_udp_ports, vec![53, 161]);
+                assert!(res.filtered_udp_ports.is_empty());
+            } else {
+                assert_eq!(res.snmp, None);
+                assert_eq!(res.filtered_udp_ports, vec![161]);
             }
         }
+    }
 
-        assert!(found_online);
-        assert!(found_progress);
-        assert!(complete);
+    #[tokio::test]
+    async fn test_local_name_fallback_only_for_online_hosts() {
+        let offline = scan_sleepy_host(0).await;
+        assert_eq!((offline.hostname, offline.hostname_source), (None, None));
+
+        let online = scan_sleepy_host(1).await;
+        assert_eq!(online.hostname.as_deref(), Some("SLEEPY-PC"));
+        assert_eq!(online.hostname_source, Some(NameSource::NetBios));
     }
 }
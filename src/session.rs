@@ -0,0 +1,375 @@
+//! Scan session persistence for autosave and crash recovery.
+//!
+//! A [`Session`] captures the scanned range and the results received so far.
+//! Frontends save it periodically while a scan runs and discard it when the
+//! scan ends normally, so a file left behind means the last scan was interrupted.
+//!
+//! A [`ResultStream`] instead appends each result to an NDJSON file the
+//! moment its host finishes, for headless scans with no autosave.
+//!
+//! [`load_results`] also reads JSON exports and result streams, for viewing
+//! saved scans.
+
+use crate::types::{GError, ScanLabel, ScanResult, ScanStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Default autosave location, next to `ragescanner.log`.
+pub const DEFAULT_SESSION_PATH: &str = "ragescanner.session.json";
+
+/// Snapshot of an in-progress scan.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// First address of the scanned range.
+    pub start: Ipv4Addr,
+    /// Last address of the scanned range (inclusive).
+    pub end: Ipv4Addr,
+    /// Name the scan was started under, if any.
+    #[serde(default)]
+    pub label: Option<ScanLabel>,
+    /// Results received before the snapshot was taken.
+    pub results: Vec<ScanResult>,
+    /// Seconds since the Unix epoch when the snapshot was written.
+    pub saved_at: u64,
+}
+
+impl Session {
+    /// Creates an empty session for the given range.
+    pub fn new(start: Ipv4Addr, end: Ipv4Addr) -> Self {
+        Self {
+            start,
+            end,
+            label: None,
+            results: Vec::new(),
+            saved_at: 0,
+        }
+    }
+
+    /// Returns the lowest address in the range without a result, i.e. where a
+    /// resumed scan should start. Targets marked [`ScanStatus::NotScanned`]
+    /// count as missing. Returns `None` if every address is covered.
+    pub fn next_ip(&self) -> Option<Ipv4Addr> {
+        let done: HashSet<Ipv4Addr> = self
+            .results
+            .iter()
+            .filter(|r| r.status != ScanStatus::NotScanned)
+            .map(|r| r.ip)
+            .collect();
+        (u32::from(self.start)..=u32::from(self.end))
+            .map(Ipv4Addr::from)
+            .find(|ip| !done.contains(ip))
+    }
+
+    /// Writes the session to `path`, stamping [`saved_at`](Session::saved_at).
+    ///
+    /// The file is written to a temporary sibling and renamed into place so a
+    /// crash mid-write never leaves a truncated session behind.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if serialization or any file operation fails.
+    pub fn save(&mut self, path: &Path) -> Result<(), GError> {
+        self.saved_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let json = serde_json::to_string(self)
+            .map_err(|e| GError::Internal(format!("Failed to serialize session: {}", e)))?;
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, json).map_err(|e| {
+            GError::Internal(format!(
+                "Failed to write session '{}': {}",
+                tmp.display(),
+                e
+            ))
+        })?;
+        fs::rename(&tmp, path).map_err(|e| {
+            GError::Internal(format!(
+                "Failed to move session to '{}': {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    /// Loads a previously saved session.
+    ///
+    /// Returns `Ok(None)` if no session file exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if the file exists but cannot be read or parsed.
+    pub fn load(path: &Path) -> Result<Option<Self>, GError> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(path).map_err(|e| {
+            GError::Internal(format!(
+                "Failed to read session '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+        serde_json::from_str(&json).map(Some).map_err(|e| {
+            GError::Internal(format!("Corrupt session file '{}': {}", path.display(), e))
+        })
+    }
+
+    /// Deletes the session file. A missing file is not an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if the file exists but cannot be removed.
+    pub fn discard(path: &Path) -> Result<(), GError> {
+        match fs::remove_file(path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(GError::Internal(format!(
+                "Failed to remove session '{}': {}",
+                path.display(),
+                e
+            ))),
+        }
+    }
+}
+
+/// Appends results to an NDJSON file, one JSON object per line.
+///
+/// Each line goes to the file in a single write as soon as it is appended,
+/// so a process that dies mid-scan leaves every finished host on disk. An
+/// existing file is appended to; [`load_results`] keeps the last result per
+/// address and skips a final line cut off mid-write.
+#[derive(Debug)]
+pub struct ResultStream {
+    file: File,
+    path: PathBuf,
+}
+
+impl ResultStream {
+    /// Opens `path` for appending, creating it if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if the file cannot be opened.
+    pub fn create(path: &Path) -> Result<Self, GError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| GError::Internal(format!("Failed to open '{}': {}", path.display(), e)))?;
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Writes `result` as one line.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if serialization or the write fails.
+    pub fn append(&mut self, result: &ScanResult) -> Result<(), GError> {
+        let mut line = serde_json::to_vec(result)
+            .map_err(|e| GError::Internal(format!("Failed to serialize result: {}", e)))?;
+        line.push(b'\n');
+        self.file.write_all(&line).map_err(|e| {
+            GError::Internal(format!(
+                "Failed to write to '{}': {}",
+                self.path.display(),
+                e
+            ))
+        })
+    }
+}
+
+/// Results read by [`load_results`], with the label they were saved under.
+#[derive(Debug, Clone)]
+pub struct SavedResults {
+    pub label: Option<ScanLabel>,
+    pub results: Vec<ScanResult>,
+}
+
+/// File layouts accepted by [`load_results`].
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedFile {
+    Session(Session),
+    /// `export::render_labeled` JSON.
+    Labeled {
+        label: Option<ScanLabel>,
+        results: Vec<ScanResult>,
+    },
+    /// `export::render` JSON.
+    Plain(Vec<ScanResult>),
+}
+
+/// Reads the results of a saved scan: an autosaved [`Session`], a JSON
+/// export, labeled or not, or a [`ResultStream`] file. Results are returned
+/// sorted by IP.
+///
+/// # Errors
+///
+/// Returns [`GError::Internal`] if the file cannot be read or is none of these.
+pub fn load_results(path: &Path) -> Result<SavedResults, GError> {
+    let json = fs::read_to_string(path)
+        .map_err(|e| GError::Internal(format!("Failed to read '{}': {}", path.display(), e)))?;
+    parse_results(&json).map_err(|e| {
+        GError::Internal(format!(
+            "'{}' is not a session, JSON export or result stream: {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+fn parse_results(json: &str) -> Result<SavedResults, serde_json::Error> {
+    let (label, mut results) = match serde_json::from_str(json) {
+        Ok(SavedFile::Session(session)) => (session.label, session.results),
+        Ok(SavedFile::Labeled { label, results }) => (label, results),
+        Ok(SavedFile::Plain(results)) => (None, results),
+        Err(e) => (None, parse_stream(json).ok_or(e)?),
+    };
+    results.sort_by_key(|r| r.ip);
+    Ok(SavedResults { label, results })
+}
+
+/// Reads a [`ResultStream`] file, keeping the last result per address.
+///
+/// Returns `None` unless every line but the last is a result and at least
+/// one result was read; the last line may have been cut off by a crash.
+fn parse_stream(contents: &str) -> Option<Vec<ScanResult>> {
+    let lines: Vec<&str> = contents.lines().filter(|l| !l.trim().is_empty()).collect();
+    let (last, complete) = lines.split_last()?;
+    let mut results = complete
+        .iter()
+        .map(|line| serde_json::from_str(line))
+        .collect::<Result<Vec<ScanResult>, _>>()
+        .ok()?;
+    if let Ok(result) = serde_json::from_str(last) {
+        results.push(result);
+    }
+    if results.is_empty() {
+        return None;
+    }
+    let latest: HashMap<Ipv4Addr, ScanResult> = results.into_iter().map(|r| (r.ip, r)).collect();
+    Some(latest.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{self, OutputFormat};
+    use crate::types::{PortInfo, ScanStatus};
+
+    #[test]
+    fn test_next_ip_skips_completed() {
+        let mut session = Session::new(Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 3));
+        assert_eq!(session.next_ip(), Some(Ipv4Addr::new(10, 0, 0, 1)));
+
+        session
+            .results
+            .push(ScanResult::new(Ipv4Addr::new(10, 0, 0, 1)));
+        session
+            .results
+            .push(ScanResult::new(Ipv4Addr::new(10, 0, 0, 3)));
+        assert_eq!(session.next_ip(), Some(Ipv4Addr::new(10, 0, 0, 2)));
+
+        session
+            .results
+            .push(ScanResult::new(Ipv4Addr::new(10, 0, 0, 2)));
+        assert_eq!(session.next_ip(), None);
+    }
+
+    #[test]
+    fn test_save_load_roundtrip() {
+        let path =
+            std::env::temp_dir().join(format!("ragescanner-session-{}.json", std::process::id()));
+        let mut session =
+            Session::new(Ipv4Addr::new(192, 168, 1, 1), Ipv4Addr::new(192, 168, 1, 9));
+        let mut res = ScanResult::new(Ipv4Addr::new(192, 168, 1, 4));
+        res.status = ScanStatus::Online;
+        let ports = vec![PortInfo::new(22), PortInfo::new(80)];
+        res.open_ports = ports.clone();
+        session.results.push(res);
+        session.label = Some(ScanLabel::new("Office 3F audit"));
+
+        session.save(&path).unwrap();
+        let loaded = Session::load(&path).unwrap().unwrap();
+        assert_eq!(loaded.end, session.end);
+        assert_eq!(loaded.label, session.label);
+        assert_eq!(loaded.results.len(), 1);
+        assert_eq!(loaded.results[0].status, ScanStatus::Online);
+        assert_eq!(loaded.results[0].open_ports, ports);
+
+        Session::discard(&path).unwrap();
+        assert!(Session::load(&path).unwrap().is_none());
+        // Discarding twice is fine.
+        Session::discard(&path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_results_reads_sessions_and_exports() {
+        let mut online = ScanResult::new(Ipv4Addr::new(10, 0, 0, 7));
+        online.status = ScanStatus::Online;
+        online.open_ports = vec![PortInfo::new(443)];
+        let results = vec![online, ScanResult::new(Ipv4Addr::new(10, 0, 0, 2))];
+        let label = ScanLabel::new("DMZ audit");
+
+        let mut session = Session::new(Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 9));
+        session.label = Some(label.clone());
+        session.results = results.clone();
+        let files = [
+            (serde_json::to_string(&session).unwrap(), Some(&label)),
+            (
+                export::render_labeled(&results, OutputFormat::Json, Some(&label)).unwrap(),
+                Some(&label),
+            ),
+            (export::render(&results, OutputFormat::Json).unwrap(), None),
+        ];
+        for (json, expected_label) in files {
+            let saved = parse_results(&json).unwrap();
+            assert_eq!(saved.label.as_ref(), expected_label);
+            let ips: Vec<u8> = saved.results.iter().map(|r| r.ip.octets()[3]).collect();
+            assert_eq!(ips, [2, 7]);
+            assert_eq!(saved.results[1].open_ports[0].port, 443);
+        }
+
+        assert!(parse_results("{\"hosts\": []}").is_err());
+        assert!(parse_results("ip,status\n").is_err());
+    }
+
+    #[test]
+    fn test_result_stream_survives_a_cut_off_line() {
+        let path =
+            std::env::temp_dir().join(format!("ragescanner-stream-{}.ndjson", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut stream = ResultStream::create(&path).unwrap();
+        let mut first = ScanResult::new(Ipv4Addr::new(10, 0, 0, 9));
+        stream.append(&first).unwrap();
+        stream
+            .append(&ScanResult::new(Ipv4Addr::new(10, 0, 0, 3)))
+            .unwrap();
+        // A rescan of .9 appended later wins.
+        first.status = ScanStatus::Online;
+        ResultStream::create(&path).unwrap().append(&first).unwrap();
+        // The process died halfway through the next line.
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"ip\":\"10.0.0.4\",\"hostn").unwrap();
+
+        let saved = load_results(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(saved.label, None);
+        let ips: Vec<u8> = saved.results.iter().map(|r| r.ip.octets()[3]).collect();
+        assert_eq!(ips, [3, 9]);
+        assert_eq!(saved.results[1].status, ScanStatus::Online);
+
+        // A broken line before the end is not a stream.
+        assert!(parse_results("{\"ip\":\"10.0.0.4\"\n{}\n").is_err());
+    }
+}
@@ -0,0 +1,345 @@
+//! User preferences shared by the GUI and TUI.
+//!
+//! Stored as TOML in `%APPDATA%\RageScanner\config.toml` (on Linux and
+//! macOS `~/.config/RageScanner/config.toml`) so the last-used
+//! range and scan tuning survive restarts. Frontends load them at startup,
+//! hand the tuning to the bridge with [`BridgeMessage::SetScanConfig`] and
+//! save again whenever a scan starts or the user edits the settings.
+//!
+//! Edits made to the file while a frontend runs are picked up too: the
+//! frontend asks the bridge to [`watch`](BridgeMessage::WatchSettings) it, and
+//! the bridge tunes later scans with the new values and reports
+//! [`BridgeMessage::ConfigReloaded`].
+//!
+//! [`BridgeMessage::SetScanConfig`]: crate::types::BridgeMessage::SetScanConfig
+//! [`BridgeMessage::WatchSettings`]: crate::types::BridgeMessage::WatchSettings
+//! [`BridgeMessage::ConfigReloaded`]: crate::types::BridgeMessage::ConfigReloaded
+
+use crate::tui::theme::Theme;
+use crate::types::{GError, ScanConfig, ScanProfile};
+use crate::wol::{self, WakeTarget};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Folder under `%APPDATA%` (or the XDG config directory) holding the settings file.
+pub const SETTINGS_DIR: &str = "RageScanner";
+/// Name of the settings file.
+pub const SETTINGS_FILE: &str = "config.toml";
+/// How often a [`SettingsWatcher`] rereads the file.
+pub const RELOAD_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Preferences that persist between runs. Missing keys take their defaults,
+/// so older files keep loading as settings are added.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Target text of the last scan started from a frontend.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_range: Option<String>,
+    /// Time to wait for each ICMP echo reply, in milliseconds.
+    pub ping_timeout_ms: u64,
+    /// TCP connect timeout in milliseconds; absent derives it from each
+    /// host's ping latency.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port_timeout_ms: Option<u64>,
+    /// Hosts probed at once.
+    pub concurrency: usize,
+    /// TCP connections open at once to any one host; absent leaves it to
+    /// the profile's per-host port concurrency.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_host_connections: Option<usize>,
+    /// TCP ports probed on online hosts.
+    pub ports: Vec<u16>,
+    pub resolve_hostnames: bool,
+    pub resolve_vendors: bool,
+    /// Named groups of MAC addresses to wake together, e.g.
+    /// `lab = ["00:11:22:33:44:55", "00:11:22:33:44:66"]`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub wake_groups: BTreeMap<String, Vec<String>>,
+    /// Profile the other scan fields are laid over; see
+    /// [`for_profile`](Settings::for_profile).
+    pub profile: ScanProfile,
+    /// Color scheme of the TUI.
+    pub theme: Theme,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::for_profile(ScanProfile::Standard)
+    }
+}
+
+impl Settings {
+    /// Settings holding the timeouts, concurrency and ports of `profile`.
+    pub fn for_profile(profile: ScanProfile) -> Self {
+        let config = profile.config();
+        Self {
+            last_range: None,
+            ping_timeout_ms: config.ping_timeout.as_millis() as u64,
+            port_timeout_ms: config.port_timeout.map(|t| t.as_millis() as u64),
+            concurrency: config.concurrency,
+            max_host_connections: config.max_host_connections,
+            ports: config.ports,
+            resolve_hostnames: config.resolve_hostnames,
+            resolve_vendors: config.resolve_vendors,
+            wake_groups: BTreeMap::new(),
+            profile,
+            theme: Theme::default(),
+        }
+    }
+
+    /// `%APPDATA%\RageScanner\config.toml`; `None` if `APPDATA` is unset.
+    ///
+    /// Elsewhere `$XDG_CONFIG_HOME/RageScanner/config.toml`, falling back to
+    /// `~/.config`; `None` if neither variable is set.
+    pub fn default_path() -> Option<PathBuf> {
+        let dir = if cfg!(windows) {
+            std::env::var_os("APPDATA").map(PathBuf::from)
+        } else {
+            std::env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| {
+                    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"))
+                })
+        };
+        dir.map(|dir| dir.join(SETTINGS_DIR).join(SETTINGS_FILE))
+    }
+
+    /// Reads settings from `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if the file cannot be read or parsed, or
+    /// fails [`validate`](Settings::validate).
+    pub fn load(path: &Path) -> Result<Self, GError> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| GError::Internal(format!("Failed to read {}: {}", path.display(), e)))?;
+        Self::parse(&text, path)
+    }
+
+    /// Parses and validates settings read from `path`.
+    fn parse(text: &str, path: &Path) -> Result<Self, GError> {
+        let settings: Self = toml::from_str(text)
+            .map_err(|e| GError::Internal(format!("{}: {}", path.display(), e)))?;
+        settings
+            .validate()
+            .map_err(|e| GError::Internal(format!("{}: {}", path.display(), e)))?;
+        Ok(settings)
+    }
+
+    /// Reads `path` if it exists; defaults otherwise.
+    ///
+    /// # Errors
+    ///
+    /// See [`load`](Settings::load).
+    pub fn load_or_default(path: &Path) -> Result<Self, GError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::load(path)
+    }
+
+    /// Writes the settings to `path`, creating its folder if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if serialization or any file operation fails.
+    pub fn save(&self, path: &Path) -> Result<(), GError> {
+        let text = toml::to_string_pretty(self)
+            .map_err(|e| GError::Internal(format!("Failed to serialize settings: {}", e)))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| {
+                GError::Internal(format!("Failed to create {}: {}", dir.display(), e))
+            })?;
+        }
+        fs::write(path, text)
+            .map_err(|e| GError::Internal(format!("Failed to write {}: {}", path.display(), e)))
+    }
+
+    /// Checks the values a scan cannot run with.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.ping_timeout_ms == 0 {
+            return Err("ping_timeout_ms must be at least 1".to_string());
+        }
+        if self.port_timeout_ms == Some(0) {
+            return Err("port_timeout_ms must be at least 1".to_string());
+        }
+        if self.concurrency == 0 {
+            return Err("concurrency must be at least 1".to_string());
+        }
+        if self.max_host_connections == Some(0) {
+            return Err("max_host_connections must be at least 1".to_string());
+        }
+        for (name, macs) in &self.wake_groups {
+            for mac in macs {
+                wol::parse_mac(mac)
+                    .map_err(|_| format!("wake_groups.{}: invalid MAC address {:?}", name, mac))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Wake targets of the named group; addresses are left to be looked up.
+    pub fn wake_group(&self, name: &str) -> Option<Vec<WakeTarget>> {
+        let macs = self.wake_groups.get(name)?;
+        Some(
+            macs.iter()
+                .map(|mac| WakeTarget {
+                    mac: mac.clone(),
+                    ip: None,
+                })
+                .collect(),
+        )
+    }
+
+    /// The profile's scan config with these settings applied.
+    pub fn scan_config(&self) -> ScanConfig {
+        ScanConfig {
+            ping_timeout: Duration::from_millis(self.ping_timeout_ms),
+            port_timeout: self.port_timeout_ms.map(Duration::from_millis),
+            concurrency: self.concurrency,
+            max_host_connections: self.max_host_connections,
+            ports: self.ports.clone(),
+            resolve_hostnames: self.resolve_hostnames,
+            resolve_vendors: self.resolve_vendors,
+            ..self.profile.config()
+        }
+    }
+}
+
+/// Notices edits to a settings file by rereading it.
+///
+/// The file is small, so comparing its text on every
+/// [`RELOAD_POLL_INTERVAL`] is cheap and catches edits a modification time
+/// with coarse resolution would miss.
+pub struct SettingsWatcher {
+    path: PathBuf,
+    last: Option<String>,
+}
+
+impl SettingsWatcher {
+    /// Starts watching `path`; its current contents count as seen.
+    pub fn new(path: PathBuf) -> Self {
+        let last = fs::read_to_string(&path).ok();
+        Self { path, last }
+    }
+
+    /// The file being watched.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Rereads the file. Returns the new settings, or why they cannot be
+    /// used, if the text changed since the last call; `None` if it did not
+    /// or the file is gone.
+    pub fn poll(&mut self) -> Option<Result<Settings, GError>> {
+        let text = fs::read_to_string(&self.path).ok()?;
+        if self.last.as_ref() == Some(&text) {
+            return None;
+        }
+        let settings = Settings::parse(&text, &self.path);
+        self.last = Some(text);
+        Some(settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_match_scan_config() {
+        assert_eq!(Settings::default().scan_config(), ScanConfig::default());
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let path = std::env::temp_dir()
+            .join(format!("ragescanner-settings-{}", std::process::id()))
+            .join(SETTINGS_FILE);
+        let settings = Settings {
+            last_range: Some("10.0.0.1-50".to_string()),
+            ping_timeout_ms: 250,
+            port_timeout_ms: Some(400),
+            concurrency: 16,
+            max_host_connections: Some(2),
+            ports: vec![22, 443],
+            resolve_hostnames: false,
+            resolve_vendors: true,
+            wake_groups: BTreeMap::from([(
+                "lab".to_string(),
+                vec!["00:11:22:33:44:55".to_string()],
+            )]),
+            profile: ScanProfile::Safe,
+            theme: Theme::HighContrast,
+        };
+        settings.save(&path).unwrap();
+        assert_eq!(Settings::load_or_default(&path).unwrap(), settings);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+
+        assert_eq!(
+            Settings::load_or_default(&path).unwrap(),
+            Settings::default()
+        );
+    }
+
+    #[test]
+    fn test_watcher_reports_each_edit_once() {
+        let path = std::env::temp_dir().join(format!(
+            "ragescanner-settings-watch-{}.toml",
+            std::process::id()
+        ));
+        Settings::default().save(&path).unwrap();
+        let mut watcher = SettingsWatcher::new(path.clone());
+        assert!(watcher.poll().is_none());
+
+        let edited = Settings {
+            ports: vec![8080],
+            ..Settings::default()
+        };
+        edited.save(&path).unwrap();
+        assert_eq!(watcher.poll().unwrap().unwrap(), edited);
+        assert!(watcher.poll().is_none());
+
+        fs::write(&path, "concurrency = 0").unwrap();
+        assert!(watcher.poll().unwrap().is_err());
+        let _ = fs::remove_file(&path);
+        assert!(watcher.poll().is_none());
+    }
+
+    #[test]
+    fn test_profile_settings_keep_the_profile_config() {
+        let safe = Settings::for_profile(ScanProfile::Safe);
+        assert_eq!(safe.scan_config(), ScanConfig::safe_mode());
+        let edited = Settings {
+            concurrency: 2,
+            ..safe
+        };
+        assert_eq!(
+            edited.scan_config().max_rate,
+            ScanConfig::safe_mode().max_rate
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_values() {
+        let settings = Settings {
+            concurrency: 0,
+            ..Settings::default()
+        };
+        assert!(settings.validate().is_err());
+        let settings = Settings {
+            port_timeout_ms: Some(0),
+            ..Settings::default()
+        };
+        assert!(settings.validate().is_err());
+        let settings = Settings {
+            wake_groups: BTreeMap::from([("lab".to_string(), vec!["00:11:22".to_string()])]),
+            ..Settings::default()
+        };
+        assert!(settings.validate().is_err());
+    }
+}
@@ -0,0 +1,535 @@
+//! SMB host information and the SMBv1 exposure audit.
+//!
+//! SMBv1 is the protocol WannaCry and NotPetya spread through, and Microsoft
+//! has deprecated it for years, yet old NAS boxes and printers still accept
+//! it. [`probe_smb1`] sends an SMB1 `NEGOTIATE` offering only the
+//! `NT LM 0.12` dialect: a server that picks it still speaks SMBv1, while
+//! one that has it disabled drops the connection or answers in SMB2.
+//!
+//! [`host_info`] negotiates SMB2/3 and starts an anonymous NTLM session
+//! setup: the server's NTLM challenge names the computer and its domain and
+//! carries the Windows version, without any credentials being sent. Servers
+//! that only speak SMBv1 name themselves in the `NEGOTIATE` response instead.
+
+use crate::types::SmbInfo;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// SMB over TCP ("direct hosting").
+pub const SMB_PORT: u16 = 445;
+
+/// Budget for connecting, negotiating and reading the reply.
+pub const SMB_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The only dialect offered; every SMBv1 server since NT 4 speaks it.
+const NT_LM_DIALECT: &[u8] = b"NT LM 0.12";
+
+const SMB1_MAGIC: &[u8; 4] = b"\xFFSMB";
+const SMB_COM_NEGOTIATE: u8 = 0x72;
+/// Offset of `WordCount` in an SMB1 message: right after the 32-byte header.
+const SMB1_HEADER_LEN: usize = 32;
+/// A negotiate response is a few hundred bytes; anything bigger is not one.
+const MAX_RESPONSE: usize = 4096;
+
+/// [`SmbInfo::dialect`] of a server that only negotiated `NT LM 0.12`.
+pub const SMB1_DIALECT: &str = "1";
+
+const SMB2_MAGIC: &[u8; 4] = b"\xFESMB";
+const SMB2_HEADER_LEN: usize = 64;
+const SMB2_NEGOTIATE: u16 = 0;
+const SMB2_SESSION_SETUP: u16 = 1;
+/// SMB 2.0.2 through 3.1.1; 3.1.1 needs the pre-authentication context.
+const SMB2_DIALECTS: [u16; 5] = [0x0202, 0x0210, 0x0300, 0x0302, 0x0311];
+/// `STATUS_MORE_PROCESSING_REQUIRED`, the status a session setup answering
+/// with a challenge carries.
+const STATUS_MORE_PROCESSING: u32 = 0xC000_0016;
+
+const NTLMSSP_SIGNATURE: &[u8; 8] = b"NTLMSSP\0";
+/// Unicode, request target, sign, NTLM, always sign, extended session
+/// security, target info, version, 128-bit, key exchange, 56-bit.
+const NTLM_NEGOTIATE_FLAGS: u32 = 0xE288_8215;
+const NTLM_NEGOTIATE_VERSION: u32 = 0x0200_0000;
+/// `AV_PAIR` ids in the challenge's target info.
+const MSV_AV_EOL: u16 = 0;
+const MSV_AV_NB_COMPUTER_NAME: u16 = 1;
+const MSV_AV_NB_DOMAIN_NAME: u16 = 2;
+/// SPNEGO and NTLMSSP object identifiers, DER-encoded.
+const SPNEGO_OID: &[u8] = &[0x06, 0x06, 0x2B, 0x06, 0x01, 0x05, 0x05, 0x02];
+const NTLMSSP_OID: &[u8] = &[
+    0x06, 0x0A, 0x2B, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x02, 0x02, 0x0A,
+];
+
+/// Asks the SMB server on `ip` for its name, domain, OS version and dialect.
+///
+/// Tries SMB2/3 first and falls back to SMBv1 when the server does not
+/// answer SMB2; each attempt has [`SMB_TIMEOUT`]. Returns `None` if port 445
+/// cannot be reached or the server speaks neither.
+pub async fn host_info(ip: Ipv4Addr) -> Option<SmbInfo> {
+    let smb2 = async {
+        let mut stream = TcpStream::connect((ip, SMB_PORT)).await.ok()?;
+        let negotiated = transact(&mut stream, &smb2_negotiate_request()).await;
+        let Some(dialect) = negotiated.as_deref().and_then(smb2_dialect) else {
+            return Some(None);
+        };
+        let mut info = SmbInfo {
+            dialect: dialect_name(dialect),
+            hostname: None,
+            domain: None,
+            os_version: None,
+        };
+        if let Some(reply) = transact(&mut stream, &smb2_session_setup_request()).await {
+            read_ntlm_challenge(&reply, &mut info);
+        }
+        Some(Some(info))
+    };
+    match tokio::time::timeout(SMB_TIMEOUT, smb2).await {
+        Ok(Some(Some(info))) => return Some(info),
+        Ok(None) => return None,
+        // Silence or a dropped connection; an SMBv1-only server does either.
+        Ok(Some(None)) | Err(_) => {}
+    }
+    let smb1 = async {
+        let mut stream = TcpStream::connect((ip, SMB_PORT)).await.ok()?;
+        let message = transact(&mut stream, &negotiate_request()).await?;
+        accepts_smb1(&message).then(|| smb1_info(&message))
+    };
+    tokio::time::timeout(SMB_TIMEOUT, smb1).await.ok().flatten()
+}
+
+/// Asks the SMB server on `ip` to negotiate SMBv1.
+///
+/// Returns `Some(true)` if it accepts, `Some(false)` if it answers otherwise
+/// or closes the connection, and `None` if it cannot be reached within
+/// [`SMB_TIMEOUT`].
+pub async fn probe_smb1(ip: Ipv4Addr) -> Option<bool> {
+    let exchange = async {
+        let mut stream = TcpStream::connect((ip, SMB_PORT)).await.ok()?;
+        let reply = transact(&mut stream, &negotiate_request()).await;
+        Some(reply.is_some_and(|message| accepts_smb1(&message)))
+    };
+    tokio::time::timeout(SMB_TIMEOUT, exchange)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Sends a framed `request` and reads one reply, without its session header.
+async fn transact(stream: &mut TcpStream, request: &[u8]) -> Option<Vec<u8>> {
+    stream.write_all(request).await.ok()?;
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).await.ok()?;
+    let len = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+    let mut message = vec![0u8; len.min(MAX_RESPONSE)];
+    stream.read_exact(&mut message).await.ok()?;
+    Some(message)
+}
+
+/// Prepends the 4-byte session header SMB uses over TCP.
+fn frame(message: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0, 0, 0, 0];
+    frame[1..].copy_from_slice(&(message.len() as u32).to_be_bytes()[1..]);
+    frame.extend_from_slice(message);
+    frame
+}
+
+/// Builds an SMB1 `NEGOTIATE` offering only [`NT_LM_DIALECT`], framed with
+/// the 4-byte session header SMB uses over TCP.
+pub fn negotiate_request() -> Vec<u8> {
+    let mut smb = Vec::with_capacity(64);
+    smb.extend_from_slice(SMB1_MAGIC);
+    smb.push(SMB_COM_NEGOTIATE);
+    smb.extend_from_slice(&[0; 4]); // Status
+    smb.push(0x18); // Flags: canonicalized, case-insensitive paths
+    smb.extend_from_slice(&0xC001u16.to_le_bytes()); // Flags2: Unicode, NT status, long names
+    smb.extend_from_slice(&[0; 12]); // PIDHigh, SecurityFeatures, Reserved
+    smb.extend_from_slice(&[0; 2]); // TID
+    smb.extend_from_slice(&0xFEFFu16.to_le_bytes()); // PIDLow
+    smb.extend_from_slice(&[0; 4]); // UID, MID
+    smb.push(0); // WordCount
+    let dialects = [&[0x02], NT_LM_DIALECT, &[0]].concat();
+    smb.extend_from_slice(&(dialects.len() as u16).to_le_bytes());
+    smb.extend_from_slice(&dialects);
+    frame(&smb)
+}
+
+/// Returns `true` if `message` (without the session header) is a successful
+/// SMB1 negotiate response that picked the offered dialect.
+pub fn accepts_smb1(message: &[u8]) -> bool {
+    if message.len() < SMB1_HEADER_LEN + 3
+        || &message[..4] != SMB1_MAGIC
+        || message[4] != SMB_COM_NEGOTIATE
+        || message[5..9] != [0; 4]
+    {
+        return false;
+    }
+    let words = &message[SMB1_HEADER_LEN..];
+    // 0xFFFF means none of the offered dialects is acceptable.
+    words[0] > 0 && u16::from_le_bytes([words[1], words[2]]) == 0
+}
+
+/// Reads the domain and server name from an SMB1 negotiate response.
+///
+/// They follow the challenge in a response without extended security, which
+/// is what [`negotiate_request`] asks for.
+pub fn smb1_info(message: &[u8]) -> SmbInfo {
+    let mut info = SmbInfo {
+        dialect: SMB1_DIALECT.to_string(),
+        hostname: None,
+        domain: None,
+        os_version: None,
+    };
+    let words = &message[SMB1_HEADER_LEN.min(message.len())..];
+    // 17 words, ByteCount, then the challenge and both names.
+    if words.first() != Some(&17) || words.len() < 37 {
+        return info;
+    }
+    let challenge_len = words[34] as usize;
+    let Some(data) = words.get(37 + challenge_len..) else {
+        return info;
+    };
+    let unicode = u16::from_le_bytes([message[10], message[11]]) & 0x8000 != 0;
+    let mut names = if unicode {
+        let units: Vec<u16> = data
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        units
+            .split(|&unit| unit == 0)
+            .map(String::from_utf16_lossy)
+            .collect::<Vec<_>>()
+    } else {
+        data.split(|&byte| byte == 0)
+            .map(|name| String::from_utf8_lossy(name).into_owned())
+            .collect()
+    }
+    .into_iter()
+    .map(|name| Some(name).filter(|name| !name.is_empty()));
+    info.domain = names.next().flatten();
+    info.hostname = names.next().flatten();
+    info
+}
+
+/// Builds an SMB2 header for `command` with the given message id.
+fn smb2_header(command: u16, message_id: u64) -> Vec<u8> {
+    let mut header = Vec::with_capacity(SMB2_HEADER_LEN);
+    header.extend_from_slice(SMB2_MAGIC);
+    header.extend_from_slice(&(SMB2_HEADER_LEN as u16).to_le_bytes());
+    header.extend_from_slice(&[0; 2]); // CreditCharge
+    header.extend_from_slice(&[0; 4]); // Status
+    header.extend_from_slice(&command.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes()); // CreditRequest
+    header.extend_from_slice(&[0; 8]); // Flags, NextCommand
+    header.extend_from_slice(&message_id.to_le_bytes());
+    header.extend_from_slice(&[0; 32]); // ProcessId, TreeId, SessionId, Signature
+    header
+}
+
+/// Builds an SMB2 `NEGOTIATE` offering [`SMB2_DIALECTS`], with the
+/// pre-authentication integrity context 3.1.1 requires.
+pub fn smb2_negotiate_request() -> Vec<u8> {
+    let mut smb = smb2_header(SMB2_NEGOTIATE, 0);
+    smb.extend_from_slice(&36u16.to_le_bytes()); // StructureSize
+    smb.extend_from_slice(&(SMB2_DIALECTS.len() as u16).to_le_bytes());
+    smb.extend_from_slice(&1u16.to_le_bytes()); // SecurityMode: signing enabled
+    smb.extend_from_slice(&[0; 2]); // Reserved
+    smb.extend_from_slice(&[0; 4]); // Capabilities
+    smb.extend_from_slice(&[0x52; 16]); // ClientGuid
+    let context_offset = (SMB2_HEADER_LEN + 36 + 2 * SMB2_DIALECTS.len()).next_multiple_of(8);
+    smb.extend_from_slice(&(context_offset as u32).to_le_bytes());
+    smb.extend_from_slice(&1u16.to_le_bytes()); // NegotiateContextCount
+    smb.extend_from_slice(&[0; 2]); // Reserved2
+    for dialect in SMB2_DIALECTS {
+        smb.extend_from_slice(&dialect.to_le_bytes());
+    }
+    smb.resize(context_offset, 0);
+    smb.extend_from_slice(&1u16.to_le_bytes()); // SMB2_PREAUTH_INTEGRITY_CAPABILITIES
+    smb.extend_from_slice(&38u16.to_le_bytes()); // DataLength
+    smb.extend_from_slice(&[0; 4]); // Reserved
+    smb.extend_from_slice(&1u16.to_le_bytes()); // HashAlgorithmCount
+    smb.extend_from_slice(&32u16.to_le_bytes()); // SaltLength
+    smb.extend_from_slice(&1u16.to_le_bytes()); // SHA-512
+    smb.extend_from_slice(&[0x52; 32]); // Salt
+    frame(&smb)
+}
+
+/// Returns the dialect a successful SMB2 negotiate response picked.
+pub fn smb2_dialect(message: &[u8]) -> Option<u16> {
+    if message.len() < SMB2_HEADER_LEN + 6
+        || &message[..4] != SMB2_MAGIC
+        || message[8..12] != [0; 4]
+        || u16::from_le_bytes([message[12], message[13]]) != SMB2_NEGOTIATE
+    {
+        return None;
+    }
+    let body = &message[SMB2_HEADER_LEN..];
+    Some(u16::from_le_bytes([body[4], body[5]]))
+}
+
+/// Formats an SMB2 dialect revision the way Microsoft names it.
+pub fn dialect_name(dialect: u16) -> String {
+    match dialect {
+        0x0202 => "2.0.2".to_string(),
+        0x0210 => "2.1".to_string(),
+        0x0300 => "3.0".to_string(),
+        0x0302 => "3.0.2".to_string(),
+        0x0311 => "3.1.1".to_string(),
+        other => format!("0x{:04X}", other),
+    }
+}
+
+/// Builds an SMB2 `SESSION_SETUP` carrying an NTLM `NEGOTIATE` in SPNEGO.
+///
+/// No credentials are involved: the server answers with its challenge and
+/// the scanner hangs up.
+pub fn smb2_session_setup_request() -> Vec<u8> {
+    let mut ntlm = Vec::with_capacity(40);
+    ntlm.extend_from_slice(NTLMSSP_SIGNATURE);
+    ntlm.extend_from_slice(&1u32.to_le_bytes()); // NEGOTIATE_MESSAGE
+    ntlm.extend_from_slice(&NTLM_NEGOTIATE_FLAGS.to_le_bytes());
+    ntlm.extend_from_slice(&[0; 16]); // DomainNameFields, WorkstationFields
+    ntlm.extend_from_slice(&[10, 0, 0, 0, 0, 0, 0, 15]); // Version 10.0, NTLM revision 15
+
+    let mech_types = der(0xA0, &der(0x30, NTLMSSP_OID));
+    let mech_token = der(0xA2, &der(0x04, &ntlm));
+    let init = der(0xA0, &der(0x30, &[mech_types, mech_token].concat()));
+    let token = der(0x60, &[SPNEGO_OID, &init].concat());
+
+    let mut smb = smb2_header(SMB2_SESSION_SETUP, 1);
+    smb.extend_from_slice(&25u16.to_le_bytes()); // StructureSize
+    smb.push(0); // Flags
+    smb.push(1); // SecurityMode: signing enabled
+    smb.extend_from_slice(&[0; 8]); // Capabilities, Channel
+    smb.extend_from_slice(&((SMB2_HEADER_LEN + 24) as u16).to_le_bytes());
+    smb.extend_from_slice(&(token.len() as u16).to_le_bytes());
+    smb.extend_from_slice(&[0; 8]); // PreviousSessionId
+    smb.extend_from_slice(&token);
+    frame(&smb)
+}
+
+/// DER-encodes one tag-length-value element.
+fn der(tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    match value.len() {
+        len @ 0..0x80 => out.push(len as u8),
+        len @ 0x80..0x100 => out.extend_from_slice(&[0x81, len as u8]),
+        len => {
+            out.push(0x82);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+    }
+    out.extend_from_slice(value);
+    out
+}
+
+/// Fills `info` from the NTLM challenge in an SMB2 session setup response.
+///
+/// The challenge is found by its signature, whether or not the server wrapped
+/// it in SPNEGO. Leaves `info` alone if there is none.
+pub fn read_ntlm_challenge(message: &[u8], info: &mut SmbInfo) {
+    if message.len() < SMB2_HEADER_LEN
+        || &message[..4] != SMB2_MAGIC
+        || u32::from_le_bytes([message[8], message[9], message[10], message[11]])
+            != STATUS_MORE_PROCESSING
+    {
+        return;
+    }
+    let Some(start) = message
+        .windows(NTLMSSP_SIGNATURE.len())
+        .position(|window| window == NTLMSSP_SIGNATURE)
+    else {
+        return;
+    };
+    let challenge = &message[start..];
+    let u16_at = |at: usize| u16::from_le_bytes([challenge[at], challenge[at + 1]]);
+    let u32_at = |at: usize| u32::from_le_bytes(challenge[at..at + 4].try_into().unwrap());
+    if challenge.len() < 48 || u32_at(8) != 2 {
+        return;
+    }
+    let info_len = u16_at(40) as usize;
+    let info_offset = u32_at(44) as usize;
+    if u32_at(20) & NTLM_NEGOTIATE_VERSION != 0 && info_offset >= 56 && challenge.len() >= 56 {
+        let (major, minor, build) = (challenge[48], challenge[49], u16_at(50));
+        // Samba reports a fixed 6.1 with build 0, which says nothing.
+        if build != 0 {
+            info.os_version = Some(format!("{}.{}.{}", major, minor, build));
+        }
+    }
+    let Some(mut pairs) = challenge.get(info_offset..info_offset + info_len) else {
+        return;
+    };
+    while pairs.len() >= 4 {
+        let id = u16::from_le_bytes([pairs[0], pairs[1]]);
+        let len = u16::from_le_bytes([pairs[2], pairs[3]]) as usize;
+        let Some(value) = pairs.get(4..4 + len) else {
+            break;
+        };
+        let text = || {
+            let units: Vec<u16> = value
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect();
+            Some(String::from_utf16_lossy(&units)).filter(|name| !name.is_empty())
+        };
+        match id {
+            MSV_AV_EOL => break,
+            MSV_AV_NB_COMPUTER_NAME => info.hostname = text(),
+            MSV_AV_NB_DOMAIN_NAME => info.domain = text(),
+            _ => {}
+        }
+        pairs = &pairs[4 + len..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: [u8; 4], dialect: u16) -> Vec<u8> {
+        let mut message = negotiate_request()[4..SMB1_HEADER_LEN + 4].to_vec();
+        message[5..9].copy_from_slice(&status);
+        message.push(17); // WordCount of an NT LM 0.12 response
+        message.extend_from_slice(&dialect.to_le_bytes());
+        message.extend_from_slice(&[0; 32]);
+        message
+    }
+
+    #[test]
+    fn test_negotiate_request_framing() {
+        let request = negotiate_request();
+        let len = u32::from_be_bytes([0, request[1], request[2], request[3]]) as usize;
+        assert_eq!(len, request.len() - 4);
+        assert_eq!(&request[4..8], SMB1_MAGIC);
+        assert_eq!(request[8], SMB_COM_NEGOTIATE);
+        assert!(request.ends_with(b"\x02NT LM 0.12\0"));
+    }
+
+    #[test]
+    fn test_accepts_smb1_only_for_successful_negotiation() {
+        assert!(accepts_smb1(&response([0; 4], 0)));
+        assert!(!accepts_smb1(&response([0; 4], 0xFFFF)));
+        // STATUS_NOT_SUPPORTED
+        assert!(!accepts_smb1(&response([0xBB, 0, 0, 0xC0], 0)));
+        let mut smb2 = response([0; 4], 0);
+        smb2[0] = 0xFE;
+        assert!(!accepts_smb1(&smb2));
+        assert!(!accepts_smb1(&[]));
+    }
+
+    #[test]
+    fn test_smb1_info_reads_the_names_after_the_challenge() {
+        let mut message = response([0; 4], 0);
+        message[10..12].copy_from_slice(&0xC001u16.to_le_bytes());
+        message[SMB1_HEADER_LEN + 34] = 8; // ChallengeLength
+        let names: Vec<u8> = "WORKGROUP\0OLDNAS\0"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        message.truncate(SMB1_HEADER_LEN + 35);
+        message.extend_from_slice(&((8 + names.len()) as u16).to_le_bytes());
+        message.extend_from_slice(&[0xAA; 8]);
+        message.extend_from_slice(&names);
+
+        let info = smb1_info(&message);
+        assert!(info.smb1_only());
+        assert_eq!(info.domain.as_deref(), Some("WORKGROUP"));
+        assert_eq!(info.hostname.as_deref(), Some("OLDNAS"));
+        assert_eq!(info.to_string(), "SMB 1, WORKGROUP\\OLDNAS");
+        // A truncated response still yields the dialect.
+        assert_eq!(smb1_info(&message[..40]).hostname, None);
+    }
+
+    #[test]
+    fn test_smb2_requests() {
+        let request = smb2_negotiate_request();
+        let len = u32::from_be_bytes([0, request[1], request[2], request[3]]) as usize;
+        assert_eq!(len, request.len() - 4);
+        let smb = &request[4..];
+        assert_eq!(&smb[..4], SMB2_MAGIC);
+        let body = &smb[SMB2_HEADER_LEN..];
+        assert_eq!(u16::from_le_bytes([body[2], body[3]]), 5);
+        let offset = u32::from_le_bytes(body[28..32].try_into().unwrap()) as usize;
+        assert_eq!(offset % 8, 0);
+        assert_eq!(&smb[offset..offset + 2], &[1, 0]);
+        assert_eq!(smb.len(), offset + 8 + 38);
+
+        let mut reply = smb[..SMB2_HEADER_LEN].to_vec();
+        reply.extend_from_slice(&[65, 0, 1, 0, 0x11, 0x03]);
+        assert_eq!(
+            smb2_dialect(&reply).map(dialect_name).as_deref(),
+            Some("3.1.1")
+        );
+        reply[8] = 0x22; // STATUS_NOT_SUPPORTED and the like
+        assert_eq!(smb2_dialect(&reply), None);
+        assert_eq!(smb2_dialect(&response([0; 4], 0)), None);
+        assert_eq!(dialect_name(0x02FF), "0x02FF");
+
+        let request = smb2_session_setup_request();
+        let smb = &request[4..];
+        let body = &smb[SMB2_HEADER_LEN..];
+        let offset = u16::from_le_bytes([body[12], body[13]]) as usize;
+        let len = u16::from_le_bytes([body[14], body[15]]) as usize;
+        assert_eq!(offset + len, smb.len());
+        let token = &smb[offset..];
+        assert_eq!(token[0], 0x60);
+        assert_eq!(token[1] as usize, token.len() - 2);
+        assert!(token.windows(8).any(|w| w == NTLMSSP_SIGNATURE));
+        assert_eq!(der(0x04, &[0; 300])[..4], [0x04, 0x82, 0x01, 0x2C]);
+    }
+
+    #[test]
+    fn test_ntlm_challenge_names_the_host() {
+        let av = |id: u16, text: &str| {
+            let value: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+            [
+                &id.to_le_bytes()[..],
+                &(value.len() as u16).to_le_bytes(),
+                &value,
+            ]
+            .concat()
+        };
+        let target_info = [
+            av(MSV_AV_NB_DOMAIN_NAME, "CORP"),
+            av(MSV_AV_NB_COMPUTER_NAME, "FILESRV"),
+            av(3, "filesrv.corp.example"),
+            av(MSV_AV_EOL, ""),
+        ]
+        .concat();
+        let mut challenge = NTLMSSP_SIGNATURE.to_vec();
+        challenge.extend_from_slice(&2u32.to_le_bytes());
+        challenge.extend_from_slice(&[0; 8]); // TargetNameFields
+        challenge.extend_from_slice(&NTLM_NEGOTIATE_FLAGS.to_le_bytes());
+        challenge.extend_from_slice(&[0; 16]); // ServerChallenge, Reserved
+        challenge.extend_from_slice(&(target_info.len() as u16).to_le_bytes());
+        challenge.extend_from_slice(&(target_info.len() as u16).to_le_bytes());
+        challenge.extend_from_slice(&56u32.to_le_bytes());
+        challenge.extend_from_slice(&[10, 0, 0x61, 0x4A, 0, 0, 0, 15]); // 10.0.19041
+        challenge.extend_from_slice(&target_info);
+
+        let mut reply = smb2_header(SMB2_SESSION_SETUP, 1);
+        reply[8..12].copy_from_slice(&STATUS_MORE_PROCESSING.to_le_bytes());
+        reply.extend_from_slice(&[9, 0, 0, 0, 0x48, 0, 0, 0]);
+        reply.extend_from_slice(&der(0xA1, &der(0x04, &challenge)));
+
+        let mut info = SmbInfo {
+            dialect: "3.1.1".to_string(),
+            hostname: None,
+            domain: None,
+            os_version: None,
+        };
+        read_ntlm_challenge(&reply, &mut info);
+        assert!(!info.smb1_only());
+        assert_eq!(
+            info.to_string(),
+            "SMB 3.1.1, CORP\\FILESRV, Windows 10.0.19041"
+        );
+
+        // Samba's placeholder version is dropped.
+        let version = reply.len() - target_info.len() - 8;
+        reply[version + 2..version + 4].copy_from_slice(&[0, 0]);
+        info.os_version = None;
+        read_ntlm_challenge(&reply, &mut info);
+        assert_eq!(info.os_version, None);
+    }
+}
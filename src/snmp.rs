@@ -0,0 +1,321 @@
+//! SNMP system group probe.
+//!
+//! Managed switches, printers and UPSes usually run an SNMP agent that
+//! describes itself far better than its open ports do. [`query_system`]
+//! sends one `GetRequest` for `sysDescr.0`, `sysUpTime.0` and `sysName.0`,
+//! first as SNMPv2c and then as SNMPv1 for agents that only speak v1.
+
+use crate::names::escape_name;
+use crate::types::SnmpInfo;
+use std::net::{Ipv4Addr, UdpSocket};
+use std::time::Duration;
+
+/// How long each SNMP version waits for the agent to answer.
+pub const SNMP_TIMEOUT: Duration = Duration::from_millis(500);
+/// Community used when none is configured.
+pub const DEFAULT_COMMUNITY: &str = "public";
+
+pub const SNMP_PORT: u16 = 161;
+
+const VERSION_1: u8 = 0;
+const VERSION_2C: u8 = 1;
+
+/// BER tags used in requests and responses.
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_NULL: u8 = 0x05;
+const TAG_OID: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_TIMETICKS: u8 = 0x43;
+const TAG_GET_REQUEST: u8 = 0xA0;
+const TAG_GET_RESPONSE: u8 = 0xA2;
+
+/// `1.3.6.1.2.1.1.{1,3,5}.0`, BER-encoded.
+const SYS_DESCR: &[u8] = &[0x2B, 6, 1, 2, 1, 1, 1, 0];
+const SYS_UPTIME: &[u8] = &[0x2B, 6, 1, 2, 1, 1, 3, 0];
+const SYS_NAME: &[u8] = &[0x2B, 6, 1, 2, 1, 1, 5, 0];
+
+/// Asks the agent on `ip` for its system group using `community`.
+///
+/// Returns `None` if the agent does not answer either version, rejects the
+/// community (agents stay silent) or reports an error.
+pub fn query_system(ip: Ipv4Addr, community: &str, timeout: Duration) -> Option<SnmpInfo> {
+    let id = request_id(ip);
+    [VERSION_2C, VERSION_1].into_iter().find_map(|version| {
+        let reply = exchange(ip, &get_request(version, community, id), timeout)?;
+        parse_response(&reply, id)
+    })
+}
+
+fn exchange(ip: Ipv4Addr, request: &[u8], timeout: Duration) -> Option<Vec<u8>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.set_read_timeout(Some(timeout)).ok()?;
+    socket.connect((ip, SNMP_PORT)).ok()?;
+    socket.send(request).ok()?;
+    let mut buf = [0u8; 1500];
+    let len = socket.recv(&mut buf).ok()?;
+    Some(buf[..len].to_vec())
+}
+
+/// Request ID derived from the address, so concurrent queries differ.
+fn request_id(ip: Ipv4Addr) -> u16 {
+    let o = ip.octets();
+    u16::from_be_bytes([o[2], o[3]]) ^ 0x5253
+}
+
+/// Encodes one TLV with a definite-form length.
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    match content.len() {
+        len @ 0..=0x7F => out.push(len as u8),
+        len @ 0x80..=0xFF => out.extend_from_slice(&[0x81, len as u8]),
+        len => {
+            out.push(0x82);
+            out.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+fn get_request(version: u8, community: &str, id: u16) -> Vec<u8> {
+    let varbinds: Vec<u8> = [SYS_DESCR, SYS_UPTIME, SYS_NAME]
+        .iter()
+        .flat_map(|oid| {
+            tlv(
+                TAG_SEQUENCE,
+                &[tlv(TAG_OID, oid), tlv(TAG_NULL, &[])].concat(),
+            )
+        })
+        .collect();
+    let pdu = [
+        // Padded to four bytes so the high bit never makes it negative.
+        tlv(TAG_INTEGER, &u32::from(id).to_be_bytes()),
+        // Error status and index.
+        tlv(TAG_INTEGER, &[0]),
+        tlv(TAG_INTEGER, &[0]),
+        tlv(TAG_SEQUENCE, &varbinds),
+    ]
+    .concat();
+    let message = [
+        tlv(TAG_INTEGER, &[version]),
+        tlv(TAG_OCTET_STRING, community.as_bytes()),
+        tlv(TAG_GET_REQUEST, &pdu),
+    ]
+    .concat();
+    tlv(TAG_SEQUENCE, &message)
+}
+
+/// Reads one TLV at the start of `buf`, returning its tag, content and the rest.
+fn read_tlv(buf: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = buf.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (len, rest) = match first {
+        0..=0x7F => (usize::from(first), rest),
+        0x81 => (usize::from(*rest.first()?), &rest[1..]),
+        0x82 => (
+            usize::from(u16::from_be_bytes([*rest.first()?, *rest.get(1)?])),
+            rest.get(2..)?,
+        ),
+        _ => return None,
+    };
+    (rest.len() >= len).then(|| (tag, &rest[..len], &rest[len..]))
+}
+
+/// Decodes a non-negative BER integer of at most eight bytes.
+fn read_uint(content: &[u8]) -> Option<u64> {
+    if content.len() > 8 {
+        return None;
+    }
+    Some(content.iter().fold(0, |n, &b| (n << 8) | u64::from(b)))
+}
+
+/// Parses a `GetResponse` to request `id` into the fields it carries.
+fn parse_response(reply: &[u8], id: u16) -> Option<SnmpInfo> {
+    let (TAG_SEQUENCE, message, _) = read_tlv(reply)? else {
+        return None;
+    };
+    let (TAG_INTEGER, _version, rest) = read_tlv(message)? else {
+        return None;
+    };
+    let (TAG_OCTET_STRING, _community, rest) = read_tlv(rest)? else {
+        return None;
+    };
+    let (TAG_GET_RESPONSE, pdu, _) = read_tlv(rest)? else {
+        return None;
+    };
+    let (TAG_INTEGER, reply_id, rest) = read_tlv(pdu)? else {
+        return None;
+    };
+    let (TAG_INTEGER, error_status, rest) = read_tlv(rest)? else {
+        return None;
+    };
+    if read_uint(reply_id)? != u64::from(id) || read_uint(error_status)? != 0 {
+        return None;
+    }
+    let (_, _error_index, rest) = read_tlv(rest)?;
+    let (TAG_SEQUENCE, mut varbinds, _) = read_tlv(rest)? else {
+        return None;
+    };
+
+    let mut info = SnmpInfo::default();
+    while !varbinds.is_empty() {
+        let (TAG_SEQUENCE, varbind, rest) = read_tlv(varbinds)? else {
+            return None;
+        };
+        varbinds = rest;
+        let (TAG_OID, oid, value) = read_tlv(varbind)? else {
+            return None;
+        };
+        // v2c agents answer missing objects with an exception value instead.
+        let (tag, value, _) = read_tlv(value)?;
+        match (oid, tag) {
+            (SYS_DESCR, TAG_OCTET_STRING) => info.description = text(value),
+            (SYS_NAME, TAG_OCTET_STRING) => info.name = text(value),
+            (SYS_UPTIME, TAG_TIMETICKS) => {
+                // TimeTicks are 32-bit; anything wider is a malformed reply.
+                info.uptime = read_uint(value)
+                    .and_then(|ticks| u32::try_from(ticks).ok())
+                    .map(|ticks| Duration::from_millis(u64::from(ticks) * 10));
+            }
+            _ => {}
+        }
+    }
+    let is_empty = info.name.is_none() && info.description.is_none() && info.uptime.is_none();
+    (!is_empty).then_some(info)
+}
+
+/// Turns an agent-supplied string into one displayable line.
+///
+/// `sysDescr` is often multi-line; line breaks and tabs become spaces
+/// before the remaining control bytes are escaped. Some agents pad with NULs.
+fn text(value: &[u8]) -> Option<String> {
+    let end = value.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    let flat: Vec<u8> = value[..end]
+        .iter()
+        .map(|&b| {
+            if matches!(b, b'\r' | b'\n' | b'\t') {
+                b' '
+            } else {
+                b
+            }
+        })
+        .collect();
+    let text = escape_name(&flat);
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a response the way an agent would answer [`get_request`].
+    fn response(id: u16, error_status: u8, varbinds: &[(&[u8], u8, &[u8])]) -> Vec<u8> {
+        let varbinds: Vec<u8> = varbinds
+            .iter()
+            .flat_map(|(oid, tag, value)| {
+                tlv(
+                    TAG_SEQUENCE,
+                    &[tlv(TAG_OID, oid), tlv(*tag, value)].concat(),
+                )
+            })
+            .collect();
+        let pdu = [
+            tlv(TAG_INTEGER, &id.to_be_bytes()),
+            tlv(TAG_INTEGER, &[error_status]),
+            tlv(TAG_INTEGER, &[0]),
+            tlv(TAG_SEQUENCE, &varbinds),
+        ]
+        .concat();
+        let message = [
+            tlv(TAG_INTEGER, &[VERSION_2C]),
+            tlv(TAG_OCTET_STRING, b"public"),
+            tlv(TAG_GET_RESPONSE, &pdu),
+        ]
+        .concat();
+        tlv(TAG_SEQUENCE, &message)
+    }
+
+    #[test]
+    fn test_get_request_matches_known_encoding() {
+        let request = get_request(VERSION_1, "public", 1);
+        let (TAG_SEQUENCE, message, rest) = read_tlv(&request).unwrap() else {
+            panic!("not a sequence");
+        };
+        assert!(rest.is_empty());
+        assert_eq!(&message[..11], b"\x02\x01\x00\x04\x06public");
+        assert_eq!(message[11], TAG_GET_REQUEST);
+        assert!(request.windows(SYS_NAME.len()).any(|w| w == SYS_NAME));
+    }
+
+    #[test]
+    fn test_long_community_uses_long_form_length() {
+        let community = "c".repeat(150);
+        let request = get_request(VERSION_2C, &community, 7);
+        assert_eq!(&request[..2], [TAG_SEQUENCE, 0x81]);
+        let (_, message, _) = read_tlv(&request).unwrap();
+        let (_, _, rest) = read_tlv(message).unwrap();
+        let (tag, value, _) = read_tlv(rest).unwrap();
+        assert_eq!((tag, value.len()), (TAG_OCTET_STRING, 150));
+    }
+
+    #[test]
+    fn test_parse_response_reads_system_group() {
+        let reply = response(
+            42,
+            0,
+            &[
+                (
+                    SYS_DESCR,
+                    TAG_OCTET_STRING,
+                    b"HP ETHERNET MULTI-ENVIRONMENT,\r\nROM none",
+                ),
+                (SYS_UPTIME, TAG_TIMETICKS, &[0x01, 0x00]),
+                (SYS_NAME, TAG_OCTET_STRING, b"printer-2f\x1b[2J"),
+            ],
+        );
+        let info = parse_response(&reply, 42).unwrap();
+        assert_eq!(
+            info.description.as_deref(),
+            Some("HP ETHERNET MULTI-ENVIRONMENT,  ROM none")
+        );
+        assert_eq!(info.uptime, Some(Duration::from_millis(2560)));
+        assert_eq!(info.name.as_deref(), Some("printer-2f\\x1B[2J"));
+    }
+
+    #[test]
+    fn test_parse_response_ignores_oversized_timeticks() {
+        let oversized = [0xFF; 8];
+        let reply = response(
+            42,
+            0,
+            &[
+                (SYS_UPTIME, TAG_TIMETICKS, &oversized),
+                (SYS_NAME, TAG_OCTET_STRING, b"sw1"),
+            ],
+        );
+        let info = parse_response(&reply, 42).unwrap();
+        assert_eq!(info.uptime, None);
+        assert_eq!(info.name.as_deref(), Some("sw1"));
+        // The largest TimeTicks value, with its BER sign byte, still reads.
+        let max = [0x00, 0xFF, 0xFF, 0xFF, 0xFF];
+        let reply = response(42, 0, &[(SYS_UPTIME, TAG_TIMETICKS, &max)]);
+        assert_eq!(
+            parse_response(&reply, 42).unwrap().uptime,
+            Some(Duration::from_millis(u64::from(u32::MAX) * 10))
+        );
+    }
+
+    #[test]
+    fn test_parse_response_rejects_errors_and_foreign_ids() {
+        let varbinds: &[(&[u8], u8, &[u8])] = &[(SYS_NAME, TAG_OCTET_STRING, b"sw1")];
+        assert!(parse_response(&response(42, 0, varbinds), 42).is_some());
+        assert_eq!(parse_response(&response(43, 0, varbinds), 42), None);
+        assert_eq!(parse_response(&response(42, 2, varbinds), 42), None);
+        // noSuchObject for every object leaves nothing to report.
+        let missing: &[(&[u8], u8, &[u8])] = &[(SYS_NAME, 0x80, &[])];
+        assert_eq!(parse_response(&response(42, 0, missing), 42), None);
+        assert_eq!(parse_response(&[0x30, 0x05, 0x02], 42), None);
+    }
+}
@@ -0,0 +1,174 @@
+//! Multi-range scan targets.
+//!
+//! A [`ScanTargets`] holds any number of (possibly overlapping) IPv4 ranges,
+//! written as comma-separated entries (`10.0.1.0/24,10.0.5.1-50`) or loaded
+//! from a newline-delimited file. The scanner merges and de-duplicates the
+//! addresses before dispatch.
+
+use crate::bridge::Bridge;
+use crate::types::GError;
+use std::fs;
+use std::net::Ipv4Addr;
+use std::path::Path;
+
+/// A set of inclusive IPv4 ranges to scan in one pass.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScanTargets {
+    ranges: Vec<(Ipv4Addr, Ipv4Addr)>,
+}
+
+impl ScanTargets {
+    /// Parses comma-separated entries, each a CIDR block (`10.0.1.0/24`) or
+    /// anything [`Bridge::parse_range`] accepts.
+    ///
+    /// Empty entries are ignored, but at least one range is required.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut targets = Self::default();
+        for entry in spec.split(',').filter(|e| !e.trim().is_empty()) {
+            targets.ranges.push(parse_entry(entry)?);
+        }
+        if targets.is_empty() {
+            return Err("No scan targets given".to_string());
+        }
+        Ok(targets)
+    }
+
+    /// Loads targets from a file with one or more entries per line.
+    ///
+    /// Lines may hold comma-separated entries as in [`ScanTargets::parse`];
+    /// blank lines and text after `#` are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GError::Internal`] if the file cannot be read, a line fails
+    /// to parse (the message names the line), or the file lists no targets.
+    pub fn from_file(path: &Path) -> Result<Self, GError> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| GError::Internal(format!("Failed to read {}: {}", path.display(), e)))?;
+
+        let mut targets = Self::default();
+        for (n, line) in contents.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            for entry in line.split(',').filter(|e| !e.trim().is_empty()) {
+                let range = parse_entry(entry).map_err(|e| {
+                    GError::Internal(format!("{}:{}: {}", path.display(), n + 1, e))
+                })?;
+                targets.ranges.push(range);
+            }
+        }
+        if targets.is_empty() {
+            return Err(GError::Internal(format!(
+                "No scan targets in {}",
+                path.display()
+            )));
+        }
+        Ok(targets)
+    }
+
+    /// The ranges as given, before merging.
+    pub fn ranges(&self) -> &[(Ipv4Addr, Ipv4Addr)] {
+        &self.ranges
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Whether any of the ranges includes `ip`.
+    pub fn contains(&self, ip: Ipv4Addr) -> bool {
+        self.ranges
+            .iter()
+            .any(|&(start, end)| (start..=end).contains(&ip))
+    }
+}
+
+impl From<(Ipv4Addr, Ipv4Addr)> for ScanTargets {
+    fn from(range: (Ipv4Addr, Ipv4Addr)) -> Self {
+        Self {
+            ranges: vec![range],
+        }
+    }
+}
+
+/// Parses one target entry into an inclusive range.
+fn parse_entry(entry: &str) -> Result<(Ipv4Addr, Ipv4Addr), String> {
+    let entry = entry.trim();
+    match entry.split_once('/') {
+        Some((addr, prefix)) => {
+            let addr: Ipv4Addr = addr
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid network address: '{}'", addr.trim()))?;
+            let prefix: u8 = match prefix.trim().parse() {
+                Ok(p) if p <= 32 => p,
+                _ => return Err(format!("Invalid prefix length: '{}'", prefix.trim())),
+            };
+            let mask = match prefix {
+                0 => 0,
+                p => u32::MAX << (32 - p as u32),
+            };
+            let network = u32::from(addr) & mask;
+            Ok((Ipv4Addr::from(network), Ipv4Addr::from(network | !mask)))
+        }
+        None => Bridge::parse_range(entry),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_parse_mixed_entries() {
+        let targets = ScanTargets::parse("10.0.1.0/24, 10.0.5.1-50,,192.168.1.7").unwrap();
+        assert_eq!(
+            targets.ranges(),
+            [
+                (Ipv4Addr::new(10, 0, 1, 0), Ipv4Addr::new(10, 0, 1, 255)),
+                (Ipv4Addr::new(10, 0, 5, 1), Ipv4Addr::new(10, 0, 5, 50)),
+                (Ipv4Addr::new(192, 168, 1, 7), Ipv4Addr::new(192, 168, 1, 7)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_cidr_masks_host_bits() {
+        let targets = ScanTargets::parse("172.16.3.77/30").unwrap();
+        assert_eq!(
+            targets.ranges(),
+            [(Ipv4Addr::new(172, 16, 3, 76), Ipv4Addr::new(172, 16, 3, 79))]
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_entries() {
+        assert!(ScanTargets::parse("").is_err());
+        assert!(ScanTargets::parse("10.0.0.0/33").is_err());
+        assert!(ScanTargets::parse("10.0.0.1,nonsense").is_err());
+        assert!(ScanTargets::parse("10.0.0.9-1").is_err());
+    }
+
+    #[test]
+    fn test_from_file_skips_comments() {
+        let path =
+            std::env::temp_dir().join(format!("ragescanner-targets-{}.txt", std::process::id()));
+        let mut file = fs::File::create(&path).unwrap();
+        writeln!(
+            file,
+            "# lab VLANs\n10.0.1.0/24\n\n10.0.5.1-50, 10.0.9.9 # printer"
+        )
+        .unwrap();
+        drop(file);
+
+        let targets = ScanTargets::from_file(&path).unwrap();
+        assert_eq!(targets.ranges().len(), 3);
+
+        fs::write(&path, "10.0.1.0/24\nbogus\n").unwrap();
+        let err = ScanTargets::from_file(&path).unwrap_err().to_string();
+        assert!(err.contains(":2:"), "{}", err);
+
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,248 @@
+//! TLS protocol version and certificate audit.
+//!
+//! [`audit`] finds the oldest protocol version a TLS port still accepts by
+//! handshaking with the version pinned to TLS 1.0, then 1.1, and reads the
+//! expiry date of the server certificate. [`issues`] turns the findings into
+//! [`SecurityIssue`]s. Like the web probe, certificates are not validated:
+//! the audit looks at them, it does not trust them.
+//!
+//! Versions the local TLS stack refuses to offer cannot be detected, so a
+//! server may accept more than reported.
+
+use crate::types::{SecurityIssue, TlsInfo, TlsVersion};
+use std::net::Ipv4Addr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::TcpStream;
+use tokio_native_tls::{TlsConnector, native_tls};
+
+/// Open ports audited, when [`ScanConfig::tls_audit`](crate::types::ScanConfig::tls_audit) is on.
+pub const TLS_AUDIT_PORTS: &[u16] = &[443, 465, 636, 993, 995, 8443];
+
+/// Budget for each handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Certificates expiring sooner than this are reported.
+pub const CERT_EXPIRY_WARNING: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// DER tags of the two ASN.1 time types.
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+/// Context tag of the optional `version` field of a certificate.
+const TAG_VERSION: u8 = 0xA0;
+
+/// Audits the TLS service on `ip:port`; `None` if it does not complete a
+/// handshake at all.
+pub async fn audit(ip: Ipv4Addr, port: u16) -> Option<TlsInfo> {
+    let cert_der = handshake(ip, port, None).await?;
+    let mut min_version = TlsVersion::Tls12;
+    for (version, protocol) in [
+        (TlsVersion::Tls10, native_tls::Protocol::Tlsv10),
+        (TlsVersion::Tls11, native_tls::Protocol::Tlsv11),
+    ] {
+        if handshake(ip, port, Some(protocol)).await.is_some() {
+            min_version = version;
+            break;
+        }
+    }
+    Some(TlsInfo {
+        port,
+        min_version,
+        cert_not_after: cert_der.as_deref().and_then(certificate_not_after),
+    })
+}
+
+/// Completes a handshake, pinned to `version` if given, and returns the
+/// server certificate (DER) if it sent one.
+async fn handshake(
+    ip: Ipv4Addr,
+    port: u16,
+    version: Option<native_tls::Protocol>,
+) -> Option<Option<Vec<u8>>> {
+    let mut builder = native_tls::TlsConnector::builder();
+    builder
+        .danger_accept_invalid_certs(true)
+        .danger_accept_invalid_hostnames(true);
+    if version.is_some() {
+        builder
+            .min_protocol_version(version)
+            .max_protocol_version(version);
+    }
+    let connector = TlsConnector::from(builder.build().ok()?);
+    let exchange = async {
+        let stream = TcpStream::connect((ip, port)).await.ok()?;
+        let tls = connector.connect(&ip.to_string(), stream).await.ok()?;
+        let cert = tls.get_ref().peer_certificate().ok().flatten();
+        Some(cert.and_then(|c| c.to_der().ok()))
+    };
+    tokio::time::timeout(HANDSHAKE_TIMEOUT, exchange)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Lists the problems in `info` as of `now`: a protocol older than TLS 1.2,
+/// and a certificate that has expired or expires within [`CERT_EXPIRY_WARNING`].
+pub fn issues(info: &TlsInfo, now: SystemTime) -> Vec<SecurityIssue> {
+    let mut issues = Vec::new();
+    if info.min_version < TlsVersion::Tls12 {
+        issues.push(SecurityIssue::LegacyTls {
+            port: info.port,
+            version: info.min_version,
+        });
+    }
+    if let Some(not_after) = info.cert_not_after {
+        let now = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let port = info.port;
+        if not_after <= now {
+            issues.push(SecurityIssue::CertificateExpired { port });
+        } else if not_after - now < CERT_EXPIRY_WARNING.as_secs() {
+            let days = (not_after - now) / (24 * 60 * 60);
+            issues.push(SecurityIssue::CertificateExpiring { port, days });
+        }
+    }
+    issues
+}
+
+/// Reads `notAfter` of a DER-encoded X.509 certificate as Unix seconds.
+pub fn certificate_not_after(der: &[u8]) -> Option<u64> {
+    let (_, cert, _) = der_element(der)?;
+    let (_, mut tbs, _) = der_element(cert)?;
+    if tbs.first() == Some(&TAG_VERSION) {
+        tbs = der_element(tbs)?.2;
+    }
+    // Skip serialNumber, signature and issuer to reach validity.
+    for _ in 0..3 {
+        tbs = der_element(tbs)?.2;
+    }
+    let (_, validity, _) = der_element(tbs)?;
+    let (_, _not_before, rest) = der_element(validity)?;
+    let (tag, not_after, _) = der_element(rest)?;
+    parse_time(tag, not_after)
+}
+
+/// Splits the first DER element off `der`: `(tag, contents, rest)`.
+fn der_element(der: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = der.split_first()?;
+    let (&first, mut rest) = rest.split_first()?;
+    let len = if first < 0x80 {
+        usize::from(first)
+    } else {
+        let octets = usize::from(first & 0x7F);
+        if octets == 0 || octets > 4 || rest.len() < octets {
+            return None;
+        }
+        let len = rest[..octets]
+            .iter()
+            .fold(0usize, |len, &b| len << 8 | usize::from(b));
+        rest = &rest[octets..];
+        len
+    };
+    (rest.len() >= len).then(|| (tag, &rest[..len], &rest[len..]))
+}
+
+/// Parses `YYMMDDHHMMSSZ` (UTCTime) or `YYYYMMDDHHMMSSZ` (GeneralizedTime).
+fn parse_time(tag: u8, text: &[u8]) -> Option<u64> {
+    let text = std::str::from_utf8(text).ok()?;
+    let (year, rest) = match tag {
+        TAG_UTC_TIME => {
+            // RFC 5280: two-digit years 50-99 are 19xx.
+            let yy: i64 = text.get(..2)?.parse().ok()?;
+            (if yy >= 50 { 1900 + yy } else { 2000 + yy }, &text[2..])
+        }
+        TAG_GENERALIZED_TIME => (text.get(..4)?.parse().ok()?, &text[4..]),
+        _ => return None,
+    };
+    let field = |i: usize| -> Option<i64> { rest.get(i..i + 2)?.parse().ok() };
+    let days = days_from_civil(year, field(0)?, field(2)?);
+    let secs = days * 86_400 + field(4)? * 3600 + field(6)? * 60 + field(8)?;
+    u64::try_from(secs).ok()
+}
+
+/// Days from 1970-01-01 to the given proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tlv(tag: u8, contents: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        if contents.len() < 0x80 {
+            out.push(contents.len() as u8);
+        } else {
+            out.extend_from_slice(&[0x82, (contents.len() >> 8) as u8, contents.len() as u8]);
+        }
+        out.extend_from_slice(contents);
+        out
+    }
+
+    /// A certificate skeleton with just enough structure to reach `validity`.
+    fn certificate(not_after: Vec<u8>) -> Vec<u8> {
+        let validity = [tlv(TAG_UTC_TIME, b"240101000000Z"), not_after].concat();
+        let tbs = [
+            tlv(TAG_VERSION, &tlv(0x02, &[2])),
+            tlv(0x02, &[1]),
+            tlv(0x30, &[]),
+            // An issuer long enough to need a two-byte length.
+            tlv(0x30, &[0x05; 200]),
+            tlv(0x30, &validity),
+        ]
+        .concat();
+        tlv(0x30, &tlv(0x30, &tbs))
+    }
+
+    #[test]
+    fn test_certificate_not_after() {
+        let generalized = certificate(tlv(TAG_GENERALIZED_TIME, b"20300615123000Z"));
+        assert_eq!(certificate_not_after(&generalized), Some(1_907_757_000));
+        let utc = certificate(tlv(TAG_UTC_TIME, b"491231235959Z"));
+        assert_eq!(certificate_not_after(&utc), Some(2_524_607_999));
+        assert_eq!(certificate_not_after(&generalized[..40]), None);
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+    }
+
+    #[test]
+    fn test_issues_flag_old_protocols_and_expiring_certificates() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        let day = 24 * 60 * 60;
+        let info = |min_version, cert_not_after| TlsInfo {
+            port: 443,
+            min_version,
+            cert_not_after,
+        };
+
+        assert!(
+            issues(
+                &info(TlsVersion::Tls12, Some(1_000_000_000 + 90 * day)),
+                now
+            )
+            .is_empty()
+        );
+        assert_eq!(
+            issues(
+                &info(TlsVersion::Tls10, Some(1_000_000_000 + 12 * day + 5)),
+                now
+            ),
+            [
+                SecurityIssue::LegacyTls {
+                    port: 443,
+                    version: TlsVersion::Tls10
+                },
+                SecurityIssue::CertificateExpiring {
+                    port: 443,
+                    days: 12
+                },
+            ]
+        );
+        let expired = issues(&info(TlsVersion::Tls12, Some(999_999_999)), now);
+        assert_eq!(expired, [SecurityIssue::CertificateExpired { port: 443 }]);
+        assert_eq!(expired[0].to_string(), "expired certificate on port 443");
+    }
+}
@@ -1,33 +1,439 @@
-use crate::types::{BridgeMessage, ScanResult};
+use crate::about;
+use crate::bridge::Bridge;
+use crate::conflict::DEFAULT_CONFLICT_WATCH_INTERVAL;
+use crate::export::{self, ExportScope};
+use crate::inventory::normalize_mac;
+use crate::net::Capabilities;
+use crate::passive::DEFAULT_ARP_WATCH_INTERVAL;
+use crate::reach::{MAX_MATRIX_HOSTS, ReachHost, ReachMatrix};
+use crate::settings::Settings;
+use crate::targets::ScanTargets;
+use crate::tui::setup::{SetupOutcome, SetupWizard};
+use crate::tui::store::{HostRow, ResultStore};
+use crate::tui::theme;
+use crate::types::{
+    BridgeMessage, BusyPolicy, DeviceNote, PhaseProgress, PortInfo, RangeProgress, ResultOrder,
+    ScanLabel, ScanResult, ScanStats, ScanStatus, ScanSummary, TraceHop, VendorDetails,
+};
+use crate::wol::{DEFAULT_WAKE_TIMEOUT, WakeTarget};
 use ratatui::crossterm::event::KeyCode;
 use ratatui::widgets::TableState;
+use std::borrow::Cow;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::mpsc::Sender;
 
+/// Interval between sweeps when monitoring from the TUI.
+pub const MONITOR_INTERVAL: Duration = Duration::from_secs(60);
+
+/// File written by the export keys; the extension picks the format.
+pub const DEFAULT_EXPORT_PATH: &str = "ragescanner-export.json";
+
+/// How long the error banner stays up unless dismissed with Esc.
+pub const ERROR_BANNER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Lines kept by the event log pane; older ones are dropped.
+pub const EVENT_LOG_CAPACITY: usize = 500;
+
+/// Minimum time between packet-rate lines in the event log.
+pub const STATS_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Lines PgUp/PgDn scroll the detail popup by.
+pub const DETAIL_PAGE_LINES: i16 = 10;
+
 #[derive(PartialEq, Eq, Debug)]
 pub enum InputMode {
     Normal,
     Editing,
+    /// Editing the label for the next scans, as `name | description`.
+    Label,
+    /// Typing the table search; rows are filtered as it changes.
+    Search,
+    /// Typing a `:` command; see [`Command`].
+    Command,
+    /// Naming the device picked from the action menu, as `name | notes`.
+    DeviceNote,
 }
 
 #[derive(PartialEq, Eq, Debug)]
 pub enum ScanState {
     Idle,
     Scanning,
+    Monitoring,
     Complete,
     Cancelled,
 }
 
+/// Column the table is sorted by in [`ResultOrder::Ip`], cycled with `o`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+    #[default]
+    Ip,
+    Hostname,
+    Vendor,
+    Latency,
+    OpenPorts,
+    Status,
+}
+
+impl SortKey {
+    /// The key after this one, wrapping back to [`SortKey::Ip`].
+    pub fn next(self) -> Self {
+        match self {
+            SortKey::Ip => SortKey::Hostname,
+            SortKey::Hostname => SortKey::Vendor,
+            SortKey::Vendor => SortKey::Latency,
+            SortKey::Latency => SortKey::OpenPorts,
+            SortKey::OpenPorts => SortKey::Status,
+            SortKey::Status => SortKey::Ip,
+        }
+    }
+
+    /// Orders `a` and `b` by this key, then by IP. Hosts with no value for
+    /// the key (no hostname, no latency) go last in either direction.
+    pub fn compare(self, a: &HostRow, b: &HostRow, descending: bool) -> Ordering {
+        let lowercase = |field: &Option<String>| field.as_deref().map(str::to_ascii_lowercase);
+        let by_key = match self {
+            SortKey::Ip => missing_last(Some(a.ip), Some(b.ip), descending, Ord::cmp),
+            SortKey::Hostname => missing_last(
+                lowercase(&a.hostname),
+                lowercase(&b.hostname),
+                descending,
+                Ord::cmp,
+            ),
+            SortKey::Vendor => missing_last(
+                lowercase(&a.vendor),
+                lowercase(&b.vendor),
+                descending,
+                Ord::cmp,
+            ),
+            SortKey::Latency => missing_last(
+                a.latency.as_ref().map(|l| l.avg_ms),
+                b.latency.as_ref().map(|l| l.avg_ms),
+                descending,
+                f32::total_cmp,
+            ),
+            SortKey::OpenPorts => missing_last(
+                Some(a.open_ports.len()),
+                Some(b.open_ports.len()),
+                descending,
+                Ord::cmp,
+            ),
+            SortKey::Status => missing_last(
+                Some(status_rank(&a.status)),
+                Some(status_rank(&b.status)),
+                descending,
+                Ord::cmp,
+            ),
+        };
+        by_key.then(a.ip.cmp(&b.ip))
+    }
+}
+
+impl std::fmt::Display for SortKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SortKey::Ip => "IP",
+            SortKey::Hostname => "Hostname",
+            SortKey::Vendor => "Vendor",
+            SortKey::Latency => "Latency",
+            SortKey::OpenPorts => "Open ports",
+            SortKey::Status => "Status",
+        })
+    }
+}
+
+/// Compares two optional values with `cmp`, putting `None` after every value.
+fn missing_last<T>(
+    a: Option<T>,
+    b: Option<T>,
+    descending: bool,
+    cmp: impl Fn(&T, &T) -> Ordering,
+) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if descending => cmp(&b, &a),
+        (Some(a), Some(b)) => cmp(&a, &b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Ascending status order: online hosts first, never-probed targets last.
+fn status_rank(status: &ScanStatus) -> u8 {
+    match status {
+        ScanStatus::Online => 0,
+        ScanStatus::Scanning => 1,
+        ScanStatus::Offline => 2,
+        ScanStatus::SystemError(_) => 3,
+        ScanStatus::NotScanned => 4,
+    }
+}
+
+/// A line of the event log pane.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventLine {
+    /// UTC time of day the event arrived, `HH:MM:SS`.
+    pub time: String,
+    pub text: String,
+    /// Errors are highlighted.
+    pub is_error: bool,
+}
+
+/// State of a deep port scan started from the detail view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeepScanView {
+    pub ip: Ipv4Addr,
+    pub progress: u8,
+    pub ports: Vec<PortInfo>,
+    pub finished: bool,
+}
+
+/// Route to a host traced from the action menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TracerouteView {
+    pub ip: Ipv4Addr,
+    pub hops: Vec<TraceHop>,
+    pub finished: bool,
+}
+
+/// Reachability matrix opened with `M`; `matrix` is `None` while probing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReachMatrixView {
+    pub hosts: usize,
+    pub matrix: Option<ReachMatrix>,
+}
+
+/// Actions the `a` menu offers on the selected host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostAction {
+    Rescan,
+    DeepScan,
+    WakeOnLan,
+    Traceroute,
+    ConflictWatch,
+    NameDevice,
+    CopyIp,
+}
+
+impl HostAction {
+    pub const ALL: [HostAction; 7] = [
+        HostAction::Rescan,
+        HostAction::DeepScan,
+        HostAction::WakeOnLan,
+        HostAction::Traceroute,
+        HostAction::ConflictWatch,
+        HostAction::NameDevice,
+        HostAction::CopyIp,
+    ];
+
+    /// Key that runs the action while the menu is open.
+    pub fn key(self) -> char {
+        match self {
+            HostAction::Rescan => 'r',
+            HostAction::DeepScan => 'd',
+            HostAction::WakeOnLan => 'w',
+            HostAction::Traceroute => 't',
+            HostAction::ConflictWatch => 'i',
+            HostAction::NameDevice => 'n',
+            HostAction::CopyIp => 'c',
+        }
+    }
+}
+
+impl fmt::Display for HostAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HostAction::Rescan => "Rescan host",
+            HostAction::DeepScan => "Deep port scan",
+            HostAction::WakeOnLan => "Wake-on-LAN",
+            HostAction::Traceroute => "Traceroute",
+            HostAction::ConflictWatch => "Watch for IP conflicts",
+            HostAction::NameDevice => "Name device / notes",
+            HostAction::CopyIp => "Copy IP to clipboard",
+        })
+    }
+}
+
+/// Usage shown for an empty or unknown `:` command.
+pub const COMMAND_HELP: &str =
+    "Commands: :scan [TARGETS] :stop :ports SPEC :export [PATH] :filter online|all|TEXT :q";
+
+/// A command typed on the `:` line, as parsed by [`Command::parse`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// `:scan [TARGETS]`: scans `TARGETS`, which replace the range input, or
+    /// the range input without any.
+    Scan(Option<String>),
+    /// `:stop`: stops the running scan or monitor.
+    Stop,
+    /// `:ports SPEC`: TCP ports probed by later scans, e.g. `1-1024`.
+    Ports(Vec<u16>),
+    /// `:export [PATH]`: writes the rows shown, to `PATH` (used by `x` from
+    /// then on) if given.
+    Export(Option<PathBuf>),
+    /// `:filter online|all|TEXT`.
+    Filter(CommandFilter),
+    /// `:q` or `:quit`.
+    Quit,
+}
+
+/// What `:filter` shows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandFilter {
+    /// Online hosts only, like `Tab`.
+    Online,
+    /// Every host: clears the online filter and the search.
+    All,
+    /// Rows matching the text, like `/`.
+    Search(String),
+}
+
+impl Command {
+    /// Parses a command line, with or without its leading `:`; the error
+    /// says what was wrong for the error banner.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let line = line.strip_prefix(':').unwrap_or(line).trim_start();
+        let (name, arg) = match line.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, Some(arg.trim().to_string())),
+            None => (line, None),
+        };
+        match (name, arg) {
+            ("scan", target) => Ok(Command::Scan(target)),
+            ("ports", Some(spec)) => Bridge::parse_ports(&spec).map(Command::Ports),
+            ("ports", None) => Err("Usage: :ports SPEC, e.g. :ports 22,80,8000-8100".to_string()),
+            ("export", path) => Ok(Command::Export(path.map(PathBuf::from))),
+            ("filter", Some(filter)) => Ok(Command::Filter(match filter.as_str() {
+                "online" => CommandFilter::Online,
+                "all" => CommandFilter::All,
+                _ => CommandFilter::Search(filter),
+            })),
+            ("filter", None) => Err("Usage: :filter online|all|TEXT".to_string()),
+            ("stop", None) => Ok(Command::Stop),
+            ("q" | "quit", None) => Ok(Command::Quit),
+            (name @ ("stop" | "q" | "quit"), Some(_)) => {
+                Err(format!(":{} takes no argument", name))
+            }
+            ("", _) => Err(COMMAND_HELP.to_string()),
+            (name, _) => Err(format!("Unknown command :{}. {}", name, COMMAND_HELP)),
+        }
+    }
+
+    /// Whether the command starts, stops or retunes scans, which a viewer
+    /// of saved results cannot do.
+    fn scans(&self) -> bool {
+        matches!(self, Command::Scan(_) | Command::Stop | Command::Ports(_))
+    }
+}
+
 pub struct App {
     pub input: String,
     pub input_mode: InputMode,
-    pub results: Vec<ScanResult>,
+    /// Every result, sorted by IP whatever [`App::result_order`] shows; the
+    /// full results of large scans are spilled to disk.
+    pub results: ResultStore,
+    /// Table order, toggled with `l`.
+    pub result_order: ResultOrder,
+    /// Column the table is sorted by in [`ResultOrder::Ip`], cycled with `o`.
+    pub sort_key: SortKey,
+    /// Whether [`App::sort_key`] sorts descending, toggled with `O`.
+    pub sort_descending: bool,
+    /// When each host last reported, as a running count, for [`ResultOrder::Live`].
+    pub arrivals: HashMap<Ipv4Addr, u64>,
+    next_arrival: u64,
     pub table_state: TableState,
+    /// Result rows that fit in the table; updated on every draw.
+    pub page_size: usize,
     pub progress: u8,
+    /// Whether the running scan is paused, as reported by [`BridgeMessage::ScanState`].
+    pub paused: bool,
+    /// Latest packet rate and ETA of the running scan.
+    pub stats: Option<ScanStats>,
+    /// Discovery and port-scan progress of the running scan.
+    pub phase: Option<PhaseProgress>,
+    /// Totals of the last finished scan, shown in the status bar.
+    pub summary: Option<ScanSummary>,
+    /// Per-range progress when the scan covers several ranges.
+    pub ranges: Vec<RangeProgress>,
     pub scan_state: ScanState,
+    /// Message shown in the error banner, if one is up.
     pub error: Option<String>,
+    /// When the current error banner appeared, for auto-hiding.
+    pub error_shown: Option<Instant>,
     pub show_detail: bool,
+    /// Lines the detail popup is scrolled down by, clamped when drawn.
+    pub detail_scroll: u16,
     pub should_quit: bool,
     pub filter_online: bool,
+    /// Text the table is filtered by; see [`matches_search`].
+    pub search: String,
+    /// Command being typed in [`InputMode::Command`], without the `:`.
+    pub command_input: String,
+    pub udp_scan: bool,
+    /// What the scan provider supports; see [`App::action_unavailable`].
+    pub capabilities: Capabilities,
+    pub include_subnet_edges: bool,
+    pub ping_attempts: u32,
+    /// Most recent device event reported while monitoring.
+    pub last_change: Option<String>,
+    /// Hosts that raised a [`BridgeMessage::SecurityAlert`] while
+    /// monitoring; their rows are highlighted.
+    pub security_alerts: HashSet<Ipv4Addr>,
+    pub busy_policy: BusyPolicy,
+    /// Short bridge feedback such as a queued or rejected scan.
+    pub notice: Option<String>,
+    pub deep_scan: Option<DeepScanView>,
+    /// Latest traceroute, shown in the detail view of its host.
+    pub traceroute: Option<TracerouteView>,
+    /// Reachability matrix popup, if open.
+    pub reach_matrix: Option<ReachMatrixView>,
+    /// Highlighted entry of the open action menu; see [`App::actions`].
+    pub action_menu: Option<usize>,
+    /// Highlighted entry of the open wake menu; see [`App::wake_choices`].
+    pub wake_menu: Option<usize>,
+    /// First-run setup, drawn over everything and taking every key while open.
+    pub setup: Option<SetupWizard>,
+    /// Text waiting to be put on the terminal clipboard with [`osc52_copy`].
+    pub clipboard: Option<String>,
+    /// OUI record shown over the table or detail view, if open.
+    pub vendor_details: Option<VendorDetails>,
+    /// Lines of the About popup, if open; see [`about::about_fields`].
+    pub about: Option<Vec<(&'static str, String)>>,
+    /// Whether `s` or `:stop` is waiting for `y` to stop the running scan.
+    pub confirm_stop: bool,
+    /// Label text being edited in [`InputMode::Label`].
+    pub label_input: String,
+    /// MAC of the device being named in [`InputMode::DeviceNote`].
+    pub device_note_mac: Option<String>,
+    /// Name and notes being typed in [`InputMode::DeviceNote`].
+    pub device_note_input: String,
+    /// Label applied to scans started from now on.
+    pub scan_label: Option<ScanLabel>,
+    /// Label of the running or last scan, from [`BridgeMessage::ScanStarted`].
+    pub active_label: Option<ScanLabel>,
+    /// When the current range scan was started, for the history log.
+    pub scan_started: Option<SystemTime>,
+    /// Browsing saved results: keys that scan or change scan settings are ignored.
+    pub read_only: bool,
+    /// Where `x`/`X` export the view, [`DEFAULT_EXPORT_PATH`] unless changed.
+    pub export_path: PathBuf,
+    /// Preferences shared with the GUI; the range is remembered on every scan.
+    pub settings: Settings,
+    /// Where `settings` are saved when a scan starts; `None` never writes them.
+    pub settings_path: Option<PathBuf>,
+    /// Recent scanner events, oldest first, at most [`EVENT_LOG_CAPACITY`].
+    pub event_log: VecDeque<EventLine>,
+    /// Whether the event log pane is shown, toggled with `L`.
+    pub show_event_log: bool,
+    /// Lines the event log pane is scrolled back from the newest.
+    pub event_log_scroll: usize,
+    last_stats_logged: Option<Instant>,
     pub cmd_tx: Sender<BridgeMessage>,
 }
 
@@ -36,43 +442,779 @@ impl App {
         Self {
             input: String::from("192.168.1.1-255"),
             input_mode: InputMode::Normal,
-            results: Vec::new(),
+            results: ResultStore::new(),
+            result_order: ResultOrder::from_env(),
+            sort_key: SortKey::Ip,
+            sort_descending: false,
+            arrivals: HashMap::new(),
+            next_arrival: 0,
             table_state: TableState::default(),
+            page_size: 1,
             progress: 0,
+            stats: None,
+            phase: None,
+            summary: None,
+            ranges: Vec::new(),
             scan_state: ScanState::Idle,
             error: None,
+            error_shown: None,
             show_detail: false,
+            detail_scroll: 0,
             should_quit: false,
             filter_online: false,
+            search: String::new(),
+            command_input: String::new(),
+            udp_scan: false,
+            capabilities: Capabilities::default(),
+            include_subnet_edges: false,
+            ping_attempts: 1,
+            last_change: None,
+            security_alerts: HashSet::new(),
+            busy_policy: BusyPolicy::default(),
+            notice: None,
+            deep_scan: None,
+            traceroute: None,
+            reach_matrix: None,
+            action_menu: None,
+            wake_menu: None,
+            setup: None,
+            clipboard: None,
+            vendor_details: None,
+            about: None,
+            confirm_stop: false,
+            label_input: String::new(),
+            device_note_mac: None,
+            device_note_input: String::new(),
+            scan_label: None,
+            active_label: None,
+            scan_started: None,
+            read_only: false,
+            export_path: PathBuf::from(DEFAULT_EXPORT_PATH),
+            settings: Settings::default(),
+            settings_path: None,
+            paused: false,
+            event_log: VecDeque::new(),
+            show_event_log: false,
+            event_log_scroll: 0,
+            last_stats_logged: None,
             cmd_tx,
         }
     }
 
-    pub fn filtered_results(&self) -> Vec<&ScanResult> {
-        if self.filter_online {
-            self.results
-                .iter()
-                .filter(|r| r.status == crate::types::ScanStatus::Online)
-                .collect()
-        } else {
-            self.results.iter().collect()
+    /// Creates a read-only app browsing `results` loaded from `source`.
+    ///
+    /// Nothing is ever sent to a bridge, so the viewer cannot start scans.
+    pub fn viewer(source: &str, results: Vec<ScanResult>, label: Option<ScanLabel>) -> Self {
+        let (cmd_tx, _) = tokio::sync::mpsc::channel(1);
+        Self {
+            input: source.to_string(),
+            results: results.into_iter().collect(),
+            progress: 100,
+            scan_state: ScanState::Complete,
+            active_label: label,
+            read_only: true,
+            ..Self::new(cmd_tx)
+        }
+    }
+
+    /// The table rows: results passing the online filter and the search, in
+    /// [`App::result_order`] and then [`App::sort_key`].
+    pub fn filtered_results(&self) -> Vec<&HostRow> {
+        let query = self.search.to_ascii_lowercase();
+        let mut rows: Vec<&HostRow> = self
+            .results
+            .iter()
+            .filter(|r| !self.filter_online || r.status == ScanStatus::Online)
+            .filter(|r| matches_search(r, &query))
+            .collect();
+        match self.result_order {
+            ResultOrder::Live => rows.sort_by_key(|r| Reverse(self.arrivals.get(&r.ip))),
+            ResultOrder::Ip if self.sorted_by_ip() => {}
+            ResultOrder::Ip => {
+                rows.sort_by(|a, b| self.sort_key.compare(a, b, self.sort_descending))
+            }
+        }
+        rows
+    }
+
+    /// Whether the table shows `results` as stored: ascending by IP.
+    fn sorted_by_ip(&self) -> bool {
+        self.result_order == ResultOrder::Ip
+            && self.sort_key == SortKey::Ip
+            && !self.sort_descending
+    }
+
+    /// The table order for the status bar, such as `Latency desc`.
+    pub fn order_label(&self) -> String {
+        match self.result_order {
+            ResultOrder::Live => ResultOrder::Live.to_string(),
+            ResultOrder::Ip if self.sort_descending => format!("{} desc", self.sort_key),
+            ResultOrder::Ip => self.sort_key.to_string(),
         }
     }
 
     pub fn start_scan(&mut self) {
         self.results.clear();
+        self.arrivals.clear();
+        self.table_state.select(None);
         self.progress = 0;
+        self.stats = None;
+        self.phase = None;
+        self.summary = None;
+        self.ranges.clear();
+        self.paused = false;
         self.scan_state = ScanState::Scanning;
-        self.error = None;
+        self.scan_started = Some(SystemTime::now());
+        self.dismiss_error();
+        self.notice = None;
+        self.remember_range();
+        let msg = if self.input_is_ranges() {
+            BridgeMessage::StartScan(self.input.clone())
+        } else {
+            match Bridge::extract_targets(&self.input) {
+                targets if targets.is_empty() => BridgeMessage::StartScan(self.input.clone()),
+                targets => BridgeMessage::StartScanTargets(targets),
+            }
+        };
+        let _ = self.cmd_tx.try_send(msg);
+    }
+
+    /// Appends pasted text to the range input, or to the command line while
+    /// one is typed, flattening line breaks.
+    ///
+    /// Otherwise switches to editing mode so the target preview is visible
+    /// before scanning.
+    pub fn on_paste(&mut self, text: &str) {
+        let flattened = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if self.input_mode == InputMode::Command {
+            self.command_input.push_str(&flattened);
+            return;
+        }
+        self.input.push_str(&flattened);
+        self.input_mode = InputMode::Editing;
+    }
+
+    /// Describes what the current input will scan when it is not a plain range.
+    ///
+    /// Returns `None` for well-formed ranges and for input without any addresses.
+    pub fn target_preview(&self) -> Option<String> {
+        if self.input_is_ranges() {
+            return None;
+        }
+        let targets = Bridge::extract_targets(&self.input);
+        (!targets.is_empty()).then(|| Bridge::describe_targets(&targets))
+    }
+
+    /// Whether the input is a range or comma-separated ranges rather than a
+    /// pasted host list.
+    fn input_is_ranges(&self) -> bool {
+        Bridge::parse_range(&self.input).is_ok() || ScanTargets::parse(&self.input).is_ok()
+    }
+
+    /// Starts re-scanning the input range every [`MONITOR_INTERVAL`].
+    ///
+    /// Results are kept across sweeps and updated in place.
+    pub fn start_monitor(&mut self) {
+        self.begin_monitoring();
+        let _ = self.cmd_tx.try_send(BridgeMessage::StartMonitor {
+            interval: MONITOR_INTERVAL,
+            range: self.input.clone(),
+        });
+    }
+
+    /// Starts watching the ARP cache for hosts in the input range, without
+    /// sending any probes. An empty input watches every address.
+    pub fn start_arp_watch(&mut self) {
+        self.begin_monitoring();
+        let _ = self.cmd_tx.try_send(BridgeMessage::StartArpWatch {
+            interval: DEFAULT_ARP_WATCH_INTERVAL,
+            range: self.input.clone(),
+        });
+    }
+
+    fn begin_monitoring(&mut self) {
+        self.progress = 0;
+        self.stats = None;
+        self.phase = None;
+        self.ranges.clear();
+        self.paused = false;
+        self.scan_state = ScanState::Monitoring;
+        self.dismiss_error();
+        self.last_change = None;
+        self.security_alerts.clear();
+        self.remember_range();
+    }
+
+    /// Adopts saved settings: restores the last range, if any, draws in their
+    /// theme and tunes later scans with them.
+    pub fn apply_settings(&mut self, settings: Settings) {
+        if let Some(range) = &settings.last_range {
+            self.input = range.clone();
+        }
+        let _ = self.cmd_tx.try_send(BridgeMessage::SetScanConfig(Box::new(
+            settings.scan_config(),
+        )));
+        theme::set(settings.theme);
+        self.settings = settings;
+    }
+
+    /// Adopts settings edited in the file while running. The bridge already
+    /// tunes later scans with them; the range being typed is left alone.
+    pub fn on_config_reloaded(&mut self, settings: Settings) {
+        self.settings = Settings {
+            last_range: self.settings.last_range.take(),
+            ..settings
+        };
+        theme::set(self.settings.theme);
+        self.notice = Some("Settings reloaded".to_string());
+    }
+
+    /// Closes the setup wizard; a finished one is applied and saved.
+    fn on_setup_outcome(&mut self, outcome: SetupOutcome) {
+        match outcome {
+            SetupOutcome::Open => {}
+            SetupOutcome::Cancelled => {
+                self.setup = None;
+                theme::set(self.settings.theme);
+                self.notice = Some("Setup skipped; it runs again next time".to_string());
+            }
+            SetupOutcome::Finished(settings) => {
+                self.setup = None;
+                self.apply_settings(*settings);
+                self.notice = match &self.settings_path {
+                    Some(path) => Some(match self.settings.save(path) {
+                        Ok(()) => format!("Settings saved to {}", path.display()),
+                        Err(e) => format!("Settings not saved: {}", e),
+                    }),
+                    None => Some(format!("Default range: {}", self.input)),
+                };
+            }
+        }
+    }
+
+    /// Records the range being scanned and saves the settings, if they have a path.
+    fn remember_range(&mut self) {
+        self.settings.last_range = Some(self.input.clone());
+        if let Some(path) = &self.settings_path
+            && let Err(e) = self.settings.save(path)
+        {
+            self.notice = Some(format!("Settings not saved: {}", e));
+        }
+    }
+
+    /// Appends the just-completed scan to the local history database.
+    #[cfg(feature = "history")]
+    pub fn save_history(&self) {
+        let started = self.scan_started.unwrap_or_else(SystemTime::now);
+        if let Err(e) = crate::history::save_completed_scan(
+            &self.input,
+            self.active_label.as_ref(),
+            started,
+            &self.results.load_where(|_| true),
+        ) {
+            log::error!("Failed to save scan history: {}", e);
+        }
+    }
+
+    /// Writes the rows in `scope` to [`App::export_path`]: with
+    /// [`ExportScope::Visible`] what the online filter and search show, with
+    /// [`ExportScope::Selected`] the host under the cursor.
+    pub fn export(&mut self, scope: ExportScope) {
+        let selected: Vec<Ipv4Addr> = self.selected_result().map(|r| r.ip).into_iter().collect();
+        let visible: HashSet<Ipv4Addr> = self.filtered_results().iter().map(|r| r.ip).collect();
+        // Only the hosts exported are read back from the spill file.
+        let results = self
+            .results
+            .load_where(|r| scope == ExportScope::All || visible.contains(&r.ip));
+        let rows = export::scoped(&results, scope, |r| visible.contains(&r.ip), &selected);
+        match export::write_file(&self.export_path, &rows, self.active_label.as_ref()) {
+            Ok(()) => {
+                self.notice = Some(format!(
+                    "Exported {} hosts to {}",
+                    rows.len(),
+                    self.export_path.display()
+                ))
+            }
+            Err(e) => self.show_error(e.to_string()),
+        }
+    }
+
+    /// Returns the row under the table cursor, honouring the online filter.
+    pub fn selected_result(&self) -> Option<&HostRow> {
+        let idx = self.table_state.selected()?;
+        self.filtered_results().get(idx).copied()
+    }
+
+    /// Returns the full result under the table cursor, for the detail view.
+    pub fn selected_detail(&self) -> Option<Cow<'_, ScanResult>> {
+        self.results.load(self.selected_result()?.ip)
+    }
+
+    /// Deep-scans the selected host, replacing any unfinished deep scan.
+    ///
+    /// Runs alongside a range scan; its events only update [`App::deep_scan`].
+    pub fn start_deep_scan(&mut self) {
+        let Some(ip) = self.selected_result().map(|r| r.ip) else {
+            return;
+        };
+        if let Some(previous) = self.deep_scan.take()
+            && !previous.finished
+        {
+            let _ = self
+                .cmd_tx
+                .try_send(BridgeMessage::StopDeepScan(previous.ip));
+        }
+        self.deep_scan = Some(DeepScanView {
+            ip,
+            progress: 0,
+            ports: Vec::new(),
+            finished: false,
+        });
+        let _ = self.cmd_tx.try_send(BridgeMessage::StartDeepScan(ip));
+    }
+
+    /// Records a [`BridgeMessage::RangeProgress`] report.
+    pub fn on_range_progress(&mut self, range: RangeProgress) {
+        match self.ranges.iter_mut().find(|r| r.index == range.index) {
+            Some(known) => *known = range,
+            None => self.ranges.push(range),
+        }
+    }
+
+    /// The ranges to show, those still in flight first, at most `max`.
+    pub fn range_rows(&self, max: usize) -> Vec<&RangeProgress> {
+        let mut rows: Vec<&RangeProgress> = self.ranges.iter().collect();
+        rows.sort_by_key(|r| (r.is_complete(), r.index));
+        rows.truncate(max);
+        rows
+    }
+
+    /// Applies a deep scan event if it belongs to the tracked host.
+    pub fn on_deep_scan_event(&mut self, msg: BridgeMessage) {
+        let Some(view) = self.deep_scan.as_mut() else {
+            return;
+        };
+        match msg {
+            BridgeMessage::DeepScanPort(ip, info) if ip == view.ip => view.ports.push(info),
+            BridgeMessage::DeepScanProgress(ip, p) if ip == view.ip => view.progress = p,
+            BridgeMessage::DeepScanComplete(ip, ports) if ip == view.ip => {
+                view.ports = ports;
+                view.progress = 100;
+                view.finished = true;
+            }
+            BridgeMessage::DeepScanCancelled(ip) if ip == view.ip => view.finished = true,
+            _ => {}
+        }
+    }
+
+    /// Records a traceroute event for the host being traced.
+    pub fn on_traceroute_event(&mut self, msg: BridgeMessage) {
+        let Some(view) = self.traceroute.as_mut() else {
+            return;
+        };
+        match msg {
+            BridgeMessage::TracerouteHop(ip, hop) if ip == view.ip => view.hops.push(hop),
+            BridgeMessage::TracerouteComplete(ip) if ip == view.ip => view.finished = true,
+            _ => {}
+        }
+    }
+
+    /// Tests the visible online hosts, at most [`MAX_MATRIX_HOSTS`], on the
+    /// configured scan ports and opens the matrix popup.
+    pub fn start_reach_matrix(&mut self) {
+        let hosts: Vec<ReachHost> = self
+            .filtered_results()
+            .into_iter()
+            .filter(|r| r.status == ScanStatus::Online)
+            .take(MAX_MATRIX_HOSTS)
+            .map(|r| ReachHost::from_ip(r.ip))
+            .collect();
+        if hosts.is_empty() {
+            self.notice = Some("No online hosts to test".to_string());
+            return;
+        }
+        self.reach_matrix = Some(ReachMatrixView {
+            hosts: hosts.len(),
+            matrix: None,
+        });
+        let _ = self.cmd_tx.try_send(BridgeMessage::ReachMatrix {
+            hosts,
+            ports: Vec::new(),
+        });
+    }
+
+    /// Shows a finished matrix, if its popup is still open.
+    pub fn on_reach_matrix(&mut self, matrix: ReachMatrix) {
+        if let Some(view) = self.reach_matrix.as_mut() {
+            view.matrix = Some(matrix);
+        }
+    }
+
+    /// Writes the open matrix next to [`App::export_path`], as
+    /// `<name>-matrix.<ext>`.
+    pub fn export_reach_matrix(&mut self) {
+        let Some(matrix) = self.reach_matrix.as_ref().and_then(|v| v.matrix.as_ref()) else {
+            return;
+        };
+        let stem = self
+            .export_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut path = self.export_path.with_file_name(format!("{}-matrix", stem));
+        if let Some(ext) = self.export_path.extension() {
+            path.set_extension(ext);
+        }
+        match matrix.write_file(&path) {
+            Ok(()) => self.notice = Some(format!("Exported matrix to {}", path.display())),
+            Err(e) => self.show_error(e.to_string()),
+        }
+    }
+
+    /// Actions the menu offers: only copying without a bridge to send to.
+    pub fn actions(&self) -> Vec<HostAction> {
+        HostAction::ALL
+            .into_iter()
+            .filter(|a| !self.read_only || *a == HostAction::CopyIp)
+            .collect()
+    }
+
+    /// Why `action` cannot run with this provider, if it cannot; the menu
+    /// grays such actions out.
+    pub fn action_unavailable(&self, action: HostAction) -> Option<&'static str> {
+        match action {
+            HostAction::Traceroute if !self.capabilities.icmp => {
+                Some("Traceroute needs ICMP, which is unavailable here")
+            }
+            HostAction::WakeOnLan if !self.capabilities.udp => {
+                Some("Wake-on-LAN needs UDP, which is unavailable here")
+            }
+            HostAction::ConflictWatch if !self.capabilities.arp => {
+                Some("The conflict watch needs ARP, which is unavailable here")
+            }
+            _ => None,
+        }
+    }
+
+    /// Opens the action menu on the selected host.
+    pub fn open_action_menu(&mut self) {
+        if self.selected_result().is_some() {
+            self.action_menu = Some(0);
+        }
+    }
+
+    /// Handles a key while the action menu is open.
+    fn on_action_menu_key(&mut self, code: KeyCode, highlighted: usize) {
+        let actions = self.actions();
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('a') => self.action_menu = None,
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.action_menu = Some((highlighted + 1) % actions.len());
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.action_menu = Some((highlighted + actions.len() - 1) % actions.len());
+            }
+            KeyCode::Enter => self.run_action(actions[highlighted]),
+            KeyCode::Char(c) => {
+                if let Some(action) = actions.into_iter().find(|a| a.key() == c) {
+                    self.run_action(action);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// What `W` can wake: the hosts in view that have a MAC, then each
+    /// `wake_groups` entry of the settings, labelled with their size.
+    pub fn wake_choices(&self) -> Vec<(String, Vec<WakeTarget>)> {
+        let mut choices = Vec::new();
+        let in_view: Vec<WakeTarget> = self
+            .filtered_results()
+            .iter()
+            .filter_map(|r| {
+                Some(WakeTarget {
+                    mac: r.mac.clone()?,
+                    ip: Some(r.ip),
+                })
+            })
+            .collect();
+        if !in_view.is_empty() {
+            choices.push((format!("Hosts in view ({})", in_view.len()), in_view));
+        }
+        for name in self.settings.wake_groups.keys() {
+            if let Some(group) = self.settings.wake_group(name) {
+                choices.push((format!("Group {} ({})", name, group.len()), group));
+            }
+        }
+        choices
+    }
+
+    /// Opens the wake menu, or explains why there is nothing to wake.
+    pub fn open_wake_menu(&mut self) {
+        if let Some(reason) = self.action_unavailable(HostAction::WakeOnLan) {
+            self.notice = Some(reason.to_string());
+        } else if self.wake_choices().is_empty() {
+            self.notice = Some("Nothing to wake: no MAC in view and no wake groups".to_string());
+        } else {
+            self.wake_menu = Some(0);
+        }
+    }
+
+    /// Handles a key while the wake menu is open.
+    fn on_wake_menu_key(&mut self, code: KeyCode, highlighted: usize) {
+        let mut choices = self.wake_choices();
+        let len = choices.len().max(1);
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('W') => self.wake_menu = None,
+            KeyCode::Char('j') | KeyCode::Down => self.wake_menu = Some((highlighted + 1) % len),
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.wake_menu = Some((highlighted + len - 1) % len);
+            }
+            KeyCode::Enter => {
+                self.wake_menu = None;
+                if highlighted < choices.len() {
+                    let targets = choices.swap_remove(highlighted).1;
+                    self.notice = Some(format!("Waking {} devices...", targets.len()));
+                    let _ = self.cmd_tx.try_send(BridgeMessage::WakeBatch {
+                        targets,
+                        timeout: DEFAULT_WAKE_TIMEOUT,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Runs a menu action on the selected host and closes the menu. Actions
+    /// with streamed results open the detail view to show them.
+    pub fn run_action(&mut self, action: HostAction) {
+        self.action_menu = None;
+        if let Some(reason) = self.action_unavailable(action) {
+            self.notice = Some(reason.to_string());
+            return;
+        }
+        let Some(res) = self.selected_result() else {
+            return;
+        };
+        let (ip, mac) = (res.ip, res.mac.clone());
+        match action {
+            HostAction::Rescan => {
+                let _ = self.cmd_tx.try_send(BridgeMessage::RescanHost(ip));
+                self.notice = Some(format!("Rescanning {}", ip));
+            }
+            HostAction::DeepScan => {
+                self.start_deep_scan();
+                self.open_detail();
+            }
+            HostAction::WakeOnLan => match mac {
+                Some(mac) => {
+                    let _ = self.cmd_tx.try_send(BridgeMessage::WakeOnLan(mac));
+                }
+                None => self.notice = Some(format!("No MAC address known for {}", ip)),
+            },
+            HostAction::Traceroute => {
+                self.traceroute = Some(TracerouteView {
+                    ip,
+                    hops: Vec::new(),
+                    finished: false,
+                });
+                let _ = self.cmd_tx.try_send(BridgeMessage::Traceroute(ip));
+                self.open_detail();
+            }
+            HostAction::ConflictWatch => {
+                self.begin_monitoring();
+                let _ = self.cmd_tx.try_send(BridgeMessage::StartConflictWatch {
+                    ip,
+                    interval: DEFAULT_CONFLICT_WATCH_INTERVAL,
+                });
+                self.notice = Some(format!("Watching {} for IP conflicts; s stops", ip));
+            }
+            HostAction::NameDevice => match mac {
+                Some(mac) => {
+                    self.device_note_input = res
+                        .device_note
+                        .as_ref()
+                        .map(ToString::to_string)
+                        .unwrap_or_default();
+                    self.device_note_mac = Some(mac);
+                    self.input_mode = InputMode::DeviceNote;
+                }
+                None => self.notice = Some(format!("No MAC address known for {}", ip)),
+            },
+            HostAction::CopyIp => {
+                self.clipboard = Some(ip.to_string());
+                self.notice = Some(format!("Copied {} to the clipboard", ip));
+            }
+        }
+    }
+
+    /// Opens the full OUI record for the selected host's MAC.
+    ///
+    /// Leaves a notice instead when the host has no MAC or the database has
+    /// no record for it.
+    pub fn show_vendor_details(&mut self) {
+        let Some(mac) = self.selected_result().and_then(|r| r.mac.clone()) else {
+            self.notice = Some("No MAC address for vendor lookup".to_string());
+            return;
+        };
+        self.vendor_details = crate::net::vendor_details(&mac);
+        if self.vendor_details.is_none() {
+            self.notice = Some(format!("No OUI record for {}", mac));
+        }
+    }
+
+    /// Opens the About popup: version, build, settings and data sources.
+    pub fn show_about(&mut self) {
+        self.about = Some(about::about_fields(
+            &self.settings,
+            self.settings_path.as_deref(),
+        ));
+    }
+
+    /// Starts editing the scan label, pre-filled with the current one.
+    pub fn edit_label(&mut self) {
+        self.label_input = match &self.scan_label {
+            Some(ScanLabel {
+                name,
+                description: Some(description),
+            }) => format!("{} | {}", name, description),
+            Some(label) => label.name.clone(),
+            None => String::new(),
+        };
+        self.input_mode = InputMode::Label;
+    }
+
+    /// Saves the typed name and notes for the device being named; a blank
+    /// name forgets the device.
+    pub fn apply_device_note(&mut self) {
+        self.input_mode = InputMode::Normal;
+        if let Some(mac) = self.device_note_mac.take() {
+            let note = DeviceNote::parse(&self.device_note_input);
+            let _ = self
+                .cmd_tx
+                .try_send(BridgeMessage::SetDeviceNote { mac, note });
+        }
+    }
+
+    /// Shows a note reported by [`BridgeMessage::DeviceNoteSaved`] on every
+    /// row with that MAC.
+    pub fn on_device_note_saved(&mut self, mac: &str, note: Option<DeviceNote>) {
+        let key = normalize_mac(mac);
+        self.results.update_where(
+            |row| row.mac.as_deref().and_then(normalize_mac) == key,
+            |res| res.device_note = note.clone(),
+        );
+        self.notice = Some(match note {
+            Some(note) => format!("{} is now named {}", mac, note.name),
+            None => format!("Forgot the name of {}", mac),
+        });
+    }
+
+    /// Applies the edited label to subsequent scans; a blank name clears it.
+    pub fn apply_label(&mut self) {
+        self.scan_label = ScanLabel::parse(&self.label_input);
+        self.input_mode = InputMode::Normal;
         let _ = self
             .cmd_tx
-            .try_send(BridgeMessage::StartScan(self.input.clone()));
+            .try_send(BridgeMessage::SetScanLabel(self.scan_label.clone()));
     }
 
     pub fn stop_scan(&mut self) {
         let _ = self.cmd_tx.try_send(BridgeMessage::StopScan);
     }
 
+    /// Asks before stopping a running scan or monitor, whose remaining hosts
+    /// would only be reported as not scanned; see [`App::on_confirm_stop_key`].
+    pub fn request_stop(&mut self) {
+        if matches!(self.scan_state, ScanState::Scanning | ScanState::Monitoring) {
+            self.confirm_stop = true;
+        } else {
+            self.stop_scan();
+        }
+    }
+
+    /// Stops the scan on `y`; any other key keeps it running.
+    fn on_confirm_stop_key(&mut self, code: KeyCode) {
+        self.confirm_stop = false;
+        if matches!(code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+            self.stop_scan();
+        } else {
+            self.notice = Some("Scan continues".to_string());
+        }
+    }
+
+    /// Stops the running scan or monitor before the program exits. Returns
+    /// whether one was running, i.e. whether to wait for
+    /// [`BridgeMessage::ScanCancelled`].
+    pub fn stop_for_exit(&mut self) -> bool {
+        if !matches!(self.scan_state, ScanState::Scanning | ScanState::Monitoring) {
+            return false;
+        }
+        self.stop_scan();
+        self.notice = Some("Stopping scan...".to_string());
+        true
+    }
+
+    /// Asks the bridge to pause or resume the running scan.
+    ///
+    /// `paused` itself only changes once the scanner confirms with
+    /// [`BridgeMessage::ScanState`].
+    pub fn toggle_pause(&mut self) {
+        if !matches!(self.scan_state, ScanState::Scanning | ScanState::Monitoring) {
+            return;
+        }
+        let msg = if self.paused {
+            BridgeMessage::ResumeScan
+        } else {
+            BridgeMessage::PauseScan
+        };
+        let _ = self.cmd_tx.try_send(msg);
+    }
+
+    pub fn toggle_udp_scan(&mut self) {
+        if !self.capabilities.udp {
+            self.notice = Some("UDP scanning needs UDP, which is unavailable here".to_string());
+            return;
+        }
+        self.udp_scan = !self.udp_scan;
+        let _ = self
+            .cmd_tx
+            .try_send(BridgeMessage::SetUdpScan(self.udp_scan));
+    }
+
+    /// Toggles scanning of `.0`/`.255` addresses on full-subnet ranges.
+    pub fn toggle_subnet_edges(&mut self) {
+        self.include_subnet_edges = !self.include_subnet_edges;
+        let _ = self.cmd_tx.try_send(BridgeMessage::SetIncludeSubnetEdges(
+            self.include_subnet_edges,
+        ));
+    }
+
+    /// Cycles what happens to a new scan while one is running.
+    pub fn cycle_busy_policy(&mut self) {
+        self.busy_policy = match self.busy_policy {
+            BusyPolicy::Replace => BusyPolicy::Queue,
+            BusyPolicy::Queue => BusyPolicy::Reject,
+            BusyPolicy::Reject => BusyPolicy::Parallel,
+            BusyPolicy::Parallel => BusyPolicy::Replace,
+        };
+        let _ = self
+            .cmd_tx
+            .try_send(BridgeMessage::SetBusyPolicy(self.busy_policy));
+    }
+
+    /// Cycles the echo attempts per host through 1, 3 and 5.
+    pub fn cycle_ping_attempts(&mut self) {
+        self.ping_attempts = match self.ping_attempts {
+            1 => 3,
+            3 => 5,
+            _ => 1,
+        };
+        let _ = self
+            .cmd_tx
+            .try_send(BridgeMessage::SetPingAttempts(self.ping_attempts));
+    }
+
     pub fn next_row(&mut self) {
         let i = match self.table_state.selected() {
             Some(i) => {
@@ -101,21 +1243,379 @@ impl App {
         self.table_state.select(Some(i));
     }
 
-    pub fn sort_results(&mut self) {
-        self.results.sort_by(|a, b| a.ip.cmp(&b.ip));
+    /// Moves the cursor one page down, stopping at the last row.
+    pub fn next_page(&mut self) {
+        let last = self.filtered_results().len().saturating_sub(1);
+        let i = self
+            .table_state
+            .selected()
+            .map_or(0, |i| i + self.page_size);
+        self.table_state.select(Some(i.min(last)));
     }
 
-    /// Processes a key press event and updates application state.
+    /// Moves the cursor one page up, stopping at the first row.
+    pub fn previous_page(&mut self) {
+        let i = self
+            .table_state
+            .selected()
+            .map_or(0, |i| i.saturating_sub(self.page_size));
+        self.table_state.select(Some(i));
+    }
+
+    /// Scrolls the table so the cursor is on screen and returns the page of
+    /// filtered rows to draw, given `total` rows and room for `height`.
     ///
-    /// Delegates to the current mode's handler:
-    /// - **Editing**: character input, backspace, enter (start scan), escape.
-    /// - **Detail view**: escape/q to close popup.
-    /// - **Normal**: quit, edit mode, stop scan, navigation, detail view, filter.
+    /// Only this page is turned into widgets, so redraws stay cheap when a
+    /// scan returns tens of thousands of rows.
+    pub fn visible_rows(&mut self, height: usize, total: usize) -> Range<usize> {
+        self.page_size = height.max(1);
+        let mut offset = self.table_state.offset().min(total.saturating_sub(1));
+        if let Some(selected) = self.table_state.selected() {
+            if selected < offset {
+                offset = selected;
+            } else if selected >= offset + self.page_size {
+                offset = selected + 1 - self.page_size;
+            }
+        }
+        *self.table_state.offset_mut() = offset;
+        offset..(offset + self.page_size).min(total)
+    }
+
+    /// Inserts or replaces a streamed result, keeping `results` sorted by IP.
     ///
-    /// # Parameters
-    /// - `code`: The `KeyCode` of the pressed key.
-    pub fn on_key(&mut self, code: KeyCode) {
-        if self.input_mode == InputMode::Editing {
+    /// A replaced row keeps when its host was first and last seen online.
+    /// The selection follows the highlighted host when rows are inserted above
+    /// it, or moves to the next visible one if the update hides it.
+    pub fn upsert_result(&mut self, res: ScanResult) {
+        let selected_ip = self.selected_result().map(|r| r.ip);
+        self.next_arrival += 1;
+        self.arrivals.insert(res.ip, self.next_arrival);
+        self.results.upsert(res);
+        if let Some(ip) = selected_ip {
+            self.select_ip(ip);
+        }
+    }
+
+    /// Adds a port reported by [`BridgeMessage::PortFound`] to its host's
+    /// row, creating an online row for a host not listed yet. The host's
+    /// final result replaces the row.
+    pub fn on_port_found(&mut self, ip: Ipv4Addr, port: u16) {
+        let mut res = self
+            .results
+            .load(ip)
+            .map_or_else(|| ScanResult::new(ip), Cow::into_owned);
+        if let Err(i) = res.open_ports.binary_search_by_key(&port, |p| p.port) {
+            res.open_ports.insert(i, PortInfo::new(port));
+        }
+        res.status = ScanStatus::Online;
+        self.upsert_result(res);
+    }
+
+    /// Shows or hides offline hosts, keeping the cursor on the same host.
+    pub fn toggle_filter_online(&mut self) {
+        let selected_ip = self.selected_result().map(|r| r.ip);
+        self.filter_online = !self.filter_online;
+        if let Some(ip) = selected_ip {
+            self.select_ip(ip);
+        }
+    }
+
+    /// Opens the `:` command line.
+    pub fn start_command(&mut self) {
+        self.command_input.clear();
+        self.input_mode = InputMode::Command;
+    }
+
+    /// Runs the typed command line and closes it; a line that does not
+    /// parse is shown in the error banner.
+    pub fn run_command(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let line = std::mem::take(&mut self.command_input);
+        match Command::parse(&line) {
+            Ok(command) => self.execute(command),
+            Err(e) => self.show_error(e),
+        }
+    }
+
+    /// Carries out `command` the way its key binding would.
+    pub fn execute(&mut self, command: Command) {
+        if self.read_only && command.scans() {
+            self.show_error("Saved results are read-only; they cannot be scanned".to_string());
+            return;
+        }
+        match command {
+            Command::Scan(target) => {
+                if let Some(target) = target {
+                    self.input = target;
+                }
+                self.start_scan();
+            }
+            Command::Stop => self.request_stop(),
+            Command::Ports(ports) => {
+                self.notice = Some(format!("Later scans probe {} TCP ports", ports.len()));
+                self.settings.ports = ports;
+                let _ = self.cmd_tx.try_send(BridgeMessage::SetScanConfig(Box::new(
+                    self.settings.scan_config(),
+                )));
+            }
+            Command::Export(path) => {
+                if let Some(path) = path {
+                    self.export_path = path;
+                }
+                self.export(ExportScope::Visible);
+            }
+            Command::Filter(filter) => {
+                let online = match &filter {
+                    CommandFilter::Online => true,
+                    CommandFilter::All => false,
+                    CommandFilter::Search(_) => self.filter_online,
+                };
+                if online != self.filter_online {
+                    self.toggle_filter_online();
+                }
+                match filter {
+                    CommandFilter::Online => {}
+                    CommandFilter::All => self.set_search(String::new()),
+                    CommandFilter::Search(text) => self.set_search(text),
+                }
+            }
+            Command::Quit => self.should_quit = true,
+        }
+    }
+
+    /// Opens the search box, keeping the current search for editing.
+    pub fn start_search(&mut self) {
+        self.input_mode = InputMode::Search;
+    }
+
+    /// Replaces the search, keeping the cursor on the same host while it
+    /// still matches and on the first row otherwise.
+    fn set_search(&mut self, search: String) {
+        let selected_ip = self.selected_result().map(|r| r.ip);
+        self.search = search;
+        let visible = self.filtered_results();
+        let idx = selected_ip
+            .and_then(|ip| visible.iter().position(|r| r.ip == ip))
+            .or((!visible.is_empty()).then_some(0));
+        self.table_state.select(idx);
+    }
+
+    /// Moves the cursor to `ip`. When the filter hides `ip`, it goes to the
+    /// next visible host by address in ascending IP order, and stays put in
+    /// any other order.
+    fn select_ip(&mut self, ip: Ipv4Addr) {
+        let rows = self.filtered_results();
+        let last = rows.len().checked_sub(1);
+        let idx = if self.sorted_by_ip() {
+            rows.iter().position(|r| r.ip >= ip).or(last)
+        } else {
+            rows.iter()
+                .position(|r| r.ip == ip)
+                .or_else(|| Some(self.table_state.selected()?.min(last?)))
+        };
+        self.table_state.select(idx);
+    }
+
+    /// Switches between IP order and newest response first, keeping the
+    /// cursor on the same host.
+    pub fn toggle_result_order(&mut self) {
+        let selected_ip = self.selected_result().map(|r| r.ip);
+        self.result_order = self.result_order.toggled();
+        if let Some(ip) = selected_ip {
+            self.select_ip(ip);
+        }
+    }
+
+    /// Sorts by the next [`SortKey`], ascending, keeping the cursor on the
+    /// same host. Leaves live order, which would hide the sort.
+    pub fn cycle_sort_key(&mut self) {
+        let selected_ip = self.selected_result().map(|r| r.ip);
+        self.result_order = ResultOrder::Ip;
+        self.sort_key = self.sort_key.next();
+        self.sort_descending = false;
+        if let Some(ip) = selected_ip {
+            self.select_ip(ip);
+        }
+    }
+
+    /// Flips between ascending and descending [`App::sort_key`], keeping the
+    /// cursor on the same host.
+    pub fn toggle_sort_direction(&mut self) {
+        let selected_ip = self.selected_result().map(|r| r.ip);
+        self.result_order = ResultOrder::Ip;
+        self.sort_descending = !self.sort_descending;
+        if let Some(ip) = selected_ip {
+            self.select_ip(ip);
+        }
+    }
+
+    /// Marks the scan cancelled and says how many targets it never reached.
+    pub fn on_scan_cancelled(&mut self) {
+        self.scan_state = ScanState::Cancelled;
+        let skipped = self
+            .results
+            .iter()
+            .filter(|r| r.status == ScanStatus::NotScanned)
+            .count();
+        if skipped > 0 {
+            self.notice = Some(format!("Scan cancelled: {} targets not scanned", skipped));
+        }
+    }
+
+    /// Shows `message` in the error banner, restarting its timeout.
+    pub fn show_error(&mut self, message: String) {
+        self.error = Some(message);
+        self.error_shown = Some(Instant::now());
+    }
+
+    /// Hides the error banner.
+    pub fn dismiss_error(&mut self) {
+        self.error = None;
+        self.error_shown = None;
+    }
+
+    /// Appends a line to the event log, dropping the oldest past
+    /// [`EVENT_LOG_CAPACITY`]. A scrolled-back pane keeps showing the same lines.
+    pub fn log_event(&mut self, text: String, is_error: bool) {
+        let secs = SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() % 86_400)
+            .unwrap_or(0);
+        self.event_log.push_back(EventLine {
+            time: format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60),
+            text,
+            is_error,
+        });
+        if self.event_log.len() > EVENT_LOG_CAPACITY {
+            self.event_log.pop_front();
+        }
+        if self.event_log_scroll > 0 {
+            self.event_log_scroll = (self.event_log_scroll + 1).min(self.event_log.len() - 1);
+        }
+    }
+
+    /// Logs the bridge messages worth seeing while debugging a scan: errors,
+    /// hosts that failed with a system error, pauses, cancellations and,
+    /// every [`STATS_LOG_INTERVAL`], the packet rate.
+    pub fn record_bridge_event(&mut self, msg: &BridgeMessage) {
+        let (text, is_error) = match msg {
+            BridgeMessage::Error(e) => (format!("Error: {}", e), true),
+            BridgeMessage::ScanUpdate(res) => match &res.status {
+                ScanStatus::SystemError(e) => (format!("{}: {}", res.ip, e), true),
+                _ => return,
+            },
+            BridgeMessage::ScanStarted(Some(label)) => (format!("Scan started: {}", label), false),
+            BridgeMessage::ScanStarted(None) => ("Scan started".to_string(), false),
+            BridgeMessage::ScanComplete => ("Scan complete".to_string(), false),
+            // Where it stopped, so a half-full gauge is not read as a result.
+            BridgeMessage::ScanCancelled => match &self.phase {
+                Some(phase) => (format!("Scan cancelled at {}", phase), false),
+                None => ("Scan cancelled".to_string(), false),
+            },
+            BridgeMessage::ScanQueued(position) => (format!("Scan queued (#{})", position), false),
+            BridgeMessage::ScanRejected => {
+                ("Scan rejected: another scan is running".to_string(), true)
+            }
+            BridgeMessage::ScanStats(stats) => {
+                if self
+                    .last_stats_logged
+                    .is_some_and(|t| t.elapsed() < STATS_LOG_INTERVAL)
+                {
+                    return;
+                }
+                self.last_stats_logged = Some(Instant::now());
+                (format!("Rate: {}", stats), false)
+            }
+            BridgeMessage::ScanSummary(summary) => (format!("Summary: {}", summary), false),
+            BridgeMessage::ScanPaused(reason) => (format!("Paused: {}", reason), false),
+            BridgeMessage::ScanResumed => ("Network settled, resumed".to_string(), false),
+            BridgeMessage::SystemSuspended => ("System going to sleep".to_string(), false),
+            BridgeMessage::SystemResumed => ("System woke from sleep".to_string(), false),
+            BridgeMessage::InterceptionSuspected(warning) => (warning.to_string(), true),
+            BridgeMessage::ConfigReloaded(_) => ("Settings reloaded".to_string(), false),
+            BridgeMessage::WakeOnLanFailed(mac, e) => {
+                (format!("Could not wake {}: {}", mac, e), true)
+            }
+            BridgeMessage::WakeBatchComplete(report) => {
+                for outcome in &report.outcomes {
+                    let failed = outcome.online_after.is_none();
+                    self.log_event(format!("Wake: {}", outcome), failed);
+                }
+                (format!("Wake-on-LAN: {}", report.summary()), false)
+            }
+            BridgeMessage::ConfigReloadFailed(e) => (format!("Settings not reloaded: {}", e), true),
+            _ => return,
+        };
+        self.log_event(text, is_error);
+    }
+
+    /// Opens the detail popup for the selected row, scrolled to the top.
+    pub fn open_detail(&mut self) {
+        self.show_detail = true;
+        self.detail_scroll = 0;
+    }
+
+    /// Scrolls the detail popup by `lines`, up for negative values.
+    pub fn scroll_detail(&mut self, lines: i16) {
+        self.detail_scroll = self.detail_scroll.saturating_add_signed(lines);
+    }
+
+    /// Shows or hides the event log pane, back at the newest line.
+    pub fn toggle_event_log(&mut self) {
+        self.show_event_log = !self.show_event_log;
+        self.event_log_scroll = 0;
+    }
+
+    /// Scrolls the shown event log pane one line back in time, or forward.
+    pub fn scroll_event_log(&mut self, older: bool) {
+        if !self.show_event_log {
+            return;
+        }
+        self.event_log_scroll = if older {
+            (self.event_log_scroll + 1).min(self.event_log.len().saturating_sub(1))
+        } else {
+            self.event_log_scroll.saturating_sub(1)
+        };
+    }
+
+    /// Called on every tick: hides the error banner after [`ERROR_BANNER_TIMEOUT`].
+    pub fn on_tick(&mut self) {
+        if self
+            .error_shown
+            .is_some_and(|shown| shown.elapsed() >= ERROR_BANNER_TIMEOUT)
+        {
+            self.dismiss_error();
+        }
+    }
+
+    /// Processes a key press event and updates application state.
+    ///
+    /// Esc first dismisses a visible error banner. Otherwise delegates to the
+    /// current mode's handler:
+    /// - **Stop confirmation**: y stops the scan, any other key keeps it running.
+    /// - **Editing**: character input, backspace, enter (start scan), escape.
+    /// - **Label**: character input, backspace, enter (apply label), escape.
+    /// - **Device note**: character input, backspace, enter (save the name and
+    ///   notes), escape.
+    /// - **Search**: character input and backspace (filtering as typed), enter
+    ///   (keep the search), escape (clear it).
+    /// - **Vendor details**: escape/q/v to close popup.
+    /// - **Detail view**: escape/q to close popup, d to deep scan, v for vendor details.
+    /// - **Normal**: quit (escape clears an active search first), edit mode, search,
+    ///   stop scan, navigation (including PgUp/PgDn), detail view, vendor details,
+    ///   filter, UDP toggle, ping attempts, scan label, export, result order.
+    ///
+    /// # Parameters
+    /// - `code`: The `KeyCode` of the pressed key.
+    pub fn on_key(&mut self, code: KeyCode) {
+        if self.error.is_some() && code == KeyCode::Esc {
+            self.dismiss_error();
+        } else if let Some(setup) = &mut self.setup {
+            let outcome = setup.on_key(code);
+            self.on_setup_outcome(outcome);
+        } else if self.confirm_stop {
+            self.on_confirm_stop_key(code);
+        } else if self.input_mode == InputMode::Editing {
             match code {
                 KeyCode::Enter => {
                     self.input_mode = InputMode::Normal;
@@ -128,25 +1628,202 @@ impl App {
                 KeyCode::Esc => self.input_mode = InputMode::Normal,
                 _ => {}
             }
+        } else if self.input_mode == InputMode::Label {
+            match code {
+                KeyCode::Enter => self.apply_label(),
+                KeyCode::Char(c) => self.label_input.push(c),
+                KeyCode::Backspace => {
+                    self.label_input.pop();
+                }
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                _ => {}
+            }
+        } else if self.input_mode == InputMode::DeviceNote {
+            match code {
+                KeyCode::Enter => self.apply_device_note(),
+                KeyCode::Char(c) => self.device_note_input.push(c),
+                KeyCode::Backspace => {
+                    self.device_note_input.pop();
+                }
+                KeyCode::Esc => {
+                    self.device_note_mac = None;
+                    self.input_mode = InputMode::Normal;
+                }
+                _ => {}
+            }
+        } else if self.input_mode == InputMode::Command {
+            match code {
+                KeyCode::Enter => self.run_command(),
+                KeyCode::Char(c) => self.command_input.push(c),
+                // Deleting past the start leaves the command line, as in vim.
+                KeyCode::Backspace if self.command_input.pop().is_none() => {
+                    self.input_mode = InputMode::Normal
+                }
+                KeyCode::Esc => self.input_mode = InputMode::Normal,
+                _ => {}
+            }
+        } else if self.input_mode == InputMode::Search {
+            match code {
+                KeyCode::Enter => self.input_mode = InputMode::Normal,
+                KeyCode::Char(c) => {
+                    let mut search = self.search.clone();
+                    search.push(c);
+                    self.set_search(search);
+                }
+                KeyCode::Backspace => {
+                    let mut search = self.search.clone();
+                    search.pop();
+                    self.set_search(search);
+                }
+                KeyCode::Esc => {
+                    self.input_mode = InputMode::Normal;
+                    self.set_search(String::new());
+                }
+                _ => {}
+            }
+        } else if let Some(highlighted) = self.action_menu {
+            self.on_action_menu_key(code, highlighted);
+        } else if let Some(highlighted) = self.wake_menu {
+            self.on_wake_menu_key(code, highlighted);
+        } else if self.reach_matrix.is_some() {
+            match code {
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('M') => self.reach_matrix = None,
+                KeyCode::Char('x') => self.export_reach_matrix(),
+                _ => {}
+            }
+        } else if self.vendor_details.is_some() {
+            if matches!(code, KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('v')) {
+                self.vendor_details = None;
+            }
+        } else if self.about.is_some() {
+            if matches!(code, KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('?')) {
+                self.about = None;
+            }
         } else if self.show_detail {
-            if code == KeyCode::Esc || code == KeyCode::Char('q') {
-                self.show_detail = false;
+            match code {
+                KeyCode::Esc | KeyCode::Char('q') => self.show_detail = false,
+                KeyCode::Char('j') | KeyCode::Down => self.scroll_detail(1),
+                KeyCode::Char('k') | KeyCode::Up => self.scroll_detail(-1),
+                KeyCode::PageDown => self.scroll_detail(DETAIL_PAGE_LINES),
+                KeyCode::PageUp => self.scroll_detail(-DETAIL_PAGE_LINES),
+                KeyCode::Char('d') if !self.read_only => self.start_deep_scan(),
+                KeyCode::Char('a') => self.open_action_menu(),
+                KeyCode::Char('v') => self.show_vendor_details(),
+                _ => {}
+            }
+        } else if code == KeyCode::Esc && !self.search.is_empty() {
+            self.set_search(String::new());
+        } else if self.read_only {
+            match code {
+                KeyCode::Char('q') | KeyCode::Esc => self.should_quit = true,
+                KeyCode::Char('/') => self.start_search(),
+                KeyCode::Char(':') => self.start_command(),
+                KeyCode::Char('j') | KeyCode::Down => self.next_row(),
+                KeyCode::Char('k') | KeyCode::Up => self.previous_row(),
+                KeyCode::PageDown => self.next_page(),
+                KeyCode::PageUp => self.previous_page(),
+                KeyCode::Enter => self.open_detail(),
+                KeyCode::Char('v') => self.show_vendor_details(),
+                KeyCode::Char('a') => self.open_action_menu(),
+                KeyCode::Tab => self.toggle_filter_online(),
+                KeyCode::Char('o') => self.cycle_sort_key(),
+                KeyCode::Char('O') => self.toggle_sort_direction(),
+                KeyCode::Char('L') => self.toggle_event_log(),
+                KeyCode::Char('[') => self.scroll_event_log(true),
+                KeyCode::Char(']') => self.scroll_event_log(false),
+                KeyCode::Char('x') => self.export(ExportScope::Visible),
+                KeyCode::Char('X') => self.export(ExportScope::Selected),
+                KeyCode::Char('?') => self.show_about(),
+                _ => {}
             }
         } else {
             match code {
                 KeyCode::Char('q') | KeyCode::Esc => self.should_quit = true,
                 KeyCode::Char('i') | KeyCode::Char('e') => self.input_mode = InputMode::Editing,
-                KeyCode::Char('s') => self.stop_scan(),
+                KeyCode::Char('/') => self.start_search(),
+                KeyCode::Char(':') => self.start_command(),
+                KeyCode::Char('s') => self.request_stop(),
+                KeyCode::Char(' ') => self.toggle_pause(),
                 KeyCode::Char('j') | KeyCode::Down => self.next_row(),
                 KeyCode::Char('k') | KeyCode::Up => self.previous_row(),
-                KeyCode::Enter => self.show_detail = true,
-                KeyCode::Tab => self.filter_online = !self.filter_online,
+                KeyCode::PageDown => self.next_page(),
+                KeyCode::PageUp => self.previous_page(),
+                KeyCode::Enter => self.open_detail(),
+                KeyCode::Char('v') => self.show_vendor_details(),
+                KeyCode::Char('a') => self.open_action_menu(),
+                KeyCode::Tab => self.toggle_filter_online(),
+                KeyCode::Char('u') => self.toggle_udp_scan(),
+                KeyCode::Char('p') => self.cycle_ping_attempts(),
+                KeyCode::Char('b') => self.toggle_subnet_edges(),
+                KeyCode::Char('m') => self.start_monitor(),
+                KeyCode::Char('P') => self.start_arp_watch(),
+                KeyCode::Char('B') => self.cycle_busy_policy(),
+                KeyCode::Char('W') => self.open_wake_menu(),
+                KeyCode::Char('M') => self.start_reach_matrix(),
+                KeyCode::Char('n') => self.edit_label(),
+                KeyCode::Char('l') => self.toggle_result_order(),
+                KeyCode::Char('o') => self.cycle_sort_key(),
+                KeyCode::Char('O') => self.toggle_sort_direction(),
+                KeyCode::Char('L') => self.toggle_event_log(),
+                KeyCode::Char('[') => self.scroll_event_log(true),
+                KeyCode::Char(']') => self.scroll_event_log(false),
+                KeyCode::Char('x') => self.export(ExportScope::Visible),
+                KeyCode::Char('X') => self.export(ExportScope::Selected),
+                KeyCode::Char('?') => self.show_about(),
                 _ => {}
             }
         }
     }
 }
 
+/// Whether the IP, hostname, MAC, vendor or device name and notes of `res`
+/// contain `query`, which must already be ASCII-lowercase. An empty query
+/// matches everything.
+pub fn matches_search(res: &HostRow, query: &str) -> bool {
+    if query.is_empty() {
+        return true;
+    }
+    let contains = |field: &str| field.to_ascii_lowercase().contains(query);
+    let (name, notes) = match &res.device_note {
+        Some(note) => (Some(&note.name), note.notes.as_ref()),
+        None => (None, None),
+    };
+    contains(&res.ip.to_string())
+        || [
+            res.hostname.as_ref(),
+            res.mac.as_ref(),
+            res.vendor.as_ref(),
+            name,
+            notes,
+        ]
+        .into_iter()
+        .flatten()
+        .any(|field| contains(field))
+}
+
+/// Terminal escape sequence (OSC 52) that puts `text` on the clipboard of
+/// the terminal running the TUI, local or over SSH.
+pub fn osc52_copy(text: &str) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in text.as_bytes().chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    format!("\x1b]52;c;{}\x07", encoded)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -194,6 +1871,307 @@ mod tests {
         assert_eq!(app.scan_state, ScanState::Scanning);
     }
 
+    #[test]
+    fn test_viewer_ignores_scan_keys() {
+        let mut online = ScanResult::new(Ipv4Addr::new(10, 0, 0, 1));
+        online.status = crate::types::ScanStatus::Online;
+        let results = vec![online, ScanResult::new(Ipv4Addr::new(10, 0, 0, 2))];
+        let mut app = App::viewer("audit.json", results, Some(ScanLabel::new("audit")));
+        for key in ['i', 'e', 's', 'm', 'u', 'p', 'b', 'B', 'n', 'M'] {
+            app.on_key(KeyCode::Char(key));
+        }
+        app.on_key(KeyCode::Enter);
+        assert!(app.show_detail);
+        app.on_key(KeyCode::Char('d'));
+        assert_eq!(app.deep_scan, None);
+        app.on_key(KeyCode::Esc);
+
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.scan_state, ScanState::Complete);
+        assert!(!app.udp_scan && !app.include_subnet_edges);
+        assert_eq!((app.ping_attempts, app.scan_label.clone()), (1, None));
+        assert_eq!(app.input, "audit.json");
+        assert_eq!(app.reach_matrix, None);
+        app.on_key(KeyCode::Tab);
+        assert_eq!(app.filtered_results().len(), 1);
+        app.on_key(KeyCode::Char('q'));
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn test_x_exports_the_filtered_view_and_shift_x_the_cursor_row() {
+        let mut online = ScanResult::new(Ipv4Addr::new(10, 0, 0, 1));
+        online.status = ScanStatus::Online;
+        let results = vec![online, ScanResult::new(Ipv4Addr::new(10, 0, 0, 2))];
+        let mut app = App::viewer("audit.json", results, None);
+        app.export_path =
+            std::env::temp_dir().join(format!("ragescanner-export-{}.csv", std::process::id()));
+        let exported = |app: &App| std::fs::read_to_string(&app.export_path).unwrap();
+
+        app.on_key(KeyCode::Char('x'));
+        assert_eq!(exported(&app).lines().count(), 3);
+        app.on_key(KeyCode::Tab);
+        app.on_key(KeyCode::Char('x'));
+        assert_eq!(exported(&app).lines().count(), 2);
+        assert!(
+            app.notice
+                .as_deref()
+                .unwrap()
+                .starts_with("Exported 1 hosts")
+        );
+
+        app.on_key(KeyCode::Tab);
+        app.table_state.select(Some(1));
+        app.on_key(KeyCode::Char('X'));
+        let csv = exported(&app);
+        assert_eq!(
+            csv.lines().nth(1).unwrap().split(',').next(),
+            Some("10.0.0.2")
+        );
+        assert_eq!(csv.lines().count(), 2);
+        let _ = std::fs::remove_file(&app.export_path);
+    }
+
+    #[test]
+    fn test_m_starts_monitor() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let mut app = App::new(tx);
+        app.on_key(KeyCode::Char('m'));
+        assert_eq!(app.scan_state, ScanState::Monitoring);
+        match rx.try_recv() {
+            Ok(BridgeMessage::StartMonitor { interval, range }) => {
+                assert_eq!(interval, MONITOR_INTERVAL);
+                assert_eq!(range, app.input);
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+
+        app.on_key(KeyCode::Char('P'));
+        match rx.try_recv() {
+            Ok(BridgeMessage::StartArpWatch { interval, range }) => {
+                assert_eq!(interval, DEFAULT_ARP_WATCH_INTERVAL);
+                assert_eq!(range, app.input);
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_settings_restore_range_and_save_the_next_one() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let mut app = App::new(tx);
+        let settings = Settings {
+            last_range: Some("10.0.0.1-20".to_string()),
+            concurrency: 8,
+            ..Settings::default()
+        };
+        app.apply_settings(settings.clone());
+        assert_eq!(app.input, "10.0.0.1-20");
+        match rx.try_recv() {
+            Ok(BridgeMessage::SetScanConfig(config)) => {
+                assert_eq!(*config, settings.scan_config());
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+
+        let path = std::env::temp_dir().join(format!(
+            "ragescanner-tui-settings-{}.toml",
+            std::process::id()
+        ));
+        app.settings_path = Some(path.clone());
+        app.input = "10.0.1.0/24".to_string();
+        app.input_mode = InputMode::Editing;
+        app.on_key(KeyCode::Enter);
+        let saved = Settings::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(saved.last_range.as_deref(), Some("10.0.1.0/24"));
+        assert_eq!(saved.concurrency, 8);
+    }
+
+    #[test]
+    fn test_exit_stops_a_running_scan_only() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let mut app = App::new(tx);
+        assert!(!app.stop_for_exit());
+        assert!(rx.try_recv().is_err());
+
+        app.scan_state = ScanState::Monitoring;
+        assert!(app.stop_for_exit());
+        assert!(matches!(rx.try_recv(), Ok(BridgeMessage::StopScan)));
+        assert_eq!(app.notice.as_deref(), Some("Stopping scan..."));
+    }
+
+    #[test]
+    fn test_stop_asks_before_cancelling_a_running_scan() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let mut app = App::new(tx);
+        app.scan_state = ScanState::Scanning;
+
+        app.on_key(KeyCode::Char('s'));
+        assert!(app.confirm_stop);
+        app.on_key(KeyCode::Char('n'));
+        assert!(!app.confirm_stop);
+        assert!(rx.try_recv().is_err());
+        assert_eq!(app.scan_state, ScanState::Scanning);
+        assert_eq!(app.notice.as_deref(), Some("Scan continues"));
+
+        app.execute(Command::Stop);
+        app.on_key(KeyCode::Char('y'));
+        assert!(!app.confirm_stop);
+        assert!(matches!(rx.try_recv(), Ok(BridgeMessage::StopScan)));
+    }
+
+    #[test]
+    fn test_finished_setup_applies_and_saves_settings() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let mut app = App::new(tx);
+        let path =
+            std::env::temp_dir().join(format!("ragescanner-tui-setup-{}.toml", std::process::id()));
+        app.settings_path = Some(path.clone());
+        app.setup = Some(SetupWizard::new(Vec::new(), "10.0.0.1-20"));
+        for _ in 0..3 {
+            app.on_key(KeyCode::Enter);
+        }
+        assert_eq!(app.setup, None);
+        assert_eq!(app.input, "10.0.0.1-20");
+        assert!(matches!(rx.try_recv(), Ok(BridgeMessage::SetScanConfig(_))));
+        let saved = Settings::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(saved.last_range.as_deref(), Some("10.0.0.1-20"));
+    }
+
+    #[test]
+    fn test_d_deep_scans_selected_host() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let mut app = App::new(tx);
+        let ip = Ipv4Addr::new(10, 0, 0, 7);
+        app.results.upsert(ScanResult::new(ip));
+        app.table_state.select(Some(0));
+        app.show_detail = true;
+
+        app.on_key(KeyCode::Char('d'));
+        assert!(matches!(rx.try_recv(), Ok(BridgeMessage::StartDeepScan(host)) if host == ip));
+
+        // Events for other hosts are ignored.
+        app.on_deep_scan_event(BridgeMessage::DeepScanProgress(
+            Ipv4Addr::new(10, 0, 0, 8),
+            50,
+        ));
+        app.on_deep_scan_event(BridgeMessage::DeepScanPort(ip, PortInfo::new(8443)));
+        let view = app.deep_scan.as_ref().unwrap();
+        assert_eq!(view.progress, 0);
+        assert_eq!(view.ports, vec![PortInfo::new(8443)]);
+
+        app.on_deep_scan_event(BridgeMessage::DeepScanComplete(
+            ip,
+            vec![PortInfo::new(8443)],
+        ));
+        assert!(app.deep_scan.as_ref().unwrap().finished);
+    }
+
+    #[test]
+    fn test_pages_through_large_result_sets() {
+        let mut app = test_app();
+        for i in 0..50_000u32 {
+            app.results
+                .upsert(ScanResult::new(Ipv4Addr::from(0x0A00_0000 + i)));
+        }
+        let total = app.results.len();
+        assert_eq!(app.visible_rows(20, total), 0..20);
+
+        app.table_state.select(Some(0));
+        app.on_key(KeyCode::PageDown);
+        assert_eq!(app.table_state.selected(), Some(20));
+        assert_eq!(app.visible_rows(20, total), 1..21);
+
+        app.table_state.select(Some(total - 5));
+        app.on_key(KeyCode::PageDown);
+        assert_eq!(app.table_state.selected(), Some(total - 1));
+        assert_eq!(app.visible_rows(20, total), total - 20..total);
+
+        app.on_key(KeyCode::PageUp);
+        assert_eq!(app.table_state.selected(), Some(total - 21));
+        assert_eq!(app.visible_rows(20, total), total - 21..total - 1);
+
+        // The first hosts' full results went to disk; their details come back.
+        app.table_state.select(Some(0));
+        let detail = app.selected_detail().unwrap();
+        assert!(matches!(detail, Cow::Owned(_)));
+        assert_eq!(detail.ip, Ipv4Addr::new(10, 0, 0, 0));
+    }
+
+    #[test]
+    fn test_v_without_mac_leaves_notice() {
+        let mut app = test_app();
+        app.results
+            .upsert(ScanResult::new(Ipv4Addr::new(10, 0, 0, 7)));
+        app.table_state.select(Some(0));
+        app.on_key(KeyCode::Char('v'));
+        assert_eq!(app.vendor_details, None);
+        assert!(app.notice.as_deref().unwrap().contains("No MAC"));
+
+        // An open vendor popup takes the keys until closed.
+        app.vendor_details = Some(VendorDetails::default());
+        app.on_key(KeyCode::Char('q'));
+        assert!(!app.should_quit);
+        assert_eq!(app.vendor_details, None);
+    }
+
+    #[test]
+    fn test_question_mark_toggles_about_popup() {
+        let mut app = test_app();
+        app.on_key(KeyCode::Char('?'));
+        let about = app.about.as_ref().unwrap();
+        assert!(about.iter().any(|(label, _)| *label == "Vendors"));
+
+        // The popup takes the keys until closed.
+        app.on_key(KeyCode::Char('q'));
+        assert!(!app.should_quit);
+        assert_eq!(app.about, None);
+    }
+
+    #[test]
+    fn test_n_edits_scan_label() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let mut app = App::new(tx);
+        app.on_key(KeyCode::Char('n'));
+        assert_eq!(app.input_mode, InputMode::Label);
+        for c in "3F | audit".chars() {
+            app.on_key(KeyCode::Char(c));
+        }
+        app.on_key(KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        let expected = ScanLabel {
+            name: "3F".to_string(),
+            description: Some("audit".to_string()),
+        };
+        assert_eq!(app.scan_label.as_ref(), Some(&expected));
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(BridgeMessage::SetScanLabel(Some(label))) if label == expected
+        ));
+
+        // Editing again starts from the current label.
+        app.on_key(KeyCode::Char('n'));
+        assert_eq!(app.label_input, "3F | audit");
+    }
+
+    #[test]
+    fn test_shift_b_cycles_busy_policy() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let mut app = App::new(tx);
+        app.on_key(KeyCode::Char('B'));
+        assert_eq!(app.busy_policy, BusyPolicy::Queue);
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(BridgeMessage::SetBusyPolicy(BusyPolicy::Queue))
+        ));
+        for _ in 0..3 {
+            app.on_key(KeyCode::Char('B'));
+        }
+        assert_eq!(app.busy_policy, BusyPolicy::Replace);
+    }
+
     #[test]
     fn test_esc_exits_editing() {
         let mut app = test_app();
@@ -210,6 +2188,254 @@ mod tests {
         assert!(app.should_quit);
     }
 
+    #[test]
+    fn test_detail_popup_scrolls_and_reopens_at_the_top() {
+        let mut app = test_app();
+        app.on_key(KeyCode::Enter);
+        app.on_key(KeyCode::Down);
+        app.on_key(KeyCode::PageDown);
+        assert_eq!(app.detail_scroll, 1 + DETAIL_PAGE_LINES as u16);
+        app.on_key(KeyCode::Char('k'));
+        app.on_key(KeyCode::PageUp);
+        app.on_key(KeyCode::PageUp);
+        assert_eq!(app.detail_scroll, 0);
+        assert_eq!(app.table_state.selected(), None);
+
+        app.on_key(KeyCode::Char('j'));
+        app.on_key(KeyCode::Esc);
+        app.on_key(KeyCode::Enter);
+        assert!(app.show_detail);
+        assert_eq!(app.detail_scroll, 0);
+    }
+
+    #[test]
+    fn test_missing_capabilities_gray_out_actions() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let mut app = App::new(tx);
+        app.capabilities = Capabilities {
+            icmp: false,
+            udp: false,
+            ..Capabilities::default()
+        };
+        let mut res = ScanResult::new(Ipv4Addr::new(10, 0, 0, 7));
+        res.mac = Some("AA:BB:CC:DD:EE:FF".to_string());
+        app.upsert_result(res);
+        app.table_state.select(Some(0));
+
+        // Still listed, but refused with the reason.
+        assert_eq!(app.actions(), HostAction::ALL);
+        assert!(app.action_unavailable(HostAction::Rescan).is_none());
+        app.on_key(KeyCode::Char('a'));
+        app.on_key(KeyCode::Char('t'));
+        assert!(app.notice.as_deref().unwrap().contains("ICMP"));
+        assert!(app.traceroute.is_none());
+        app.on_key(KeyCode::Char('a'));
+        app.on_key(KeyCode::Char('w'));
+        assert!(app.notice.as_deref().unwrap().contains("UDP"));
+        app.capabilities.arp = false;
+        app.on_key(KeyCode::Char('a'));
+        app.on_key(KeyCode::Char('i'));
+        assert!(app.notice.as_deref().unwrap().contains("ARP"));
+        app.open_wake_menu();
+        assert_eq!(app.wake_menu, None);
+        app.toggle_udp_scan();
+        assert!(!app.udp_scan);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_action_menu_runs_the_chosen_action() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let mut app = App::new(tx);
+        let mut res = ScanResult::new(Ipv4Addr::new(10, 0, 0, 7));
+        res.status = ScanStatus::Online;
+        app.upsert_result(res);
+        app.table_state.select(Some(0));
+
+        app.on_key(KeyCode::Char('a'));
+        assert_eq!(app.action_menu, Some(0));
+        app.on_key(KeyCode::Up);
+        assert_eq!(app.action_menu, Some(HostAction::ALL.len() - 1));
+        app.on_key(KeyCode::Enter);
+        assert_eq!(app.action_menu, None);
+        assert_eq!(app.clipboard.as_deref(), Some("10.0.0.7"));
+
+        // No MAC: Wake-on-LAN leaves a notice instead of a command.
+        app.on_key(KeyCode::Char('a'));
+        app.on_key(KeyCode::Char('w'));
+        assert!(rx.try_recv().is_err());
+        assert!(app.notice.as_deref().unwrap().contains("No MAC"));
+
+        app.on_key(KeyCode::Char('a'));
+        app.on_key(KeyCode::Char('t'));
+        assert!(app.show_detail);
+        assert!(
+            matches!(rx.try_recv(), Ok(BridgeMessage::Traceroute(ip)) if ip == Ipv4Addr::new(10, 0, 0, 7))
+        );
+        let hop = TraceHop {
+            ttl: 1,
+            addr: Some(Ipv4Addr::new(10, 0, 0, 7)),
+            rtt_ms: Some(1),
+        };
+        app.on_traceroute_event(BridgeMessage::TracerouteHop(
+            Ipv4Addr::new(10, 0, 0, 7),
+            hop,
+        ));
+        app.on_traceroute_event(BridgeMessage::TracerouteComplete(Ipv4Addr::new(
+            10, 0, 0, 7,
+        )));
+        let trace = app.traceroute.as_ref().unwrap();
+        assert_eq!(
+            (trace.hops.as_slice(), trace.finished),
+            ([hop].as_slice(), true)
+        );
+
+        app.on_key(KeyCode::Char('a'));
+        app.on_key(KeyCode::Char('i'));
+        assert_eq!(app.scan_state, ScanState::Monitoring);
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(BridgeMessage::StartConflictWatch { ip, interval })
+                if ip == Ipv4Addr::new(10, 0, 0, 7) && interval == DEFAULT_CONFLICT_WATCH_INTERVAL
+        ));
+
+        // The viewer can only copy.
+        app.read_only = true;
+        assert_eq!(app.actions(), vec![HostAction::CopyIp]);
+    }
+
+    #[test]
+    fn test_device_note_popup_names_the_device() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let mut app = App::new(tx);
+        let mut res = ScanResult::new(Ipv4Addr::new(10, 0, 0, 7));
+        res.mac = Some("aa-bb-cc-dd-ee-ff".to_string());
+        res.device_note = DeviceNote::parse("AP");
+        app.upsert_result(res);
+        app.table_state.select(Some(0));
+
+        app.on_key(KeyCode::Char('a'));
+        app.on_key(KeyCode::Char('n'));
+        assert_eq!(app.input_mode, InputMode::DeviceNote);
+        assert_eq!(app.device_note_input, "AP");
+        for c in " | ceiling".chars() {
+            app.on_key(KeyCode::Char(c));
+        }
+        app.on_key(KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        let Ok(BridgeMessage::SetDeviceNote { mac, note }) = rx.try_recv() else {
+            panic!("no device note sent");
+        };
+        assert_eq!(mac, "aa-bb-cc-dd-ee-ff");
+        assert_eq!(note, DeviceNote::parse("AP | ceiling"));
+
+        app.on_device_note_saved("AA:BB:CC:DD:EE:FF", note);
+        assert_eq!(
+            app.results[0]
+                .device_note
+                .as_ref()
+                .unwrap()
+                .notes
+                .as_deref(),
+            Some("ceiling")
+        );
+        assert!(matches_search(&app.results[0], "ceil"));
+    }
+
+    #[test]
+    fn test_shift_w_wakes_the_view_or_a_group() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let mut app = App::new(tx);
+        app.on_key(KeyCode::Char('W'));
+        assert_eq!(app.wake_menu, None);
+        assert!(
+            app.notice
+                .as_deref()
+                .unwrap()
+                .starts_with("Nothing to wake")
+        );
+
+        for (last, mac) in [(5, Some("00:11:22:33:44:55")), (6, None)] {
+            let mut res = ScanResult::new(Ipv4Addr::new(10, 0, 0, last));
+            res.mac = mac.map(str::to_string);
+            app.upsert_result(res);
+        }
+        app.settings
+            .wake_groups
+            .insert("lab".to_string(), vec!["00:11:22:33:44:77".to_string()]);
+        let labels: Vec<String> = app.wake_choices().into_iter().map(|(l, _)| l).collect();
+        assert_eq!(labels, ["Hosts in view (1)", "Group lab (1)"]);
+
+        app.on_key(KeyCode::Char('W'));
+        app.on_key(KeyCode::Down);
+        app.on_key(KeyCode::Enter);
+        assert_eq!(app.wake_menu, None);
+        let Ok(BridgeMessage::WakeBatch { targets, timeout }) = rx.try_recv() else {
+            panic!("expected a wake batch");
+        };
+        assert_eq!(timeout, DEFAULT_WAKE_TIMEOUT);
+        assert_eq!(
+            targets,
+            [WakeTarget {
+                mac: "00:11:22:33:44:77".to_string(),
+                ip: None
+            }]
+        );
+    }
+
+    #[test]
+    fn test_shift_m_tests_online_hosts_and_exports_the_matrix() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let mut app = App::new(tx);
+        app.on_key(KeyCode::Char('M'));
+        assert_eq!(app.reach_matrix, None);
+        assert_eq!(app.notice.as_deref(), Some("No online hosts to test"));
+
+        for last in [4, 5] {
+            let mut res = ScanResult::new(Ipv4Addr::new(10, 0, 0, last));
+            res.status = ScanStatus::Online;
+            app.upsert_result(res);
+        }
+        app.upsert_result(ScanResult::new(Ipv4Addr::new(10, 0, 0, 6)));
+        app.on_key(KeyCode::Char('M'));
+        let Ok(BridgeMessage::ReachMatrix { hosts, ports }) = rx.try_recv() else {
+            panic!("expected a matrix request");
+        };
+        let ips: Vec<_> = hosts.iter().filter_map(|h| h.ip).collect();
+        assert_eq!(
+            ips,
+            [Ipv4Addr::new(10, 0, 0, 4), Ipv4Addr::new(10, 0, 0, 5)]
+        );
+        assert!(ports.is_empty());
+
+        let matrix = ReachMatrix {
+            ports: vec![22],
+            rows: Vec::new(),
+        };
+        app.on_reach_matrix(matrix.clone());
+        assert_eq!(app.reach_matrix.as_ref().unwrap().matrix, Some(matrix));
+        let dir = std::env::temp_dir().join(format!("ragescanner-matrix-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        app.export_path = dir.join("export.csv");
+        app.on_key(KeyCode::Char('x'));
+        let csv = std::fs::read_to_string(dir.join("export-matrix.csv")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(csv, "host,ip,22\n");
+
+        app.on_key(KeyCode::Esc);
+        assert_eq!(app.reach_matrix, None);
+        // A late answer does not reopen the popup.
+        app.on_reach_matrix(ReachMatrix::default());
+        assert_eq!(app.reach_matrix, None);
+    }
+
+    #[test]
+    fn test_osc52_copy_encodes_base64() {
+        assert_eq!(osc52_copy("10.0.0.7"), "\x1b]52;c;MTAuMC4wLjc=\x07");
+        assert_eq!(osc52_copy("ab"), "\x1b]52;c;YWI=\x07");
+        assert_eq!(osc52_copy("abc"), "\x1b]52;c;YWJj\x07");
+    }
+
     #[test]
     fn test_esc_closes_detail_popup() {
         let mut app = test_app();
@@ -219,6 +2445,52 @@ mod tests {
         assert!(!app.should_quit);
     }
 
+    #[test]
+    fn test_slash_searches_across_fields_and_esc_clears() {
+        let mut app = test_app();
+        for (last, hostname, vendor) in [
+            (1, Some("router"), Some("Ubiquiti")),
+            (2, Some("NAS-01"), None),
+            (12, None, Some("Synology")),
+        ] {
+            let mut res = ScanResult::new(Ipv4Addr::new(10, 0, 0, last));
+            res.hostname = hostname.map(str::to_string);
+            res.vendor = vendor.map(str::to_string);
+            app.upsert_result(res);
+        }
+
+        app.on_key(KeyCode::Char('/'));
+        assert_eq!(app.input_mode, InputMode::Search);
+        for c in "nas".chars() {
+            app.on_key(KeyCode::Char(c));
+        }
+        let ips = |app: &App| -> Vec<u8> {
+            app.filtered_results()
+                .iter()
+                .map(|r| r.ip.octets()[3])
+                .collect()
+        };
+        assert_eq!(ips(&app), [2]);
+        assert_eq!(app.selected_result().map(|r| r.ip.octets()[3]), Some(2));
+        app.on_key(KeyCode::Backspace);
+        app.on_key(KeyCode::Backspace);
+        app.on_key(KeyCode::Backspace);
+        app.on_key(KeyCode::Char('1'));
+        // "1" is in every address; matching is by substring.
+        assert_eq!(ips(&app), [1, 2, 12]);
+        app.on_key(KeyCode::Char('2'));
+        assert_eq!(ips(&app), [12]);
+
+        app.on_key(KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.search, "12");
+        app.on_key(KeyCode::Esc);
+        assert!(app.search.is_empty() && !app.should_quit);
+        assert_eq!(ips(&app).len(), 3);
+        app.on_key(KeyCode::Esc);
+        assert!(app.should_quit);
+    }
+
     #[test]
     fn test_tab_toggles_filter() {
         let mut app = test_app();
@@ -228,4 +2500,427 @@ mod tests {
         app.on_key(KeyCode::Tab);
         assert!(!app.filter_online);
     }
+
+    #[test]
+    fn test_command_parse() {
+        assert_eq!(
+            Command::parse(":scan 10.0.0.0/24"),
+            Ok(Command::Scan(Some("10.0.0.0/24".to_string())))
+        );
+        assert_eq!(Command::parse("scan"), Ok(Command::Scan(None)));
+        assert_eq!(
+            Command::parse(":ports 20-22, 80"),
+            Ok(Command::Ports(vec![20, 21, 22, 80]))
+        );
+        assert!(Command::parse(":ports").is_err());
+        assert!(Command::parse(":ports 0").is_err());
+        assert_eq!(
+            Command::parse(":export results.csv"),
+            Ok(Command::Export(Some(PathBuf::from("results.csv"))))
+        );
+        assert_eq!(
+            Command::parse(":filter online"),
+            Ok(Command::Filter(CommandFilter::Online))
+        );
+        assert_eq!(
+            Command::parse(":filter web server"),
+            Ok(Command::Filter(CommandFilter::Search(
+                "web server".to_string()
+            )))
+        );
+        assert_eq!(Command::parse(" :q "), Ok(Command::Quit));
+        assert_eq!(
+            Command::parse(":q now"),
+            Err(":q takes no argument".to_string())
+        );
+        assert!(
+            Command::parse(":frobnicate")
+                .unwrap_err()
+                .starts_with("Unknown command :frobnicate.")
+        );
+        assert_eq!(Command::parse(":"), Err(COMMAND_HELP.to_string()));
+    }
+
+    #[test]
+    fn test_colon_commands_drive_the_app() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let mut app = App::new(tx);
+        let run = |app: &mut App, line: &str| {
+            app.on_key(KeyCode::Char(':'));
+            assert_eq!(app.input_mode, InputMode::Command);
+            line.chars().for_each(|c| app.on_key(KeyCode::Char(c)));
+            app.on_key(KeyCode::Enter);
+            assert_eq!(app.input_mode, InputMode::Normal);
+        };
+
+        run(&mut app, "ports 1-1024");
+        assert_eq!(app.settings.ports.len(), 1024);
+        match rx.try_recv() {
+            Ok(BridgeMessage::SetScanConfig(config)) => assert_eq!(config.ports.len(), 1024),
+            other => panic!("unexpected message: {:?}", other),
+        }
+        run(&mut app, "scan 10.0.0.0/24");
+        assert_eq!(app.input, "10.0.0.0/24");
+        assert_eq!(app.scan_state, ScanState::Scanning);
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(BridgeMessage::StartScan(range)) if range == "10.0.0.0/24"
+        ));
+
+        let mut online = ScanResult::new(Ipv4Addr::new(10, 0, 0, 1));
+        online.status = ScanStatus::Online;
+        app.upsert_result(online);
+        app.upsert_result(ScanResult::new(Ipv4Addr::new(10, 0, 0, 2)));
+        run(&mut app, "filter online");
+        assert!(app.filter_online);
+        run(&mut app, "filter 10.0.0.2");
+        assert_eq!(app.search, "10.0.0.2");
+        assert!(app.filtered_results().is_empty());
+        run(&mut app, "filter all");
+        assert!(!app.filter_online && app.search.is_empty());
+
+        let path =
+            std::env::temp_dir().join(format!("ragescanner-command-{}.csv", std::process::id()));
+        run(&mut app, &format!("export {}", path.display()));
+        assert_eq!(app.export_path, path);
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 3);
+        let _ = std::fs::remove_file(&path);
+
+        run(&mut app, "sacn");
+        assert!(
+            app.error
+                .as_deref()
+                .unwrap()
+                .starts_with("Unknown command :sacn.")
+        );
+        app.on_key(KeyCode::Esc);
+        // Backspace past the start or Esc leaves the line without running it.
+        app.on_key(KeyCode::Char(':'));
+        app.on_key(KeyCode::Backspace);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        run(&mut app, "q");
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn test_viewer_commands_cannot_scan() {
+        let mut app = App::viewer("audit.json", Vec::new(), None);
+        app.execute(Command::Scan(Some("10.0.0.0/24".to_string())));
+        assert_eq!(app.input, "audit.json");
+        assert!(app.error.is_some());
+        app.on_key(KeyCode::Esc);
+        app.execute(Command::Filter(CommandFilter::Online));
+        assert!(app.filter_online);
+    }
+
+    #[test]
+    fn test_paste_messy_text_scans_target_list() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let mut app = App::new(tx);
+        app.input.clear();
+        app.on_paste("Host 192.168.1.4 \n 192.168.1.9, 192.168.1.20");
+        assert_eq!(app.input_mode, InputMode::Editing);
+        assert_eq!(
+            app.target_preview().as_deref(),
+            Some("3 targets: 192.168.1.4, 192.168.1.9, 192.168.1.20")
+        );
+
+        app.on_key(KeyCode::Enter);
+        match rx.try_recv() {
+            Ok(BridgeMessage::StartScanTargets(targets)) => assert_eq!(targets.len(), 3),
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_multi_range_input_is_sent_as_text() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let mut app = App::new(tx);
+        app.input = "10.0.1.0/24,10.0.5.1-50".to_string();
+        assert_eq!(app.target_preview(), None);
+
+        app.start_scan();
+        match rx.try_recv() {
+            Ok(BridgeMessage::StartScan(text)) => assert_eq!(text, "10.0.1.0/24,10.0.5.1-50"),
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_range_progress_lists_ranges_in_flight_first() {
+        let mut app = test_app();
+        let range = |index, completed| RangeProgress {
+            index,
+            start: Ipv4Addr::new(10, 0, index as u8, 1),
+            end: Ipv4Addr::new(10, 0, index as u8, 10),
+            completed,
+            total: 10,
+        };
+        app.on_range_progress(range(0, 0));
+        app.on_range_progress(range(1, 0));
+        app.on_range_progress(range(2, 0));
+        app.on_range_progress(range(0, 10));
+        app.on_range_progress(range(2, 4));
+
+        let order: Vec<usize> = app.range_rows(5).iter().map(|r| r.index).collect();
+        assert_eq!(order, [1, 2, 0]);
+        assert_eq!(app.range_rows(2).len(), 2);
+
+        app.start_scan();
+        assert!(app.ranges.is_empty());
+    }
+
+    #[test]
+    fn test_p_cycles_ping_attempts() {
+        let mut app = test_app();
+        app.on_key(KeyCode::Char('p'));
+        assert_eq!(app.ping_attempts, 3);
+        app.on_key(KeyCode::Char('p'));
+        app.on_key(KeyCode::Char('p'));
+        assert_eq!(app.ping_attempts, 1);
+    }
+
+    #[test]
+    fn test_u_toggles_udp_scan() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let mut app = App::new(tx);
+        app.on_key(KeyCode::Char('u'));
+        assert!(app.udp_scan);
+        assert!(matches!(rx.try_recv(), Ok(BridgeMessage::SetUdpScan(true))));
+    }
+
+    #[test]
+    fn test_space_pauses_and_resumes_running_scan() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(8);
+        let mut app = App::new(tx);
+        app.on_key(KeyCode::Char(' '));
+        assert!(rx.try_recv().is_err(), "nothing to pause while idle");
+
+        app.scan_state = ScanState::Scanning;
+        app.on_key(KeyCode::Char(' '));
+        assert!(matches!(rx.try_recv(), Ok(BridgeMessage::PauseScan)));
+
+        app.paused = true;
+        app.on_key(KeyCode::Char(' '));
+        assert!(matches!(rx.try_recv(), Ok(BridgeMessage::ResumeScan)));
+    }
+
+    #[test]
+    fn test_cancelled_scan_counts_unscanned_targets() {
+        let mut app = test_app();
+        app.scan_state = ScanState::Scanning;
+        for last in 1..=3 {
+            let mut res = ScanResult::new(std::net::Ipv4Addr::new(10, 0, 0, last));
+            if last > 1 {
+                res.status = ScanStatus::NotScanned;
+            }
+            app.upsert_result(res);
+        }
+        app.on_scan_cancelled();
+        assert_eq!(app.scan_state, ScanState::Cancelled);
+        assert_eq!(
+            app.notice.as_deref(),
+            Some("Scan cancelled: 2 targets not scanned")
+        );
+    }
+
+    #[test]
+    fn test_upsert_keeps_results_sorted_and_selection() {
+        let mut app = test_app();
+        for last in [20, 5, 30] {
+            app.upsert_result(ScanResult::new(std::net::Ipv4Addr::new(10, 0, 0, last)));
+        }
+        app.table_state.select(Some(1));
+        app.upsert_result(ScanResult::new(std::net::Ipv4Addr::new(10, 0, 0, 1)));
+        let mut online = ScanResult::new(std::net::Ipv4Addr::new(10, 0, 0, 20));
+        online.status = crate::types::ScanStatus::Online;
+        online.stamp(Duration::from_millis(3), SystemTime::UNIX_EPOCH);
+        app.upsert_result(online);
+        app.upsert_result(ScanResult::new(std::net::Ipv4Addr::new(10, 0, 0, 20)));
+        assert_eq!(app.results[2].first_seen, Some(SystemTime::UNIX_EPOCH));
+        let mut online = ScanResult::new(std::net::Ipv4Addr::new(10, 0, 0, 20));
+        online.status = crate::types::ScanStatus::Online;
+        app.upsert_result(online);
+
+        let order: Vec<u8> = app.results.iter().map(|r| r.ip.octets()[3]).collect();
+        assert_eq!(order, vec![1, 5, 20, 30]);
+        assert_eq!(app.results[2].status, crate::types::ScanStatus::Online);
+        assert_eq!(app.selected_result().unwrap().ip.octets()[3], 20);
+    }
+
+    #[test]
+    fn test_port_found_fills_rows_before_the_result() {
+        let mut app = test_app();
+        let ip = Ipv4Addr::new(10, 0, 0, 7);
+        for port in [8080, 22, 8080] {
+            app.on_port_found(ip, port);
+        }
+        let ports: Vec<u16> = app.results[0].open_ports.iter().map(|p| p.port).collect();
+        assert_eq!(ports, [22, 8080]);
+        assert_eq!(app.results[0].status, ScanStatus::Online);
+
+        let mut done = ScanResult::new(ip);
+        done.status = ScanStatus::Online;
+        done.open_ports = vec![PortInfo::new(22), PortInfo::new(443), PortInfo::new(8080)];
+        app.upsert_result(done);
+        assert_eq!(app.results.len(), 1);
+        assert_eq!(app.results[0].open_ports.len(), 3);
+    }
+
+    #[test]
+    fn test_o_cycles_sort_key_and_streams_into_place() {
+        let mut app = test_app();
+        app.result_order = ResultOrder::Live;
+        for (last, ports) in [(1, 2), (2, 0), (3, 5)] {
+            let mut res = ScanResult::new(Ipv4Addr::new(10, 0, 0, last));
+            res.open_ports = (1..=ports).map(PortInfo::new).collect();
+            app.upsert_result(res);
+        }
+        for _ in 0..4 {
+            app.on_key(KeyCode::Char('o'));
+        }
+        assert_eq!(app.result_order, ResultOrder::Ip);
+        assert_eq!(app.sort_key, SortKey::OpenPorts);
+        app.on_key(KeyCode::Char('O'));
+        assert_eq!(app.order_label(), "Open ports desc");
+        let rows = |app: &App| -> Vec<u8> {
+            app.filtered_results()
+                .iter()
+                .map(|r| r.ip.octets()[3])
+                .collect()
+        };
+        assert_eq!(rows(&app), [3, 1, 2]);
+
+        // A new host lands in sorted position, not at the end.
+        let mut res = ScanResult::new(Ipv4Addr::new(10, 0, 0, 4));
+        res.open_ports = vec![PortInfo::new(22), PortInfo::new(80), PortInfo::new(443)];
+        app.upsert_result(res);
+        assert_eq!(rows(&app), [3, 4, 1, 2]);
+
+        app.on_key(KeyCode::Char('o'));
+        assert_eq!(
+            (app.sort_key, app.sort_descending),
+            (SortKey::Status, false)
+        );
+        app.on_key(KeyCode::Char('o'));
+        assert_eq!(rows(&app), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_event_log_records_failures_and_keeps_scroll_position() {
+        let mut app = test_app();
+        let mut failed = ScanResult::new(Ipv4Addr::new(10, 0, 0, 7));
+        failed.status = ScanStatus::SystemError(crate::types::GError::Internal("boom".to_string()));
+        app.record_bridge_event(&BridgeMessage::ScanUpdate(ScanResult::new(Ipv4Addr::new(
+            10, 0, 0, 1,
+        ))));
+        app.record_bridge_event(&BridgeMessage::ScanUpdate(failed));
+        app.record_bridge_event(&BridgeMessage::ScanCancelled);
+        let texts: Vec<&str> = app.event_log.iter().map(|e| e.text.as_str()).collect();
+        assert_eq!(texts.len(), 2);
+        assert!(texts[0].starts_with("10.0.0.7: ") && app.event_log[0].is_error);
+        assert_eq!(texts[1], "Scan cancelled");
+
+        app.on_key(KeyCode::Char('['));
+        assert_eq!(app.event_log_scroll, 0, "scrolling needs the pane open");
+        app.on_key(KeyCode::Char('L'));
+        app.on_key(KeyCode::Char('['));
+        app.on_key(KeyCode::Char('['));
+        assert_eq!(app.event_log_scroll, 1);
+        app.record_bridge_event(&BridgeMessage::ScanComplete);
+        assert_eq!(app.event_log_scroll, 2);
+        app.phase = Some(PhaseProgress {
+            hosts_discovered: 1,
+            hosts_total: 4,
+            ..PhaseProgress::default()
+        });
+        app.record_bridge_event(&BridgeMessage::ScanCancelled);
+        assert_eq!(
+            app.event_log.back().unwrap().text,
+            "Scan cancelled at Discovery 25%"
+        );
+
+        for _ in 0..EVENT_LOG_CAPACITY {
+            app.log_event("tick".to_string(), false);
+        }
+        assert_eq!(app.event_log.len(), EVENT_LOG_CAPACITY);
+        assert_eq!(app.event_log_scroll, EVENT_LOG_CAPACITY - 1);
+    }
+
+    #[test]
+    fn test_live_order_shows_newest_response_first() {
+        let mut app = test_app();
+        app.result_order = ResultOrder::Ip;
+        for last in [20, 5, 30] {
+            app.upsert_result(ScanResult::new(Ipv4Addr::new(10, 0, 0, last)));
+        }
+        app.table_state.select(Some(0));
+        app.on_key(KeyCode::Char('l'));
+        assert_eq!(app.result_order, ResultOrder::Live);
+        let rows = |app: &App| -> Vec<u8> {
+            app.filtered_results()
+                .iter()
+                .map(|r| r.ip.octets()[3])
+                .collect()
+        };
+        assert_eq!(rows(&app), [30, 5, 20]);
+        assert_eq!(app.selected_result().unwrap().ip.octets()[3], 5);
+
+        // A host reporting again moves to the top; the cursor follows its host.
+        app.upsert_result(ScanResult::new(Ipv4Addr::new(10, 0, 0, 20)));
+        assert_eq!(rows(&app), [20, 30, 5]);
+        assert_eq!(app.selected_result().unwrap().ip.octets()[3], 5);
+        let order: Vec<u8> = app.results.iter().map(|r| r.ip.octets()[3]).collect();
+        assert_eq!(order, [5, 20, 30]);
+
+        app.on_key(KeyCode::Char('l'));
+        assert_eq!(rows(&app), [5, 20, 30]);
+    }
+
+    #[test]
+    fn test_filter_toggle_keeps_selected_host() {
+        let mut app = test_app();
+        for (last, online) in [(1, true), (2, false), (3, true), (4, false)] {
+            let mut res = ScanResult::new(Ipv4Addr::new(10, 0, 0, last));
+            if online {
+                res.status = crate::types::ScanStatus::Online;
+            }
+            app.upsert_result(res);
+        }
+        app.table_state.select(Some(2));
+        app.on_key(KeyCode::Tab);
+        assert_eq!(
+            app.selected_result().unwrap().ip,
+            Ipv4Addr::new(10, 0, 0, 3)
+        );
+        app.on_key(KeyCode::Tab);
+        assert_eq!(
+            app.selected_result().unwrap().ip,
+            Ipv4Addr::new(10, 0, 0, 3)
+        );
+
+        // A hidden host hands the cursor to the next visible one.
+        app.table_state.select(Some(1));
+        app.on_key(KeyCode::Tab);
+        assert_eq!(
+            app.selected_result().unwrap().ip,
+            Ipv4Addr::new(10, 0, 0, 3)
+        );
+    }
+
+    #[test]
+    fn test_error_banner_dismisses_on_esc_and_times_out() {
+        let mut app = test_app();
+        app.show_error("Invalid IP Range".to_string());
+        app.on_key(KeyCode::Esc);
+        assert!(app.error.is_none());
+        assert!(!app.should_quit, "Esc only closes the banner");
+
+        app.show_error("Invalid IP Range".to_string());
+        app.on_tick();
+        assert!(app.error.is_some());
+        app.error_shown = Instant::now().checked_sub(ERROR_BANNER_TIMEOUT);
+        app.on_tick();
+        assert!(app.error.is_none());
+    }
 }
@@ -7,8 +7,11 @@ use tokio::sync::mpsc;
 #[derive(Debug, Clone)]
 pub enum AppEvent {
     Input(KeyEvent),
+    /// Text delivered in one piece via bracketed paste.
+    Paste(String),
     Tick,
-    Bridge(BridgeMessage),
+    /// Boxed: scan results are much larger than key events.
+    Bridge(Box<BridgeMessage>),
 }
 
 pub struct EventHandler {
@@ -30,9 +33,16 @@ impl EventHandler {
 
                 tokio::select! {
                     maybe_event = crossterm_event => {
-                        if let Some(Ok(CrosstermEvent::Key(key))) = maybe_event
-                            && key.kind == crossterm::event::KeyEventKind::Press {
+                        match maybe_event {
+                            Some(Ok(CrosstermEvent::Key(key)))
+                                if key.kind == crossterm::event::KeyEventKind::Press =>
+                            {
                                 let _ = tx.send(AppEvent::Input(key));
+                            }
+                            Some(Ok(CrosstermEvent::Paste(text))) => {
+                                let _ = tx.send(AppEvent::Paste(text));
+                            }
+                            _ => {}
                         }
                     }
                     _ = tick_delay => {
@@ -42,15 +52,14 @@ impl EventHandler {
             }
         });
 
-        // 2. Bridge events (poll crossbeam from tokio)
-        tokio::spawn(async move {
-            loop {
-                // We use try_recv to avoid blocking the loop, but in a tokio task
-                // it's better to yield if empty.
-                while let Ok(msg) = bridge_rx.try_recv() {
-                    let _ = tx_clone.send(AppEvent::Bridge(msg));
+        // 2. Bridge events: a blocking receive on a dedicated thread, so nothing
+        // polls while idle and each message is forwarded as soon as it arrives.
+        // The thread ends when the bridge hangs up or the handler is dropped.
+        std::thread::spawn(move || {
+            while let Ok(msg) = bridge_rx.recv() {
+                if tx_clone.send(AppEvent::Bridge(Box::new(msg))).is_err() {
+                    break;
                 }
-                tokio::time::sleep(Duration::from_millis(10)).await;
             }
         });
 
@@ -1,4 +1,6 @@
 pub mod app;
 pub mod event;
+pub mod setup;
+pub mod store;
 pub mod theme;
 pub mod ui;
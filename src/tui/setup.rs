@@ -0,0 +1,200 @@
+use crate::net::InterfaceInfo;
+use crate::settings::Settings;
+use crate::targets::ScanTargets;
+use crate::tui::theme::{self, Theme};
+use crate::types::ScanProfile;
+use ratatui::crossterm::event::KeyCode;
+
+/// Profiles offered by the wizard, in the order shown.
+pub const PROFILES: [ScanProfile; 2] = [ScanProfile::Standard, ScanProfile::Safe];
+
+/// Pages of the [`SetupWizard`], in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetupStep {
+    Range,
+    Profile,
+    Theme,
+}
+
+/// What a key did to the [`SetupWizard`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetupOutcome {
+    Open,
+    /// Closed with `Esc` on the first page; nothing is saved.
+    Cancelled,
+    /// The chosen settings, to be applied and saved.
+    Finished(Box<Settings>),
+}
+
+/// First-run setup, shown when no settings file exists yet: picks the
+/// default range from the detected subnets, the scan profile and the theme.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetupWizard {
+    pub step: SetupStep,
+    /// Subnets to offer, most likely first; see
+    /// [`candidate_interfaces`](crate::net::candidate_interfaces).
+    pub interfaces: Vec<InterfaceInfo>,
+    /// Highlighted entry of `interfaces`.
+    pub highlighted: usize,
+    /// Default range: the highlighted subnet's, unless typed over.
+    pub range: String,
+    pub profile: ScanProfile,
+    pub theme: Theme,
+    /// Why the typed range was not accepted.
+    pub error: Option<String>,
+}
+
+impl SetupWizard {
+    /// Starts on the range page with the first interface's range, or
+    /// `fallback_range` if none was detected.
+    pub fn new(interfaces: Vec<InterfaceInfo>, fallback_range: &str) -> Self {
+        let range = interfaces
+            .first()
+            .map_or_else(|| fallback_range.to_string(), |i| i.range_string());
+        Self {
+            step: SetupStep::Range,
+            interfaces,
+            highlighted: 0,
+            range,
+            profile: ScanProfile::default(),
+            theme: theme::current(),
+            error: None,
+        }
+    }
+
+    pub fn on_key(&mut self, code: KeyCode) -> SetupOutcome {
+        match self.step {
+            SetupStep::Range => match code {
+                KeyCode::Esc => return SetupOutcome::Cancelled,
+                KeyCode::Down | KeyCode::Up if !self.interfaces.is_empty() => {
+                    let len = self.interfaces.len();
+                    self.highlighted = match code {
+                        KeyCode::Down => (self.highlighted + 1) % len,
+                        _ => (self.highlighted + len - 1) % len,
+                    };
+                    self.range = self.interfaces[self.highlighted].range_string();
+                    self.error = None;
+                }
+                KeyCode::Char(c) => self.range.push(c),
+                KeyCode::Backspace => {
+                    self.range.pop();
+                }
+                KeyCode::Enter => match ScanTargets::parse(&self.range) {
+                    Ok(_) => {
+                        self.range = self.range.trim().to_string();
+                        self.error = None;
+                        self.step = SetupStep::Profile;
+                    }
+                    Err(e) => self.error = Some(e),
+                },
+                _ => {}
+            },
+            SetupStep::Profile => match code {
+                KeyCode::Esc => self.step = SetupStep::Range,
+                KeyCode::Up | KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('k') => {
+                    self.profile = cycle(&PROFILES, self.profile, code);
+                }
+                KeyCode::Enter => self.step = SetupStep::Theme,
+                _ => {}
+            },
+            SetupStep::Theme => match code {
+                KeyCode::Esc => self.step = SetupStep::Profile,
+                KeyCode::Up | KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('k') => {
+                    self.theme = cycle(&Theme::ALL, self.theme, code);
+                    // Preview: the wizard and the table behind it redraw in it.
+                    theme::set(self.theme);
+                }
+                KeyCode::Enter => {
+                    return SetupOutcome::Finished(Box::new(Settings {
+                        last_range: Some(self.range.clone()),
+                        theme: self.theme,
+                        ..Settings::for_profile(self.profile)
+                    }));
+                }
+                _ => {}
+            },
+        }
+        SetupOutcome::Open
+    }
+}
+
+/// The entry of `items` after (`Down`/`j`) or before `current`, wrapping.
+fn cycle<T: Copy + PartialEq>(items: &[T], current: T, code: KeyCode) -> T {
+    let index = items.iter().position(|i| *i == current).unwrap_or(0);
+    let len = items.len();
+    match code {
+        KeyCode::Down | KeyCode::Char('j') => items[(index + 1) % len],
+        _ => items[(index + len - 1) % len],
+    }
+}
+
+/// One line describing `profile` for the wizard.
+pub fn describe_profile(profile: ScanProfile) -> &'static str {
+    match profile {
+        ScanProfile::Standard => "Standard: default timeouts, common ports, name lookups",
+        ScanProfile::Safe => "Safe: one host at a time at 5 packets/s, ICMP only (OT/ICS)",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn iface(name: &str, address: [u8; 4]) -> InterfaceInfo {
+        InterfaceInfo {
+            name: name.to_string(),
+            address: Ipv4Addr::from(address),
+            prefix_len: 24,
+            gateway: None,
+            is_up: true,
+            dns_servers: Vec::new(),
+            dhcp_server: None,
+        }
+    }
+
+    #[test]
+    fn test_wizard_walks_range_profile_and_theme() {
+        let mut wizard = SetupWizard::new(
+            vec![iface("Wi-Fi", [10, 1, 2, 3]), iface("Lab", [172, 16, 0, 9])],
+            "192.168.1.1-255",
+        );
+        assert_eq!(wizard.range, "10.1.2.1-10.1.2.254");
+        wizard.on_key(KeyCode::Down);
+        assert_eq!(wizard.range, "172.16.0.1-172.16.0.254");
+
+        wizard.range.clear();
+        wizard.on_key(KeyCode::Enter);
+        assert!(wizard.error.is_some());
+        assert_eq!(wizard.step, SetupStep::Range);
+        for c in "10.9.0.0/30".chars() {
+            wizard.on_key(KeyCode::Char(c));
+        }
+        wizard.on_key(KeyCode::Enter);
+        assert_eq!(wizard.step, SetupStep::Profile);
+
+        wizard.on_key(KeyCode::Down);
+        wizard.on_key(KeyCode::Enter);
+        wizard.on_key(KeyCode::Esc);
+        assert_eq!(wizard.step, SetupStep::Profile);
+        wizard.on_key(KeyCode::Enter);
+        let SetupOutcome::Finished(settings) = wizard.on_key(KeyCode::Enter) else {
+            panic!("expected the wizard to finish");
+        };
+        assert_eq!(settings.last_range.as_deref(), Some("10.9.0.0/30"));
+        assert_eq!(settings.profile, ScanProfile::Safe);
+        assert_eq!(
+            settings.scan_config().max_rate,
+            ScanProfile::Safe.config().max_rate
+        );
+    }
+
+    #[test]
+    fn test_wizard_without_interfaces_offers_the_fallback() {
+        let mut wizard = SetupWizard::new(Vec::new(), "192.168.1.1-255");
+        assert_eq!(wizard.range, "192.168.1.1-255");
+        wizard.on_key(KeyCode::Down);
+        assert_eq!(wizard.range, "192.168.1.1-255");
+        assert_eq!(wizard.on_key(KeyCode::Esc), SetupOutcome::Cancelled);
+    }
+}
@@ -0,0 +1,595 @@
+//! The TUI's result set, held partly on disk so that a scan of tens of
+//! thousands of hosts does not keep every [`ScanResult`] in memory.
+//!
+//! Every host keeps a [`HostRow`] in memory: what the table filters, searches,
+//! sorts and draws. At most [`RESIDENT_RESULTS`] full results stay in memory
+//! as well; past that, the ones held longest are appended as JSON lines to a
+//! spill file in the temp directory and read back one at a time for the
+//! detail view, actions and exports. The file is deleted with the store.
+
+use crate::types::{
+    DeviceNote, DeviceType, LatencyStats, NetworkRole, PortInfo, ScanResult, ScanStatus,
+};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::net::Ipv4Addr;
+use std::ops::Index;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+/// Full results kept in memory before the ones held longest go to the spill file.
+pub const RESIDENT_RESULTS: usize = 10_000;
+
+/// Superseded bytes the spill file may hold before it is rewritten, as long
+/// as they also outweigh the live ones.
+const COMPACT_MIN_BYTES: u64 = 16 * 1024 * 1024;
+
+/// What the table needs of a host: enough to filter, search, sort and draw
+/// its row without loading the full result.
+#[derive(Debug, Clone)]
+pub struct HostRow {
+    pub ip: Ipv4Addr,
+    pub hostname: Option<String>,
+    pub mac: Option<String>,
+    pub vendor: Option<String>,
+    pub status: ScanStatus,
+    /// Open TCP ports, without banners.
+    pub open_ports: Vec<PortInfo>,
+    pub latency: Option<LatencyStats>,
+    pub device_type: Option<DeviceType>,
+    pub device_note: Option<DeviceNote>,
+    pub roles: Vec<NetworkRole>,
+    pub first_seen: Option<SystemTime>,
+    pub last_seen: Option<SystemTime>,
+}
+
+impl HostRow {
+    /// Average echo round-trip time, as [`ScanResult::latency_ms`].
+    pub fn latency_ms(&self) -> Option<f32> {
+        self.latency.map(|l| l.avg_ms)
+    }
+
+    /// A result holding only what the row knows, for when the full one
+    /// cannot be read back.
+    fn to_result(&self) -> ScanResult {
+        let mut res = ScanResult::new(self.ip);
+        res.hostname = self.hostname.clone();
+        res.mac = self.mac.clone();
+        res.vendor = self.vendor.clone();
+        res.status = self.status.clone();
+        res.open_ports = self.open_ports.clone();
+        res.latency = self.latency;
+        res.device_type = self.device_type;
+        res.device_note = self.device_note.clone();
+        res.roles = self.roles.clone();
+        res.first_seen = self.first_seen;
+        res.last_seen = self.last_seen;
+        res
+    }
+}
+
+impl From<&ScanResult> for HostRow {
+    fn from(res: &ScanResult) -> Self {
+        Self {
+            ip: res.ip,
+            hostname: res.hostname.clone(),
+            mac: res.mac.clone(),
+            vendor: res.vendor.clone(),
+            status: res.status.clone(),
+            open_ports: res
+                .open_ports
+                .iter()
+                .map(|p| PortInfo {
+                    port: p.port,
+                    service: p.service.clone(),
+                    banner: None,
+                })
+                .collect(),
+            latency: res.latency,
+            device_type: res.device_type,
+            device_note: res.device_note.clone(),
+            roles: res.roles.clone(),
+            first_seen: res.first_seen,
+            last_seen: res.last_seen,
+        }
+    }
+}
+
+/// Where a host's full result lives.
+enum Slot {
+    Resident(Box<ScanResult>),
+    /// A line of `len` bytes at `offset` in the spill file.
+    Spilled {
+        offset: u64,
+        len: usize,
+    },
+}
+
+struct Entry {
+    row: HostRow,
+    slot: Slot,
+}
+
+/// Every result of the scan shown, one per host, sorted by IP.
+pub struct ResultStore {
+    /// Sorted by IP.
+    entries: Vec<Entry>,
+    /// Hosts whose full result is in memory, in the order it got there.
+    resident: VecDeque<Ipv4Addr>,
+    /// Most full results kept in memory.
+    limit: usize,
+    /// Created when the first result is spilled.
+    spill: Option<SpillFile>,
+}
+
+impl Default for ResultStore {
+    fn default() -> Self {
+        Self::with_limit(RESIDENT_RESULTS)
+    }
+}
+
+impl ResultStore {
+    /// Creates an empty store keeping [`RESIDENT_RESULTS`] full results in memory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty store keeping at most `limit` full results in memory.
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            resident: VecDeque::new(),
+            limit,
+            spill: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Forgets every result and deletes the spill file.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.resident.clear();
+        self.spill = None;
+    }
+
+    /// Every host's row, by IP.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &HostRow> {
+        self.entries.iter().map(|entry| &entry.row)
+    }
+
+    /// The row of `ip`, if listed.
+    pub fn row(&self, ip: Ipv4Addr) -> Option<&HostRow> {
+        let i = self.position(ip).ok()?;
+        Some(&self.entries[i].row)
+    }
+
+    /// The full result of `ip`, read back from the spill file if needed.
+    pub fn load(&self, ip: Ipv4Addr) -> Option<Cow<'_, ScanResult>> {
+        let i = self.position(ip).ok()?;
+        Some(self.full(&self.entries[i]))
+    }
+
+    /// The full results of the hosts whose row passes `keep`, by IP.
+    pub fn load_where(&self, keep: impl Fn(&HostRow) -> bool) -> Vec<ScanResult> {
+        self.entries
+            .iter()
+            .filter(|entry| keep(&entry.row))
+            .map(|entry| self.full(entry).into_owned())
+            .collect()
+    }
+
+    /// Inserts or replaces the result of `res.ip`, spilling the results held
+    /// longest past the limit.
+    ///
+    /// A replaced host keeps when it was first and last seen online, as
+    /// [`ScanResult::merge_seen`] does, taken from its row so a spilled
+    /// result is not read back.
+    pub fn upsert(&mut self, mut res: ScanResult) {
+        let ip = res.ip;
+        match self.position(ip) {
+            Ok(i) => {
+                let entry = &mut self.entries[i];
+                res.first_seen = match (entry.row.first_seen, res.first_seen) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (a, b) => a.or(b),
+                };
+                res.last_seen = res.last_seen.or(entry.row.last_seen);
+                entry.row = HostRow::from(&res);
+                if let Slot::Spilled { len, .. } = entry.slot {
+                    if let Some(spill) = &mut self.spill {
+                        spill.forget(len);
+                    }
+                    self.resident.push_back(ip);
+                }
+                entry.slot = Slot::Resident(Box::new(res));
+            }
+            Err(i) => {
+                let entry = Entry {
+                    row: HostRow::from(&res),
+                    slot: Slot::Resident(Box::new(res)),
+                };
+                self.entries.insert(i, entry);
+                self.resident.push_back(ip);
+            }
+        }
+        self.spill_oldest();
+        self.compact_if_wasteful();
+    }
+
+    /// Applies `update` to the full result of every host whose row passes
+    /// `keep`, writing spilled results back to the spill file.
+    pub fn update_where(
+        &mut self,
+        keep: impl Fn(&HostRow) -> bool,
+        mut update: impl FnMut(&mut ScanResult),
+    ) {
+        for i in 0..self.entries.len() {
+            if !keep(&self.entries[i].row) {
+                continue;
+            }
+            if let Slot::Resident(res) = &mut self.entries[i].slot {
+                update(res);
+                self.entries[i].row = HostRow::from(&**res);
+                continue;
+            }
+            let mut res = self.full(&self.entries[i]).into_owned();
+            update(&mut res);
+            let entry = &mut self.entries[i];
+            entry.row = HostRow::from(&res);
+            let written = match &mut self.spill {
+                Some(spill) => {
+                    if let Slot::Spilled { len, .. } = entry.slot {
+                        spill.forget(len);
+                    }
+                    spill.append(&res)
+                }
+                None => Err(io::ErrorKind::NotFound.into()),
+            };
+            entry.slot = match written {
+                Ok(slot) => slot,
+                Err(e) => {
+                    log::warn!("Could not write {} to the spill file: {}", res.ip, e);
+                    self.resident.push_back(res.ip);
+                    Slot::Resident(Box::new(res))
+                }
+            };
+        }
+        self.spill_oldest();
+        self.compact_if_wasteful();
+    }
+
+    fn position(&self, ip: Ipv4Addr) -> Result<usize, usize> {
+        self.entries.binary_search_by_key(&ip, |entry| entry.row.ip)
+    }
+
+    /// The full result of `entry`. One that cannot be read back is logged
+    /// and replaced by what its row knows.
+    fn full<'a>(&'a self, entry: &'a Entry) -> Cow<'a, ScanResult> {
+        let (offset, len) = match entry.slot {
+            Slot::Resident(ref res) => return Cow::Borrowed(res),
+            Slot::Spilled { offset, len } => (offset, len),
+        };
+        let read = match &self.spill {
+            Some(spill) => spill.read(offset, len),
+            None => Err(io::ErrorKind::NotFound.into()),
+        };
+        Cow::Owned(read.unwrap_or_else(|e| {
+            log::error!(
+                "Could not read {} back from the spill file: {}",
+                entry.row.ip,
+                e
+            );
+            entry.row.to_result()
+        }))
+    }
+
+    /// Moves the full results held longest to the spill file until at most
+    /// `limit` are in memory. If the file cannot be written,
+    /// everything stays in memory from then on.
+    fn spill_oldest(&mut self) {
+        while self.resident.len() > self.limit {
+            if self.spill.is_none() {
+                match SpillFile::create() {
+                    Ok(spill) => self.spill = Some(spill),
+                    Err(e) => {
+                        log::warn!("Keeping every result in memory: {}", e);
+                        self.limit = usize::MAX;
+                        return;
+                    }
+                }
+            }
+            let (Some(ip), Some(spill)) = (self.resident.front().copied(), &mut self.spill) else {
+                return;
+            };
+            let Ok(i) = self.entries.binary_search_by_key(&ip, |entry| entry.row.ip) else {
+                self.resident.pop_front();
+                continue;
+            };
+            let entry = &mut self.entries[i];
+            if let Slot::Resident(res) = &entry.slot {
+                match spill.append(res) {
+                    Ok(slot) => entry.slot = slot,
+                    Err(e) => {
+                        log::warn!("Keeping every result in memory: {}", e);
+                        self.limit = usize::MAX;
+                        return;
+                    }
+                }
+            }
+            self.resident.pop_front();
+        }
+    }
+
+    /// Rewrites the spill file without superseded lines once they take up
+    /// more than [`COMPACT_MIN_BYTES`] and more than the live ones, so
+    /// monitor sweeps reporting every host again do not grow it forever.
+    fn compact_if_wasteful(&mut self) {
+        if let Some(spill) = &self.spill
+            && spill.garbage() >= COMPACT_MIN_BYTES
+            && spill.garbage() > spill.live
+        {
+            self.compact();
+        }
+    }
+
+    /// Copies the live lines of the spill file to a new one.
+    fn compact(&mut self) {
+        let Some(old) = &self.spill else {
+            return;
+        };
+        let copied = (|| {
+            let mut new = SpillFile::create()?;
+            let mut slots = Vec::new();
+            for (i, entry) in self.entries.iter().enumerate() {
+                if let Slot::Spilled { offset, len } = entry.slot {
+                    slots.push((i, new.append_line(&old.read_line(offset, len)?)?));
+                }
+            }
+            io::Result::Ok((new, slots))
+        })();
+        match copied {
+            Ok((new, slots)) => {
+                for (i, slot) in slots {
+                    self.entries[i].slot = slot;
+                }
+                self.spill = Some(new);
+            }
+            Err(e) => log::warn!("Could not compact the spill file: {}", e),
+        }
+    }
+}
+
+impl Index<usize> for ResultStore {
+    type Output = HostRow;
+
+    /// The row of the `index`-th host by IP.
+    fn index(&self, index: usize) -> &HostRow {
+        &self.entries[index].row
+    }
+}
+
+impl FromIterator<ScanResult> for ResultStore {
+    fn from_iter<I: IntoIterator<Item = ScanResult>>(results: I) -> Self {
+        let mut results: Vec<ScanResult> = results.into_iter().collect();
+        // In IP order every result lands at the end, without shifting rows.
+        results.sort_by_key(|res| res.ip);
+        let mut store = Self::new();
+        for res in results {
+            store.upsert(res);
+        }
+        store
+    }
+}
+
+/// Deletes the file at the path when dropped.
+struct TempPath(PathBuf);
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Append-only file of JSON lines, one per spilled result.
+struct SpillFile {
+    file: File,
+    /// Declared after `file`, so the file is closed before it is deleted.
+    _path: TempPath,
+    /// Bytes written.
+    len: u64,
+    /// Bytes of lines still referenced by a [`Slot::Spilled`].
+    live: u64,
+}
+
+impl SpillFile {
+    fn create() -> io::Result<Self> {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "ragescanner-results-{}-{}.ndjson",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        Ok(Self {
+            file,
+            _path: TempPath(path),
+            len: 0,
+            live: 0,
+        })
+    }
+
+    fn garbage(&self) -> u64 {
+        self.len - self.live
+    }
+
+    fn append(&mut self, res: &ScanResult) -> io::Result<Slot> {
+        let mut line = serde_json::to_vec(res)?;
+        line.push(b'\n');
+        self.append_line(&line)
+    }
+
+    fn append_line(&mut self, line: &[u8]) -> io::Result<Slot> {
+        self.file.seek(SeekFrom::Start(self.len))?;
+        self.file.write_all(line)?;
+        let slot = Slot::Spilled {
+            offset: self.len,
+            len: line.len(),
+        };
+        self.len += line.len() as u64;
+        self.live += line.len() as u64;
+        Ok(slot)
+    }
+
+    fn read(&self, offset: u64, len: usize) -> io::Result<ScanResult> {
+        Ok(serde_json::from_slice(&self.read_line(offset, len)?)?)
+    }
+
+    fn read_line(&self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut line = vec![0; len];
+        file.read_exact(&mut line)?;
+        Ok(line)
+    }
+
+    /// Marks a line of `len` bytes as superseded.
+    fn forget(&mut self, len: usize) {
+        self.live -= len as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn result(last: u8) -> ScanResult {
+        let mut res = ScanResult::new(Ipv4Addr::new(10, 0, 0, last));
+        res.status = ScanStatus::Online;
+        res.open_ports = vec![PortInfo {
+            banner: Some(format!("SSH-2.0-host{}", last)),
+            ..PortInfo::new(22)
+        }];
+        res.services = vec!["_ssh._tcp".to_string()];
+        res
+    }
+
+    fn spill_path(store: &ResultStore) -> PathBuf {
+        store.spill.as_ref().unwrap()._path.0.clone()
+    }
+
+    #[test]
+    fn test_spills_past_the_limit_and_reads_back() {
+        let mut store = ResultStore::with_limit(2);
+        for last in [5, 1, 4, 2, 3] {
+            store.upsert(result(last));
+        }
+        assert_eq!(
+            store.resident,
+            [2, 3].map(|last| Ipv4Addr::new(10, 0, 0, last))
+        );
+        let order: Vec<u8> = store.iter().map(|row| row.ip.octets()[3]).collect();
+        assert_eq!(order, [1, 2, 3, 4, 5]);
+        assert_eq!(store[0].open_ports[0].banner, None);
+
+        let spilled = store.load(Ipv4Addr::new(10, 0, 0, 5)).unwrap();
+        assert!(matches!(spilled, Cow::Owned(_)));
+        assert_eq!(
+            spilled.open_ports[0].banner.as_deref(),
+            Some("SSH-2.0-host5")
+        );
+        assert_eq!(spilled.services, ["_ssh._tcp"]);
+        let services: Vec<usize> = store
+            .load_where(|row| row.ip.octets()[3] % 2 == 1)
+            .iter()
+            .map(|res| res.services.len())
+            .collect();
+        assert_eq!(services, [1, 1, 1]);
+
+        let path = spill_path(&store);
+        assert!(path.exists());
+        store.clear();
+        assert!(store.is_empty());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_replacing_a_spilled_result_keeps_when_it_was_seen() {
+        let mut store = ResultStore::with_limit(1);
+        let mut first = result(1);
+        first.stamp(Duration::from_millis(3), SystemTime::UNIX_EPOCH);
+        store.upsert(first);
+        store.upsert(result(2));
+        let garbage = store.spill.as_ref().unwrap().garbage();
+
+        let mut again = result(1);
+        again.stamp(Duration::from_millis(3), SystemTime::now());
+        store.upsert(again);
+        let row = store.row(Ipv4Addr::new(10, 0, 0, 1)).unwrap();
+        assert_eq!(row.first_seen, Some(SystemTime::UNIX_EPOCH));
+        assert!(store.spill.as_ref().unwrap().garbage() > garbage);
+        let reloaded = store.load(Ipv4Addr::new(10, 0, 0, 1)).unwrap();
+        assert!(matches!(reloaded, Cow::Borrowed(_)));
+        assert_eq!(reloaded.first_seen, Some(SystemTime::UNIX_EPOCH));
+    }
+
+    #[test]
+    fn test_updates_reach_spilled_results() {
+        let mut store: ResultStore = (1..=4).map(result).collect();
+        store.limit = 1;
+        store.spill_oldest();
+        let note = DeviceNote::parse("Printer");
+        store.update_where(
+            |row| row.ip.octets()[3] <= 2,
+            |res| res.device_note = note.clone(),
+        );
+        for last in 1..=4 {
+            let ip = Ipv4Addr::new(10, 0, 0, last);
+            let expected = if last <= 2 { note.as_ref() } else { None };
+            assert_eq!(store.row(ip).unwrap().device_note.as_ref(), expected);
+            assert_eq!(store.load(ip).unwrap().device_note.as_ref(), expected);
+        }
+    }
+
+    #[test]
+    fn test_compaction_drops_superseded_lines() {
+        let mut store = ResultStore::with_limit(1);
+        for _ in 0..3 {
+            for last in 1..=3 {
+                store.upsert(result(last));
+            }
+        }
+        let old = spill_path(&store);
+        assert!(store.spill.as_ref().unwrap().garbage() > 0);
+
+        store.compact();
+        let spill = store.spill.as_ref().unwrap();
+        assert_eq!(spill.garbage(), 0);
+        assert_eq!(fs::metadata(spill_path(&store)).unwrap().len(), spill.len);
+        assert!(!old.exists());
+        for last in 1..=3 {
+            let res = store.load(Ipv4Addr::new(10, 0, 0, last)).unwrap();
+            assert_eq!(
+                res.open_ports[0].banner,
+                Some(format!("SSH-2.0-host{}", last))
+            );
+        }
+        let path = spill_path(&store);
+        drop(store);
+        assert!(!path.exists());
+    }
+}
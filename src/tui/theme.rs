@@ -1,8 +1,110 @@
 use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
 
-pub const PRIMARY: Color = Color::Rgb(19, 164, 236); // #13a4ec
-pub const BG_DARK: Color = Color::Rgb(16, 28, 34); // #101c22
-pub const ONLINE: Color = Color::Green;
-pub const OFFLINE: Color = Color::DarkGray;
-pub const ERROR: Color = Color::Red;
-pub const TEXT_DIM: Color = Color::Rgb(100, 116, 139); // Slate 400
+/// Color schemes of the TUI, kept in the settings as `theme`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    /// Blue accents for dark terminals.
+    #[default]
+    Dark,
+    /// Darker accents that stay readable on a white background.
+    Light,
+    /// The basic bright colors only, for 16-color terminals and low vision.
+    HighContrast,
+}
+
+/// Colors of one [`Theme`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub primary: Color,
+    pub online: Color,
+    pub offline: Color,
+    pub error: Color,
+    pub text_dim: Color,
+    /// Background of search matches in the table.
+    pub match_bg: Color,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Dark, Theme::Light, Theme::HighContrast];
+
+    pub fn palette(self) -> Palette {
+        match self {
+            Theme::Dark => Palette {
+                primary: Color::Rgb(19, 164, 236), // #13a4ec
+                online: Color::Green,
+                offline: Color::DarkGray,
+                error: Color::Red,
+                text_dim: Color::Rgb(100, 116, 139), // Slate 400
+                match_bg: Color::Yellow,
+            },
+            Theme::Light => Palette {
+                primary: Color::Rgb(3, 105, 161), // Sky 700
+                online: Color::Rgb(21, 128, 61),  // Green 700
+                offline: Color::Gray,
+                error: Color::Rgb(185, 28, 28),    // Red 700
+                text_dim: Color::Rgb(71, 85, 105), // Slate 600
+                match_bg: Color::LightYellow,
+            },
+            Theme::HighContrast => Palette {
+                primary: Color::LightCyan,
+                online: Color::LightGreen,
+                offline: Color::Gray,
+                error: Color::LightRed,
+                text_dim: Color::White,
+                match_bg: Color::Yellow,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+            Theme::HighContrast => "High contrast",
+        })
+    }
+}
+
+/// Index into [`Theme::ALL`] of the theme being drawn.
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// Draws everything from now on in `theme`.
+pub fn set(theme: Theme) {
+    let index = Theme::ALL.iter().position(|t| *t == theme).unwrap_or(0);
+    CURRENT.store(index as u8, Ordering::Relaxed);
+}
+
+/// The theme being drawn, [`Theme::Dark`] unless [`set`] chose another.
+pub fn current() -> Theme {
+    Theme::ALL[CURRENT.load(Ordering::Relaxed) as usize]
+}
+
+pub fn primary() -> Color {
+    current().palette().primary
+}
+
+pub fn online() -> Color {
+    current().palette().online
+}
+
+pub fn offline() -> Color {
+    current().palette().offline
+}
+
+pub fn error() -> Color {
+    current().palette().error
+}
+
+pub fn text_dim() -> Color {
+    current().palette().text_dim
+}
+
+pub fn match_bg() -> Color {
+    current().palette().match_bg
+}
@@ -1,23 +1,37 @@
-use crate::tui::app::{App, InputMode, ScanState};
-use crate::tui::theme;
-use crate::types::ScanStatus;
+use crate::tui::app::{App, InputMode, ReachMatrixView, ScanState, TracerouteView};
+use crate::tui::setup::{self, SetupStep, SetupWizard};
+use crate::tui::theme::{self, Theme};
+use crate::types::{PortInfo, ScanStatus, VendorDetails};
 use ratatui::{
     Frame,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, Gauge, Paragraph, Row, Table},
+    widgets::{Block, Borders, Clear, Gauge, LineGauge, Paragraph, Row, Table, TableState, Wrap},
 };
 
+/// Most per-range progress lines shown under the main gauge.
+const MAX_RANGE_ROWS: usize = 5;
+
+/// Height of the event log pane, borders included.
+const EVENT_LOG_HEIGHT: u16 = 8;
+
 pub fn render(f: &mut Frame, app: &mut App) {
+    let range_rows = app.range_rows(MAX_RANGE_ROWS);
+    let log_height = if app.show_event_log {
+        EVENT_LOG_HEIGHT
+    } else {
+        0
+    };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
             [
-                Constraint::Length(3), // Header/Input
-                Constraint::Length(3), // Progress
-                Constraint::Min(0),    // Table
-                Constraint::Length(4), // Status/Footer
+                Constraint::Length(3),                           // Header/Input
+                Constraint::Length(3 + range_rows.len() as u16), // Progress
+                Constraint::Min(0),                              // Table
+                Constraint::Length(log_height),                  // Event log
+                Constraint::Length(4),                           // Status/Footer
             ]
             .as_ref(),
         )
@@ -32,41 +46,114 @@ pub fn render(f: &mut Frame, app: &mut App) {
     f.render_widget(
         Paragraph::new("🔍 RageScanner").style(
             Style::default()
-                .fg(theme::PRIMARY)
+                .fg(theme::primary())
                 .add_modifier(Modifier::BOLD),
         ),
         header_chunk[0],
     );
 
     let input_style = match app.input_mode {
-        InputMode::Normal => Style::default(),
-        InputMode::Editing => Style::default().fg(Color::Yellow),
+        InputMode::Normal | InputMode::DeviceNote => Style::default(),
+        InputMode::Editing | InputMode::Label | InputMode::Search | InputMode::Command => {
+            Style::default().fg(Color::Yellow)
+        }
     };
 
-    let input = Paragraph::new(format!("RANGE: [{}]", app.input))
+    let (input_text, input_title) = if app.input_mode == InputMode::Command {
+        (
+            format!(":{}", app.command_input),
+            " Command: scan, stop, ports, export, filter, q (Enter:Run Esc:Cancel) ".to_string(),
+        )
+    } else if app.read_only {
+        (
+            format!("FILE: [{}]", app.input),
+            " Saved Results (read-only) ".to_string(),
+        )
+    } else if app.input_mode == InputMode::Search {
+        (
+            format!("SEARCH: [{}]", app.search),
+            " Search IP, hostname, MAC, vendor or name (Enter:Keep Esc:Clear) ".to_string(),
+        )
+    } else if app.input_mode == InputMode::Label {
+        (
+            format!("LABEL: [{}]", app.label_input),
+            " Scan Label (name | description, Enter:Set Esc:Cancel) ".to_string(),
+        )
+    } else {
+        let title = match app.target_preview() {
+            Some(preview) => format!(" Scan {} ", preview),
+            None => " Range Input (i:Edit n:Label Enter:Scan) ".to_string(),
+        };
+        (format!("RANGE: [{}]", app.input), title)
+    };
+    let input = Paragraph::new(input_text)
         .style(input_style)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(" Range Input (i:Edit Enter:Scan) "),
-        );
+        .block(Block::default().borders(Borders::ALL).title(input_title));
     f.render_widget(input, header_chunk[1]);
 
     // Cursor in editing mode
-    if app.input_mode == InputMode::Editing {
-        f.set_cursor_position((
+    match app.input_mode {
+        InputMode::Editing => f.set_cursor_position((
             header_chunk[1].x + 9 + app.input.len() as u16,
             header_chunk[1].y + 1,
-        ));
+        )),
+        InputMode::Label => f.set_cursor_position((
+            header_chunk[1].x + 9 + app.label_input.chars().count() as u16,
+            header_chunk[1].y + 1,
+        )),
+        InputMode::Search => f.set_cursor_position((
+            header_chunk[1].x + 10 + app.search.chars().count() as u16,
+            header_chunk[1].y + 1,
+        )),
+        InputMode::Command => f.set_cursor_position((
+            header_chunk[1].x + 2 + app.command_input.chars().count() as u16,
+            header_chunk[1].y + 1,
+        )),
+        InputMode::Normal | InputMode::DeviceNote => {}
     }
 
     // 2. Progress Gauge
-    if app.scan_state == ScanState::Scanning || app.progress > 0 {
+    if matches!(app.scan_state, ScanState::Scanning | ScanState::Monitoring) || app.progress > 0 {
         let gauge = Gauge::default()
-            .block(Block::default().borders(Borders::ALL).title(" Progress "))
-            .gauge_style(Style::default().fg(theme::PRIMARY))
+            .block(Block::default().borders(Borders::ALL).title(
+                match (&app.active_label, app.paused) {
+                    (Some(label), false) => format!(" Progress: {} ", label),
+                    (Some(label), true) => format!(" Progress: {} (paused) ", label),
+                    (None, false) => " Progress ".to_string(),
+                    (None, true) => " Progress (paused) ".to_string(),
+                },
+            ))
+            .gauge_style(Style::default().fg(theme::primary()))
             .percent(app.progress as u16);
-        f.render_widget(gauge, chunks[1]);
+        // e.g. `54% - Discovery 80% · Ports 35% - 1200 pkt/s, ETA 0:42`
+        let mut label = format!("{}%", app.progress);
+        if let Some(phase) = &app.phase {
+            label.push_str(&format!(" - {}", phase));
+        }
+        if let Some(stats) = &app.stats {
+            label.push_str(&format!(" - {}", stats));
+        }
+        let gauge = gauge.label(label);
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                std::iter::once(Constraint::Length(3))
+                    .chain(range_rows.iter().map(|_| Constraint::Length(1))),
+            )
+            .split(chunks[1]);
+        f.render_widget(gauge, rows[0]);
+        for (range, &area) in range_rows.iter().zip(&rows[1..]) {
+            let color = if range.is_complete() {
+                theme::online()
+            } else {
+                theme::primary()
+            };
+            let line = LineGauge::default()
+                .filled_style(Style::default().fg(color))
+                .label(format!(" {} ", range))
+                .ratio(f64::from(range.percent()) / 100.0);
+            f.render_widget(line, area);
+        }
     } else {
         f.render_widget(
             Paragraph::new("Ready to scan.").block(Block::default().borders(Borders::ALL)),
@@ -77,56 +164,138 @@ pub fn render(f: &mut Frame, app: &mut App) {
     // 3. Results Table
     let selected_style = Style::default()
         .add_modifier(Modifier::REVERSED)
-        .fg(theme::PRIMARY);
-    let header_cells = ["STAT", "HOSTNAME / MAC", "IP ADDRESS", "VENDOR"]
-        .iter()
-        .map(|h| {
-            Span::styled(
-                *h,
-                Style::default()
-                    .fg(theme::PRIMARY)
-                    .add_modifier(Modifier::BOLD),
-            )
-        });
+        .fg(theme::primary());
+    let header_cells = [
+        "STAT",
+        "HOSTNAME / MAC",
+        "IP ADDRESS",
+        "LATENCY",
+        "VENDOR",
+        "OPEN PORTS",
+    ]
+    .iter()
+    .map(|h| {
+        Span::styled(
+            *h,
+            Style::default()
+                .fg(theme::primary())
+                .add_modifier(Modifier::BOLD),
+        )
+    });
     let header = Row::new(header_cells).height(1).bottom_margin(1);
 
-    let rows: Vec<Row> = app
-        .filtered_results()
-        .into_iter()
+    // Borders plus the header and its margin.
+    let height = usize::from(chunks[2].height.saturating_sub(4));
+    let total = app.filtered_results().len();
+    let page = app.visible_rows(height, total);
+    let mut page_state = TableState::default().with_selected(
+        app.table_state
+            .selected()
+            .and_then(|i| i.checked_sub(page.start))
+            .filter(|&i| i < page.len()),
+    );
+    let title = if app.search.is_empty() {
+        format!(
+            " Scan Results {}-{} of {} (↑↓:Nav PgUp/PgDn:Page Enter:Details a:Actions v:Vendor Tab:Filter /:Search x/X:Export) ",
+            (page.start + 1).min(total),
+            page.end,
+            total
+        )
+    } else {
+        format!(
+            " Scan Results {}-{} of {} matching \"{}\" (Esc:Clear /:Edit) ",
+            (page.start + 1).min(total),
+            page.end,
+            total,
+            app.search
+        )
+    };
+    let query = app.search.to_ascii_lowercase();
+
+    let rows: Vec<Row> = app.filtered_results()[page]
+        .iter()
         .map(|item| {
+            let alerted = app.security_alerts.contains(&item.ip);
             let (status_icon, status_color) = match item.status {
-                ScanStatus::Online => ("●", theme::ONLINE),
-                ScanStatus::Offline => ("○", theme::OFFLINE),
-                ScanStatus::Scanning => ("◌", theme::PRIMARY),
-                ScanStatus::SystemError(_) => ("!", theme::ERROR),
+                _ if alerted => ("⚠", theme::error()),
+                ScanStatus::Online => ("●", theme::online()),
+                ScanStatus::Offline => ("○", theme::offline()),
+                ScanStatus::Scanning => ("◌", theme::primary()),
+                ScanStatus::SystemError(_) => ("!", theme::error()),
+                ScanStatus::NotScanned => ("-", theme::text_dim()),
             };
 
             let hostname = item
-                .hostname
-                .clone()
+                .device_note
+                .as_ref()
+                .map(|note| note.name.clone())
+                .or_else(|| item.hostname.clone())
                 .unwrap_or_else(|| "Unknown Device".to_string());
             let mac = item
                 .mac
                 .clone()
                 .unwrap_or_else(|| "--:--:--:--:--:--".to_string());
             let vendor = item.vendor.clone().unwrap_or_else(|| "---".to_string());
+            let latency = item
+                .latency_ms()
+                .map(|ms| format!("{:.1} ms", ms))
+                .unwrap_or_else(|| "---".to_string());
+            let ports = if item.open_ports.is_empty() {
+                "---".to_string()
+            } else {
+                item.open_ports
+                    .iter()
+                    .map(|p| format!("{}/{}", p.port, p.label()))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            };
 
             Row::new(vec![
-                Line::from(vec![Span::styled(
-                    status_icon.to_string(),
-                    Style::default().fg(status_color),
-                )]),
                 Line::from(vec![
-                    Span::styled(hostname, Style::default().add_modifier(Modifier::BOLD)),
-                    Span::raw(" "),
-                    Span::styled(mac, Style::default().fg(theme::TEXT_DIM)),
+                    Span::styled(status_icon.to_string(), Style::default().fg(status_color)),
+                    Span::styled(
+                        item.device_type
+                            .map(|t| format!(" {}", t.glyph()))
+                            .unwrap_or_default(),
+                        Style::default().fg(theme::text_dim()),
+                    ),
                 ]),
-                Line::from(vec![Span::styled(
+                Line::from(
+                    [
+                        highlighted(
+                            hostname,
+                            &query,
+                            Style::default().add_modifier(Modifier::BOLD),
+                        ),
+                        vec![Span::styled(
+                            item.roles
+                                .iter()
+                                .map(|role| format!(" [{}]", role))
+                                .collect::<String>(),
+                            Style::default().fg(theme::primary()),
+                        )],
+                        vec![Span::raw(" ")],
+                        highlighted(mac, &query, Style::default().fg(theme::text_dim())),
+                    ]
+                    .concat(),
+                ),
+                Line::from(highlighted(
                     item.ip.to_string(),
-                    Style::default().fg(theme::PRIMARY),
+                    &query,
+                    Style::default().fg(theme::primary()),
+                )),
+                Line::from(vec![Span::styled(
+                    latency,
+                    Style::default().fg(theme::text_dim()),
                 )]),
-                Line::from(vec![Span::raw(vendor)]),
+                Line::from(highlighted(vendor, &query, Style::default())),
+                Line::from(Span::styled(ports, Style::default().fg(theme::text_dim()))),
             ])
+            .style(if alerted {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            })
         })
         .collect();
 
@@ -136,19 +305,17 @@ pub fn render(f: &mut Frame, app: &mut App) {
             Constraint::Length(5),
             Constraint::Min(30),
             Constraint::Length(18),
+            Constraint::Length(10),
             Constraint::Length(15),
+            Constraint::Min(20),
         ],
     )
     .header(header)
-    .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .title(" Scan Results (↑↓:Nav Enter:Details Tab:Filter) "),
-    )
+    .block(Block::default().borders(Borders::ALL).title(title))
     .row_highlight_style(selected_style)
     .highlight_symbol(">> ");
 
-    f.render_stateful_widget(t, chunks[2], &mut app.table_state);
+    f.render_stateful_widget(t, chunks[2], &mut page_state);
 
     // 4. Status Bar
     let online_count = app
@@ -156,41 +323,484 @@ pub fn render(f: &mut Frame, app: &mut App) {
         .iter()
         .filter(|r| r.status == ScanStatus::Online)
         .count();
-    let status_text = format!(
-        " {} Found | {} Online | Mode: {:?} | q:Quit s:Stop",
-        app.results.len(),
-        online_count,
-        app.scan_state
-    );
-    let attr = " (c) WSALIGAN ";
+    let status_text = if app.read_only {
+        format!(
+            " {} Found | {} Online | Read-only | q:Quit Enter:Details a:Actions v:Vendor Tab:Online only L:Log x:Export ::Command ?:About",
+            app.results.len(),
+            online_count
+        )
+    } else {
+        format!(
+            " {} Found | {} Online | Mode: {:?} | UDP: {} | Pings: {} | .0/.255: {} | Busy: {:?} | Order: {} | q:Quit s:Stop space:Pause m:Monitor P:Passive u:UDP p:Pings b:Bcast B:Busy l:Order o/O:Sort W:Wake M:Matrix L:Log ::Command ?:About",
+            app.results.len(),
+            online_count,
+            app.scan_state,
+            match (app.capabilities.udp, app.udp_scan) {
+                (false, _) => "N/A",
+                (true, true) => "On",
+                (true, false) => "Off",
+            },
+            app.ping_attempts,
+            if app.include_subnet_edges {
+                "Scan"
+            } else {
+                "Skip"
+            },
+            app.busy_policy,
+            app.order_label()
+        )
+    };
+    let mut attr = String::from(" ");
+    if let Some(label) = &app.scan_label {
+        attr.push_str(&format!("Next label: {} | ", label.name));
+    }
+    if let Some(notice) = &app.notice {
+        attr.push_str(&format!("{} | ", notice));
+    }
+    if let Some(change) = &app.last_change {
+        attr.push_str(&format!("Last change: {} | ", change));
+    }
+    if let Some(summary) = &app.summary {
+        attr.push_str(&format!("Last scan: {} | ", summary));
+    }
+    attr.push_str("(c) WSALIGAN ");
 
     let footer = Paragraph::new(vec![
         Line::from(Span::styled(
             status_text,
-            Style::default().fg(theme::TEXT_DIM),
+            Style::default().fg(theme::text_dim()),
         )),
-        Line::from(Span::styled(attr, Style::default().fg(theme::TEXT_DIM))),
+        Line::from(Span::styled(attr, Style::default().fg(theme::text_dim()))),
     ])
     .block(Block::default().borders(Borders::TOP));
-    f.render_widget(footer, chunks[3]);
+    f.render_widget(footer, chunks[4]);
+
+    if app.show_event_log {
+        render_event_log(f, app, chunks[3]);
+    }
 
     // 5. Detail Popup
-    if app.show_detail
-        && let Some(selected_idx) = app.table_state.selected()
-        && let Some(res) = app.filtered_results().get(selected_idx)
+    if app.show_detail {
+        let max_scroll = app.selected_detail().map(|res| {
+            let deep = app.deep_scan.as_ref().filter(|d| d.ip == res.ip);
+            let trace = app.traceroute.as_ref().filter(|t| t.ip == res.ip);
+            render_detail_popup(f, &res, deep, trace, app.read_only, app.detail_scroll)
+        });
+        if let Some(max_scroll) = max_scroll {
+            app.detail_scroll = app.detail_scroll.min(max_scroll);
+        }
+    }
+
+    // 6. Action and Wake Menus
+    if let Some(highlighted) = app.action_menu
+        && let Some(res) = app.selected_result()
     {
-        render_detail_popup(f, res);
+        let items: Vec<(String, bool)> = app
+            .actions()
+            .into_iter()
+            .map(|action| {
+                let label = format!("{}  {}", action.key(), action);
+                (label, app.action_unavailable(action).is_none())
+            })
+            .collect();
+        render_menu(
+            f,
+            &format!(" Actions for {} (Esc:Close) ", res.ip),
+            &items,
+            highlighted,
+        );
+    }
+    if let Some(highlighted) = app.wake_menu {
+        let items: Vec<(String, bool)> = app
+            .wake_choices()
+            .into_iter()
+            .map(|(label, _)| (label, true))
+            .collect();
+        render_menu(
+            f,
+            " Wake-on-LAN (Enter:Wake Esc:Close) ",
+            &items,
+            highlighted,
+        );
+    }
+
+    // 7. Vendor Popup, About and Reachability Matrix
+    if let Some(details) = &app.vendor_details {
+        render_vendor_popup(f, details);
+    }
+    if let Some(view) = &app.reach_matrix {
+        render_reach_matrix(f, view);
+    }
+    if let Some(fields) = &app.about {
+        render_about_popup(f, fields);
+    }
+    if app.input_mode == InputMode::DeviceNote {
+        render_device_note_popup(f, app);
     }
+    if app.confirm_stop {
+        render_confirm_stop(f);
+    }
+
+    // 8. First-run Setup
+    if let Some(wizard) = &app.setup {
+        render_setup(f, wizard);
+    }
+
+    // 9. Error Banner, over the top of the table so it stays visible above popups
+    if let Some(error) = &app.error {
+        render_error_banner(f, error, chunks[2]);
+    }
+}
+
+/// Splits `text` into spans in `style`, with every occurrence of `query`
+/// (ASCII-lowercase, matched ignoring ASCII case) marked.
+fn highlighted(text: String, query: &str, style: Style) -> Vec<Span<'static>> {
+    if query.is_empty() {
+        return vec![Span::styled(text, style)];
+    }
+    let match_style = style.bg(theme::match_bg()).fg(Color::Black);
+    let lower = text.to_ascii_lowercase();
+    let mut spans = Vec::new();
+    let mut rest = 0;
+    for (start, _) in lower.match_indices(query) {
+        if start > rest {
+            spans.push(Span::styled(text[rest..start].to_string(), style));
+        }
+        let end = start + query.len();
+        spans.push(Span::styled(text[start..end].to_string(), match_style));
+        rest = end;
+    }
+    if rest < text.len() {
+        spans.push(Span::styled(text[rest..].to_string(), style));
+    }
+    spans
 }
 
-fn render_detail_popup(f: &mut Frame, res: &crate::types::ScanResult) {
+fn render_error_banner(f: &mut Frame, error: &str, area: Rect) {
+    let area = Rect {
+        height: area.height.min(4),
+        ..area
+    };
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(" Error (Esc:Dismiss) ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme::error()));
+    let p = Paragraph::new(error)
+        .style(Style::default().fg(theme::error()))
+        .block(block)
+        .wrap(Wrap { trim: true });
+    f.render_widget(p, area);
+}
+
+/// Asks whether to stop the running scan; see [`App::request_stop`].
+fn render_confirm_stop(f: &mut Frame) {
+    let area = centered_rect(60, 100, f.area());
+    let area = Rect {
+        y: area.y + area.height.saturating_sub(5) / 2,
+        height: area.height.min(5),
+        ..area
+    };
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(" Stop scan? ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme::primary()));
+    let p = Paragraph::new(
+        "y: stop, listing hosts not probed yet as Not scanned. Any other key keeps scanning.",
+    )
+    .block(block)
+    .wrap(Wrap { trim: true });
+    f.render_widget(p, area);
+}
+
+fn render_vendor_popup(f: &mut Frame, details: &VendorDetails) {
+    let area = centered_rect(50, 40, f.area());
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(" Vendor Details (Esc:Close) ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme::primary()));
+
+    let field = |label: &'static str, value: String| {
+        Line::from(vec![
+            Span::styled(label, Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(value),
+        ])
+    };
+    let mut registry = details.registry.clone();
+    if details.is_private {
+        registry.push_str(" (private)");
+    }
+    let text = vec![
+        field("COMPANY:  ", details.company_name.clone()),
+        field("ADDRESS:  ", details.company_address.clone()),
+        field("COUNTRY:  ", details.country_code.clone()),
+        field("OUI:      ", details.oui.clone()),
+        field("REGISTRY: ", registry),
+        field("UPDATED:  ", details.date_updated.clone()),
+    ];
+
+    let p = Paragraph::new(text).block(block).wrap(Wrap { trim: false });
+    f.render_widget(p, area);
+}
+
+fn render_about_popup(f: &mut Frame, fields: &[(&'static str, String)]) {
+    let area = centered_rect(70, 50, f.area());
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(" About (Esc:Close) ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme::primary()));
+
+    let text: Vec<Line> = fields
+        .iter()
+        .map(|(label, value)| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:<10}", format!("{}:", label.to_uppercase())),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(value.clone()),
+            ])
+        })
+        .collect();
+
+    let p = Paragraph::new(text).block(block).wrap(Wrap { trim: false });
+    f.render_widget(p, area);
+}
+
+/// Draws the name and notes being typed for a device, with the cursor.
+fn render_device_note_popup(f: &mut Frame, app: &App) {
+    let area = centered_rect(60, 100, f.area());
+    let area = Rect {
+        y: area.y + area.height.saturating_sub(3) / 2,
+        height: 3.min(area.height),
+        ..area
+    };
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(format!(
+            " Name {} (name | notes, Enter:Save Esc:Cancel) ",
+            app.device_note_mac.as_deref().unwrap_or_default()
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme::primary()));
+    let p = Paragraph::new(app.device_note_input.as_str())
+        .style(Style::default().fg(Color::Yellow))
+        .block(block);
+    f.render_widget(p, area);
+    f.set_cursor_position((
+        area.x + 1 + app.device_note_input.chars().count() as u16,
+        area.y + 1,
+    ));
+}
+
+/// Draws the host × port matrix, or a waiting line while it is probed.
+fn render_reach_matrix(f: &mut Frame, view: &ReachMatrixView) {
+    let area = centered_rect(80, 70, f.area());
+    f.render_widget(Clear, area);
+    let block = Block::default()
+        .title(" Reachability (x:Export Esc:Close) ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme::primary()));
+
+    let Some(matrix) = &view.matrix else {
+        let p = Paragraph::new(format!("Testing {} hosts...", view.hosts)).block(block);
+        f.render_widget(p, area);
+        return;
+    };
+    let bold = Style::default()
+        .fg(theme::primary())
+        .add_modifier(Modifier::BOLD);
+    let header = Row::new(
+        std::iter::once(Span::styled("HOST", bold)).chain(
+            matrix
+                .ports
+                .iter()
+                .map(|p| Span::styled(p.to_string(), bold)),
+        ),
+    )
+    .bottom_margin(1);
+    let rows = matrix.rows.iter().map(|row| {
+        let cells = matrix.ports.iter().map(|port| {
+            if row.host.ip.is_none() {
+                Span::styled("?", Style::default().fg(theme::error()))
+            } else if row.open.contains(port) {
+                Span::styled("open", Style::default().fg(theme::online()))
+            } else {
+                Span::styled("-", Style::default().fg(theme::text_dim()))
+            }
+        });
+        Row::new(std::iter::once(Span::raw(row.host.name.clone())).chain(cells))
+    });
+    let widths = std::iter::once(Constraint::Length(16))
+        .chain(matrix.ports.iter().map(|_| Constraint::Length(6)));
+    let t = Table::new(rows, widths).header(header).block(block);
+    f.render_widget(t, area);
+}
+
+/// Draws the current page of the first-run setup wizard.
+fn render_setup(f: &mut Frame, wizard: &SetupWizard) {
     let area = centered_rect(60, 60, f.area());
     f.render_widget(Clear, area);
 
+    let choice = |selected: bool, text: String| {
+        let (marker, style) = if selected {
+            (
+                "> ",
+                Style::default()
+                    .add_modifier(Modifier::REVERSED)
+                    .fg(theme::primary()),
+            )
+        } else {
+            ("  ", Style::default())
+        };
+        Line::from(Span::styled(format!("{}{}", marker, text), style))
+    };
+    let hint =
+        |text: &'static str| Line::from(Span::styled(text, Style::default().fg(theme::text_dim())));
+
+    let (title, mut lines) = match wizard.step {
+        SetupStep::Range => {
+            let mut lines = vec![Line::from(
+                "Which network should RageScanner scan by default?",
+            )];
+            lines.push(Line::from(""));
+            if wizard.interfaces.is_empty() {
+                lines.push(hint("No network adapter detected; type a range."));
+            }
+            for (i, iface) in wizard.interfaces.iter().enumerate() {
+                lines.push(choice(
+                    i == wizard.highlighted,
+                    format!(
+                        "{}  {}/{}  ({})",
+                        iface.name,
+                        iface.address,
+                        iface.prefix_len,
+                        iface.range_string()
+                    ),
+                ));
+            }
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![
+                Span::styled("RANGE: ", Style::default().add_modifier(Modifier::BOLD)),
+                Span::raw(format!("{}_", wizard.range)),
+            ]));
+            if let Some(error) = &wizard.error {
+                lines.push(Line::from(Span::styled(
+                    error.clone(),
+                    Style::default().fg(theme::error()),
+                )));
+            }
+            lines.push(Line::from(""));
+            lines.push(hint(
+                "Up/Down: pick a subnet  Type: edit  Enter: next  Esc: skip",
+            ));
+            (" Setup 1/3: Default Range ", lines)
+        }
+        SetupStep::Profile => {
+            let mut lines = vec![Line::from("How should scans probe the network?")];
+            lines.push(Line::from(""));
+            for profile in setup::PROFILES {
+                lines.push(choice(
+                    profile == wizard.profile,
+                    setup::describe_profile(profile).to_string(),
+                ));
+            }
+            lines.push(Line::from(""));
+            lines.push(hint("Up/Down: choose  Enter: next  Esc: back"));
+            (" Setup 2/3: Scan Profile ", lines)
+        }
+        SetupStep::Theme => {
+            let mut lines = vec![Line::from(
+                "Pick the colors that read best in this terminal.",
+            )];
+            lines.push(Line::from(""));
+            for theme in Theme::ALL {
+                lines.push(choice(theme == wizard.theme, theme.to_string()));
+            }
+            lines.push(Line::from(""));
+            lines.push(hint("Up/Down: preview  Enter: save  Esc: back"));
+            (" Setup 3/3: Theme ", lines)
+        }
+    };
+    lines.insert(0, Line::from(""));
+
     let block = Block::default()
-        .title(" Device Details (Esc:Close) ")
+        .title(title)
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(theme::PRIMARY));
+        .border_style(Style::default().fg(theme::primary()));
+    f.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false }),
+        area,
+    );
+}
+
+/// The newest event log lines that fit in `area`, minus the scroll offset.
+fn render_event_log(f: &mut Frame, app: &App, area: Rect) {
+    let height = area.height.saturating_sub(2) as usize;
+    let end = app.event_log.len() - app.event_log_scroll.min(app.event_log.len());
+    let lines: Vec<Line> = app
+        .event_log
+        .range(end.saturating_sub(height)..end)
+        .map(|event| {
+            let style = if event.is_error {
+                Style::default().fg(theme::error())
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::styled(
+                    format!("{} ", event.time),
+                    Style::default().fg(theme::text_dim()),
+                ),
+                Span::styled(event.text.clone(), style),
+            ])
+        })
+        .collect();
+    let title = if app.event_log_scroll > 0 {
+        format!(
+            " Events ({} newer) (L:Hide [/]:Scroll) ",
+            app.event_log_scroll
+        )
+    } else {
+        " Events (L:Hide [/]:Scroll) ".to_string()
+    };
+    f.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)),
+        area,
+    );
+}
+
+/// Draws the detail popup scrolled down by `scroll` lines and returns the
+/// furthest it can scroll.
+fn render_detail_popup(
+    f: &mut Frame,
+    res: &crate::types::ScanResult,
+    deep: Option<&crate::tui::app::DeepScanView>,
+    trace: Option<&TracerouteView>,
+    read_only: bool,
+    scroll: u16,
+) -> u16 {
+    let area = centered_rect(60, 60, f.area());
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(if read_only {
+            " Device Details (Esc:Close ↑↓/PgUp/PgDn:Scroll a:Actions v:Vendor) "
+        } else {
+            " Device Details (Esc:Close ↑↓/PgUp/PgDn:Scroll a:Actions d:Deep Scan v:Vendor) "
+        })
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme::primary()));
 
     let mut text = vec![
         Line::from(vec![
@@ -206,6 +816,12 @@ fn render_detail_popup(f: &mut Frame, res: &crate::types::ScanResult) {
                 Style::default().add_modifier(Modifier::BOLD),
             ),
             Span::raw(res.hostname.as_deref().unwrap_or("Unknown")),
+            Span::styled(
+                res.hostname_source
+                    .map(|s| format!(" ({})", s))
+                    .unwrap_or_default(),
+                Style::default().fg(theme::text_dim()),
+            ),
         ]),
         Line::from(vec![
             Span::styled(
@@ -221,6 +837,75 @@ fn render_detail_popup(f: &mut Frame, res: &crate::types::ScanResult) {
             ),
             Span::raw(res.vendor.as_deref().unwrap_or("---")),
         ]),
+        Line::from(vec![
+            Span::styled(
+                "TYPE:       ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(
+                res.device_type
+                    .map(|t| format!("{} {}", t.glyph(), t))
+                    .unwrap_or_else(|| "---".to_string()),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "ROLES:      ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(if res.roles.is_empty() {
+                "---".to_string()
+            } else {
+                res.roles
+                    .iter()
+                    .map(|role| role.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "OS GUESS:   ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(
+                res.os_guess
+                    .as_ref()
+                    .map(|g| format!("{} ({}%)", g.family, g.confidence))
+                    .unwrap_or_else(|| "---".to_string()),
+            ),
+            Span::styled(
+                res.os_guess
+                    .as_ref()
+                    .filter(|g| !g.evidence.is_empty())
+                    .map(|g| format!(" {}", g.evidence.join(", ")))
+                    .unwrap_or_default(),
+                Style::default().fg(theme::text_dim()),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "WEB:        ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(
+                res.web_info
+                    .as_ref()
+                    .map(|w| w.to_string())
+                    .unwrap_or_else(|| "---".to_string()),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                "LATENCY:    ",
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(
+                res.latency
+                    .map(|l| format!("{} ({}/{} replies)", l.summary(), l.received, l.sent))
+                    .unwrap_or_else(|| "---".to_string()),
+            ),
+        ]),
         Line::from(""),
         Line::from(vec![Span::styled(
             "ACTIVE PORTS:",
@@ -228,20 +913,327 @@ fn render_detail_popup(f: &mut Frame, res: &crate::types::ScanResult) {
         )]),
     ];
 
+    if let Some(note) = &res.device_note {
+        text.insert(
+            1,
+            Line::from(vec![
+                Span::styled(
+                    "NAME:       ",
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(note.name.clone()),
+                Span::styled(
+                    note.notes
+                        .as_ref()
+                        .map(|notes| format!(" ({})", notes))
+                        .unwrap_or_default(),
+                    Style::default().fg(theme::text_dim()),
+                ),
+            ]),
+        );
+    }
+
+    if !res.issues.is_empty() {
+        let issues: Vec<String> = res.issues.iter().map(ToString::to_string).collect();
+        // Above the blank line before the port list.
+        text.insert(
+            text.len() - 2,
+            Line::from(vec![
+                Span::styled(
+                    "ISSUES:     ",
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(issues.join(", "), Style::default().fg(theme::error())),
+            ]),
+        );
+    }
+
+    if let Some(asset) = &res.asset {
+        let fields: Vec<&str> = [&asset.asset_tag, &asset.owner, &asset.location]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        text.insert(
+            text.len() - 2,
+            Line::from(vec![
+                Span::styled(
+                    "ASSET:      ",
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(fields.join(" | ")),
+            ]),
+        );
+    }
+
+    if !res.extras.is_empty() {
+        let mut extras: Vec<String> = res
+            .extras
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        extras.sort();
+        text.insert(
+            text.len() - 2,
+            Line::from(vec![
+                Span::styled(
+                    "EXTRAS:     ",
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(extras.join(", ")),
+            ]),
+        );
+    }
+
+    for warning in &res.warnings {
+        text.insert(
+            text.len() - 2,
+            Line::from(Span::styled(
+                format!("WARNING:    {}", warning),
+                Style::default().fg(theme::error()),
+            )),
+        );
+    }
+
+    if let ScanStatus::SystemError(e) = &res.status {
+        text.insert(
+            6,
+            Line::from(Span::styled(
+                format!("ERROR:      {}", e),
+                Style::default().fg(theme::error()),
+            )),
+        );
+        if let Some(hint) = e.hint() {
+            text.insert(
+                6,
+                Line::from(Span::styled(
+                    format!("            {}", hint),
+                    Style::default().fg(theme::text_dim()),
+                )),
+            );
+        }
+    }
+
     if res.open_ports.is_empty() {
         text.push(Line::from(Span::styled(
             "  No open ports found or scan incomplete.",
-            Style::default().fg(theme::TEXT_DIM),
+            Style::default().fg(theme::text_dim()),
         )));
     } else {
-        for port in &res.open_ports {
-            let service = crate::types::port_label(*port);
-            text.push(Line::from(format!("  • Port {}: {}", port, service)));
+        text.extend(port_table(&res.open_ports));
+    }
+
+    if !res.open_udp_ports.is_empty() || !res.filtered_udp_ports.is_empty() {
+        text.push(Line::from(""));
+        text.push(Line::from(vec![Span::styled(
+            "UDP PORTS:",
+            Style::default().add_modifier(Modifier::BOLD),
+        )]));
+        for port in &res.open_udp_ports {
+            let service = crate::types::udp_port_label(*port);
+            text.push(Line::from(format!("  • Port {}/udp: {}", port, service)));
+        }
+        for port in &res.filtered_udp_ports {
+            let service = crate::types::udp_port_label(*port);
+            text.push(Line::from(Span::styled(
+                format!("  • Port {}/udp: {} (open|filtered)", port, service),
+                Style::default().fg(theme::text_dim()),
+            )));
+        }
+    }
+
+    if !res.services.is_empty() {
+        text.push(Line::from(""));
+        text.push(Line::from(vec![Span::styled(
+            "MDNS SERVICES:",
+            Style::default().add_modifier(Modifier::BOLD),
+        )]));
+        for service in &res.services {
+            text.push(Line::from(format!("  • {}", service)));
+        }
+    }
+
+    if let Some(upnp) = &res.upnp {
+        text.push(Line::from(""));
+        text.push(Line::from(vec![Span::styled(
+            "UPNP DEVICE:",
+            Style::default().add_modifier(Modifier::BOLD),
+        )]));
+        let fields = [
+            ("Name", &upnp.friendly_name),
+            ("Manufacturer", &upnp.manufacturer),
+            ("Model", &upnp.model),
+        ];
+        for (label, value) in fields {
+            if let Some(value) = value {
+                text.push(Line::from(format!("  • {}: {}", label, value)));
+            }
+        }
+    }
+
+    if let Some(smb) = &res.smb_info {
+        text.push(Line::from(""));
+        text.push(Line::from(vec![Span::styled(
+            "SMB:",
+            Style::default().add_modifier(Modifier::BOLD),
+        )]));
+        let dialect = if smb.smb1_only() {
+            Span::styled(
+                format!("  • Dialect: SMB {} (SMBv1 only)", smb.dialect),
+                Style::default().fg(theme::error()),
+            )
+        } else {
+            Span::raw(format!("  • Dialect: SMB {}", smb.dialect))
+        };
+        text.push(Line::from(dialect));
+        let fields = [
+            ("Name", &smb.hostname),
+            ("Domain", &smb.domain),
+            ("Windows", &smb.os_version),
+        ];
+        for (label, value) in fields {
+            if let Some(value) = value {
+                text.push(Line::from(format!("  • {}: {}", label, value)));
+            }
         }
     }
 
-    let p = Paragraph::new(text).block(block);
+    if let Some(snmp) = &res.snmp {
+        text.push(Line::from(""));
+        text.push(Line::from(vec![Span::styled(
+            "SNMP:",
+            Style::default().add_modifier(Modifier::BOLD),
+        )]));
+        if let Some(name) = &snmp.name {
+            text.push(Line::from(format!("  • Name: {}", name)));
+        }
+        if let Some(description) = &snmp.description {
+            text.push(Line::from(format!("  • Description: {}", description)));
+        }
+        if let Some(uptime) = snmp.uptime {
+            text.push(Line::from(format!(
+                "  • Uptime: {}",
+                crate::report::format_uptime(uptime)
+            )));
+        }
+    }
+
+    if let Some(deep) = deep {
+        text.push(Line::from(""));
+        let state = if deep.finished && deep.progress < 100 {
+            "stopped".to_string()
+        } else {
+            format!("{}%", deep.progress)
+        };
+        text.push(Line::from(vec![Span::styled(
+            format!("DEEP SCAN ({}):", state),
+            Style::default().add_modifier(Modifier::BOLD),
+        )]));
+        if deep.ports.is_empty() {
+            text.push(Line::from(Span::styled(
+                "  No open ports found yet.",
+                Style::default().fg(theme::text_dim()),
+            )));
+        } else {
+            text.extend(port_table(&deep.ports));
+        }
+    }
+
+    if let Some(trace) = trace {
+        text.push(Line::from(""));
+        let state = if trace.finished { "done" } else { "running" };
+        text.push(Line::from(vec![Span::styled(
+            format!("TRACEROUTE ({}):", state),
+            Style::default().add_modifier(Modifier::BOLD),
+        )]));
+        if trace.hops.is_empty() && !trace.finished {
+            text.push(Line::from(Span::styled(
+                "  Waiting for the first hop...",
+                Style::default().fg(theme::text_dim()),
+            )));
+        }
+        for hop in &trace.hops {
+            let style = if hop.addr.is_none() {
+                Style::default().fg(theme::text_dim())
+            } else {
+                Style::default()
+            };
+            text.push(Line::from(Span::styled(format!("  {}", hop), style)));
+        }
+    }
+
+    let max_scroll = text
+        .len()
+        .saturating_sub(usize::from(area.height.saturating_sub(2)));
+    let max_scroll = u16::try_from(max_scroll).unwrap_or(u16::MAX);
+    let p = Paragraph::new(text)
+        .block(block)
+        .scroll((scroll.min(max_scroll), 0));
     f.render_widget(p, area);
+    max_scroll
+}
+
+/// Lines of a `PORT SERVICE BANNER` table, services aligned to the longest.
+fn port_table(ports: &[PortInfo]) -> Vec<Line<'static>> {
+    let labels: Vec<String> = ports.iter().map(PortInfo::label).collect();
+    let width = labels
+        .iter()
+        .map(|l| l.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("SERVICE".len());
+    let mut lines = vec![Line::from(Span::styled(
+        format!("  {:<7}{:<width$}  BANNER", "PORT", "SERVICE"),
+        Style::default()
+            .fg(theme::text_dim())
+            .add_modifier(Modifier::BOLD),
+    ))];
+    for (info, label) in ports.iter().zip(labels) {
+        lines.push(Line::from(vec![
+            Span::raw(format!("  {:<7}{:<width$}  ", info.port, label)),
+            Span::styled(
+                info.banner.clone().unwrap_or_else(|| "-".to_string()),
+                Style::default().fg(theme::text_dim()),
+            ),
+        ]));
+    }
+    lines
+}
+
+/// Draws a centered menu of `items` with the `highlighted` one reversed;
+/// items paired with `false` are grayed out.
+fn render_menu(f: &mut Frame, title: &str, items: &[(String, bool)], highlighted: usize) {
+    let height = items.len() as u16 + 2;
+    let area = centered_rect(40, 100, f.area());
+    let area = Rect {
+        y: area.y + area.height.saturating_sub(height) / 2,
+        height: height.min(area.height),
+        ..area
+    };
+    f.render_widget(Clear, area);
+
+    let lines: Vec<Line> = items
+        .iter()
+        .enumerate()
+        .map(|(i, (item, enabled))| {
+            let style = match (i == highlighted, enabled) {
+                (true, true) => Style::default()
+                    .add_modifier(Modifier::REVERSED)
+                    .fg(theme::primary()),
+                (true, false) => Style::default()
+                    .add_modifier(Modifier::REVERSED)
+                    .fg(theme::text_dim()),
+                (false, true) => Style::default(),
+                (false, false) => Style::default().fg(theme::text_dim()),
+            };
+            Line::from(Span::styled(format!(" {}", item), style))
+        })
+        .collect();
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(theme::primary()));
+    f.render_widget(Paragraph::new(lines).block(block), area);
 }
 
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
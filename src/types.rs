@@ -1,18 +1,50 @@
 //! Core types shared across the scanner library.
 //!
 //! Defines [`GError`], [`ScanStatus`], [`ScanResult`], and [`BridgeMessage`].
+//!
+//! # Serialization
+//!
+//! Results, errors and configuration always implement `Serialize` and
+//! `Deserialize`, as sessions, exports and the APIs are built on them. The
+//! `serde` feature adds them to [`BridgeMessage`] and every type it carries,
+//! so embedders (a Tauri app, say) can pass bridge messages to a frontend
+//! as-is. The JSON layout of all of these is versioned by [`SCHEMA_VERSION`].
 
+use crate::notify::NotifyConfig;
+use crate::reach::{ReachHost, ReachMatrix};
+use crate::targets::ScanTargets;
+use crate::wol::{WakeReport, WakeTarget};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
-use std::net::Ipv4Addr;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime};
 
 /// Application-wide error type.
 ///
-/// Captures both Win32 API errors (with numeric code) and internal
+/// Well-known failures are classified by [`GError::from_win32`],
+/// [`GError::from_hresult`] or [`GError::from_io`] so frontends can react to
+/// each kind (see [`GError::code`] and [`GError::hint`]); other Win32 API
+/// errors keep their numeric code, and the rest are internal
 /// application-level errors.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GError {
-    /// A Win32 API error with its error code and descriptive message.
-    Win32(u32, String),
+    /// A Win32 API call `op` failed with a code no other variant covers.
+    Win32 { code: u32, op: String },
+    /// The target, or the network it is on, cannot be reached from this machine.
+    HostUnreachable(String),
+    /// The target or service did not answer in time.
+    Timeout(String),
+    /// The local network adapter is disconnected or disabled.
+    AdapterDown(String),
+    /// The operation was refused for lack of privileges, or blocked by
+    /// security software.
+    #[serde(alias = "AccessDenied")]
+    PermissionDenied(String),
+    /// The system ran out of memory, handles or socket buffers.
+    ResourceExhausted(String),
     /// An application-level error with a descriptive message.
     Internal(String),
 }
@@ -20,19 +52,189 @@ pub enum GError {
 impl fmt::Display for GError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            GError::Win32(code, msg) => write!(f, "Win32 Error ({}): {}", code, msg),
+            // HRESULTs outside the Win32 facility read better in hex.
+            GError::Win32 { code, op } if *code > 0xFFFF => {
+                write!(f, "Win32 Error ({:#010X}): {} failed", code, op)
+            }
+            GError::Win32 { code, op } => write!(f, "Win32 Error ({}): {} failed", code, op),
+            GError::HostUnreachable(msg) => write!(f, "Host Unreachable: {}", msg),
+            GError::Timeout(msg) => write!(f, "Timed Out: {}", msg),
+            GError::AdapterDown(msg) => write!(f, "Adapter Down: {}", msg),
+            GError::PermissionDenied(msg) => write!(f, "Permission Denied: {}", msg),
+            GError::ResourceExhausted(msg) => write!(f, "Out of Resources: {}", msg),
             GError::Internal(msg) => write!(f, "Internal Error: {}", msg),
         }
     }
 }
 
+impl std::error::Error for GError {}
+
+// Win32, Winsock and IP Helper status codes returned by SendARP, IcmpSendEcho
+// and the other APIs the scanner calls.
+const ERROR_TOO_MANY_OPEN_FILES: u32 = 4;
+const ERROR_ACCESS_DENIED: u32 = 5;
+const ERROR_NOT_ENOUGH_MEMORY: u32 = 8;
+const ERROR_OUTOFMEMORY: u32 = 14;
+const ERROR_GEN_FAILURE: u32 = 31;
+const ERROR_NETWORK_ACCESS_DENIED: u32 = 65;
+const ERROR_SEM_TIMEOUT: u32 = 121;
+const WAIT_TIMEOUT: u32 = 258;
+const ERROR_DEVICE_NOT_CONNECTED: u32 = 1167;
+const ERROR_NO_NETWORK: u32 = 1222;
+const ERROR_NETWORK_UNREACHABLE: u32 = 1231;
+const ERROR_HOST_UNREACHABLE: u32 = 1232;
+const ERROR_NO_SYSTEM_RESOURCES: u32 = 1450;
+const ERROR_TIMEOUT: u32 = 1460;
+const ERROR_NOT_CONNECTED: u32 = 2250;
+const WSAEACCES: u32 = 10013;
+const WSAEMFILE: u32 = 10024;
+const WSAENETDOWN: u32 = 10050;
+const WSAENETUNREACH: u32 = 10051;
+const WSAENOBUFS: u32 = 10055;
+const WSAETIMEDOUT: u32 = 10060;
+const WSAEHOSTUNREACH: u32 = 10065;
+const IP_DEST_NET_UNREACHABLE: u32 = 11002;
+const IP_DEST_HOST_UNREACHABLE: u32 = 11003;
+const IP_NO_RESOURCES: u32 = 11006;
+const IP_HW_ERROR: u32 = 11008;
+const IP_REQ_TIMED_OUT: u32 = 11010;
+
+/// Facility of HRESULTs that wrap a Win32 error code in their low word.
+const FACILITY_WIN32: i32 = 7;
+
+impl GError {
+    /// Classifies a Win32 error code returned by `api`.
+    ///
+    /// Codes without a specific variant stay as [`GError::Win32`].
+    pub fn from_win32(code: u32, api: &str) -> Self {
+        let msg = format!("{} failed (code {})", api, code);
+        match code {
+            ERROR_ACCESS_DENIED | ERROR_NETWORK_ACCESS_DENIED | WSAEACCES => {
+                GError::PermissionDenied(msg)
+            }
+            // SendARP reports ERROR_GEN_FAILURE for targets outside the local subnet.
+            ERROR_GEN_FAILURE
+            | ERROR_NETWORK_UNREACHABLE
+            | ERROR_HOST_UNREACHABLE
+            | WSAENETUNREACH
+            | WSAEHOSTUNREACH
+            | IP_DEST_NET_UNREACHABLE
+            | IP_DEST_HOST_UNREACHABLE => GError::HostUnreachable(msg),
+            ERROR_SEM_TIMEOUT | WAIT_TIMEOUT | ERROR_TIMEOUT | WSAETIMEDOUT | IP_REQ_TIMED_OUT => {
+                GError::Timeout(msg)
+            }
+            ERROR_DEVICE_NOT_CONNECTED
+            | ERROR_NO_NETWORK
+            | ERROR_NOT_CONNECTED
+            | WSAENETDOWN
+            | IP_HW_ERROR => GError::AdapterDown(msg),
+            ERROR_TOO_MANY_OPEN_FILES
+            | ERROR_NOT_ENOUGH_MEMORY
+            | ERROR_OUTOFMEMORY
+            | ERROR_NO_SYSTEM_RESOURCES
+            | WSAEMFILE
+            | WSAENOBUFS
+            | IP_NO_RESOURCES => GError::ResourceExhausted(msg),
+            _ => GError::Win32 {
+                code,
+                op: api.to_string(),
+            },
+        }
+    }
+
+    /// Classifies a failed `HRESULT` from `api`, such as the code of a
+    /// `windows::core::Error`.
+    ///
+    /// One wrapping a Win32 error code is classified like
+    /// [`from_win32`](GError::from_win32); any other stays as [`GError::Win32`]
+    /// with the whole `HRESULT` as its code.
+    pub fn from_hresult(hresult: i32, api: &str) -> Self {
+        if (hresult >> 16) & 0x1FFF == FACILITY_WIN32 {
+            Self::from_win32((hresult & 0xFFFF) as u32, api)
+        } else {
+            GError::Win32 {
+                code: hresult as u32,
+                op: api.to_string(),
+            }
+        }
+    }
+
+    /// Classifies an OS error from `api` on platforms without Win32 codes.
+    ///
+    /// Kinds without a specific variant become [`GError::Internal`].
+    pub fn from_io(e: &std::io::Error, api: &str) -> Self {
+        let msg = format!("{} failed: {}", api, e);
+        #[cfg(unix)]
+        if let Some(libc::EMFILE | libc::ENFILE | libc::ENOBUFS) = e.raw_os_error() {
+            return GError::ResourceExhausted(msg);
+        }
+        match e.kind() {
+            std::io::ErrorKind::PermissionDenied => GError::PermissionDenied(msg),
+            std::io::ErrorKind::HostUnreachable | std::io::ErrorKind::NetworkUnreachable => {
+                GError::HostUnreachable(msg)
+            }
+            std::io::ErrorKind::TimedOut => GError::Timeout(msg),
+            std::io::ErrorKind::NetworkDown => GError::AdapterDown(msg),
+            std::io::ErrorKind::OutOfMemory => GError::ResourceExhausted(msg),
+            _ => GError::Internal(msg),
+        }
+    }
+
+    /// Stable identifier of the kind of error, e.g. `"permission_denied"`, for
+    /// frontends and API clients that react to it.
+    pub fn code(&self) -> &'static str {
+        match self {
+            GError::Win32 { .. } => "win32",
+            GError::HostUnreachable(_) => "host_unreachable",
+            GError::Timeout(_) => "timeout",
+            GError::AdapterDown(_) => "adapter_down",
+            GError::PermissionDenied(_) => "permission_denied",
+            GError::ResourceExhausted(_) => "resource_exhausted",
+            GError::Internal(_) => "internal",
+        }
+    }
+
+    /// Whether the error comes from this machine rather than the target, so
+    /// it would fail every host alike and is worth stopping a scan for.
+    pub fn is_local(&self) -> bool {
+        matches!(
+            self,
+            GError::AdapterDown(_) | GError::PermissionDenied(_) | GError::ResourceExhausted(_)
+        )
+    }
+
+    /// Returns a short suggestion for the user, if the error has a likely fix.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            GError::HostUnreachable(_) => Some(
+                "Check that you're on the same subnet as the target; ARP only resolves local hosts.",
+            ),
+            GError::AdapterDown(_) => {
+                Some("Check that the network adapter is enabled and connected.")
+            }
+            GError::PermissionDenied(_) => {
+                Some("Try running as Administrator, or check firewall / security software.")
+            }
+            GError::Timeout(_) => Some(
+                "The target may be slow or filtering probes; try a longer timeout, or check its firewall.",
+            ),
+            GError::ResourceExhausted(_) => Some(
+                "Lower the concurrency setting, or close programs holding many network connections.",
+            ),
+            GError::Win32 { .. } | GError::Internal(_) => None,
+        }
+    }
+}
+
 /// Status of a specific IP scan.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ScanStatus {
     Scanning,
     Online,
     Offline,
     SystemError(GError),
+    /// The scan was cancelled before this target was probed.
+    NotScanned,
 }
 
 impl fmt::Display for ScanStatus {
@@ -42,19 +244,415 @@ impl fmt::Display for ScanStatus {
             ScanStatus::Online => write!(f, "Online"),
             ScanStatus::Offline => write!(f, "Offline"),
             ScanStatus::SystemError(e) => write!(f, "Error: {}", e),
+            ScanStatus::NotScanned => write!(f, "Not scanned"),
+        }
+    }
+}
+
+/// Protocol that supplied a host's name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NameSource {
+    Dns,
+    NetBios,
+    Llmnr,
+    Mdns,
+}
+
+impl fmt::Display for NameSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameSource::Dns => write!(f, "DNS"),
+            NameSource::NetBios => write!(f, "NetBIOS"),
+            NameSource::Llmnr => write!(f, "LLMNR"),
+            NameSource::Mdns => write!(f, "mDNS"),
+        }
+    }
+}
+
+/// User-chosen name for a scan, e.g. "Office 3F audit", with optional notes.
+///
+/// Carried from [`ScanConfig::label`] through [`BridgeMessage::ScanStarted`]
+/// into session files, exports and the scan history.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanLabel {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl ScanLabel {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+        }
+    }
+
+    /// Parses `name` or `name | description`, as typed into a frontend.
+    ///
+    /// Returns `None` if the name is blank.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (name, description) = match text.split_once('|') {
+            Some((name, description)) => (name.trim(), description.trim()),
+            None => (text.trim(), ""),
+        };
+        (!name.is_empty()).then(|| Self {
+            name: name.to_string(),
+            description: (!description.is_empty()).then(|| description.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for ScanLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.description {
+            Some(description) => write!(f, "{} ({})", self.name, description),
+            None => f.write_str(&self.name),
+        }
+    }
+}
+
+/// Full OUI registry record for a MAC prefix, looked up on demand.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VendorDetails {
+    /// The assigned prefix, e.g. `00:1B:C5`.
+    pub oui: String,
+    pub company_name: String,
+    pub company_address: String,
+    pub country_code: String,
+    /// IEEE registry the block comes from: `MA-L`, `MA-M`, `MA-S` or `IAB`.
+    pub registry: String,
+    /// Whether the owner asked for the registration to be kept private.
+    pub is_private: bool,
+    pub date_updated: String,
+}
+
+impl fmt::Display for VendorDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Company: {}", self.company_name)?;
+        if !self.company_address.is_empty() {
+            writeln!(f, "Address: {}", self.company_address)?;
+        }
+        if !self.country_code.is_empty() {
+            writeln!(f, "Country: {}", self.country_code)?;
+        }
+        write!(f, "Block: {} ({})", self.oui, self.registry)?;
+        if self.is_private {
+            write!(f, ", private")?;
         }
+        if !self.date_updated.is_empty() {
+            write!(f, "\nUpdated: {}", self.date_updated)?;
+        }
+        Ok(())
+    }
+}
+
+/// A host's row in the asset inventory; see [`inventory`](crate::inventory).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetInfo {
+    pub asset_tag: Option<String>,
+    pub owner: Option<String>,
+    pub location: Option<String>,
+}
+
+/// Friendly name and notes the user gave a device, e.g. "Living-room AP";
+/// kept by MAC in the [`devices`](crate::devices) book.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceNote {
+    pub name: String,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+impl DeviceNote {
+    /// Parses `name` or `name | notes`, as typed into a frontend.
+    ///
+    /// Returns `None` if the name is blank.
+    pub fn parse(text: &str) -> Option<Self> {
+        ScanLabel::parse(text).map(|label| Self {
+            name: label.name,
+            notes: label.description,
+        })
+    }
+}
+
+/// Formats as `name | notes`, the form [`DeviceNote::parse`] reads back.
+impl fmt::Display for DeviceNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.notes {
+            Some(notes) => write!(f, "{} | {}", self.name, notes),
+            None => f.write_str(&self.name),
+        }
+    }
+}
+
+/// Infrastructure service a host provides to the scanning machine's networks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum NetworkRole {
+    /// Default gateway of a local adapter.
+    Gateway,
+    /// Leased an adapter its address, or answered a DHCPINFORM.
+    DhcpServer,
+    /// DNS server configured on a local adapter.
+    DnsServer,
+}
+
+impl fmt::Display for NetworkRole {
+    /// Short badge text: `Gateway`, `DHCP` or `DNS`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NetworkRole::Gateway => "Gateway",
+            NetworkRole::DhcpServer => "DHCP",
+            NetworkRole::DnsServer => "DNS",
+        })
+    }
+}
+
+/// Names from a UPnP device description.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UpnpInfo {
+    pub friendly_name: Option<String>,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+}
+
+/// How a host's web server answered `GET /`; see [`web::fetch`](crate::web::fetch).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebInfo {
+    pub port: u16,
+    /// HTTP status code, e.g. `200` or `302`.
+    pub status: u16,
+    /// Page `<title>`, entities decoded and whitespace collapsed.
+    pub title: Option<String>,
+    /// `Server` response header.
+    pub server: Option<String>,
+}
+
+impl WebInfo {
+    /// Short text for a table cell: the title, else the server, else the status.
+    pub fn summary(&self) -> String {
+        self.title
+            .clone()
+            .or_else(|| self.server.clone())
+            .unwrap_or_else(|| format!("HTTP {}", self.status))
+    }
+}
+
+impl fmt::Display for WebInfo {
+    /// Formats as `:443 200 "Title" (Server)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, ":{} {}", self.port, self.status)?;
+        if let Some(title) = &self.title {
+            write!(f, " \"{}\"", title)?;
+        }
+        if let Some(server) = &self.server {
+            write!(f, " ({})", server)?;
+        }
+        Ok(())
+    }
+}
+
+/// Many unrelated hosts gave the same answer to `GET /`, as a captive portal
+/// or transparent proxy would; see [`web::InterceptDetector`](crate::web::InterceptDetector).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InterceptionWarning {
+    /// Hosts that gave the identical response.
+    pub hosts: usize,
+    /// The shared response, as the first of those hosts returned it.
+    pub response: WebInfo,
+}
+
+impl fmt::Display for InterceptionWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Possible captive portal or proxy: {} hosts answer HTTP identically ({}); open TCP ports may be unreliable",
+            self.hosts,
+            self.response.summary()
+        )
+    }
+}
+
+/// System group reported by a host's SNMP agent.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnmpInfo {
+    /// `sysName.0`, usually the configured host name.
+    pub name: Option<String>,
+    /// `sysDescr.0`, flattened to one line.
+    pub description: Option<String>,
+    /// `sysUpTime.0`: time since the agent last restarted.
+    pub uptime: Option<Duration>,
+}
+
+/// Operating system family suggested by [`fingerprint::guess_os`](crate::fingerprint::guess_os).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OsFamily {
+    Windows,
+    Linux,
+    MacOs,
+    Nas,
+    NetworkDevice,
+}
+
+impl fmt::Display for OsFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OsFamily::Windows => "Windows",
+            OsFamily::Linux => "Linux/Unix",
+            OsFamily::MacOs => "macOS",
+            OsFamily::Nas => "NAS",
+            OsFamily::NetworkDevice => "Network device",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Heuristic OS guess from the echo TTL, open ports and vendor.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OsGuess {
+    pub family: OsFamily,
+    /// Rough confidence in percent (at most 95: these are only hints).
+    pub confidence: u8,
+    /// The hints that support the guess, e.g. `TTL 128`, `port 3389`.
+    pub evidence: Vec<String>,
+}
+
+impl fmt::Display for OsGuess {
+    /// Formats as `Windows (80%: TTL 128, port 3389)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}%", self.family, self.confidence)?;
+        if !self.evidence.is_empty() {
+            write!(f, ": {}", self.evidence.join(", "))?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Kind of device suggested by [`classify::classify`](crate::classify::classify).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DeviceType {
+    Router,
+    Printer,
+    Nas,
+    Camera,
+    Phone,
+    Pc,
+    MediaPlayer,
+    Iot,
+}
+
+impl DeviceType {
+    /// One-cell symbol for table columns.
+    pub fn glyph(self) -> char {
+        match self {
+            DeviceType::Router => '⇄',
+            DeviceType::Printer => '⎙',
+            DeviceType::Nas => '▤',
+            DeviceType::Camera => '◉',
+            DeviceType::Phone => '☎',
+            DeviceType::Pc => '▣',
+            DeviceType::MediaPlayer => '▶',
+            DeviceType::Iot => '◇',
+        }
+    }
+}
+
+impl fmt::Display for DeviceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DeviceType::Router => "Router",
+            DeviceType::Printer => "Printer",
+            DeviceType::Nas => "NAS",
+            DeviceType::Camera => "Camera",
+            DeviceType::Phone => "Phone",
+            DeviceType::Pc => "PC",
+            DeviceType::MediaPlayer => "Media player",
+            DeviceType::Iot => "IoT",
+        };
+        write!(f, "{}", name)
     }
 }
 
 /// Result of scanning a single IP address.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScanResult {
     pub ip: Ipv4Addr,
     pub hostname: Option<String>,
+    /// Where `hostname` came from; `None` for imported or older results.
+    #[serde(default)]
+    pub hostname_source: Option<NameSource>,
     pub mac: Option<String>,
     pub vendor: Option<String>,
     pub status: ScanStatus,
-    pub open_ports: Vec<u16>,
+    /// Open TCP ports. Older session files store bare port numbers.
+    #[serde(deserialize_with = "deserialize_ports")]
+    pub open_ports: Vec<PortInfo>,
+    /// ICMP round-trip statistics; `None` if the host never answered an echo.
+    #[serde(default)]
+    pub latency: Option<LatencyStats>,
+    /// UDP ports that answered a service probe.
+    #[serde(default)]
+    pub open_udp_ports: Vec<u16>,
+    /// UDP ports that neither answered nor reported ICMP port-unreachable.
+    #[serde(default)]
+    pub filtered_udp_ports: Vec<u16>,
+    /// DNS-SD service types the host announced over mDNS, e.g. `_ipp._tcp`.
+    #[serde(default)]
+    pub services: Vec<String>,
+    /// Device description of a UPnP root device found over SSDP.
+    #[serde(default)]
+    pub upnp: Option<UpnpInfo>,
+    /// System group from the host's SNMP agent.
+    #[serde(default)]
+    pub snmp: Option<SnmpInfo>,
+    /// Operating system guess for online hosts.
+    #[serde(default)]
+    pub os_guess: Option<OsGuess>,
+    /// Kind of device, for online hosts with enough hints.
+    #[serde(default)]
+    pub device_type: Option<DeviceType>,
+    /// Answer to `GET /` on the first open web port.
+    #[serde(default)]
+    pub web_info: Option<WebInfo>,
+    /// What the SMB server on port 445 reported.
+    #[serde(default)]
+    pub smb_info: Option<SmbInfo>,
+    /// Probes it took to reach a verdict, for diagnosing flaky hosts.
+    #[serde(default)]
+    pub attempts: ProbeAttempts,
+    /// TLS audit findings per open TLS port.
+    #[serde(default)]
+    pub tls: Vec<TlsInfo>,
+    /// Security problems found by the opt-in audits.
+    #[serde(default)]
+    pub issues: Vec<SecurityIssue>,
+    /// Probe phases that failed without sinking the host, e.g. an ARP
+    /// request refused while the echo still answered.
+    #[serde(default)]
+    pub warnings: Vec<GError>,
+    /// Values found by custom [`Probe`](crate::probe::Probe)s.
+    #[serde(default)]
+    pub extras: HashMap<String, String>,
+    /// The host's asset inventory row, if an inventory is loaded and lists it.
+    #[serde(default)]
+    pub asset: Option<AssetInfo>,
+    /// The name and notes given to the host's MAC in the device book.
+    #[serde(default)]
+    pub device_note: Option<DeviceNote>,
+    /// Infrastructure roles of the host, in [`NetworkRole`] order.
+    #[serde(default)]
+    pub roles: Vec<NetworkRole>,
+    /// When the host was first found online; frontends keep the earliest
+    /// across rescans and monitor sweeps.
+    #[serde(default)]
+    pub first_seen: Option<SystemTime>,
+    /// When the host was last found online.
+    #[serde(default)]
+    pub last_seen: Option<SystemTime>,
+    /// Time spent probing the host, from its first packet to its result.
+    #[serde(default)]
+    pub scan_duration: Option<Duration>,
 }
 
 impl ScanResult {
@@ -62,29 +660,1470 @@ impl ScanResult {
         Self {
             ip,
             hostname: None,
+            hostname_source: None,
             mac: None,
             vendor: None,
             status: ScanStatus::Scanning,
             open_ports: Vec::new(),
+            latency: None,
+            open_udp_ports: Vec::new(),
+            filtered_udp_ports: Vec::new(),
+            services: Vec::new(),
+            upnp: None,
+            snmp: None,
+            os_guess: None,
+            device_type: None,
+            web_info: None,
+            smb_info: None,
+            attempts: ProbeAttempts::default(),
+            tls: Vec::new(),
+            issues: Vec::new(),
+            warnings: Vec::new(),
+            extras: HashMap::new(),
+            asset: None,
+            device_note: None,
+            roles: Vec::new(),
+            first_seen: None,
+            last_seen: None,
+            scan_duration: None,
+        }
+    }
+
+    /// Records how long the host took and, if it is online, that it was
+    /// seen at `now`.
+    pub fn stamp(&mut self, duration: Duration, now: SystemTime) {
+        self.scan_duration = Some(duration);
+        if self.status == ScanStatus::Online {
+            self.first_seen = Some(now);
+            self.last_seen = Some(now);
+        }
+    }
+
+    /// Carries the sightings of an earlier result for the same host over to
+    /// this newer one: the earliest `first_seen`, and `last_seen` if this
+    /// scan did not find it online.
+    pub fn merge_seen(&mut self, previous: &ScanResult) {
+        self.first_seen = match (previous.first_seen, self.first_seen) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.last_seen = self.last_seen.or(previous.last_seen);
+    }
+}
+
+/// An open TCP port with its service label and, when grabbed, its banner.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortInfo {
+    pub port: u16,
+    pub service: String,
+    /// First line the service sent (or its HTTP `Server:` header).
+    #[serde(default)]
+    pub banner: Option<String>,
+}
+
+impl PortInfo {
+    /// Creates an entry labelled by [`port_label`], without a banner.
+    pub fn new(port: u16) -> Self {
+        Self {
+            port,
+            service: port_label(port),
+            banner: None,
+        }
+    }
+
+    /// The service label to display: a user-defined label if one is set now,
+    /// else the label stored when the port was found.
+    pub fn label(&self) -> String {
+        port_label_override(self.port).unwrap_or_else(|| self.service.clone())
+    }
+
+    /// Formats as `port (service)` or `port (service: banner)`.
+    pub fn describe(&self) -> String {
+        match &self.banner {
+            Some(banner) => format!("{} ({}: {})", self.port, self.label(), banner),
+            None => format!("{} ({})", self.port, self.label()),
+        }
+    }
+}
+
+/// Accepts both `[22, 80]` and `[{"port": 22, ..}]` for [`ScanResult::open_ports`].
+fn deserialize_ports<'de, D>(deserializer: D) -> Result<Vec<PortInfo>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Entry {
+        Bare(u16),
+        Info(PortInfo),
+    }
+    let entries = Vec::<Entry>::deserialize(deserializer)?;
+    Ok(entries
+        .into_iter()
+        .map(|e| match e {
+            Entry::Bare(port) => PortInfo::new(port),
+            Entry::Info(info) => info,
+        })
+        .collect())
+}
+
+/// A security problem found on a host by one of the opt-in audits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SecurityIssue {
+    /// The SMB server still negotiates SMBv1; see [`smb::probe_smb1`](crate::smb::probe_smb1).
+    Smb1Enabled,
+    /// The SMB server speaks nothing newer than SMBv1.
+    Smb1Only,
+    /// A TLS service accepts a protocol older than TLS 1.2.
+    LegacyTls { port: u16, version: TlsVersion },
+    /// A TLS service presents an expired certificate.
+    CertificateExpired { port: u16 },
+    /// A TLS service's certificate expires within
+    /// [`CERT_EXPIRY_WARNING`](crate::tls::CERT_EXPIRY_WARNING).
+    CertificateExpiring { port: u16, days: u64 },
+    /// The host's reverse DNS name does not resolve back to it; `addresses`
+    /// are where it leads instead, if anywhere.
+    DnsMismatch {
+        name: String,
+        addresses: Vec<Ipv4Addr>,
+    },
+}
+
+impl fmt::Display for SecurityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecurityIssue::Smb1Enabled => write!(f, "SMBv1 enabled"),
+            SecurityIssue::Smb1Only => write!(f, "SMBv1 only"),
+            SecurityIssue::LegacyTls { port, version } => {
+                write!(f, "{} accepted on port {}", version, port)
+            }
+            SecurityIssue::CertificateExpired { port } => {
+                write!(f, "expired certificate on port {}", port)
+            }
+            SecurityIssue::CertificateExpiring { port, days } => {
+                write!(f, "certificate on port {} expires in {} days", port, days)
+            }
+            SecurityIssue::DnsMismatch { name, addresses } if addresses.is_empty() => {
+                write!(f, "reverse DNS name {} does not resolve", name)
+            }
+            SecurityIssue::DnsMismatch { name, addresses } => {
+                let addresses: Vec<String> = addresses.iter().map(ToString::to_string).collect();
+                write!(
+                    f,
+                    "reverse DNS name {} resolves to {}",
+                    name,
+                    addresses.join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// Oldest TLS protocol version a service accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum TlsVersion {
+    Tls10,
+    Tls11,
+    /// TLS 1.2 or newer; the audit does not tell 1.2 from 1.3.
+    Tls12,
+}
+
+impl fmt::Display for TlsVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TlsVersion::Tls10 => write!(f, "TLS 1.0"),
+            TlsVersion::Tls11 => write!(f, "TLS 1.1"),
+            TlsVersion::Tls12 => write!(f, "TLS 1.2"),
+        }
+    }
+}
+
+/// What the TLS audit found on one port; see [`tls::audit`](crate::tls::audit).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TlsInfo {
+    pub port: u16,
+    /// Oldest protocol version the service completed a handshake with.
+    pub min_version: TlsVersion,
+    /// Certificate `notAfter` in Unix seconds; `None` without a readable certificate.
+    pub cert_not_after: Option<u64>,
+}
+
+/// What an SMB server says about itself; see [`smb::host_info`](crate::smb::host_info).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SmbInfo {
+    /// Dialect the server picked, e.g. `3.1.1` or `2.1`; `1` for SMBv1.
+    pub dialect: String,
+    /// NetBIOS computer name.
+    pub hostname: Option<String>,
+    /// NetBIOS domain, or the workgroup of a standalone machine.
+    pub domain: Option<String>,
+    /// Windows version from the NTLM challenge, e.g. `10.0.19041`.
+    pub os_version: Option<String>,
+}
+
+impl SmbInfo {
+    /// Whether the server only speaks SMBv1.
+    pub fn smb1_only(&self) -> bool {
+        self.dialect == crate::smb::SMB1_DIALECT
+    }
+}
+
+impl fmt::Display for SmbInfo {
+    /// Formats as `SMB 3.1.1, CORP\FILESRV, Windows 10.0.19041`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SMB {}", self.dialect)?;
+        match (&self.domain, &self.hostname) {
+            (Some(domain), Some(host)) => write!(f, ", {}\\{}", domain, host)?,
+            (None, Some(host)) => write!(f, ", {}", host)?,
+            (Some(domain), None) => write!(f, ", {}", domain)?,
+            (None, None) => {}
         }
+        if let Some(version) = &self.os_version {
+            write!(f, ", Windows {}", version)?;
+        }
+        Ok(())
+    }
+}
+
+/// Probes sent to one host before it answered or was given up on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProbeAttempts {
+    /// Echoes sent up to and including the first reply, or all of them if
+    /// none came back.
+    pub ping: u32,
+    /// ARP requests sent; `0` if the MAC came from the ARP cache.
+    pub arp: u32,
+}
+
+impl fmt::Display for ProbeAttempts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ping {}, ARP {}", self.ping, self.arp)
+    }
+}
+
+/// One hop of a traceroute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceHop {
+    /// Time-to-live the probe was sent with: 1 for the first router.
+    pub ttl: u8,
+    /// Router that reported the TTL expired, or the target itself; `None`
+    /// if nothing answered in time.
+    pub addr: Option<Ipv4Addr>,
+    pub rtt_ms: Option<u32>,
+}
+
+impl fmt::Display for TraceHop {
+    /// Formats as `3  10.0.0.1  4 ms`, or `3  *` for a silent hop.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.addr, self.rtt_ms) {
+            (Some(addr), Some(rtt)) => write!(f, "{:>2}  {}  {} ms", self.ttl, addr, rtt),
+            (Some(addr), None) => write!(f, "{:>2}  {}", self.ttl, addr),
+            (None, _) => write!(f, "{:>2}  *", self.ttl),
+        }
+    }
+}
+
+/// Round-trip time statistics over the ICMP echoes sent to one host.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub min_ms: f32,
+    pub avg_ms: f32,
+    pub max_ms: f32,
+    /// Echo requests sent.
+    pub sent: u32,
+    /// Echo replies received.
+    pub received: u32,
+}
+
+impl LatencyStats {
+    /// Builds statistics from the RTT samples of the replies received out of `sent` requests.
+    ///
+    /// Returns `None` if no replies were received.
+    pub fn from_samples(samples_ms: &[u32], sent: u32) -> Option<Self> {
+        let min = *samples_ms.iter().min()?;
+        let max = *samples_ms.iter().max()?;
+        let sum: u64 = samples_ms.iter().map(|&s| s as u64).sum();
+        Some(Self {
+            min_ms: min as f32,
+            avg_ms: sum as f32 / samples_ms.len() as f32,
+            max_ms: max as f32,
+            sent,
+            received: samples_ms.len() as u32,
+        })
+    }
+
+    /// Formats as `min/avg/max ms`, e.g. `1/1.5/2 ms`.
+    pub fn summary(&self) -> String {
+        format!("{}/{:.1}/{} ms", self.min_ms, self.avg_ms, self.max_ms)
+    }
+}
+
+impl ScanResult {
+    /// Average round-trip time in milliseconds, if the host answered ICMP.
+    pub fn latency_ms(&self) -> Option<f32> {
+        self.latency.map(|l| l.avg_ms)
+    }
+}
+
+/// Outcome of probing a single UDP port.
+///
+/// UDP is connectionless, so silence cannot distinguish an open port from a
+/// firewall dropping the datagram; those cases are reported as [`UdpPortState::OpenFiltered`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UdpPortState {
+    /// The service replied to the probe payload.
+    Open,
+    /// No reply and no ICMP port-unreachable within the timeout.
+    OpenFiltered,
+    /// An ICMP port-unreachable was received.
+    Closed,
+}
+
+/// Default number of hosts probed at once.
+pub const DEFAULT_CONCURRENCY: usize = 1000;
+/// TCP connections (port probes, banner grabs, service probes) a scan keeps
+/// open at once across all its hosts, so a high `concurrency` cannot run the
+/// machine out of ephemeral ports or socket handles.
+pub const MAX_SCAN_CONNECTIONS: usize = 512;
+/// Default number of TCP ports probed at once on each host.
+pub const DEFAULT_PORT_CONCURRENCY: usize = 8;
+/// Default time to wait for an ICMP echo reply.
+pub const DEFAULT_PING_TIMEOUT: Duration = Duration::from_millis(1000);
+/// Default pause before a ping or ARP retry.
+pub const DEFAULT_RETRY_DELAY: Duration = Duration::from_millis(100);
+/// Default time to wait for a DNS server to answer.
+pub const DEFAULT_DNS_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Tuning for a single scan, sent with [`BridgeMessage::StartScanWithConfig`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanConfig {
+    /// Hosts probed at once (minimum 1).
+    pub concurrency: usize,
+    /// TCP ports probed at once on each host (minimum 1).
+    #[serde(default = "default_port_concurrency")]
+    pub port_concurrency: usize,
+    /// TCP connections open at once to any one host, counting port probes,
+    /// banner grabs and service probes of this and every other scan with
+    /// the same cap; see [`HostLimitProvider`](crate::net::HostLimitProvider).
+    /// `None` leaves only [`port_concurrency`](Self::port_concurrency).
+    #[serde(default)]
+    pub max_host_connections: Option<usize>,
+    /// Time to wait for each ICMP echo reply.
+    pub ping_timeout: Duration,
+    /// TCP connect timeout; `None` derives it from each host's ping latency.
+    pub port_timeout: Option<Duration>,
+    /// Extra echoes sent to a host that misses the first one, and extra ARP
+    /// requests if it never answers, before it is reported offline.
+    pub retries: u32,
+    /// Pause before each retry, jittered by ±50% so retries to many hosts
+    /// do not go out in lockstep.
+    #[serde(default = "default_retry_delay")]
+    pub retry_delay: Duration,
+    /// TCP ports probed on online hosts.
+    pub ports: Vec<u16>,
+    /// Look up reverse DNS names.
+    pub resolve_hostnames: bool,
+    /// Servers and timeout of those lookups.
+    #[serde(default)]
+    pub dns: DnsConfig,
+    /// Look reverse DNS names up again and report a
+    /// [`SecurityIssue::DnsMismatch`] for those that do not lead back to
+    /// their host.
+    #[serde(default)]
+    pub verify_dns: bool,
+    /// Send TCP port probes and banner grabs through this SOCKS5 proxy, to
+    /// reach segments behind a bastion. Echoes, ARP and UDP cannot cross
+    /// it, so every target is assumed up and counts as online once a port
+    /// answers.
+    #[serde(default)]
+    pub proxy: Option<Socks5Config>,
+    /// Look up OUI vendors for resolved MACs.
+    pub resolve_vendors: bool,
+    /// Browse mDNS/DNS-SD before probing; hosts that answer count as online
+    /// and get their announced services.
+    #[serde(default)]
+    pub mdns_discovery: bool,
+    /// Search for UPnP devices over SSDP before probing; devices that answer
+    /// count as online and get their description.
+    #[serde(default)]
+    pub ssdp_discovery: bool,
+    /// Broadcast a DHCPINFORM before probing to find DHCP servers the
+    /// adapters do not report, rogue ones included. Gateways, DNS servers
+    /// and the adapters' own DHCP servers are tagged either way.
+    #[serde(default)]
+    pub dhcp_discovery: bool,
+    /// Query the SNMP system group of online hosts with this community;
+    /// `None` disables the probe.
+    #[serde(default)]
+    pub snmp_community: Option<String>,
+    /// Name and notes for this scan.
+    #[serde(default)]
+    pub label: Option<ScanLabel>,
+    /// Packets per second across the whole scan (echoes and port probes);
+    /// `None` sends as fast as `concurrency` allows.
+    #[serde(default)]
+    pub max_rate: Option<u32>,
+    /// Back off from `max_rate` when echo timeouts spike and recover once
+    /// they settle. Has no effect without `max_rate`.
+    #[serde(default)]
+    pub adaptive_rate: bool,
+    /// Most probe packets (see [`ProbeKind`]) the scan may send. Once used
+    /// up, no further probe goes out, no new host is dispatched and the scan
+    /// ends as cancelled with [`ScanSummary::budget_exhausted`] set.
+    #[serde(default)]
+    pub probe_budget: Option<u64>,
+    /// Cap on the estimated probe bandwidth in kilobits per second, paced
+    /// like `max_rate` (ARP requests are counted but not paced); `None` is
+    /// uncapped.
+    #[serde(default)]
+    pub max_bandwidth_kbps: Option<u32>,
+    /// Socket options for TCP port probes.
+    #[serde(default)]
+    pub tcp_options: TcpProbeOptions,
+    /// Check hosts with port 445 open for SMBv1 and report
+    /// [`SecurityIssue::Smb1Enabled`].
+    #[serde(default)]
+    pub smb1_audit: bool,
+    /// Audit open TLS ports for old protocol versions and expiring
+    /// certificates; see [`tls::audit`](crate::tls::audit).
+    #[serde(default)]
+    pub tls_audit: bool,
+    /// Send targets nothing but ICMP echoes (and ARP requests): no TCP
+    /// port probes or UDP probes, whatever `ports` and
+    /// [`BridgeMessage::SetUdpScan`] say.
+    #[serde(default)]
+    pub icmp_only: bool,
+    /// Order hosts, and each host's ports, are probed in.
+    #[serde(default)]
+    pub order: ScanOrder,
+    /// Seed of [`ScanOrder::Random`], to repeat a scan's order; `None` picks
+    /// one per scan and logs it.
+    #[serde(default)]
+    pub order_seed: Option<u64>,
+    /// Wait a random time up to this long before each echo and port probe,
+    /// so probes do not go out at a fixed cadence.
+    #[serde(default)]
+    pub probe_jitter: Option<Duration>,
+}
+
+/// Order a scan walks its hosts and ports in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanOrder {
+    /// Ascending addresses, ports as configured.
+    #[default]
+    Sequential,
+    /// Hosts and each host's ports shuffled, which keeps a sweep from
+    /// walking a subnet in the obvious order intrusion detection looks for.
+    Random,
+}
+
+impl std::str::FromStr for ScanOrder {
+    type Err = String;
+
+    /// Parses `sequential` or `random`, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "sequential" => Ok(ScanOrder::Sequential),
+            "random" => Ok(ScanOrder::Random),
+            _ => Err(format!(
+                "Unknown scan order '{}' (expected sequential or random)",
+                s.trim()
+            )),
+        }
+    }
+}
+
+/// Where reverse DNS lookups go, see [`ScanConfig::dns`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsConfig {
+    /// Servers asked on port 53, in order; empty uses the system's.
+    #[serde(default)]
+    pub servers: Vec<IpAddr>,
+    /// Time to wait for each answer before giving up on a name.
+    #[serde(default = "default_dns_timeout")]
+    pub timeout: Duration,
+}
+
+impl Default for DnsConfig {
+    fn default() -> Self {
+        Self {
+            servers: Vec::new(),
+            timeout: DEFAULT_DNS_TIMEOUT,
+        }
+    }
+}
+
+fn default_dns_timeout() -> Duration {
+    DEFAULT_DNS_TIMEOUT
+}
+
+/// A SOCKS5 proxy TCP port probes go through, see [`ScanConfig::proxy`].
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Socks5Config {
+    /// `host:port` of the proxy, e.g. an `ssh -D 1080 bastion` tunnel.
+    pub addr: String,
+    /// User name and password, if the proxy asks for them (RFC 1929).
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+impl fmt::Debug for Socks5Config {
+    /// Leaves the password out, as configs end up in logs.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Socks5Config")
+            .field("addr", &self.addr)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "***"))
+            .finish()
+    }
+}
+
+impl std::str::FromStr for Socks5Config {
+    type Err = String;
+
+    /// Parses `[socks5://][user[:password]@]host:port`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let rest = s.strip_prefix("socks5://").unwrap_or(s);
+        let (credentials, addr) = match rest.rsplit_once('@') {
+            Some((credentials, addr)) => (Some(credentials), addr),
+            None => (None, rest),
+        };
+        match addr.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {}
+            _ => {
+                return Err(format!(
+                    "Invalid proxy address '{}' (expected host:port)",
+                    addr
+                ));
+            }
+        }
+        let (username, password) = match credentials {
+            Some(credentials) => match credentials.split_once(':') {
+                Some((user, password)) => (Some(user.to_string()), Some(password.to_string())),
+                None => (Some(credentials.to_string()), None),
+            },
+            None => (None, None),
+        };
+        Ok(Self {
+            addr: addr.to_string(),
+            username,
+            password,
+        })
+    }
+}
+
+/// Socket options for TCP port probes, for probes that must pass picky
+/// middleboxes or be easy to pick out in firewall logs.
+///
+/// The default leaves every option to the operating system.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TcpProbeOptions {
+    /// Local port every probe is sent from. `SO_REUSEADDR` is set so
+    /// concurrent probes can share it, but reconnecting to the same
+    /// host and port soon after can fail while the old connection lingers
+    /// in `TIME_WAIT`.
+    #[serde(default)]
+    pub source_port: Option<u16>,
+    /// IP time-to-live of probe packets. Too small a value makes remote
+    /// ports look closed.
+    #[serde(default)]
+    pub ttl: Option<u8>,
+    /// Turn `SO_KEEPALIVE` off explicitly.
+    #[serde(default)]
+    pub disable_keepalive: bool,
+}
+
+impl TcpProbeOptions {
+    /// Returns `true` if no option differs from the operating system's defaults.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
     }
 }
 
+fn default_port_concurrency() -> usize {
+    DEFAULT_PORT_CONCURRENCY
+}
+
+fn default_retry_delay() -> Duration {
+    DEFAULT_RETRY_DELAY
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_CONCURRENCY,
+            port_concurrency: DEFAULT_PORT_CONCURRENCY,
+            max_host_connections: None,
+            ping_timeout: DEFAULT_PING_TIMEOUT,
+            port_timeout: None,
+            retries: 0,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            ports: COMMON_PORTS.iter().map(|(p, _)| *p).collect(),
+            resolve_hostnames: true,
+            dns: DnsConfig::default(),
+            verify_dns: false,
+            proxy: None,
+            resolve_vendors: true,
+            mdns_discovery: false,
+            ssdp_discovery: false,
+            dhcp_discovery: false,
+            snmp_community: None,
+            label: None,
+            max_rate: None,
+            adaptive_rate: false,
+            probe_budget: None,
+            max_bandwidth_kbps: None,
+            tcp_options: TcpProbeOptions::default(),
+            smb1_audit: false,
+            tls_audit: false,
+            icmp_only: false,
+            order: ScanOrder::Sequential,
+            order_seed: None,
+            probe_jitter: None,
+        }
+    }
+}
+
+impl ScanConfig {
+    /// A gentle scan for fragile OT/ICS segments, where PLCs and other
+    /// embedded stacks can hang on a burst of probes: one host at a time,
+    /// ICMP echoes only at no more than [`SAFE_MODE_MAX_RATE`] packets per
+    /// second, long timeouts,
+    /// and no discovery, SNMP, audits or host name lookups, which would send
+    /// NetBIOS queries to the targets.
+    pub fn safe_mode() -> Self {
+        Self {
+            concurrency: 1,
+            port_concurrency: 1,
+            ping_timeout: Duration::from_secs(3),
+            port_timeout: Some(Duration::from_secs(5)),
+            retries: 1,
+            retry_delay: Duration::from_secs(1),
+            ports: Vec::new(),
+            resolve_hostnames: false,
+            max_rate: Some(SAFE_MODE_MAX_RATE),
+            icmp_only: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// Packet rate limit of [`ScanConfig::safe_mode`].
+pub const SAFE_MODE_MAX_RATE: u32 = 5;
+
+/// Named starting points for a [`ScanConfig`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanProfile {
+    /// [`ScanConfig::default`].
+    #[default]
+    Standard,
+    /// [`ScanConfig::safe_mode`].
+    Safe,
+}
+
+impl ScanProfile {
+    pub fn config(self) -> ScanConfig {
+        match self {
+            ScanProfile::Standard => ScanConfig::default(),
+            ScanProfile::Safe => ScanConfig::safe_mode(),
+        }
+    }
+
+    /// The profile's config with any subset of the [`ScanConfig`] fields,
+    /// given as JSON, overlaid on it.
+    pub fn config_with(
+        self,
+        overrides: Option<serde_json::Map<String, serde_json::Value>>,
+    ) -> Result<ScanConfig, serde_json::Error> {
+        let Some(overrides) = overrides else {
+            return Ok(self.config());
+        };
+        let mut config = serde_json::to_value(self.config())?;
+        if let Some(fields) = config.as_object_mut() {
+            fields.extend(overrides);
+        }
+        serde_json::from_value(config)
+    }
+}
+
+impl std::str::FromStr for ScanProfile {
+    type Err = String;
+
+    /// Parses `standard` or `safe`, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "standard" => Ok(ScanProfile::Standard),
+            "safe" => Ok(ScanProfile::Safe),
+            _ => Err(format!(
+                "Unknown scan profile '{}' (expected standard or safe)",
+                s.trim()
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ScanProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ScanProfile::Standard => "standard",
+            ScanProfile::Safe => "safe",
+        })
+    }
+}
+
+/// Throughput of a running scan, sent about once a second as
+/// [`BridgeMessage::ScanStats`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScanStats {
+    /// Packets sent per second since the previous report.
+    pub packets_per_second: f64,
+    /// Current packet rate limit, lowered by adaptive backoff; `None` if unlimited.
+    pub rate_limit: Option<u32>,
+    /// Hosts finished so far.
+    pub completed: u32,
+    /// Hosts in the scan.
+    pub total: u32,
+    /// Estimated time until the scan finishes, from the average host rate so far.
+    pub eta: Option<Duration>,
+}
+
+/// Kinds of probe packets a scan sends to its targets, as counted in
+/// [`ProbeTraffic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProbeKind {
+    /// ICMP echo requests.
+    Icmp,
+    /// ARP requests for hosts missing from the ARP cache.
+    Arp,
+    /// TCP connects (their SYN) to probed ports.
+    Tcp,
+    /// UDP port probes.
+    Udp,
+}
+
+/// Packets and their estimated size in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PacketCount {
+    pub packets: u64,
+    pub bytes: u64,
+}
+
+impl fmt::Display for PacketCount {
+    /// Formats as `1530 pkt, 91.8 KB`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} pkt, {}", self.packets, format_bytes(self.bytes))
+    }
+}
+
+/// `bytes` in B, KB or MB (powers of 1000), e.g. `91.8 KB`.
+pub fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        0..1_000 => format!("{} B", bytes),
+        1_000..1_000_000 => format!("{:.1} KB", bytes as f64 / 1e3),
+        _ => format!("{:.1} MB", bytes as f64 / 1e6),
+    }
+}
+
+/// Probe packets a scan sent, by [`ProbeKind`].
+///
+/// Bytes are estimated at the IP layer (ARP: the ARP payload) from each
+/// probe's headers and payload; retransmissions, the rest of a TCP
+/// handshake, replies and the service probes run on open ports are not
+/// counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProbeTraffic {
+    pub icmp: PacketCount,
+    pub arp: PacketCount,
+    pub tcp: PacketCount,
+    pub udp: PacketCount,
+}
+
+impl ProbeTraffic {
+    pub fn get(&self, kind: ProbeKind) -> PacketCount {
+        match kind {
+            ProbeKind::Icmp => self.icmp,
+            ProbeKind::Arp => self.arp,
+            ProbeKind::Tcp => self.tcp,
+            ProbeKind::Udp => self.udp,
+        }
+    }
+
+    /// Counts one packet of `kind` and `bytes`.
+    pub fn record(&mut self, kind: ProbeKind, bytes: u64) {
+        let count = match kind {
+            ProbeKind::Icmp => &mut self.icmp,
+            ProbeKind::Arp => &mut self.arp,
+            ProbeKind::Tcp => &mut self.tcp,
+            ProbeKind::Udp => &mut self.udp,
+        };
+        count.packets += 1;
+        count.bytes += bytes;
+    }
+
+    /// All kinds together.
+    pub fn total(&self) -> PacketCount {
+        [self.icmp, self.arp, self.tcp, self.udp].into_iter().fold(
+            PacketCount::default(),
+            |sum, count| PacketCount {
+                packets: sum.packets + count.packets,
+                bytes: sum.bytes + count.bytes,
+            },
+        )
+    }
+}
+
+impl fmt::Display for ProbeTraffic {
+    /// Formats the kinds that sent anything, e.g.
+    /// `ICMP 254 pkt, 9.7 KB; TCP 1276 pkt, 76.6 KB`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kinds = [
+            ("ICMP", self.icmp),
+            ("ARP", self.arp),
+            ("TCP", self.tcp),
+            ("UDP", self.udp),
+        ];
+        let mut sent = kinds.iter().filter(|(_, count)| count.packets > 0);
+        match sent.next() {
+            None => write!(f, "nothing sent"),
+            Some((name, count)) => {
+                write!(f, "{} {}", name, count)?;
+                for (name, count) in sent {
+                    write!(f, "; {} {}", name, count)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Totals of a finished scan, sent as [`BridgeMessage::ScanSummary`] just
+/// before [`BridgeMessage::ScanComplete`] or [`BridgeMessage::ScanCancelled`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanSummary {
+    /// From the first host dispatched to the last one finished.
+    pub duration: Duration,
+    pub hosts_up: u32,
+    pub hosts_down: u32,
+    /// Hosts whose probe failed with [`ScanStatus::SystemError`].
+    pub errors: u32,
+    /// ICMP echoes, ARP requests, TCP connects and UDP probes sent.
+    pub probes_sent: u64,
+    /// The probes sent, by kind, with their estimated bytes.
+    #[serde(default)]
+    pub traffic: ProbeTraffic,
+    /// The scan stopped early because [`ScanConfig::probe_budget`] was used up.
+    #[serde(default)]
+    pub budget_exhausted: bool,
+}
+
+impl fmt::Display for ScanSummary {
+    /// Formats as `12 up, 240 down, 1 error, 1530 probes (91.8 KB) in 42.0 s`,
+    /// followed by `, probe budget used up` if it was.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} up, {} down", self.hosts_up, self.hosts_down)?;
+        if self.errors > 0 {
+            let plural = if self.errors == 1 { "" } else { "s" };
+            write!(f, ", {} error{}", self.errors, plural)?;
+        }
+        write!(f, ", {} probes", self.probes_sent)?;
+        let bytes = self.traffic.total().bytes;
+        if bytes > 0 {
+            write!(f, " ({})", format_bytes(bytes))?;
+        }
+        write!(f, " in {:.1} s", self.duration.as_secs_f64())?;
+        if self.budget_exhausted {
+            write!(f, ", probe budget used up")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ScanStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.0} pkt/s", self.packets_per_second)?;
+        if let Some(limit) = self.rate_limit {
+            write!(f, " (limit {})", limit)?;
+        }
+        if let Some(eta) = self.eta {
+            let secs = eta.as_secs();
+            write!(f, ", ETA {}:{:02}", secs / 60, secs % 60)?;
+        }
+        Ok(())
+    }
+}
+
+/// Share of a scan's overall progress given to discovery when it also
+/// probes ports; the port phase has the rest.
+const DISCOVERY_WEIGHT: f64 = 0.5;
+
+/// Progress of a scan's two phases, sent as [`BridgeMessage::Phase`]
+/// whenever either percentage changes.
+///
+/// Discovery counts hosts whose ping and ARP lookups finished. The port
+/// phase counts TCP and UDP probes of the hosts found online so far, so its
+/// total grows while discovery runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseProgress {
+    pub hosts_discovered: u32,
+    pub hosts_total: u32,
+    pub ports_probed: u32,
+    /// Probes owed by the online hosts so far; `None` when the scan probes
+    /// no ports (ICMP only, or an empty port list).
+    pub ports_total: Option<u32>,
+}
+
+impl PhaseProgress {
+    pub fn discovery_complete(&self) -> bool {
+        self.hosts_discovered >= self.hosts_total
+    }
+
+    pub fn discovery_percent(&self) -> u8 {
+        percent(self.hosts_discovered, self.hosts_total)
+    }
+
+    /// `None` without a port phase. Nothing to probe counts as 0% until
+    /// discovery is done, since a host found later may still need probes.
+    pub fn ports_percent(&self) -> Option<u8> {
+        let total = self.ports_total?;
+        Some(match total {
+            0 if !self.discovery_complete() => 0,
+            _ => percent(self.ports_probed, total),
+        })
+    }
+
+    /// Both phases weighted together. The port phase only counts for the
+    /// share of hosts discovered so far, so the bar does not race ahead on
+    /// the first few online hosts.
+    pub fn overall_percent(&self) -> u8 {
+        let discovery = f64::from(self.discovery_percent()) / 100.0;
+        let overall = match self.ports_percent() {
+            None => discovery,
+            Some(ports) => {
+                let ports = f64::from(ports) / 100.0;
+                DISCOVERY_WEIGHT * discovery + (1.0 - DISCOVERY_WEIGHT) * discovery * ports
+            }
+        };
+        (overall * 100.0).round() as u8
+    }
+}
+
+impl fmt::Display for PhaseProgress {
+    /// Formats as `Discovery 80% · Ports 35%`, or `Discovery 80%` without a
+    /// port phase.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Discovery {}%", self.discovery_percent())?;
+        if let Some(ports) = self.ports_percent() {
+            write!(f, " · Ports {}%", ports)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where one host of a running scan stands, in a [`StateSnapshot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HostPhase {
+    /// Being pinged and ARPed, with its names and vendor looked up.
+    Discovery,
+    /// Online, with its ports being probed.
+    Ports,
+    /// Reported online.
+    Online,
+    /// Reported offline.
+    Offline,
+    /// Reported with a [`ScanStatus::SystemError`].
+    Error,
+}
+
+impl HostPhase {
+    /// The phase of a host whose result was reported with `status`; `None`
+    /// for statuses that are no final answer.
+    pub fn from_status(status: &ScanStatus) -> Option<Self> {
+        match status {
+            ScanStatus::Online => Some(HostPhase::Online),
+            ScanStatus::Offline => Some(HostPhase::Offline),
+            ScanStatus::SystemError(_) => Some(HostPhase::Error),
+            ScanStatus::Scanning | ScanStatus::NotScanned => None,
+        }
+    }
+}
+
+/// The phase of every host of a running scan, sent as
+/// [`BridgeMessage::StateSnapshot`] when asked for with
+/// [`BridgeMessage::RequestStateSnapshot`], so an embedding UI can draw a
+/// per-host grid without replaying the update stream.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    /// Every host dispatched so far.
+    pub hosts: BTreeMap<Ipv4Addr, HostPhase>,
+    /// Targets not dispatched yet, which `hosts` leaves out.
+    pub pending: u32,
+}
+
+/// `done` of `total` as a whole percentage; an empty total is complete.
+fn percent(done: u32, total: u32) -> u8 {
+    if total == 0 {
+        return 100;
+    }
+    (u64::from(done.min(total)) * 100 / u64::from(total)) as u8
+}
+
+/// Progress of one range of a multi-range scan, sent as
+/// [`BridgeMessage::RangeProgress`] by [`Scanner::scan_multi`](crate::scanner::Scanner::scan_multi).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RangeProgress {
+    /// Position of the range in [`ScanTargets::ranges`].
+    pub index: usize,
+    pub start: Ipv4Addr,
+    pub end: Ipv4Addr,
+    /// Hosts of the range finished so far.
+    pub completed: u32,
+    /// Hosts of the range in the scan, subnet edges excluded.
+    pub total: u32,
+}
+
+impl RangeProgress {
+    pub fn percent(&self) -> u8 {
+        if self.total == 0 {
+            return 100;
+        }
+        (u64::from(self.completed) * 100 / u64::from(self.total)) as u8
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.completed >= self.total
+    }
+
+    /// The range as `10.0.1.0-10.0.1.255`, or a single address.
+    pub fn range(&self) -> String {
+        if self.start == self.end {
+            self.start.to_string()
+        } else {
+            format!("{}-{}", self.start, self.end)
+        }
+    }
+}
+
+impl fmt::Display for RangeProgress {
+    /// Formats as `10.0.5.1-10.0.5.50: 12/50 (24%)` or `…: done`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_complete() {
+            write!(f, "{}: done", self.range())
+        } else {
+            write!(
+                f,
+                "{}: {}/{} ({}%)",
+                self.range(),
+                self.completed,
+                self.total,
+                self.percent()
+            )
+        }
+    }
+}
+
+/// Whether a running scan is dispatching hosts; see [`BridgeMessage::ScanState`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ScanRunState {
+    Running,
+    Paused,
+}
+
+/// Caller-chosen identifier of a scan started with [`BridgeMessage::StartTaggedScan`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScanId(pub u64);
+
+impl fmt::Display for ScanId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// What a [`BridgeMessage::SecurityAlert`] saw change about a host's identity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum IdentityChange {
+    /// The address now answers with another MAC: ARP spoofing or a swapped device.
+    MacChanged { previous: String },
+    /// The host's MAC was last seen at another address.
+    MacMoved { previous_ip: Ipv4Addr },
+    /// The host reports another name than last time.
+    HostnameChanged { previous: String },
+}
+
+impl fmt::Display for IdentityChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdentityChange::MacChanged { previous } => write!(
+                f,
+                "MAC changed from {} (possible ARP spoofing or device swap)",
+                previous
+            ),
+            IdentityChange::MacMoved { previous_ip } => {
+                write!(f, "MAC moved here from {}", previous_ip)
+            }
+            IdentityChange::HostnameChanged { previous } => {
+                write!(f, "hostname changed from {}", previous)
+            }
+        }
+    }
+}
+
+/// How the bridge handles a scan request that arrives while a scan is running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BusyPolicy {
+    /// Cancel the running scan silently and start the new one.
+    #[default]
+    Replace,
+    /// Start the new scan once the running and earlier queued scans finish.
+    Queue,
+    /// Refuse the new scan with [`BridgeMessage::ScanRejected`].
+    Reject,
+    /// Run both scans at once; their results interleave.
+    Parallel,
+}
+
+/// Environment variable picking the initial [`ResultOrder`] of both UIs.
+pub const RESULT_ORDER_VAR: &str = "RAGESCANNER_RESULT_ORDER";
+
+/// Order of the results table in the GUI and TUI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResultOrder {
+    /// Ascending by address.
+    #[default]
+    Ip,
+    /// Most recent response first, for watching hosts come and go.
+    Live,
+}
+
+impl ResultOrder {
+    /// Reads [`RESULT_ORDER_VAR`]: `live` for [`ResultOrder::Live`], anything
+    /// else (or nothing) for [`ResultOrder::Ip`].
+    pub fn from_env() -> Self {
+        match std::env::var(RESULT_ORDER_VAR) {
+            Ok(value) if value.trim().eq_ignore_ascii_case("live") => ResultOrder::Live,
+            _ => ResultOrder::Ip,
+        }
+    }
+
+    /// The other order.
+    pub fn toggled(self) -> Self {
+        match self {
+            ResultOrder::Ip => ResultOrder::Live,
+            ResultOrder::Live => ResultOrder::Ip,
+        }
+    }
+}
+
+impl fmt::Display for ResultOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ResultOrder::Ip => "IP",
+            ResultOrder::Live => "Live",
+        })
+    }
+}
+
+/// Version of the JSON layout of the serializable public types.
+///
+/// Raised when a field or variant is renamed or removed, or changes type.
+/// New optional fields and new variants keep the version: readers should
+/// ignore fields they do not know, and older data without them still loads.
+pub const SCHEMA_VERSION: u32 = 2;
+
 /// Messages exchanged between the UI and the scanner bridge.
+///
+/// With the `serde` feature, a message serializes as `{"type": <variant>,
+/// "data": <fields>}`; `data` is absent for variants without fields, holds
+/// the value of single-field tuple variants, an array for the others and an
+/// object for struct variants. [`Duration`]s are `{"secs", "nanos"}` objects.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "data"))]
 pub enum BridgeMessage {
+    /// Start a scan from text: a range, comma-separated ranges and CIDR
+    /// blocks (`10.0.1.0/24,10.0.5.1-50`), or a pasted host list.
     StartScan(String),
     /// Start a scan using typed IP addresses (no string parsing needed).
     StartScanRange(Ipv4Addr, Ipv4Addr),
+    /// Start a scan of an explicit, non-contiguous list of addresses.
+    StartScanTargets(Vec<Ipv4Addr>),
+    /// Start a scan of several ranges, e.g. loaded with [`ScanTargets::from_file`].
+    StartScanMulti(ScanTargets),
+    /// Like [`BridgeMessage::StartScan`], with tuning for this scan only.
+    StartScanWithConfig {
+        range: String,
+        config: Box<ScanConfig>,
+    },
+    /// Re-scan `range` every `interval` and report device changes until `StopScan`.
+    StartMonitor {
+        interval: Duration,
+        range: String,
+    },
+    /// Read the ARP/neighbor cache every `interval` and report hosts that
+    /// show up in it until `StopScan`, sending no probes. `range` limits the
+    /// addresses reported; empty means all of them.
+    StartArpWatch {
+        interval: Duration,
+        range: String,
+    },
+    /// ARP `ip` every `interval` until `StopScan` and send a
+    /// [`BridgeMessage::IpConflict`] whenever another MAC answers for it.
+    StartConflictWatch {
+        ip: Ipv4Addr,
+        interval: Duration,
+    },
+    /// Run `request` (any `StartScan*`, `StartMonitor`, `StartArpWatch` or
+    /// `StartConflictWatch`) as scan `id`, alongside other scans and
+    /// regardless of the [`BusyPolicy`]. Everything the scan reports arrives
+    /// wrapped in [`BridgeMessage::Tagged`].
+    StartTaggedScan {
+        id: ScanId,
+        request: Box<BridgeMessage>,
+    },
     /// Request cancellation of the currently running scan.
     StopScan,
+    /// Cancel only the scan started as `id`.
+    StopTaggedScan(ScanId),
+    /// Stop dispatching new hosts in running scans; hosts already in flight finish.
+    PauseScan,
+    /// Continue scans paused with [`BridgeMessage::PauseScan`].
+    ResumeScan,
+    /// Ask every running scan where its hosts stand; each answers with a
+    /// [`BridgeMessage::StateSnapshot`], tagged if the scan was started tagged.
+    RequestStateSnapshot,
+    /// Deep-scan one host's ports alongside any running range scan.
+    StartDeepScan(Ipv4Addr),
+    /// Cancel the deep scan of a host; range scans are unaffected.
+    StopDeepScan(Ipv4Addr),
+    /// Scan one host again with the current settings, alongside any range
+    /// scan; answered with [`BridgeMessage::HostRescanned`].
+    RescanHost(Ipv4Addr),
+    /// Scan one host again and then probe `ports` on it, alongside any range
+    /// scan. The host's row is refreshed with [`BridgeMessage::ScanUpdate`]
+    /// after the host scan and again for every open port found; progress
+    /// arrives as [`BridgeMessage::HostScanProgress`], and
+    /// [`BridgeMessage::HostScanFinished`] ends it. Cancelled by
+    /// [`BridgeMessage::StopDeepScan`].
+    ScanHost {
+        ip: Ipv4Addr,
+        ports: Vec<u16>,
+    },
+    /// Broadcast a Wake-on-LAN magic packet to a MAC address; answered with
+    /// [`BridgeMessage::WakeOnLanSent`] or [`BridgeMessage::WakeOnLanFailed`].
+    WakeOnLan(String),
+    /// Wake several hosts, [`WAKE_STAGGER`](crate::wol::WAKE_STAGGER) apart,
+    /// and wait up to `timeout` for them to answer pings. Targets without an
+    /// address are looked up by MAC in the ARP table. Each packet is answered
+    /// with [`BridgeMessage::WakeOnLanSent`] or
+    /// [`BridgeMessage::WakeOnLanFailed`], the batch with
+    /// [`BridgeMessage::WakeBatchComplete`].
+    WakeBatch {
+        targets: Vec<WakeTarget>,
+        timeout: Duration,
+    },
+    /// Trace the route to a host, one TTL at a time up to
+    /// [`MAX_TRACE_HOPS`](crate::bridge::MAX_TRACE_HOPS); answered with a
+    /// [`BridgeMessage::TracerouteHop`] per hop, then
+    /// [`BridgeMessage::TracerouteComplete`]. Replaces a trace of the same host.
+    Traceroute(Ipv4Addr),
+    /// Test which of `hosts` accept TCP connections on which of `ports` (the
+    /// configured scan ports if empty), alongside any scan; answered with
+    /// [`BridgeMessage::ReachMatrixComplete`].
+    ReachMatrix {
+        hosts: Vec<ReachHost>,
+        ports: Vec<u16>,
+    },
+    /// Enable or disable UDP port probing for subsequent scans.
+    SetUdpScan(bool),
+    /// Include `.0`/`.255` of fully covered /24s in range scans (skipped by default).
+    SetIncludeSubnetEdges(bool),
+    /// Set the number of ICMP echoes sent to each responding host (minimum 1).
+    SetPingAttempts(u32),
+    /// Set how long port results are reused across scans; zero disables the cache.
+    SetPortCacheTtl(Duration),
+    /// Choose what happens to scan requests while a scan is running.
+    SetBusyPolicy(BusyPolicy),
+    /// Replace the user-defined TCP port labels; see [`set_port_labels`].
+    SetPortLabels(BTreeMap<u16, String>),
+    /// Resolve vendors from a custom OUI CSV; `None` restores the bundled database.
+    SetOuiDatabase(Option<PathBuf>),
+    /// Re-read the current OUI database from disk.
+    ReloadOuiDatabase,
+    /// Annotate results with an asset inventory CSV; `None` drops it. See
+    /// [`inventory`](crate::inventory).
+    SetInventory(Option<PathBuf>),
+    /// Name the device with this MAC, or forget its name for `None`, in the
+    /// [`devices`](crate::devices) book; replies [`BridgeMessage::DeviceNoteSaved`].
+    SetDeviceNote {
+        mac: String,
+        note: Option<DeviceNote>,
+    },
+    /// Tune subsequent scans (and host rescans) started without a config of
+    /// their own; see [`Settings::scan_config`](crate::settings::Settings::scan_config).
+    SetScanConfig(Box<ScanConfig>),
+    /// Reread this settings file every
+    /// [`RELOAD_POLL_INTERVAL`](crate::settings::RELOAD_POLL_INTERVAL) and tune
+    /// subsequent scans with its values when they change, replying
+    /// [`BridgeMessage::ConfigReloaded`]. Replaces any earlier watch.
+    WatchSettings(PathBuf),
+    /// The watched settings file changed the scan tuning, which now applies to
+    /// subsequent scans. Carries the whole file, including its `last_range`.
+    ConfigReloaded(Box<crate::settings::Settings>),
+    /// The watched settings file changed but cannot be used; the previous
+    /// tuning stays in effect.
+    ConfigReloadFailed(GError),
+    /// Label subsequent scans whose config has no label of its own; `None` clears it.
+    SetScanLabel(Option<ScanLabel>),
+    /// Notify on events of subsequent scans; `None` turns notifications off.
+    /// See [`notify`](crate::notify).
+    SetNotifications(Option<NotifyConfig>),
+    /// A requested scan began (immediately or after waiting in the queue),
+    /// with the label it runs under.
+    ScanStarted(Option<ScanLabel>),
     ScanUpdate(ScanResult),
     /// Sent when a scan is completed successfully.
     ScanComplete,
     /// Sent when a scan is cancelled before completion.
     ScanCancelled,
+    /// Overall percentage of the running scan, both phases weighted
+    /// together ([`PhaseProgress::overall_percent`]); it never goes down.
     Progress(u8),
+    /// Discovery and port-scan progress of the running scan.
+    Phase(PhaseProgress),
+    /// Per-host phases of a running scan, in answer to
+    /// [`BridgeMessage::RequestStateSnapshot`]. Empty for ARP and conflict
+    /// watches, which dispatch no hosts.
+    StateSnapshot(StateSnapshot),
+    /// Current packet rate and estimated time remaining for the running scan.
+    ScanStats(ScanStats),
+    /// Totals of the scan, sent right before it completes or is cancelled
+    /// (after every sweep of a monitor).
+    ScanSummary(ScanSummary),
+    /// Per-range progress of a scan of several ranges: every range once
+    /// when the scan starts, then whenever a range's percentage changes.
+    RangeProgress(RangeProgress),
     Error(GError),
+    /// The OUI database was (re)loaded with this many records.
+    OuiDatabaseLoaded(usize),
+    /// The asset inventory was loaded with this many devices (0 when
+    /// dropped); frontends re-[`annotate`](crate::inventory::annotate) the
+    /// results they hold.
+    InventoryLoaded(usize),
+    /// A device note was saved; frontends re-[`annotate`](crate::devices::annotate)
+    /// the results they hold.
+    DeviceNoteSaved {
+        mac: String,
+        note: Option<DeviceNote>,
+    },
+    /// The scan request was queued behind a running scan; 1 means it runs next.
+    ScanQueued(usize),
+    /// The scan request was refused because a scan is running ([`BusyPolicy::Reject`]).
+    ScanRejected,
+    /// The scan stopped dispatching hosts because a network interface changed;
+    /// carries a user-facing reason.
+    ScanPaused(String),
+    /// The network settled after [`BridgeMessage::ScanPaused`] and the scan continues.
+    ScanResumed,
+    /// The machine is going to sleep mid-scan; running scans are paused.
+    /// Frontends checkpoint their results.
+    SystemSuspended,
+    /// The machine woke after [`BridgeMessage::SystemSuspended`]. The scan
+    /// stays paused until the frontend sends [`BridgeMessage::ResumeScan`]
+    /// or restarts it.
+    SystemResumed,
+    /// A scan stopped or restarted dispatching hosts after
+    /// [`BridgeMessage::PauseScan`] / [`BridgeMessage::ResumeScan`].
+    ScanState(ScanRunState),
+    /// Many unrelated hosts answered `GET /` identically, so TCP results are
+    /// probably being intercepted. Sent at most once per scan.
+    InterceptionSuspected(InterceptionWarning),
+    /// A message from the scan started with [`BridgeMessage::StartTaggedScan`] as `id`.
+    Tagged(ScanId, Box<BridgeMessage>),
+    /// An open TCP port found on a host still being scanned, ahead of its
+    /// [`BridgeMessage::ScanUpdate`]. Only sent for hosts probed on more than
+    /// [`PORT_STREAM_THRESHOLD`](crate::scanner::PORT_STREAM_THRESHOLD)
+    /// ports, and thinned out by the bridge when ports open faster than a
+    /// frontend can draw them; the final result lists every port.
+    PortFound {
+        ip: Ipv4Addr,
+        port: u16,
+    },
+    /// Deep scan: an open port found on the host.
+    DeepScanPort(Ipv4Addr, PortInfo),
+    /// Deep scan: percentage of the host's ports probed.
+    DeepScanProgress(Ipv4Addr, u8),
+    /// Deep scan finished; all open ports with banners, sorted.
+    DeepScanComplete(Ipv4Addr, Vec<PortInfo>),
+    /// Deep scan stopped before completion.
+    DeepScanCancelled(Ipv4Addr),
+    /// Fresh result for a host requested with [`BridgeMessage::RescanHost`].
+    HostRescanned(ScanResult),
+    /// Percentage of the ports probed by a [`BridgeMessage::ScanHost`].
+    HostScanProgress(Ipv4Addr, u8),
+    /// A [`BridgeMessage::ScanHost`] completed or was cancelled.
+    HostScanFinished(Ipv4Addr),
+    /// A magic packet was broadcast to this MAC address.
+    WakeOnLanSent(String),
+    /// The magic packet for this MAC address could not be sent.
+    WakeOnLanFailed(String, GError),
+    /// A [`BridgeMessage::WakeBatch`] finished: which hosts came online.
+    WakeBatchComplete(WakeReport),
+    /// Traceroute: the router (or target) answering at one TTL.
+    TracerouteHop(Ipv4Addr, TraceHop),
+    /// Traceroute finished: the target answered, the hop limit was reached
+    /// or a local error stopped it.
+    TracerouteComplete(Ipv4Addr),
+    /// The host × port table of a [`BridgeMessage::ReachMatrix`].
+    ReachMatrixComplete(ReachMatrix),
+    /// Monitoring: an online host that was absent from the previous sweep.
+    DeviceAppeared(ScanResult),
+    /// Monitoring: a host from the previous sweep that is no longer online.
+    DeviceDisappeared(ScanResult),
+    /// Monitoring: a host whose MAC, hostname or open ports differ from the previous sweep.
+    DeviceChanged {
+        previous: Box<ScanResult>,
+        current: Box<ScanResult>,
+    },
+    /// Monitoring alert: TCP ports opened or closed on a host since it was
+    /// last seen online; see [`PortTracker`](crate::monitor::PortTracker).
+    PortsChanged {
+        host: Box<ScanResult>,
+        opened: Vec<u16>,
+        closed: Vec<u16>,
+    },
+    /// Monitoring alert: a host answering echoes far slower than its rolling
+    /// baseline; see [`LatencyTracker`](crate::monitor::LatencyTracker).
+    LatencyAnomaly {
+        host: Box<ScanResult>,
+        baseline_ms: f32,
+    },
+    /// Monitoring alert: a host's MAC or hostname differs from its last
+    /// sighting, or its MAC turned up at a new address; see
+    /// [`IdentityTracker`](crate::monitor::IdentityTracker).
+    SecurityAlert {
+        host: Box<ScanResult>,
+        change: IdentityChange,
+    },
+    /// Conflict watch alert: `current` answered ARP for `ip` where
+    /// `previous` did last time. `macs` lists every MAC that answered so
+    /// far, in order of first answer.
+    IpConflict {
+        ip: Ipv4Addr,
+        previous: String,
+        current: String,
+        macs: Vec<String>,
+    },
 }
 
 /// Well-known port definitions used for scanning.
@@ -109,9 +2148,63 @@ pub const COMMON_PORTS: &[(u16, &str)] = &[
     (8080, "HTTP-Alt"),
 ];
 
-/// Returns the service label for a given port, or `"Unknown"` if not in the dictionary.
-pub fn port_label(port: u16) -> &'static str {
-    COMMON_PORTS
+/// Well-known UDP services probed when UDP scanning is enabled.
+///
+/// Each entry is `(port_number, service_label)`.
+pub const COMMON_UDP_PORTS: &[(u16, &str)] = &[
+    (53, "DNS"),
+    (67, "DHCP"),
+    (69, "TFTP"),
+    (123, "NTP"),
+    (137, "NetBIOS-NS"),
+    (161, "SNMP"),
+    (500, "IKE"),
+    (1900, "SSDP"),
+    (5353, "mDNS"),
+];
+
+/// User-defined TCP port labels, consulted before [`COMMON_PORTS`].
+static PORT_LABELS: RwLock<BTreeMap<u16, String>> = RwLock::new(BTreeMap::new());
+
+/// Replaces the user-defined port labels (e.g. 8006 → "Proxmox").
+///
+/// They take precedence over [`COMMON_PORTS`] in [`port_label`] and
+/// [`PortInfo::label`], and so in detail views and exports.
+pub fn set_port_labels(labels: BTreeMap<u16, String>) {
+    if let Ok(mut current) = PORT_LABELS.write() {
+        *current = labels;
+    }
+}
+
+/// Returns the user-defined port labels currently set.
+pub fn port_labels() -> BTreeMap<u16, String> {
+    PORT_LABELS
+        .read()
+        .map(|labels| labels.clone())
+        .unwrap_or_default()
+}
+
+/// Returns the user-defined label for a port, if one is set.
+fn port_label_override(port: u16) -> Option<String> {
+    PORT_LABELS.read().ok()?.get(&port).cloned()
+}
+
+/// Returns the service label for a given port: a user-defined label, else
+/// the [`COMMON_PORTS`] entry, else `"Unknown"`.
+pub fn port_label(port: u16) -> String {
+    port_label_override(port).unwrap_or_else(|| {
+        COMMON_PORTS
+            .iter()
+            .find(|(p, _)| *p == port)
+            .map(|(_, label)| *label)
+            .unwrap_or("Unknown")
+            .to_string()
+    })
+}
+
+/// Returns the service label for a given UDP port, or `"Unknown"` if not in the dictionary.
+pub fn udp_port_label(port: u16) -> &'static str {
+    COMMON_UDP_PORTS
         .iter()
         .find(|(p, _)| *p == port)
         .map(|(_, label)| *label)
@@ -122,6 +2215,104 @@ pub fn port_label(port: u16) -> &'static str {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_phase_progress_weights_the_port_phase() {
+        let mut phase = PhaseProgress {
+            hosts_discovered: 200,
+            hosts_total: 250,
+            ports_probed: 35,
+            ports_total: Some(100),
+        };
+        assert_eq!(phase.to_string(), "Discovery 80% · Ports 35%");
+        // 0.5 * 0.8 + 0.5 * 0.8 * 0.35
+        assert_eq!(phase.overall_percent(), 54);
+
+        // No online host yet: the port phase is still open.
+        phase.ports_total = Some(0);
+        phase.ports_probed = 0;
+        assert_eq!(phase.ports_percent(), Some(0));
+        assert_eq!(phase.overall_percent(), 40);
+        phase.hosts_discovered = 250;
+        assert_eq!(phase.overall_percent(), 100);
+
+        phase.hosts_discovered = 200;
+        phase.ports_total = None;
+        assert_eq!(phase.to_string(), "Discovery 80%");
+        assert_eq!(phase.overall_percent(), 80);
+    }
+
+    #[test]
+    fn test_merge_seen_keeps_the_first_sighting() {
+        let t = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let mut earlier = ScanResult::new(Ipv4Addr::new(10, 0, 0, 1));
+        earlier.status = ScanStatus::Online;
+        earlier.stamp(Duration::from_millis(5), t(100));
+
+        let mut offline = ScanResult::new(earlier.ip);
+        offline.status = ScanStatus::Offline;
+        offline.stamp(Duration::from_millis(900), t(200));
+        offline.merge_seen(&earlier);
+        assert_eq!(
+            (offline.first_seen, offline.last_seen),
+            (Some(t(100)), Some(t(100)))
+        );
+
+        let mut back = ScanResult::new(earlier.ip);
+        back.status = ScanStatus::Online;
+        back.stamp(Duration::from_millis(5), t(300));
+        back.merge_seen(&offline);
+        assert_eq!(
+            (back.first_seen, back.last_seen),
+            (Some(t(100)), Some(t(300)))
+        );
+
+        let mut summary = ScanSummary {
+            duration: Duration::from_millis(42_000),
+            hosts_up: 12,
+            hosts_down: 240,
+            errors: 1,
+            probes_sent: 1530,
+            ..ScanSummary::default()
+        };
+        assert_eq!(
+            summary.to_string(),
+            "12 up, 240 down, 1 error, 1530 probes in 42.0 s"
+        );
+        summary.traffic.record(ProbeKind::Icmp, 39);
+        summary.traffic.record(ProbeKind::Tcp, 60);
+        summary.traffic.record(ProbeKind::Tcp, 1_000);
+        summary.budget_exhausted = true;
+        assert_eq!(
+            summary.to_string(),
+            "12 up, 240 down, 1 error, 1530 probes (1.1 KB) in 42.0 s, probe budget used up"
+        );
+        assert_eq!(
+            summary.traffic.to_string(),
+            "ICMP 1 pkt, 39 B; TCP 2 pkt, 1.1 KB"
+        );
+        assert_eq!(summary.traffic.get(ProbeKind::Udp), PacketCount::default());
+        assert_eq!(format_bytes(2_500_000), "2.5 MB");
+    }
+
+    #[test]
+    fn test_scan_profile_parses_and_picks_config() {
+        assert_eq!(" Safe ".parse(), Ok(ScanProfile::Safe));
+        assert_eq!("standard".parse(), Ok(ScanProfile::Standard));
+        assert!(
+            "turbo"
+                .parse::<ScanProfile>()
+                .unwrap_err()
+                .contains("turbo")
+        );
+        assert_eq!(ScanProfile::Standard.config(), ScanConfig::default());
+        let safe = ScanProfile::Safe.config();
+        assert_eq!(
+            (safe.concurrency, safe.max_rate),
+            (1, Some(SAFE_MODE_MAX_RATE))
+        );
+        assert!(safe.icmp_only && safe.ports.is_empty());
+    }
+
     #[test]
     fn test_port_label_known() {
         assert_eq!(port_label(135), "RPC/EPMAP");
@@ -130,11 +2321,121 @@ mod tests {
         assert_eq!(port_label(445), "SMB");
     }
 
+    #[test]
+    fn test_from_win32_classifies_known_codes() {
+        assert!(matches!(
+            GError::from_win32(31, "SendARP"),
+            GError::HostUnreachable(_)
+        ));
+        assert!(matches!(
+            GError::from_win32(11008, "IcmpSendEcho"),
+            GError::AdapterDown(_)
+        ));
+        assert!(matches!(
+            GError::from_win32(5, "IcmpSendEcho"),
+            GError::PermissionDenied(_)
+        ));
+        assert!(matches!(
+            GError::from_win32(11010, "IcmpSendEcho2"),
+            GError::Timeout(_)
+        ));
+        let exhausted = GError::from_win32(10055, "connect");
+        assert_eq!(exhausted.code(), "resource_exhausted");
+        assert!(exhausted.is_local());
+        let other = GError::from_win32(87, "SendARP");
+        assert_eq!(
+            other,
+            GError::Win32 {
+                code: 87,
+                op: "SendARP".to_string()
+            }
+        );
+        assert_eq!(other.to_string(), "Win32 Error (87): SendARP failed");
+        assert!(other.hint().is_none());
+        assert!(!other.is_local());
+        assert!(
+            GError::from_win32(1231, "SendARP")
+                .hint()
+                .unwrap()
+                .contains("same subnet")
+        );
+    }
+
+    #[test]
+    fn test_from_hresult_unwraps_win32_codes() {
+        // E_ACCESSDENIED is HRESULT_FROM_WIN32(ERROR_ACCESS_DENIED).
+        assert!(matches!(
+            GError::from_hresult(0x8007_0005_u32 as i32, "ShowToast"),
+            GError::PermissionDenied(_)
+        ));
+        let other = GError::from_hresult(0x8000_4005_u32 as i32, "ShowToast");
+        assert_eq!(other.code(), "win32");
+        assert_eq!(
+            other.to_string(),
+            "Win32 Error (0x80004005): ShowToast failed"
+        );
+    }
+
+    #[test]
+    fn test_old_access_denied_errors_still_load() {
+        let e: GError = serde_json::from_str(r#"{"AccessDenied":"SendARP"}"#).unwrap();
+        assert_eq!(e, GError::PermissionDenied("SendARP".to_string()));
+    }
+
+    #[test]
+    fn test_from_io_classifies_known_kinds() {
+        use std::io::{Error, ErrorKind};
+        assert!(matches!(
+            GError::from_io(&Error::from(ErrorKind::PermissionDenied), "socket"),
+            GError::PermissionDenied(_)
+        ));
+        assert!(matches!(
+            GError::from_io(&Error::from(ErrorKind::TimedOut), "connect"),
+            GError::Timeout(_)
+        ));
+        assert!(matches!(
+            GError::from_io(&Error::from(ErrorKind::NetworkUnreachable), "send"),
+            GError::HostUnreachable(_)
+        ));
+        assert!(matches!(
+            GError::from_io(&Error::from(ErrorKind::NetworkDown), "send"),
+            GError::AdapterDown(_)
+        ));
+        assert!(matches!(
+            GError::from_io(&Error::other("boom"), "recv"),
+            GError::Internal(msg) if msg == "recv failed: boom"
+        ));
+    }
+
     #[test]
     fn test_port_label_unknown() {
         assert_eq!(port_label(9999), "Unknown");
     }
 
+    #[test]
+    fn test_port_label_overrides() {
+        let stored = PortInfo::new(32400);
+        assert_eq!(stored.service, "Unknown");
+
+        set_port_labels(BTreeMap::from([
+            (8006, "Proxmox".to_string()),
+            (32400, "Plex".to_string()),
+        ]));
+        assert_eq!(port_label(8006), "Proxmox");
+        assert_eq!(PortInfo::new(8006).service, "Proxmox");
+        // Entries found before the override still display it.
+        assert_eq!(stored.describe(), "32400 (Plex)");
+    }
+
+    #[test]
+    fn test_open_ports_accepts_bare_numbers() {
+        let json = r#"{"ip":"10.0.0.1","hostname":null,"mac":null,"vendor":null,
+            "status":"Online","open_ports":[22,{"port":80,"service":"HTTP","banner":"nginx"}]}"#;
+        let res: ScanResult = serde_json::from_str(json).unwrap();
+        assert_eq!(res.open_ports[0], PortInfo::new(22));
+        assert_eq!(res.open_ports[1].describe(), "80 (HTTP: nginx)");
+    }
+
     #[test]
     fn test_common_ports_complete() {
         // Every port in COMMON_PORTS has a non-empty label
@@ -147,4 +2448,111 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_latency_stats_from_samples() {
+        let stats = LatencyStats::from_samples(&[1, 2, 3], 4).unwrap();
+        assert_eq!(stats.min_ms, 1.0);
+        assert_eq!(stats.avg_ms, 2.0);
+        assert_eq!(stats.max_ms, 3.0);
+        assert_eq!((stats.sent, stats.received), (4, 3));
+        assert_eq!(stats.summary(), "1/2.0/3 ms");
+        assert!(LatencyStats::from_samples(&[], 4).is_none());
+    }
+
+    #[test]
+    fn test_vendor_details_display_skips_empty_fields() {
+        let details = VendorDetails {
+            oui: "00:1B:C5".to_string(),
+            company_name: "IEEE Registration Authority".to_string(),
+            registry: "MA-L".to_string(),
+            is_private: true,
+            ..VendorDetails::default()
+        };
+        assert_eq!(
+            details.to_string(),
+            "Company: IEEE Registration Authority\nBlock: 00:1B:C5 (MA-L), private"
+        );
+    }
+
+    #[test]
+    fn test_scan_label_parse() {
+        let label = ScanLabel::parse(" Office 3F audit | pre-migration ").unwrap();
+        assert_eq!(label.name, "Office 3F audit");
+        assert_eq!(label.description.as_deref(), Some("pre-migration"));
+        assert_eq!(label.to_string(), "Office 3F audit (pre-migration)");
+        assert_eq!(ScanLabel::parse("lab |"), Some(ScanLabel::new("lab")));
+        assert_eq!(ScanLabel::parse("  | notes"), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_bridge_messages_serialize_adjacently_tagged() {
+        let mut res = ScanResult::new(Ipv4Addr::new(10, 0, 0, 9));
+        res.status = ScanStatus::Online;
+        let msg = BridgeMessage::Tagged(ScanId(3), Box::new(BridgeMessage::ScanUpdate(res)));
+        let json = serde_json::to_value(&msg).unwrap();
+        assert_eq!(json["type"], "Tagged");
+        assert_eq!(json["data"][0], 3);
+        assert_eq!(json["data"][1]["type"], "ScanUpdate");
+        assert_eq!(json["data"][1]["data"]["ip"], "10.0.0.9");
+
+        let back: BridgeMessage = serde_json::from_value(json).unwrap();
+        assert!(matches!(back, BridgeMessage::Tagged(ScanId(3), inner)
+            if matches!(&*inner, BridgeMessage::ScanUpdate(r) if r.status == ScanStatus::Online)));
+
+        let unit = serde_json::to_string(&BridgeMessage::ScanComplete).unwrap();
+        assert_eq!(unit, r#"{"type":"ScanComplete"}"#);
+        let error: BridgeMessage =
+            serde_json::from_str(r#"{"type":"Error","data":{"Internal":"boom"}}"#).unwrap();
+        assert!(matches!(error, BridgeMessage::Error(GError::Internal(m)) if m == "boom"));
+    }
+
+    #[test]
+    fn test_device_note_round_trips_through_text() {
+        let note = DeviceNote::parse("Living-room AP | ceiling, PoE port 4").unwrap();
+        assert_eq!(note.notes.as_deref(), Some("ceiling, PoE port 4"));
+        assert_eq!(DeviceNote::parse(&note.to_string()), Some(note));
+        assert_eq!(DeviceNote::parse(" | notes"), None);
+    }
+
+    #[test]
+    fn test_socks5_config_parse() {
+        let proxy: Socks5Config = "socks5://scout:s3:cret@bastion:1080".parse().unwrap();
+        assert_eq!(proxy.addr, "bastion:1080");
+        assert_eq!(proxy.username.as_deref(), Some("scout"));
+        assert_eq!(proxy.password.as_deref(), Some("s3:cret"));
+        assert!(!format!("{:?}", proxy).contains("s3:cret"));
+
+        let proxy: Socks5Config = "127.0.0.1:1080".parse().unwrap();
+        assert_eq!((proxy.username, proxy.password), (None, None));
+        let proxy: Socks5Config = "scout@127.0.0.1:1080".parse().unwrap();
+        assert_eq!(proxy.username.as_deref(), Some("scout"));
+        assert_eq!(proxy.password, None);
+
+        assert!("bastion".parse::<Socks5Config>().is_err());
+        assert!(":1080".parse::<Socks5Config>().is_err());
+        assert!("bastion:socks".parse::<Socks5Config>().is_err());
+    }
+
+    #[test]
+    fn test_scan_stats_display() {
+        let mut stats = ScanStats {
+            packets_per_second: 118.6,
+            rate_limit: Some(120),
+            completed: 40,
+            total: 254,
+            eta: Some(Duration::from_secs(95)),
+        };
+        assert_eq!(stats.to_string(), "119 pkt/s (limit 120), ETA 1:35");
+        (stats.rate_limit, stats.eta) = (None, None);
+        assert_eq!(stats.to_string(), "119 pkt/s");
+    }
+
+    #[test]
+    fn test_udp_port_label() {
+        assert_eq!(udp_port_label(161), "SNMP");
+        assert_eq!(udp_port_label(123), "NTP");
+        assert_eq!(udp_port_label(9999), "Unknown");
+    }
 }
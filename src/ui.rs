@@ -2,21 +2,162 @@ use log::error;
 use native_windows_derive::NwgUi;
 use native_windows_gui as nwg;
 use nwg::NativeUi;
-use ragescanner::types::{BridgeMessage, ScanResult};
-use std::cell::RefCell;
+use ragescanner::about;
+use ragescanner::bridge::{Bridge, EXIT_STOP_TIMEOUT};
+use ragescanner::devices;
+use ragescanner::elevation::{self, PendingScan};
+use ragescanner::export::{self, ExportScope};
+use ragescanner::import;
+use ragescanner::inventory::{self, INVENTORY_VAR};
+use ragescanner::monitor;
+use ragescanner::net::{self, NetUtils, NetworkProvider};
+use ragescanner::report;
+use ragescanner::session::{self, DEFAULT_SESSION_PATH, Session};
+use ragescanner::settings::Settings;
+use ragescanner::targets::ScanTargets;
+use ragescanner::types::{
+    BridgeMessage, DeviceNote, GError, PhaseProgress, PortInfo, RangeProgress, ResultOrder,
+    ScanLabel, ScanResult, ScanRunState, ScanStatus, ScanSummary,
+};
+use ragescanner::wol::{DEFAULT_WAKE_TIMEOUT, WakeTarget};
+use std::cell::{Cell, RefCell};
+use std::cmp;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::mpsc::Sender as TokioSender;
 
+/// Minimum time between session autosaves while a scan is running.
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(10);
+/// Bridge messages handled per notice before yielding to the window message loop.
+const MAX_MESSAGES_PER_NOTICE: usize = 256;
+/// Last port probed by "Full Port Scan".
+const FULL_SCAN_LAST_PORT: u16 = 65535;
+/// Log file written next to the executable, shown by "Log Viewer".
+pub const LOG_PATH: &str = "ragescanner.log";
+/// How much of the end of the log "Log Viewer" shows.
+const LOG_VIEW_BYTES: u64 = 64 * 1024;
+/// Differences listed by "Compare with Saved Scan" before the rest are counted.
+const COMPARE_MAX_LINES: usize = 40;
+/// Interval between sweeps of "Monitor".
+const MONITOR_INTERVAL: Duration = Duration::from_secs(60);
+
 #[derive(Default, NwgUi)]
 pub struct RageScannerApp {
     #[nwg_resource(family: "Segoe UI", size: 16)]
     font: nwg::Font,
 
-    #[nwg_control(size: (700, 500), position: (300, 300), title: "RageScanner - Windows IP Scanner", flags: "WINDOW|VISIBLE")]
-    #[nwg_events( OnWindowClose: [RageScannerApp::exit] )]
+    #[nwg_control(size: (780, 500), position: (300, 300), title: "RageScanner - Windows IP Scanner", flags: "WINDOW|VISIBLE")]
+    #[nwg_events(
+        OnWindowClose: [RageScannerApp::exit],
+        OnWindowMinimize: [RageScannerApp::minimize_to_tray]
+    )]
     window: nwg::Window,
 
+    // Menu bar
+    #[nwg_control(parent: window, text: "&File")]
+    file_menu: nwg::Menu,
+
+    #[nwg_control(parent: file_menu, text: "New Scan")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::new_scan])]
+    new_scan_item: nwg::MenuItem,
+
+    #[nwg_control(parent: file_menu, text: "Open Session...")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::open_session])]
+    open_session_item: nwg::MenuItem,
+
+    #[nwg_control(parent: file_menu, text: "Save Session...")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::save_session])]
+    save_session_item: nwg::MenuItem,
+
+    #[nwg_control(parent: file_menu)]
+    file_menu_separator: nwg::MenuSeparator,
+
+    #[nwg_control(parent: file_menu, text: "Import...")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::import_results])]
+    import_item: nwg::MenuItem,
+
+    #[nwg_control(parent: file_menu, text: "Export...")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::export_results])]
+    export_item: nwg::MenuItem,
+
+    #[nwg_control(parent: file_menu, text: "Save Report...")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::save_scan_report])]
+    file_report_item: nwg::MenuItem,
+
+    #[nwg_control(parent: file_menu)]
+    exit_separator: nwg::MenuSeparator,
+
+    #[nwg_control(parent: file_menu, text: "Exit")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::exit])]
+    exit_item: nwg::MenuItem,
+
+    #[nwg_control(parent: window, text: "&Scan")]
+    scan_menu: nwg::Menu,
+
+    #[nwg_control(parent: scan_menu, text: "Start")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::start_scan])]
+    start_item: nwg::MenuItem,
+
+    #[nwg_control(parent: scan_menu, text: "Stop")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::confirm_stop_scan])]
+    stop_item: nwg::MenuItem,
+
+    #[nwg_control(parent: scan_menu, text: "Pause / Resume")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::toggle_pause])]
+    pause_item: nwg::MenuItem,
+
+    #[nwg_control(parent: scan_menu, text: "My Subnet")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::scan_my_subnet])]
+    subnet_item: nwg::MenuItem,
+
+    #[nwg_control(parent: scan_menu, text: "Monitor")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::start_monitor])]
+    monitor_item: nwg::MenuItem,
+
+    #[nwg_control(parent: window, text: "&View")]
+    view_menu: nwg::Menu,
+
+    #[nwg_control(parent: view_menu, text: "Online Only")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::toggle_online_only])]
+    online_only_item: nwg::MenuItem,
+
+    #[nwg_control(parent: view_menu, text: "Switch Order")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::toggle_result_order])]
+    order_item: nwg::MenuItem,
+
+    #[nwg_control(parent: view_menu, text: "History...")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::show_history_search])]
+    history_item: nwg::MenuItem,
+
+    #[nwg_control(parent: window, text: "&Tools")]
+    tools_menu: nwg::Menu,
+
+    #[nwg_control(parent: tools_menu, text: "Settings...")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::show_settings])]
+    settings_item: nwg::MenuItem,
+
+    #[nwg_control(parent: tools_menu, text: "Compare with Saved Scan...")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::compare_with_saved])]
+    compare_item: nwg::MenuItem,
+
+    #[nwg_control(parent: tools_menu, text: "Log Viewer")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::show_log])]
+    log_item: nwg::MenuItem,
+
+    #[nwg_control(parent: window, text: "&Help")]
+    help_menu: nwg::Menu,
+
+    #[nwg_control(parent: help_menu, text: "About RageScanner")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::show_about])]
+    about_item: nwg::MenuItem,
+
     #[nwg_layout(parent: window, spacing: 3)]
     layout: nwg::GridLayout,
 
@@ -26,183 +167,2075 @@ pub struct RageScannerApp {
     label_start: nwg::Label,
 
     #[nwg_control(text: "192.168.1.1")]
-    #[nwg_layout_item(layout: layout, col: 1, row: 0, row_span: 2)]
+    #[nwg_layout_item(layout: layout, col: 1, row: 0, col_span: 2, row_span: 2)]
+    #[nwg_events( OnTextInput: [RageScannerApp::validate_range_input] )]
     start_ip_input: nwg::TextInput,
 
     // Row 0: End IP
     #[nwg_control(text: "End IP:", h_align: nwg::HTextAlign::Right)]
-    #[nwg_layout_item(layout: layout, col: 2, row: 0, row_span: 2)]
+    #[nwg_layout_item(layout: layout, col: 3, row: 0, row_span: 2)]
     label_end: nwg::Label,
 
     #[nwg_control(text: "255")]
-    #[nwg_layout_item(layout: layout, col: 3, row: 0, row_span: 2)]
+    #[nwg_layout_item(layout: layout, col: 4, row: 0, col_span: 2, row_span: 2)]
+    #[nwg_events( OnTextInput: [RageScannerApp::validate_range_input] )]
     end_ip_input: nwg::TextInput,
 
     #[nwg_control(text: "Scan")]
-    #[nwg_layout_item(layout: layout, col: 4, row: 0, row_span: 2)]
+    #[nwg_layout_item(layout: layout, col: 6, row: 0, row_span: 2)]
     #[nwg_events( OnButtonClick: [RageScannerApp::start_scan] )]
     scan_btn: nwg::Button,
 
+    #[nwg_control(text: "Stop", enabled: false)]
+    #[nwg_layout_item(layout: layout, col: 7, row: 0, row_span: 2)]
+    #[nwg_events( OnButtonClick: [RageScannerApp::confirm_stop_scan] )]
+    stop_btn: nwg::Button,
+
+    #[nwg_control(text: "Pause", enabled: false)]
+    #[nwg_layout_item(layout: layout, col: 8, row: 0, row_span: 2)]
+    #[nwg_events( OnButtonClick: [RageScannerApp::toggle_pause] )]
+    pause_btn: nwg::Button,
+
+    #[nwg_control(text: "My Subnet")]
+    #[nwg_layout_item(layout: layout, col: 9, row: 0, row_span: 2)]
+    #[nwg_events( OnButtonClick: [RageScannerApp::scan_my_subnet] )]
+    subnet_btn: nwg::Button,
+
+    #[nwg_control(text: "Order: IP")]
+    #[nwg_layout_item(layout: layout, col: 10, row: 0, row_span: 2)]
+    #[nwg_events( OnButtonClick: [RageScannerApp::toggle_result_order] )]
+    order_btn: nwg::Button,
+
+    #[nwg_resource(title: "Import scan results", action: nwg::FileDialogAction::Open, filters: "Scanner exports (*.csv;*.txt;*.xml)|All files (*.*)")]
+    import_dialog: nwg::FileDialog,
+
+    #[nwg_resource(title: "Export scan results", action: nwg::FileDialogAction::Save, filters: "JSON (*.json)|CSV (*.csv)|Nmap XML (*.xml)|Text table (*.txt)")]
+    export_dialog: nwg::FileDialog,
+
+    #[nwg_resource(title: "Save host report", action: nwg::FileDialogAction::Save, filters: "Markdown (*.md)|Text (*.txt)")]
+    report_dialog: nwg::FileDialog,
+
+    #[nwg_resource(title: "Save scan report", action: nwg::FileDialogAction::Save, filters: "HTML (*.html)")]
+    scan_report_dialog: nwg::FileDialog,
+
+    #[nwg_resource(title: "Open session", action: nwg::FileDialogAction::Open, filters: "Sessions (*.json)|All files (*.*)")]
+    open_session_dialog: nwg::FileDialog,
+
+    #[nwg_resource(title: "Save session", action: nwg::FileDialogAction::Save, filters: "Sessions (*.json)")]
+    save_session_dialog: nwg::FileDialog,
+
+    #[nwg_resource(title: "Compare with saved scan", action: nwg::FileDialogAction::Open, filters: "Saved scans (*.json;*.ndjson)|All files (*.*)")]
+    compare_dialog: nwg::FileDialog,
+
+    #[nwg_control(text: "", placeholder_text: Some("Filter by hostname, vendor or IP"))]
+    #[nwg_layout_item(layout: layout, col: 0, row: 2, col_span: 9)]
+    #[nwg_events( OnTextInput: [RageScannerApp::show_results] )]
+    filter_input: nwg::TextInput,
+
+    #[nwg_control(text: "Online only")]
+    #[nwg_layout_item(layout: layout, col: 9, row: 2, col_span: 2)]
+    #[nwg_events( OnButtonClick: [RageScannerApp::online_only_changed] )]
+    online_only: nwg::CheckBox,
+
+    // Row 3: what is wrong with the Start / End fields, blank while they are valid
+    #[nwg_control(text: "")]
+    #[nwg_layout_item(layout: layout, col: 0, row: 3, col_span: 11)]
+    input_error_label: nwg::Label,
+
     #[nwg_control(list_style: nwg::ListViewStyle::Detailed)]
-    #[nwg_layout_item(layout: layout, col: 0, row: 2, col_span: 5, row_span: 16)]
+    #[nwg_layout_item(layout: layout, col: 0, row: 4, col_span: 11, row_span: 14)]
+    #[nwg_events(
+        OnListViewDoubleClick: [RageScannerApp::show_vendor_details],
+        OnListViewRightClick: [RageScannerApp::show_row_menu],
+        OnListViewColumnClick: [RageScannerApp::sort_by_column(SELF, EVT_DATA)]
+    )]
     list_view: nwg::ListView,
 
-    #[nwg_control(range: 0..100, pos: 0)]
-    #[nwg_layout_item(layout: layout, col: 0, row: 18, col_span: 5)]
-    progress_bar: nwg::ProgressBar,
+    // Per-host actions for the right-clicked row
+    #[nwg_control(parent: window, popup: true)]
+    row_menu: nwg::Menu,
+
+    #[nwg_control(parent: row_menu, text: "Copy IP")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::copy_ip])]
+    copy_ip_item: nwg::MenuItem,
+
+    #[nwg_control(parent: row_menu, text: "Copy MAC")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::copy_mac])]
+    copy_mac_item: nwg::MenuItem,
+
+    #[nwg_control(parent: row_menu, text: "Copy Hostname")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::copy_hostname])]
+    copy_hostname_item: nwg::MenuItem,
+
+    #[nwg_control(parent: row_menu, text: "Copy Report")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::copy_report])]
+    copy_report_item: nwg::MenuItem,
+
+    #[nwg_control(parent: row_menu, text: "Save Host Report...")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::save_report])]
+    save_report_item: nwg::MenuItem,
+
+    #[nwg_control(parent: row_menu, text: "Save Report...")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::save_scan_report])]
+    save_scan_report_item: nwg::MenuItem,
+
+    #[nwg_control(parent: row_menu)]
+    row_menu_separator: nwg::MenuSeparator,
+
+    #[nwg_control(parent: row_menu, text: "Open in Browser")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::open_in_browser])]
+    browser_item: nwg::MenuItem,
+
+    #[nwg_control(parent: row_menu, text: "Remote Desktop")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::open_rdp])]
+    rdp_item: nwg::MenuItem,
+
+    #[nwg_control(parent: row_menu, text: "Ping in Console")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::ping_in_console])]
+    ping_item: nwg::MenuItem,
+
+    #[nwg_control(parent: row_menu, text: "Rescan Host")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::rescan_host])]
+    rescan_item: nwg::MenuItem,
+
+    #[nwg_control(parent: row_menu, text: "Full Port Scan")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::full_port_scan])]
+    full_scan_item: nwg::MenuItem,
+
+    #[nwg_control(parent: row_menu, text: "Wake-on-LAN")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::wake_host])]
+    wake_item: nwg::MenuItem,
+
+    #[nwg_control(parent: row_menu, text: "Name Device...")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::name_device])]
+    name_device_item: nwg::MenuItem,
+
+    #[nwg_control(range: 0..100, pos: 0)]
+    #[nwg_layout_item(layout: layout, col: 0, row: 18, col_span: 6)]
+    progress_bar: nwg::ProgressBar,
+
+    #[nwg_control(text: "")]
+    #[nwg_layout_item(layout: layout, col: 6, row: 18, col_span: 5)]
+    range_label: nwg::Label,
+
+    #[nwg_control(text: "Ready")]
+    #[nwg_layout_item(layout: layout, col: 0, row: 19, col_span: 11)]
+    status_bar: nwg::StatusBar,
+
+    #[nwg_control]
+    #[nwg_events(OnNotice: [RageScannerApp::handle_ui_message])]
+    ui_notice: nwg::Notice,
+
+    #[nwg_control]
+    #[nwg_events(OnNotice: [RageScannerApp::clear_results])]
+    clear_notice: nwg::Notice,
+
+    // Tray icon; minimizing hides the window here and scans keep running
+    #[nwg_resource(source_system: Some(nwg::OemIcon::Information))]
+    tray_icon: nwg::Icon,
+
+    #[nwg_control(parent: window, icon: Some(&data.tray_icon), tip: Some("RageScanner"))]
+    #[nwg_events(
+        MousePressLeftUp: [RageScannerApp::restore_from_tray],
+        OnContextMenu: [RageScannerApp::show_tray_menu]
+    )]
+    tray: nwg::TrayNotification,
+
+    #[nwg_control(parent: window, popup: true)]
+    tray_menu: nwg::Menu,
+
+    #[nwg_control(parent: tray_menu, text: "Quick Scan")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::scan_my_subnet])]
+    tray_scan_item: nwg::MenuItem,
+
+    #[nwg_control(parent: tray_menu, text: "Open")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::restore_from_tray])]
+    tray_open_item: nwg::MenuItem,
+
+    #[nwg_control(parent: tray_menu)]
+    tray_menu_separator: nwg::MenuSeparator,
+
+    #[nwg_control(parent: tray_menu, text: "Exit")]
+    #[nwg_events(OnMenuItemSelected: [RageScannerApp::exit])]
+    tray_exit_item: nwg::MenuItem,
+
+    // Settings window, hidden until "Settings..." is picked; closing hides it
+    #[nwg_control(size: (340, 250), position: (360, 360), title: "Settings", flags: "WINDOW")]
+    settings_window: nwg::Window,
+
+    #[nwg_layout(parent: settings_window, spacing: 3)]
+    settings_layout: nwg::GridLayout,
+
+    #[nwg_control(parent: settings_window, text: "Ping timeout (ms):", h_align: nwg::HTextAlign::Right)]
+    #[nwg_layout_item(layout: settings_layout, col: 0, row: 0)]
+    ping_timeout_label: nwg::Label,
+
+    #[nwg_control(parent: settings_window, text: "")]
+    #[nwg_layout_item(layout: settings_layout, col: 1, row: 0)]
+    ping_timeout_input: nwg::TextInput,
+
+    #[nwg_control(parent: settings_window, text: "Port timeout (ms):", h_align: nwg::HTextAlign::Right)]
+    #[nwg_layout_item(layout: settings_layout, col: 0, row: 1)]
+    port_timeout_label: nwg::Label,
+
+    #[nwg_control(parent: settings_window, text: "", placeholder_text: Some("auto"))]
+    #[nwg_layout_item(layout: settings_layout, col: 1, row: 1)]
+    port_timeout_input: nwg::TextInput,
+
+    #[nwg_control(parent: settings_window, text: "Concurrency:", h_align: nwg::HTextAlign::Right)]
+    #[nwg_layout_item(layout: settings_layout, col: 0, row: 2)]
+    concurrency_label: nwg::Label,
+
+    #[nwg_control(parent: settings_window, text: "")]
+    #[nwg_layout_item(layout: settings_layout, col: 1, row: 2)]
+    concurrency_input: nwg::TextInput,
+
+    #[nwg_control(parent: settings_window, text: "Ports:", h_align: nwg::HTextAlign::Right)]
+    #[nwg_layout_item(layout: settings_layout, col: 0, row: 3)]
+    ports_label: nwg::Label,
+
+    #[nwg_control(parent: settings_window, text: "", placeholder_text: Some("none"))]
+    #[nwg_layout_item(layout: settings_layout, col: 1, row: 3)]
+    ports_input: nwg::TextInput,
+
+    #[nwg_control(parent: settings_window, text: "Look up hostnames")]
+    #[nwg_layout_item(layout: settings_layout, col: 0, row: 4, col_span: 2)]
+    resolve_hostnames_check: nwg::CheckBox,
+
+    #[nwg_control(parent: settings_window, text: "Look up vendors")]
+    #[nwg_layout_item(layout: settings_layout, col: 0, row: 5, col_span: 2)]
+    resolve_vendors_check: nwg::CheckBox,
+
+    #[nwg_control(parent: settings_window, text: "Save")]
+    #[nwg_layout_item(layout: settings_layout, col: 0, row: 6)]
+    #[nwg_events( OnButtonClick: [RageScannerApp::save_settings] )]
+    settings_save_btn: nwg::Button,
+
+    #[nwg_control(parent: settings_window, text: "Cancel")]
+    #[nwg_layout_item(layout: settings_layout, col: 1, row: 6)]
+    #[nwg_events( OnButtonClick: [RageScannerApp::hide_settings] )]
+    settings_cancel_btn: nwg::Button,
+
+    // Device naming window, hidden until "Name Device..." is picked
+    #[nwg_control(size: (340, 130), position: (360, 360), title: "Name Device", flags: "WINDOW")]
+    device_window: nwg::Window,
+
+    #[nwg_layout(parent: device_window, spacing: 3)]
+    device_layout: nwg::GridLayout,
+
+    #[nwg_control(parent: device_window, text: "Name:", h_align: nwg::HTextAlign::Right)]
+    #[nwg_layout_item(layout: device_layout, col: 0, row: 0)]
+    device_name_label: nwg::Label,
+
+    #[nwg_control(parent: device_window, text: "", placeholder_text: Some("e.g. Living-room AP"))]
+    #[nwg_layout_item(layout: device_layout, col: 1, row: 0, col_span: 2)]
+    device_name_input: nwg::TextInput,
+
+    #[nwg_control(parent: device_window, text: "Notes:", h_align: nwg::HTextAlign::Right)]
+    #[nwg_layout_item(layout: device_layout, col: 0, row: 1)]
+    device_notes_label: nwg::Label,
+
+    #[nwg_control(parent: device_window, text: "")]
+    #[nwg_layout_item(layout: device_layout, col: 1, row: 1, col_span: 2)]
+    device_notes_input: nwg::TextInput,
+
+    #[nwg_control(parent: device_window, text: "Save")]
+    #[nwg_layout_item(layout: device_layout, col: 1, row: 2)]
+    #[nwg_events( OnButtonClick: [RageScannerApp::save_device_note] )]
+    device_save_btn: nwg::Button,
+
+    #[nwg_control(parent: device_window, text: "Cancel")]
+    #[nwg_layout_item(layout: device_layout, col: 2, row: 2)]
+    #[nwg_events( OnButtonClick: [RageScannerApp::hide_device_window] )]
+    device_cancel_btn: nwg::Button,
+
+    // History search window, hidden until "History..." is picked
+    #[nwg_control(size: (620, 320), position: (340, 340), title: "Search History", flags: "WINDOW")]
+    history_window: nwg::Window,
+
+    #[nwg_layout(parent: history_window, spacing: 3)]
+    history_layout: nwg::GridLayout,
+
+    #[nwg_control(parent: history_window, text: "", placeholder_text: Some("MAC, IP or hostname"))]
+    #[nwg_layout_item(layout: history_layout, col: 0, row: 0, col_span: 4)]
+    history_input: nwg::TextInput,
+
+    #[nwg_control(parent: history_window, text: "Search")]
+    #[nwg_layout_item(layout: history_layout, col: 4, row: 0)]
+    #[nwg_events( OnButtonClick: [RageScannerApp::search_history] )]
+    history_search_btn: nwg::Button,
+
+    #[nwg_control(parent: history_window, list_style: nwg::ListViewStyle::Detailed)]
+    #[nwg_layout_item(layout: history_layout, col: 0, row: 1, col_span: 5, row_span: 7)]
+    history_list: nwg::ListView,
+
+    // Log viewer, hidden until "Log Viewer" is picked
+    #[nwg_control(size: (640, 400), position: (320, 320), title: "Log Viewer", flags: "WINDOW|RESIZABLE")]
+    log_window: nwg::Window,
+
+    #[nwg_layout(parent: log_window, spacing: 3)]
+    log_layout: nwg::GridLayout,
+
+    #[nwg_control(parent: log_window, text: "", readonly: true, flags: "VISIBLE|VSCROLL|HSCROLL")]
+    #[nwg_layout_item(layout: log_layout, col: 0, row: 0, col_span: 5, row_span: 9)]
+    log_text: nwg::TextBox,
+
+    #[nwg_control(parent: log_window, text: "Refresh")]
+    #[nwg_layout_item(layout: log_layout, col: 4, row: 9)]
+    #[nwg_events( OnButtonClick: [RageScannerApp::refresh_log] )]
+    log_refresh_btn: nwg::Button,
+
+    // App State
+    cmd_tx: Option<TokioSender<BridgeMessage>>,
+    ui_rx: Option<Arc<crossbeam_channel::Receiver<BridgeMessage>>>,
+    /// Set by the pump thread when it raises `ui_notice`, cleared when the
+    /// notice is handled, so a burst of messages queues a single notice.
+    notice_pending: Arc<AtomicBool>,
+    scan_in_progress: Arc<AtomicBool>,
+    /// Whether the scanner reported the running scan as paused.
+    scan_paused: Cell<bool>,
+    /// Results in the order they last reported.
+    scan_results: RefCell<Vec<ScanResult>>,
+    /// How the list is ordered: by IP, or newest response on top.
+    result_order: Cell<ResultOrder>,
+    /// Column the list is sorted by and whether descending; overrides
+    /// `result_order` until the order button is pressed.
+    sort_column: Cell<Option<(usize, bool)>>,
+    scan_range: Cell<Option<(Ipv4Addr, Ipv4Addr)>>,
+    last_autosave: Cell<Option<Instant>>,
+    /// Start the scan again once the stop requested after a sleep lands.
+    restart_after_stop: Cell<bool>,
+    /// What the running scan targets and when it started, for the history log.
+    scan_target: RefCell<String>,
+    scan_started: Cell<Option<SystemTime>>,
+    /// Label of the running scan, for autosaves and the history log.
+    scan_label: RefCell<Option<ScanLabel>>,
+    /// Totals reported just before the scan ends, for the final status text.
+    scan_summary: Cell<Option<ScanSummary>>,
+    /// Discovery and port-scan progress of the running scan.
+    scan_phase: Cell<Option<PhaseProgress>>,
+    /// Per-range progress of a scan of several ranges.
+    range_progress: RefCell<Vec<RangeProgress>>,
+    /// Percentage probed by each running full port scan, shown in the
+    /// host's Status cell.
+    host_scans: RefCell<HashMap<Ipv4Addr, u8>>,
+    /// Hosts with a [`BridgeMessage::SecurityAlert`], flagged in their
+    /// Status cell until the results are cleared.
+    security_alerts: RefCell<HashSet<Ipv4Addr>>,
+    /// Preferences shared with the TUI; the range is remembered on every scan.
+    settings: RefCell<Settings>,
+    /// Where `settings` are saved; `None` never writes them.
+    settings_path: Option<PathBuf>,
+    /// MAC of the device open in the naming window.
+    device_note_mac: RefCell<Option<String>>,
+}
+
+impl RageScannerApp {
+    fn init_list_view(&self) {
+        self.list_view.insert_column("Status");
+        self.list_view.insert_column("Hostname");
+        self.list_view.insert_column("IP Address");
+        self.list_view.insert_column("MAC Address");
+        self.list_view.insert_column("Vendor");
+        self.list_view.insert_column("Open Ports");
+        self.list_view.insert_column("Latency");
+        self.list_view.insert_column("OS");
+        self.list_view.insert_column("Web");
+        self.list_view.insert_column("Type");
+
+        self.list_view.set_headers_enabled(true);
+        self.list_view.set_column_width(0, 80);
+        self.list_view.set_column_width(1, 120);
+        self.list_view.set_column_width(2, 100);
+        self.list_view.set_column_width(3, 120);
+        self.list_view.set_column_width(4, 120);
+        self.list_view.set_column_width(5, 120);
+        self.list_view.set_column_width(6, 110);
+        self.list_view.set_column_width(7, 140);
+        self.list_view.set_column_width(8, 160);
+
+        for (column, width) in [
+            ("Seen", 150),
+            ("IP Address", 100),
+            ("MAC Address", 120),
+            ("Hostname", 120),
+            ("Vendor", 120),
+            ("Scan", 50),
+        ] {
+            let index = self.history_list.column_len();
+            self.history_list.insert_column(column);
+            self.history_list.set_column_width(index, width);
+        }
+        self.history_list.set_headers_enabled(true);
+    }
+
+    fn validate_range_input(&self) {
+        self.check_range_fields();
+    }
+
+    /// Shows what is wrong with the Start / End fields under them, so a
+    /// malformed range never reaches the bridge. Returns whether they are valid.
+    fn check_range_fields(&self) -> bool {
+        match Bridge::check_range_input(&self.start_ip_input.text(), &self.end_ip_input.text()) {
+            Ok(()) => {
+                self.input_error_label.set_text("");
+                true
+            }
+            Err(e) => {
+                self.input_error_label.set_text(&format!("⚠ {}", e));
+                false
+            }
+        }
+    }
+
+    fn start_scan(&self) {
+        if self.scan_in_progress.load(Ordering::SeqCst) {
+            return;
+        }
+
+        if !self.check_range_fields() {
+            self.start_ip_input.set_focus();
+            return;
+        }
+        let start = Bridge::normalize_input(&self.start_ip_input.text());
+        let end = Bridge::normalize_input(&self.end_ip_input.text());
+
+        // Comma-separated ranges or CIDR blocks in the Start IP field are
+        // scanned together; the End field is ignored.
+        if start.trim().parse::<Ipv4Addr>().is_err() && ScanTargets::parse(&start).is_ok() {
+            self.scan_results.borrow_mut().clear();
+            self.scan_range.set(None);
+            self.clear_notice.sender().notice();
+            self.remember_range(&start);
+            self.send_scan_command(BridgeMessage::StartScan(start));
+            return;
+        }
+
+        // Pasted host lists land in the Start IP field; scan every address found.
+        if start.trim().parse::<Ipv4Addr>().is_err() {
+            let targets = Bridge::extract_targets(&start);
+            if !targets.is_empty() {
+                self.remember_range(&start);
+                self.start_target_list(targets);
+                return;
+            }
+        }
+
+        let range = format!("{}-{}", start.trim(), end.trim());
+
+        // Clear previous results buffer
+        self.scan_results.borrow_mut().clear();
+        self.scan_range.set(Bridge::parse_range(&range).ok());
+        self.last_autosave.set(None);
+
+        self.clear_notice.sender().notice();
+
+        self.remember_range(&range);
+        self.send_scan_command(BridgeMessage::StartScan(range));
+    }
+
+    /// Adopts saved settings: restores the last range into the Start/End
+    /// fields and tunes later scans with them.
+    fn apply_settings(&self, settings: Settings) {
+        if let Some(range) = settings.last_range.as_deref() {
+            match Bridge::parse_range(range) {
+                Ok((start, end)) => {
+                    self.start_ip_input.set_text(&start.to_string());
+                    self.end_ip_input.set_text(&end.to_string());
+                }
+                // Several ranges or a host list live in the Start field alone.
+                Err(_) => {
+                    self.start_ip_input.set_text(range);
+                    self.end_ip_input.set_text("");
+                }
+            }
+        }
+        self.send_command(BridgeMessage::SetScanConfig(Box::new(
+            settings.scan_config(),
+        )));
+        *self.settings.borrow_mut() = settings;
+    }
+
+    /// Records the range being scanned and saves the settings.
+    fn remember_range(&self, range: &str) {
+        self.settings.borrow_mut().last_range = Some(range.to_string());
+        self.persist_settings();
+    }
+
+    fn persist_settings(&self) {
+        if let Some(path) = &self.settings_path
+            && let Err(e) = self.settings.borrow().save(path)
+        {
+            error!("{}", e);
+            self.status_bar
+                .set_text(0, &format!("Settings not saved: {}", e));
+        }
+    }
+
+    /// Opens the settings window filled in with the current settings.
+    fn show_settings(&self) {
+        let settings = self.settings.borrow();
+        self.ping_timeout_input
+            .set_text(&settings.ping_timeout_ms.to_string());
+        self.port_timeout_input.set_text(
+            &settings
+                .port_timeout_ms
+                .map(|ms| ms.to_string())
+                .unwrap_or_default(),
+        );
+        self.concurrency_input
+            .set_text(&settings.concurrency.to_string());
+        let ports: Vec<String> = settings.ports.iter().map(u16::to_string).collect();
+        self.ports_input.set_text(&ports.join(","));
+        self.resolve_hostnames_check
+            .set_check_state(check_state(settings.resolve_hostnames));
+        self.resolve_vendors_check
+            .set_check_state(check_state(settings.resolve_vendors));
+        self.settings_window.set_visible(true);
+        self.settings_window.set_focus();
+    }
+
+    fn show_history_search(&self) {
+        self.history_window.set_visible(true);
+        self.history_input.set_focus();
+    }
+
+    /// Lists every stored sighting matching the history search box, newest first.
+    #[cfg(feature = "history")]
+    fn search_history(&self) {
+        use ragescanner::history::{DEFAULT_HISTORY_PATH, History, HistoryQuery};
+        use std::time::UNIX_EPOCH;
+
+        let Some(query) = HistoryQuery::parse(&self.history_input.text()) else {
+            return;
+        };
+        let sightings = History::open(Path::new(DEFAULT_HISTORY_PATH))
+            .and_then(|history| history.search(&query));
+        let sightings = match sightings {
+            Ok(sightings) => sightings,
+            Err(e) => {
+                error!("History search failed: {}", e);
+                nwg::modal_error_message(&self.history_window, "Search History", &e.to_string());
+                return;
+            }
+        };
+
+        self.history_list.clear();
+        for (row, sighting) in sightings.into_iter().enumerate() {
+            let res = sighting.result;
+            let seen = report::format_utc(UNIX_EPOCH + Duration::from_secs(sighting.seen_at));
+            let cells = [
+                seen,
+                res.ip.to_string(),
+                res.mac.unwrap_or_default(),
+                res.hostname.unwrap_or_default(),
+                res.vendor.unwrap_or_default(),
+                sighting.scan_id.to_string(),
+            ];
+            for (column, text) in cells.into_iter().enumerate() {
+                let item = nwg::InsertListViewItem {
+                    index: Some(row as i32),
+                    column_index: column as i32,
+                    text: Some(text),
+                    image: None,
+                };
+                if column == 0 {
+                    self.history_list.insert_item(item);
+                } else {
+                    self.history_list.update_item(row, item);
+                }
+            }
+        }
+        if self.history_list.len() == 0 {
+            nwg::modal_info_message(
+                &self.history_window,
+                "Search History",
+                "No stored scan saw this device online.",
+            );
+        }
+    }
+
+    #[cfg(not(feature = "history"))]
+    fn search_history(&self) {
+        nwg::modal_info_message(
+            &self.history_window,
+            "Search History",
+            "This build does not keep scan history.",
+        );
+    }
+
+    fn hide_settings(&self) {
+        self.settings_window.set_visible(false);
+    }
+
+    /// Applies and saves the settings window, or explains what is wrong with it.
+    fn save_settings(&self) {
+        let settings = match self.read_settings() {
+            Ok(settings) => settings,
+            Err(e) => {
+                nwg::modal_error_message(&self.settings_window, "Settings", &e);
+                return;
+            }
+        };
+        self.send_command(BridgeMessage::SetScanConfig(Box::new(
+            settings.scan_config(),
+        )));
+        *self.settings.borrow_mut() = settings;
+        self.persist_settings();
+        self.hide_settings();
+    }
+
+    /// Settings as entered in the settings window.
+    fn read_settings(&self) -> Result<Settings, String> {
+        let number = |input: &nwg::TextInput, name: &str| {
+            input
+                .text()
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| format!("{} must be a whole number.", name))
+        };
+        let port_timeout = self.port_timeout_input.text();
+        let ports = self.ports_input.text();
+        let settings = Settings {
+            last_range: self.settings.borrow().last_range.clone(),
+            ping_timeout_ms: number(&self.ping_timeout_input, "Ping timeout")?,
+            port_timeout_ms: match port_timeout.trim() {
+                "" => None,
+                _ => Some(number(&self.port_timeout_input, "Port timeout")?),
+            },
+            concurrency: number(&self.concurrency_input, "Concurrency")? as usize,
+            ports: match ports.trim() {
+                "" => Vec::new(),
+                text => Bridge::parse_ports(text)?,
+            },
+            resolve_hostnames: self.resolve_hostnames_check.check_state()
+                == nwg::CheckBoxState::Checked,
+            resolve_vendors: self.resolve_vendors_check.check_state()
+                == nwg::CheckBoxState::Checked,
+            // Kept from the file: wake groups, profile and TUI theme.
+            ..self.settings.borrow().clone()
+        };
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// First run: asks which detected subnet to scan by default, one network
+    /// at a time, and saves the answer so the question is not asked again.
+    /// Nothing is saved when no network was detected.
+    fn offer_detected_subnets(&self) {
+        let candidates = match net::interfaces() {
+            Ok(list) => net::candidate_interfaces(&list),
+            Err(e) => {
+                log::warn!("Failed to list network interfaces: {}", e);
+                return;
+            }
+        };
+        if candidates.is_empty() {
+            return;
+        }
+
+        let current = format!(
+            "{}-{}",
+            self.start_ip_input.text(),
+            self.end_ip_input.text()
+        );
+        let mut chosen = None;
+        for (i, iface) in candidates.iter().enumerate() {
+            let next = if i + 1 < candidates.len() {
+                "show the next network"
+            } else {
+                "keep the current range"
+            };
+            let content = format!(
+                "Which network should RageScanner scan by default? ({} of {})\n\n{}: {}/{}\nRange: {}\n\nYes: use this network.\nNo: {}.\nCancel: keep {}.",
+                i + 1,
+                candidates.len(),
+                iface.name,
+                iface.address,
+                iface.prefix_len,
+                iface.range_string(),
+                next,
+                current
+            );
+            let choice = nwg::modal_message(
+                &self.window,
+                &nwg::MessageParams {
+                    title: "Default network",
+                    content: &content,
+                    buttons: nwg::MessageButtons::YesNoCancel,
+                    icons: nwg::MessageIcons::Question,
+                },
+            );
+            match choice {
+                nwg::MessageChoice::Yes => {
+                    chosen = Some(iface);
+                    break;
+                }
+                nwg::MessageChoice::No => {}
+                _ => break,
+            }
+        }
+
+        match chosen {
+            Some(iface) => {
+                log::info!(
+                    "Default range set to the subnet of {} ({}/{})",
+                    iface.name,
+                    iface.address,
+                    iface.prefix_len
+                );
+                let (start, end) = iface.scan_range();
+                self.start_ip_input.set_text(&start.to_string());
+                self.end_ip_input.set_text(&end.to_string());
+                self.remember_range(&iface.range_string());
+            }
+            None => self.persist_settings(),
+        }
+    }
+
+    /// Fills the range from the active network interface and scans it.
+    fn scan_my_subnet(&self) {
+        if self.scan_in_progress.load(Ordering::SeqCst) {
+            return;
+        }
+        let Some(iface) = net::active_interface() else {
+            nwg::modal_error_message(
+                &self.window,
+                "Scan My Subnet",
+                "No active network adapter with an IPv4 address was found.",
+            );
+            return;
+        };
+
+        log::info!(
+            "Scanning subnet of {} ({}/{})",
+            iface.name,
+            iface.address,
+            iface.prefix_len
+        );
+        let (start, end) = iface.scan_range();
+        self.start_ip_input.set_text(&start.to_string());
+        self.end_ip_input.set_text(&end.to_string());
+        self.start_scan();
+    }
+
+    /// Re-scans the Start / End range every [`MONITOR_INTERVAL`] until
+    /// stopped, reporting devices that appear, disappear or change.
+    fn start_monitor(&self) {
+        if self.scan_in_progress.load(Ordering::SeqCst) || !self.check_range_fields() {
+            return;
+        }
+        let start = Bridge::normalize_input(&self.start_ip_input.text());
+        let end = Bridge::normalize_input(&self.end_ip_input.text());
+        let range = format!("{}-{}", start.trim(), end.trim());
+
+        self.scan_results.borrow_mut().clear();
+        self.security_alerts.borrow_mut().clear();
+        self.scan_range.set(None);
+        self.clear_notice.sender().notice();
+        self.remember_range(&range);
+        self.send_scan_command(BridgeMessage::StartMonitor {
+            interval: MONITOR_INTERVAL,
+            range,
+        });
+        self.status_bar.set_text(0, "Monitoring...");
+    }
+
+    /// Hides the minimized window; the tray icon brings it back.
+    fn minimize_to_tray(&self) {
+        self.window.set_visible(false);
+    }
+
+    fn restore_from_tray(&self) {
+        self.window.set_visible(true);
+        self.window.restore();
+        self.window.set_focus();
+    }
+
+    fn show_tray_menu(&self) {
+        let busy = self.scan_in_progress.load(Ordering::SeqCst);
+        self.tray_scan_item.set_enabled(!busy);
+        let (x, y) = nwg::GlobalCursor::position();
+        self.tray_menu.popup(x, y);
+    }
+
+    /// Shows a balloon from the tray icon while the window is hidden there;
+    /// with the window open the status bar says the same.
+    fn notify_from_tray(&self, title: &str, text: &str, flags: nwg::TrayNotificationFlags) {
+        if !self.window.visible() {
+            self.tray.show(text, Some(title), Some(flags), None);
+        }
+    }
+
+    /// Asks whether to stop the running scan, since its remaining hosts
+    /// would only be reported as not scanned, and stops it if so.
+    fn confirm_stop_scan(&self) {
+        if !self.scan_in_progress.load(Ordering::SeqCst) {
+            return;
+        }
+        let choice = nwg::modal_message(
+            &self.window,
+            &nwg::MessageParams {
+                title: "Stop scan",
+                content: "Stop the running scan?\n\nHosts not probed yet will be listed as Not scanned.",
+                buttons: nwg::MessageButtons::YesNo,
+                icons: nwg::MessageIcons::Question,
+            },
+        );
+        if matches!(choice, nwg::MessageChoice::Yes) {
+            self.stop_scan();
+        }
+    }
+
+    /// Asks the bridge to cancel the running scan.
+    ///
+    /// The UI is only reset once the bridge confirms with `ScanCancelled`.
+    fn stop_scan(&self) {
+        if !self.scan_in_progress.load(Ordering::SeqCst) {
+            return;
+        }
+        if let Some(tx) = &self.cmd_tx {
+            self.stop_btn.set_enabled(false);
+            self.status_bar.set_text(0, "Stopping...");
+            if let Err(e) = tx.blocking_send(BridgeMessage::StopScan) {
+                error!("Failed to send StopScan command: {}", e);
+            }
+        }
+    }
+
+    /// Asks the bridge to pause or resume the running scan.
+    ///
+    /// The button label only flips once the scanner confirms with `ScanState`.
+    fn toggle_pause(&self) {
+        if !self.scan_in_progress.load(Ordering::SeqCst) {
+            return;
+        }
+        self.send_command(if self.scan_paused.get() {
+            BridgeMessage::ResumeScan
+        } else {
+            BridgeMessage::PauseScan
+        });
+    }
+
+    /// Asks whether to resume the scan paused by a system sleep or to start
+    /// it over, since hosts may have come and gone while the machine slept.
+    fn offer_resume_after_sleep(&self) {
+        if !self.scan_in_progress.load(Ordering::SeqCst) {
+            return;
+        }
+        let choice = nwg::modal_message(
+            &self.window,
+            &nwg::MessageParams {
+                title: "Resume scan",
+                content: "The computer slept during the scan.\n\nResume where it left off? Choose No to restart the scan.",
+                buttons: nwg::MessageButtons::YesNo,
+                icons: nwg::MessageIcons::Question,
+            },
+        );
+        if matches!(choice, nwg::MessageChoice::Yes) {
+            self.send_command(BridgeMessage::ResumeScan);
+        } else {
+            self.restart_after_stop.set(true);
+            self.stop_scan();
+        }
+    }
+
+    /// Confirms a pasted target list with a preview, then scans it.
+    fn start_target_list(&self, targets: Vec<Ipv4Addr>) {
+        let content = format!("Scan {}?", Bridge::describe_targets(&targets));
+        let choice = nwg::modal_message(
+            &self.window,
+            &nwg::MessageParams {
+                title: "Scan pasted addresses",
+                content: &content,
+                buttons: nwg::MessageButtons::OkCancel,
+                icons: nwg::MessageIcons::Question,
+            },
+        );
+        if !matches!(choice, nwg::MessageChoice::Ok) {
+            return;
+        }
+
+        self.scan_results.borrow_mut().clear();
+        // Sessions resume by range, which a sparse list cannot express.
+        self.scan_range.set(None);
+        self.clear_notice.sender().notice();
+        self.send_scan_command(BridgeMessage::StartScanTargets(targets));
+    }
+
+    /// Sends a settings command to the bridge, logging failures.
+    fn send_command(&self, msg: BridgeMessage) {
+        if let Some(tx) = &self.cmd_tx
+            && let Err(e) = tx.blocking_send(msg)
+        {
+            error!("Failed to send command: {}", e);
+        }
+    }
+
+    fn send_scan_command(&self, msg: BridgeMessage) {
+        *self.scan_target.borrow_mut() = match &msg {
+            BridgeMessage::StartScan(range) => range.clone(),
+            BridgeMessage::StartScanRange(start, end) => format!("{}-{}", start, end),
+            BridgeMessage::StartScanTargets(targets) => Bridge::describe_targets(targets),
+            BridgeMessage::StartMonitor { range, .. } => range.clone(),
+            _ => String::new(),
+        };
+        self.scan_started.set(Some(SystemTime::now()));
+
+        if let Some(tx) = &self.cmd_tx {
+            let tx = tx.clone();
+            self.scan_in_progress.store(true, Ordering::SeqCst);
+            self.scan_btn.set_enabled(false);
+            self.stop_btn.set_enabled(true);
+            self.set_paused(false);
+            self.pause_btn.set_enabled(true);
+            self.progress_bar.set_pos(0);
+            self.range_progress.borrow_mut().clear();
+            self.range_label.set_text("");
+            self.scan_summary.set(None);
+            self.scan_phase.set(None);
+            self.status_bar.set_text(0, "Scanning...");
+
+            // Use blocking_send to bridge sync -> async safely.
+            // We handle the error by logging it, ensuring the app doesn't panic if the channel is closed.
+            if let Err(e) = tx.blocking_send(msg) {
+                error!("Failed to send scan command: {}", e);
+                nwg::modal_error_message(
+                    &self.window,
+                    "Internal Error",
+                    &format!("Failed to start scan: {}", e),
+                );
+            }
+        }
+    }
+
+    /// Loads an Angry IP Scanner / Advanced IP Scanner export into the results list.
+    fn import_results(&self) {
+        if self.scan_in_progress.load(Ordering::SeqCst)
+            || !self.import_dialog.run(Some(&self.window))
+        {
+            return;
+        }
+        let path = match self.import_dialog.get_selected_item() {
+            Ok(p) => p,
+            Err(e) => {
+                error!("Import dialog failed: {}", e);
+                return;
+            }
+        };
+
+        match import::import_file(Path::new(&path)) {
+            Ok(session) => {
+                self.list_view.clear();
+                self.append_rows(session.results.iter().cloned());
+                self.start_ip_input.set_text(&session.start.to_string());
+                self.end_ip_input.set_text(&session.end.to_string());
+                self.status_bar
+                    .set_text(0, &format!("Imported {} hosts", session.results.len()));
+                *self.scan_results.borrow_mut() = session.results;
+                self.scan_summary.set(None);
+            }
+            Err(e) => {
+                nwg::modal_error_message(&self.window, "Import Failed", &e.to_string());
+            }
+        }
+    }
+
+    /// Saves the selected rows, or every row if none is selected, as JSON,
+    /// CSV or a text table depending on the chosen file's extension.
+    fn export_results(&self) {
+        if !self.export_dialog.run(Some(&self.window)) {
+            return;
+        }
+        let path = match self.export_dialog.get_selected_item() {
+            Ok(p) => p,
+            Err(e) => {
+                error!("Export dialog failed: {}", e);
+                return;
+            }
+        };
+
+        let rows = export::scoped(
+            &self.scan_results.borrow(),
+            ExportScope::Selected,
+            |r| self.is_visible(r),
+            &self.selected_ips(),
+        );
+        match export::write_file(Path::new(&path), &rows, self.scan_label.borrow().as_ref()) {
+            Ok(()) => self
+                .status_bar
+                .set_text(0, &format!("Exported {} hosts", rows.len())),
+            Err(e) => {
+                nwg::modal_error_message(&self.window, "Export Failed", &e.to_string());
+            }
+        }
+    }
+
+    /// Clears the results for a fresh scan and puts the cursor in the Start field.
+    fn new_scan(&self) {
+        if self.scan_in_progress.load(Ordering::SeqCst) {
+            return;
+        }
+        self.scan_results.borrow_mut().clear();
+        self.list_view.clear();
+        self.scan_range.set(None);
+        self.scan_summary.set(None);
+        *self.scan_label.borrow_mut() = None;
+        self.host_scans.borrow_mut().clear();
+        self.security_alerts.borrow_mut().clear();
+        self.progress_bar.set_pos(0);
+        self.range_label.set_text("");
+        self.status_bar.set_text(0, "Ready");
+        self.start_ip_input.set_focus();
+    }
+
+    /// Shows the results of a saved session, without resuming it.
+    fn open_session(&self) {
+        if self.scan_in_progress.load(Ordering::SeqCst)
+            || !self.open_session_dialog.run(Some(&self.window))
+        {
+            return;
+        }
+        let path = match self.open_session_dialog.get_selected_item() {
+            Ok(p) => p,
+            Err(e) => {
+                error!("Session dialog failed: {}", e);
+                return;
+            }
+        };
+
+        let session = match Session::load(Path::new(&path)) {
+            Ok(Some(session)) => session,
+            Ok(None) => return,
+            Err(e) => {
+                nwg::modal_error_message(&self.window, "Open Failed", &e.to_string());
+                return;
+            }
+        };
+        self.start_ip_input.set_text(&session.start.to_string());
+        self.end_ip_input.set_text(&session.end.to_string());
+        self.status_bar.set_text(
+            0,
+            &format!("Opened session of {} hosts", session.results.len()),
+        );
+        *self.scan_results.borrow_mut() = session.results;
+        *self.scan_label.borrow_mut() = session.label;
+        self.scan_summary.set(None);
+        self.show_results();
+    }
+
+    /// Saves the results with their range and label as a session, which
+    /// "Open Session..." shows again. Without a known range, the session
+    /// spans the lowest to the highest result.
+    fn save_session(&self) {
+        let results = self.scan_results.borrow().clone();
+        let range = self.scan_range.get().or_else(|| {
+            let ips = results.iter().map(|r| r.ip);
+            Some((ips.clone().min()?, ips.max()?))
+        });
+        let Some((start, end)) = range else {
+            nwg::modal_info_message(
+                &self.window,
+                "Save Session",
+                "There are no results to save.",
+            );
+            return;
+        };
+        if !self.save_session_dialog.run(Some(&self.window)) {
+            return;
+        }
+        let mut path = match self.save_session_dialog.get_selected_item() {
+            Ok(p) => PathBuf::from(p),
+            Err(e) => {
+                error!("Session dialog failed: {}", e);
+                return;
+            }
+        };
+        if path.extension().is_none() {
+            path.set_extension("json");
+        }
+
+        let mut session = Session::new(start, end);
+        session.label = self.scan_label.borrow().clone();
+        session.results = results;
+        match session.save(&path) {
+            Ok(()) => self.status_bar.set_text(
+                0,
+                &format!("Saved session of {} hosts", session.results.len()),
+            ),
+            Err(e) => {
+                nwg::modal_error_message(&self.window, "Save Failed", &e.to_string());
+            }
+        }
+    }
+
+    /// Offers to restore a session left behind by an interrupted scan.
+    fn offer_recovery(&self) {
+        let path = Path::new(DEFAULT_SESSION_PATH);
+        let session = match Session::load(path) {
+            Ok(Some(s)) => s,
+            Ok(None) => return,
+            Err(e) => {
+                error!("Discarding unreadable session: {}", e);
+                let _ = Session::discard(path);
+                return;
+            }
+        };
+
+        let content = format!(
+            "A scan of {} - {} was interrupted with {} results saved.\n\nRecover the last session and resume scanning?",
+            session.start,
+            session.end,
+            session.results.len()
+        );
+        let choice = nwg::modal_message(
+            &self.window,
+            &nwg::MessageParams {
+                title: "Recover last session",
+                content: &content,
+                buttons: nwg::MessageButtons::YesNo,
+                icons: nwg::MessageIcons::Question,
+            },
+        );
+        if !matches!(choice, nwg::MessageChoice::Yes) {
+            let _ = Session::discard(path);
+            return;
+        }
+
+        self.start_ip_input.set_text(&session.start.to_string());
+        self.end_ip_input.set_text(&session.end.to_string());
+        self.append_rows(session.results.iter().cloned());
+
+        let next = session.next_ip();
+        self.scan_range.set(Some((session.start, session.end)));
+        *self.scan_results.borrow_mut() = session.results;
+
+        match next {
+            Some(next) => {
+                // Resume under the interrupted scan's label, for this scan only.
+                self.send_command(BridgeMessage::SetScanLabel(session.label));
+                self.send_scan_command(BridgeMessage::StartScanRange(next, session.end));
+                self.send_command(BridgeMessage::SetScanLabel(None));
+            }
+            None => {
+                let _ = Session::discard(path);
+                self.status_bar.set_text(0, "Recovered last session");
+            }
+        }
+    }
+
+    /// Saves the in-progress results, at most once per [`AUTOSAVE_INTERVAL`].
+    fn autosave(&self) {
+        let Some((start, end)) = self.scan_range.get() else {
+            return;
+        };
+        if self
+            .last_autosave
+            .get()
+            .is_some_and(|t| t.elapsed() < AUTOSAVE_INTERVAL)
+        {
+            return;
+        }
+
+        let mut session = Session::new(start, end);
+        session.label = self.scan_label.borrow().clone();
+        session.results = self.scan_results.borrow().clone();
+        if let Err(e) = session.save(Path::new(DEFAULT_SESSION_PATH)) {
+            error!("Session autosave failed: {}", e);
+        }
+        self.last_autosave.set(Some(Instant::now()));
+    }
+
+    /// Drops the autosaved session once a scan has ended without crashing.
+    fn end_session(&self) {
+        self.scan_range.set(None);
+        if let Err(e) = Session::discard(Path::new(DEFAULT_SESSION_PATH)) {
+            error!("{}", e);
+        }
+    }
+
+    fn clear_results(&self) {
+        self.list_view.clear();
+    }
+
+    fn handle_ui_message(&self) {
+        self.notice_pending.store(false, Ordering::SeqCst);
+        if let Some(rx) = &self.ui_rx {
+            let mut count = 0;
+            // Rows arriving in one notice are added under a single redraw.
+            let mut pending: Vec<ScanResult> = Vec::new();
+            while let Ok(msg) = rx.try_recv() {
+                // Other messages may read or rebuild the list, so flush rows first.
+                if !matches!(msg, BridgeMessage::ScanUpdate(_)) {
+                    self.append_rows(pending.drain(..));
+                }
+                match msg {
+                    BridgeMessage::ScanUpdate(mut res) => {
+                        // Buffer the result (a resumed session or a host scan may revisit an IP)
+                        let known = {
+                            let mut results = self.scan_results.borrow_mut();
+                            let previous = results.iter().position(|r| r.ip == res.ip);
+                            if let Some(i) = previous {
+                                res.merge_seen(&results.remove(i));
+                            }
+                            results.push(res.clone());
+                            previous.is_some()
+                        };
+                        if known {
+                            self.append_rows(pending.drain(..));
+                            self.replace_row(res);
+                        } else {
+                            pending.push(res);
+                        }
+                    }
+                    BridgeMessage::PortFound { ip, port } => {
+                        // Shown right away; the host's final result replaces the row.
+                        let res = {
+                            let mut results = self.scan_results.borrow_mut();
+                            let i = match results.iter().position(|r| r.ip == ip) {
+                                Some(i) => i,
+                                None => {
+                                    results.push(ScanResult::new(ip));
+                                    results.len() - 1
+                                }
+                            };
+                            let res = &mut results[i];
+                            if let Err(at) = res.open_ports.binary_search_by_key(&port, |p| p.port)
+                            {
+                                res.open_ports.insert(at, PortInfo::new(port));
+                            }
+                            res.status = ScanStatus::Online;
+                            res.clone()
+                        };
+                        self.replace_row(res);
+                    }
+                    BridgeMessage::ScanStarted(label) => {
+                        if let Some(label) = &label {
+                            self.status_bar
+                                .set_text(0, &format!("Scanning: {}...", label));
+                        }
+                        *self.scan_label.borrow_mut() = label;
+                    }
+                    BridgeMessage::ScanComplete => {
+                        self.finish_scan("Scan Complete");
+                        self.progress_bar.set_pos(100);
+                        self.save_history();
+                        self.notify_from_tray(
+                            "Scan complete",
+                            &self.status_bar.text(0),
+                            nwg::TrayNotificationFlags::INFO_ICON,
+                        );
+                    }
+                    BridgeMessage::ScanCancelled => {
+                        let skipped = self
+                            .scan_results
+                            .borrow()
+                            .iter()
+                            .filter(|r| r.status == ScanStatus::NotScanned)
+                            .count();
+                        // Say where it stopped; the bar alone reads like a result.
+                        let mut status = match self.scan_phase.get() {
+                            Some(phase) => format!("Scan Stopped at {}", phase),
+                            None => "Scan Stopped".to_string(),
+                        };
+                        if skipped > 0 {
+                            status.push_str(&format!(" - {} targets not scanned", skipped));
+                        }
+                        self.finish_scan(&status);
+                        if self.restart_after_stop.replace(false) {
+                            self.start_scan();
+                        }
+                    }
+                    BridgeMessage::Progress(p) => {
+                        self.progress_bar.set_pos(p as u32);
+                    }
+                    BridgeMessage::RangeProgress(range) => {
+                        let mut ranges = self.range_progress.borrow_mut();
+                        match ranges.iter_mut().find(|r| r.index == range.index) {
+                            Some(known) => *known = range,
+                            None => ranges.push(range),
+                        }
+                        self.range_label
+                            .set_text(&progress_text(self.scan_phase.get(), &ranges));
+                    }
+                    BridgeMessage::Phase(phase) => {
+                        self.scan_phase.set(Some(phase));
+                        let ranges = self.range_progress.borrow();
+                        self.range_label
+                            .set_text(&progress_text(Some(phase), &ranges));
+                    }
+                    BridgeMessage::ScanSummary(summary) => self.scan_summary.set(Some(summary)),
+                    BridgeMessage::ScanStats(stats) => {
+                        let text = match self.scan_label.borrow().as_ref() {
+                            Some(label) => format!("Scanning: {} - {}", label, stats),
+                            None => format!("Scanning - {}", stats),
+                        };
+                        self.status_bar.set_text(0, &text);
+                    }
+                    BridgeMessage::ScanPaused(reason) => {
+                        self.status_bar.set_text(0, &format!("Paused: {}", reason));
+                    }
+                    BridgeMessage::ScanResumed => {
+                        self.status_bar.set_text(0, "Scanning...");
+                    }
+                    BridgeMessage::SystemSuspended => {
+                        // Checkpoint now: the machine may not wake up.
+                        self.last_autosave.set(None);
+                        self.autosave();
+                        self.status_bar
+                            .set_text(0, "Paused: the computer is going to sleep");
+                    }
+                    BridgeMessage::SystemResumed => self.offer_resume_after_sleep(),
+                    BridgeMessage::ConfigReloaded(settings) => {
+                        let mut current = self.settings.borrow_mut();
+                        *current = Settings {
+                            last_range: current.last_range.take(),
+                            ..*settings
+                        };
+                        self.status_bar.set_text(0, "Settings reloaded");
+                    }
+                    BridgeMessage::ConfigReloadFailed(e) => {
+                        self.status_bar
+                            .set_text(0, &format!("Settings not reloaded: {}", e));
+                    }
+                    BridgeMessage::DeviceNoteSaved { mac, note } => {
+                        devices::annotate(&mut self.scan_results.borrow_mut());
+                        self.show_results();
+                        let text = match note {
+                            Some(note) => format!("{} is now named {}", mac, note.name),
+                            None => format!("Forgot the name of {}", mac),
+                        };
+                        self.status_bar.set_text(0, &text);
+                    }
+                    BridgeMessage::InventoryLoaded(count) => {
+                        inventory::annotate(&mut self.scan_results.borrow_mut());
+                        self.show_results();
+                        self.status_bar
+                            .set_text(0, &format!("Asset inventory: {} devices", count));
+                    }
+                    BridgeMessage::InterceptionSuspected(warning) => {
+                        nwg::modal_info_message(
+                            &self.window,
+                            "Possible Interception",
+                            &warning.to_string(),
+                        );
+                    }
+                    BridgeMessage::ScanState(state) => {
+                        let paused = state == ScanRunState::Paused;
+                        self.set_paused(paused);
+                        self.status_bar
+                            .set_text(0, if paused { "Paused" } else { "Scanning..." });
+                    }
+                    BridgeMessage::HostRescanned(res) => {
+                        let ip = res.ip;
+                        {
+                            let mut results = self.scan_results.borrow_mut();
+                            results.retain(|r| r.ip != ip);
+                            results.push(res);
+                        }
+                        self.show_results();
+                        self.status_bar.set_text(0, &format!("Rescanned {}", ip));
+                    }
+                    BridgeMessage::HostScanProgress(ip, p) => {
+                        self.host_scans.borrow_mut().insert(ip, p);
+                        self.refresh_row(ip);
+                    }
+                    BridgeMessage::HostScanFinished(ip) => {
+                        self.host_scans.borrow_mut().remove(&ip);
+                        self.refresh_row(ip);
+                        self.status_bar
+                            .set_text(0, &format!("Port scan of {} finished", ip));
+                    }
+                    BridgeMessage::SecurityAlert { ref host, .. } => {
+                        self.security_alerts.borrow_mut().insert(host.ip);
+                        self.refresh_row(host.ip);
+                        if let Some(text) = monitor::describe_event(&msg) {
+                            self.status_bar.set_text(0, &text);
+                            self.notify_from_tray(
+                                "Security alert",
+                                &text,
+                                nwg::TrayNotificationFlags::WARNING_ICON,
+                            );
+                        }
+                    }
+                    BridgeMessage::DeviceAppeared(_) | BridgeMessage::DeviceDisappeared(_) => {
+                        if let Some(text) = monitor::describe_event(&msg) {
+                            self.status_bar.set_text(0, &text);
+                            self.notify_from_tray(
+                                "Device change",
+                                &text,
+                                nwg::TrayNotificationFlags::INFO_ICON,
+                            );
+                        }
+                    }
+                    BridgeMessage::WakeOnLanSent(mac) => {
+                        self.status_bar
+                            .set_text(0, &format!("Wake-on-LAN packet sent to {}", mac));
+                    }
+                    BridgeMessage::WakeBatchComplete(report) => {
+                        self.status_bar.set_text(0, &report.summary());
+                        nwg::modal_info_message(&self.window, "Wake-on-LAN", &report.to_string());
+                    }
+                    BridgeMessage::WakeOnLanFailed(mac, e) => {
+                        nwg::modal_error_message(
+                            &self.window,
+                            "Wake-on-LAN",
+                            &format!("Could not wake {}: {}", mac, e),
+                        );
+                    }
+                    BridgeMessage::Error(e) => {
+                        self.end_session();
+                        self.scan_in_progress.store(false, Ordering::SeqCst);
+                        self.scan_btn.set_enabled(true);
+                        self.stop_btn.set_enabled(false);
+                        self.pause_btn.set_enabled(false);
+                        self.status_bar.set_text(0, &format!("Error: {}", e));
+                        if matches!(e, GError::PermissionDenied(_)) && !elevation::is_elevated() {
+                            self.offer_elevation(&e);
+                        } else {
+                            let message = match e.hint() {
+                                Some(hint) => format!("{}\n\n{}", e, hint),
+                                None => e.to_string(),
+                            };
+                            nwg::modal_error_message(&self.window, "Scan Error", &message);
+                        }
+                    }
+                    _ => {}
+                }
+
+                count += 1;
+                if count >= MAX_MESSAGES_PER_NOTICE {
+                    // Yield to let UI process window messages, then re-trigger
+                    self.ui_notice.sender().notice();
+                    break;
+                }
+            }
+            self.append_rows(pending.drain(..));
+
+            if self.scan_in_progress.load(Ordering::SeqCst) {
+                self.autosave();
+            }
+        }
+    }
+
+    /// Appends the finished scan to the local history database.
+    #[cfg(feature = "history")]
+    fn save_history(&self) {
+        let started = self.scan_started.get().unwrap_or_else(SystemTime::now);
+        if let Err(e) = ragescanner::history::save_completed_scan(
+            &self.scan_target.borrow(),
+            self.scan_label.borrow().as_ref(),
+            started,
+            &self.scan_results.borrow(),
+        ) {
+            error!("Failed to save scan history: {}", e);
+        }
+    }
+
+    #[cfg(not(feature = "history"))]
+    fn save_history(&self) {}
+
+    /// Records the scanner's pause state and relabels the pause button to match.
+    fn set_paused(&self, paused: bool) {
+        self.scan_paused.set(paused);
+        self.pause_btn
+            .set_text(if paused { "Resume" } else { "Pause" });
+    }
+
+    /// Resets scan controls and shows the buffered results sorted by IP,
+    /// with `status` and the scan's totals, when reported, in the status bar.
+    fn finish_scan(&self, status: &str) {
+        let status = match self.scan_summary.get() {
+            Some(summary) => format!("{} - {}", status, summary),
+            None => status.to_string(),
+        };
+        let status = status.as_str();
+        self.end_session();
+        self.scan_in_progress.store(false, Ordering::SeqCst);
+        self.scan_btn.set_enabled(true);
+        self.stop_btn.set_enabled(false);
+        self.pause_btn.set_enabled(false);
+        self.set_paused(false);
+        self.status_bar
+            .set_text(0, &format!("{} - Sorting...", status));
+
+        self.show_results();
+        self.status_bar.set_text(0, status);
+    }
+
+    /// Rebuilds the list from the buffered results that pass the filter, in
+    /// the current order.
+    fn show_results(&self) {
+        let mut results = self.scan_results.borrow().clone();
+        match self.sort_column.get() {
+            Some((column, descending)) => results.sort_by(|a, b| {
+                let order = compare_column(a, b, column).then(a.ip.cmp(&b.ip));
+                if descending { order.reverse() } else { order }
+            }),
+            None if self.result_order.get() == ResultOrder::Ip => results.sort_by_key(|r| r.ip),
+            None => {}
+        }
+        self.list_view.clear();
+        self.append_rows(results);
+    }
+
+    /// Sorts the list by the clicked column; a second click reverses it.
+    fn sort_by_column(&self, data: &nwg::EventData) {
+        let nwg::EventData::OnListViewItemIndex { column_index, .. } = data else {
+            return;
+        };
+        let descending = self.sort_column.get() == Some((*column_index, false));
+        self.sort_column.set(Some((*column_index, descending)));
+        self.show_results();
+    }
+
+    /// Keeps the View menu's check mark in step with the checkbox.
+    fn online_only_changed(&self) {
+        self.online_only_item
+            .set_checked(self.online_only.check_state() == nwg::CheckBoxState::Checked);
+        self.show_results();
+    }
+
+    /// "Online Only" in the View menu flips the checkbox under the filter.
+    fn toggle_online_only(&self) {
+        let checked = self.online_only.check_state() != nwg::CheckBoxState::Checked;
+        self.online_only.set_check_state(check_state(checked));
+        self.online_only_changed();
+    }
+
+    /// Whether `res` passes the filter box and the online-only checkbox.
+    fn is_visible(&self, res: &ScanResult) -> bool {
+        if self.online_only.check_state() == nwg::CheckBoxState::Checked
+            && res.status != ScanStatus::Online
+        {
+            return false;
+        }
+        let filter = self.filter_input.text().trim().to_lowercase();
+        filter.is_empty()
+            || res.ip.to_string().contains(&filter)
+            || [&res.hostname, &res.vendor]
+                .into_iter()
+                .flatten()
+                .any(|text| text.to_lowercase().contains(&filter))
+    }
+
+    /// Switches between IP order and newest response first, dropping any
+    /// column sort.
+    fn toggle_result_order(&self) {
+        self.sort_column.set(None);
+        self.set_result_order(self.result_order.get().toggled());
+        self.show_results();
+    }
+
+    /// Records the list order and relabels the order button to match.
+    fn set_result_order(&self, order: ResultOrder) {
+        self.result_order.set(order);
+        self.order_btn.set_text(&format!("Order: {}", order));
+    }
+
+    /// Shows the full OUI record for the double-clicked row's MAC address,
+    /// followed by the host's OS guess and what it is based on.
+    fn show_vendor_details(&self) {
+        let Some(row) = self.list_view.selected_item() else {
+            return;
+        };
+        let Some(mac) = self.list_view.item(row, 3, 64).map(|item| item.text) else {
+            return;
+        };
+        let mut message = match net::vendor_details(&mac) {
+            Some(details) => details.to_string(),
+            None => format!("No OUI record for {}.", mac),
+        };
+        let ip = self.list_view.item(row, 2, 64).map(|item| item.text);
+        if let Some(res) = self
+            .scan_results
+            .borrow()
+            .iter()
+            .find(|r| Some(r.ip.to_string()) == ip)
+        {
+            if let Some(guess) = &res.os_guess {
+                message.push_str(&format!("\n\nOS guess: {}", guess));
+            }
+            if let Some(kind) = res.device_type {
+                message.push_str(&format!("\nDevice type: {}", kind));
+            }
+            if !res.roles.is_empty() {
+                let roles: Vec<String> = res.roles.iter().map(|role| role.to_string()).collect();
+                message.push_str(&format!("\nRoles: {}", roles.join(", ")));
+            }
+            if let Some(web) = &res.web_info {
+                message.push_str(&format!("\nWeb: {}", web));
+            }
+            if let Some(smb) = &res.smb_info {
+                message.push_str(&format!("\n{}", smb));
+            }
+            for issue in &res.issues {
+                message.push_str(&format!("\nWarning: {}", issue));
+            }
+        }
+        nwg::modal_info_message(&self.window, "Vendor Details", &message);
+    }
+
+    /// The buffered result shown in the selected row.
+    fn selected_result(&self) -> Option<ScanResult> {
+        let row = self.list_view.selected_item()?;
+        let ip = self.list_view.item(row, 2, 64)?.text;
+        self.scan_results
+            .borrow()
+            .iter()
+            .find(|r| r.ip.to_string() == ip)
+            .cloned()
+    }
+
+    /// Addresses of every selected row.
+    fn selected_ips(&self) -> Vec<Ipv4Addr> {
+        self.list_view
+            .selected_items()
+            .into_iter()
+            .filter_map(|row| self.list_view.item(row, 2, 64)?.text.parse().ok())
+            .collect()
+    }
+
+    /// Pops up the per-host menu at the cursor for the right-clicked row.
+    fn show_row_menu(&self) {
+        let Some(res) = self.selected_result() else {
+            return;
+        };
+        let has_mac = res.mac.is_some();
+        // Features the network stack cannot support stay grayed out.
+        let capabilities = NetUtils::new().capabilities();
+        self.copy_mac_item.set_enabled(has_mac);
+        self.wake_item.set_enabled(has_mac && capabilities.udp);
+        self.name_device_item.set_enabled(has_mac);
+        self.ping_item.set_enabled(capabilities.icmp);
+        self.copy_hostname_item.set_enabled(res.hostname.is_some());
+        let (x, y) = nwg::GlobalCursor::position();
+        self.row_menu.popup(x, y);
+    }
+
+    /// Opens the naming window on the selected host, filled with its note.
+    fn name_device(&self) {
+        let Some(res) = self.selected_result() else {
+            return;
+        };
+        let Some(mac) = res.mac else {
+            return;
+        };
+        let note = res.device_note.unwrap_or_default();
+        self.device_name_input.set_text(&note.name);
+        self.device_notes_input
+            .set_text(note.notes.as_deref().unwrap_or_default());
+        self.device_window.set_text(&format!("Name Device {}", mac));
+        *self.device_note_mac.borrow_mut() = Some(mac);
+        self.device_window.set_visible(true);
+        self.device_name_input.set_focus();
+    }
+
+    /// Saves the naming window's note; a blank name forgets the device.
+    fn save_device_note(&self) {
+        if let Some(mac) = self.device_note_mac.borrow_mut().take() {
+            let name = self.device_name_input.text().trim().to_string();
+            let notes = self.device_notes_input.text().trim().to_string();
+            let note = (!name.is_empty()).then(|| DeviceNote {
+                name,
+                notes: (!notes.is_empty()).then_some(notes),
+            });
+            self.send_command(BridgeMessage::SetDeviceNote { mac, note });
+        }
+        self.hide_device_window();
+    }
+
+    fn hide_device_window(&self) {
+        self.device_window.set_visible(false);
+    }
 
-    #[nwg_control(text: "Ready")]
-    #[nwg_layout_item(layout: layout, col: 0, row: 19, col_span: 5)]
-    status_bar: nwg::StatusBar,
+    fn copy_ip(&self) {
+        self.copy_field(|r| Some(r.ip.to_string()));
+    }
 
-    #[nwg_control]
-    #[nwg_events(OnNotice: [RageScannerApp::handle_ui_message])]
-    ui_notice: nwg::Notice,
+    fn copy_mac(&self) {
+        self.copy_field(|r| r.mac.clone());
+    }
 
-    #[nwg_control]
-    #[nwg_events(OnNotice: [RageScannerApp::clear_results])]
-    clear_notice: nwg::Notice,
+    fn copy_hostname(&self) {
+        self.copy_field(|r| r.hostname.clone());
+    }
 
-    // App State
-    cmd_tx: Option<TokioSender<BridgeMessage>>,
-    ui_rx: Option<Arc<crossbeam_channel::Receiver<BridgeMessage>>>,
-    scan_in_progress: Arc<AtomicBool>,
-    scan_results: RefCell<Vec<ScanResult>>,
-}
+    /// Puts a field of the selected host on the clipboard.
+    fn copy_field(&self, field: impl FnOnce(&ScanResult) -> Option<String>) {
+        if let Some(text) = self.selected_result().as_ref().and_then(field) {
+            nwg::Clipboard::set_data_text(&self.window, &text);
+        }
+    }
 
-impl RageScannerApp {
-    fn init_list_view(&self) {
-        self.list_view.insert_column("Status");
-        self.list_view.insert_column("Hostname");
-        self.list_view.insert_column("IP Address");
-        self.list_view.insert_column("MAC Address");
-        self.list_view.insert_column("Vendor");
-        self.list_view.insert_column("Open Ports");
+    /// Markdown report of the selected host, stamped with the scan's start.
+    fn selected_report(&self) -> Option<(Ipv4Addr, String)> {
+        let res = self.selected_result()?;
+        let text = report::host_report(&res, self.scan_started.get(), SystemTime::now());
+        Some((res.ip, text))
+    }
 
-        self.list_view.set_headers_enabled(true);
-        self.list_view.set_column_width(0, 80);
-        self.list_view.set_column_width(1, 120);
-        self.list_view.set_column_width(2, 100);
-        self.list_view.set_column_width(3, 120);
-        self.list_view.set_column_width(4, 120);
-        self.list_view.set_column_width(5, 120);
+    fn copy_report(&self) {
+        if let Some((ip, text)) = self.selected_report() {
+            nwg::Clipboard::set_data_text(&self.window, &text);
+            self.status_bar
+                .set_text(0, &format!("Copied report for {}", ip));
+        }
     }
 
-    fn start_scan(&self) {
-        if self.scan_in_progress.load(Ordering::SeqCst) {
+    fn save_report(&self) {
+        let Some((ip, text)) = self.selected_report() else {
+            return;
+        };
+        if !self.report_dialog.run(Some(&self.window)) {
             return;
         }
+        let mut path = match self.report_dialog.get_selected_item() {
+            Ok(p) => PathBuf::from(p),
+            Err(e) => {
+                error!("Report dialog failed: {}", e);
+                return;
+            }
+        };
+        if path.extension().is_none() {
+            path.set_extension("md");
+        }
+        match std::fs::write(&path, text) {
+            Ok(()) => self
+                .status_bar
+                .set_text(0, &format!("Saved report for {}", ip)),
+            Err(e) => {
+                nwg::modal_error_message(&self.window, "Save Failed", &e.to_string());
+            }
+        }
+    }
 
-        let start = self.start_ip_input.text();
-        let end = self.end_ip_input.text();
+    /// Saves every result of the last scan as a standalone HTML report.
+    fn save_scan_report(&self) {
+        if !self.scan_report_dialog.run(Some(&self.window)) {
+            return;
+        }
+        let mut path = match self.scan_report_dialog.get_selected_item() {
+            Ok(p) => PathBuf::from(p),
+            Err(e) => {
+                error!("Report dialog failed: {}", e);
+                return;
+            }
+        };
+        if path.extension().is_none() {
+            path.set_extension("html");
+        }
+        let results = self.scan_results.borrow();
+        let summary = self.scan_summary.get();
+        match report::write_scan_report(
+            &path,
+            &results,
+            summary.as_ref(),
+            self.scan_label.borrow().as_ref(),
+            self.scan_started.get(),
+        ) {
+            Ok(()) => self
+                .status_bar
+                .set_text(0, &format!("Saved report of {} hosts", results.len())),
+            Err(e) => {
+                nwg::modal_error_message(&self.window, "Save Failed", &e.to_string());
+            }
+        }
+    }
 
-        if start.is_empty() || end.is_empty() {
-            nwg::modal_error_message(
-                &self.window,
-                "Error",
-                "Please enter both Start and End IP/Octet.",
-            );
+    /// Lists what changed between a saved scan and the current results:
+    /// online devices that appeared, disappeared or changed, as monitoring
+    /// reports them.
+    fn compare_with_saved(&self) {
+        if !self.compare_dialog.run(Some(&self.window)) {
             return;
         }
+        let path = match self.compare_dialog.get_selected_item() {
+            Ok(p) => PathBuf::from(p),
+            Err(e) => {
+                error!("Compare dialog failed: {}", e);
+                return;
+            }
+        };
+        let saved = match session::load_results(&path) {
+            Ok(saved) => saved,
+            Err(e) => {
+                nwg::modal_error_message(&self.window, "Compare Failed", &e.to_string());
+                return;
+            }
+        };
 
-        let range = format!("{}-{}", start, end);
+        let online = |results: &[ScanResult]| -> BTreeMap<Ipv4Addr, ScanResult> {
+            results
+                .iter()
+                .filter(|r| r.status == ScanStatus::Online)
+                .map(|r| (r.ip, r.clone()))
+                .collect()
+        };
+        let events = monitor::diff_sweeps(
+            &online(&saved.results),
+            &online(&self.scan_results.borrow()),
+        );
+        let lines: Vec<String> = events.iter().filter_map(monitor::describe_event).collect();
+        let mut content = lines
+            .iter()
+            .take(COMPARE_MAX_LINES)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n");
+        if lines.is_empty() {
+            content = "No device appeared, disappeared or changed.".to_string();
+        } else if lines.len() > COMPARE_MAX_LINES {
+            content.push_str(&format!(
+                "\n... and {} more",
+                lines.len() - COMPARE_MAX_LINES
+            ));
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        nwg::modal_info_message(&self.window, &format!("Compared with {}", name), &content);
+    }
 
-        // Clear previous results buffer
-        self.scan_results.borrow_mut().clear();
+    fn show_log(&self) {
+        self.refresh_log();
+        self.log_window.set_visible(true);
+        self.log_window.set_focus();
+    }
 
-        self.clear_notice.sender().notice();
+    /// Loads the end of the log file into the log viewer.
+    fn refresh_log(&self) {
+        let text = match read_log_tail(Path::new(LOG_PATH), LOG_VIEW_BYTES) {
+            // Edit controls only break lines at CRLF.
+            Ok(text) => text.lines().collect::<Vec<_>>().join("\r\n"),
+            Err(e) => format!("Failed to read {}: {}", LOG_PATH, e),
+        };
+        self.log_text.set_text(&text);
+    }
 
-        if let Some(tx) = &self.cmd_tx {
-            let tx = tx.clone();
-            self.scan_in_progress.store(true, Ordering::SeqCst);
-            self.scan_btn.set_enabled(false);
-            self.progress_bar.set_pos(0);
-            self.status_bar.set_text(0, "Scanning...");
+    /// Shows the version, build, settings in effect and the data sources
+    /// vendor and service names come from.
+    fn show_about(&self) {
+        let fields = about::about_fields(&self.settings.borrow(), self.settings_path.as_deref());
+        let mut content = format!("{}\n\n", env!("CARGO_PKG_DESCRIPTION"));
+        for (label, value) in fields {
+            content.push_str(&format!("{}: {}\n", label, value));
+        }
+        content.push_str(&format!("Log: {}", LOG_PATH));
+        nwg::modal_info_message(&self.window, "About RageScanner", &content);
+    }
 
-            // Use blocking_send to bridge sync -> async safely.
-            // We handle the error by logging it, ensuring the app doesn't panic if the channel is closed.
-            if let Err(e) = tx.blocking_send(BridgeMessage::StartScan(range)) {
-                error!("Failed to send StartScan command: {}", e);
-                nwg::modal_error_message(
-                    &self.window,
-                    "Internal Error",
-                    &format!("Failed to start scan: {}", e),
-                );
-            }
+    fn open_in_browser(&self) {
+        if let Some(res) = self.selected_result() {
+            self.launch("explorer", &[&format!("http://{}", res.ip)]);
         }
     }
 
-    fn clear_results(&self) {
-        self.list_view.clear();
+    fn open_rdp(&self) {
+        if let Some(res) = self.selected_result() {
+            self.launch("mstsc", &[&format!("/v:{}", res.ip)]);
+        }
     }
 
-    fn handle_ui_message(&self) {
-        if let Some(rx) = &self.ui_rx {
-            let mut count = 0;
-            // Process max 50 messages per tick to keep UI responsive
-            while let Ok(msg) = rx.try_recv() {
-                match msg {
-                    BridgeMessage::ScanUpdate(res) => {
-                        // Buffer the result
-                        self.scan_results.borrow_mut().push(res.clone());
-                        // Update UI immediately (streaming view)
-                        self.update_list(res);
-                    }
-                    BridgeMessage::ScanComplete => {
-                        self.scan_in_progress.store(false, Ordering::SeqCst);
-                        self.scan_btn.set_enabled(true);
-                        self.status_bar.set_text(0, "Scan Complete - Sorting...");
+    /// Opens a console window pinging the selected host until it is closed.
+    fn ping_in_console(&self) {
+        if let Some(res) = self.selected_result() {
+            let ip = res.ip.to_string();
+            let title = format!("Ping {}", ip);
+            self.launch("cmd", &["/C", "start", &title, "ping", "-t", &ip]);
+        }
+    }
 
-                        // Sort results by IP
-                        let mut results = self.scan_results.borrow_mut();
-                        results.sort_by_key(|r| r.ip);
+    fn rescan_host(&self) {
+        if let Some(res) = self.selected_result() {
+            self.status_bar
+                .set_text(0, &format!("Rescanning {}...", res.ip));
+            self.send_command(BridgeMessage::RescanHost(res.ip));
+        }
+    }
 
-                        // Refresh List View
-                        self.list_view.clear();
-                        for res in results.iter() {
-                            self.update_list(res.clone());
-                        }
+    /// Rescans the selected host and probes every TCP port on it.
+    fn full_port_scan(&self) {
+        if let Some(res) = self.selected_result() {
+            self.status_bar
+                .set_text(0, &format!("Scanning all ports of {}...", res.ip));
+            self.host_scans.borrow_mut().insert(res.ip, 0);
+            self.refresh_row(res.ip);
+            self.send_command(BridgeMessage::ScanHost {
+                ip: res.ip,
+                ports: (1..=FULL_SCAN_LAST_PORT).collect(),
+            });
+        }
+    }
 
-                        self.status_bar.set_text(0, "Scan Complete");
-                        self.progress_bar.set_pos(100);
-                    }
-                    BridgeMessage::Progress(p) => {
-                        self.progress_bar.set_pos(p as u32);
-                    }
-                    BridgeMessage::Error(e) => {
-                        self.scan_in_progress.store(false, Ordering::SeqCst);
-                        self.scan_btn.set_enabled(true);
-                        self.status_bar.set_text(0, &format!("Error: {}", e));
-                        nwg::modal_error_message(&self.window, "Scan Error", &e.to_string());
-                    }
-                    _ => {}
-                }
+    /// Wakes the selected hosts that have a MAC: one packet for a single
+    /// host, a staggered batch reported once it ends for several.
+    fn wake_host(&self) {
+        let selected = self.selected_ips();
+        let targets: Vec<WakeTarget> = self
+            .scan_results
+            .borrow()
+            .iter()
+            .filter(|r| selected.contains(&r.ip))
+            .filter_map(|r| {
+                Some(WakeTarget {
+                    mac: r.mac.clone()?,
+                    ip: Some(r.ip),
+                })
+            })
+            .collect();
+        match targets.as_slice() {
+            [] => {}
+            [target] => self.send_command(BridgeMessage::WakeOnLan(target.mac.clone())),
+            _ => {
+                self.status_bar
+                    .set_text(0, &format!("Waking {} devices...", targets.len()));
+                self.send_command(BridgeMessage::WakeBatch {
+                    targets,
+                    timeout: DEFAULT_WAKE_TIMEOUT,
+                });
+            }
+        }
+    }
 
-                count += 1;
-                if count >= 50 {
-                    // Yield to let UI process window messages, then re-trigger
-                    self.ui_notice.sender().notice();
-                    break;
-                }
+    /// Starts an external program, reporting failure in a dialog.
+    fn launch(&self, program: &str, args: &[&str]) {
+        if let Err(e) = Command::new(program).args(args).spawn() {
+            error!("Failed to run {}: {}", program, e);
+            nwg::modal_error_message(
+                &self.window,
+                "Launch Failed",
+                &format!("Failed to run {}: {}", program, e),
+            );
+        }
+    }
+
+    /// Offers to restart as administrator after `e`, handing the new instance
+    /// the scan in the Start/End fields; exits once it has started.
+    fn offer_elevation(&self, e: &GError) {
+        let choice = nwg::modal_message(
+            &self.window,
+            &nwg::MessageParams {
+                title: "Administrator Required",
+                content: &format!(
+                    "{}\n\nRestart RageScanner as administrator and run this scan again?",
+                    e
+                ),
+                buttons: nwg::MessageButtons::YesNo,
+                icons: nwg::MessageIcons::Warning,
+            },
+        );
+        if choice != nwg::MessageChoice::Yes {
+            return;
+        }
+        let pending = PendingScan {
+            start: self.start_ip_input.text(),
+            end: self.end_ip_input.text(),
+        };
+        match elevation::relaunch_elevated(&pending.to_args()) {
+            Ok(()) => self.exit(),
+            Err(e) => {
+                error!("Relaunch as administrator failed: {}", e);
+                self.status_bar.set_text(0, &format!("Error: {}", e));
+            }
+        }
+    }
+
+    /// Adds rows with redrawing suspended, so a batch costs one repaint.
+    ///
+    /// In live order each row goes on top, so pass them oldest first.
+    fn append_rows(&self, results: impl IntoIterator<Item = ScanResult>) {
+        let mut results = results
+            .into_iter()
+            .filter(|r| self.is_visible(r))
+            .peekable();
+        if results.peek().is_none() {
+            return;
+        }
+        let _redraw = RedrawGuard::new(&self.list_view);
+        for res in results {
+            self.update_list(res);
+        }
+    }
+
+    /// Row showing `ip`, if it is listed.
+    fn row_of(&self, ip: Ipv4Addr) -> Option<usize> {
+        let ip = ip.to_string();
+        (0..self.list_view.len()).find(|&row| {
+            self.list_view
+                .item(row, 2, 64)
+                .is_some_and(|item| item.text == ip)
+        })
+    }
+
+    /// Shows an updated result in its existing row, or adds a row if the
+    /// host is not listed; drops the row if the filter now hides the host.
+    fn replace_row(&self, res: ScanResult) {
+        match self.row_of(res.ip) {
+            Some(row) if self.is_visible(&res) => self.fill_row(row, res),
+            Some(row) => {
+                self.list_view.remove_item(row);
             }
+            None => self.append_rows([res]),
+        }
+    }
+
+    /// Redraws the row of `ip` from the buffered result.
+    fn refresh_row(&self, ip: Ipv4Addr) {
+        let res = self
+            .scan_results
+            .borrow()
+            .iter()
+            .find(|r| r.ip == ip)
+            .cloned();
+        if let (Some(res), Some(row)) = (res, self.row_of(ip)) {
+            self.fill_row(row, res);
         }
     }
 
     fn update_list(&self, res: ScanResult) {
-        let index = self.list_view.len();
+        let index = match self.result_order.get() {
+            ResultOrder::Ip => self.list_view.len(),
+            ResultOrder::Live => 0,
+        };
         self.list_view.insert_item(nwg::InsertListViewItem {
             index: Some(index as i32),
             column_index: 0,
             text: Some(res.status.to_string()),
             image: None,
         });
+        self.fill_row(index, res);
+    }
+
+    /// Writes every column of row `index` from `res`.
+    fn fill_row(&self, index: usize, res: ScanResult) {
+        let mut status = match self.host_scans.borrow().get(&res.ip) {
+            Some(p) => format!("{} ({}%)", res.status, p),
+            None => res.status.to_string(),
+        };
+        if self.security_alerts.borrow().contains(&res.ip) {
+            status.insert_str(0, "⚠ ");
+        }
+        self.list_view.update_item(
+            index,
+            nwg::InsertListViewItem {
+                index: Some(index as i32),
+                column_index: 0,
+                text: Some(status),
+                image: None,
+            },
+        );
 
         self.list_view.update_item(
             index,
             nwg::InsertListViewItem {
                 index: Some(index as i32),
                 column_index: 1,
-                text: Some(res.hostname.unwrap_or_default()),
+                text: Some(
+                    res.device_note
+                        .as_ref()
+                        .map(|note| note.name.clone())
+                        .or_else(|| res.hostname.clone())
+                        .unwrap_or_default(),
+                ),
                 image: None,
             },
         );
@@ -240,7 +2273,12 @@ impl RageScannerApp {
         let ports_str = res
             .open_ports
             .iter()
-            .map(|p| format!("{} ({})", p, ragescanner::types::port_label(*p)))
+            .map(|p| p.describe())
+            .chain(
+                res.open_udp_ports
+                    .iter()
+                    .map(|p| format!("{}/udp ({})", p, ragescanner::types::udp_port_label(*p))),
+            )
             .collect::<Vec<String>>()
             .join(", ");
 
@@ -253,46 +2291,290 @@ impl RageScannerApp {
                 image: None,
             },
         );
+
+        self.list_view.update_item(
+            index,
+            nwg::InsertListViewItem {
+                index: Some(index as i32),
+                column_index: 6,
+                text: Some(res.latency.map(|l| l.summary()).unwrap_or_default()),
+                image: None,
+            },
+        );
+
+        self.list_view.update_item(
+            index,
+            nwg::InsertListViewItem {
+                index: Some(index as i32),
+                column_index: 7,
+                text: Some(
+                    res.os_guess
+                        .map(|g| format!("{} ({}%)", g.family, g.confidence))
+                        .unwrap_or_default(),
+                ),
+                image: None,
+            },
+        );
+
+        self.list_view.update_item(
+            index,
+            nwg::InsertListViewItem {
+                index: Some(index as i32),
+                column_index: 8,
+                text: Some(res.web_info.map(|w| w.summary()).unwrap_or_default()),
+                image: None,
+            },
+        );
+
+        self.list_view.update_item(
+            index,
+            nwg::InsertListViewItem {
+                index: Some(index as i32),
+                column_index: 9,
+                text: Some(
+                    res.device_type
+                        .map(|t| format!("{} {}", t.glyph(), t))
+                        .into_iter()
+                        .chain(res.roles.iter().map(|role| format!("[{}]", role)))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                ),
+                image: None,
+            },
+        );
     }
 
     fn exit(&self) {
+        if self.scan_in_progress.load(Ordering::SeqCst) {
+            self.stop_scan_for_exit();
+        }
         nwg::stop_thread_dispatch();
     }
+
+    /// Stops the running scan before the window goes away and saves what it
+    /// found, so the next launch offers to resume it instead of the process
+    /// ending with probes in flight. Waits at most [`EXIT_STOP_TIMEOUT`].
+    fn stop_scan_for_exit(&self) {
+        self.send_command(BridgeMessage::StopScan);
+        self.status_bar.set_text(0, "Stopping scan...");
+        if let Some(rx) = &self.ui_rx {
+            let deadline = Instant::now() + EXIT_STOP_TIMEOUT;
+            while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+                match rx.recv_timeout(left) {
+                    Ok(BridgeMessage::ScanUpdate(mut res)) => {
+                        let mut results = self.scan_results.borrow_mut();
+                        if let Some(i) = results.iter().position(|r| r.ip == res.ip) {
+                            res.merge_seen(&results.remove(i));
+                        }
+                        results.push(res);
+                    }
+                    Ok(BridgeMessage::ScanComplete) => {
+                        self.end_session();
+                        return;
+                    }
+                    Ok(BridgeMessage::ScanCancelled) | Err(_) => break,
+                    Ok(_) => {}
+                }
+            }
+        }
+        // Targets never reached are `NotScanned`, which a resume picks up.
+        self.last_autosave.set(None);
+        self.autosave();
+    }
+}
+
+/// Reads the last `max_bytes` of the file at `path`, from the first line
+/// that starts within them.
+fn read_log_tail(path: &Path, max_bytes: u64) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let start = file.metadata()?.len().saturating_sub(max_bytes);
+    file.seek(SeekFrom::Start(start))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    let mut text = String::from_utf8_lossy(&bytes).into_owned();
+    if start > 0
+        && let Some(newline) = text.find('\n')
+    {
+        text.drain(..=newline);
+    }
+    Ok(text)
+}
+
+fn check_state(checked: bool) -> nwg::CheckBoxState {
+    if checked {
+        nwg::CheckBoxState::Checked
+    } else {
+        nwg::CheckBoxState::Unchecked
+    }
+}
+
+/// Orders two results by a list column: IPs numerically, latency by the
+/// average, text case-insensitively; empty cells last.
+fn compare_column(a: &ScanResult, b: &ScanResult, column: usize) -> cmp::Ordering {
+    match column {
+        2 => a.ip.cmp(&b.ip),
+        5 => {
+            let ports = |res: &ScanResult| {
+                let ports: Vec<u16> = res.open_ports.iter().map(|p| p.port).collect();
+                (!ports.is_empty()).then_some(ports)
+            };
+            empty_last(ports(a), ports(b))
+        }
+        6 => empty_last(a.latency_ms(), b.latency_ms()),
+        _ => {
+            let text = |res: &ScanResult| match column {
+                0 => Some(res.status.to_string()),
+                1 => res
+                    .device_note
+                    .as_ref()
+                    .map(|note| &note.name)
+                    .or(res.hostname.as_ref())
+                    .map(|h| h.to_lowercase()),
+                3 => res.mac.clone(),
+                4 => res.vendor.as_ref().map(|v| v.to_lowercase()),
+                7 => res.os_guess.as_ref().map(|g| g.family.to_string()),
+                8 => res.web_info.as_ref().map(|w| w.summary()),
+                9 => res.device_type.map(|t| t.to_string()),
+                _ => None,
+            };
+            empty_last(text(a), text(b))
+        }
+    }
+}
+
+fn empty_last<T: PartialOrd>(a: Option<T>, b: Option<T>) -> cmp::Ordering {
+    a.is_none()
+        .cmp(&b.is_none())
+        .then_with(|| a.partial_cmp(&b).unwrap_or(cmp::Ordering::Equal))
+}
+
+/// One line for the range label: how many ranges are done, then the ones
+/// still in flight.
+/// The phases of the running scan followed by its ranges, e.g.
+/// `Discovery 80% · Ports 35% | Ranges: 1/2 done | ...`.
+fn progress_text(phase: Option<PhaseProgress>, ranges: &[RangeProgress]) -> String {
+    let mut parts: Vec<String> = phase.iter().map(|p| p.to_string()).collect();
+    if !ranges.is_empty() {
+        parts.push(range_summary(ranges));
+    }
+    parts.join(" | ")
+}
+
+fn range_summary(ranges: &[RangeProgress]) -> String {
+    let done = ranges.iter().filter(|r| r.is_complete()).count();
+    let mut text = format!("Ranges: {}/{} done", done, ranges.len());
+    for range in ranges.iter().filter(|r| !r.is_complete()) {
+        text.push_str(&format!(" | {}", range));
+    }
+    text
+}
+
+/// Suspends ListView repainting (`WM_SETREDRAW`) until dropped.
+struct RedrawGuard<'a>(&'a nwg::ListView);
+
+impl<'a> RedrawGuard<'a> {
+    fn new(list: &'a nwg::ListView) -> Self {
+        list.set_redraw(false);
+        Self(list)
+    }
+}
+
+impl Drop for RedrawGuard<'_> {
+    fn drop(&mut self) {
+        self.0.set_redraw(true);
+    }
+}
+
+/// Forwards bridge messages to the GUI thread as they arrive.
+///
+/// Blocks on `recv` instead of polling, so an idle GUI never wakes up. Each
+/// message is moved to `forward` (drained by `handle_ui_message`) and a notice
+/// is raised only if none is already pending. The thread exits once the
+/// bridge or the window goes away.
+fn spawn_notice_pump(
+    bridge_rx: crossbeam_channel::Receiver<BridgeMessage>,
+    forward: crossbeam_channel::Sender<BridgeMessage>,
+    ui_notice: nwg::NoticeSender,
+    notice_pending: Arc<AtomicBool>,
+) {
+    std::thread::spawn(move || {
+        while let Ok(msg) = bridge_rx.recv() {
+            if forward.send(msg).is_err() {
+                break;
+            }
+            if !notice_pending.swap(true, Ordering::SeqCst) {
+                ui_notice.notice();
+            }
+        }
+    });
 }
 
 pub fn run_app(
     cmd_tx: TokioSender<BridgeMessage>,
     ui_rx: crossbeam_channel::Receiver<BridgeMessage>,
+    pending: Option<PendingScan>,
 ) {
     nwg::init().expect("Failed to init Native Windows GUI");
     nwg::Font::set_global_family("Segoe UI").expect("Failed to set default font");
 
+    // A broken settings file is reported and left alone rather than overwritten.
+    let first_run = Settings::default_path().is_some_and(|path| !path.exists());
+    let (settings, settings_path) = match Settings::default_path() {
+        Some(path) => match Settings::load_or_default(&path) {
+            Ok(settings) => (Ok(settings), Some(path)),
+            Err(e) => (Err(e), None),
+        },
+        None => (Ok(Settings::default()), None),
+    };
+
+    let (pump_tx, pump_rx) = crossbeam_channel::unbounded();
     let app = RageScannerApp::build_ui(RageScannerApp {
         cmd_tx: Some(cmd_tx),
-        ui_rx: Some(Arc::new(ui_rx)),
+        ui_rx: Some(Arc::new(pump_rx)),
         scan_in_progress: Arc::new(AtomicBool::new(false)),
+        settings_path,
         ..Default::default()
     })
     .expect("Failed to build UI");
 
     app.init_list_view();
-
-    let ui_notice = app.ui_notice.sender();
-    let rx = app.ui_rx.as_ref().unwrap().clone();
-
-    std::thread::spawn(move || {
-        loop {
-            if !rx.is_empty() {
-                ui_notice.notice();
-                // Rate-limit the pump to prevent flooding the Windows Message Queue.
-                // This allows the channel to buffer messages naturally.
-                std::thread::sleep(std::time::Duration::from_millis(50));
-            } else {
-                // Idle poll to check for new messages
-                std::thread::sleep(std::time::Duration::from_millis(10));
+    app.set_result_order(ResultOrder::from_env());
+    if let Some(path) = std::env::var_os(INVENTORY_VAR) {
+        app.send_command(BridgeMessage::SetInventory(Some(PathBuf::from(path))));
+    }
+    match settings {
+        Ok(settings) => {
+            app.apply_settings(settings);
+            if let Some(path) = &app.settings_path {
+                app.send_command(BridgeMessage::WatchSettings(path.clone()));
             }
         }
-    });
+        Err(e) => {
+            error!("{}", e);
+            nwg::modal_error_message(&app.window, "Settings", &e.to_string());
+        }
+    }
+    match pending {
+        // Relaunched as administrator: run the scan that needed it.
+        Some(pending) => {
+            app.start_ip_input.set_text(&pending.start);
+            app.end_ip_input.set_text(&pending.end);
+            app.start_scan();
+        }
+        None => {
+            if first_run {
+                app.offer_detected_subnets();
+            }
+            app.offer_recovery();
+        }
+    }
+
+    spawn_notice_pump(
+        ui_rx,
+        pump_tx,
+        app.ui_notice.sender(),
+        app.notice_pending.clone(),
+    );
 
     nwg::dispatch_thread_events();
 }
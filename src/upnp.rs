@@ -0,0 +1,229 @@
+//! SSDP/UPnP discovery.
+//!
+//! Routers, TVs, media servers and NAS boxes answer an SSDP `M-SEARCH` with
+//! the URL of a device-description XML that names the device far better
+//! than an OUI lookup: friendly name, manufacturer and model.
+
+use crate::types::UpnpInfo;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, TcpStream, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// How long [`discover_upnp`] listens for `M-SEARCH` replies.
+pub const SSDP_SEARCH_TIMEOUT: Duration = Duration::from_millis(2000);
+/// Connect and read timeout for fetching one device description.
+const DESCRIPTION_TIMEOUT: Duration = Duration::from_millis(2000);
+/// Largest device description read, in bytes.
+const MAX_DESCRIPTION_LEN: u64 = 64 * 1024;
+
+const SSDP_GROUP: Ipv4Addr = Ipv4Addr::new(239, 255, 255, 250);
+const SSDP_PORT: u16 = 1900;
+
+const M_SEARCH: &str = "M-SEARCH * HTTP/1.1\r\n\
+    HOST: 239.255.255.250:1900\r\n\
+    MAN: \"ssdp:discover\"\r\n\
+    MX: 1\r\n\
+    ST: upnp:rootdevice\r\n\r\n";
+
+/// Sends an SSDP `M-SEARCH` and returns the description of every UPnP root
+/// device that answered, keyed by its address.
+///
+/// Listens for `timeout`, then fetches the descriptions in parallel. A
+/// description is only fetched from the device that announced it; devices
+/// whose description cannot be fetched or parsed are left out.
+pub fn discover_upnp(timeout: Duration) -> HashMap<Ipv4Addr, UpnpInfo> {
+    let locations = search(timeout);
+    std::thread::scope(|scope| {
+        let fetches: Vec<_> = locations
+            .into_iter()
+            .map(|(ip, location)| scope.spawn(move || (ip, fetch_description(ip, &location))))
+            .collect();
+        fetches
+            .into_iter()
+            .filter_map(|fetch| match fetch.join() {
+                Ok((ip, Some(info))) => Some((ip, info)),
+                _ => None,
+            })
+            .collect()
+    })
+}
+
+/// Collects the description URL of each responding device.
+fn search(timeout: Duration) -> HashMap<Ipv4Addr, String> {
+    let mut locations = HashMap::new();
+    let Ok(socket) = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)) else {
+        return locations;
+    };
+    if socket
+        .send_to(M_SEARCH.as_bytes(), (SSDP_GROUP, SSDP_PORT))
+        .is_err()
+    {
+        return locations;
+    }
+
+    let deadline = Instant::now() + timeout;
+    let mut buf = [0u8; 2048];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() || socket.set_read_timeout(Some(remaining)).is_err() {
+            break;
+        }
+        let (len, from) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(_) => break,
+        };
+        let SocketAddr::V4(from) = from else {
+            continue;
+        };
+        let reply = String::from_utf8_lossy(&buf[..len]);
+        if let Some(location) = header(&reply, "location") {
+            locations.entry(*from.ip()).or_insert(location);
+        }
+    }
+    locations
+}
+
+/// Returns the value of an HTTP-style header, matching the name case-insensitively.
+fn header(message: &str, name: &str) -> Option<String> {
+    message.lines().skip(1).find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().to_string())
+    })
+}
+
+/// Splits an `http://ip[:port]/path` URL. Host names are rejected so that
+/// nothing but a literal address on the link is ever contacted.
+fn parse_location(url: &str) -> Option<(SocketAddrV4, String)> {
+    let rest = url.strip_prefix("http://")?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let addr = match authority.split_once(':') {
+        Some((host, port)) => SocketAddrV4::new(host.parse().ok()?, port.parse().ok()?),
+        None => SocketAddrV4::new(authority.parse().ok()?, 80),
+    };
+    Some((addr, path.to_string()))
+}
+
+/// Downloads and parses a device description over plain HTTP/1.0.
+///
+/// Refuses locations that point anywhere but `device`, the host that sent them.
+fn fetch_description(device: Ipv4Addr, location: &str) -> Option<UpnpInfo> {
+    let (addr, path) = parse_location(location)?;
+    if *addr.ip() != device {
+        log::warn!(
+            "Ignoring UPnP location {} announced by {}",
+            location,
+            device
+        );
+        return None;
+    }
+    let mut stream = TcpStream::connect_timeout(&addr.into(), DESCRIPTION_TIMEOUT).ok()?;
+    stream.set_read_timeout(Some(DESCRIPTION_TIMEOUT)).ok()?;
+    // HTTP/1.0 rules out chunked encoding; the device closes when done.
+    let request = format!(
+        "GET {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        path, addr
+    );
+    stream.write_all(request.as_bytes()).ok()?;
+    let mut body = Vec::new();
+    // A timeout after some data still leaves a usable partial read.
+    let _ = stream.take(MAX_DESCRIPTION_LEN).read_to_end(&mut body);
+    let text = String::from_utf8_lossy(&body);
+    let (_, xml) = text.split_once("\r\n\r\n")?;
+    parse_description(xml)
+}
+
+/// Reads the root device's names from a description document.
+fn parse_description(xml: &str) -> Option<UpnpInfo> {
+    // Embedded devices follow the root's own fields, so the first match wins.
+    let device = &xml[xml.find("<device")?..];
+    let info = UpnpInfo {
+        friendly_name: element(device, "friendlyName"),
+        manufacturer: element(device, "manufacturer"),
+        model: element(device, "modelName"),
+    };
+    let is_empty =
+        info.friendly_name.is_none() && info.manufacturer.is_none() && info.model.is_none();
+    (!is_empty).then_some(info)
+}
+
+/// Returns the trimmed, unescaped text of the first `<tag>` element.
+fn element(body: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = body.find(&open)? + open.len();
+    let len = body[start..].find(&close)?;
+    let value = body[start..start + len]
+        .trim()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+    // Strip control characters so a hostile device cannot drive the terminal.
+    let value: String = value.chars().filter(|c| !c.is_control()).collect();
+    (!value.is_empty()).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESCRIPTION: &str = r#"<?xml version="1.0"?>
+<root xmlns="urn:schemas-upnp-org:device-1-0">
+  <device>
+    <deviceType>urn:schemas-upnp-org:device:InternetGatewayDevice:1</deviceType>
+    <friendlyName> Living Room Router </friendlyName>
+    <manufacturer>AVM &amp; Co</manufacturer>
+    <modelName>FRITZ!Box 7590</modelName>
+    <deviceList>
+      <device><friendlyName>WANDevice</friendlyName><modelName>WAN</modelName></device>
+    </deviceList>
+  </device>
+</root>"#;
+
+    #[test]
+    fn test_header_is_case_insensitive() {
+        let reply = "HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age=1800\r\n\
+            Location: http://192.168.1.1:49000/igddesc.xml\r\nST: upnp:rootdevice\r\n\r\n";
+        assert_eq!(
+            header(reply, "LOCATION").as_deref(),
+            Some("http://192.168.1.1:49000/igddesc.xml")
+        );
+        assert_eq!(header(reply, "server"), None);
+    }
+
+    #[test]
+    fn test_parse_location() {
+        assert_eq!(
+            parse_location("http://192.168.1.1:49000/igddesc.xml"),
+            Some((
+                SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 1), 49000),
+                "/igddesc.xml".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_location("http://10.0.0.5"),
+            Some((
+                SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 5), 80),
+                "/".to_string()
+            ))
+        );
+        assert_eq!(parse_location("http://router.lan/desc.xml"), None);
+        assert_eq!(parse_location("https://10.0.0.5/desc.xml"), None);
+    }
+
+    #[test]
+    fn test_parse_description_reads_root_device() {
+        let info = parse_description(DESCRIPTION).unwrap();
+        assert_eq!(info.friendly_name.as_deref(), Some("Living Room Router"));
+        assert_eq!(info.manufacturer.as_deref(), Some("AVM & Co"));
+        assert_eq!(info.model.as_deref(), Some("FRITZ!Box 7590"));
+        assert_eq!(parse_description("<root><device></device></root>"), None);
+    }
+}
@@ -0,0 +1,333 @@
+//! HTTP enrichment for hosts with open web ports.
+//!
+//! [`fetch`] requests `/` from one port and records the status code,
+//! `Server` header and page `<title>` as a [`WebInfo`], which is usually
+//! enough to tell a router's admin page from a camera or NAS. HTTPS ports
+//! skip certificate checks: devices almost always use self-signed
+//! certificates, and nothing is sent beyond the request itself.
+
+use crate::net::NetworkProvider;
+use crate::oui::RANDOMIZED_MAC_VENDOR;
+use crate::types::{InterceptionWarning, PortInfo, ScanResult, WebInfo};
+use std::collections::{HashMap, HashSet};
+use std::net::Ipv4Addr;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_native_tls::{TlsConnector, native_tls};
+
+/// Open ports that get a `GET /`, in order of preference.
+pub const WEB_PORTS: &[u16] = &[80, 443, 8080];
+
+/// Ports spoken to over TLS.
+const TLS_PORTS: &[u16] = &[443, 8443];
+
+/// Budget for connecting, the TLS handshake and reading the response.
+const WEB_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Response bytes read at most; titles sit near the top of the page.
+const MAX_RESPONSE: usize = 64 * 1024;
+
+/// Titles and server names longer than this are truncated.
+const MAX_FIELD_LEN: usize = 120;
+
+/// Hosts with an identical response needed before interception is suspected.
+pub const INTERCEPT_MIN_HOSTS: usize = 8;
+
+/// Flags likely captive portals and transparent proxies.
+///
+/// An interceptor answers every connection itself, so unrelated hosts all
+/// return the same status, title and `Server` header. Responses are grouped
+/// on those; a group of [`INTERCEPT_MIN_HOSTS`] hosts is suspicious unless
+/// they all share one known MAC vendor, which is more likely a fleet of
+/// identical devices. Randomized MACs count as an unknown vendor.
+#[derive(Debug, Default)]
+pub struct InterceptDetector {
+    groups: HashMap<ResponseKey, ResponseGroup>,
+    reported: bool,
+}
+
+#[derive(Debug)]
+struct ResponseGroup {
+    first: WebInfo,
+    hosts: usize,
+    vendors: HashSet<Option<String>>,
+}
+
+impl InterceptDetector {
+    /// Records a host's response. Returns the warning the first time a group
+    /// becomes suspicious, and `None` ever after.
+    pub fn record(&mut self, vendor: Option<&str>, info: &WebInfo) -> Option<InterceptionWarning> {
+        if self.reported {
+            return None;
+        }
+        let group = self
+            .groups
+            .entry(response_key(info))
+            .or_insert_with(|| ResponseGroup {
+                first: info.clone(),
+                hosts: 0,
+                vendors: HashSet::new(),
+            });
+        group.hosts += 1;
+        let vendor = vendor.filter(|v| *v != RANDOMIZED_MAC_VENDOR);
+        group.vendors.insert(vendor.map(str::to_string));
+        let unrelated = group.vendors.len() > 1 || group.vendors.contains(&None);
+        if group.hosts < INTERCEPT_MIN_HOSTS || !unrelated {
+            return None;
+        }
+        self.reported = true;
+        Some(InterceptionWarning {
+            hosts: group.hosts,
+            response: group.first.clone(),
+        })
+    }
+}
+
+/// Checks finished results for interception; see [`InterceptDetector`].
+pub fn detect_interception(results: &[ScanResult]) -> Option<InterceptionWarning> {
+    let mut detector = InterceptDetector::default();
+    let mut warning = results.iter().find_map(|res| {
+        let info = res.web_info.as_ref()?;
+        detector.record(res.vendor.as_deref(), info)
+    })?;
+    // Count the whole group, not just the hosts up to the threshold.
+    let key = response_key(&warning.response);
+    warning.hosts = results
+        .iter()
+        .filter(|r| r.web_info.as_ref().map(response_key).as_ref() == Some(&key))
+        .count();
+    Some(warning)
+}
+
+type ResponseKey = (u16, Option<String>, Option<String>);
+
+/// What an interceptor's answers have in common.
+fn response_key(info: &WebInfo) -> ResponseKey {
+    (info.status, info.title.clone(), info.server.clone())
+}
+
+/// Fetches `/` from every open port in [`WEB_PORTS`] and keeps the most
+/// useful answer: the first page with a title, else the first response.
+pub async fn probe(
+    net: &dyn NetworkProvider,
+    ip: Ipv4Addr,
+    open_ports: &[PortInfo],
+) -> Option<WebInfo> {
+    let mut fallback = None;
+    for &port in WEB_PORTS {
+        if !open_ports.iter().any(|p| p.port == port) {
+            continue;
+        }
+        match net.fetch_web_info(ip, port).await {
+            Some(info) if info.title.is_some() => return Some(info),
+            Some(info) => {
+                fallback.get_or_insert(info);
+            }
+            None => {}
+        }
+    }
+    fallback
+}
+
+/// Requests `/` from `ip:port` and parses the reply; `None` if the port
+/// does not answer with HTTP within [`WEB_TIMEOUT`].
+pub async fn fetch(ip: Ipv4Addr, port: u16) -> Option<WebInfo> {
+    let exchange = async {
+        let stream = TcpStream::connect((ip, port)).await.ok()?;
+        let raw = if TLS_PORTS.contains(&port) {
+            let connector = native_tls::TlsConnector::builder()
+                .danger_accept_invalid_certs(true)
+                .danger_accept_invalid_hostnames(true)
+                .build()
+                .ok()?;
+            let stream = TlsConnector::from(connector)
+                .connect(&ip.to_string(), stream)
+                .await
+                .ok()?;
+            request(stream, ip).await?
+        } else {
+            request(stream, ip).await?
+        };
+        parse_response(port, &raw)
+    };
+    tokio::time::timeout(WEB_TIMEOUT, exchange)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Sends the request and reads until the server closes or [`MAX_RESPONSE`].
+async fn request<S>(mut stream: S, ip: Ipv4Addr) -> Option<Vec<u8>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let request = format!(
+        "GET / HTTP/1.1\r\nHost: {}\r\nUser-Agent: ragescanner\r\nAccept: text/html\r\nConnection: close\r\n\r\n",
+        ip
+    );
+    stream.write_all(request.as_bytes()).await.ok()?;
+    let mut raw = Vec::new();
+    let mut buf = [0u8; 4096];
+    while raw.len() < MAX_RESPONSE {
+        match stream.read(&mut buf).await {
+            Ok(0) => break,
+            Ok(n) => raw.extend_from_slice(&buf[..n]),
+            // Keep what arrived; some devices reset instead of closing.
+            Err(_) if !raw.is_empty() => break,
+            Err(_) => return None,
+        }
+    }
+    Some(raw)
+}
+
+/// Parses the status line, `Server` header and `<title>` of a response.
+pub fn parse_response(port: u16, raw: &[u8]) -> Option<WebInfo> {
+    let text = String::from_utf8_lossy(raw);
+    let (head, body) = text.split_once("\r\n\r\n").unwrap_or((&text, ""));
+    let mut lines = head.lines();
+    let status = lines
+        .next()?
+        .strip_prefix("HTTP/")?
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()?;
+    let server = lines
+        .find_map(|l| {
+            let (name, value) = l.split_once(':')?;
+            name.eq_ignore_ascii_case("server").then(|| clean(value))
+        })
+        .filter(|v| !v.is_empty());
+    Some(WebInfo {
+        port,
+        status,
+        title: extract_title(body),
+        server,
+    })
+}
+
+/// Returns the text of the first `<title>` element, decoded and flattened.
+fn extract_title(body: &str) -> Option<String> {
+    let lower = body.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    Some(clean(&decode_entities(&body[start..end]))).filter(|t| !t.is_empty())
+}
+
+/// Decodes the character references common in titles.
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').filter(|&semi| semi <= 10).and_then(|semi| {
+            let entity = &rest[1..semi];
+            let c = match entity {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => ' ',
+                _ => {
+                    let code = match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => entity.strip_prefix('#')?.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((c, semi))
+        });
+        match decoded {
+            Some((c, semi)) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Collapses whitespace, drops control characters and truncates to [`MAX_FIELD_LEN`].
+fn clean(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_FIELD_LEN)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response_reads_status_server_and_title() {
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nserver:  lighttpd/1.4 \r\n\r\n\
+            <html><head><TITLE>\n  Synology &amp; DiskStation&#33;\n</TITLE></head></html>";
+        let info = parse_response(5000, raw).unwrap();
+        assert_eq!(info.port, 5000);
+        assert_eq!(info.status, 200);
+        assert_eq!(info.server.as_deref(), Some("lighttpd/1.4"));
+        assert_eq!(info.title.as_deref(), Some("Synology & DiskStation!"));
+    }
+
+    #[test]
+    fn test_identical_answers_from_unrelated_hosts_suggest_interception() {
+        let portal = WebInfo {
+            port: 80,
+            status: 302,
+            title: Some("Guest Wi-Fi Login".to_string()),
+            server: None,
+        };
+        let host = |last: u8, vendor: &str| {
+            let mut res = ScanResult::new(Ipv4Addr::new(10, 0, 0, last));
+            res.vendor = Some(vendor.to_string());
+            res.web_info = Some(portal.clone());
+            res
+        };
+        // A fleet of identical cameras is not flagged...
+        let fleet: Vec<_> = (1..=20).map(|i| host(i, "Hikvision")).collect();
+        assert_eq!(detect_interception(&fleet), None);
+        // ...while randomized MACs say nothing about the device.
+        let phones: Vec<_> = (1..=8).map(|i| host(i, RANDOMIZED_MAC_VENDOR)).collect();
+        assert!(detect_interception(&phones).is_some());
+        // ...but the same answer from unrelated hosts is, counting all of them.
+        let mut mixed = fleet;
+        mixed[3].vendor = Some("Dell Inc.".to_string());
+        let warning = detect_interception(&mixed).unwrap();
+        assert_eq!(warning.hosts, 20);
+        assert!(warning.to_string().contains("Guest Wi-Fi Login"));
+
+        let mut detector = InterceptDetector::default();
+        let alerts = mixed
+            .iter()
+            .filter_map(|r| detector.record(r.vendor.as_deref(), r.web_info.as_ref().unwrap()))
+            .count();
+        assert_eq!(alerts, 1);
+    }
+
+    #[test]
+    fn test_parse_response_without_title_or_http() {
+        let redirect = b"HTTP/1.0 302 Found\r\nLocation: https://10.0.0.1/\r\n\r\n";
+        let info = parse_response(80, redirect).unwrap();
+        assert_eq!((info.status, info.title, info.server), (302, None, None));
+        assert_eq!(parse_response(80, b"SSH-2.0-OpenSSH_9.6\r\n"), None);
+        assert_eq!(extract_title("<title>  </title>"), None);
+        assert_eq!(
+            decode_entities("a & b &bogus; &#xZZ;"),
+            "a & b &bogus; &#xZZ;"
+        );
+    }
+}
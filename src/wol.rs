@@ -0,0 +1,306 @@
+//! Wake-on-LAN.
+//!
+//! A sleeping host whose NIC has WoL enabled powers up when it sees a "magic
+//! packet": six `0xFF` bytes followed by its MAC address repeated 16 times,
+//! sent as a UDP broadcast so it reaches a host that has no ARP entry.
+//!
+//! [`wake_batch`] wakes a list of hosts, spacing the packets out so a room of
+//! machines does not draw power all at once, then pings them until they
+//! answer and reports which came up.
+
+use crate::net::NetworkProvider;
+use crate::types::GError;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::Instant;
+
+/// UDP port magic packets are sent to ("discard"; the port is not significant).
+pub const WOL_PORT: u16 = 9;
+/// Length of a magic packet in bytes.
+pub const MAGIC_PACKET_LEN: usize = 6 + 16 * 6;
+/// Pause between the magic packets of a batch.
+pub const WAKE_STAGGER: Duration = Duration::from_millis(500);
+/// Default time a batch waits for woken hosts to answer pings.
+pub const DEFAULT_WAKE_TIMEOUT: Duration = Duration::from_secs(120);
+/// Interval between ping rounds while waiting for woken hosts.
+pub const WAKE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A host to wake, and the address it is expected to answer on once awake.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WakeTarget {
+    pub mac: String,
+    /// `None` if unknown: the packet is sent, but arrival cannot be checked.
+    pub ip: Option<Ipv4Addr>,
+}
+
+/// What happened to one host of a batch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WakeOutcome {
+    pub target: WakeTarget,
+    /// Why the magic packet could not be sent.
+    pub error: Option<GError>,
+    /// Time from the start of the batch until the host answered a ping.
+    pub online_after: Option<Duration>,
+}
+
+impl fmt::Display for WakeOutcome {
+    /// Formats as `00:11:22:33:44:55 (10.0.0.5): online after 34 s`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.target.mac)?;
+        if let Some(ip) = self.target.ip {
+            write!(f, " ({})", ip)?;
+        }
+        match (&self.error, self.online_after, self.target.ip) {
+            (Some(e), _, _) => write!(f, ": failed: {}", e),
+            (None, Some(after), _) => write!(f, ": online after {} s", after.as_secs()),
+            (None, None, Some(_)) => write!(f, ": no answer"),
+            (None, None, None) => write!(f, ": sent, no address to check"),
+        }
+    }
+}
+
+/// Result of [`wake_batch`]: one outcome per target, in the order given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WakeReport {
+    pub outcomes: Vec<WakeOutcome>,
+    /// How long the batch waited for hosts to answer.
+    pub timeout: Duration,
+}
+
+impl WakeReport {
+    /// Hosts that answered a ping within the timeout.
+    pub fn online_count(&self) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|o| o.online_after.is_some())
+            .count()
+    }
+
+    /// One-line summary, e.g. `3 of 5 devices online within 120 s`.
+    pub fn summary(&self) -> String {
+        format!(
+            "{} of {} devices online within {} s",
+            self.online_count(),
+            self.outcomes.len(),
+            self.timeout.as_secs()
+        )
+    }
+}
+
+impl fmt::Display for WakeReport {
+    /// The summary, then one line per host.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.summary())?;
+        for outcome in &self.outcomes {
+            write!(f, "\n  {}", outcome)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a MAC address written with `:` or `-` separators, or none at all.
+pub fn parse_mac(mac: &str) -> Result<[u8; 6], GError> {
+    let invalid = || GError::Internal(format!("Invalid MAC address: {}", mac));
+    let hex: String = mac
+        .chars()
+        .filter(|c| !matches!(c, ':' | '-' | '.'))
+        .collect();
+    if hex.len() != 12 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let mut bytes = [0u8; 6];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(bytes)
+}
+
+/// Builds the magic packet that wakes `mac`.
+pub fn magic_packet(mac: [u8; 6]) -> [u8; MAGIC_PACKET_LEN] {
+    let mut packet = [0xFF; MAGIC_PACKET_LEN];
+    for chunk in packet[6..].chunks_exact_mut(6) {
+        chunk.copy_from_slice(&mac);
+    }
+    packet
+}
+
+/// Broadcasts a magic packet for `mac` on the local network.
+pub async fn wake_on_lan(mac: &str) -> Result<(), GError> {
+    let packet = magic_packet(parse_mac(mac)?);
+    let failed =
+        |e: std::io::Error| GError::Internal(format!("Failed to send magic packet: {}", e));
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .await
+        .map_err(failed)?;
+    socket.set_broadcast(true).map_err(failed)?;
+    socket
+        .send_to(&packet, (Ipv4Addr::BROADCAST, WOL_PORT))
+        .await
+        .map_err(failed)?;
+    Ok(())
+}
+
+/// Fills in the address of targets without one from an ARP table (address
+/// to MAC), matching MACs whatever their separators.
+pub fn fill_addresses(targets: &mut [WakeTarget], arp: &HashMap<Ipv4Addr, String>) {
+    for target in targets.iter_mut().filter(|t| t.ip.is_none()) {
+        let Ok(mac) = parse_mac(&target.mac) else {
+            continue;
+        };
+        target.ip = arp
+            .iter()
+            .find(|(_, known)| parse_mac(known).ok() == Some(mac))
+            .map(|(ip, _)| *ip);
+    }
+}
+
+/// Sends a magic packet to every target, [`WAKE_STAGGER`] apart, then pings
+/// the targets with a known address every [`WAKE_POLL_INTERVAL`] until all
+/// have answered or `timeout` has passed since the first packet.
+///
+/// `on_sent` is called after each packet with its outcome, for progress.
+pub async fn wake_batch(
+    net: &dyn NetworkProvider,
+    targets: Vec<WakeTarget>,
+    timeout: Duration,
+    mut on_sent: impl FnMut(&WakeTarget, Result<(), GError>),
+) -> WakeReport {
+    let started = Instant::now();
+    let mut outcomes = Vec::with_capacity(targets.len());
+    for (i, target) in targets.into_iter().enumerate() {
+        if i > 0 {
+            tokio::time::sleep(WAKE_STAGGER).await;
+        }
+        let sent = wake_on_lan(&target.mac).await;
+        on_sent(&target, sent.clone());
+        outcomes.push(WakeOutcome {
+            target,
+            error: sent.err(),
+            online_after: None,
+        });
+    }
+    wait_online(net, &mut outcomes, started, timeout).await;
+    WakeReport { outcomes, timeout }
+}
+
+/// Pings the woken hosts of `outcomes` until each has answered or `timeout`
+/// has passed since `started`, recording when they came up.
+pub async fn wait_online(
+    net: &dyn NetworkProvider,
+    outcomes: &mut [WakeOutcome],
+    started: Instant,
+    timeout: Duration,
+) {
+    let deadline = started + timeout;
+    loop {
+        let pending: Vec<(usize, Ipv4Addr)> = outcomes
+            .iter()
+            .enumerate()
+            .filter(|(_, o)| o.error.is_none() && o.online_after.is_none())
+            .filter_map(|(i, o)| o.target.ip.map(|ip| (i, ip)))
+            .collect();
+        if pending.is_empty() {
+            return;
+        }
+        let replies = futures::future::join_all(
+            pending
+                .iter()
+                .map(|(_, ip)| net.echo_async(*ip, Duration::from_secs(1))),
+        )
+        .await;
+        for ((i, _), reply) in pending.into_iter().zip(replies) {
+            if let Ok(Some(_)) = reply {
+                outcomes[i].online_after = Some(started.elapsed());
+            }
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return;
+        }
+        tokio::time::sleep(WAKE_POLL_INTERVAL.min(deadline - now)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::MockNet;
+
+    #[test]
+    fn test_parse_mac_accepts_common_separators() {
+        let expected = [0x00, 0x1A, 0x2B, 0x3C, 0x4D, 0x5E];
+        assert_eq!(parse_mac("00:1A:2B:3C:4D:5E").unwrap(), expected);
+        assert_eq!(parse_mac("00-1a-2b-3c-4d-5e").unwrap(), expected);
+        assert_eq!(parse_mac("001A2B3C4D5E").unwrap(), expected);
+        assert!(parse_mac("00:1A:2B:3C:4D").is_err());
+        assert!(parse_mac("00:1A:2B:3C:4D:ZZ").is_err());
+    }
+
+    #[test]
+    fn test_magic_packet_repeats_mac_after_sync_stream() {
+        let mac = [1, 2, 3, 4, 5, 6];
+        let packet = magic_packet(mac);
+        assert_eq!(&packet[..6], &[0xFF; 6]);
+        assert!(packet[6..].chunks(6).all(|chunk| chunk == mac));
+        assert_eq!(packet[6..].chunks(6).count(), 16);
+    }
+
+    #[test]
+    fn test_fill_addresses_matches_macs_in_any_format() {
+        let arp = HashMap::from([(Ipv4Addr::new(10, 0, 0, 5), "00-11-22-33-44-55".to_string())]);
+        let mut targets = vec![
+            WakeTarget {
+                mac: "00:11:22:33:44:55".to_string(),
+                ip: None,
+            },
+            WakeTarget {
+                mac: "00:11:22:33:44:66".to_string(),
+                ip: None,
+            },
+        ];
+        fill_addresses(&mut targets, &arp);
+        assert_eq!(targets[0].ip, Some(Ipv4Addr::new(10, 0, 0, 5)));
+        assert_eq!(targets[1].ip, None);
+    }
+
+    #[tokio::test]
+    async fn test_wait_online_reports_who_answered_in_time() {
+        let outcome = |mac: &str, ip: Option<[u8; 4]>, error: Option<GError>| WakeOutcome {
+            target: WakeTarget {
+                mac: mac.to_string(),
+                ip: ip.map(Ipv4Addr::from),
+            },
+            error,
+            online_after: None,
+        };
+        let mut outcomes = vec![
+            outcome("00:11:22:33:44:55", Some([192, 168, 1, 1]), None),
+            outcome("00:11:22:33:44:66", Some([192, 168, 1, 3]), None),
+            outcome("00:11:22:33:44:77", None, None),
+            outcome(
+                "00:11:22:33:44:88",
+                Some([192, 168, 1, 1]),
+                Some(GError::Internal("no route".to_string())),
+            ),
+        ];
+        let timeout = Duration::from_millis(50);
+        wait_online(&MockNet, &mut outcomes, Instant::now(), timeout).await;
+
+        let report = WakeReport { outcomes, timeout };
+        assert_eq!(report.online_count(), 1);
+        assert_eq!(
+            report.to_string(),
+            "1 of 4 devices online within 0 s\n  \
+             00:11:22:33:44:55 (192.168.1.1): online after 0 s\n  \
+             00:11:22:33:44:66 (192.168.1.3): no answer\n  \
+             00:11:22:33:44:77: sent, no address to check\n  \
+             00:11:22:33:44:88 (192.168.1.1): failed: Internal Error: no route"
+        );
+    }
+}
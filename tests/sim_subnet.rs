@@ -0,0 +1,369 @@
+//! End-to-end runs of the [`Bridge`] → [`Scanner`] pipeline against a
+//! simulated subnet.
+//!
+//! `SimNet` answers echoes, ARP and names for a handful of hosts and serves
+//! their open TCP ports from real listeners on 127.0.0.1, so port probes go
+//! through the loopback stack. The tests drive the bridge the way a frontend
+//! does, through `cmd_tx` and `ui_rx`, and check the order of what comes
+//! back rather than single results.
+//!
+//! [`Scanner`]: ragescanner::scanner::Scanner
+
+use async_trait::async_trait;
+use ragescanner::bridge::Bridge;
+use ragescanner::net::{EchoReply, NetworkProvider};
+use ragescanner::types::{
+    BridgeMessage, GError, ScanConfig, ScanResult, ScanRunState, ScanStatus, ScanSummary,
+    UdpPortState,
+};
+use std::collections::{HashMap, HashSet};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Longest wait for any one message before a test gives up.
+const RECV_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Ports every scan in this file probes.
+const PORTS: &[u16] = &[22, 80, 443, 445, 8080];
+
+/// A simulated host: last octet, MAC, name and open TCP ports.
+struct SimHost {
+    last: u8,
+    mac: &'static str,
+    name: &'static str,
+    ports: &'static [u16],
+}
+
+/// Online hosts of the simulated 10.0.0.0/24.
+const HOSTS: &[SimHost] = &[
+    SimHost {
+        last: 1,
+        mac: "00:0C:42:00:00:01",
+        name: "router.lan",
+        ports: &[22, 80, 443],
+    },
+    SimHost {
+        last: 5,
+        mac: "00:11:32:00:00:05",
+        name: "nas.lan",
+        ports: &[22, 445],
+    },
+    SimHost {
+        last: 9,
+        mac: "3C:22:FB:00:00:09",
+        name: "desk-09.lan",
+        ports: &[],
+    },
+];
+
+/// A [`NetworkProvider`] answering for [`HOSTS`], with each open port
+/// backed by a loopback listener. Ports a host does not serve are refused
+/// without a connection attempt.
+struct SimNet {
+    listeners: HashMap<(Ipv4Addr, u16), TcpListener>,
+    /// Time every echo takes, answered or not, to keep a scan running long
+    /// enough to stop or pause it.
+    echo_delay: Duration,
+}
+
+impl SimNet {
+    fn new(echo_delay: Duration) -> Self {
+        let mut listeners = HashMap::new();
+        for host in HOSTS {
+            for &port in host.ports {
+                let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+                listeners.insert((Self::ip(host), port), listener);
+            }
+        }
+        Self {
+            listeners,
+            echo_delay,
+        }
+    }
+
+    fn ip(host: &SimHost) -> Ipv4Addr {
+        Ipv4Addr::new(10, 0, 0, host.last)
+    }
+
+    fn host(ip: Ipv4Addr) -> Option<&'static SimHost> {
+        HOSTS.iter().find(|h| Self::ip(h) == ip)
+    }
+
+    fn listener(&self, ip: Ipv4Addr, port: u16) -> Option<SocketAddr> {
+        self.listeners
+            .get(&(ip, port))
+            .and_then(|listener| listener.local_addr().ok())
+    }
+}
+
+#[async_trait]
+impl NetworkProvider for SimNet {
+    fn echo(&self, ip: Ipv4Addr) -> Result<Option<u32>, GError> {
+        Ok(Self::host(ip).map(|h| u32::from(h.last)))
+    }
+
+    async fn echo_async(
+        &self,
+        ip: Ipv4Addr,
+        _timeout: Duration,
+    ) -> Result<Option<EchoReply>, GError> {
+        tokio::time::sleep(self.echo_delay).await;
+        let rtt = self.echo(ip)?;
+        Ok(rtt.map(|rtt_ms| EchoReply { rtt_ms, ttl: None }))
+    }
+
+    fn resolve_mac(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+        Ok(Self::host(ip).map(|h| h.mac.to_string()))
+    }
+
+    fn resolve_hostname(&self, ip: Ipv4Addr) -> Result<Option<String>, GError> {
+        Ok(Self::host(ip).map(|h| h.name.to_string()))
+    }
+
+    fn resolve_vendor(&self, _mac: &str) -> Option<String> {
+        None
+    }
+
+    async fn scan_port(&self, ip: Ipv4Addr, port: u16) -> bool {
+        let Some(addr) = self.listener(ip, port) else {
+            return false;
+        };
+        matches!(
+            tokio::time::timeout(RECV_TIMEOUT, tokio::net::TcpStream::connect(addr)).await,
+            Ok(Ok(_))
+        )
+    }
+
+    async fn scan_udp_port(&self, _ip: Ipv4Addr, _port: u16) -> UdpPortState {
+        UdpPortState::Closed
+    }
+}
+
+fn bridge(net: SimNet) -> Bridge {
+    Bridge::builder().network_provider(Arc::new(net)).build()
+}
+
+fn start(bridge: &Bridge, range: &str, config: ScanConfig) {
+    bridge
+        .cmd_tx
+        .blocking_send(BridgeMessage::StartScanWithConfig {
+            range: range.to_string(),
+            config: Box::new(config),
+        })
+        .unwrap();
+}
+
+fn config(concurrency: usize) -> ScanConfig {
+    ScanConfig {
+        concurrency,
+        ports: PORTS.to_vec(),
+        ..ScanConfig::default()
+    }
+}
+
+fn next(bridge: &Bridge) -> BridgeMessage {
+    bridge
+        .ui_rx
+        .recv_timeout(RECV_TIMEOUT)
+        .expect("the bridge went quiet")
+}
+
+/// Messages up to and including the first one `last` accepts.
+fn collect_until(bridge: &Bridge, last: impl Fn(&BridgeMessage) -> bool) -> Vec<BridgeMessage> {
+    let mut messages = Vec::new();
+    loop {
+        let msg = next(bridge);
+        let done = last(&msg);
+        messages.push(msg);
+        if done {
+            return messages;
+        }
+    }
+}
+
+fn updates(messages: &[BridgeMessage]) -> Vec<&ScanResult> {
+    messages
+        .iter()
+        .filter_map(|msg| match msg {
+            BridgeMessage::ScanUpdate(res) => Some(res),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Checks what every finished scan shares: it opens with `ScanStarted`,
+/// progress and discovery never go back, each target is reported once, and
+/// the summary matches the results before it. Only targets a cancelled scan
+/// never reached, as not scanned, come between the summary and `end`.
+fn assert_well_formed(messages: &[BridgeMessage], end: fn(&BridgeMessage) -> bool) -> ScanSummary {
+    assert!(
+        matches!(messages.first(), Some(BridgeMessage::ScanStarted(None))),
+        "{:?}",
+        messages.first()
+    );
+    let (last, rest) = messages.split_last().unwrap();
+    assert!(end(last), "{:?}", last);
+
+    let progress: Vec<u8> = rest
+        .iter()
+        .filter_map(|msg| match msg {
+            BridgeMessage::Progress(p) => Some(*p),
+            _ => None,
+        })
+        .collect();
+    assert!(
+        progress.windows(2).all(|w| w[0] < w[1]),
+        "progress went back: {:?}",
+        progress
+    );
+    assert!(progress.iter().all(|&p| p <= 100), "{:?}", progress);
+    let discovered: Vec<u32> = rest
+        .iter()
+        .filter_map(|msg| match msg {
+            BridgeMessage::Phase(phase) => Some(phase.hosts_discovered),
+            _ => None,
+        })
+        .collect();
+    assert!(
+        discovered.windows(2).all(|w| w[0] <= w[1]),
+        "discovery went back: {:?}",
+        discovered
+    );
+
+    let unique: HashSet<Ipv4Addr> = updates(rest).iter().map(|r| r.ip).collect();
+    assert_eq!(
+        unique.len(),
+        updates(rest).len(),
+        "a target was reported twice"
+    );
+
+    let at = rest
+        .iter()
+        .position(|msg| matches!(msg, BridgeMessage::ScanSummary(_)))
+        .expect("no summary");
+    let (scanned, [BridgeMessage::ScanSummary(summary), unreached @ ..]) = rest.split_at(at) else {
+        unreachable!();
+    };
+    for msg in unreached {
+        assert!(
+            matches!(msg, BridgeMessage::ScanUpdate(r) if r.status == ScanStatus::NotScanned),
+            "after the summary: {:?}",
+            msg
+        );
+    }
+    let results = updates(scanned);
+    let count = |status: ScanStatus| results.iter().filter(|r| r.status == status).count() as u32;
+    assert_eq!(summary.hosts_up, count(ScanStatus::Online));
+    assert_eq!(summary.hosts_down, count(ScanStatus::Offline));
+    assert_eq!(summary.errors, 0);
+    *summary
+}
+
+#[test]
+fn test_full_scan_reports_every_host_in_order() {
+    let bridge = bridge(SimNet::new(Duration::ZERO));
+    start(&bridge, "10.0.0.1-10.0.0.20", config(8));
+    let messages = collect_until(&bridge, |msg| {
+        matches!(
+            msg,
+            BridgeMessage::ScanComplete | BridgeMessage::ScanCancelled
+        )
+    });
+
+    let summary = assert_well_formed(&messages, |msg| matches!(msg, BridgeMessage::ScanComplete));
+    let progress = messages.iter().rev().find_map(|msg| match msg {
+        BridgeMessage::Progress(p) => Some(*p),
+        _ => None,
+    });
+    assert_eq!(progress, Some(100));
+    assert_eq!((summary.hosts_up, summary.hosts_down), (3, 17));
+
+    let results = updates(&messages);
+    assert_eq!(results.len(), 20);
+    for host in HOSTS {
+        let res = results
+            .iter()
+            .find(|r| r.ip == SimNet::ip(host))
+            .expect("no result for a simulated host");
+        assert_eq!(res.status, ScanStatus::Online);
+        assert_eq!(res.mac.as_deref(), Some(host.mac));
+        assert_eq!(res.hostname.as_deref(), Some(host.name));
+        let ports: Vec<u16> = res.open_ports.iter().map(|p| p.port).collect();
+        assert_eq!(ports, host.ports, "open ports of {}", res.ip);
+    }
+}
+
+#[test]
+fn test_stop_cancels_and_the_bridge_scans_again() {
+    // Thousands of targets at 50 ms an echo and 64 at a time: seconds of work.
+    let bridge = bridge(SimNet::new(Duration::from_millis(50)));
+    start(&bridge, "10.0.0.0/20", config(64));
+    let before_stop = collect_until(&bridge, |msg| matches!(msg, BridgeMessage::ScanUpdate(_)));
+    bridge
+        .cmd_tx
+        .blocking_send(BridgeMessage::StopScan)
+        .unwrap();
+    let after_stop = collect_until(&bridge, |msg| {
+        assert!(
+            !matches!(msg, BridgeMessage::ScanComplete),
+            "a stopped scan completed"
+        );
+        matches!(msg, BridgeMessage::ScanCancelled)
+    });
+
+    let messages: Vec<BridgeMessage> = before_stop.into_iter().chain(after_stop).collect();
+    assert_well_formed(&messages, |msg| matches!(msg, BridgeMessage::ScanCancelled));
+    // Targets never dispatched are reported, after the summary, as not scanned.
+    let results = updates(&messages);
+    assert!(results.iter().any(|r| r.status == ScanStatus::NotScanned));
+    assert!(results.len() <= 4096);
+
+    // The cancelled scan left nothing behind that holds up the next one.
+    start(&bridge, "10.0.0.1-10.0.0.10", config(8));
+    let messages = collect_until(&bridge, |msg| {
+        matches!(
+            msg,
+            BridgeMessage::ScanComplete | BridgeMessage::ScanCancelled
+        )
+    });
+    let summary = assert_well_formed(&messages, |msg| matches!(msg, BridgeMessage::ScanComplete));
+    assert_eq!((summary.hosts_up, summary.hosts_down), (3, 7));
+}
+
+#[test]
+fn test_paused_scan_resumes_and_completes() {
+    let bridge = bridge(SimNet::new(Duration::from_millis(20)));
+    start(&bridge, "10.0.0.1-10.0.0.64", config(4));
+    let mut messages = collect_until(&bridge, |msg| matches!(msg, BridgeMessage::ScanUpdate(_)));
+    bridge
+        .cmd_tx
+        .blocking_send(BridgeMessage::PauseScan)
+        .unwrap();
+    messages.extend(collect_until(&bridge, |msg| {
+        matches!(msg, BridgeMessage::ScanState(ScanRunState::Paused))
+    }));
+    bridge
+        .cmd_tx
+        .blocking_send(BridgeMessage::ResumeScan)
+        .unwrap();
+    messages.extend(collect_until(&bridge, |msg| {
+        matches!(
+            msg,
+            BridgeMessage::ScanComplete | BridgeMessage::ScanCancelled
+        )
+    }));
+
+    let resumed = messages
+        .iter()
+        .position(|msg| matches!(msg, BridgeMessage::ScanState(ScanRunState::Running)))
+        .expect("no Running state after resuming");
+    let paused = messages
+        .iter()
+        .position(|msg| matches!(msg, BridgeMessage::ScanState(ScanRunState::Paused)))
+        .unwrap();
+    assert!(paused < resumed);
+    let summary = assert_well_formed(&messages, |msg| matches!(msg, BridgeMessage::ScanComplete));
+    assert_eq!((summary.hosts_up, summary.hosts_down), (3, 61));
+    assert_eq!(updates(&messages).len(), 64);
+}